@@ -0,0 +1,28 @@
+//! Authorization header values as clients send them, down to the claims every handler reads.
+#![no_main]
+
+use actix_web::http::header::HeaderValue;
+use actix_web::test::TestRequest;
+use bus_book::middleware::auth::{claims_from_header, decode_claims, jwt_secret, OptionalClaims};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // What the Auth and AdminAuth middleware see
+    let _ = claims_from_header(data);
+
+    // What handlers see; actix refuses some byte sequences as header values before we get them
+    if let Ok(value) = HeaderValue::from_bytes(data) {
+        let req = TestRequest::default().insert_header(("Authorization", value)).to_http_request();
+        let _ = OptionalClaims::of(&req);
+    }
+
+    // Random bytes never carry a valid signature, so also sign the input as a claims payload
+    // to reach claims deserialization
+    if let Ok(claims) = serde_json::from_slice::<serde_json::Value>(data) {
+        let secret = jwt_secret();
+        if let Ok(token) = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())) {
+            let _ = decode_claims(&token, &secret);
+        }
+    }
+});
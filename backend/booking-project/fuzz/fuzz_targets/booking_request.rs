@@ -0,0 +1,24 @@
+//! Request bodies of the booking flow, deserialized and validated the way the handlers do.
+#![no_main]
+
+use bus_book::models::booking::CreateBookingRequest;
+use bus_book::models::consent::{validate_consents, UpdateConsentsRequest};
+use bus_book::models::hold::CreateHoldRequest;
+use bus_book::models::RegisterRequest;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(req) = serde_json::from_slice::<CreateBookingRequest>(data) {
+        if let Some(next_of_kin) = req.passenger.as_ref().and_then(|p| p.next_of_kin.as_ref()) {
+            let _ = next_of_kin.validated();
+        }
+        let _ = serde_json::to_vec(&req);
+    }
+    let _ = serde_json::from_slice::<CreateHoldRequest>(data);
+    if let Ok(req) = serde_json::from_slice::<RegisterRequest>(data) {
+        let _ = validate_consents(&req.consents, "registration");
+    }
+    if let Ok(req) = serde_json::from_slice::<UpdateConsentsRequest>(data) {
+        let _ = validate_consents(&req.consents, req.source.as_deref().unwrap_or("account_settings"));
+    }
+});
@@ -0,0 +1,28 @@
+//! Provider callback bodies, parsed and read the way the webhook handlers do. These arrive from
+//! the internet before any signature or token has been checked.
+#![no_main]
+
+use bus_book::models::webhook::{external_id, verify_stripe_signature, MpesaConfirmation, MpesaStkCallback, StripeEvent};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(confirmation) = MpesaConfirmation::parse(data) {
+        let _ = confirmation.amount();
+    }
+    if let Ok(callback) = MpesaStkCallback::parse(data) {
+        let _ = (callback.succeeded(), callback.amount(), callback.receipt_number());
+    }
+    if let Ok(event) = StripeEvent::parse(data) {
+        let _ = (event.booking_id(), event.amount_received());
+    }
+    for provider in ["mpesa", "mpesa_stk", "stripe"] {
+        let _ = external_id(provider, data);
+    }
+
+    // The first line stands in for the Stripe-Signature header, the rest for the body it signs
+    if let Some(split) = data.iter().position(|b| *b == b'\n') {
+        if let Ok(header) = std::str::from_utf8(&data[..split]) {
+            let _ = verify_stripe_signature(header, &data[split + 1..], "whsec_fuzz");
+        }
+    }
+});
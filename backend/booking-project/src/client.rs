@@ -0,0 +1,179 @@
+//! Typed client for the public API, for our own Rust services (the reporting service, the CLI)
+//! rather than hand-written JSON. Requests and responses are the structs the handlers use, so the
+//! two can't drift apart. Only compiled with the `client` feature.
+
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::models::auth::{AuthResponse, LoginRequest, RefreshRequest, RegisterRequest};
+use crate::models::booking::{BookingConfirmation, BookingDetailResponse, BookingPageQuery, CreateBookingRequest};
+use crate::models::bus::{BusResponse, BusSearchQuery, SeatAvailabilityResponse};
+use crate::models::schedule::{TripQuery, TripResponse};
+use crate::openapi::ErrorResponse;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum ClientError {
+    // Never got a response: DNS, TLS, timeouts, or a body that didn't decode
+    Transport(reqwest::Error),
+    // The API answered with an error status and, usually, its `{"error": ...}` message
+    Api { status: StatusCode, message: String },
+}
+
+impl ClientError {
+    /// The HTTP status the API answered with, if it answered.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Transport(e) => e.status(),
+            ClientError::Api { status, .. } => Some(*status),
+        }
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "request failed: {}", e),
+            ClientError::Api { status, message } => write!(f, "{}: {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Transport(e)
+    }
+}
+
+/// One page of a keyset-paginated list; pass `next_cursor` as `after` for the next one.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+pub struct BusBookClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl BusBookClient {
+    /// A client for the API at `base_url`, e.g. `https://api.example.com`, not yet signed in.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Uses an access token obtained elsewhere, e.g. a long-lived service account's.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    // Sends the request, turning any error status into `ClientError::Api` with the API's message
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&body).map(|e| e.error).unwrap_or(body);
+        Err(ClientError::Api { status, message })
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, ClientError> {
+        self.json(self.request(Method::POST, path).json(body)).await
+    }
+
+    /// Signs in and keeps the access token for the calls that follow.
+    pub async fn login(&mut self, credentials: &LoginRequest) -> Result<AuthResponse, ClientError> {
+        let auth: AuthResponse = self.post("/api/auth/login", credentials).await?;
+        self.token = Some(auth.token.clone());
+        Ok(auth)
+    }
+
+    /// Creates an account and keeps its access token for the calls that follow.
+    pub async fn register(&mut self, account: &RegisterRequest) -> Result<AuthResponse, ClientError> {
+        let auth: AuthResponse = self.post("/api/auth/register", account).await?;
+        self.token = Some(auth.token.clone());
+        Ok(auth)
+    }
+
+    /// Trades a refresh token for a new pair; the old refresh token stops working.
+    pub async fn refresh(&mut self, refresh_token: &str) -> Result<AuthResponse, ClientError> {
+        let auth: AuthResponse = self.post("/api/auth/refresh", &RefreshRequest { refresh_token: refresh_token.to_string() }).await?;
+        self.token = Some(auth.token.clone());
+        Ok(auth)
+    }
+
+    pub async fn search_buses(&self, query: &BusSearchQuery) -> Result<Vec<BusResponse>, ClientError> {
+        self.json(self.request(Method::GET, "/api/buses/search").query(query)).await
+    }
+
+    /// A bus, with seats left when `date` is given.
+    pub async fn get_bus(&self, bus_id: &str, date: Option<&str>) -> Result<BusResponse, ClientError> {
+        let mut request = self.request(Method::GET, &format!("/api/buses/{}", bus_id));
+        if let Some(date) = date {
+            request = request.query(&[("date", date)]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn get_seats(&self, bus_id: &str, date: &str) -> Result<SeatAvailabilityResponse, ClientError> {
+        self.json(self.request(Method::GET, &format!("/api/buses/{}/seats", bus_id)).query(&[("date", date)])).await
+    }
+
+    /// The timetable of departures on a date.
+    pub async fn list_trips(&self, query: &TripQuery) -> Result<Vec<TripResponse>, ClientError> {
+        self.json(self.request(Method::GET, "/api/trips").query(query)).await
+    }
+
+    pub async fn create_booking(&self, booking: &CreateBookingRequest) -> Result<BookingConfirmation, ClientError> {
+        self.post("/api/bookings", booking).await
+    }
+
+    /// The signed-in user's bookings, a page at a time.
+    pub async fn list_bookings(&self, query: &BookingPageQuery) -> Result<Page<BookingDetailResponse>, ClientError> {
+        let response = self.send(self.request(Method::GET, "/api/bookings/user").query(query)).await?;
+        let next_cursor = response.headers()
+            .get("x-next-cursor")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok(Page { items: response.json().await?, next_cursor })
+    }
+
+    pub async fn get_booking(&self, booking_id: &str) -> Result<BookingDetailResponse, ClientError> {
+        self.json(self.request(Method::GET, &format!("/api/bookings/{}", booking_id))).await
+    }
+
+    pub async fn cancel_booking(&self, booking_id: &str) -> Result<(), ClientError> {
+        self.send(self.request(Method::DELETE, &format!("/api/bookings/{}", booking_id))).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,841 @@
+use std::env;
+use std::str::FromStr;
+
+use mongodb::options::{ReadPreference, ReadPreferenceOptions};
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+// Thresholds for the anomaly monitor; alerts go to a Slack-compatible webhook
+#[derive(Clone)]
+pub struct MonitoringConfig {
+    pub webhook_url: Option<String>,
+    pub check_interval_secs: u64,
+    pub window_minutes: i64,
+    pub min_sample: u64,
+    pub booking_failure_ratio: f64,
+    pub payment_dropoff_ratio: f64,
+    pub cooldown_minutes: i64,
+}
+
+impl MonitoringConfig {
+    pub fn from_env() -> Self {
+        Self {
+            webhook_url: env::var("ALERT_WEBHOOK_URL").ok().filter(|v| !v.is_empty()),
+            check_interval_secs: env_or("ALERT_CHECK_INTERVAL_SECS", 60),
+            window_minutes: env_or("ALERT_WINDOW_MINUTES", 15),
+            min_sample: env_or("ALERT_MIN_SAMPLE", 20),
+            booking_failure_ratio: env_or("ALERT_BOOKING_FAILURE_RATIO", 0.25),
+            payment_dropoff_ratio: env_or("ALERT_PAYMENT_DROPOFF_RATIO", 0.4),
+            cooldown_minutes: env_or("ALERT_COOLDOWN_MINUTES", 30),
+        }
+    }
+}
+
+// Jurisdictions whose monthly return is generated automatically; others can be exported on demand
+#[derive(Clone)]
+pub struct RegulatoryConfig {
+    pub jurisdictions: Vec<String>,
+    pub check_interval_secs: u64,
+}
+
+impl RegulatoryConfig {
+    pub fn from_env() -> Self {
+        Self {
+            jurisdictions: env::var("REGULATORY_JURISDICTIONS")
+                .unwrap_or_else(|_| "KE".to_string())
+                .split(',')
+                .map(|c| c.trim().to_uppercase())
+                .filter(|c| !c.is_empty())
+                .collect(),
+            check_interval_secs: env_or("REGULATORY_EXPORT_CHECK_SECS", 3600),
+        }
+    }
+}
+
+// Seats for the next AVAILABILITY_WARMUP_DAYS of departures are created ahead of the first booking,
+// for the AVAILABILITY_WARMUP_ROUTES most searched routes (every route while nothing has been searched)
+#[derive(Clone)]
+pub struct WarmupConfig {
+    pub days: i64,
+    pub routes: i64,
+    pub search_days: i64,
+    pub interval_secs: u64,
+}
+
+impl WarmupConfig {
+    pub fn from_env() -> Self {
+        Self {
+            days: env_or("AVAILABILITY_WARMUP_DAYS", 7),
+            routes: env_or("AVAILABILITY_WARMUP_ROUTES", 20),
+            search_days: env_or("AVAILABILITY_WARMUP_SEARCH_DAYS", 30),
+            interval_secs: env_or("AVAILABILITY_WARMUP_INTERVAL_SECS", 3600),
+        }
+    }
+}
+
+// Bus types in HEADCOUNT_BUS_TYPES sell places by headcount, up to the vehicle's capacity, instead
+// of numbered seats; matched case-insensitively
+#[derive(Clone)]
+pub struct InventoryConfig {
+    pub headcount_bus_types: Vec<String>,
+}
+
+impl InventoryConfig {
+    pub fn from_env() -> Self {
+        Self {
+            headcount_bus_types: env::var("HEADCOUNT_BUS_TYPES")
+                .unwrap_or_else(|_| "Shuttle".to_string())
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn sells_by_headcount(&self, bus_type: &str) -> bool {
+        self.headcount_bus_types.contains(&bus_type.trim().to_lowercase())
+    }
+}
+
+// Domain events reach in-process subscribers straight away, each of which may fall behind by up to
+// EVENTS_BUFFER events before missing some. With EVENTS_PERSIST they are written to the
+// `domain_events` outbox first and relayed from there, so none are lost to a restart; relayed
+// events are kept for EVENTS_RETENTION_DAYS
+#[derive(Clone)]
+pub struct EventsConfig {
+    pub buffer: usize,
+    pub persist: bool,
+    pub relay_interval_secs: u64,
+    pub retention_days: u64,
+}
+
+impl EventsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            buffer: env_or("EVENTS_BUFFER", 1024).max(1),
+            persist: env_or("EVENTS_PERSIST", false),
+            relay_interval_secs: env_or("EVENTS_RELAY_INTERVAL_SECS", 1),
+            retention_days: env_or("EVENTS_RETENTION_DAYS", 7),
+        }
+    }
+}
+
+// Forwards domain events to a broker for downstream consumers when EVENT_STREAM is kafka (through a
+// Kafka REST proxy at KAFKA_REST_URL) or nats (NATS_URL). Each event goes to
+// `<EVENT_STREAM_PREFIX>.<type>` through the outbox, so a broker outage only delays it; set
+// EVENTS_PERSIST as well so a restart can't drop events on the way to the outbox
+#[derive(Clone)]
+pub struct EventStreamConfig {
+    pub provider: Option<String>,
+    pub prefix: String,
+    pub environment: String,
+    pub kafka_rest_url: Option<String>,
+    pub kafka_username: Option<String>,
+    pub kafka_password: Option<String>,
+    pub nats_url: Option<String>,
+    pub nats_token: Option<String>,
+    pub max_attempts: i32,
+}
+
+impl EventStreamConfig {
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+        Self {
+            provider: var("EVENT_STREAM").map(|p| p.to_lowercase()),
+            prefix: env_or("EVENT_STREAM_PREFIX", "busbook".to_string()),
+            environment: env_or("APP_ENV", "development".to_string()),
+            kafka_rest_url: var("KAFKA_REST_URL").map(|url| url.trim_end_matches('/').to_string()),
+            kafka_username: var("KAFKA_REST_USERNAME"),
+            kafka_password: var("KAFKA_REST_PASSWORD"),
+            nats_url: var("NATS_URL"),
+            nats_token: var("NATS_TOKEN"),
+            max_attempts: env_or("EVENT_STREAM_MAX_ATTEMPTS", 12).max(1),
+        }
+    }
+}
+
+// Customer accounts with no sign-in or booking for RETENTION_INACTIVE_YEARS are anonymized, after an
+// email warning sent RETENTION_NOTICE_DAYS ahead; 0 years turns retention off
+#[derive(Clone)]
+pub struct RetentionConfig {
+    pub inactive_years: i64,
+    pub notice_days: i64,
+    pub batch_size: i64,
+    pub check_interval_secs: u64,
+}
+
+impl RetentionConfig {
+    pub fn from_env() -> Self {
+        Self {
+            inactive_years: env_or("RETENTION_INACTIVE_YEARS", 3),
+            notice_days: env_or("RETENTION_NOTICE_DAYS", 30),
+            batch_size: env_or("RETENTION_BATCH_SIZE", 200),
+            check_interval_secs: env_or("RETENTION_CHECK_SECS", 86400),
+        }
+    }
+
+    /// Accounts whose last activity is before this are due for anonymization.
+    pub fn inactive_before(&self, now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        now - chrono::Duration::days(365 * self.inactive_years)
+    }
+}
+
+// A latency objective: `target` of requests to the route finish within `threshold_ms`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SloObjective {
+    pub name: String,
+    pub method: String,
+    pub route: String,
+    pub target: f64,
+    pub threshold_ms: u64,
+}
+
+impl SloObjective {
+    /// Parses `name METHOD /route pNN<MSms`, e.g. `seat_availability GET /api/buses/{id}/seats p99<300ms`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.split_whitespace();
+        let (name, method, route, objective) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let (percentile, threshold) = objective.strip_prefix('p')?.split_once('<')?;
+        let percentile: f64 = percentile.parse().ok()?;
+        if parts.next().is_some() || !(0.0..100.0).contains(&percentile) || !route.starts_with('/') {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            method: method.to_uppercase(),
+            route: route.to_string(),
+            target: percentile / 100.0,
+            threshold_ms: threshold.trim_end_matches("ms").parse().ok()?,
+        })
+    }
+}
+
+// Objectives are `;`-separated in SLO_OBJECTIVES. A burn rate of 1 spends the error budget exactly
+// over SLO_BUDGET_WINDOW_MINUTES; SLO_BURN_RATE_ALERT is the rate that warns when sustained over
+// both the 5 minute and 1 hour windows
+#[derive(Clone)]
+pub struct SloConfig {
+    pub objectives: Vec<SloObjective>,
+    pub burn_rate_alert: f64,
+    pub budget_window_minutes: i64,
+    pub check_interval_secs: u64,
+}
+
+impl SloConfig {
+    pub fn from_env() -> Self {
+        let specs = env::var("SLO_OBJECTIVES").unwrap_or_else(|_| {
+            "seat_availability GET /api/buses/{id}/seats p99<300ms;\
+             bus_search GET /api/buses p95<500ms;\
+             create_booking POST /api/bookings p99<1000ms".to_string()
+        });
+        let objectives = specs
+            .split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|spec| {
+                let objective = SloObjective::parse(spec);
+                if objective.is_none() {
+                    log::warn!("Ignoring invalid SLO objective '{}'", spec);
+                }
+                objective
+            })
+            .collect();
+        Self {
+            objectives,
+            burn_rate_alert: env_or("SLO_BURN_RATE_ALERT", 14.4),
+            budget_window_minutes: env_or("SLO_BUDGET_WINDOW_MINUTES", 24 * 60),
+            check_interval_secs: env_or("SLO_CHECK_INTERVAL_SECS", 60),
+        }
+    }
+}
+
+// Commands slower than SLOW_QUERY_MS are logged and counted; QUERY_PROFILING_DEBUG adds a debug
+// line per command and explains the slow ones, which costs an extra round trip each
+#[derive(Clone)]
+pub struct ProfilingConfig {
+    pub slow_query_ms: u64,
+    pub debug: bool,
+}
+
+impl ProfilingConfig {
+    pub fn from_env() -> Self {
+        Self {
+            slow_query_ms: env_or("SLOW_QUERY_MS", 200),
+            debug: env_or("QUERY_PROFILING_DEBUG", false),
+        }
+    }
+}
+
+// Limits past which search and reporting requests are turned away; LOAD_SHEDDING_MAX_IN_FLIGHT
+// is per process, across all workers
+#[derive(Clone)]
+pub struct LoadSheddingConfig {
+    pub enabled: bool,
+    pub max_in_flight: usize,
+    pub latency_ms: u64,
+    pub retry_after_secs: u64,
+}
+
+impl LoadSheddingConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env_or("LOAD_SHEDDING_ENABLED", true),
+            max_in_flight: env_or("LOAD_SHEDDING_MAX_IN_FLIGHT", 512),
+            latency_ms: env_or("LOAD_SHEDDING_LATENCY_MS", 2000),
+            retry_after_secs: env_or("LOAD_SHEDDING_RETRY_AFTER_SECS", 10),
+        }
+    }
+}
+
+// Requests allowed per RATE_LIMIT_WINDOW_SECS for each signed-in user, or for each IP when
+// anonymous. Callers that keep going past RATE_LIMIT_ABUSE_FACTOR times their limit in one
+// window are blocked for RATE_LIMIT_BLOCK_SECS
+#[derive(Clone, serde::Serialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub window_secs: i64,
+    pub anonymous_limit: i64,
+    pub user_limit: i64,
+    pub abuse_factor: i64,
+    pub block_secs: i64,
+    pub trust_forwarded: bool,
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env_or("RATE_LIMIT_ENABLED", true),
+            window_secs: env_or("RATE_LIMIT_WINDOW_SECS", 60i64).max(1),
+            anonymous_limit: env_or("RATE_LIMIT_ANONYMOUS", 120),
+            user_limit: env_or("RATE_LIMIT_USER", 300),
+            abuse_factor: env_or("RATE_LIMIT_ABUSE_FACTOR", 5i64).max(2),
+            block_secs: env_or("RATE_LIMIT_BLOCK_SECS", 900),
+            trust_forwarded: env_or("RATE_LIMIT_TRUST_FORWARDED", false),
+        }
+    }
+
+    /// The standing limit for a `user:` or `ip:` key, before any override.
+    pub fn default_limit(&self, key: &str) -> i64 {
+        if key.starts_with("user:") { self.user_limit } else { self.anonymous_limit }
+    }
+}
+
+// Bookings scoring at or above the threshold wait for manual review instead of confirming
+#[derive(Clone)]
+pub struct FraudConfig {
+    pub review_threshold: u32,
+}
+
+impl FraudConfig {
+    pub fn from_env() -> Self {
+        Self {
+            review_threshold: env_or("FRAUD_REVIEW_THRESHOLD", 60),
+        }
+    }
+}
+
+// GEOIP_DATABASE points at a MaxMind country database; without it requests carry no country.
+// Payment initiation honours GEOIP_PAYMENT_DENY, and GEOIP_PAYMENT_ALLOW when set; unresolved countries are let through.
+#[derive(Clone)]
+pub struct GeoIpConfig {
+    pub database_path: Option<String>,
+    pub trust_forwarded: bool,
+    pub home_countries: Vec<String>,
+    pub payment_allow: Vec<String>,
+    pub payment_deny: Vec<String>,
+}
+
+fn country_list(key: &str, default: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+impl GeoIpConfig {
+    pub fn from_env() -> Self {
+        Self {
+            database_path: env::var("GEOIP_DATABASE").ok().filter(|v| !v.is_empty()),
+            trust_forwarded: env_or("GEOIP_TRUST_FORWARDED", false),
+            home_countries: country_list("GEOIP_HOME_COUNTRIES", "KE"),
+            payment_allow: country_list("GEOIP_PAYMENT_ALLOW", ""),
+            payment_deny: country_list("GEOIP_PAYMENT_DENY", ""),
+        }
+    }
+
+    pub fn allows_payment_from(&self, country: Option<&str>) -> bool {
+        let Some(country) = country else {
+            return true;
+        };
+        let country = country.to_uppercase();
+        !self.payment_deny.contains(&country) && (self.payment_allow.is_empty() || self.payment_allow.contains(&country))
+    }
+
+    pub fn is_foreign(&self, country: &str) -> bool {
+        !self.home_countries.is_empty() && !self.home_countries.contains(&country.to_uppercase())
+    }
+}
+
+// PII_ENCRYPTION_KEY is 32 random bytes, base64-encoded; without it the key is derived from JWT_SECRET,
+// which is only acceptable in development
+#[derive(Clone)]
+pub struct PiiConfig {
+    pub key: [u8; 32],
+}
+
+impl PiiConfig {
+    pub fn from_env() -> Self {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let configured = env::var("PII_ENCRYPTION_KEY").ok()
+            .and_then(|v| STANDARD.decode(v.trim()).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+        let key = configured.unwrap_or_else(|| {
+            let secret = TokenConfig::from_env().secret;
+            let digest = ring::digest::digest(&ring::digest::SHA256, secret.as_bytes());
+            let mut key = [0u8; 32];
+            key.copy_from_slice(digest.as_ref());
+            key
+        });
+        Self { key }
+    }
+}
+
+// TICKET_SIGNING_KEY signs the QR codes on e-tickets; without it JWT_SECRET is used, which is only
+// acceptable in development. Changing it turns every ticket already issued into a forgery.
+#[derive(Clone)]
+pub struct TicketConfig {
+    pub signing_key: String,
+}
+
+impl TicketConfig {
+    pub fn from_env() -> Self {
+        let signing_key = env::var("TICKET_SIGNING_KEY").ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| TokenConfig::from_env().secret);
+        Self { signing_key }
+    }
+}
+
+// Crew handsets sign conductor and check-in requests with a key provisioned per device.
+// DEVICE_SIGNING_REQUIRED (on by default) refuses unsigned requests. Switching it off lets
+// handsets without a key in while they are rolled out, but then a revoked one can simply stop
+// signing, so revocation means nothing until it is back on.
+// DEVICE_SIGNATURE_MAX_SKEW_SECS is how far a device's clock may be from ours.
+#[derive(Clone)]
+pub struct DeviceSigningConfig {
+    pub required: bool,
+    pub max_skew_secs: i64,
+}
+
+impl DeviceSigningConfig {
+    pub fn from_env() -> Self {
+        Self {
+            required: env_or("DEVICE_SIGNING_REQUIRED", true),
+            max_skew_secs: env_or("DEVICE_SIGNATURE_MAX_SKEW_SECS", 300_i64).clamp(30, 3600),
+        }
+    }
+}
+
+// Conductors on routes with no signal upload the trip's check-ins and cash sales once they
+// reach coverage. OFFLINE_SYNC_GRACE_HOURS is how long after the trip token expires that
+// upload is still accepted; the token opens nothing else once it has run out.
+#[derive(Clone)]
+pub struct OfflineSyncConfig {
+    pub grace_hours: i64,
+}
+
+impl OfflineSyncConfig {
+    pub fn from_env() -> Self {
+        Self {
+            grace_hours: env_or("OFFLINE_SYNC_GRACE_HOURS", 48_i64).clamp(0, 168),
+        }
+    }
+}
+
+// Bump TERMS_VERSION whenever the published terms or cancellation rules change
+#[derive(Clone)]
+pub struct TermsConfig {
+    pub version: String,
+    pub url: Option<String>,
+}
+
+impl TermsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            version: env_or("TERMS_VERSION", "2025-01-01".to_string()),
+            url: env::var("TERMS_URL").ok().filter(|v| !v.is_empty()),
+        }
+    }
+}
+
+// gzip/brotli/zstd is negotiated per request from Accept-Encoding; COMPRESSION=off disables it
+#[derive(Clone)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+}
+
+impl CompressionConfig {
+    pub fn from_env() -> Self {
+        let setting = env::var("COMPRESSION").unwrap_or_default().to_lowercase();
+        Self {
+            enabled: !matches!(setting.as_str(), "off" | "false" | "0"),
+        }
+    }
+}
+
+// Provider credentials for saved payment methods; a provider without credentials is unavailable
+#[derive(Clone)]
+pub struct PaymentsConfig {
+    pub stripe_secret_key: Option<String>,
+    pub paypal_client_id: Option<String>,
+    pub paypal_client_secret: Option<String>,
+    pub paypal_api_base: String,
+}
+
+impl PaymentsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            stripe_secret_key: env::var("STRIPE_SECRET_KEY").ok().filter(|v| !v.is_empty()),
+            paypal_client_id: env::var("PAYPAL_CLIENT_ID").ok().filter(|v| !v.is_empty()),
+            paypal_client_secret: env::var("PAYPAL_CLIENT_SECRET").ok().filter(|v| !v.is_empty()),
+            paypal_api_base: env_or("PAYPAL_API_BASE", "https://api-m.sandbox.paypal.com".to_string()),
+        }
+    }
+}
+
+// Safaricom Daraja credentials for STK Push. Results are posted to our webhook endpoint with
+// MPESA_CALLBACK_TOKEN, like C2B confirmations. Once STK Push is configured new bookings wait
+// in PendingPayment until paid (BOOKING_PAYMENT_REQUIRED overrides that), and are cancelled
+// if still unpaid after MPESA_PAYMENT_TIMEOUT_MINUTES
+#[derive(Clone)]
+pub struct MpesaConfig {
+    pub consumer_key: Option<String>,
+    pub consumer_secret: Option<String>,
+    pub shortcode: Option<String>,
+    pub passkey: Option<String>,
+    pub api_base: String,
+    pub callback_url: String,
+    pub payment_required: bool,
+    pub payment_timeout_minutes: i64,
+}
+
+impl MpesaConfig {
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+        let (consumer_key, consumer_secret, shortcode, passkey) =
+            (var("MPESA_CONSUMER_KEY"), var("MPESA_CONSUMER_SECRET"), var("MPESA_SHORTCODE"), var("MPESA_PASSKEY"));
+        let stk_configured = consumer_key.is_some() && consumer_secret.is_some() && shortcode.is_some() && passkey.is_some();
+        let base_url = env_or("PUBLIC_BASE_URL", "http://localhost:8080".to_string());
+        Self {
+            consumer_key,
+            consumer_secret,
+            shortcode,
+            passkey,
+            api_base: env_or("MPESA_API_BASE", "https://sandbox.safaricom.co.ke".to_string()).trim_end_matches('/').to_string(),
+            callback_url: format!(
+                "{}/api/webhooks/mpesa_stk?token={}",
+                base_url.trim_end_matches('/'),
+                var("MPESA_CALLBACK_TOKEN").unwrap_or_default()
+            ),
+            payment_required: env_or("BOOKING_PAYMENT_REQUIRED", stk_configured),
+            payment_timeout_minutes: env_or("MPESA_PAYMENT_TIMEOUT_MINUTES", 15),
+        }
+    }
+}
+
+// Commuter passes sell PASS_RIDES rides on one route at PASS_DISCOUNT_PERCENT off the single
+// fare, to be ridden within PASS_VALID_DAYS of paying
+#[derive(Clone)]
+pub struct PassConfig {
+    pub rides: i32,
+    pub discount_percent: f64,
+    pub valid_days: i64,
+}
+
+impl PassConfig {
+    pub fn from_env() -> Self {
+        Self {
+            rides: env_or("PASS_RIDES", 10).max(1),
+            discount_percent: env_or("PASS_DISCOUNT_PERCENT", 15.0_f64).clamp(0.0, 100.0),
+            valid_days: env_or("PASS_VALID_DAYS", 60).max(1),
+        }
+    }
+}
+
+// Charters: accepting an operator's quote takes a CHARTER_DEPOSIT_PERCENT deposit of its price by
+// M-Pesa, and a quote can be accepted for CHARTER_QUOTE_VALID_HOURS after it is given
+#[derive(Clone)]
+pub struct CharterConfig {
+    pub deposit_percent: f64,
+    pub quote_valid_hours: i64,
+}
+
+impl CharterConfig {
+    pub fn from_env() -> Self {
+        Self {
+            deposit_percent: env_or("CHARTER_DEPOSIT_PERCENT", 30.0_f64).clamp(1.0, 100.0),
+            quote_valid_hours: env_or("CHARTER_QUOTE_VALID_HOURS", 48).max(1),
+        }
+    }
+}
+
+// Parcels cost PARCEL_BASE_FEE plus the size's surcharge plus PARCEL_PER_KG for every started
+// kilogram; nothing over PARCEL_MAX_WEIGHT_KG goes on a passenger bus
+#[derive(Clone)]
+pub struct ParcelConfig {
+    pub base_fee: f64,
+    pub per_kg: f64,
+    pub max_weight_kg: f64,
+}
+
+impl ParcelConfig {
+    pub fn from_env() -> Self {
+        Self {
+            base_fee: env_or("PARCEL_BASE_FEE", 150.0_f64).max(0.0),
+            per_kg: env_or("PARCEL_PER_KG", 30.0_f64).max(0.0),
+            max_weight_kg: env_or("PARCEL_MAX_WEIGHT_KG", 50.0_f64).max(1.0),
+        }
+    }
+}
+
+// Outgoing email. EMAIL_PROVIDER picks how it is sent: "sendgrid" (SENDGRID_API_KEY), "smtp"
+// (SMTP_HOST, with STARTTLS on SMTP_PORT unless SMTP_TLS=implicit) or "relay" (POST to
+// EMAIL_RELAY_URL). Left unset, the first one configured in that order is used
+#[derive(Clone)]
+pub struct EmailConfig {
+    pub provider: Option<String>,
+    pub from_address: String,
+    pub from_name: String,
+    pub relay_url: Option<String>,
+    pub sendgrid_api_key: Option<String>,
+    pub sendgrid_api_base: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_implicit_tls: bool,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+}
+
+impl EmailConfig {
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+        let (relay_url, sendgrid_api_key, smtp_host) = (var("EMAIL_RELAY_URL"), var("SENDGRID_API_KEY"), var("SMTP_HOST"));
+        let provider = var("EMAIL_PROVIDER").map(|p| p.to_lowercase()).or_else(|| {
+            let configured = [("sendgrid", sendgrid_api_key.is_some()), ("smtp", smtp_host.is_some()), ("relay", relay_url.is_some())];
+            configured.iter().find(|(_, set)| *set).map(|(name, _)| name.to_string())
+        });
+        let smtp_implicit_tls = var("SMTP_TLS").is_some_and(|t| t.eq_ignore_ascii_case("implicit"));
+        Self {
+            provider,
+            from_address: env_or("EMAIL_FROM", "tickets@busbook.co.ke".to_string()),
+            from_name: env_or("EMAIL_FROM_NAME", "Bus Book".to_string()),
+            relay_url,
+            sendgrid_api_key,
+            sendgrid_api_base: env_or("SENDGRID_API_BASE", "https://api.sendgrid.com".to_string()).trim_end_matches('/').to_string(),
+            smtp_host,
+            smtp_port: env_or("SMTP_PORT", if smtp_implicit_tls { 465 } else { 587 }),
+            smtp_implicit_tls,
+            smtp_username: var("SMTP_USERNAME"),
+            smtp_password: var("SMTP_PASSWORD"),
+        }
+    }
+}
+
+// Session tokens issued by the auth flows. Access tokens go with every request; the refresh
+// token issued alongside buys a new pair for JWT_REFRESH_TTL_DAYS. JWT_ISSUER adds an `iss`
+// claim for downstream services that check it; JWT_EMBED_SCOPES=false leaves permissions out
+// of the token, and they are then resolved from the role on every request
+#[derive(Clone)]
+pub struct TokenConfig {
+    pub secret: String,
+    pub issuer: Option<String>,
+    pub access_ttl_hours: i64,
+    pub refresh_ttl_days: i64,
+    pub anonymous_ttl_days: i64,
+    pub embed_scopes: bool,
+}
+
+impl TokenConfig {
+    pub fn from_env() -> Self {
+        Self {
+            secret: env_or("JWT_SECRET", "secret".to_string()),
+            issuer: env::var("JWT_ISSUER").ok().filter(|v| !v.is_empty()),
+            access_ttl_hours: env_or("JWT_ACCESS_TTL_HOURS", 168),
+            refresh_ttl_days: env_or("JWT_REFRESH_TTL_DAYS", 30),
+            anonymous_ttl_days: env_or("JWT_ANONYMOUS_TTL_DAYS", 7),
+            embed_scopes: env_or("JWT_EMBED_SCOPES", true),
+        }
+    }
+}
+
+// Guest booking lookups (reference + email). Failures are counted per IP and per reference
+// over the window; past CAPTCHA_AFTER failures a solved CAPTCHA is required when
+// CAPTCHA_SECRET is set, and past the hard limits the caller is turned away until the window
+// moves on. An IP failing against ENUMERATION_REFERENCES distinct references, or a reference
+// tried from that many IPs, is flagged to the fraud team
+#[derive(Clone)]
+pub struct LookupConfig {
+    pub window_minutes: i64,
+    pub max_failures_per_ip: u64,
+    pub max_failures_per_reference: u64,
+    pub captcha_after: u64,
+    pub captcha_secret: Option<String>,
+    pub captcha_verify_url: String,
+    pub enumeration_references: u64,
+}
+
+impl LookupConfig {
+    pub fn from_env() -> Self {
+        Self {
+            window_minutes: env_or("BOOKING_LOOKUP_WINDOW_MINUTES", 15),
+            max_failures_per_ip: env_or("BOOKING_LOOKUP_MAX_FAILURES_PER_IP", 20),
+            max_failures_per_reference: env_or("BOOKING_LOOKUP_MAX_FAILURES_PER_REFERENCE", 5),
+            captcha_after: env_or("BOOKING_LOOKUP_CAPTCHA_AFTER", 3),
+            captcha_secret: env::var("CAPTCHA_SECRET").ok().filter(|v| !v.is_empty()),
+            captcha_verify_url: env_or("CAPTCHA_VERIFY_URL", "https://hcaptcha.com/siteverify".to_string()),
+            enumeration_references: env_or("BOOKING_LOOKUP_ENUMERATION_REFERENCES", 10),
+        }
+    }
+}
+
+// Shared secrets for inbound payment callbacks. Stripe signs each delivery with
+// STRIPE_WEBHOOK_SECRET; M-Pesa has no signature, so the callback URL registered with
+// Safaricom carries MPESA_CALLBACK_TOKEN as `?token=`
+#[derive(Clone)]
+pub struct WebhookConfig {
+    pub stripe_secret: Option<String>,
+    pub mpesa_token: Option<String>,
+    // Oldest Stripe signature timestamp accepted on live delivery; replays skip the check
+    pub tolerance_secs: i64,
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Self {
+        Self {
+            stripe_secret: env::var("STRIPE_WEBHOOK_SECRET").ok().filter(|v| !v.is_empty()),
+            mpesa_token: env::var("MPESA_CALLBACK_TOKEN").ok().filter(|v| !v.is_empty()),
+            tolerance_secs: env_or("WEBHOOK_TOLERANCE_SECS", 300),
+        }
+    }
+}
+
+// Partner cross-sell offers shown after booking. Click links are signed with
+// PARTNER_LINK_SECRET (the JWT secret when unset) so hand-made URLs can't inflate the click
+// counts partners are paid on; partners report conversions with PARTNER_CONVERSION_TOKEN as
+// a bearer token, and without one conversion reporting is switched off
+#[derive(Clone)]
+pub struct PartnerConfig {
+    pub link_secret: String,
+    pub conversion_token: Option<String>,
+    pub public_base_url: String,
+    pub max_offers: i64,
+}
+
+impl PartnerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            link_secret: env::var("PARTNER_LINK_SECRET").ok().filter(|v| !v.is_empty()).unwrap_or_else(crate::middleware::auth::jwt_secret),
+            conversion_token: env::var("PARTNER_CONVERSION_TOKEN").ok().filter(|v| !v.is_empty()),
+            public_base_url: env_or("PUBLIC_BASE_URL", "http://localhost:8080".to_string()).trim_end_matches('/').to_string(),
+            max_offers: env_or("PARTNER_MAX_OFFERS", 3),
+        }
+    }
+}
+
+// Open data GTFS feed. Every operator is published with our site as its agency URL, since
+// that is where the trips are sold; GTFS_SERVICE_DAYS is how far ahead the calendar runs.
+// Imported archives are capped at GTFS_MAX_UPLOAD_MB
+#[derive(Clone)]
+pub struct GtfsConfig {
+    pub publisher_name: String,
+    pub publisher_url: String,
+    pub service_days: i64,
+    pub refresh_secs: u64,
+    pub max_upload_bytes: usize,
+}
+
+impl GtfsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            publisher_name: env_or("GTFS_PUBLISHER_NAME", "Bus Book".to_string()),
+            publisher_url: env_or("PUBLIC_BASE_URL", "http://localhost:8080".to_string()).trim_end_matches('/').to_string(),
+            service_days: env_or("GTFS_SERVICE_DAYS", 60_i64).clamp(1, 365),
+            refresh_secs: env_or("GTFS_REFRESH_SECS", 86400),
+            max_upload_bytes: env_or("GTFS_MAX_UPLOAD_MB", 20_usize) * 1024 * 1024,
+        }
+    }
+}
+
+// Target of `bus-book --smoke-test`. Without SMOKE_TEST_TRAVEL_DATE the journey books a week
+// out; without SMOKE_TEST_PAYMENT_TOKEN (a provider test token such as pm_card_visa) the
+// payment step is skipped
+#[derive(Clone)]
+pub struct SmokeTestConfig {
+    pub base_url: String,
+    pub travel_date: Option<String>,
+    pub payment_provider: String,
+    pub payment_token: Option<String>,
+    pub email_domain: String,
+    pub timeout_secs: u64,
+}
+
+impl SmokeTestConfig {
+    pub fn from_env() -> Self {
+        Self {
+            base_url: env_or("SMOKE_TEST_BASE_URL", "http://localhost:8080".to_string()).trim_end_matches('/').to_string(),
+            travel_date: env::var("SMOKE_TEST_TRAVEL_DATE").ok().filter(|v| !v.is_empty()),
+            payment_provider: env_or("SMOKE_TEST_PAYMENT_PROVIDER", "stripe".to_string()),
+            payment_token: env::var("SMOKE_TEST_PAYMENT_TOKEN").ok().filter(|v| !v.is_empty()),
+            email_domain: env_or("SMOKE_TEST_EMAIL_DOMAIN", "example.com".to_string()),
+            timeout_secs: env_or("SMOKE_TEST_TIMEOUT_SECS", 15),
+        }
+    }
+}
+
+/// The kind of read a repository method performs; decides which replica set member serves it.
+#[derive(Clone, Copy)]
+pub enum ReadWorkload {
+    Search,
+    Analytics,
+}
+
+// Bookings and auth always read from the primary; only the heavy list/report reads are routable
+#[derive(Clone)]
+pub struct ReadPreferenceConfig {
+    pub search: ReadPreference,
+    pub analytics: ReadPreference,
+}
+
+impl ReadPreferenceConfig {
+    pub fn from_env() -> Self {
+        Self {
+            search: read_preference_or("READ_PREFERENCE_SEARCH", "secondaryPreferred"),
+            analytics: read_preference_or("READ_PREFERENCE_ANALYTICS", "secondaryPreferred"),
+        }
+    }
+
+    pub fn for_workload(&self, workload: ReadWorkload) -> &ReadPreference {
+        match workload {
+            ReadWorkload::Search => &self.search,
+            ReadWorkload::Analytics => &self.analytics,
+        }
+    }
+}
+
+// Accepts the connection-string mode names, e.g. `secondaryPreferred`
+fn read_preference_or(key: &str, default: &str) -> ReadPreference {
+    let mode = env::var(key).unwrap_or_else(|_| default.to_string());
+    let options = ReadPreferenceOptions::default();
+    match mode.to_lowercase().as_str() {
+        "primarypreferred" => ReadPreference::PrimaryPreferred { options },
+        "secondary" => ReadPreference::Secondary { options },
+        "secondarypreferred" => ReadPreference::SecondaryPreferred { options },
+        "nearest" => ReadPreference::Nearest { options },
+        _ => ReadPreference::Primary,
+    }
+}
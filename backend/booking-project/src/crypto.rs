@@ -0,0 +1,58 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::config::PiiConfig;
+use crate::error::AppError;
+
+// Bumped if the algorithm or key ever changes so old values can still be told apart
+const VERSION: &str = "v1";
+
+fn key() -> Result<LessSafeKey, AppError> {
+    let unbound = UnboundKey::new(&AES_256_GCM, &PiiConfig::from_env().key).map_err(|_| AppError::internal("Invalid encryption key"))?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+/// Encrypts a value with AES-256-GCM. `context` (e.g. the owning record's id) is authenticated,
+/// so a sealed value copied onto another record fails to open.
+pub fn seal(plaintext: &str, context: &str) -> Result<String, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| AppError::internal("Failed to generate nonce"))?;
+
+    let mut sealed = plaintext.as_bytes().to_vec();
+    key()?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(context.as_bytes()), &mut sealed)
+        .map_err(|_| AppError::internal("Encryption failed"))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend(sealed);
+    Ok(format!("{}:{}", VERSION, STANDARD.encode(payload)))
+}
+
+pub fn open(sealed: &str, context: &str) -> Result<String, AppError> {
+    let encoded = sealed.strip_prefix(&format!("{}:", VERSION)).ok_or_else(|| AppError::internal("Unsupported encryption version"))?;
+    let mut payload = STANDARD.decode(encoded).map_err(|e| AppError::internal(e.to_string()))?;
+    if payload.len() < NONCE_LEN {
+        return Err(AppError::internal("Sealed value is truncated"));
+    }
+    let mut ciphertext = payload.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&payload).map_err(|_| AppError::internal("Invalid nonce"))?;
+
+    let plaintext = key()?
+        .open_in_place(nonce, Aad::from(context.as_bytes()), &mut ciphertext)
+        .map_err(|_| AppError::internal("Decryption failed"))?;
+    String::from_utf8(plaintext.to_vec()).map_err(|e| AppError::internal(e.to_string()))
+}
+
+/// Compares secrets without returning early, so response timing doesn't reveal how much matched.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// FNV-1a: a quick, non-cryptographic hash that is stable across builds and platforms, unlike
+/// std's DefaultHasher. For bucketing and cache validators, never for secrets.
+pub fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
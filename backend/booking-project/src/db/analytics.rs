@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, Document},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ReadWorkload;
+use crate::models::analytics::{day_of_week, time_slot, CountryActivity, DemandHeatmap, HeatmapCell, OperatorRevenue, RevenueReport};
+use crate::models::settlement::round_money;
+use crate::models::search_log::SearchLogEntry;
+
+// (from, to, day of week, time slot)
+type CellKey = (String, String, String, String);
+
+impl MongoDB {
+    pub(super) fn get_search_log_collection(&self) -> Collection<SearchLogEntry> {
+        self.database().collection("search_log")
+    }
+
+    pub(super) fn get_search_log_reporting_collection(&self) -> Collection<Document> {
+        self.collection_for("search_log", ReadWorkload::Analytics)
+    }
+
+    /// Booking and search demand per route, day of week and departure slot, including lost demand.
+    pub async fn get_demand_heatmap(&self, days: i64, from: Option<&str>, to: Option<&str>) -> Result<DemandHeatmap, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let mut cells: BTreeMap<CellKey, HeatmapCell> = BTreeMap::new();
+        let wanted = |f: &str, t: &str| {
+            from.is_none_or(|from| from.eq_ignore_ascii_case(f)) && to.is_none_or(|to| to.eq_ignore_ascii_case(t))
+        };
+
+        let mut buses = HashMap::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if let Some(id) = bus.id {
+                buses.insert(id, bus);
+            }
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "booking_date": { "$gte": since }, "status": { "$ne": "Cancelled" } } },
+            doc! { "$group": {
+                "_id": { "bus_id": "$bus_id", "travel_date": "$travel_date" },
+                "count": { "$sum": 1 },
+            } },
+        ];
+        let mut groups = self.collection_for::<Document>("bookings", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let key = group.get_document("_id")?;
+            let Some(bus) = buses.get(&key.get_object_id("bus_id")?) else {
+                continue;
+            };
+            if !wanted(&bus.route.from, &bus.route.to) {
+                continue;
+            }
+            let Some(day) = day_of_week(key.get_str("travel_date")?) else {
+                continue;
+            };
+            cell(&mut cells, &bus.route.from, &bus.route.to, day, time_slot(&bus.route.departure_time))
+                .bookings += count(&group);
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "created_at": { "$gte": since } } },
+            doc! { "$group": {
+                "_id": { "from": "$from", "to": "$to", "travel_date": "$travel_date", "departure_time": "$departure_time" },
+                "count": { "$sum": 1 },
+                "zero_results": { "$sum": { "$cond": [{ "$lte": ["$results_count", 0] }, 1, 0] } },
+            } },
+        ];
+        let mut groups = self.get_search_log_reporting_collection().aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let key = group.get_document("_id")?;
+            let (Ok(route_from), Ok(route_to)) = (key.get_str("from"), key.get_str("to")) else {
+                continue;
+            };
+            if !wanted(route_from, route_to) {
+                continue;
+            }
+            let Some(day) = key.get_str("travel_date").ok().and_then(day_of_week) else {
+                continue;
+            };
+            let slot = key.get_str("departure_time").map(time_slot).unwrap_or_else(|_| "any".to_string());
+            let entry = cell(&mut cells, route_from, route_to, day, slot);
+            entry.searches += count(&group);
+            entry.zero_result_searches += group.get_i32("zero_results").map(i64::from).unwrap_or(0);
+        }
+
+        Ok(DemandHeatmap {
+            days,
+            cells: cells.into_values().collect(),
+        })
+    }
+
+    /// Gross fares and the commission/operator split per operator, net of cancellations.
+    pub async fn get_revenue_report(&self, from: Option<&str>, to: Option<&str>, operator: Option<&str>) -> Result<RevenueReport, AppError> {
+        let mut travel_date = Document::new();
+        if let Some(from) = from {
+            travel_date.insert("$gte", from);
+        }
+        if let Some(to) = to {
+            travel_date.insert("$lte", to);
+        }
+        let mut filter = if travel_date.is_empty() { doc! {} } else { doc! { "travel_date": travel_date } };
+        if let Some(operator) = operator {
+            filter.insert("operator", operator);
+        }
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": "$operator",
+                "bookings": { "$sum": { "$cond": ["$reversal", -1, 1] } },
+                "gross": { "$sum": "$fare" },
+                "commission": { "$sum": "$commission" },
+                "operator_share": { "$sum": "$operator_share" },
+            } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let mut groups = self.collection_for::<Document>("booking_splits", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+
+        let mut operators = Vec::new();
+        let mut totals = OperatorRevenue { operator: "all".to_string(), ..Default::default() };
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let revenue = OperatorRevenue {
+                operator: group.get_str("_id").unwrap_or_default().to_string(),
+                bookings: group.get_i32("bookings").map(i64::from).unwrap_or(0),
+                gross: round_money(group.get_f64("gross").unwrap_or(0.0)),
+                commission: round_money(group.get_f64("commission").unwrap_or(0.0)),
+                operator_share: round_money(group.get_f64("operator_share").unwrap_or(0.0)),
+            };
+            totals.bookings += revenue.bookings;
+            totals.gross += revenue.gross;
+            totals.commission += revenue.commission;
+            totals.operator_share += revenue.operator_share;
+            operators.push(revenue);
+        }
+        totals.gross = round_money(totals.gross);
+        totals.commission = round_money(totals.commission);
+        totals.operator_share = round_money(totals.operator_share);
+
+        Ok(RevenueReport {
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+            operators,
+            totals,
+        })
+    }
+
+    /// Searches and bookings per request country over the last `days` days.
+    pub async fn get_country_report(&self, days: i64) -> Result<Vec<CountryActivity>, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let mut countries: BTreeMap<String, CountryActivity> = BTreeMap::new();
+
+        let pipeline = vec![
+            doc! { "$match": { "created_at": { "$gte": since } } },
+            doc! { "$group": { "_id": "$country", "searches": { "$sum": 1 } } },
+        ];
+        let mut groups = self.get_search_log_reporting_collection().aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            country_entry(&mut countries, group.get_str("_id").ok()).searches += group.get_i32("searches").map(i64::from).unwrap_or(0);
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "booking_date": { "$gte": since }, "status": { "$ne": "Cancelled" } } },
+            doc! { "$group": {
+                "_id": "$country",
+                "bookings": { "$sum": 1 },
+                "held_for_review": { "$sum": { "$cond": [{ "$eq": ["$status", "PendingReview"] }, 1, 0] } },
+            } },
+        ];
+        let mut groups = self.collection_for::<Document>("bookings", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let activity = country_entry(&mut countries, group.get_str("_id").ok());
+            activity.bookings += group.get_i32("bookings").map(i64::from).unwrap_or(0);
+            activity.held_for_review += group.get_i32("held_for_review").map(i64::from).unwrap_or(0);
+        }
+
+        Ok(countries.into_values().collect())
+    }
+}
+
+fn cell<'a>(cells: &'a mut BTreeMap<CellKey, HeatmapCell>, from: &str, to: &str, day: String, slot: String) -> &'a mut HeatmapCell {
+    cells
+        .entry((from.to_string(), to.to_string(), day.clone(), slot.clone()))
+        .or_insert_with(|| HeatmapCell {
+            from: from.to_string(),
+            to: to.to_string(),
+            day_of_week: day,
+            time_slot: slot,
+            bookings: 0,
+            searches: 0,
+            zero_result_searches: 0,
+        })
+}
+
+fn count(group: &Document) -> i64 {
+    group.get_i32("count").map(i64::from).unwrap_or(0)
+}
+
+fn country_entry<'a>(countries: &'a mut BTreeMap<String, CountryActivity>, country: Option<&str>) -> &'a mut CountryActivity {
+    let country = country.unwrap_or("unknown").to_string();
+    countries
+        .entry(country.clone())
+        .or_insert_with(|| CountryActivity { country, ..Default::default() })
+}
@@ -0,0 +1,82 @@
+use futures::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::outbox::OutboxMessage;
+use crate::models::trip::{AnnouncementRequest, TripAnnouncement};
+
+impl MongoDB {
+    fn get_trip_announcements_collection(&self) -> Collection<TripAnnouncement> {
+        self.database().collection("trip_announcements")
+    }
+
+    /// Stores the announcement and pushes it to every booked passenger in-app and by email.
+    pub async fn post_trip_announcement(&self, bus_id: &str, travel_date: &str, req: &AnnouncementRequest, author: &str) -> Result<(TripAnnouncement, usize), AppError> {
+        let message = req.message.trim();
+        if message.is_empty() || message.len() > 500 {
+            return Err("Announcement must be between 1 and 500 characters".into());
+        }
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let mut announcement = TripAnnouncement {
+            id: None,
+            bus_id: bus_oid,
+            travel_date: travel_date.to_string(),
+            message: message.to_string(),
+            author: author.to_string(),
+            created_at: bson::DateTime::now(),
+        };
+        let result = self.get_trip_announcements_collection().insert_one(&announcement, None).await?;
+        announcement.id = result.inserted_id.as_object_id();
+
+        let user_ids: Vec<ObjectId> = self.get_bookings_collection()
+            .distinct("user_id", doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": { "$ne": "Cancelled" } }, None)
+            .await?
+            .into_iter()
+            .filter_map(|id| id.as_object_id())
+            .collect();
+
+        let title = format!("Trip update: {} → {} on {}", bus.route.from, bus.route.to, travel_date);
+        let mut users = self.get_users_collection().find(doc! { "_id": { "$in": &user_ids } }, None).await?;
+        while let Some(user) = users.next().await {
+            let user = user?;
+            let Some(user_id) = user.id else {
+                continue;
+            };
+            // One passenger's delivery failing shouldn't stop the rest
+            if let Err(e) = self.notify_user(user_id, &title, message).await {
+                error!("Failed to notify {} of trip announcement: {}", user_id, e);
+            }
+            let email = OutboxMessage::new("email", &user.email, Some(title.clone()), format!(
+                "Hi {},\n\n{}\n\n{} ({}), departing {} on {}.",
+                user.username, message, bus.bus_number, bus.bus_type, bus.route.departure_time, travel_date
+            ));
+            if let Err(e) = self.enqueue_outbox(email).await {
+                error!("Failed to queue trip announcement email for {}: {}", user_id, e);
+            }
+        }
+
+        Ok((announcement, user_ids.len()))
+    }
+
+    pub async fn get_trip_announcements(&self, bus_id: ObjectId, travel_date: &str) -> Result<Vec<TripAnnouncement>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_trip_announcements_collection().find(
+            doc! { "bus_id": bus_id, "travel_date": travel_date },
+            find_options
+        ).await?;
+
+        let mut announcements = Vec::new();
+        while let Some(result) = cursor.next().await {
+            announcements.push(result?);
+        }
+        Ok(announcements)
+    }
+}
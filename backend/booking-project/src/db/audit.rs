@@ -0,0 +1,47 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::audit::AuditEntry;
+
+impl MongoDB {
+    fn get_audit_log_collection(&self) -> Collection<AuditEntry> {
+        self.database().collection("audit_log")
+    }
+
+    pub async fn record_audit(&self, action: &str, actor: &str, subject: &str, details: Option<String>) -> Result<(), AppError> {
+        let entry = AuditEntry {
+            id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            subject: subject.to_string(),
+            details,
+            at: bson::DateTime::now(),
+        };
+        self.get_audit_log_collection().insert_one(entry, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_audit_log(&self, action: Option<&str>, subject: Option<&str>) -> Result<Vec<AuditEntry>, AppError> {
+        let mut filter = doc! {};
+        if let Some(action) = action {
+            filter.insert("action", action);
+        }
+        if let Some(subject) = subject {
+            filter.insert("subject", subject);
+        }
+        let find_options = FindOptions::builder().sort(doc! { "at": -1 }).limit(500).build();
+        let mut cursor = self.get_audit_log_collection().find(filter, find_options).await?;
+
+        let mut entries = Vec::new();
+        while let Some(result) = cursor.next().await {
+            entries.push(result?);
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,541 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{IndexOptions, InsertManyOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use serde::Deserialize;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::InventoryConfig;
+use crate::models::bus::{numbered_seats, HeadcountSummary, TripSeat, OPEN_SEATING, SEAT_AVAILABLE, SEAT_BOOKED, SEAT_CHARTERED, SEAT_HELD};
+use crate::models::trip::TripVehicle;
+use crate::models::{Bus, Seat};
+
+#[derive(Deserialize)]
+struct SeatsLeft {
+    #[serde(rename = "_id")]
+    bus_id: ObjectId,
+    seats_left: i32,
+}
+
+/// Matches the availability document of a trip sold by headcount while it has a place left.
+pub(super) fn free_place_filter(bus_id: ObjectId, travel_date: &str) -> Document {
+    doc! { "bus_id": bus_id, "travel_date": travel_date, "$expr": { "$lt": ["$headcount", "$capacity"] } }
+}
+
+/// Matches a seat nobody has, counting holds that have run out as free.
+pub(super) fn free_seat_filter(now: bson::DateTime) -> Document {
+    doc! { "$or": [
+        { "state": SEAT_AVAILABLE },
+        { "state": SEAT_HELD, "hold_expiry": { "$lte": now } },
+    ] }
+}
+
+// Row number first, so "2A" comes before "10A"
+fn seat_order(seat_number: &str) -> (i32, String) {
+    let digits = seat_number.chars().take_while(char::is_ascii_digit).count();
+    (seat_number[..digits].parse().unwrap_or(i32::MAX), seat_number[digits..].to_string())
+}
+
+impl MongoDB {
+    pub(super) fn get_trip_seats_collection(&self) -> Collection<TripSeat> {
+        self.database().collection("trip_seats")
+    }
+
+    // One availability document per bus and date, one seat document per seat on it
+    pub async fn ensure_seat_availability_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "bus_id": 1, "travel_date": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_seat_availability_collection().create_index(index, None).await?;
+
+        let seats = self.get_trip_seats_collection();
+        let index = IndexModel::builder()
+            .keys(doc! { "bus_id": 1, "travel_date": 1, "seat_number": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        seats.create_index(index, None).await?;
+        // Seats-left counts for a whole date group by bus without touching the documents
+        let index = IndexModel::builder()
+            .keys(doc! { "travel_date": 1, "state": 1, "bus_id": 1 })
+            .build();
+        seats.create_index(index, None).await?;
+
+        self.migrate_embedded_seats().await?;
+        Ok(())
+    }
+
+    /// Moves seat maps stored as an embedded `seats` array into per-seat documents.
+    /// Taken seats with a live hold become held until it runs out, the rest booked.
+    /// Safe to run again: seats already moved are left as they are.
+    pub async fn migrate_embedded_seats(&self) -> Result<u64, AppError> {
+        let availability = self.get_seat_availability_collection().clone_with_type::<Document>();
+        let mut cursor = availability.find(doc! { "seats": { "$exists": true } }, None).await?;
+        let mut migrated = 0;
+        while let Some(result) = cursor.next().await {
+            let trip = result?;
+            let (Ok(id), Ok(bus_id), Ok(travel_date)) = (trip.get_object_id("_id"), trip.get_object_id("bus_id"), trip.get_str("travel_date")) else {
+                continue;
+            };
+
+            let mut hold_expiries = HashMap::new();
+            let mut holds = self.database().collection::<Document>("seat_holds").find(
+                doc! { "bus_id": bus_id, "travel_date": travel_date, "expires_at": { "$gt": bson::DateTime::now() } },
+                None
+            ).await?;
+            while let Some(hold) = holds.next().await {
+                let hold = hold?;
+                if let (Ok(seat), Ok(expires_at)) = (hold.get_str("seat_number"), hold.get_datetime("expires_at")) {
+                    hold_expiries.insert(seat.to_string(), *expires_at);
+                }
+            }
+
+            let seats: Vec<TripSeat> = trip.get_array("seats").map(|s| s.as_slice()).unwrap_or_default()
+                .iter()
+                .filter_map(|s| s.as_document())
+                .filter_map(|s| {
+                    let seat_number = s.get_str("seat_number").ok()?.to_string();
+                    let hold_expiry = hold_expiries.get(&seat_number).copied();
+                    let state = match (s.get_bool("is_available").unwrap_or(true), hold_expiry) {
+                        (true, _) => SEAT_AVAILABLE,
+                        (false, Some(_)) => SEAT_HELD,
+                        (false, None) => SEAT_BOOKED,
+                    };
+                    Some(TripSeat {
+                        id: None,
+                        bus_id,
+                        travel_date: travel_date.to_string(),
+                        hold_expiry: hold_expiry.filter(|_| state == SEAT_HELD),
+                        state: state.to_string(),
+                        seat_number,
+                    })
+                })
+                .collect();
+            self.insert_trip_seats(seats).await?;
+            availability.update_one(
+                doc! { "_id": id },
+                doc! { "$unset": { "seats": "", "seats_left": "" } },
+                None
+            ).await?;
+            migrated += 1;
+        }
+        if migrated > 0 {
+            info!("Moved the seat maps of {} trip(s) to per-seat documents", migrated);
+        }
+        Ok(migrated)
+    }
+
+    async fn insert_trip_seats(&self, seats: Vec<TripSeat>) -> Result<(), AppError> {
+        if seats.is_empty() {
+            return Ok(());
+        }
+        let options = InsertManyOptions::builder().ordered(false).build();
+        match self.get_trip_seats_collection().insert_many(seats, options).await {
+            Ok(_) => Ok(()),
+            // Seats someone else created first are already right
+            Err(e) if super::locks::is_duplicate_key(&e) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Creates the free seats of a trip that don't exist yet.
+    pub async fn create_trip_seats(&self, bus_id: ObjectId, travel_date: &str, seat_numbers: Vec<String>) -> Result<(), AppError> {
+        let seats = seat_numbers.into_iter()
+            .map(|seat_number| TripSeat {
+                id: None,
+                bus_id,
+                travel_date: travel_date.to_string(),
+                seat_number,
+                state: SEAT_AVAILABLE.to_string(),
+                hold_expiry: None,
+            })
+            .collect();
+        self.insert_trip_seats(seats).await
+    }
+
+    pub(super) async fn trip_seats_exist(&self, bus_id: ObjectId, travel_date: &str) -> Result<bool, AppError> {
+        let seat = self.get_trip_seats_collection()
+            .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date }, None)
+            .await?;
+        Ok(seat.is_some())
+    }
+
+    /// Every seat on the trip in seat order; empty until the first seat of the date is taken.
+    pub async fn get_trip_seats(&self, bus_id: ObjectId, travel_date: &str) -> Result<Vec<TripSeat>, AppError> {
+        let mut cursor = self.get_trip_seats_collection()
+            .find(doc! { "bus_id": bus_id, "travel_date": travel_date }, None)
+            .await?;
+        let mut seats = Vec::new();
+        while let Some(result) = cursor.next().await {
+            seats.push(result?);
+        }
+        seats.sort_by_cached_key(|s| seat_order(&s.seat_number));
+        Ok(seats)
+    }
+
+    /// The seat map as clients see it; trips nobody has booked yet are all free.
+    pub async fn get_bus_seats(&self, bus_id: &str, date: &str) -> Result<Vec<Seat>, AppError> {
+        let object_id = self.string_to_id(bus_id)?;
+        let now = bson::DateTime::now();
+        let seats = self.get_trip_seats(object_id, date).await?;
+        if !seats.is_empty() {
+            return Ok(seats.iter()
+                .map(|s| Seat { seat_number: s.seat_number.clone(), is_available: s.is_available(now) })
+                .collect());
+        }
+
+        let seat_numbers = match self.get_trip_vehicle(object_id, date).await? {
+            Some(vehicle) => numbered_seats(vehicle.total_seats),
+            None => match self.get_bus(bus_id).await? {
+                Some(bus) => bus.seat_labels(),
+                None => return Ok(vec![]),
+            },
+        };
+        Ok(seat_numbers.into_iter()
+            .map(|seat_number| Seat { seat_number, is_available: true })
+            .collect())
+    }
+
+    /// Remaining seats per bus on `travel_date`; buses with no bookings yet are absent.
+    pub async fn get_seats_left(&self, travel_date: &str, bus_id: Option<ObjectId>) -> Result<HashMap<ObjectId, i32>, AppError> {
+        let mut filter = doc! { "travel_date": travel_date };
+        if let Some(bus_id) = bus_id {
+            filter.insert("bus_id", bus_id);
+        }
+        let now = bson::DateTime::now();
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": "$bus_id",
+                "seats_left": { "$sum": { "$cond": [{ "$or": [
+                    { "$eq": ["$state", SEAT_AVAILABLE] },
+                    { "$and": [{ "$eq": ["$state", SEAT_HELD] }, { "$lte": ["$hold_expiry", now] }] },
+                ] }, 1, 0] } },
+            } },
+        ];
+        let mut cursor = self.get_trip_seats_collection().aggregate(pipeline, None).await?;
+
+        let mut seats_left = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            let counter: SeatsLeft = bson::from_document(result?)?;
+            seats_left.insert(counter.bus_id, counter.seats_left);
+        }
+
+        // Trips sold by headcount keep a counter instead of seats
+        let mut filter = doc! { "travel_date": travel_date, "headcount": { "$exists": true } };
+        if let Some(bus_id) = bus_id {
+            filter.insert("bus_id", bus_id);
+        }
+        let mut cursor = self.get_seat_availability_collection().find(filter, None).await?;
+        while let Some(result) = cursor.next().await {
+            let trip = result?;
+            let summary = HeadcountSummary::new(trip.capacity.unwrap_or_default(), trip.headcount.unwrap_or_default());
+            seats_left.insert(trip.bus_id, summary.available);
+        }
+        Ok(seats_left)
+    }
+
+    /// Places booked and left on a trip sold by headcount; trips nobody has booked yet are all free.
+    pub async fn get_trip_headcount(&self, bus: &Bus, travel_date: &str) -> Result<HeadcountSummary, AppError> {
+        let availability = match bus.id {
+            Some(bus_id) => self.get_seat_availability_collection()
+                .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date }, None)
+                .await?,
+            None => None,
+        };
+        let capacity = availability.as_ref()
+            .and_then(|a| a.capacity.or(a.vehicle.as_ref().map(|v| v.total_seats)))
+            .unwrap_or(bus.total_seats);
+        let booked = availability.and_then(|a| a.headcount).unwrap_or_default();
+        Ok(HeadcountSummary::new(capacity, booked))
+    }
+
+    /// Starts counting places on a trip sold by headcount, up to what the vehicle running it seats.
+    /// Trips already counting are left alone.
+    pub(super) async fn open_headcount(&self, bus: &Bus, travel_date: &str) -> Result<(), AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let options = UpdateOptions::builder().upsert(true).build();
+        let result = self.get_seat_availability_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date },
+            vec![doc! { "$set": {
+                "headcount": { "$ifNull": ["$headcount", 0] },
+                "capacity": { "$ifNull": ["$capacity", { "$ifNull": ["$vehicle.total_seats", bus.total_seats] }] },
+            } }],
+            options
+        ).await;
+        match result {
+            Ok(_) => Ok(()),
+            // Someone else opened it first
+            Err(e) if super::locks::is_duplicate_key(&e) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sizes a trip sold by headcount to a different vehicle. Places already booked are kept,
+    /// even past the new capacity.
+    pub(super) async fn set_trip_capacity(&self, bus: &Bus, travel_date: &str, capacity: i32) -> Result<(), AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        self.open_headcount(bus, travel_date).await?;
+        self.get_seat_availability_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date },
+            doc! { "$set": { "capacity": capacity } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    // Takes one place on a trip sold by headcount; false when it is full
+    async fn take_place(&self, bus: &Bus, travel_date: &str) -> Result<bool, AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        self.open_headcount(bus, travel_date).await?;
+        let result = self.get_seat_availability_collection().update_one(
+            free_place_filter(bus_id, travel_date),
+            doc! { "$inc": { "headcount": 1 } },
+            None
+        ).await?;
+        Ok(result.modified_count == 1)
+    }
+
+    /// Takes every seat of the trip off sale for a charter. Fails, leaving the trip as it was,
+    /// when any of them is already booked or held.
+    pub(super) async fn block_trip(&self, bus: &Bus, travel_date: &str) -> Result<(), AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        if InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) {
+            self.open_headcount(bus, travel_date).await?;
+            let filled = self.get_seat_availability_collection().update_one(
+                doc! { "bus_id": bus_id, "travel_date": travel_date, "headcount": 0 },
+                vec![doc! { "$set": { "headcount": "$capacity", "chartered": true } }],
+                None
+            ).await?;
+            if filled.modified_count == 0 {
+                return Err(AppError::conflict("Places on this trip are already booked"));
+            }
+            return Ok(());
+        }
+
+        if !self.trip_seats_exist(bus_id, travel_date).await? {
+            self.create_trip_seats(bus_id, travel_date, bus.seat_labels()).await?;
+        }
+        let seats = self.get_trip_seats_collection();
+        let mut filter = free_seat_filter(bson::DateTime::now());
+        filter.insert("bus_id", bus_id);
+        filter.insert("travel_date", travel_date);
+        seats.update_many(filter, doc! { "$set": { "state": SEAT_CHARTERED }, "$unset": { "hold_expiry": "" } }, None).await?;
+        let taken = seats.count_documents(
+            doc! { "bus_id": bus_id, "travel_date": travel_date, "state": { "$ne": SEAT_CHARTERED } },
+            None
+        ).await?;
+        if taken > 0 {
+            self.release_trip_block(bus, travel_date).await?;
+            return Err(AppError::conflict(format!("{} seat(s) on this trip are already taken", taken)));
+        }
+        Ok(())
+    }
+
+    /// Puts a chartered trip's seats back on sale.
+    pub(super) async fn release_trip_block(&self, bus: &Bus, travel_date: &str) -> Result<(), AppError> {
+        let Some(bus_id) = bus.id else {
+            return Ok(());
+        };
+        self.get_seat_availability_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date, "chartered": true },
+            doc! { "$set": { "headcount": 0 }, "$unset": { "chartered": "" } },
+            None
+        ).await?;
+        self.get_trip_seats_collection().update_many(
+            doc! { "bus_id": bus_id, "travel_date": travel_date, "state": SEAT_CHARTERED },
+            doc! { "$set": { "state": SEAT_AVAILABLE } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Whether the seat exists on the vehicle running this trip, which may have been swapped.
+    pub async fn trip_has_seat(&self, bus: &Bus, travel_date: &str, seat_number: &str) -> Result<bool, AppError> {
+        let Some(bus_id) = bus.id else {
+            return Ok(false);
+        };
+        Ok(match self.get_trip_vehicle(bus_id, travel_date).await? {
+            Some(vehicle) => seat_number.parse::<i32>().is_ok_and(|n| n >= 1 && n <= vehicle.total_seats),
+            None => bus.has_seat(seat_number),
+        })
+    }
+
+    /// The swapped-in vehicle running this trip, if it isn't the scheduled bus.
+    pub async fn get_trip_vehicle(&self, bus_id: ObjectId, travel_date: &str) -> Result<Option<TripVehicle>, AppError> {
+        let availability = self.get_seat_availability_collection()
+            .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date }, None)
+            .await?;
+        Ok(availability.and_then(|a| a.vehicle))
+    }
+
+    /// Moves one free seat to `update`'s state, creating the trip's seats on its first claim.
+    async fn claim_seat(&self, bus: &Bus, travel_date: &str, seat_number: &str, update: Document) -> Result<bool, AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let collection = self.get_trip_seats_collection();
+        let mut filter = free_seat_filter(bson::DateTime::now());
+        filter.insert("bus_id", bus_id);
+        filter.insert("travel_date", travel_date);
+        filter.insert("seat_number", seat_number);
+
+        let result = collection.update_one(filter.clone(), update.clone(), None).await?;
+        if result.matched_count == 1 {
+            return Ok(true);
+        }
+        // Seats are created on demand unless the warm-up got there first
+        if self.trip_seats_exist(bus_id, travel_date).await? {
+            return Ok(false);
+        }
+        self.create_trip_seats(bus_id, travel_date, bus.seat_labels()).await?;
+        Ok(collection.update_one(filter, update, None).await?.matched_count == 1)
+    }
+
+    /// Books the seat; false if someone else has it.
+    pub async fn reserve_seat(&self, bus: &Bus, travel_date: &str, seat_number: &str) -> Result<bool, AppError> {
+        if seat_number == OPEN_SEATING {
+            return self.take_place(bus, travel_date).await;
+        }
+        self.claim_seat(bus, travel_date, seat_number, doc! {
+            "$set": { "state": SEAT_BOOKED },
+            "$unset": { "hold_expiry": "" },
+        }).await
+    }
+
+    /// Holds the seat until `expires_at`; false if someone else has it.
+    pub async fn hold_seat(&self, bus: &Bus, travel_date: &str, seat_number: &str, expires_at: bson::DateTime) -> Result<bool, AppError> {
+        self.claim_seat(bus, travel_date, seat_number, doc! {
+            "$set": { "state": SEAT_HELD, "hold_expiry": expires_at },
+        }).await
+    }
+
+    /// Frees a booked seat, or a place on a trip sold by headcount.
+    pub async fn release_seat(&self, bus_id: ObjectId, travel_date: &str, seat_number: &str) -> Result<(), AppError> {
+        if seat_number == OPEN_SEATING {
+            self.get_seat_availability_collection().update_one(
+                doc! { "bus_id": bus_id, "travel_date": travel_date, "headcount": { "$gt": 0 } },
+                doc! { "$inc": { "headcount": -1 } },
+                None
+            ).await?;
+            return Ok(());
+        }
+        self.get_trip_seats_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date, "seat_number": seat_number, "state": SEAT_BOOKED },
+            doc! { "$set": { "state": SEAT_AVAILABLE } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Frees a held seat, but only for the hold ending at `expires_at`: once it ran out the
+    /// seat may already belong to someone else.
+    pub async fn release_held_seat(&self, bus_id: ObjectId, travel_date: &str, seat_number: &str, expires_at: bson::DateTime) -> Result<(), AppError> {
+        self.get_trip_seats_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date, "seat_number": seat_number, "state": SEAT_HELD, "hold_expiry": expires_at },
+            doc! { "$set": { "state": SEAT_AVAILABLE }, "$unset": { "hold_expiry": "" } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Puts a trip on a vehicle with `total_seats`: seats beyond it are dropped and missing ones created free.
+    pub async fn resize_trip_seats(&self, bus: &Bus, travel_date: &str, total_seats: i32) -> Result<(), AppError> {
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        // Seats that were never claimed still need to exist before the trip's first one is
+        if !self.trip_seats_exist(bus_id, travel_date).await? {
+            self.create_trip_seats(bus_id, travel_date, bus.seat_labels()).await?;
+        }
+        let beyond: Vec<String> = self.get_trip_seats(bus_id, travel_date).await?
+            .into_iter()
+            .filter(|s| !s.seat_number.parse::<i32>().is_ok_and(|n| n >= 1 && n <= total_seats))
+            .map(|s| s.seat_number)
+            .collect();
+        if !beyond.is_empty() {
+            self.get_trip_seats_collection().delete_many(
+                doc! { "bus_id": bus_id, "travel_date": travel_date, "seat_number": { "$in": beyond } },
+                None
+            ).await?;
+        }
+        self.create_trip_seats(bus_id, travel_date, numbered_seats(total_seats)).await?;
+        Ok(())
+    }
+
+    /// Brings the seats of the bus's trips from `from_date` on in line with the seats it has
+    /// after an edit. Booked seats are left alone, and so are trips running on a swapped-in vehicle.
+    pub(super) async fn sync_trip_seats(&self, bus: &Bus, from_date: &str) -> Result<(), AppError> {
+        let Some(bus_id) = bus.id else {
+            return Ok(());
+        };
+        let seats = self.get_trip_seats_collection();
+        let dates = seats.distinct("travel_date", doc! { "bus_id": bus_id, "travel_date": { "$gte": from_date } }, None).await?;
+        let seat_numbers = bus.seat_labels();
+        for travel_date in dates.iter().filter_map(|d| d.as_str()) {
+            if self.get_trip_vehicle(bus_id, travel_date).await?.is_some() {
+                continue;
+            }
+            seats.delete_many(
+                doc! { "bus_id": bus_id, "travel_date": travel_date, "seat_number": { "$nin": &seat_numbers }, "state": { "$nin": [SEAT_BOOKED, SEAT_CHARTERED] } },
+                None
+            ).await?;
+            self.create_trip_seats(bus_id, travel_date, seat_numbers.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the seats of every departure on `routes` (all of them when empty) from today through
+    /// `days` ahead, then reads each date's counts back so their index pages are in the database's
+    /// cache before customers ask. Returns how many trips needed seats.
+    pub async fn warm_up_availability(&self, routes: &[(String, String)], days: i64) -> Result<usize, AppError> {
+        let inventory = InventoryConfig::from_env();
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(result) = cursor.next().await {
+            let bus = result?;
+            let popular = routes.is_empty() || routes.iter().any(|(from, to)| {
+                bus.route.from.eq_ignore_ascii_case(from) && bus.route.to.eq_ignore_ascii_case(to)
+            });
+            // Trips sold by headcount have no seats to create
+            let numbered = !inventory.sells_by_headcount(&bus.bus_type);
+            if popular && numbered && bus.id.is_some() {
+                buses.push(bus);
+            }
+        }
+
+        let today = chrono::Utc::now().with_timezone(&crate::models::bus::local_offset()).date_naive();
+        let mut created = 0;
+        for offset in 0..days.max(0) {
+            let travel_date = (today + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+            for bus in &buses {
+                let Some(bus_id) = bus.id else { continue };
+                if self.trip_seats_exist(bus_id, &travel_date).await? {
+                    continue;
+                }
+                let seat_numbers = match self.get_trip_vehicle(bus_id, &travel_date).await? {
+                    Some(vehicle) => numbered_seats(vehicle.total_seats),
+                    None => bus.seat_labels(),
+                };
+                self.create_trip_seats(bus_id, &travel_date, seat_numbers).await?;
+                created += 1;
+            }
+            self.get_seats_left(&travel_date, None).await?;
+        }
+        Ok(created)
+    }
+
+    /// Records the vehicle running a trip on its availability document.
+    pub async fn set_trip_vehicle(&self, bus_id: ObjectId, travel_date: &str, vehicle: &TripVehicle) -> Result<(), AppError> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_seat_availability_collection().update_one(
+            doc! { "bus_id": bus_id, "travel_date": travel_date },
+            doc! { "$set": { "vehicle": bson::to_bson(vehicle)? } },
+            options
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::FindOptions,
+    IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::{BookingPage, BookingPageQuery, BookingSearchQuery, MAX_PAGE_SIZE};
+use crate::models::tenancy::Tenant;
+
+impl MongoDB {
+    // Newest first; `_id` breaks ties between bookings made in the same millisecond
+    pub async fn ensure_booking_indexes(&self) -> Result<(), AppError> {
+        let indexes = vec![
+            IndexModel::builder().keys(doc! { "user_id": 1, "booking_date": -1, "_id": -1 }).build(),
+            IndexModel::builder().keys(doc! { "booking_date": -1, "_id": -1 }).build(),
+            IndexModel::builder().keys(doc! { "bus_id": 1, "travel_date": 1, "booking_date": -1, "_id": -1 }).build(),
+        ];
+        self.get_bookings_collection().create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// A user's bookings, newest first. Without `after` or `limit` the whole history is returned.
+    pub async fn get_user_bookings(&self, user_id: &str, query: &BookingPageQuery) -> Result<BookingPage, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let limit = match (&query.after, query.limit) {
+            (None, None) => None,
+            (_, limit) => Some(limit.unwrap_or(MAX_PAGE_SIZE)),
+        };
+        self.find_booking_page(doc! { "user_id": user_oid }, query.after.as_deref(), limit).await
+    }
+
+    pub async fn search_bookings(&self, query: &BookingSearchQuery, tenant: &Tenant) -> Result<BookingPage, AppError> {
+        let mut filter = doc! {};
+        if let Some(status) = &query.status {
+            filter.insert("status", status);
+        }
+        if let Some(bus_id) = &query.bus_id {
+            filter.insert("bus_id", self.string_to_id(bus_id)?);
+        }
+        if let Some(user_id) = &query.user_id {
+            filter.insert("user_id", self.string_to_id(user_id)?);
+        }
+        if let Some(travel_date) = &query.travel_date {
+            filter.insert("travel_date", travel_date);
+        }
+        let filter = self.scope_bookings_filter(tenant, filter).await?;
+        self.find_booking_page(filter, query.after.as_deref(), Some(query.limit.unwrap_or(MAX_PAGE_SIZE))).await
+    }
+
+    async fn find_booking_page(&self, mut filter: Document, after: Option<&str>, limit: Option<i64>) -> Result<BookingPage, AppError> {
+        if let Some(after) = after {
+            let cursor_booking = self.get_booking(after).await?.ok_or("Invalid cursor")?;
+            let (booking_date, id) = (cursor_booking.booking_date, cursor_booking.id.ok_or("Invalid cursor")?);
+            filter.insert("$or", vec![
+                doc! { "booking_date": { "$lt": booking_date } },
+                doc! { "booking_date": booking_date, "_id": { "$lt": id } },
+            ]);
+        }
+
+        let limit = limit.map(|l| l.clamp(1, MAX_PAGE_SIZE));
+        // Fetch one extra to know whether another page follows
+        let options = FindOptions::builder()
+            .sort(doc! { "booking_date": -1, "_id": -1 })
+            .limit(limit.map(|l| l + 1))
+            .build();
+        let mut cursor = self.get_bookings_collection().find(filter, options).await?;
+
+        let mut bookings = Vec::new();
+        while let Some(result) = cursor.next().await {
+            bookings.push(result?);
+        }
+
+        let next_cursor = match limit {
+            Some(limit) if bookings.len() as i64 > limit => {
+                bookings.truncate(limit as usize);
+                bookings.last().and_then(|b| b.id).map(|id| id.to_hex())
+            }
+            _ => None,
+        };
+        Ok(BookingPage { bookings, next_cursor })
+    }
+}
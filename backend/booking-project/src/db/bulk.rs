@@ -0,0 +1,45 @@
+use mongodb::{
+    error::ErrorKind,
+    options::InsertManyOptions,
+    Collection,
+};
+use serde::Serialize;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bulk::{BulkInsertError, BulkInsertReport};
+
+const BATCH_SIZE: usize = 500;
+
+impl MongoDB {
+    /// Inserts documents in unordered batches so one bad row doesn't stop the rest of the load.
+    pub(super) async fn insert_batched<T: Serialize>(&self, collection: &Collection<T>, documents: Vec<T>) -> Result<BulkInsertReport, AppError> {
+        let mut report = BulkInsertReport::default();
+        let mut offset = 0;
+        let mut documents = documents.into_iter().peekable();
+
+        while documents.peek().is_some() {
+            let batch: Vec<T> = documents.by_ref().take(BATCH_SIZE).collect();
+            let size = batch.len();
+            let options = InsertManyOptions::builder().ordered(false).build();
+
+            match collection.insert_many(batch, options).await {
+                Ok(result) => report.inserted += result.inserted_ids.len(),
+                Err(e) => match e.kind.as_ref() {
+                    ErrorKind::BulkWrite(failure) if failure.write_concern_error.is_none() => {
+                        let errors = failure.write_errors.as_deref().unwrap_or_default();
+                        report.inserted += size - errors.len();
+                        report.failed.extend(errors.iter().map(|err| BulkInsertError {
+                            index: offset + err.index,
+                            message: err.message.clone(),
+                        }));
+                    }
+                    _ => return Err(e.into()),
+                },
+            }
+            offset += size;
+        }
+
+        Ok(report)
+    }
+}
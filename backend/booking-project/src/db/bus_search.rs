@@ -0,0 +1,52 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::doc,
+    options::{Collation, CollationStrength, FindOptions, IndexOptions},
+    IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ReadWorkload;
+use crate::models::bus::{departure_sort_key, BusSearchQuery};
+use crate::models::Bus;
+
+// Town names and bus types match whatever case the client sends
+fn case_insensitive() -> Collation {
+    Collation::builder().locale("en").strength(CollationStrength::Secondary).build()
+}
+
+impl MongoDB {
+    pub async fn ensure_bus_search_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "route.from": 1, "route.to": 1, "bus_type": 1 })
+            .options(IndexOptions::builder().collation(case_insensitive()).build())
+            .build();
+        self.get_buses_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    /// Buses running `from` to `to`, optionally of one type, by departure time. With a date,
+    /// buses not scheduled that day and trips cancelled that day are left out.
+    pub async fn search_buses(&self, query: &BusSearchQuery) -> Result<Vec<Bus>, AppError> {
+        let mut filter = doc! { "route.from": query.from.trim(), "route.to": query.to.trim() };
+        if let Some(bus_type) = query.bus_type.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            filter.insert("bus_type", bus_type);
+        }
+        let options = FindOptions::builder().collation(case_insensitive()).build();
+        let mut cursor = self.collection_for::<Bus>("buses", ReadWorkload::Search).find(filter, options).await?;
+
+        let mut buses = Vec::new();
+        while let Some(bus) = cursor.next().await {
+            buses.push(bus?);
+        }
+        if let Some(date) = &query.date {
+            buses = self.buses_running_on(buses, date).await?;
+            let bus_ids: Vec<_> = buses.iter().filter_map(|b| b.id).collect();
+            let statuses = self.get_trip_statuses(&bus_ids, date).await?;
+            buses.retain(|b| b.id.and_then(|id| statuses.get(&id)).is_none_or(|s| s.status != "cancelled"));
+        }
+        buses.sort_by_cached_key(|b| departure_sort_key(&b.route.departure_time));
+        Ok(buses)
+    }
+}
@@ -0,0 +1,345 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::FindOptions,
+    Collection, IndexModel,
+};
+
+use super::fleet::today;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::{CharterConfig, MpesaConfig};
+use crate::models::charter::{
+    charter_deposit, AcceptCharterRequest, Charter, CharterQuote, CharterQuoteRequest, CharterRequest,
+    CHARTER_ACCEPTED, CHARTER_CANCELLED, CHARTER_DECLINED, CHARTER_QUOTED, CHARTER_REQUESTED, CHARTER_RESERVED,
+};
+use crate::models::payment::Payment;
+use crate::models::tenancy::Tenant;
+use crate::models::webhook::WebhookOutcome;
+
+fn millis(time: chrono::DateTime<chrono::Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(time.timestamp_millis())
+}
+
+fn visible_to(tenant: &Tenant, charter: &Charter) -> bool {
+    charter.status == CHARTER_REQUESTED || match &charter.quote {
+        Some(quote) => tenant.owns_operator(&quote.operator),
+        None => *tenant == Tenant::Platform,
+    }
+}
+
+impl MongoDB {
+    fn get_charters_collection(&self) -> Collection<Charter> {
+        self.database().collection("charters")
+    }
+
+    pub async fn ensure_charter_indexes(&self) -> Result<(), AppError> {
+        let charters = self.get_charters_collection();
+        charters.create_index(IndexModel::builder().keys(doc! { "user_id": 1, "created_at": -1 }).build(), None).await?;
+        charters.create_index(IndexModel::builder().keys(doc! { "status": 1, "travel_date": 1 }).build(), None).await?;
+        charters.create_index(IndexModel::builder().keys(doc! { "quote.bus_id": 1, "travel_date": 1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// Asks operators to quote for hiring a whole bus.
+    pub async fn request_charter(&self, user_id: &str, req: &CharterRequest) -> Result<Charter, AppError> {
+        req.validate(&today())?;
+        let user_oid = self.string_to_id(user_id)?;
+        let now = bson::DateTime::now();
+        let mut charter = Charter {
+            id: None,
+            user_id: user_oid,
+            from: req.from.trim().to_string(),
+            to: req.to.trim().to_string(),
+            travel_date: req.travel_date.clone(),
+            passengers: req.passengers,
+            notes: req.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+            status: CHARTER_REQUESTED.to_string(),
+            quote: None,
+            decline_reason: None,
+            created_at: now,
+            updated_at: now,
+            accepted_at: None,
+            reserved_at: None,
+        };
+        let result = self.get_charters_collection().insert_one(&charter, None).await?;
+        charter.id = result.inserted_id.as_object_id();
+        self.notify_admins(
+            "Charter requested",
+            &format!("A group of {} wants a bus from {} to {} on {}", charter.passengers, charter.from, charter.to, charter.travel_date),
+        ).await?;
+        Ok(charter)
+    }
+
+    /// The user's charters, newest first.
+    pub async fn list_user_charters(&self, user_id: &str) -> Result<Vec<Charter>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        self.find_charters(doc! { "user_id": user_oid }).await
+    }
+
+    pub async fn get_user_charter(&self, user_id: &str, charter_id: &str) -> Result<Option<Charter>, AppError> {
+        let charter_oid = self.string_to_id(charter_id)?;
+        let user_oid = self.string_to_id(user_id)?;
+        Ok(self.get_charters_collection().find_one(doc! { "_id": charter_oid, "user_id": user_oid }, None).await?)
+    }
+
+    async fn find_charters(&self, filter: Document) -> Result<Vec<Charter>, AppError> {
+        let mut cursor = self.get_charters_collection().find(
+            filter,
+            FindOptions::builder().sort(doc! { "created_at": -1 }).build()
+        ).await?;
+        let mut charters = Vec::new();
+        while let Some(charter) = cursor.next().await {
+            charters.push(charter?);
+        }
+        Ok(charters)
+    }
+
+    /// Withdraws a charter request before its deposit is taken.
+    pub async fn cancel_charter(&self, user_id: &str, charter_id: &str) -> Result<Charter, AppError> {
+        let charter = self.get_user_charter(user_id, charter_id).await?.ok_or_else(|| AppError::not_found("Charter not found"))?;
+        let cancelled = self.get_charters_collection().update_one(
+            doc! { "_id": charter.id, "status": { "$in": [CHARTER_REQUESTED, CHARTER_QUOTED] } },
+            doc! { "$set": { "status": CHARTER_CANCELLED, "updated_at": bson::DateTime::now() } },
+            None
+        ).await?;
+        if cancelled.modified_count == 0 {
+            return Err("Only charters that haven't been accepted can be cancelled".into());
+        }
+        Ok(Charter { status: CHARTER_CANCELLED.to_string(), ..charter })
+    }
+
+    /// Accepts the operator's quote: the bus's trip that day is taken off sale at once, and the
+    /// customer's phone is prompted for the deposit that reserves it.
+    pub async fn accept_charter(&self, user_id: &str, charter_id: &str, req: &AcceptCharterRequest) -> Result<(Charter, Payment), AppError> {
+        let phone = req.validated_phone()?;
+        let charter = self.get_user_charter(user_id, charter_id).await?.ok_or_else(|| AppError::not_found("Charter not found"))?;
+        match charter.state(chrono::Utc::now()) {
+            "quoted" => {}
+            "quote_expired" => return Err("This quote has expired; ask the operator for a new one".into()),
+            "requested" => return Err("This charter hasn't been quoted yet".into()),
+            _ => return Err(AppError::conflict("This charter can no longer be accepted")),
+        }
+        let quote = charter.quote.clone().ok_or("This charter hasn't been quoted yet")?;
+        let bus = self.get_bus(&quote.bus_id.to_hex()).await?.ok_or_else(|| AppError::conflict("The quoted bus is no longer available"))?;
+
+        // One charter per bus and day: blocking a trip another charter already holds would succeed
+        let lock = format!("charter:{}:{}", quote.bus_id, charter.travel_date);
+        let Some(_guard) = self.try_lock(&lock, Duration::from_secs(30)).await? else {
+            return Err(AppError::conflict("The quoted bus is being booked right now, please try again"));
+        };
+        let other = self.get_charters_collection().find_one(
+            doc! {
+                "_id": { "$ne": charter.id },
+                "quote.bus_id": quote.bus_id,
+                "travel_date": &charter.travel_date,
+                "status": { "$in": [CHARTER_ACCEPTED, CHARTER_RESERVED] },
+            },
+            None
+        ).await?;
+        if other.is_some() {
+            return Err(AppError::conflict("The quoted bus has already been hired for that day"));
+        }
+        self.block_trip(&bus, &charter.travel_date).await?;
+
+        let now = bson::DateTime::now();
+        let charters = self.get_charters_collection();
+        let accepted = charters.update_one(
+            doc! { "_id": charter.id, "status": CHARTER_QUOTED },
+            doc! { "$set": { "status": CHARTER_ACCEPTED, "accepted_at": now, "updated_at": now } },
+            None
+        ).await?;
+        if accepted.modified_count == 0 {
+            self.release_trip_block(&bus, &charter.travel_date).await?;
+            return Err(AppError::conflict("This charter can no longer be accepted"));
+        }
+        let charter = Charter { status: CHARTER_ACCEPTED.to_string(), accepted_at: Some(now), ..charter };
+        match self.initiate_charter_deposit(&charter, phone).await {
+            Ok(payment) => Ok((charter, payment)),
+            Err(e) => {
+                // Nothing was charged, so the quote stands and the bus goes back on sale
+                charters.update_one(
+                    doc! { "_id": charter.id, "status": CHARTER_ACCEPTED },
+                    doc! { "$set": { "status": CHARTER_QUOTED }, "$unset": { "accepted_at": "" } },
+                    None
+                ).await?;
+                self.release_trip_block(&bus, &charter.travel_date).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Reserves the bus once the deposit went through; a failed deposit puts it back on sale and
+    /// leaves the quote to be accepted again while it lasts.
+    pub(super) async fn settle_charter_deposit(&self, charter_id: ObjectId, payment: &Payment, paid: bool, receipt: Option<String>, result: &str) -> Result<WebhookOutcome, AppError> {
+        let charters = self.get_charters_collection();
+        let Some(charter) = charters.find_one(doc! { "_id": charter_id }, None).await? else {
+            return Ok(WebhookOutcome::ignored(format!("Unknown charter {}", charter_id)));
+        };
+        if !paid {
+            self.reopen_charter(&charter).await?;
+            self.notify_user(payment.user_id, "Charter deposit not paid", &format!("Your M-Pesa deposit for the charter on {} didn't go through: {}. You can accept the quote again while it lasts.", charter.travel_date, result)).await?;
+            return Ok(WebhookOutcome::processed(format!("Charter deposit failed: {}", result)));
+        }
+
+        let receipt = receipt.unwrap_or_default();
+        let now = bson::DateTime::now();
+        let reserved = charters.update_one(
+            doc! { "_id": charter_id, "status": CHARTER_ACCEPTED },
+            doc! { "$set": { "status": CHARTER_RESERVED, "reserved_at": now, "updated_at": now } },
+            None
+        ).await?;
+        if reserved.modified_count == 0 {
+            return Ok(WebhookOutcome::processed(format!("Deposit {} arrived for charter {} that is no longer awaiting it; it needs a manual refund", receipt, charter_id)));
+        }
+        info!("Charter {} reserved by deposit {}", charter_id, receipt);
+        let bus_number = charter.quote.as_ref().map(|q| q.bus_number.as_str()).unwrap_or_default();
+        self.notify_user(
+            payment.user_id,
+            "Charter reserved",
+            &format!("We received your KES {:.2} deposit (M-Pesa {}). {} is reserved for your group on {}.", payment.amount, receipt, bus_number, charter.travel_date),
+        ).await?;
+        self.notify_admins("Charter reserved", &format!("{} is hired from {} to {} on {}", bus_number, charter.from, charter.to, charter.travel_date)).await?;
+        Ok(WebhookOutcome::processed(format!("Deposit {} reserved charter {}", receipt, charter_id)))
+    }
+
+    // Back to quoted with its bus on sale again; false when it was no longer awaiting its deposit
+    async fn reopen_charter(&self, charter: &Charter) -> Result<bool, AppError> {
+        let reopened = self.get_charters_collection().update_one(
+            doc! { "_id": charter.id, "status": CHARTER_ACCEPTED },
+            doc! { "$set": { "status": CHARTER_QUOTED, "updated_at": bson::DateTime::now() }, "$unset": { "accepted_at": "" } },
+            None
+        ).await?;
+        if reopened.modified_count == 0 {
+            return Ok(false);
+        }
+        if let Some(bus) = match &charter.quote {
+            Some(quote) => self.get_bus(&quote.bus_id.to_hex()).await?,
+            None => None,
+        } {
+            self.release_trip_block(&bus, &charter.travel_date).await?;
+        }
+        Ok(true)
+    }
+
+    /// Reopens accepted charters whose deposit wasn't paid in time, so their buses go back on sale.
+    pub async fn expire_unpaid_charters(&self) -> Result<usize, AppError> {
+        let timeout = chrono::Duration::minutes(MpesaConfig::from_env().payment_timeout_minutes);
+        let cutoff = millis(chrono::Utc::now() - timeout);
+        let due = self.find_charters(doc! { "status": CHARTER_ACCEPTED, "accepted_at": { "$lt": cutoff } }).await?;
+        let mut expired = 0;
+        for charter in due {
+            if !self.reopen_charter(&charter).await? {
+                continue;
+            }
+            self.notify_user(
+                charter.user_id,
+                "Charter deposit not paid",
+                &format!("The deposit for your charter on {} wasn't paid in time, so the bus is back on sale. You can accept the quote again while it lasts.", charter.travel_date),
+            ).await?;
+            expired += 1;
+        }
+        Ok(expired)
+    }
+
+    /// Charter requests a tenant can answer: every one for the platform; for an operator, the
+    /// open requests and the ones it quoted.
+    pub async fn list_charters(&self, tenant: &Tenant, status: Option<&str>) -> Result<Vec<Charter>, AppError> {
+        let filter = match status {
+            Some(status) => doc! { "status": status },
+            None => doc! {},
+        };
+        let mut charters = self.find_charters(filter).await?;
+        charters.retain(|c| visible_to(tenant, c));
+        Ok(charters)
+    }
+
+    // Another operator's charters read as missing, like its buses
+    async fn tenant_charter(&self, tenant: &Tenant, charter_id: &str) -> Result<Charter, AppError> {
+        let charter_oid = self.string_to_id(charter_id)?;
+        let charter = self.get_charters_collection().find_one(doc! { "_id": charter_oid }, None).await?.ok_or_else(|| AppError::not_found("Charter not found"))?;
+        if !visible_to(tenant, &charter) {
+            return Err(AppError::not_found("Charter not found"));
+        }
+        Ok(charter)
+    }
+
+    /// Prices a charter with one of the tenant's buses. A quote may be revised by the operator
+    /// that gave it, and replaced by anyone once it has expired.
+    pub async fn quote_charter(&self, tenant: &Tenant, charter_id: &str, req: &CharterQuoteRequest, actor: &str) -> Result<Charter, AppError> {
+        req.validate()?;
+        let charter = self.tenant_charter(tenant, charter_id).await?;
+        let now = chrono::Utc::now();
+        let state = charter.state(now);
+        let revising = state == "quoted" && charter.quote.as_ref().is_some_and(|q| tenant.owns_operator(&q.operator));
+        if !(state == "requested" || state == "quote_expired" || revising) {
+            return Err("This charter can't be quoted any more".into());
+        }
+        let bus = match self.get_bus(&req.bus_id).await? {
+            Some(bus) if tenant.owns_bus(&bus) => bus,
+            _ => return Err(AppError::not_found("Bus not found")),
+        };
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        if bus.total_seats < charter.passengers {
+            return Err(format!("{} seats {}, the group is {}", bus.bus_number, bus.total_seats, charter.passengers).into());
+        }
+
+        let config = CharterConfig::from_env();
+        let quote = CharterQuote {
+            bus_id,
+            bus_number: bus.bus_number.clone(),
+            operator: bus.operator_name(),
+            price: req.price,
+            deposit: charter_deposit(req.price, config.deposit_percent),
+            note: req.note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+            quoted_by: actor.to_string(),
+            quoted_at: millis(now),
+            expires_at: millis(now + chrono::Duration::hours(config.quote_valid_hours)),
+        };
+        // Only from the state it was read in, so two operators quoting at once can't both win
+        let quoted = self.get_charters_collection().update_one(
+            doc! { "_id": charter.id, "status": &charter.status, "updated_at": charter.updated_at },
+            doc! { "$set": { "status": CHARTER_QUOTED, "quote": bson::to_bson(&quote)?, "updated_at": bson::DateTime::now() } },
+            None
+        ).await?;
+        if quoted.modified_count == 0 {
+            return Err(AppError::conflict("This charter changed meanwhile, please reload it"));
+        }
+        let charter_hex = charter.id.map(|id| id.to_hex()).unwrap_or_default();
+        self.record_audit("charter.quoted", actor, &format!("charter:{}", charter_hex), Some(format!("{} for KES {:.0}", quote.bus_number, quote.price))).await?;
+        self.notify_user(
+            charter.user_id,
+            "Charter quote ready",
+            &format!("{} can take your group from {} to {} on {} for KES {:.0}. Accept it with a KES {:.0} deposit.", quote.operator, charter.from, charter.to, charter.travel_date, quote.price, quote.deposit),
+        ).await?;
+        Ok(Charter { status: CHARTER_QUOTED.to_string(), quote: Some(quote), ..charter })
+    }
+
+    /// Turns a charter request down, or withdraws the tenant's quote for it.
+    pub async fn decline_charter(&self, tenant: &Tenant, charter_id: &str, reason: &str, actor: &str) -> Result<Charter, AppError> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err("reason is required".into());
+        }
+        let charter = self.tenant_charter(tenant, charter_id).await?;
+        // An operator can't close a request other operators may still quote for
+        if *tenant != Tenant::Platform && charter.quote.is_none() {
+            return Err(AppError::forbidden("Only charters you quoted can be declined"));
+        }
+        let declined = self.get_charters_collection().update_one(
+            doc! { "_id": charter.id, "status": { "$in": [CHARTER_REQUESTED, CHARTER_QUOTED] } },
+            doc! { "$set": { "status": CHARTER_DECLINED, "decline_reason": reason, "updated_at": bson::DateTime::now() } },
+            None
+        ).await?;
+        if declined.modified_count == 0 {
+            return Err("Only charters that haven't been accepted can be declined".into());
+        }
+        let charter_hex = charter.id.map(|id| id.to_hex()).unwrap_or_default();
+        self.record_audit("charter.declined", actor, &format!("charter:{}", charter_hex), Some(reason.to_string())).await?;
+        self.notify_user(charter.user_id, "Charter declined", &format!("Your charter from {} to {} on {} was declined: {}", charter.from, charter.to, charter.travel_date, reason)).await?;
+        Ok(Charter { status: CHARTER_DECLINED.to_string(), decline_reason: Some(reason.to_string()), ..charter })
+    }
+}
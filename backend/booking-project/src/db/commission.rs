@@ -0,0 +1,92 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOptions, UpdateOptions},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::commission::{CommissionRule, UpsertCommissionRuleRequest, COMMISSION_KINDS};
+use crate::models::Bus;
+
+impl MongoDB {
+    fn get_commission_rules_collection(&self) -> Collection<CommissionRule> {
+        self.database().collection("commission_rules")
+    }
+
+    pub async fn get_commission_rules(&self, operator: Option<&str>) -> Result<Vec<CommissionRule>, AppError> {
+        let filter = match operator {
+            Some(operator) => doc! { "operator": operator },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "operator": 1, "bus_id": 1 }).build();
+        let mut cursor = self.get_commission_rules_collection().find(filter, find_options).await?;
+
+        let mut rules = Vec::new();
+        while let Some(result) = cursor.next().await {
+            rules.push(result?);
+        }
+        Ok(rules)
+    }
+
+    // One rule per operator, plus at most one override per route
+    pub async fn upsert_commission_rule(&self, req: &UpsertCommissionRuleRequest) -> Result<CommissionRule, AppError> {
+        if req.operator.trim().is_empty() {
+            return Err("Operator is required".into());
+        }
+        if !COMMISSION_KINDS.contains(&req.kind.as_str()) {
+            return Err(format!("kind must be one of: {}", COMMISSION_KINDS.join(", ")).into());
+        }
+        if req.value < 0.0 || (req.kind == "percentage" && req.value > 100.0) {
+            return Err("Commission value is out of range".into());
+        }
+
+        let mut operator = req.operator.trim().to_string();
+        let bus_id = match &req.bus_id {
+            Some(bus_id) => {
+                let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+                if !bus.operator_name().eq_ignore_ascii_case(&operator) {
+                    return Err("Bus does not belong to this operator".into());
+                }
+                operator = bus.operator_name();
+                bus.id
+            }
+            None => None,
+        };
+
+        let filter = doc! { "operator": &operator, "bus_id": bus_id };
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_commission_rules_collection().update_one(
+            filter.clone(),
+            doc! { "$set": {
+                "kind": &req.kind,
+                "value": req.value,
+                "updated_at": bson::DateTime::now(),
+            } },
+            options
+        ).await?;
+
+        self.get_commission_rules_collection().find_one(filter, None).await?
+            .ok_or_else(|| AppError::internal("Failed to save commission rule"))
+    }
+
+    pub async fn delete_commission_rule(&self, rule_id: &str) -> Result<bool, AppError> {
+        let rule_oid = self.string_to_id(rule_id)?;
+        let result = self.get_commission_rules_collection().delete_one(doc! { "_id": rule_oid }, None).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Commission rule for a route: its own override, else the operator's rule, else the platform default.
+    pub async fn resolve_commission_rule(&self, bus: &Bus) -> Result<CommissionRule, AppError> {
+        let operator = bus.operator_name();
+        let collection = self.get_commission_rules_collection();
+        if let Some(bus_id) = bus.id {
+            if let Some(rule) = collection.find_one(doc! { "bus_id": bus_id }, None).await? {
+                return Ok(rule);
+            }
+        }
+        let rule = collection.find_one(doc! { "operator": &operator, "bus_id": bson::Bson::Null }, None).await?;
+        Ok(rule.unwrap_or_else(|| CommissionRule::builtin(&operator)))
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use futures::StreamExt;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::compare::{compare_operators, RouteCompareQuery, RouteComparison};
+
+// Same window the bus list shows punctuality over
+const PUNCTUALITY_WINDOW_DAYS: i64 = 90;
+
+impl MongoDB {
+    /// Every operator running `from` to `to`, side by side. With a date, trips that don't run,
+    /// are cancelled or are sold out that day are left out.
+    pub async fn compare_route(&self, query: &RouteCompareQuery) -> Result<RouteComparison, AppError> {
+        let (from, to) = (query.from.trim(), query.to.trim());
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if bus.route.from.eq_ignore_ascii_case(from) && bus.route.to.eq_ignore_ascii_case(to) {
+                buses.push(bus);
+            }
+        }
+
+        let mut trips = Vec::new();
+        match &query.date {
+            Some(date) => {
+                let buses = self.buses_running_on(buses, date).await?;
+                let bus_ids: Vec<_> = buses.iter().filter_map(|b| b.id).collect();
+                let statuses = self.get_trip_statuses(&bus_ids, date).await?;
+                let seats_left = self.get_seats_left(date, None).await?;
+                for bus in buses {
+                    let Some(bus_id) = bus.id else { continue };
+                    if statuses.get(&bus_id).is_some_and(|s| s.status == "cancelled") {
+                        continue;
+                    }
+                    let left = seats_left.get(&bus_id).copied().unwrap_or(bus.total_seats);
+                    if left > 0 {
+                        trips.push((bus, Some(left)));
+                    }
+                }
+            }
+            None => trips.extend(buses.into_iter().map(|bus| (bus, None))),
+        }
+
+        let punctuality: BTreeMap<_, _> = self.get_route_punctuality(PUNCTUALITY_WINDOW_DAYS).await?
+            .into_iter()
+            .filter(|((_, f, t), _)| f.eq_ignore_ascii_case(from) && t.eq_ignore_ascii_case(to))
+            .map(|((operator, _, _), stats)| (operator, stats))
+            .collect();
+
+        let ratings = self.get_operator_ratings().await?;
+        let mut operators = compare_operators(&trips, &punctuality);
+        for row in &mut operators {
+            row.average_rating = ratings.get(&row.operator).copied();
+        }
+
+        Ok(RouteComparison {
+            from: trips.first().map(|(b, _)| b.route.from.clone()).unwrap_or_else(|| from.to_string()),
+            to: trips.first().map(|(b, _)| b.route.to.clone()).unwrap_or_else(|| to.to_string()),
+            date: query.date.clone(),
+            operators,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use log::{error, info};
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::consent::{marketing_purpose, validate_consents, ConsentEvent, PromotionRequest, UserConsents};
+use crate::models::outbox::OutboxMessage;
+
+impl MongoDB {
+    fn get_consents_collection(&self) -> Collection<UserConsents> {
+        self.database().collection("consents")
+    }
+
+    fn get_consent_events_collection(&self) -> Collection<ConsentEvent> {
+        self.database().collection("consent_events")
+    }
+
+    pub async fn ensure_consent_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "user_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_consents_collection().create_index(index, None).await?;
+        let index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "recorded_at": -1 })
+            .build();
+        self.get_consent_events_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_consents(&self, user_id: ObjectId) -> Result<UserConsents, AppError> {
+        let consents = self.get_consents_collection().find_one(doc! { "user_id": user_id }, None).await?;
+        Ok(consents.unwrap_or(UserConsents { id: None, user_id, purposes: HashMap::new() }))
+    }
+
+    pub async fn has_consent(&self, user_id: ObjectId, purpose: &str) -> Result<bool, AppError> {
+        Ok(self.get_consents(user_id).await?.is_granted(purpose))
+    }
+
+    /// Records the decisions in `changes` and returns the user's consents after them.
+    /// Decisions that match what is already recorded are not logged again.
+    pub async fn update_consents(&self, user_id: ObjectId, changes: &HashMap<String, bool>, source: &str) -> Result<UserConsents, AppError> {
+        validate_consents(changes, source)?;
+
+        let current = self.get_consents(user_id).await?;
+        let now = bson::DateTime::now();
+        let mut set = doc! {};
+        let mut events = Vec::new();
+        for (purpose, granted) in changes {
+            if current.purposes.get(purpose).is_some_and(|c| c.granted == *granted) {
+                continue;
+            }
+            set.insert(format!("purposes.{}", purpose), doc! { "granted": *granted, "source": source, "updated_at": now });
+            events.push(ConsentEvent {
+                id: None,
+                user_id,
+                purpose: purpose.clone(),
+                granted: *granted,
+                source: source.to_string(),
+                recorded_at: now,
+            });
+        }
+        if events.is_empty() {
+            return Ok(current);
+        }
+
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let updated = self.get_consents_collection().find_one_and_update(
+            doc! { "user_id": user_id },
+            doc! { "$set": set },
+            options
+        ).await?;
+        self.get_consent_events_collection().insert_many(events, None).await?;
+        updated.ok_or_else(|| AppError::internal("Failed to save consents"))
+    }
+
+    pub async fn get_consent_history(&self, user_id: ObjectId) -> Result<Vec<ConsentEvent>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "recorded_at": -1 }).limit(200).build();
+        let mut cursor = self.get_consent_events_collection().find(doc! { "user_id": user_id }, find_options).await?;
+        let mut events = Vec::new();
+        while let Some(result) = cursor.next().await {
+            events.push(result?);
+        }
+        Ok(events)
+    }
+
+    /// Queues a promotional message only if the user agreed to promotions on its channel; the
+    /// outbox checks again before delivery in case they withdraw in the meantime.
+    pub async fn enqueue_promotional(&self, user_id: ObjectId, message: OutboxMessage) -> Result<Option<ObjectId>, AppError> {
+        let purpose = marketing_purpose(&message.channel)
+            .ok_or_else(|| format!("Promotions can't be sent by {}", message.channel))?;
+        if !self.has_consent(user_id, purpose).await? {
+            return Ok(None);
+        }
+        Ok(Some(self.enqueue_outbox(message.requiring_consent(user_id, purpose)).await?))
+    }
+
+    /// Phone number a user most recently booked under, for texting them.
+    async fn get_user_phone(&self, user_id: ObjectId) -> Result<Option<String>, AppError> {
+        let find_options = mongodb::options::FindOneOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let booking = self.get_bookings_collection().find_one(
+            doc! { "user_id": user_id, "passenger.phone": { "$nin": [null, ""] } },
+            find_options
+        ).await?;
+        Ok(booking.and_then(|b| b.passenger).and_then(|p| p.phone).map(|p| p.trim().to_string()))
+    }
+
+    /// Queues `req` for every user who currently consents to promotions on its channel.
+    pub async fn send_promotion(&self, req: &PromotionRequest, actor: &str) -> Result<usize, AppError> {
+        let purpose = marketing_purpose(&req.channel)
+            .ok_or_else(|| format!("Promotions can't be sent by {}", req.channel))?;
+        let body = req.body.trim();
+        if body.is_empty() {
+            return Err("body is required".into());
+        }
+
+        let user_ids: Vec<ObjectId> = self.get_consents_collection()
+            .distinct("user_id", doc! { format!("purposes.{}.granted", purpose): true }, None)
+            .await?
+            .into_iter()
+            .filter_map(|id| id.as_object_id())
+            .collect();
+
+        let mut queued = 0;
+        let mut users = self.get_users_collection().find(
+            doc! { "_id": { "$in": &user_ids }, "anonymized_at": { "$exists": false } },
+            None
+        ).await?;
+        while let Some(user) = users.next().await {
+            let user = user?;
+            let Some(user_id) = user.id else {
+                continue;
+            };
+            let recipient = match req.channel.as_str() {
+                "sms" => match self.get_user_phone(user_id).await? {
+                    Some(phone) => phone,
+                    None => continue,
+                },
+                _ => user.email.clone(),
+            };
+            let message = OutboxMessage::new(&req.channel, &recipient, req.subject.clone(), body.to_string());
+            match self.enqueue_promotional(user_id, message).await {
+                Ok(Some(_)) => queued += 1,
+                Ok(None) => {}
+                Err(e) => error!("Failed to queue promotion for {}: {}", user_id, e),
+            }
+        }
+
+        self.record_audit(
+            "promotion.sent",
+            actor,
+            &format!("channel:{}", req.channel),
+            Some(format!("{} recipient(s) queued", queued)),
+        ).await?;
+        info!("Promotion by {} queued for {} recipient(s)", req.channel, queued);
+        Ok(queued)
+    }
+}
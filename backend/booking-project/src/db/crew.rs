@@ -0,0 +1,94 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::crew::{AssignCrewRequest, CrewAssignment, CREW_ROLES};
+
+impl MongoDB {
+    fn get_crew_collection(&self) -> Collection<CrewAssignment> {
+        self.database().collection("trip_crew")
+    }
+
+    pub async fn assign_crew(&self, bus_id: &str, travel_date: &str, req: &AssignCrewRequest) -> Result<CrewAssignment, AppError> {
+        if !CREW_ROLES.contains(&req.role.as_str()) {
+            return Err(format!("role must be one of: {}", CREW_ROLES.join(", ")).into());
+        }
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let user = match &req.user_id {
+            Some(user_id) => Some(
+                self.get_users_collection().find_one(doc! { "_id": self.string_to_id(user_id)? }, None).await?
+                    .ok_or_else(|| AppError::not_found("User not found"))?
+            ),
+            None => None,
+        };
+        if req.role == "conductor" {
+            let user = user.as_ref().ok_or("Conductors must be assigned by user_id")?;
+            if user.role != "conductor" {
+                return Err("That account does not have the conductor role".into());
+            }
+            if !user.operator.as_deref().is_some_and(|o| o.eq_ignore_ascii_case(&bus.operator_name())) {
+                return Err(AppError::forbidden("That conductor works for a different operator"));
+            }
+        }
+        let name = match (&user, req.name.as_deref().map(str::trim)) {
+            (_, Some(name)) if !name.is_empty() => name.to_string(),
+            (Some(user), _) => user.username.clone(),
+            _ => return Err("A name is required".into()),
+        };
+
+        let collection = self.get_crew_collection();
+        let user_id = user.as_ref().and_then(|u| u.id);
+        if let Some(user_id) = user_id {
+            let existing = collection.find_one(doc! { "bus_id": bus_oid, "travel_date": travel_date, "user_id": user_id }, None).await?;
+            if existing.is_some() {
+                return Err("Already assigned to this trip".into());
+            }
+        }
+
+        let mut assignment = CrewAssignment {
+            id: None,
+            bus_id: bus_oid,
+            travel_date: travel_date.to_string(),
+            role: req.role.clone(),
+            user_id,
+            name,
+            phone: req.phone.clone(),
+            licence_number: req.licence_number.clone(),
+            assigned_at: bson::DateTime::now(),
+        };
+        let result = collection.insert_one(&assignment, None).await?;
+        assignment.id = result.inserted_id.as_object_id();
+        Ok(assignment)
+    }
+
+    pub async fn get_trip_crew(&self, bus_id: ObjectId, travel_date: &str) -> Result<Vec<CrewAssignment>, AppError> {
+        let mut cursor = self.get_crew_collection().find(doc! { "bus_id": bus_id, "travel_date": travel_date }, None).await?;
+
+        let mut crew = Vec::new();
+        while let Some(result) = cursor.next().await {
+            crew.push(result?);
+        }
+        Ok(crew)
+    }
+
+    pub async fn remove_crew(&self, bus_id: ObjectId, travel_date: &str, assignment_id: &str) -> Result<bool, AppError> {
+        let id = self.string_to_id(assignment_id)?;
+        let result = self.get_crew_collection()
+            .delete_one(doc! { "_id": id, "bus_id": bus_id, "travel_date": travel_date }, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    pub async fn is_assigned_conductor(&self, bus_id: ObjectId, travel_date: &str, user_id: ObjectId) -> Result<bool, AppError> {
+        let assignment = self.get_crew_collection()
+            .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date, "role": "conductor", "user_id": user_id }, None)
+            .await?;
+        Ok(assignment.is_some())
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::crypto;
+use crate::models::device::{new_device_key, Device, ProvisionDeviceRequest};
+use crate::models::tenancy::Tenant;
+
+impl MongoDB {
+    fn get_devices_collection(&self) -> Collection<Device> {
+        self.database().collection("devices")
+    }
+
+    fn get_device_nonces_collection(&self) -> Collection<Document> {
+        self.database().collection("device_nonces")
+    }
+
+    pub async fn ensure_device_indexes(&self) -> Result<(), AppError> {
+        self.get_devices_collection()
+            .create_index(IndexModel::builder().keys(doc! { "operator": 1, "created_at": -1 }).build(), None)
+            .await?;
+        let nonces = self.get_device_nonces_collection();
+        nonces.create_index(
+            IndexModel::builder()
+                .keys(doc! { "device_id": 1, "nonce": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        nonces.create_index(
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    // A device the tenant may manage; anyone else's is reported as missing
+    async fn get_tenant_device(&self, tenant: &Tenant, device_id: &str) -> Result<Device, AppError> {
+        let device_oid = ObjectId::parse_str(device_id).map_err(|_| AppError::not_found("Device not found"))?;
+        match self.get_devices_collection().find_one(doc! { "_id": device_oid }, None).await? {
+            Some(device) if *tenant == Tenant::Platform || device.operator.as_deref().is_some_and(|o| tenant.owns_operator(o)) => Ok(device),
+            _ => Err(AppError::not_found("Device not found")),
+        }
+    }
+
+    /// Registers a crew handset and returns it with its signing key, which is stored encrypted
+    /// and never shown again.
+    pub async fn provision_device(&self, tenant: &Tenant, req: &ProvisionDeviceRequest, actor: &str) -> Result<(Device, String), AppError> {
+        let label = req.validated_label()?;
+        let operator = match tenant {
+            Tenant::Operator(own) => Some(own.clone()),
+            Tenant::Platform => req.operator.as_deref().map(str::trim).filter(|o| !o.is_empty()).map(str::to_string),
+        };
+        let id = ObjectId::new();
+        let key = new_device_key()?;
+        let device = Device {
+            id: Some(id),
+            label,
+            operator,
+            sealed_key: crypto::seal(&key, &id.to_hex())?,
+            created_by: actor.to_string(),
+            created_at: bson::DateTime::now(),
+            last_seen_at: None,
+            revoked_at: None,
+            revoked_by: None,
+            revocation_reason: None,
+        };
+        self.get_devices_collection().insert_one(&device, None).await?;
+        self.record_audit("device.provisioned", actor, &format!("device:{}", id.to_hex()), Some(device.label.clone())).await?;
+        Ok((device, key))
+    }
+
+    pub async fn list_devices(&self, tenant: &Tenant) -> Result<Vec<Device>, AppError> {
+        let filter = match tenant {
+            Tenant::Platform => doc! {},
+            Tenant::Operator(own) => doc! { "operator": own },
+        };
+        let options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_devices_collection().find(filter, options).await?;
+        let mut devices = Vec::new();
+        while let Some(device) = cursor.next().await {
+            devices.push(device?);
+        }
+        Ok(devices)
+    }
+
+    /// Cuts a device off: its signed requests are refused from now on. Revoking twice keeps
+    /// the first reason.
+    pub async fn revoke_device(&self, tenant: &Tenant, device_id: &str, reason: Option<&str>, actor: &str) -> Result<Device, AppError> {
+        let mut device = self.get_tenant_device(tenant, device_id).await?;
+        if device.revoked_at.is_some() {
+            return Ok(device);
+        }
+        let reason = reason.map(str::trim).filter(|r| !r.is_empty()).map(str::to_string);
+        let now = bson::DateTime::now();
+        self.get_devices_collection().update_one(
+            doc! { "_id": device.id, "revoked_at": null },
+            doc! { "$set": { "revoked_at": now, "revoked_by": actor, "revocation_reason": reason.clone() } },
+            None
+        ).await?;
+        self.record_audit("device.revoked", actor, &format!("device:{}", device_id), reason.clone()).await?;
+        device.revoked_at = Some(now);
+        device.revoked_by = Some(actor.to_string());
+        device.revocation_reason = reason;
+        Ok(device)
+    }
+
+    /// A device that is still allowed to sign, with its signing key; None for unknown or revoked ones.
+    pub async fn active_device(&self, device_id: &str) -> Result<Option<(Device, String)>, AppError> {
+        let Ok(device_oid) = ObjectId::parse_str(device_id) else {
+            return Ok(None);
+        };
+        let device = self.get_devices_collection()
+            .find_one(doc! { "_id": device_oid, "revoked_at": null }, None)
+            .await?;
+        match device {
+            Some(device) => {
+                let key = crypto::open(&device.sealed_key, device_id)?;
+                Ok(Some((device, key)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Records a nonce as used until `expires_at`. False when the device already used it,
+    /// i.e. the request is a replay.
+    pub async fn claim_device_nonce(&self, device_id: &str, nonce: &str, expires_at: bson::DateTime) -> Result<bool, AppError> {
+        let claimed = self.get_device_nonces_collection()
+            .insert_one(doc! { "device_id": device_id, "nonce": nonce, "expires_at": expires_at }, None)
+            .await;
+        match claimed {
+            Ok(_) => Ok(true),
+            Err(e) if super::locks::is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn touch_device(&self, device_id: &str) -> Result<(), AppError> {
+        let Ok(device_oid) = ObjectId::parse_str(device_id) else {
+            return Ok(());
+        };
+        self.get_devices_collection()
+            .update_one(doc! { "_id": device_oid }, doc! { "$set": { "last_seen_at": bson::DateTime::now() } }, None)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeSet, HashMap};
+
+use futures::StreamExt;
+use log::info;
+use mongodb::bson::{self, doc, oid::ObjectId, Document};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::BookingEvent;
+use crate::models::duplicates::{cluster_duplicates, DuplicateAccount, DuplicateGroup, DuplicateLink, MergeAccountsRequest, MergeResult};
+use crate::models::fraud::normalize_blocklist_value;
+use crate::models::User;
+
+// A survivor can itself be merged later on; chains longer than this are left where they stop
+const MAX_MERGE_HOPS: usize = 5;
+
+impl MongoDB {
+    /// The account to sign in to: a merged duplicate opens the account it was merged into.
+    pub(super) async fn resolve_merged_account(&self, mut user: User) -> Result<User, AppError> {
+        for _ in 0..MAX_MERGE_HOPS {
+            let Some(survivor_id) = user.merged_into else { break };
+            match self.get_users_collection().find_one(doc! { "_id": survivor_id }, None).await? {
+                Some(survivor) => user = survivor,
+                None => break,
+            }
+        }
+        Ok(user)
+    }
+
+    // Groups of `user_id`s sharing the value of `key` in `collection`, at least two to a group
+    async fn shared_by_users(&self, collection: &str, filter: Document, key: bson::Bson) -> Result<Vec<(bson::Bson, BTreeSet<ObjectId>)>, AppError> {
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": { "_id": key, "users": { "$addToSet": "$user_id" } } },
+            doc! { "$match": { "users.1": { "$exists": true } } },
+        ];
+        let mut cursor = self.database().collection::<Document>(collection).aggregate(pipeline, None).await?;
+        let mut shared = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let group = result?;
+            let users = group.get_array("users").map(|u| u.iter().filter_map(|id| id.as_object_id()).collect()).unwrap_or_default();
+            shared.push((group.get("_id").cloned().unwrap_or(bson::Bson::Null), users));
+        }
+        Ok(shared)
+    }
+
+    /// Customer accounts that are likely the same person: the same passenger phone on their
+    /// bookings, or the same name and the same M-Pesa number or saved card.
+    pub async fn find_duplicate_accounts(&self) -> Result<Vec<DuplicateGroup>, AppError> {
+        // Phones are stored as typed; numbers only match once normalized
+        let mut phones: HashMap<String, BTreeSet<ObjectId>> = HashMap::new();
+        let passenger_phones = self.shared_by_users(
+            "bookings",
+            doc! { "passenger.phone": { "$nin": [null, ""] } },
+            bson::Bson::String("$passenger.phone".to_string()),
+        ).await?;
+        for (phone, users) in passenger_phones {
+            if let Some(phone) = phone.as_str() {
+                phones.entry(normalize_blocklist_value("phone", phone)).or_default().extend(users);
+            }
+        }
+        let mut links: Vec<DuplicateLink> = phones.into_iter()
+            .filter(|(_, users)| users.len() > 1)
+            .map(|(phone, user_ids)| DuplicateLink { user_ids, reason: format!("Same phone {}", phone) })
+            .collect();
+
+        let mut by_payment = Vec::new();
+        for (phone, users) in self.shared_by_users("payments", doc! {}, bson::Bson::String("$phone".to_string())).await? {
+            by_payment.push((users, format!("Same name and M-Pesa number {}", phone.as_str().unwrap_or_default())));
+        }
+        let cards = self.shared_by_users(
+            "payment_methods",
+            doc! { "last4": { "$nin": [null, ""] } },
+            bson::Bson::Document(doc! { "brand": "$brand", "last4": "$last4", "exp_month": "$exp_month", "exp_year": "$exp_year" }),
+        ).await?;
+        for (card, users) in cards {
+            let card = card.as_document().cloned().unwrap_or_default();
+            by_payment.push((users, format!(
+                "Same name and saved card {} ending {}",
+                card.get_str("brand").unwrap_or_default(),
+                card.get_str("last4").unwrap_or_default()
+            )));
+        }
+
+        // Only customer accounts still in use are candidates
+        let mut candidates: BTreeSet<ObjectId> = links.iter().flat_map(|l| l.user_ids.iter().copied()).collect();
+        candidates.extend(by_payment.iter().flat_map(|(users, _)| users.iter().copied()));
+        let mut accounts = HashMap::new();
+        let mut cursor = self.get_users_collection().find(
+            doc! {
+                "_id": { "$in": candidates.into_iter().collect::<Vec<_>>() },
+                "role": "user",
+                "anonymized_at": { "$exists": false },
+                "merged_into": { "$exists": false },
+            },
+            None
+        ).await?;
+        while let Some(result) = cursor.next().await {
+            let user = result?;
+            if let Some(id) = user.id {
+                accounts.insert(id, user);
+            }
+        }
+
+        let names: HashMap<ObjectId, String> = accounts.iter().map(|(id, user)| (*id, user.username.clone())).collect();
+        for (users, reason) in &by_payment {
+            links.extend(DuplicateLink::by_name(users, &names, reason));
+        }
+        for link in &mut links {
+            link.user_ids.retain(|id| accounts.contains_key(id));
+        }
+
+        let mut groups = Vec::new();
+        for (user_ids, reasons) in cluster_duplicates(links) {
+            let mut group = DuplicateGroup { accounts: Vec::new(), reasons };
+            for user_id in user_ids {
+                let Some(user) = accounts.get(&user_id) else { continue };
+                group.accounts.push(DuplicateAccount {
+                    id: user_id.to_hex(),
+                    username: user.username.clone(),
+                    email: user.email.clone(),
+                    has_password: !user.password.is_empty(),
+                    bookings: self.get_bookings_collection().count_documents(doc! { "user_id": user_id }, None).await?,
+                    created_at: user.created_at.and_then(|at| at.try_to_rfc3339_string().ok()),
+                    last_login_at: user.last_login_at.and_then(|at| at.try_to_rfc3339_string().ok()),
+                });
+            }
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+
+    /// Folds a duplicate account into the survivor: its bookings, payments, saved payment methods,
+    /// support requests and live sessions move over, and signing in to it opens the survivor from
+    /// then on. A merge that failed part way can be run again.
+    pub async fn merge_accounts(&self, req: &MergeAccountsRequest, actor: &str) -> Result<MergeResult, AppError> {
+        req.validate()?;
+        let survivor_id = self.string_to_id(&req.survivor_id)?;
+        let duplicate_id = self.string_to_id(&req.duplicate_id)?;
+        let users = self.get_users_collection();
+        let survivor = users.find_one(doc! { "_id": survivor_id }, None).await?.ok_or_else(|| AppError::not_found("Surviving account not found"))?;
+        let duplicate = users.find_one(doc! { "_id": duplicate_id }, None).await?.ok_or_else(|| AppError::not_found("Duplicate account not found"))?;
+        for user in [&survivor, &duplicate] {
+            if user.role != "user" {
+                return Err(format!("{} is a staff account; only customer accounts can be merged", user.email).into());
+            }
+            if user.anonymized_at.is_some() {
+                return Err(format!("{} has been anonymized", user.email).into());
+            }
+        }
+        if survivor.merged_into.is_some() {
+            return Err("The surviving account has itself been merged into another".into());
+        }
+
+        // Marked first, so sign-ins already go to the survivor while everything moves over
+        let now = bson::DateTime::now();
+        let marked = users.update_one(
+            doc! {
+                "_id": duplicate_id,
+                "$or": [{ "merged_into": { "$exists": false } }, { "merged_into": survivor_id }],
+            },
+            doc! { "$set": { "merged_into": survivor_id, "sessions_revoked_at": now, "updated_at": now } },
+            None
+        ).await?;
+        if marked.matched_count == 0 {
+            return Err(AppError::conflict("The duplicate account has already been merged into another"));
+        }
+
+        let mut result = MergeResult {
+            survivor_id: survivor_id.to_hex(),
+            merged_id: duplicate_id.to_hex(),
+            ..Default::default()
+        };
+        let event = BookingEvent::new("account_merged", actor, Some(format!("Moved from account {}", duplicate.email)));
+        result.bookings = self.get_bookings_collection().update_many(
+            doc! { "user_id": duplicate_id },
+            doc! { "$set": { "user_id": survivor_id }, "$push": { "history": bson::to_bson(&event)? } },
+            None
+        ).await?.modified_count;
+
+        let database = self.database();
+        let relink = doc! { "$set": { "user_id": survivor_id } };
+        for (collection, moved) in [
+            ("payments", &mut result.payments),
+            ("payment_methods", &mut result.payment_methods),
+            ("refund_requests", &mut result.refund_requests),
+            ("lost_items", &mut result.lost_items),
+            ("notifications", &mut result.notifications),
+            ("seat_holds", &mut result.seat_holds),
+        ] {
+            *moved = database.collection::<Document>(collection)
+                .update_many(doc! { "user_id": duplicate_id }, relink.clone(), None)
+                .await?
+                .modified_count;
+        }
+        // Refreshing a live session then signs in as the survivor
+        result.sessions = database.collection::<Document>("refresh_tokens")
+            .update_many(doc! { "user_id": duplicate_id, "revoked_at": bson::Bson::Null }, relink, None)
+            .await?
+            .modified_count;
+        database.collection::<Document>("search_log").update_many(
+            doc! { "user_id": duplicate_id.to_hex() },
+            doc! { "$set": { "user_id": survivor_id.to_hex() } },
+            None
+        ).await?;
+        // A pending email change on the duplicate no longer has an account to change
+        database.collection::<Document>("email_changes").update_many(
+            doc! { "user_id": duplicate_id, "status": "pending" },
+            doc! { "$set": { "status": "cancelled" } },
+            None
+        ).await?;
+
+        let reason = req.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+        self.record_audit(
+            "account.merged",
+            actor,
+            &format!("user:{}", survivor_id.to_hex()),
+            Some(format!("Merged user:{} <{}> into <{}>{}: {}", duplicate_id.to_hex(), duplicate.email, survivor.email, reason, result.summary())),
+        ).await?;
+        info!("Merged user {} into {}", duplicate_id.to_hex(), survivor_id.to_hex());
+        Ok(result)
+    }
+}
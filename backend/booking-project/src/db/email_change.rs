@@ -0,0 +1,201 @@
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::info;
+use mongodb::{
+    bson::{self, doc},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::account::{issued_before_revocation, EmailChange, EmailChangeClaims, EmailChangeRequest};
+use crate::models::outbox::OutboxMessage;
+
+pub(crate) const CONFIRM_SCOPE: &str = "email:confirm";
+pub(crate) const UNDO_SCOPE: &str = "email:undo";
+const CONFIRM_HOURS: i64 = 24;
+// The old address can undo a change for a week after it went through
+const UNDO_DAYS: i64 = 7;
+
+fn link(path: &str, token: &str) -> String {
+    let base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+    format!("{}/api/account/email/{}/{}", base_url.trim_end_matches('/'), path, token)
+}
+
+pub(crate) fn sign_change_link(change_id: &str, scope: &str, valid_for: chrono::Duration) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = EmailChangeClaims {
+        change_id: change_id.to_string(),
+        scope: scope.to_string(),
+        exp: (chrono::Utc::now() + valid_for).timestamp() as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref()))
+}
+
+/// The change a link's token is for, if it was issued for `scope` and hasn't expired.
+pub(crate) fn verify_change_link(token: &str, scope: &str) -> Result<String, AppError> {
+    let claims = decode::<EmailChangeClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &Validation::new(Algorithm::HS256),
+    )?.claims;
+    if claims.scope != scope {
+        return Err("Invalid link".into());
+    }
+    Ok(claims.change_id)
+}
+
+impl MongoDB {
+    fn get_email_changes_collection(&self) -> Collection<EmailChange> {
+        self.database().collection("email_changes")
+    }
+
+    /// Starts a change: the new address gets a confirmation link, the old one a heads-up with an undo link.
+    pub async fn request_email_change(&self, user_id: &str, req: &EmailChangeRequest) -> Result<EmailChange, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let user = self.get_users_collection().find_one(doc! { "_id": user_oid }, None).await?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        let new_email = req.new_email.trim().to_lowercase();
+        if !new_email.contains('@') || new_email == user.email.to_lowercase() {
+            return Err("Enter a different, valid email address".into());
+        }
+        if !user.password.is_empty() {
+            let password = req.password.as_deref().ok_or("Current password is required")?;
+            if !bcrypt::verify(password, &user.password)? {
+                return Err("Current password is incorrect".into());
+            }
+        }
+        if self.get_users_collection().find_one(doc! { "email": &new_email }, None).await?.is_some() {
+            return Err(AppError::conflict("That email address is already in use"));
+        }
+
+        // A newer request supersedes any that are still waiting
+        let collection = self.get_email_changes_collection();
+        collection.update_many(
+            doc! { "user_id": user_oid, "status": "pending" },
+            doc! { "$set": { "status": "cancelled" } },
+            None
+        ).await?;
+
+        let expires_at = chrono::Utc::now() + chrono::Duration::hours(CONFIRM_HOURS);
+        let mut change = EmailChange {
+            id: None,
+            user_id: user_oid,
+            old_email: user.email.clone(),
+            new_email: new_email.clone(),
+            status: "pending".to_string(),
+            expires_at: bson::DateTime::from_millis(expires_at.timestamp_millis()),
+            created_at: bson::DateTime::now(),
+            confirmed_at: None,
+        };
+        let result = collection.insert_one(&change, None).await?;
+        change.id = result.inserted_id.as_object_id();
+        let change_id = change.id.ok_or_else(|| AppError::internal("Failed to create email change"))?.to_hex();
+
+        let confirm_token = sign_change_link(&change_id, CONFIRM_SCOPE, chrono::Duration::hours(CONFIRM_HOURS))?;
+        let undo_token = sign_change_link(&change_id, UNDO_SCOPE, chrono::Duration::hours(CONFIRM_HOURS) + chrono::Duration::days(UNDO_DAYS))?;
+
+        self.enqueue_outbox(OutboxMessage::new(
+            "email",
+            &new_email,
+            Some("Confirm your new email address".to_string()),
+            format!(
+                "Hi {},\n\nConfirm that you want to use this address to sign in to Burudani Mint Travels:\n{}\n\nThe link expires in {} hours. If you didn't ask for this, ignore this email.",
+                user.username, link("confirm", &confirm_token), CONFIRM_HOURS
+            ),
+        )).await?;
+        self.enqueue_outbox(OutboxMessage::new(
+            "email",
+            &user.email,
+            Some("Your sign-in email is being changed".to_string()),
+            format!(
+                "Hi {},\n\nSomeone asked to change the sign-in email on your account to {}. If this wasn't you, cancel the change and sign everyone out:\n{}",
+                user.username, new_email, link("undo", &undo_token)
+            ),
+        )).await?;
+
+        info!("Email change requested for user {}", user_id);
+        Ok(change)
+    }
+
+    /// Commits a pending change and signs out every existing session.
+    pub async fn confirm_email_change(&self, token: &str) -> Result<EmailChange, AppError> {
+        let change_id = self.string_to_id(&verify_change_link(token, CONFIRM_SCOPE)?)?;
+        let collection = self.get_email_changes_collection();
+        let change = collection.find_one(doc! { "_id": change_id }, None).await?
+            .ok_or("Invalid link")?;
+        if change.status != "pending" {
+            return Err(AppError::conflict("This change is no longer pending"));
+        }
+        if change.expires_at < bson::DateTime::now() {
+            return Err("This link has expired".into());
+        }
+        if self.get_users_collection().find_one(doc! { "email": &change.new_email }, None).await?.is_some() {
+            return Err(AppError::conflict("That email address is already in use"));
+        }
+
+        self.get_users_collection().update_one(
+            doc! { "_id": change.user_id, "email": &change.old_email },
+            doc! { "$set": {
+                "email": &change.new_email,
+                "updated_at": bson::DateTime::now(),
+                "sessions_revoked_at": bson::DateTime::now(),
+            } },
+            None
+        ).await?;
+        collection.update_one(
+            doc! { "_id": change_id },
+            doc! { "$set": { "status": "confirmed", "confirmed_at": bson::DateTime::now() } },
+            None
+        ).await?;
+
+        info!("Email change confirmed for user {}", change.user_id);
+        Ok(collection.find_one(doc! { "_id": change_id }, None).await?.ok_or("Invalid link")?)
+    }
+
+    /// Cancels a pending change, or puts the old address back if it already went through.
+    pub async fn undo_email_change(&self, token: &str) -> Result<EmailChange, AppError> {
+        let change_id = self.string_to_id(&verify_change_link(token, UNDO_SCOPE)?)?;
+        let collection = self.get_email_changes_collection();
+        let change = collection.find_one(doc! { "_id": change_id }, None).await?
+            .ok_or("Invalid link")?;
+
+        let status = match change.status.as_str() {
+            "pending" => {
+                // The account owner didn't ask for this, so whoever did may be signed in
+                self.get_users_collection().update_one(
+                    doc! { "_id": change.user_id },
+                    doc! { "$set": { "sessions_revoked_at": bson::DateTime::now() } },
+                    None
+                ).await?;
+                "cancelled"
+            }
+            "confirmed" => {
+                self.get_users_collection().update_one(
+                    doc! { "_id": change.user_id, "email": &change.new_email },
+                    doc! { "$set": {
+                        "email": &change.old_email,
+                        "updated_at": bson::DateTime::now(),
+                        "sessions_revoked_at": bson::DateTime::now(),
+                    } },
+                    None
+                ).await?;
+                "reverted"
+            }
+            _ => return Err(AppError::conflict("This change can no longer be undone")),
+        };
+        collection.update_one(doc! { "_id": change_id }, doc! { "$set": { "status": status } }, None).await?;
+
+        info!("Email change {} for user {}", status, change.user_id);
+        Ok(collection.find_one(doc! { "_id": change_id }, None).await?.ok_or("Invalid link")?)
+    }
+
+    /// Whether a token issued at `issued_at` (seconds) predates the user's last session revocation.
+    pub async fn is_session_revoked(&self, user_id: &str, issued_at: usize) -> Result<bool, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let user = self.get_users_collection().find_one(doc! { "_id": user_oid }, None).await?;
+        Ok(user
+            .and_then(|u| u.sessions_revoked_at)
+            .is_some_and(|revoked| issued_before_revocation(issued_at, revoked)))
+    }
+}
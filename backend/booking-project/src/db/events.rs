@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use log::error;
+use mongodb::{
+    bson::{self, doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::{EventStreamConfig, EventsConfig};
+use crate::events::stream::{topic, StreamRecord};
+use crate::events::{DomainEvent, EventEnvelope};
+use crate::models::outbox::OutboxMessage;
+
+const EVENT_PENDING: &str = "pending";
+const EVENT_DISPATCHED: &str = "dispatched";
+
+impl MongoDB {
+    fn get_domain_events_collection(&self) -> Collection<Document> {
+        self.database().collection("domain_events")
+    }
+
+    pub async fn ensure_domain_event_indexes(&self) -> Result<(), AppError> {
+        let events = self.get_domain_events_collection();
+        events.create_index(IndexModel::builder().keys(doc! { "status": 1, "_id": 1 }).build(), None).await?;
+        // Only relayed events have `dispatched_at`, so pending ones never expire
+        let retention = Duration::from_secs(EventsConfig::from_env().retention_days * 86_400);
+        events.create_index(
+            IndexModel::builder()
+                .keys(doc! { "dispatched_at": 1 })
+                .options(IndexOptions::builder().expire_after(retention).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Tells subscribers about the event, through the outbox when events are persisted. Never
+    /// fails the write it reports on: an event that can't be stored is logged and dropped.
+    pub(super) async fn publish_event(&self, event: DomainEvent) {
+        let envelope = EventEnvelope::new(event);
+        if !self.events().persists() {
+            self.events().dispatch(envelope);
+            return;
+        }
+        let stored = bson::to_document(&envelope).map(|mut document| {
+            document.insert("status", EVENT_PENDING);
+            document
+        });
+        let result = match stored {
+            Ok(document) => self.get_domain_events_collection().insert_one(document, None).await.map(|_| ()).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            error!("Failed to store {} event for {}: {}", envelope.event.name(), envelope.event.subject(), e);
+        }
+    }
+
+    /// Hands stored events to this instance's subscribers, oldest first, up to `limit`. Each one
+    /// is claimed before it is dispatched, so with several instances only one of them relays it.
+    pub async fn relay_domain_events(&self, limit: usize) -> Result<usize, AppError> {
+        let events = self.get_domain_events_collection();
+        let options = FindOneAndUpdateOptions::builder().sort(doc! { "_id": 1 }).build();
+        let mut relayed = 0;
+        while relayed < limit {
+            let Some(mut document) = events.find_one_and_update(
+                doc! { "status": EVENT_PENDING },
+                doc! { "$set": { "status": EVENT_DISPATCHED, "dispatched_at": bson::DateTime::now() } },
+                options.clone()
+            ).await? else {
+                break;
+            };
+            document.remove("status");
+            document.remove("dispatched_at");
+            match bson::from_document::<EventEnvelope>(document) {
+                Ok(envelope) => {
+                    self.events().dispatch(envelope);
+                }
+                Err(e) => error!("Skipping unreadable domain event: {}", e),
+            }
+            relayed += 1;
+        }
+        Ok(relayed)
+    }
+
+    /// Queues an event for the Kafka or NATS publisher; the outbox retries it through broker
+    /// outages for up to `max_attempts`.
+    pub async fn enqueue_stream_record(&self, envelope: &EventEnvelope, config: &EventStreamConfig) -> Result<(), AppError> {
+        let record = StreamRecord::new(envelope, &config.environment);
+        let mut message = OutboxMessage::new(
+            "event_stream",
+            &topic(&config.prefix, &envelope.event),
+            Some(record.subject.clone()),
+            serde_json::to_string(&record)?,
+        );
+        message.max_attempts = config.max_attempts;
+        self.enqueue_outbox(message).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::UpdateOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::experiment::{Experiment, ExperimentExposure, UpsertExperimentRequest};
+
+impl MongoDB {
+    fn get_experiments_collection(&self) -> Collection<Experiment> {
+        self.database().collection("experiments")
+    }
+
+    fn get_experiment_exposures_collection(&self) -> Collection<ExperimentExposure> {
+        self.database().collection("experiment_exposures")
+    }
+
+    pub async fn get_experiments(&self, active_only: bool) -> Result<Vec<Experiment>, AppError> {
+        let filter = if active_only { doc! { "active": true } } else { doc! {} };
+        let mut cursor = self.get_experiments_collection().find(filter, None).await?;
+
+        let mut experiments = Vec::new();
+        while let Some(result) = cursor.next().await {
+            experiments.push(result?);
+        }
+        Ok(experiments)
+    }
+
+    pub async fn upsert_experiment(&self, req: &UpsertExperimentRequest) -> Result<(), AppError> {
+        if req.key.trim().is_empty() {
+            return Err("Experiment key is required".into());
+        }
+        if req.variants.len() < 2 || req.variants.iter().all(|v| v.weight == 0) {
+            return Err("An experiment needs at least two variants with a positive total weight".into());
+        }
+
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_experiments_collection().update_one(
+            doc! { "key": &req.key },
+            doc! { "$set": {
+                "description": &req.description,
+                "variants": bson::to_bson(&req.variants)?,
+                "active": req.active,
+                "updated_at": bson::DateTime::now(),
+            } },
+            options
+        ).await?;
+        Ok(())
+    }
+
+    // One document per experiment/subject, so conversion analysis can join on the subject id
+    pub async fn record_exposure(&self, experiment: &str, variant: &str, subject: &str, context: &str) -> Result<(), AppError> {
+        let now = bson::DateTime::now();
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_experiment_exposures_collection().update_one(
+            doc! { "experiment": experiment, "subject": subject },
+            doc! {
+                "$setOnInsert": { "variant": variant, "context": context, "first_exposed_at": now },
+                "$set": { "last_exposed_at": now },
+                "$inc": { "exposures": 1 },
+            },
+            options
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use mongodb::{bson::doc, options::ReplaceOptions, Collection};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::fare::{quote_fare, FareQuote, FareTable, FareTableRequest};
+
+impl MongoDB {
+    fn get_fare_tables_collection(&self) -> Collection<FareTable> {
+        self.database().collection("fare_tables")
+    }
+
+    pub async fn get_fare_table(&self, bus_id: &str) -> Result<Option<FareTable>, AppError> {
+        let oid = self.string_to_id(bus_id)?;
+        Ok(self.get_fare_tables_collection().find_one(doc! { "_id": oid }, None).await?)
+    }
+
+    /// Replaces the bus's stops and segment fares.
+    pub async fn set_fare_table(&self, bus_id: &str, req: FareTableRequest, actor: &str) -> Result<FareTable, AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        req.validate(&bus)?;
+        let table = req.into_table(self.string_to_id(bus_id)?);
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_fare_tables_collection().replace_one(doc! { "_id": table.bus_id }, &table, options).await?;
+        self.record_audit(
+            "fare_table.updated",
+            actor,
+            bus_id,
+            Some(format!("{} stops, {} fares", table.stops.len(), table.fares.len())),
+        ).await?;
+        Ok(table)
+    }
+
+    /// Drops the table; the bus then sells its whole route only.
+    pub async fn delete_fare_table(&self, bus_id: &str, actor: &str) -> Result<bool, AppError> {
+        let oid = self.string_to_id(bus_id)?;
+        let result = self.get_fare_tables_collection().delete_one(doc! { "_id": oid }, None).await?;
+        if result.deleted_count == 0 {
+            return Ok(false);
+        }
+        self.record_audit("fare_table.deleted", actor, bus_id, None).await?;
+        Ok(true)
+    }
+
+    pub async fn quote_bus_fare(&self, bus_id: &str, from: Option<&str>, to: Option<&str>) -> Result<FareQuote, AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let table = self.get_fare_table(bus_id).await?;
+        Ok(quote_fare(&bus, table.as_ref(), from, to)?)
+    }
+}
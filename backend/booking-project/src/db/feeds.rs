@@ -0,0 +1,40 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::ReplaceOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::feed::{summarize_routes, RouteFeed};
+
+const ROUTE_FEED_ID: &str = "routes";
+
+impl MongoDB {
+    fn get_feeds_collection(&self) -> Collection<RouteFeed> {
+        self.database().collection("feeds")
+    }
+
+    pub async fn get_route_feed(&self) -> Result<Option<RouteFeed>, AppError> {
+        Ok(self.get_feeds_collection().find_one(doc! { "_id": ROUTE_FEED_ID }, None).await?)
+    }
+
+    /// Summarizes every route in the fleet and stores the result as the feed snapshot.
+    pub async fn refresh_route_feed(&self) -> Result<RouteFeed, AppError> {
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            buses.push(bus?);
+        }
+
+        let feed = RouteFeed {
+            id: ROUTE_FEED_ID.to_string(),
+            routes: summarize_routes(&buses),
+            generated_at: bson::DateTime::now(),
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_feeds_collection().replace_one(doc! { "_id": ROUTE_FEED_ID }, &feed, options).await?;
+        Ok(feed)
+    }
+}
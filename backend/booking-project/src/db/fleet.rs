@@ -0,0 +1,124 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::bson::{doc, oid::ObjectId};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::{local_offset, operator_from_bus_number, Bus, BusRequest, OPEN_SEATING};
+use crate::models::tenancy::Tenant;
+use crate::models::Booking;
+
+// The operator is read off the bus number, so an operator can't file a bus under someone else
+fn ensure_operator_name_owned(tenant: &Tenant, bus_number: &str) -> Result<(), AppError> {
+    let operator = operator_from_bus_number(bus_number);
+    if tenant.owns_operator(&operator) {
+        Ok(())
+    } else {
+        Err(AppError::forbidden(format!("Buses you add must be numbered under your own operator, not {}", operator)))
+    }
+}
+
+pub(super) fn today() -> String {
+    chrono::Utc::now().with_timezone(&local_offset()).format("%Y-%m-%d").to_string()
+}
+
+impl MongoDB {
+    /// The buses a tenant manages: every bus for the platform, its own for an operator.
+    pub async fn list_fleet(&self, tenant: &Tenant) -> Result<Vec<Bus>, AppError> {
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if tenant.owns_bus(&bus) {
+                buses.push(bus);
+            }
+        }
+        buses.sort_by(|a, b| a.bus_number.cmp(&b.bus_number));
+        Ok(buses)
+    }
+
+    pub async fn create_bus(&self, tenant: &Tenant, req: &BusRequest, actor: &str) -> Result<Bus, AppError> {
+        req.validate()?;
+        ensure_operator_name_owned(tenant, &req.bus_number)?;
+        if self.get_buses_collection().find_one(doc! { "bus_number": req.bus_number.trim() }, None).await?.is_some() {
+            return Err(AppError::conflict(format!("Bus {} already exists", req.bus_number.trim())));
+        }
+
+        let mut bus = Bus {
+            id: None,
+            bus_number: req.bus_number.trim().to_string(),
+            bus_type: req.bus_type.trim().to_string(),
+            total_seats: req.total_seats,
+            route: req.route.clone(),
+            policy_id: None,
+            layout: req.layout.clone(),
+        };
+        let result = self.get_buses_collection().insert_one(&bus, None).await?;
+        bus.id = result.inserted_id.as_object_id();
+        let bus_id = bus.id.map(|id| id.to_hex()).unwrap_or_default();
+        self.record_audit("bus.created", actor, &format!("bus:{}", bus_id), Some(bus.bus_number.clone())).await?;
+        info!("Bus {} added by {}", bus.bus_number, actor);
+        Ok(bus)
+    }
+
+    /// Edits a bus. Seats still booked on upcoming trips can't be taken away or relabelled.
+    pub async fn update_bus(&self, tenant: &Tenant, bus_id: &str, req: &BusRequest, actor: &str) -> Result<Bus, AppError> {
+        req.validate()?;
+        ensure_operator_name_owned(tenant, &req.bus_number)?;
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_number = req.bus_number.trim();
+        if bus_number != bus.bus_number
+            && self.get_buses_collection().find_one(doc! { "bus_number": bus_number }, None).await?.is_some()
+        {
+            return Err(AppError::conflict(format!("Bus {} already exists", bus_number)));
+        }
+
+        let updated = Bus {
+            id: Some(bus_oid),
+            bus_number: bus_number.to_string(),
+            bus_type: req.bus_type.trim().to_string(),
+            total_seats: req.total_seats,
+            route: req.route.clone(),
+            policy_id: bus.policy_id,
+            layout: req.layout.clone(),
+        };
+        let unseated = self.upcoming_bus_bookings(bus_oid).await?.into_iter()
+            .filter(|b| b.seat_number != OPEN_SEATING && !updated.has_seat(&b.seat_number))
+            .count();
+        if unseated > 0 {
+            return Err(format!("{} upcoming booking(s) hold seats the bus would no longer have; reseat them first", unseated).into());
+        }
+
+        self.get_buses_collection().replace_one(doc! { "_id": bus_oid }, &updated, None).await?;
+        self.sync_trip_seats(&updated, &today()).await?;
+        self.record_audit("bus.updated", actor, &format!("bus:{}", bus_id), Some(updated.bus_number.clone())).await?;
+        Ok(updated)
+    }
+
+    /// Retires a bus nobody is booked on any more.
+    pub async fn delete_bus(&self, bus_id: &str, actor: &str) -> Result<(), AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let upcoming = self.upcoming_bus_bookings(bus_oid).await?.len();
+        if upcoming > 0 {
+            return Err(format!("{} has {} upcoming booking(s); cancel or reseat them first", bus.bus_number, upcoming).into());
+        }
+        self.get_buses_collection().delete_one(doc! { "_id": bus_oid }, None).await?;
+        self.delete_schedule(bus_id, actor).await?;
+        self.record_audit("bus.deleted", actor, &format!("bus:{}", bus_id), Some(bus.bus_number)).await?;
+        Ok(())
+    }
+
+    async fn upcoming_bus_bookings(&self, bus_id: ObjectId) -> Result<Vec<Booking>, AppError> {
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "bus_id": bus_id, "travel_date": { "$gte": today() }, "status": { "$ne": "Cancelled" } },
+            None
+        ).await?;
+        let mut bookings = Vec::new();
+        while let Some(booking) = cursor.next().await {
+            bookings.push(booking?);
+        }
+        Ok(bookings)
+    }
+}
@@ -0,0 +1,274 @@
+use futures::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::{GeoIpConfig, MpesaConfig};
+use crate::models::booking::{BookingEvent, CreateGroupBookingRequest};
+use crate::models::fraud::{
+    normalize_blocklist_value, BlocklistEntry, BlocklistRequest, EnforcementDecision, FraudSignals, RiskAssessment,
+    BLOCKLIST_KINDS,
+};
+use crate::models::monitoring::{ENUMERATION_SUSPECTED, PAYMENT_FAILED};
+use crate::models::tenancy::Tenant;
+use crate::models::Booking;
+
+/// One filter per (kind, value) being checked, in the form entries are stored; blank values
+/// can't match anything and are left out.
+pub(crate) fn blocklist_candidates(subjects: &[(&str, &str)]) -> Vec<Document> {
+    subjects
+        .iter()
+        .map(|(kind, value)| (*kind, normalize_blocklist_value(kind, value)))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(kind, value)| doc! { "kind": kind, "value": value })
+        .collect()
+}
+
+impl MongoDB {
+    fn get_blocklist_collection(&self) -> Collection<BlocklistEntry> {
+        self.database().collection("blocklist")
+    }
+
+    fn get_fraud_decisions_collection(&self) -> Collection<EnforcementDecision> {
+        self.database().collection("fraud_decisions")
+    }
+
+    pub async fn ensure_blocklist_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "kind": 1, "value": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_blocklist_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_blocklist(&self, kind: Option<&str>) -> Result<Vec<BlocklistEntry>, AppError> {
+        let filter = match kind {
+            Some(kind) => doc! { "kind": kind },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_blocklist_collection().find(filter, find_options).await?;
+
+        let mut entries = Vec::new();
+        while let Some(result) = cursor.next().await {
+            entries.push(result?);
+        }
+        Ok(entries)
+    }
+
+    pub async fn add_blocklist_entry(&self, req: &BlocklistRequest, created_by: &str) -> Result<BlocklistEntry, AppError> {
+        if !BLOCKLIST_KINDS.contains(&req.kind.as_str()) {
+            return Err(format!("kind must be one of: {}", BLOCKLIST_KINDS.join(", ")).into());
+        }
+        let value = normalize_blocklist_value(&req.kind, &req.value);
+        if value.is_empty() {
+            return Err("A value to block is required".into());
+        }
+        if req.reason.trim().is_empty() {
+            return Err("A reason is required".into());
+        }
+        let expires_at = match &req.expires_at {
+            Some(time) => Some(bson::DateTime::from_millis(chrono::DateTime::parse_from_rfc3339(time)?.timestamp_millis())),
+            None => None,
+        };
+
+        let mut entry = BlocklistEntry {
+            id: None,
+            kind: req.kind.clone(),
+            value,
+            reason: req.reason.trim().to_string(),
+            created_by: created_by.to_string(),
+            created_at: bson::DateTime::now(),
+            expires_at,
+        };
+        let result = match self.get_blocklist_collection().insert_one(&entry, None).await {
+            Ok(result) => result,
+            Err(e) if super::locks::is_duplicate_key(&e) => return Err(AppError::conflict("This value is already blocked")),
+            Err(e) => return Err(e.into()),
+        };
+        entry.id = result.inserted_id.as_object_id();
+        Ok(entry)
+    }
+
+    pub async fn remove_blocklist_entry(&self, entry_id: &str) -> Result<bool, AppError> {
+        let entry_oid = self.string_to_id(entry_id)?;
+        let result = self.get_blocklist_collection().delete_one(doc! { "_id": entry_oid }, None).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Looks the subjects of a request up in the blocklist, recording any refusal.
+    pub async fn check_blocklist(&self, context: &str, subjects: &[(&str, &str)], user_id: Option<ObjectId>) -> Result<Option<BlocklistEntry>, AppError> {
+        let candidates = blocklist_candidates(subjects);
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let entry = self.get_blocklist_collection().find_one(
+            doc! {
+                "$or": candidates,
+                "$and": [{ "$or": [
+                    { "expires_at": bson::Bson::Null },
+                    { "expires_at": { "$gt": bson::DateTime::now() } },
+                ] }],
+            },
+            None
+        ).await?;
+
+        if let Some(entry) = &entry {
+            let decision = EnforcementDecision {
+                id: None,
+                context: context.to_string(),
+                kind: entry.kind.clone(),
+                value: entry.value.clone(),
+                entry_id: entry.id,
+                user_id,
+                decision: "blocked".to_string(),
+                at: bson::DateTime::now(),
+            };
+            self.get_fraud_decisions_collection().insert_one(decision, None).await?;
+        }
+        Ok(entry)
+    }
+
+    /// Records a suspected enumeration attempt for the fraud team and the anomaly monitor.
+    pub async fn record_enumeration_suspected(&self, context: &str, kind: &str, value: &str) -> Result<(), AppError> {
+        let decision = EnforcementDecision {
+            id: None,
+            context: context.to_string(),
+            kind: kind.to_string(),
+            value: value.to_string(),
+            entry_id: None,
+            user_id: None,
+            decision: ENUMERATION_SUSPECTED.to_string(),
+            at: bson::DateTime::now(),
+        };
+        self.get_fraud_decisions_collection().insert_one(decision, None).await?;
+        self.record_monitoring_event(ENUMERATION_SUSPECTED, Some(format!("{} {} {}", context, kind, value))).await
+    }
+
+    /// Email on file for an account, checked against the blocklist on booking.
+    pub async fn user_blocklist_email(&self, user_id: &str) -> Result<Option<String>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let user = self.get_users_collection().find_one(doc! { "_id": user_oid }, None).await?;
+        Ok(user.map(|u| u.email))
+    }
+
+    pub async fn get_fraud_decisions(&self, limit: i64) -> Result<Vec<EnforcementDecision>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "at": -1 }).limit(limit).build();
+        let mut cursor = self.get_fraud_decisions_collection().find(None, find_options).await?;
+
+        let mut decisions = Vec::new();
+        while let Some(result) = cursor.next().await {
+            decisions.push(result?);
+        }
+        Ok(decisions)
+    }
+
+    /// Velocity and consistency signals for a booking attempt, scored.
+    pub async fn assess_booking_risk(&self, user_id: ObjectId, req: &CreateGroupBookingRequest, country: Option<&str>) -> Result<RiskAssessment, AppError> {
+        let now = chrono::Utc::now();
+        let user = self.get_users_collection().find_one(doc! { "_id": user_id }, None).await?;
+        let account_age_hours = user
+            .and_then(|u| u.created_at)
+            .map(|created| (now.timestamp_millis() - created.timestamp_millis()) / 3_600_000);
+
+        let hour_ago = bson::DateTime::from_millis((now - chrono::Duration::hours(1)).timestamp_millis());
+        let bookings_last_hour = self.get_bookings_collection()
+            .count_documents(doc! { "user_id": user_id, "booking_date": { "$gte": hour_ago } }, None)
+            .await?;
+
+        // On a group booking the payer only needs to be one of the passengers
+        let passenger_phones: Vec<String> = req.passengers.iter()
+            .filter_map(|p| p.phone.as_deref())
+            .map(|phone| normalize_blocklist_value("phone", phone))
+            .collect();
+        let payer_phone_mismatch = match req.payer_phone.as_deref() {
+            Some(payer) if !passenger_phones.is_empty() => !passenger_phones.contains(&normalize_blocklist_value("phone", payer)),
+            _ => false,
+        };
+
+        let day_ago = bson::DateTime::from_millis((now - chrono::Duration::hours(24)).timestamp_millis());
+        let failed_payments_24h = self.count_subject_events(PAYMENT_FAILED, &user_id.to_hex(), day_ago).await?;
+
+        Ok(FraudSignals {
+            account_age_hours,
+            bookings_last_hour,
+            payer_phone_mismatch,
+            failed_payments_24h,
+            foreign_country: country.filter(|c| GeoIpConfig::from_env().is_foreign(c)).map(str::to_string),
+        }.assess())
+    }
+
+    pub async fn get_review_queue(&self, tenant: &Tenant) -> Result<Vec<Booking>, AppError> {
+        let filter = self.scope_bookings_filter(tenant, doc! { "status": "PendingReview" }).await?;
+        let find_options = FindOptions::builder().sort(doc! { "risk.score": -1, "booking_date": 1 }).build();
+        let mut cursor = self.get_bookings_collection().find(filter, find_options).await?;
+
+        let mut bookings = Vec::new();
+        while let Some(result) = cursor.next().await {
+            bookings.push(result?);
+        }
+        Ok(bookings)
+    }
+
+    /// Confirms a held booking, or rejects it and gives the seat back.
+    pub async fn review_booking(&self, booking_id: &str, decision: &str, note: Option<String>) -> Result<(), AppError> {
+        let booking_oid = self.string_to_id(booking_id)?;
+        let booking = self.get_bookings_collection()
+            .find_one(doc! { "_id": booking_oid, "status": "PendingReview" }, None).await?
+            .ok_or_else(|| AppError::not_found("Booking not found or not awaiting review"))?;
+
+        match decision {
+            "approve" => {
+                // An approved booking still has to be paid for, unless it already was
+                let unpaid = MpesaConfig::from_env().payment_required && !booking.history.iter().any(|e| e.event == "paid");
+                let status = if unpaid { "PendingPayment" } else { "Confirmed" };
+                let event = BookingEvent::new("review_approved", "admin", note);
+                let result = self.get_bookings_collection().update_one(
+                    doc! { "_id": booking_oid, "status": "PendingReview" },
+                    doc! { "$set": { "status": status }, "$push": { "history": bson::to_bson(&event)? } },
+                    None
+                ).await?;
+                if result.modified_count == 0 {
+                    return Err("Booking is no longer awaiting review".into());
+                }
+                let message = if unpaid {
+                    format!("Your booking for seat {} on {} is approved. Pay for it to confirm it.", booking.seat_number, booking.travel_date)
+                } else {
+                    format!("Your booking for seat {} on {} is confirmed.", booking.seat_number, booking.travel_date)
+                };
+                self.notify_user(booking.user_id, if unpaid { "Booking approved" } else { "Booking confirmed" }, &message).await?;
+                if !unpaid {
+                    self.post_booking_to_ledger(booking_oid, booking.user_id).await;
+                    self.refresh_next_trip_quietly(booking.user_id).await;
+                    let confirmed = Booking { status: status.to_string(), ..booking };
+                    if let Err(e) = self.queue_booking_confirmation_sms(&confirmed, None).await {
+                        error!("Failed to queue booking confirmation SMS for {}: {}", booking_id, e);
+                    }
+                    if let Err(e) = self.queue_booking_confirmation_email(&confirmed, None).await {
+                        error!("Failed to queue booking confirmation email for {}: {}", booking_id, e);
+                    }
+                }
+            }
+            "reject" => {
+                self.cancel_booking(booking_id, &booking.user_id.to_hex(), "admin").await?;
+                self.notify_user(
+                    booking.user_id,
+                    "Booking not confirmed",
+                    &format!(
+                        "We couldn't confirm your booking for seat {} on {}. {}",
+                        booking.seat_number, booking.travel_date, note.unwrap_or_default()
+                    ),
+                ).await?;
+            }
+            _ => return Err("decision must be either 'approve' or 'reject'".into()),
+        }
+        Ok(())
+    }
+}
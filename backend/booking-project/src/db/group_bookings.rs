@@ -0,0 +1,272 @@
+use log::{error, warn};
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    error::{TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    ClientSession,
+};
+
+use super::availability::{free_place_filter, free_seat_filter};
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::{FraudConfig, InventoryConfig, MpesaConfig, TermsConfig};
+use crate::events::DomainEvent;
+use crate::models::booking::{BookingEvent, CreateGroupBookingRequest, NextOfKin};
+use crate::models::lookup::new_booking_reference;
+use crate::models::bus::{OPEN_SEATING, SEAT_BOOKED, SEAT_HELD};
+use crate::models::pass::{PassRedemption, PASS_ACTIVE};
+use crate::models::Booking;
+
+// Write conflicts with a concurrent booking abort the transaction; a retry then sees the seat taken
+const MAX_TRANSACTION_ATTEMPTS: u32 = 3;
+
+fn has_error_label(e: &AppError, label: &str) -> bool {
+    e.as_database().is_some_and(|e| e.contains_label(label))
+}
+
+impl MongoDB {
+    /// Books every seat in the request in one transaction: either all of them end up booked
+    /// with a booking each, or none do. A single seat booking is a group of one.
+    pub async fn create_group_booking(&self, user_id: &str, req: &CreateGroupBookingRequest, country: Option<&str>) -> Result<Vec<Booking>, AppError> {
+        req.validate()?;
+        let bus_id = self.string_to_id(&req.bus_id)?;
+        let user_oid = self.string_to_id(user_id)?;
+        let bus = self.get_bus(&req.bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        self.ensure_bus_runs(&bus, &req.travel_date).await?;
+        let by_headcount = InventoryConfig::from_env().sells_by_headcount(&bus.bus_type);
+        let seats = if by_headcount {
+            req.open_places()
+        } else if req.headcount.is_some() {
+            return Err("Seats on this bus are numbered, pick them with seat_numbers".into());
+        } else {
+            req.seats()
+        };
+        let next_of_kin = seats.iter()
+            .map(|(_, passenger)| passenger.as_ref().and_then(|p| p.next_of_kin.as_ref()).map(NextOfKin::validated).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if next_of_kin.iter().any(Option::is_none) && self.get_operator_settings(&bus.operator_name()).await?.next_of_kin_required {
+            return Err("This operator requires next of kin details for every passenger".into());
+        }
+        // Created up front: inserting them inside the transaction would conflict with every
+        // other first booking of the trip
+        if by_headcount {
+            self.open_headcount(&bus, &req.travel_date).await?;
+        } else {
+            for (seat_number, _) in &seats {
+                if !self.trip_has_seat(&bus, &req.travel_date, seat_number).await? {
+                    return Err(AppError::not_found(format!("Seat {} not found", seat_number)));
+                }
+            }
+            if !self.trip_seats_exist(bus_id, &req.travel_date).await? {
+                self.create_trip_seats(bus_id, &req.travel_date, bus.seat_labels()).await?;
+            }
+        }
+
+        let pass_id = match &req.pass_id {
+            Some(pass_id) => Some(self.usable_pass(user_oid, pass_id, &bus, &req.travel_date, seats.len()).await?),
+            None => None,
+        };
+
+        // Risky attempts wait for manual review
+        let policy = self.resolve_policy(&bus, &req.travel_date).await?;
+        let risk = self.assess_booking_risk(user_oid, req, country).await?;
+        let needs_review = risk.score >= FraudConfig::from_env().review_threshold;
+        let mut history = vec![BookingEvent::new("created", &format!("user:{}", user_id), None)];
+        if let Some(pass_id) = pass_id {
+            history.push(BookingEvent::new("paid", &format!("user:{}", user_id), Some(format!("Ride on commuter pass {}", pass_id))));
+        }
+        if needs_review {
+            history.push(BookingEvent::new("held_for_review", "system", Some(risk.reasons.join("; "))));
+        }
+        // Without a review hold the seats are kept for the customer to pay, when payment is taken
+        let status = if needs_review {
+            "PendingReview"
+        } else if pass_id.is_none() && MpesaConfig::from_env().payment_required {
+            "PendingPayment"
+        } else {
+            "Confirmed"
+        };
+        let bookings = seats.into_iter().map(|(seat_number, passenger)| Ok(Booking {
+            id: None,
+            user_id: user_oid,
+            bus_id,
+            seat_number,
+            travel_date: req.travel_date.clone(),
+            booking_date: bson::DateTime::now(),
+            status: status.to_string(),
+            passenger,
+            history: history.clone(),
+            terms_version: Some(TermsConfig::from_env().version),
+            policy_version: Some(policy.version()),
+            risk: Some(risk.clone()),
+            country: country.map(str::to_string),
+            reference: Some(new_booking_reference()?),
+        })).collect::<Result<Vec<_>, String>>()?;
+        let bookings = self.book_seats(user_oid, bookings, pass_id).await?;
+
+        for (booking, next_of_kin) in bookings.iter().zip(&next_of_kin) {
+            let (Some(booking_id), Some(next_of_kin)) = (booking.id, next_of_kin) else {
+                continue;
+            };
+            if let Err(e) = self.store_next_of_kin(booking_id, next_of_kin).await {
+                self.unbook_seats(&bookings).await?;
+                return Err(e);
+            }
+        }
+
+        for booking in &bookings {
+            self.publish_event(DomainEvent::BookingCreated { booking: booking.into() }).await;
+        }
+
+        if needs_review {
+            let ids: Vec<String> = bookings.iter().filter_map(|b| b.id).map(|id| id.to_hex()).collect();
+            self.notify_admins(
+                "Booking held for review",
+                &format!("Booking {} scored {} for fraud risk", ids.join(", "), risk.score),
+            ).await?;
+        }
+
+        // Book the operator's share for the next settlement
+        for booking in &bookings {
+            if let Err(e) = self.record_booking_split(booking, &bus).await {
+                error!("Failed to record financial split for booking {:?}: {}", booking.id, e);
+            }
+        }
+        Ok(bookings)
+    }
+
+    /// Claims the seats, inserts the bookings and takes their rides off the pass, if any, in one
+    /// transaction, retrying it when it lost a write conflict.
+    async fn book_seats(&self, user_id: ObjectId, mut bookings: Vec<Booking>, pass_id: Option<ObjectId>) -> Result<Vec<Booking>, AppError> {
+        let mut session = self.start_session().await?;
+        let mut attempt = 1;
+        loop {
+            session.start_transaction(None).await?;
+            let result = match self.claim_and_insert(&mut session, user_id, &mut bookings, pass_id).await {
+                Ok(()) => self.commit(&mut session).await,
+                Err(e) => {
+                    if let Err(abort) = session.abort_transaction().await {
+                        warn!("Failed to abort booking transaction: {}", abort);
+                    }
+                    Err(e)
+                }
+            };
+            match result {
+                Ok(()) => return Ok(bookings),
+                Err(e) if attempt < MAX_TRANSACTION_ATTEMPTS && has_error_label(&e, TRANSIENT_TRANSACTION_ERROR) => {
+                    warn!("Booking transaction conflicted, retrying (attempt {}): {}", attempt, e);
+                    attempt += 1;
+                }
+                // Most likely a reference another booking already has; new ones are drawn for the retry
+                Err(e) if attempt < MAX_TRANSACTION_ATTEMPTS && e.as_database().is_some_and(is_duplicate_key) => {
+                    for booking in bookings.iter_mut() {
+                        booking.reference = Some(new_booking_reference()?);
+                    }
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn commit(&self, session: &mut ClientSession) -> Result<(), AppError> {
+        loop {
+            match session.commit_transaction().await {
+                Ok(()) => return Ok(()),
+                // The commit itself is safe to retry until the server says how it went
+                Err(e) if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    async fn claim_and_insert(&self, session: &mut ClientSession, user_id: ObjectId, bookings: &mut [Booking], pass_id: Option<ObjectId>) -> Result<(), AppError> {
+        let now = bson::DateTime::now();
+        let holds = self.get_seat_holds_collection();
+        let seats = self.get_trip_seats_collection();
+        let availability = self.get_seat_availability_collection();
+        let collection = self.get_bookings_collection();
+        for booking in bookings.iter_mut() {
+            if booking.seat_number == OPEN_SEATING {
+                let claimed = availability.update_one_with_session(
+                    free_place_filter(booking.bus_id, &booking.travel_date),
+                    doc! { "$inc": { "headcount": 1 } },
+                    None,
+                    session
+                ).await?;
+                if claimed.matched_count == 0 {
+                    return Err(AppError::conflict("No places are left on this trip"));
+                }
+                let result = collection.insert_one_with_session(&*booking, None, session).await?;
+                booking.id = result.inserted_id.as_object_id();
+                continue;
+            }
+
+            // A live hold from this user already has the seat set aside for them
+            let hold = holds.find_one_and_delete_with_session(
+                doc! {
+                    "user_id": user_id,
+                    "bus_id": booking.bus_id,
+                    "travel_date": &booking.travel_date,
+                    "seat_number": &booking.seat_number,
+                    "expires_at": { "$gt": now },
+                },
+                None,
+                session
+            ).await?;
+            let mut filter = match hold {
+                Some(hold) => doc! { "state": SEAT_HELD, "hold_expiry": hold.expires_at },
+                None => free_seat_filter(now),
+            };
+            filter.insert("bus_id", booking.bus_id);
+            filter.insert("travel_date", &booking.travel_date);
+            filter.insert("seat_number", &booking.seat_number);
+
+            let claimed = seats.update_one_with_session(
+                filter,
+                doc! { "$set": { "state": SEAT_BOOKED }, "$unset": { "hold_expiry": "" } },
+                None,
+                session
+            ).await?;
+            if claimed.matched_count == 0 {
+                return Err(AppError::conflict(format!("Seat {} is already booked", booking.seat_number)));
+            }
+
+            let result = collection.insert_one_with_session(&*booking, None, session).await?;
+            booking.id = result.inserted_id.as_object_id();
+        }
+
+        if let Some(pass_id) = pass_id {
+            let redemptions: Vec<PassRedemption> = bookings.iter()
+                .filter_map(|b| b.id)
+                .map(|booking_id| PassRedemption { booking_id, at: now })
+                .collect();
+            let rides = bookings.len() as i32;
+            // Checked again here: another booking may have used the last rides meanwhile
+            let used = self.get_passes_collection().update_one_with_session(
+                doc! { "_id": pass_id, "user_id": user_id, "status": PASS_ACTIVE, "rides_left": { "$gte": rides }, "expires_at": { "$gt": now } },
+                doc! { "$inc": { "rides_left": -rides }, "$push": { "redemptions": { "$each": bson::to_bson(&redemptions)? } } },
+                None,
+                session
+            ).await?;
+            if used.matched_count == 0 {
+                return Err("Your pass doesn't have enough rides left".into());
+            }
+        }
+        Ok(())
+    }
+
+    // Undoes a committed group booking whose follow-up writes failed
+    pub(super) async fn unbook_seats(&self, bookings: &[Booking]) -> Result<(), AppError> {
+        let ids: Vec<ObjectId> = bookings.iter().filter_map(|b| b.id).collect();
+        for booking_id in &ids {
+            self.return_pass_ride(*booking_id).await?;
+        }
+        self.get_bookings_collection().delete_many(doc! { "_id": { "$in": ids } }, None).await?;
+        for booking in bookings {
+            self.release_seat(booking.bus_id, &booking.travel_date, &booking.seat_number).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, spec::BinarySubtype},
+    options::{ReplaceOptions, UpdateOptions},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::GtfsConfig;
+use crate::models::bus::local_offset;
+use crate::models::gtfs::{build_gtfs, GtfsFeed, GtfsStop, GtfsStopRequest, GtfsStopResponse};
+use crate::models::gtfs_import::{plan_gtfs_import, GtfsImportQuery, GtfsImportReport, GtfsIssue};
+use crate::models::terminal::terminal_id;
+use crate::models::zip::read_zip;
+use crate::models::Bus;
+
+const GTFS_FEED_ID: &str = "gtfs";
+
+impl MongoDB {
+    fn get_gtfs_stops_collection(&self) -> Collection<GtfsStop> {
+        self.database().collection("gtfs_stops")
+    }
+
+    // Shares the collection with the route feed snapshot
+    fn get_gtfs_feed_collection(&self) -> Collection<GtfsFeed> {
+        self.database().collection("feeds")
+    }
+
+    async fn fleet(&self) -> Result<Vec<Bus>, AppError> {
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            buses.push(bus?);
+        }
+        Ok(buses)
+    }
+
+    async fn gtfs_stops(&self) -> Result<HashMap<String, GtfsStop>, AppError> {
+        let mut cursor = self.get_gtfs_stops_collection().find(None, None).await?;
+        let mut stops = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            let stop = result?;
+            stops.insert(stop.id.clone(), stop);
+        }
+        Ok(stops)
+    }
+
+    /// Every terminal the fleet serves, with the coordinates entered for it so far.
+    pub async fn list_gtfs_stops(&self) -> Result<Vec<GtfsStopResponse>, AppError> {
+        let mut stops = self.gtfs_stops().await?;
+        let mut terminals = BTreeMap::new();
+        for bus in self.fleet().await? {
+            for city in [bus.route.from, bus.route.to] {
+                terminals.entry(terminal_id(&city)).or_insert(city);
+            }
+        }
+        Ok(terminals.into_iter().map(|(id, city)| {
+            let stop = stops.remove(&id);
+            GtfsStopResponse {
+                name: stop.as_ref().map_or(city, |s| s.name.clone()),
+                lat: stop.as_ref().map(|s| s.lat),
+                lon: stop.as_ref().map(|s| s.lon),
+                id,
+            }
+        }).collect())
+    }
+
+    pub async fn set_gtfs_stop(&self, stop_id: &str, req: GtfsStopRequest, actor: &str) -> Result<GtfsStop, AppError> {
+        req.validate()?;
+        let id = terminal_id(stop_id);
+        let name = match req.name.filter(|n| !n.trim().is_empty()) {
+            Some(name) => name.trim().to_string(),
+            None => self.list_gtfs_stops().await?
+                .into_iter()
+                .find(|s| s.id == id)
+                .map(|s| s.name)
+                .ok_or_else(|| AppError::not_found("No route serves this terminal"))?,
+        };
+        let stop = GtfsStop { id: id.clone(), name, lat: req.lat, lon: req.lon, updated_at: bson::DateTime::now() };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_gtfs_stops_collection().replace_one(doc! { "_id": &id }, &stop, options).await?;
+        self.record_audit("gtfs_stop.updated", actor, &id, Some(format!("{:.6},{:.6}", stop.lat, stop.lon))).await?;
+        Ok(stop)
+    }
+
+    pub async fn get_gtfs_feed(&self) -> Result<Option<GtfsFeed>, AppError> {
+        Ok(self.get_gtfs_feed_collection().find_one(doc! { "_id": GTFS_FEED_ID }, None).await?)
+    }
+
+    /// Exports the fleet's schedules from today onwards and stores the archive as the
+    /// published feed.
+    pub async fn refresh_gtfs_feed(&self) -> Result<GtfsFeed, AppError> {
+        let config = GtfsConfig::from_env();
+        let now = chrono::Utc::now();
+        let local = now.with_timezone(&local_offset()).naive_local();
+        let start = local.date();
+        let end = start + chrono::Duration::days(config.service_days - 1);
+
+        let mut cursor = self.get_trip_status_collection().find(
+            doc! {
+                "status": "cancelled",
+                "travel_date": { "$gte": start.format("%Y-%m-%d").to_string(), "$lte": end.format("%Y-%m-%d").to_string() },
+            },
+            None
+        ).await?;
+        let mut cancellations = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let status = result?;
+            cancellations.push((status.bus_id, status.travel_date));
+        }
+
+        let export = build_gtfs(&self.fleet().await?, &self.gtfs_stops().await?, &cancellations, start, &config);
+        let feed = GtfsFeed {
+            id: GTFS_FEED_ID.to_string(),
+            data: bson::Binary { subtype: BinarySubtype::Generic, bytes: export.to_zip(local) },
+            trips: export.trips as i64,
+            missing_stops: export.missing_stops,
+            generated_at: bson::DateTime::from_millis(now.timestamp_millis()),
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_gtfs_feed_collection().replace_one(doc! { "_id": GTFS_FEED_ID }, &feed, options).await?;
+        Ok(feed)
+    }
+
+    /// Creates schedules from an uploaded GTFS archive. Trips already imported are left alone, as
+    /// are coordinates already entered for a terminal. A dry run reports what would be created.
+    pub async fn import_gtfs(&self, archive: &[u8], query: &GtfsImportQuery, actor: &str) -> Result<GtfsImportReport, AppError> {
+        // Feeds are sometimes zipped with their folder; the tables are what matter
+        let files = read_zip(archive)?
+            .into_iter()
+            .map(|(name, contents)| (name.rsplit('/').next().unwrap_or_default().to_string(), String::from_utf8_lossy(&contents).to_string()))
+            .collect();
+        let mut plan = plan_gtfs_import(&files, query)?;
+
+        let bus_numbers: Vec<&str> = plan.buses.iter().map(|b| b.bus_number.as_str()).collect();
+        let mut cursor = self.get_buses_collection().find(doc! { "bus_number": { "$in": bus_numbers } }, None).await?;
+        let mut existing = Vec::new();
+        while let Some(bus) = cursor.next().await {
+            existing.push(bus?.bus_number);
+        }
+        if !existing.is_empty() {
+            plan.report.skipped.extend(existing.iter().map(|n| GtfsIssue {
+                file: "trips.txt".to_string(),
+                row: None,
+                message: format!("{} already exists", n),
+            }));
+            plan.buses.retain(|b| !existing.contains(&b.bus_number));
+            plan.report.buses.retain(|b| !existing.contains(&b.bus_number));
+        }
+        let known_stops = self.gtfs_stops().await?;
+        plan.stops.retain(|s| !known_stops.contains_key(&s.id));
+        plan.report.stops_added = plan.stops.len();
+        if query.dry_run {
+            return Ok(plan.report);
+        }
+
+        let inserted = self.insert_batched(&self.get_buses_collection(), plan.buses).await?;
+        plan.report.inserted = inserted.inserted;
+        plan.report.failed = inserted.failed;
+        let options = UpdateOptions::builder().upsert(true).build();
+        for stop in &plan.stops {
+            self.get_gtfs_stops_collection().update_one(
+                doc! { "_id": &stop.id },
+                doc! { "$setOnInsert": bson::to_document(stop)? },
+                options.clone()
+            ).await?;
+        }
+        self.record_audit(
+            "gtfs.imported",
+            actor,
+            &plan.report.agencies.join(", "),
+            Some(format!("{} schedules created, {} trips skipped", plan.report.inserted, plan.report.skipped.len()))
+        ).await?;
+        Ok(plan.report)
+    }
+}
@@ -0,0 +1,169 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::InventoryConfig;
+use crate::models::booking::MAX_SEATS_PER_BOOKING;
+use crate::models::hold::{CreateHoldRequest, HoldOwner, SeatHold, HOLD_MINUTES};
+
+fn owner_filter(owner: &HoldOwner) -> Document {
+    match owner {
+        HoldOwner::User(user_id) => doc! { "user_id": user_id },
+        HoldOwner::Anonymous(anon_id) => doc! { "anon_id": anon_id },
+    }
+}
+
+impl MongoDB {
+    pub(super) fn get_seat_holds_collection(&self) -> Collection<SeatHold> {
+        self.database().collection("seat_holds")
+    }
+
+    async fn hold_one_seat(&self, owner: &HoldOwner, req: &CreateHoldRequest, seat_number: &str) -> Result<SeatHold, AppError> {
+        let bus = self.get_bus(&req.bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        self.ensure_bus_runs(&bus, &req.travel_date).await?;
+        if InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) {
+            return Err("Places on this bus aren't numbered, book them directly".into());
+        }
+        if !self.trip_has_seat(&bus, &req.travel_date, seat_number).await? {
+            return Err(AppError::not_found("Seat not found"));
+        }
+        // One session can't sit on a whole bus; a hold is only ever the start of one booking
+        let mut own_holds = owner_filter(owner);
+        own_holds.insert("bus_id", bus_id);
+        own_holds.insert("travel_date", &req.travel_date);
+        own_holds.insert("expires_at", doc! { "$gt": bson::DateTime::now() });
+        if self.get_seat_holds_collection().count_documents(own_holds, None).await? >= MAX_SEATS_PER_BOOKING as u64 {
+            return Err(AppError::conflict(format!("At most {} seats can be held on a trip at once", MAX_SEATS_PER_BOOKING)));
+        }
+
+        let lock_name = format!("seat:{}:{}:{}", req.bus_id, req.travel_date, seat_number);
+        let _seat_lock = self.try_lock(&lock_name, std::time::Duration::from_secs(30)).await?
+            .ok_or_else(|| AppError::conflict("This seat is being booked by someone else, please try again"))?;
+        let expires_at = chrono::Utc::now() + chrono::Duration::minutes(HOLD_MINUTES);
+        let expires_at = bson::DateTime::from_millis(expires_at.timestamp_millis());
+        if !self.hold_seat(&bus, &req.travel_date, seat_number, expires_at).await? {
+            return Err(AppError::conflict("Seat is already booked"));
+        }
+
+        let (user_id, anon_id) = match owner {
+            HoldOwner::User(user_id) => (Some(*user_id), None),
+            HoldOwner::Anonymous(anon_id) => (None, Some(anon_id.clone())),
+        };
+        let mut hold = SeatHold {
+            id: None,
+            bus_id,
+            travel_date: req.travel_date.clone(),
+            seat_number: seat_number.to_string(),
+            user_id,
+            anon_id,
+            expires_at,
+            created_at: bson::DateTime::now(),
+        };
+        let result = match self.get_seat_holds_collection().insert_one(&hold, None).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.release_held_seat(bus_id, &req.travel_date, seat_number, expires_at).await?;
+                return Err(e.into());
+            }
+        };
+        hold.id = result.inserted_id.as_object_id();
+        Ok(hold)
+    }
+
+    /// Holds every seat in the request, or none: seats held before one fails are let go again.
+    pub async fn create_holds(&self, owner: &HoldOwner, req: &CreateHoldRequest) -> Result<Vec<SeatHold>, AppError> {
+        req.validate()?;
+        let mut holds = Vec::new();
+        for seat in req.seats() {
+            match self.hold_one_seat(owner, req, &seat).await {
+                Ok(hold) => holds.push(hold),
+                Err(e) => {
+                    for hold in &holds {
+                        if let Some(id) = hold.id {
+                            self.release_hold(owner, &id.to_hex()).await?;
+                        }
+                    }
+                    return Err(e.prefixed(&format!("Seat {}", seat)));
+                }
+            }
+        }
+        Ok(holds)
+    }
+
+    /// The user's holds among `hold_ids` that haven't run out yet.
+    pub async fn get_live_holds(&self, user_id: ObjectId, hold_ids: &[String]) -> Result<Vec<SeatHold>, AppError> {
+        let ids: Vec<ObjectId> = hold_ids.iter().filter_map(|id| ObjectId::parse_str(id).ok()).collect();
+        let mut cursor = self.get_seat_holds_collection().find(
+            doc! { "_id": { "$in": ids }, "user_id": user_id, "expires_at": { "$gt": bson::DateTime::now() } },
+            None
+        ).await?;
+
+        let mut holds = Vec::new();
+        while let Some(result) = cursor.next().await {
+            holds.push(result?);
+        }
+        Ok(holds)
+    }
+
+    pub async fn get_holds(&self, owner: &HoldOwner) -> Result<Vec<SeatHold>, AppError> {
+        let mut filter = owner_filter(owner);
+        filter.insert("expires_at", doc! { "$gt": bson::DateTime::now() });
+        let mut cursor = self.get_seat_holds_collection().find(filter, None).await?;
+
+        let mut holds = Vec::new();
+        while let Some(result) = cursor.next().await {
+            holds.push(result?);
+        }
+        Ok(holds)
+    }
+
+    pub async fn release_hold(&self, owner: &HoldOwner, hold_id: &str) -> Result<bool, AppError> {
+        let mut filter = owner_filter(owner);
+        filter.insert("_id", self.string_to_id(hold_id)?);
+        match self.get_seat_holds_collection().find_one_and_delete(filter, None).await? {
+            Some(hold) => {
+                self.release_held_seat(hold.bus_id, &hold.travel_date, &hold.seat_number, hold.expires_at).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Hands an anonymous session's holds to the account that just signed in.
+    pub async fn migrate_anonymous_holds(&self, anon_id: &str, user_id: &str) -> Result<u64, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let result = self.get_seat_holds_collection().update_many(
+            doc! { "anon_id": anon_id, "expires_at": { "$gt": bson::DateTime::now() } },
+            doc! { "$set": { "user_id": user_oid, "anon_id": bson::Bson::Null } },
+            None
+        ).await?;
+        if result.modified_count > 0 {
+            info!("Moved {} seat hold(s) from anonymous session to user {}", result.modified_count, user_id);
+        }
+        Ok(result.modified_count)
+    }
+
+    /// Frees seats whose holds ran out.
+    pub async fn sweep_expired_holds(&self) -> Result<u64, AppError> {
+        let collection = self.get_seat_holds_collection();
+        let mut released = 0;
+        loop {
+            let expired = collection.find_one_and_delete(
+                doc! { "expires_at": { "$lte": bson::DateTime::now() } },
+                None
+            ).await?;
+            let Some(hold) = expired else {
+                break;
+            };
+            self.release_held_seat(hold.bus_id, &hold.travel_date, &hold.seat_number, hold.expires_at).await?;
+            released += 1;
+        }
+        Ok(released)
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ReadWorkload;
+use crate::models::incident::{
+    is_high_severity, Incident, IncidentAttachment, IncidentQuery, IncidentReport, OperatorIncidentStats,
+    ReportIncidentRequest, INCIDENT_KINDS, INCIDENT_SEVERITIES,
+};
+
+fn check_attachment(attachment: &IncidentAttachment) -> Result<(), AppError> {
+    if !attachment.url.starts_with("https://") {
+        return Err("Attachments must be https:// links to uploaded media".into());
+    }
+    Ok(())
+}
+
+impl MongoDB {
+    fn get_incidents_collection(&self) -> Collection<Incident> {
+        self.database().collection("incidents")
+    }
+
+    pub async fn report_incident(&self, bus_id: &str, travel_date: &str, req: &ReportIncidentRequest, reported_by: &str) -> Result<Incident, AppError> {
+        if !INCIDENT_KINDS.contains(&req.kind.as_str()) {
+            return Err(format!("kind must be one of: {}", INCIDENT_KINDS.join(", ")).into());
+        }
+        if !INCIDENT_SEVERITIES.contains(&req.severity.as_str()) {
+            return Err(format!("severity must be one of: {}", INCIDENT_SEVERITIES.join(", ")).into());
+        }
+        if req.description.trim().is_empty() {
+            return Err("Description is required".into());
+        }
+        for attachment in &req.attachments {
+            check_attachment(attachment)?;
+        }
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let occurred_at = match &req.occurred_at {
+            Some(time) => bson::DateTime::from_millis(chrono::DateTime::parse_from_rfc3339(time)?.timestamp_millis()),
+            None => bson::DateTime::now(),
+        };
+        let now = bson::DateTime::now();
+        let mut incident = Incident {
+            id: None,
+            bus_id: bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?,
+            operator: bus.operator_name(),
+            travel_date: travel_date.to_string(),
+            kind: req.kind.clone(),
+            severity: req.severity.clone(),
+            description: req.description.trim().to_string(),
+            location: req.location.clone(),
+            attachments: req.attachments.clone(),
+            reported_by: reported_by.to_string(),
+            status: "Open".to_string(),
+            resolution: None,
+            occurred_at,
+            created_at: now,
+            updated_at: now,
+        };
+        let result = self.get_incidents_collection().insert_one(&incident, None).await?;
+        incident.id = result.inserted_id.as_object_id();
+
+        if is_high_severity(&incident.severity) {
+            self.notify_admins(
+                &format!("{} incident: {}", incident.severity.to_uppercase(), incident.kind),
+                &format!(
+                    "{} on {} ({} → {}){}: {}",
+                    bus.bus_number,
+                    travel_date,
+                    bus.route.from,
+                    bus.route.to,
+                    incident.location.as_ref().map(|l| format!(" near {}", l)).unwrap_or_default(),
+                    incident.description
+                ),
+            ).await?;
+        }
+        Ok(incident)
+    }
+
+    pub async fn get_incidents(&self, query: &IncidentQuery) -> Result<Vec<Incident>, AppError> {
+        let mut filter = Document::new();
+        if let Some(operator) = &query.operator {
+            filter.insert("operator", operator);
+        }
+        if let Some(severity) = &query.severity {
+            filter.insert("severity", severity);
+        }
+        if let Some(status) = &query.status {
+            filter.insert("status", status);
+        }
+        if let Some(days) = query.days {
+            let since = chrono::Utc::now() - chrono::Duration::days(days.max(1));
+            filter.insert("occurred_at", doc! { "$gte": bson::DateTime::from_millis(since.timestamp_millis()) });
+        }
+
+        let find_options = FindOptions::builder().sort(doc! { "occurred_at": -1 }).build();
+        let mut cursor = self.get_incidents_collection().find(filter, find_options).await?;
+
+        let mut incidents = Vec::new();
+        while let Some(result) = cursor.next().await {
+            incidents.push(result?);
+        }
+        Ok(incidents)
+    }
+
+    pub async fn add_incident_attachment(&self, incident_id: &str, attachment: &IncidentAttachment) -> Result<Incident, AppError> {
+        check_attachment(attachment)?;
+        let incident_oid = self.string_to_id(incident_id)?;
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let incident = self.get_incidents_collection().find_one_and_update(
+            doc! { "_id": incident_oid },
+            doc! {
+                "$push": { "attachments": bson::to_bson(attachment)? },
+                "$set": { "updated_at": bson::DateTime::now() },
+            },
+            options
+        ).await?;
+        incident.ok_or_else(|| AppError::not_found("Incident not found"))
+    }
+
+    pub async fn resolve_incident(&self, incident_id: &str, resolution: &str) -> Result<Incident, AppError> {
+        let incident_oid = self.string_to_id(incident_id)?;
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let incident = self.get_incidents_collection().find_one_and_update(
+            doc! { "_id": incident_oid, "status": "Open" },
+            doc! { "$set": { "status": "Resolved", "resolution": resolution, "updated_at": bson::DateTime::now() } },
+            options
+        ).await?;
+        incident.ok_or_else(|| AppError::not_found("Incident not found or already resolved"))
+    }
+
+    /// Incident counts per operator by severity and kind, for operator analytics.
+    pub async fn get_incident_report(&self, days: i64) -> Result<IncidentReport, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let pipeline = vec![
+            doc! { "$match": { "occurred_at": { "$gte": since } } },
+            doc! { "$group": {
+                "_id": { "operator": "$operator", "severity": "$severity", "kind": "$kind", "status": "$status" },
+                "count": { "$sum": 1 },
+            } },
+        ];
+        let mut groups = self.collection_for::<Document>("incidents", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+
+        let mut operators: BTreeMap<String, OperatorIncidentStats> = BTreeMap::new();
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let key = group.get_document("_id")?;
+            let count = group.get_i32("count").map(i64::from).unwrap_or(0);
+            let operator = key.get_str("operator").unwrap_or_default().to_string();
+            let stats = operators.entry(operator.clone()).or_insert_with(|| OperatorIncidentStats { operator, ..Default::default() });
+            stats.total += count;
+            if key.get_str("status") == Ok("Open") {
+                stats.open += count;
+            }
+            *stats.by_severity.entry(key.get_str("severity").unwrap_or_default().to_string()).or_default() += count;
+            *stats.by_kind.entry(key.get_str("kind").unwrap_or_default().to_string()).or_default() += count;
+        }
+
+        Ok(IncidentReport {
+            days,
+            operators: operators.into_values().collect(),
+        })
+    }
+}
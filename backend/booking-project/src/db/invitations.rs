@@ -0,0 +1,228 @@
+use futures::StreamExt;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::invitation::{
+    AcceptInvitationRequest, CreateInvitationRequest, Invitation, InvitationClaims, INVITABLE_ROLES, INVITATION_DAYS,
+};
+use crate::models::outbox::OutboxMessage;
+use crate::models::tenancy::{Tenant, OPERATOR_SCOPED_ROLES};
+use crate::models::User;
+
+const INVITE_SCOPE: &str = "staff:invite";
+
+fn sign(invitation_id: &str) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = InvitationClaims {
+        invitation_id: invitation_id.to_string(),
+        scope: INVITE_SCOPE.to_string(),
+        exp: (chrono::Utc::now() + chrono::Duration::days(INVITATION_DAYS)).timestamp() as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref()))
+}
+
+fn verify(token: &str) -> Result<String, AppError> {
+    let claims = decode::<InvitationClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &Validation::new(Algorithm::HS256),
+    )?.claims;
+    if claims.scope != INVITE_SCOPE {
+        return Err("Invalid invitation".into());
+    }
+    Ok(claims.invitation_id)
+}
+
+impl MongoDB {
+    fn get_invitations_collection(&self) -> Collection<Invitation> {
+        self.database().collection("invitations")
+    }
+
+    /// Invites someone to a staff role and emails them a link to accept it.
+    pub async fn create_invitation(&self, tenant: &Tenant, invited_by: &str, req: &CreateInvitationRequest) -> Result<Invitation, AppError> {
+        let email = req.email.trim().to_lowercase();
+        if !email.contains('@') {
+            return Err("A valid email address is required".into());
+        }
+
+        let operator = match tenant {
+            Tenant::Operator(own) => {
+                if !INVITABLE_ROLES.contains(&req.role.as_str()) {
+                    return Err(format!("role must be one of: {}", INVITABLE_ROLES.join(", ")).into());
+                }
+                Some(own.clone())
+            }
+            Tenant::Platform => {
+                if !INVITABLE_ROLES.contains(&req.role.as_str()) && req.role != "operator_admin" {
+                    return Err(format!("role must be one of: {}, operator_admin", INVITABLE_ROLES.join(", ")).into());
+                }
+                let operator = req.operator.as_deref().map(str::trim).filter(|o| !o.is_empty()).map(str::to_string);
+                if operator.is_none() && OPERATOR_SCOPED_ROLES.contains(&req.role.as_str()) {
+                    return Err(format!("An operator is required for the {} role", req.role).into());
+                }
+                operator
+            }
+        };
+
+        if let Some(user) = self.get_users_collection().find_one(doc! { "email": &email }, None).await? {
+            if user.role == "admin" {
+                return Err(AppError::conflict("That account is already a platform admin"));
+            }
+        }
+
+        // A newer invitation for the same person and operator replaces the old one
+        let collection = self.get_invitations_collection();
+        collection.update_many(
+            doc! { "email": &email, "operator": &operator, "status": "pending" },
+            doc! { "$set": { "status": "revoked" } },
+            None
+        ).await?;
+
+        let expires_at = chrono::Utc::now() + chrono::Duration::days(INVITATION_DAYS);
+        let mut invitation = Invitation {
+            id: None,
+            email: email.clone(),
+            role: req.role.clone(),
+            operator: operator.clone(),
+            invited_by: self.string_to_id(invited_by)?,
+            status: "pending".to_string(),
+            expires_at: bson::DateTime::from_millis(expires_at.timestamp_millis()),
+            created_at: bson::DateTime::now(),
+            accepted_at: None,
+            user_id: None,
+        };
+        let result = collection.insert_one(&invitation, None).await?;
+        invitation.id = result.inserted_id.as_object_id();
+        let token = sign(&invitation.id.ok_or_else(|| AppError::internal("Failed to create invitation"))?.to_hex())?;
+
+        let base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+        let team = operator.as_deref().unwrap_or("Burudani Mint Travels");
+        self.enqueue_outbox(OutboxMessage::new(
+            "email",
+            &email,
+            Some(format!("You've been invited to join {}", team)),
+            format!(
+                "Hi,\n\nYou've been invited to join {} as {}. Accept the invitation here:\n{}/api/invitations/{}\n\nThe invitation expires in {} days.",
+                team, req.role.replace('_', " "), base_url.trim_end_matches('/'), token, INVITATION_DAYS
+            ),
+        )).await?;
+
+        info!("Invitation sent to {} for role {}", email, req.role);
+        Ok(invitation)
+    }
+
+    pub async fn get_pending_invitations(&self, tenant: &Tenant) -> Result<Vec<Invitation>, AppError> {
+        let mut filter = doc! { "status": "pending", "expires_at": { "$gt": bson::DateTime::now() } };
+        if let Tenant::Operator(operator) = tenant {
+            filter.insert("operator", operator);
+        }
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_invitations_collection().find(filter, find_options).await?;
+
+        let mut invitations = Vec::new();
+        while let Some(result) = cursor.next().await {
+            invitations.push(result?);
+        }
+        Ok(invitations)
+    }
+
+    pub async fn revoke_invitation(&self, tenant: &Tenant, invitation_id: &str) -> Result<(), AppError> {
+        let id = self.string_to_id(invitation_id)?;
+        let collection = self.get_invitations_collection();
+        let invitation = collection.find_one(doc! { "_id": id, "status": "pending" }, None).await?
+            .filter(|i| *tenant == Tenant::Platform || i.operator.as_deref().is_some_and(|o| tenant.owns_operator(o)))
+            .ok_or_else(|| AppError::not_found("Invitation not found"))?;
+
+        collection.update_one(
+            doc! { "_id": invitation.id, "status": "pending" },
+            doc! { "$set": { "status": "revoked" } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// The invitation behind an emailed link, while it can still be accepted.
+    pub async fn get_open_invitation(&self, token: &str) -> Result<Invitation, AppError> {
+        let id = self.string_to_id(&verify(token)?)?;
+        let invitation = self.get_invitations_collection().find_one(doc! { "_id": id }, None).await?
+            .ok_or("Invalid invitation")?;
+        if invitation.status != "pending" {
+            return Err(format!("This invitation has been {}", invitation.status).into());
+        }
+        if invitation.expires_at < bson::DateTime::now() {
+            return Err("This invitation has expired".into());
+        }
+        Ok(invitation)
+    }
+
+    /// Links the invited role to an existing account, or creates one; either way old sessions end.
+    pub async fn accept_invitation(&self, req: &AcceptInvitationRequest) -> Result<Invitation, AppError> {
+        let mut invitation = self.get_open_invitation(&req.token).await?;
+        let users = self.get_users_collection();
+        let now = bson::DateTime::now();
+
+        let user_id: ObjectId = match users.find_one(doc! { "email": &invitation.email }, None).await? {
+            Some(user) if user.role == "admin" => return Err(AppError::conflict("That account is already a platform admin")),
+            Some(user) => {
+                let user_id = user.id.ok_or_else(|| AppError::internal("User ID not found"))?;
+                users.update_one(
+                    doc! { "_id": user_id },
+                    doc! { "$set": {
+                        "role": &invitation.role,
+                        "operator": &invitation.operator,
+                        "updated_at": now,
+                        "sessions_revoked_at": now,
+                    } },
+                    None
+                ).await?;
+                user_id
+            }
+            None => {
+                let username = req.username.as_deref().map(str::trim).filter(|u| !u.is_empty())
+                    .ok_or("A username is required to create your account")?;
+                let password = req.password.as_deref().filter(|p| p.len() >= 8)
+                    .ok_or("A password of at least 8 characters is required")?;
+                let user = User {
+                    id: None,
+                    username: username.to_string(),
+                    email: invitation.email.clone(),
+                    password: bcrypt::hash(password, bcrypt::DEFAULT_COST)?,
+                    role: invitation.role.clone(),
+                    created_at: Some(now),
+                    updated_at: Some(now),
+                    terms_acceptances: Vec::new(),
+                    operator: invitation.operator.clone(),
+                    sessions_revoked_at: None,
+                    language: None,
+                    last_login_at: Some(bson::DateTime::now()),
+                    retention_notice_at: None,
+                    anonymized_at: None,
+                    merged_into: None,
+                };
+                users.insert_one(user, None).await?.inserted_id.as_object_id().ok_or_else(|| AppError::internal("Failed to create account"))?
+            }
+        };
+
+        let result = self.get_invitations_collection().update_one(
+            doc! { "_id": invitation.id, "status": "pending" },
+            doc! { "$set": { "status": "accepted", "accepted_at": now, "user_id": user_id } },
+            None
+        ).await?;
+        if result.modified_count == 0 {
+            return Err(AppError::conflict("This invitation is no longer pending"));
+        }
+
+        info!("Invitation for {} accepted as {}", invitation.email, invitation.role);
+        invitation.status = "accepted".to_string();
+        invitation.accepted_at = Some(now);
+        invitation.user_id = Some(user_id);
+        Ok(invitation)
+    }
+}
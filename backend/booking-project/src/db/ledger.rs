@@ -0,0 +1,275 @@
+use futures::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::ledger::{
+    customer_account, operator_account, to_cents, ACCOUNT_KINDS, AccountBalance, LedgerCheck, LedgerEntry, LedgerQuery,
+    LedgerTransaction, PLATFORM_BANK_ACCOUNT, PLATFORM_COMMISSION_ACCOUNT,
+};
+use crate::models::settlement::{round_money, Settlement};
+use crate::models::RefundRequest;
+
+impl MongoDB {
+    fn get_ledger_collection(&self) -> Collection<LedgerTransaction> {
+        self.database().collection("ledger")
+    }
+
+    // One transaction per kind and reference, so a retried callback can't post the same money twice
+    pub async fn ensure_ledger_indexes(&self) -> Result<(), AppError> {
+        self.get_ledger_collection().create_index(
+            IndexModel::builder()
+                .keys(doc! { "kind": 1, "reference": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Posts a transaction; None when one of this kind and reference is already in the ledger.
+    pub async fn record_ledger_transaction(&self, kind: &str, reference: &str, entries: Vec<LedgerEntry>) -> Result<Option<LedgerTransaction>, AppError> {
+        let mut transaction = LedgerTransaction::new(kind, reference, entries)?;
+        match self.get_ledger_collection().insert_one(&transaction, None).await {
+            Ok(result) => transaction.id = result.inserted_id.as_object_id(),
+            Err(e) if is_duplicate_key(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        Ok(Some(transaction))
+    }
+
+    // Money movements are already committed by the time these run, so a ledger failure is logged, not raised
+    async fn post_or_log(&self, kind: &str, reference: &str, entries: Vec<LedgerEntry>) {
+        if let Err(e) = self.record_ledger_transaction(kind, reference, entries).await {
+            error!("Failed to post {} {} to the ledger: {}", kind, reference, e);
+        }
+    }
+
+    pub(super) async fn find_ledger_transaction(&self, kind: &str, reference: &str) -> Result<Option<LedgerTransaction>, AppError> {
+        Ok(self.get_ledger_collection().find_one(doc! { "kind": kind, "reference": reference }, None).await?)
+    }
+
+    /// The customer pays the fare, split between the operator's share and platform commission.
+    /// Posted once the booking is paid for, or confirmed without needing payment, and only once.
+    pub(super) async fn post_booking_to_ledger(&self, booking_id: ObjectId, user_id: ObjectId) {
+        let reference = booking_id.to_hex();
+        let split = match self.get_booking_splits_collection()
+            .find_one(doc! { "booking_id": booking_id, "reversal": false }, None).await
+        {
+            Ok(Some(split)) => split,
+            Ok(None) => {
+                error!("No financial split for paid booking {}; sale not posted to the ledger", booking_id);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to load split for booking {}: {}", booking_id, e);
+                return;
+            }
+        };
+        self.post_or_log("booking", &reference, vec![
+            LedgerEntry::debit(&customer_account(&user_id), split.fare),
+            LedgerEntry::credit(&operator_account(&split.operator), split.operator_share),
+            LedgerEntry::credit(PLATFORM_COMMISSION_ACCOUNT, split.commission),
+        ]).await;
+    }
+
+    /// Backs a cancelled booking's sale out again, if one was posted.
+    pub(super) async fn reverse_booking_in_ledger(&self, booking_id: ObjectId) {
+        let reference = booking_id.to_hex();
+        let sale = match self.find_ledger_transaction("booking", &reference).await {
+            Ok(Some(sale)) => sale,
+            // Never paid for, so there is nothing to take back
+            Ok(None) => return,
+            Err(e) => {
+                error!("Failed to load the sale of booking {} from the ledger: {}", booking_id, e);
+                return;
+            }
+        };
+        let entries = sale.entries.into_iter().map(|e| LedgerEntry { debit: e.credit, credit: e.debit, ..e }).collect();
+        self.post_or_log("booking_reversal", &reference, entries).await;
+    }
+
+    /// A passenger's amendment fee, shared between the operator and the platform like the sale.
+    pub(super) async fn post_amendment_fee_to_ledger(&self, booking_id: ObjectId, user_id: ObjectId, reference: &str, fee: f64) {
+        let sale = match self.find_ledger_transaction("booking", &booking_id.to_hex()).await {
+            Ok(Some(sale)) => sale,
+            Ok(None) => {
+                error!("No sale for amended booking {}; amendment fee not posted to the ledger", booking_id);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to load the sale of booking {} from the ledger: {}", booking_id, e);
+                return;
+            }
+        };
+        let mut entries = vec![LedgerEntry::debit(&customer_account(&user_id), fee)];
+        entries.extend(sale.apportion_credits(fee));
+        self.post_or_log("amendment", reference, entries).await;
+    }
+
+    /// A paid refund comes back out of the operator share and commission in the booking's proportions.
+    pub(super) async fn post_refund_to_ledger(&self, refund: &RefundRequest) {
+        let reference = refund.id.map(|oid| oid.to_hex()).unwrap_or_default();
+        // Cancelling the booking already backed its whole sale out, so the customer is only still
+        // charged whatever the refund keeps back, shared between operator and platform like the sale
+        let booking_reference = refund.booking_id.to_hex();
+        match self.find_ledger_transaction("booking_reversal", &booking_reference).await {
+            Ok(Some(_)) => {
+                let sale = match self.find_ledger_transaction("booking", &booking_reference).await {
+                    Ok(Some(sale)) => sale,
+                    Ok(None) => {
+                        error!("Booking {} was reversed without a sale; refund not posted to the ledger", booking_reference);
+                        return;
+                    }
+                    Err(e) => {
+                        error!("Failed to load the sale for refund {:?}: {}", refund.id, e);
+                        return;
+                    }
+                };
+                let kept = round_money(sale.total() - refund.amount);
+                if kept > 0.0 {
+                    let mut entries = vec![LedgerEntry::debit(&customer_account(&refund.user_id), kept)];
+                    entries.extend(sale.apportion_credits(kept));
+                    self.post_or_log("refund", &reference, entries).await;
+                }
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to check the ledger for refund {:?}: {}", refund.id, e);
+                return;
+            }
+        }
+
+        let split = match self.get_booking_splits_collection()
+            .find_one(doc! { "booking_id": refund.booking_id, "reversal": false }, None).await
+        {
+            Ok(Some(split)) => split,
+            Ok(None) => {
+                error!("No financial split for refunded booking {}; refund not posted to the ledger", refund.booking_id);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to load split for refund {:?}: {}", refund.id, e);
+                return;
+            }
+        };
+
+        let ratio = if split.fare > 0.0 { refund.amount / split.fare } else { 0.0 };
+        let from_commission = round_money(split.commission * ratio);
+        self.post_or_log("refund", &reference, vec![
+            LedgerEntry::debit(&operator_account(&split.operator), round_money(refund.amount - from_commission)),
+            LedgerEntry::debit(PLATFORM_COMMISSION_ACCOUNT, from_commission),
+            LedgerEntry::credit(&customer_account(&refund.user_id), refund.amount),
+        ]).await;
+    }
+
+    /// Paying out a settlement clears what the platform owes the operator.
+    pub(super) async fn post_settlement_to_ledger(&self, settlement: &Settlement) {
+        let reference = settlement.id.map(|oid| oid.to_hex()).unwrap_or_default();
+        // A period with more reversals than sales leaves the operator owing the platform
+        let account = operator_account(&settlement.operator);
+        let entries = if settlement.net_payout >= 0.0 {
+            vec![
+                LedgerEntry::debit(&account, settlement.net_payout),
+                LedgerEntry::credit(PLATFORM_BANK_ACCOUNT, settlement.net_payout),
+            ]
+        } else {
+            vec![
+                LedgerEntry::debit(PLATFORM_BANK_ACCOUNT, -settlement.net_payout),
+                LedgerEntry::credit(&account, -settlement.net_payout),
+            ]
+        };
+        self.post_or_log("settlement_payout", &reference, entries).await;
+    }
+
+    pub async fn get_ledger_transactions(&self, query: &LedgerQuery) -> Result<Vec<LedgerTransaction>, AppError> {
+        let mut filter = Document::new();
+        if let Some(account) = &query.account {
+            filter.insert("entries.account", account);
+        }
+        if let Some(kind) = &query.kind {
+            filter.insert("kind", kind);
+        }
+        if let Some(reference) = &query.reference {
+            filter.insert("reference", reference);
+        }
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .limit(query.limit.unwrap_or(100).clamp(1, 1000))
+            .build();
+        let mut cursor = self.get_ledger_collection().find(filter, find_options).await?;
+
+        let mut transactions = Vec::new();
+        while let Some(result) = cursor.next().await {
+            transactions.push(result?);
+        }
+        Ok(transactions)
+    }
+
+    pub async fn get_ledger_balances(&self, account_kind: Option<&str>) -> Result<Vec<AccountBalance>, AppError> {
+        let mut pipeline = vec![doc! { "$unwind": "$entries" }];
+        if let Some(kind) = account_kind {
+            if !ACCOUNT_KINDS.contains(&kind) {
+                return Err(format!("kind must be one of: {}", ACCOUNT_KINDS.join(", ")).into());
+            }
+            pipeline.push(doc! { "$match": { "entries.account": { "$regex": format!("^{}:", kind) } } });
+        }
+        pipeline.push(doc! { "$group": {
+            "_id": "$entries.account",
+            "debits": { "$sum": "$entries.debit" },
+            "credits": { "$sum": "$entries.credit" },
+        } });
+        pipeline.push(doc! { "$sort": { "_id": 1 } });
+
+        let mut groups = self.database().collection::<Document>("ledger").aggregate(pipeline, None).await?;
+        let mut balances = Vec::new();
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            let debits = round_money(group.get_f64("debits").unwrap_or(0.0));
+            let credits = round_money(group.get_f64("credits").unwrap_or(0.0));
+            balances.push(AccountBalance {
+                account: group.get_str("_id").unwrap_or_default().to_string(),
+                debits,
+                credits,
+                balance: round_money(debits - credits),
+            });
+        }
+        Ok(balances)
+    }
+
+    /// Re-verifies every stored transaction, catching anything written around `record_ledger_transaction`.
+    pub async fn check_ledger(&self) -> Result<LedgerCheck, AppError> {
+        let mut cursor = self.get_ledger_collection().find(None, None).await?;
+        let mut check = LedgerCheck {
+            balanced: true,
+            transactions: 0,
+            total_debits: 0.0,
+            total_credits: 0.0,
+            unbalanced_transactions: Vec::new(),
+        };
+        let (mut total_debits, mut total_credits) = (0i64, 0i64);
+        while let Some(result) = cursor.next().await {
+            let transaction = result?;
+            let debits: i64 = transaction.entries.iter().map(|e| to_cents(e.debit)).sum();
+            let credits: i64 = transaction.entries.iter().map(|e| to_cents(e.credit)).sum();
+            if debits != credits {
+                check.unbalanced_transactions.push(transaction.id.map(|oid| oid.to_hex()).unwrap_or_default());
+            }
+            check.transactions += 1;
+            total_debits += debits;
+            total_credits += credits;
+        }
+        check.total_debits = total_debits as f64 / 100.0;
+        check.total_credits = total_credits as f64 / 100.0;
+        check.balanced = total_debits == total_credits && check.unbalanced_transactions.is_empty();
+        Ok(check)
+    }
+}
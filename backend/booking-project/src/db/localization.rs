@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use mongodb::{
+    bson::doc,
+    options::UpdateOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::localization::{supported_language, Localization, Localizer, UpsertLocalizationRequest, SUPPORTED_LANGUAGES};
+
+impl MongoDB {
+    fn get_localizations_collection(&self) -> Collection<Localization> {
+        self.database().collection("localizations")
+    }
+
+    pub async fn get_localizations(&self, kind: Option<&str>) -> Result<Vec<Localization>, AppError> {
+        let filter = match kind {
+            Some(kind) => doc! { "kind": kind },
+            None => doc! {},
+        };
+        let mut cursor = self.get_localizations_collection().find(filter, None).await?;
+
+        let mut localizations = Vec::new();
+        while let Some(result) = cursor.next().await {
+            localizations.push(result?);
+        }
+        Ok(localizations)
+    }
+
+    pub async fn get_localizer(&self, language: &str) -> Result<Localizer, AppError> {
+        Ok(Localizer::new(language, self.get_localizations(None).await?))
+    }
+
+    pub async fn upsert_localization(&self, req: &UpsertLocalizationRequest) -> Result<(), AppError> {
+        if req.kind != "location" && req.kind != "bus_type" {
+            return Err("kind must be either 'location' or 'bus_type'".into());
+        }
+
+        let mut set = doc! {};
+        for (language, name) in &req.names {
+            // The language becomes part of a field path, so only known codes get that far
+            let language = supported_language(language).ok_or_else(|| {
+                format!("Unsupported language '{}', expected one of: {}", language, SUPPORTED_LANGUAGES.join(", "))
+            })?;
+            set.insert(format!("names.{}", language), name);
+        }
+        if set.is_empty() {
+            return Err("At least one localized name is required".into());
+        }
+
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_localizations_collection().update_one(
+            doc! { "kind": &req.kind, "key": &req.key },
+            doc! { "$set": set },
+            options
+        ).await?;
+        Ok(())
+    }
+
+    pub async fn seed_localizations(&self) -> Result<(), AppError> {
+        let collection = self.get_localizations_collection();
+        if collection.count_documents(None, None).await? > 0 {
+            return Ok(());
+        }
+
+        println!("🌱 Seeding English/Swahili display names...");
+        let defaults = [
+            ("location", "Nairobi", "Nairobi"),
+            ("location", "Mombasa", "Mombasa"),
+            ("location", "Kisumu", "Kisumu"),
+            ("location", "Eldoret", "Eldoret"),
+            ("location", "Busia", "Busia"),
+            ("location", "Nakuru", "Nakuru"),
+            ("location", "Kisii", "Kisii"),
+            ("location", "Kakamega", "Kakamega"),
+            ("location", "Malindi", "Malindi"),
+            ("bus_type", "Standard", "Kawaida"),
+            ("bus_type", "Standard Coach", "Basi la Kawaida"),
+            ("bus_type", "Semi-Luxury", "Nusu Kifahari"),
+            ("bus_type", "Luxury Coach", "Basi la Kifahari"),
+            ("bus_type", "Executive", "Daraja la Juu"),
+            ("bus_type", "VIP", "VIP"),
+            ("bus_type", "VIP Oxygen", "VIP Oxygen"),
+            ("bus_type", "Shuttle", "Shuttle"),
+        ];
+
+        let localizations: Vec<Localization> = defaults
+            .iter()
+            .map(|(kind, key, sw)| Localization {
+                id: None,
+                kind: kind.to_string(),
+                key: key.to_string(),
+                names: HashMap::from([
+                    ("en".to_string(), key.to_string()),
+                    ("sw".to_string(), sw.to_string()),
+                ]),
+            })
+            .collect();
+        let report = self.insert_batched(&collection, localizations).await?;
+        for failure in &report.failed {
+            log::warn!("Failed to seed localization #{}: {}", failure.index, failure.message);
+        }
+        Ok(())
+    }
+}
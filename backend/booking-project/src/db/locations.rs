@@ -0,0 +1,56 @@
+use mongodb::{
+    bson::{self, doc},
+    options::{IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::manifest::TripAccessClaims;
+use crate::models::trip::{TripLocation, TripLocationRequest};
+
+impl MongoDB {
+    fn get_trip_locations_collection(&self) -> Collection<TripLocation> {
+        self.database().collection("trip_locations")
+    }
+
+    pub async fn ensure_trip_location_indexes(&self) -> Result<(), AppError> {
+        self.get_trip_locations_collection().create_index(
+            IndexModel::builder()
+                .keys(doc! { "bus_id": 1, "travel_date": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Keeps the latest GPS fix for the trip the crew is on. A fix older than the one already
+    /// held, e.g. from a queue the handset flushed late, is accepted but changes nothing.
+    pub async fn record_trip_location(&self, access: &TripAccessClaims, req: &TripLocationRequest, device_id: Option<&str>) -> Result<(), AppError> {
+        let recorded_at = req.validated_time(chrono::Utc::now())?;
+        let recorded_at = bson::DateTime::from_millis(recorded_at.timestamp_millis());
+        let bus_oid = self.string_to_id(&access.bus_id)?;
+        let result = self.get_trip_locations_collection().update_one(
+            doc! { "bus_id": bus_oid, "travel_date": &access.travel_date, "recorded_at": { "$lt": recorded_at } },
+            doc! {
+                "$set": {
+                    "latitude": req.latitude,
+                    "longitude": req.longitude,
+                    "speed_kmh": req.speed_kmh,
+                    "heading": req.heading,
+                    "device_id": device_id,
+                    "recorded_at": recorded_at,
+                    "received_at": bson::DateTime::now(),
+                },
+            },
+            UpdateOptions::builder().upsert(true).build(),
+        ).await;
+        match result {
+            Ok(_) => Ok(()),
+            // The trip already has a newer fix, so the upsert tried to add a second document
+            Err(e) if super::locks::is_duplicate_key(&e) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::rc::Rc;
+use std::cell::Cell;
+use std::time::Duration;
+
+use log::warn;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+
+/// A held lease on a named lock. The lease is renewed in the background until the guard is dropped,
+/// after which the lock is released for other instances.
+pub struct LockGuard {
+    db: MongoDB,
+    name: String,
+    token: String,
+    released: Rc<Cell<bool>>,
+    lost: Rc<Cell<bool>>,
+}
+
+impl LockGuard {
+    /// False once a renewal found the lease taken over, e.g. after a long GC-style stall.
+    pub fn is_held(&self) -> bool {
+        !self.lost.get()
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        self.released.set(true);
+        let (db, name, token) = (self.db.clone(), self.name.clone(), self.token.clone());
+        actix_web::rt::spawn(async move {
+            if let Err(e) = db.get_locks_collection().delete_one(doc! { "_id": &name, "owner": &token }, None).await {
+                warn!("Failed to release lock {}: {}", name, e);
+            }
+        });
+    }
+}
+
+impl MongoDB {
+    fn get_locks_collection(&self) -> Collection<Document> {
+        self.database().collection("locks")
+    }
+
+    // Expired leases are also cleaned up by MongoDB in case an instance dies holding one
+    pub async fn ensure_lock_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+            .build();
+        self.get_locks_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    /// Tries to take the named lock for `ttl`; returns `None` if another instance holds it.
+    pub async fn try_lock(&self, name: &str, ttl: Duration) -> Result<Option<LockGuard>, AppError> {
+        let token = ObjectId::new().to_hex();
+        if !self.extend_lease(name, &token, ttl, true).await? {
+            return Ok(None);
+        }
+
+        let released = Rc::new(Cell::new(false));
+        let lost = Rc::new(Cell::new(false));
+        let guard = LockGuard {
+            db: self.clone(),
+            name: name.to_string(),
+            token: token.clone(),
+            released: released.clone(),
+            lost: lost.clone(),
+        };
+
+        // Renew at a third of the TTL so a single missed renewal doesn't drop the lease
+        let (db, name) = (self.clone(), name.to_string());
+        actix_web::rt::spawn(async move {
+            loop {
+                actix_web::rt::time::sleep(ttl / 3).await;
+                if released.get() {
+                    break;
+                }
+                match db.extend_lease(&name, &token, ttl, false).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!("Lost lock {} to another instance", name);
+                        lost.set(true);
+                        break;
+                    }
+                    Err(e) => warn!("Failed to renew lock {}: {}", name, e),
+                }
+            }
+        });
+
+        Ok(Some(guard))
+    }
+
+    async fn extend_lease(&self, name: &str, token: &str, ttl: Duration, acquire: bool) -> Result<bool, AppError> {
+        let now = chrono::Utc::now();
+        let expires_at = bson::DateTime::from_millis(now.timestamp_millis() + ttl.as_millis() as i64);
+        let filter = if acquire {
+            doc! { "_id": name, "$or": [
+                { "expires_at": { "$lte": bson::DateTime::from_millis(now.timestamp_millis()) } },
+                { "owner": token },
+            ] }
+        } else {
+            doc! { "_id": name, "owner": token }
+        };
+        let options = FindOneAndUpdateOptions::builder().upsert(acquire).build();
+
+        match self.get_locks_collection().find_one_and_update(
+            filter,
+            doc! { "$set": { "owner": token, "expires_at": expires_at } },
+            options
+        ).await {
+            Ok(_) if acquire => Ok(true),
+            Ok(previous) => Ok(previous.is_some()),
+            // The upsert collides with the live lock document when someone else holds it
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+pub(super) fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Command(e) if e.code == 11000
+    ) || matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(e)) if e.code == 11000
+    ) || matches!(
+        error.kind.as_ref(),
+        // Unordered inserts report every document that collided; any other failure is a real error
+        mongodb::error::ErrorKind::BulkWrite(mongodb::error::BulkWriteFailure { write_errors: Some(errors), write_concern_error: None, .. })
+            if errors.iter().all(|e| e.code == 11000)
+    )
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use log::{error, info, warn};
+use mongodb::{
+    bson::{self, doc},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::LookupConfig;
+use crate::models::bus::Bus;
+use crate::models::lookup::{email_matches, new_booking_reference, normalize_booking_reference, LookupFailure, LookupFailures};
+use crate::models::Booking;
+
+// Compared against when the reference matches nothing, so a wrong reference costs the same as a wrong email
+const ABSENT_EMAIL: &str = "nobody@invalid";
+
+impl MongoDB {
+    fn get_lookup_failures_collection(&self) -> Collection<LookupFailure> {
+        self.database().collection("lookup_failures")
+    }
+
+    pub async fn ensure_lookup_indexes(&self) -> Result<(), AppError> {
+        let failures = self.get_lookup_failures_collection();
+        failures.create_index(IndexModel::builder().keys(doc! { "ip": 1, "at": -1 }).build(), None).await?;
+        failures.create_index(IndexModel::builder().keys(doc! { "reference": 1, "at": -1 }).build(), None).await?;
+        failures.create_index(
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                .build(),
+            None
+        ).await?;
+
+        let bookings = self.get_bookings_collection();
+        bookings.create_index(
+            IndexModel::builder()
+                .keys(doc! { "reference": 1 })
+                .options(IndexOptions::builder()
+                    .unique(true)
+                    .partial_filter_expression(doc! { "reference": { "$exists": true } })
+                    .build())
+                .build(),
+            None
+        ).await?;
+        let assigned = self.assign_booking_references().await?;
+        if assigned > 0 {
+            info!("Gave {} existing bookings a reference", assigned);
+        }
+        Ok(())
+    }
+
+    /// Gives bookings made before references existed one of their own. Safe to run again.
+    async fn assign_booking_references(&self) -> Result<u64, AppError> {
+        let bookings = self.get_bookings_collection();
+        let mut cursor = bookings.find(doc! { "reference": { "$exists": false } }, None).await?;
+        let mut assigned = 0;
+        while let Some(booking) = cursor.next().await {
+            let Some(booking_id) = booking?.id else {
+                continue;
+            };
+            loop {
+                let reference = new_booking_reference().map_err(AppError::Internal)?;
+                match bookings.update_one(
+                    doc! { "_id": booking_id, "reference": { "$exists": false } },
+                    doc! { "$set": { "reference": reference } },
+                    None
+                ).await {
+                    Ok(result) => {
+                        assigned += result.modified_count;
+                        break;
+                    }
+                    Err(e) if super::locks::is_duplicate_key(&e) => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(assigned)
+    }
+
+    fn lookup_window_start(config: &LookupConfig) -> bson::DateTime {
+        bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::minutes(config.window_minutes)).timestamp_millis())
+    }
+
+    pub async fn count_lookup_failures(&self, ip: &str, reference: &str, config: &LookupConfig) -> Result<LookupFailures, AppError> {
+        let since = Self::lookup_window_start(config);
+        let failures = self.get_lookup_failures_collection();
+        Ok(LookupFailures {
+            by_ip: failures.count_documents(doc! { "ip": ip, "at": { "$gte": since } }, None).await?,
+            by_reference: failures.count_documents(doc! { "reference": reference, "at": { "$gte": since } }, None).await?,
+        })
+    }
+
+    /// Counts a failed lookup, flagging the caller or reference to the fraud team the moment
+    /// the failures spread across enough references or IPs to look like enumeration.
+    pub async fn record_lookup_failure(&self, ip: &str, reference: &str, config: &LookupConfig) -> Result<(), AppError> {
+        let now = chrono::Utc::now();
+        let failures = self.get_lookup_failures_collection();
+        failures.insert_one(LookupFailure {
+            ip: ip.to_string(),
+            reference: reference.to_string(),
+            at: bson::DateTime::from_millis(now.timestamp_millis()),
+            expires_at: bson::DateTime::from_millis((now + chrono::Duration::minutes(config.window_minutes)).timestamp_millis()),
+        }, None).await?;
+
+        let since = Self::lookup_window_start(config);
+        let references = failures.distinct("reference", doc! { "ip": ip, "at": { "$gte": since } }, None).await?;
+        if references.len() as u64 == config.enumeration_references {
+            warn!("Booking lookups from {} failed against {} references", ip, references.len());
+            self.flag_lookup_enumeration("ip", ip).await;
+        }
+        let ips = failures.distinct("ip", doc! { "reference": reference, "at": { "$gte": since } }, None).await?;
+        if ips.len() as u64 == config.enumeration_references {
+            warn!("Booking reference {} was tried from {} IPs", reference, ips.len());
+            self.flag_lookup_enumeration("reference", reference).await;
+        }
+        Ok(())
+    }
+
+    async fn flag_lookup_enumeration(&self, kind: &str, value: &str) {
+        if let Err(e) = self.record_enumeration_suspected("booking_lookup", kind, value).await {
+            error!("Failed to flag booking lookup enumeration by {} {}: {}", kind, value, e);
+        }
+    }
+
+    /// The booking behind a short reference code, in whatever form it was typed.
+    pub async fn find_booking_by_reference(&self, code: &str) -> Result<Option<(Booking, Option<Bus>)>, AppError> {
+        let Some(reference) = normalize_booking_reference(code) else {
+            return Ok(None);
+        };
+        let Some(booking) = self.get_bookings_collection().find_one(doc! { "reference": reference }, None).await? else {
+            return Ok(None);
+        };
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?;
+        Ok(Some((booking, bus)))
+    }
+
+    /// The booking behind a reference, if `email` is the booker's. A wrong reference and a
+    /// wrong email are indistinguishable to the caller.
+    pub async fn find_booking_for_guest(&self, reference: &str, email: &str) -> Result<Option<(Booking, Option<Bus>)>, AppError> {
+        // Either the booking id or its short code
+        let filter = match (self.string_to_id(reference), normalize_booking_reference(reference)) {
+            (Ok(booking_oid), _) => Some(doc! { "_id": booking_oid }),
+            (Err(_), Some(code)) => Some(doc! { "reference": code }),
+            _ => None,
+        };
+        let booking = match filter {
+            Some(filter) => self.get_bookings_collection().find_one(filter, None).await?,
+            None => None,
+        };
+        let on_file = match &booking {
+            Some(booking) => self.user_blocklist_email(&booking.user_id.to_hex()).await?,
+            None => None,
+        };
+        if !email_matches(email, on_file.as_deref().unwrap_or(ABSENT_EMAIL)) || on_file.is_none() {
+            return Ok(None);
+        }
+        let Some(booking) = booking else {
+            return Ok(None);
+        };
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?;
+        Ok(Some((booking, bus)))
+    }
+}
@@ -0,0 +1,237 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+use crate::models::lost_found::{
+    FoundItem, LogFoundItemRequest, LostItemReport, LostItemStatusRequest, MatchSuggestion, ReportLostItemRequest,
+    FoundItemResponse, FOUND_ITEM_STATUSES, ITEM_CATEGORIES, LOST_REPORT_STATUSES,
+};
+
+// Suggestions below this score are noise
+const MIN_MATCH_SCORE: f64 = 0.4;
+
+fn check_category(category: &str) -> Result<(), AppError> {
+    if !ITEM_CATEGORIES.contains(&category) {
+        return Err(format!("category must be one of: {}", ITEM_CATEGORIES.join(", ")).into());
+    }
+    Ok(())
+}
+
+fn check_description(description: &str) -> Result<(), AppError> {
+    if description.trim().is_empty() || description.len() > 1000 {
+        return Err("Description must be between 1 and 1000 characters".into());
+    }
+    Ok(())
+}
+
+impl MongoDB {
+    fn get_lost_items_collection(&self) -> Collection<LostItemReport> {
+        self.database().collection("lost_items")
+    }
+
+    fn get_found_items_collection(&self) -> Collection<FoundItem> {
+        self.database().collection("found_items")
+    }
+
+    pub async fn report_lost_item(&self, user_id: &str, req: &ReportLostItemRequest) -> Result<LostItemReport, AppError> {
+        check_category(&req.category)?;
+        check_description(&req.description)?;
+
+        let booking = self.get_user_booking(&req.booking_id, user_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        if booking.status == "Cancelled" {
+            return Err(AppError::forbidden("Lost items can only be reported for trips you travelled on"));
+        }
+        let today = chrono::Utc::now().with_timezone(&local_offset()).format("%Y-%m-%d").to_string();
+        if booking.travel_date > today {
+            return Err("This trip hasn't taken place yet".into());
+        }
+
+        let now = bson::DateTime::now();
+        let mut report = LostItemReport {
+            id: None,
+            user_id: booking.user_id,
+            booking_id: booking.id.ok_or_else(|| AppError::not_found("Booking not found"))?,
+            bus_id: booking.bus_id,
+            travel_date: booking.travel_date,
+            seat_number: booking.seat_number,
+            category: req.category.clone(),
+            description: req.description.trim().to_string(),
+            status: "Open".to_string(),
+            found_item_id: None,
+            note: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let result = self.get_lost_items_collection().insert_one(&report, None).await?;
+        report.id = result.inserted_id.as_object_id();
+
+        self.notify_admins(
+            "Lost item reported",
+            &format!("{} lost on {} ({}), seat {}: {}", report.category, report.travel_date, report.bus_id, report.seat_number, report.description),
+        ).await?;
+        Ok(report)
+    }
+
+    pub async fn get_user_lost_items(&self, user_id: &str) -> Result<Vec<LostItemReport>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        self.find_lost_items(doc! { "user_id": user_oid }).await
+    }
+
+    pub async fn get_lost_items(&self, status: Option<&str>) -> Result<Vec<LostItemReport>, AppError> {
+        let filter = match status {
+            Some(status) => doc! { "status": status },
+            None => doc! {},
+        };
+        self.find_lost_items(filter).await
+    }
+
+    async fn find_lost_items(&self, filter: Document) -> Result<Vec<LostItemReport>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_lost_items_collection().find(filter, find_options).await?;
+
+        let mut reports = Vec::new();
+        while let Some(result) = cursor.next().await {
+            reports.push(result?);
+        }
+        Ok(reports)
+    }
+
+    pub async fn log_found_item(&self, req: &LogFoundItemRequest, logged_by: &str) -> Result<FoundItem, AppError> {
+        check_category(&req.category)?;
+        check_description(&req.description)?;
+        let bus = self.get_bus(&req.bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let mut item = FoundItem {
+            id: None,
+            bus_id: bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?,
+            travel_date: req.travel_date.clone(),
+            seat_number: req.seat_number.clone(),
+            category: req.category.clone(),
+            description: req.description.trim().to_string(),
+            status: "Unclaimed".to_string(),
+            logged_by: logged_by.to_string(),
+            created_at: bson::DateTime::now(),
+        };
+        let result = self.get_found_items_collection().insert_one(&item, None).await?;
+        item.id = result.inserted_id.as_object_id();
+        Ok(item)
+    }
+
+    pub async fn get_found_items(&self, status: Option<&str>) -> Result<Vec<FoundItem>, AppError> {
+        let filter = match status {
+            Some(status) => doc! { "status": status },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_found_items_collection().find(filter, find_options).await?;
+
+        let mut items = Vec::new();
+        while let Some(result) = cursor.next().await {
+            items.push(result?);
+        }
+        Ok(items)
+    }
+
+    pub async fn set_found_item_status(&self, item_id: &str, status: &str) -> Result<FoundItem, AppError> {
+        if !FOUND_ITEM_STATUSES.contains(&status) {
+            return Err(format!("status must be one of: {}", FOUND_ITEM_STATUSES.join(", ")).into());
+        }
+        let item_oid = self.string_to_id(item_id)?;
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let item = self.get_found_items_collection().find_one_and_update(
+            doc! { "_id": item_oid },
+            doc! { "$set": { "status": status } },
+            options
+        ).await?;
+        item.ok_or_else(|| AppError::not_found("Found item not found"))
+    }
+
+    /// Unclaimed items found on the same bus within a day of the trip, best match first.
+    pub async fn suggest_found_items(&self, report_id: &str) -> Result<Vec<MatchSuggestion>, AppError> {
+        let report_oid = self.string_to_id(report_id)?;
+        let report = self.get_lost_items_collection().find_one(doc! { "_id": report_oid }, None).await?
+            .ok_or_else(|| AppError::not_found("Lost item report not found"))?;
+
+        let travel_date = chrono::NaiveDate::parse_from_str(&report.travel_date, "%Y-%m-%d")?;
+        let window: Vec<String> = (-1..=1)
+            .map(|offset| (travel_date + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string())
+            .collect();
+        let mut cursor = self.get_found_items_collection().find(
+            doc! { "bus_id": report.bus_id, "travel_date": { "$in": window }, "status": "Unclaimed" },
+            None
+        ).await?;
+
+        let mut suggestions = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let item = result?;
+            let score = item.match_score(&report);
+            if score >= MIN_MATCH_SCORE {
+                suggestions.push(MatchSuggestion { score: (score * 100.0).round() / 100.0, item: FoundItemResponse::from(item) });
+            }
+        }
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(suggestions)
+    }
+
+    pub async fn update_lost_item_status(&self, report_id: &str, req: &LostItemStatusRequest) -> Result<LostItemReport, AppError> {
+        if !LOST_REPORT_STATUSES.contains(&req.status.as_str()) {
+            return Err(format!("status must be one of: {}", LOST_REPORT_STATUSES.join(", ")).into());
+        }
+        let report_oid = self.string_to_id(report_id)?;
+
+        let mut set = doc! { "status": &req.status, "note": &req.note, "updated_at": bson::DateTime::now() };
+        let found_item_oid = match &req.found_item_id {
+            Some(found_item_id) => Some(self.string_to_id(found_item_id)?),
+            None => None,
+        };
+        if req.status == "Matched" && found_item_oid.is_none() {
+            return Err("found_item_id is required to mark a report as matched".into());
+        }
+        if let Some(found_item_oid) = found_item_oid {
+            set.insert("found_item_id", found_item_oid);
+        }
+
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let report = self.get_lost_items_collection().find_one_and_update(
+            doc! { "_id": report_oid },
+            doc! { "$set": set },
+            options
+        ).await?.ok_or_else(|| AppError::not_found("Lost item report not found"))?;
+
+        // Keep the found item's state in step with the claim
+        if let Some(found_item_id) = report.found_item_id {
+            let item_status = match report.status.as_str() {
+                "Matched" => Some("Matched"),
+                "Returned" => Some("Returned"),
+                _ => None,
+            };
+            if let Some(item_status) = item_status {
+                self.get_found_items_collection().update_one(
+                    doc! { "_id": found_item_id },
+                    doc! { "$set": { "status": item_status } },
+                    None
+                ).await?;
+            }
+        }
+
+        let message = match report.status.as_str() {
+            "Matched" => "We think we've found your item. Our team will contact you to arrange collection.".to_string(),
+            "Returned" => "Your item has been returned. Thanks for travelling with us.".to_string(),
+            "Closed" => "We weren't able to find your item and have closed the report.".to_string(),
+            _ => "Your lost item report has been reopened.".to_string(),
+        };
+        let message = match &report.note {
+            Some(note) => format!("{} {}", message, note),
+            None => message,
+        };
+        self.notify_user(report.user_id, &format!("Lost {}: {}", report.category, report.status.to_lowercase()), &message).await?;
+
+        Ok(report)
+    }
+}
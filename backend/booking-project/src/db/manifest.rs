@@ -0,0 +1,192 @@
+use futures::StreamExt;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use mongodb::bson::{self, doc};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::booking::BookingEvent;
+use crate::models::localization::document_language;
+use crate::models::manifest::{
+    Manifest, ManifestEntry, ManifestRow, OpenedManifest, PrintableManifest, TripAccessClaims, SHIFT_GRACE_HOURS, SHIFT_LEAD_HOURS, TRIP_ACCESS_SCOPE,
+};
+use crate::models::permissions::{authorize, TRIPS_OPERATE};
+use crate::models::Claims;
+
+/// Decodes a trip token and checks it was issued for exactly this trip. `grace_secs` keeps
+/// an expired token usable that much longer.
+pub fn verify_trip_token(token: &str, bus_id: &str, travel_date: &str, grace_secs: u64) -> Result<TripAccessClaims, AppError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway += grace_secs;
+    let claims = decode::<TripAccessClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &validation,
+    )?.claims;
+    if claims.scope != TRIP_ACCESS_SCOPE || claims.bus_id != bus_id || claims.travel_date != travel_date {
+        return Err(AppError::forbidden("This trip token is not valid for this trip"));
+    }
+    Ok(claims)
+}
+
+impl MongoDB {
+    pub async fn get_manifest(&self, bus_id: &str, travel_date: &str) -> Result<Manifest, AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": "Confirmed" },
+            None
+        ).await?;
+        let mut passengers = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let booking = result?;
+            passengers.push(ManifestEntry {
+                booking_id: booking.id.map(|id| id.to_hex()).unwrap_or_default(),
+                passenger_name: booking.passenger.as_ref().map(|p| p.name.clone()).unwrap_or_default(),
+                checked_in: booking.history.iter().any(|e| e.event == "checked_in"),
+                seat_number: booking.seat_number,
+                status: booking.status,
+            });
+        }
+        passengers.sort_by_key(|p| p.seat_number.parse::<i32>().unwrap_or(i32::MAX));
+
+        Ok(Manifest {
+            bus_id: bus_oid.to_hex(),
+            departure: bus.route.departure_at(travel_date).map(|d| d.to_rfc3339()),
+            bus_number: bus.bus_number,
+            travel_date: travel_date.to_string(),
+            from: bus.route.from,
+            to: bus.route.to,
+            passengers,
+        })
+    }
+
+    /// The regulatory manifest for a trip, with crew and vehicle details, ready to print.
+    /// The manifest as printed, worded in `language` when the client asked for a supported one,
+    /// otherwise in the operator's default language.
+    pub async fn get_printable_manifest(&self, bus_id: &str, travel_date: &str, language: Option<&str>) -> Result<PrintableManifest, AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let settings = self.get_operator_settings(&bus.operator_name()).await?;
+        let language = document_language(None, language, settings.default_language.as_deref());
+
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": "Confirmed" },
+            None
+        ).await?;
+        let mut bookings = Vec::new();
+        while let Some(result) = cursor.next().await {
+            bookings.push(result?);
+        }
+        let booking_ids: Vec<_> = bookings.iter().filter_map(|b| b.id).collect();
+        let mut next_of_kin = self.get_next_of_kin(&booking_ids).await?;
+
+        let mut rows = Vec::new();
+        for booking in bookings {
+            let checked_in = booking.history.iter().any(|e| e.event == "checked_in");
+            let passenger = booking.passenger.unwrap_or_else(|| crate::models::booking::Passenger {
+                name: "Unknown".to_string(),
+                age: String::new(),
+                gender: String::new(),
+                phone: None,
+                id_number: None,
+                next_of_kin: None,
+            });
+            rows.push(ManifestRow {
+                seat_number: booking.seat_number,
+                name: passenger.name,
+                gender: passenger.gender,
+                age: passenger.age,
+                id_number: passenger.id_number.unwrap_or_default(),
+                next_of_kin: booking.id.and_then(|id| next_of_kin.remove(&id)).map(|k| k.to_string()).unwrap_or_default(),
+                boarding_point: bus.route.from.clone(),
+                checked_in,
+            });
+        }
+        rows.sort_by_key(|r| r.seat_number.parse::<i32>().unwrap_or(i32::MAX));
+
+        // A same-day vehicle swap replaces the registered vehicle on the manifest
+        let vehicle = self.get_trip_vehicle(bus_oid, travel_date).await?;
+        let (vehicle_number, seats) = match vehicle {
+            Some(v) => (v.bus_number, v.total_seats),
+            None => (bus.bus_number.clone(), bus.total_seats),
+        };
+
+        Ok(PrintableManifest {
+            operator: bus.operator_name(),
+            vehicle: vehicle_number,
+            bus_type: bus.bus_type.clone(),
+            seats,
+            departure: bus.route.departure_at(travel_date).map(|d| d.format("%H:%M").to_string()),
+            arrival: bus.route.arrival_at(travel_date).map(|d| d.format("%d %b %H:%M").to_string()),
+            from: bus.route.from,
+            to: bus.route.to,
+            travel_date: travel_date.to_string(),
+            crew: self.get_trip_crew(bus_oid, travel_date).await?,
+            rows,
+            language: language.to_string(),
+        })
+    }
+
+    /// Opens a manifest during the shift window and issues the trip-bound token for it.
+    /// Conductors must be on the trip's crew; staff who can operate any trip skip that check.
+    pub async fn open_manifest(&self, claims: &Claims, bus_id: &str, travel_date: &str) -> Result<OpenedManifest, AppError> {
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let user_oid = self.string_to_id(&claims.sub)?;
+
+        if !authorize(claims, TRIPS_OPERATE) && !self.is_assigned_conductor(bus_oid, travel_date, user_oid).await? {
+            return Err(AppError::forbidden("You are not assigned to this trip"));
+        }
+
+        let departure = bus.route.departure_at(travel_date).ok_or("Invalid travel date")?;
+        let arrival = bus.route.arrival_at(travel_date).ok_or("Invalid travel date")?;
+        let now = chrono::Utc::now();
+        if now < departure - chrono::Duration::hours(SHIFT_LEAD_HOURS) {
+            return Err(AppError::forbidden(format!("The manifest opens {} hours before departure", SHIFT_LEAD_HOURS)));
+        }
+        let expires_at = arrival + chrono::Duration::hours(SHIFT_GRACE_HOURS);
+        if now >= expires_at {
+            return Err(AppError::forbidden("This trip's shift has ended"));
+        }
+
+        let trip_claims = TripAccessClaims {
+            sub: claims.sub.clone(),
+            scope: TRIP_ACCESS_SCOPE.to_string(),
+            bus_id: bus_oid.to_hex(),
+            travel_date: travel_date.to_string(),
+            exp: expires_at.timestamp() as usize,
+            iat: now.timestamp() as usize,
+        };
+        let trip_token = encode(&Header::default(), &trip_claims, &EncodingKey::from_secret(jwt_secret().as_ref()))?;
+
+        Ok(OpenedManifest {
+            manifest: self.get_manifest(bus_id, travel_date).await?,
+            trip_token,
+            trip_token_expires_at: expires_at.to_rfc3339(),
+        })
+    }
+
+    pub async fn check_in_passenger(&self, claims: &TripAccessClaims, booking_id: &str) -> Result<(), AppError> {
+        let booking_oid = self.string_to_id(booking_id)?;
+        let bus_oid = self.string_to_id(&claims.bus_id)?;
+        let booking = self.get_bookings_collection()
+            .find_one(doc! { "_id": booking_oid, "bus_id": bus_oid, "travel_date": &claims.travel_date }, None).await?
+            .ok_or("Booking is not on this trip")?;
+        if booking.status != "Confirmed" {
+            return Err(format!("Booking is {}", booking.status.to_lowercase()).into());
+        }
+
+        let event = BookingEvent::new("checked_in", &format!("user:{}", claims.sub), None);
+        let result = self.get_bookings_collection().update_one(
+            doc! { "_id": booking_oid, "history.event": { "$ne": "checked_in" } },
+            doc! { "$push": { "history": bson::to_bson(&event)? } },
+            None
+        ).await?;
+        if result.modified_count == 0 {
+            return Err(AppError::conflict("Passenger is already checked in"));
+        }
+        Ok(())
+    }
+}
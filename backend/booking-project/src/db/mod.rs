@@ -1,2 +1,77 @@
+pub mod analytics;
+pub mod announcements;
+pub mod audit;
+pub mod availability;
+pub mod booking_pages;
+pub mod bulk;
+pub mod bus_search;
+pub mod charters;
+pub mod commission;
+pub mod compare;
+pub mod consent;
+pub mod crew;
+pub mod devices;
+pub mod duplicates;
+pub mod email_change;
+pub mod events;
+pub mod experiments;
+pub mod fares;
+pub mod feeds;
+pub mod fleet;
+pub mod fraud;
+pub mod group_bookings;
+pub mod gtfs;
+pub mod holds;
+pub mod incidents;
+pub mod invitations;
+pub mod ledger;
+pub mod localization;
+pub mod locations;
+pub mod locks;
+pub mod lookup;
+pub mod lost_found;
+pub mod manifest;
 pub mod mongodb;
+pub mod monitoring;
+pub mod next_of_kin;
+pub mod next_trip;
+pub mod notification_resends;
+pub mod notifications;
+pub mod offline_sync;
+pub mod operators;
+pub mod outbox;
+pub mod parcels;
+pub mod passes;
+pub mod password_reset;
+pub mod payments;
+pub mod partners;
+pub mod payment_methods;
+pub mod permissions;
+pub mod policies;
+pub mod price_history;
+pub mod profiling;
+pub mod punctuality;
+pub mod rate_limits;
+pub mod receipts;
+pub mod refresh_tokens;
+pub mod refunds;
+pub mod regulatory;
+pub mod reseat;
+pub mod retention;
+pub mod schedules;
+pub mod schema;
+pub mod search_log;
+pub mod settlements;
+pub mod sharing;
+pub mod stats;
+pub mod status;
+pub mod tenancy;
+pub mod terminals;
+pub mod terms;
+pub mod tickets;
+pub mod trip_history;
+pub mod trips;
+pub mod vehicle_swap;
+pub mod waiting_rooms;
+pub mod webhooks;
 pub use self::mongodb::MongoDB;  // Add 'self::' to specify our own module
\ No newline at end of file
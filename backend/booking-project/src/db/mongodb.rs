@@ -1,88 +1,141 @@
+use std::sync::Arc;
+
 use log::{info, error, warn};
 use mongodb::{
-    bson::{self, doc, Document},
-    options::FindOptions,
-    Client, Collection, Cursor,
+    bson::{self, doc},
+    options::{CollectionOptions, FindOptions, SelectionCriteria},
+    Client, ClientSession, Collection, Cursor, Database,
 };
-use futures::StreamExt;
 
 // Import the models we need
-use crate::models::{User, UserResponse, Claims, AuthResponse, RegisterRequest, LoginRequest, GoogleLoginRequest, Bus, Seat, Booking};
+use crate::models::{User, UserResponse, AuthResponse, RegisterRequest, LoginRequest, Bus, Booking};
+use crate::models::bus::SeatAvailability;
+use crate::models::booking::BookingEvent;
+use crate::config::{EventsConfig, ProfilingConfig, ReadPreferenceConfig, ReadWorkload, TermsConfig};
+use crate::events::{BookingSnapshot, DomainEvent, EventBus};
+use crate::models::consent::validate_consents;
+use crate::models::terms::TermsAcceptance;
+use super::profiling::QueryProfiler;
+use crate::error::AppError;
 
 #[derive(Clone)]
 pub struct MongoDB {
     client: Client,
+    profiler: Arc<QueryProfiler>,
     db_name: String,
+    read_preferences: ReadPreferenceConfig,
+    events: EventBus,
 }
 
 impl MongoDB {
-    pub async fn new(uri: &str, db_name: &str) -> Result<Self, mongodb::error::Error> {
-        let client_options = mongodb::options::ClientOptions::parse(uri).await?;
+    pub async fn new(uri: &str, db_name: &str) -> Result<Self, AppError> {
+        let mut client_options = mongodb::options::ClientOptions::parse(uri).await?;
+        let profiler = Arc::new(QueryProfiler::new(ProfilingConfig::from_env()));
+        client_options.command_event_handler = Some(profiler.clone());
         let client = Client::with_options(client_options)?;
+        let events = EventsConfig::from_env();
         Ok(MongoDB {
             client,
+            profiler,
             db_name: db_name.to_string(),
+            read_preferences: ReadPreferenceConfig::from_env(),
+            events: EventBus::new(events.buffer, events.persist),
         })
     }
 
-    fn get_users_collection(&self) -> Collection<Document> {
-        self.client.database(&self.db_name).collection("users")
+    pub fn events(&self) -> &EventBus {
+        &self.events
     }
 
-    fn get_buses_collection(&self) -> Collection<Bus> {
-        self.client.database(&self.db_name).collection("buses")
+    pub(super) fn profiler(&self) -> &QueryProfiler {
+        &self.profiler
     }
 
-    fn get_seat_availability_collection(&self) -> Collection<Document> {
-        self.client.database(&self.db_name).collection("seat_availability")
+    pub(super) fn database(&self) -> Database {
+        self.client.database(&self.db_name)
     }
 
-    fn get_bookings_collection(&self) -> Collection<Booking> {
-        self.client.database(&self.db_name).collection("bookings")
+    // Multi-document transactions need a replica set or sharded cluster
+    pub(super) async fn start_session(&self) -> Result<ClientSession, AppError> {
+        Ok(self.client.start_session(None).await?)
     }
 
-    pub fn string_to_id(&self, id: &str) -> Result<bson::oid::ObjectId, mongodb::error::Error> {
-        bson::oid::ObjectId::parse_str(id).map_err(|e| {
-            mongodb::error::Error::from(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                e.to_string(),
-            ))
-        })
+    // Same collection, but reads are routed by the configured preference for the workload
+    pub(super) fn collection_for<T>(&self, name: &str, workload: ReadWorkload) -> Collection<T> {
+        let criteria = SelectionCriteria::ReadPreference(self.read_preferences.for_workload(workload).clone());
+        let options = CollectionOptions::builder().selection_criteria(criteria).build();
+        self.database().collection_with_options(name, options)
+    }
+
+    pub(super) fn get_users_collection(&self) -> Collection<User> {
+        self.database().collection("users")
+    }
+
+    pub(super) fn get_buses_collection(&self) -> Collection<Bus> {
+        self.database().collection("buses")
+    }
+
+    pub(super) fn get_seat_availability_collection(&self) -> Collection<SeatAvailability> {
+        self.database().collection("seat_availability")
+    }
+
+    pub(super) fn get_bookings_collection(&self) -> Collection<Booking> {
+        self.database().collection("bookings")
+    }
+
+    pub fn string_to_id(&self, id: &str) -> Result<bson::oid::ObjectId, AppError> {
+        bson::oid::ObjectId::parse_str(id).map_err(|_| AppError::Validation(format!("{} is not a valid id", id)))
     }
 
-    pub async fn create_user(&self, user: &RegisterRequest) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+    pub async fn create_user(&self, user: &RegisterRequest) -> Result<AuthResponse, AppError> {
         let collection = self.get_users_collection();
         
         // Check if user already exists
         let existing_user = collection.find_one(doc! { "email": &user.email }, None).await?;
         if existing_user.is_some() {
-            return Err("User already exists".into());
+            return Err(AppError::conflict("User already exists"));
         }
+        validate_consents(&user.consents, "registration")?;
+
+        // Acceptance is only recorded for the version actually in force
+        let terms = TermsConfig::from_env();
+        let terms_acceptances = match &user.accepted_terms_version {
+            Some(version) if *version == terms.version => vec![TermsAcceptance {
+                version: version.clone(),
+                context: "registration".to_string(),
+                accepted_at: bson::DateTime::now(),
+            }],
+            Some(version) => return Err(format!("Terms version {} is out of date; the current version is {}", version, terms.version).into()),
+            None => Vec::new(),
+        };
 
         let hashed_password = bcrypt::hash(&user.password, bcrypt::DEFAULT_COST)?;
         
-        let user_doc = doc! {
-            "username": &user.username,
-            "email": &user.email,
-            "password": &hashed_password,
-            "role": "user",
-            "created_at": bson::DateTime::now(),
-            "updated_at": bson::DateTime::now(),
+        let new_user = User {
+            id: None,
+            username: user.username.clone(),
+            email: user.email.clone(),
+            password: hashed_password,
+            role: "user".to_string(),
+            created_at: Some(bson::DateTime::now()),
+            updated_at: Some(bson::DateTime::now()),
+            terms_acceptances,
+            operator: None,
+            sessions_revoked_at: None,
+            language: None,
+            last_login_at: Some(bson::DateTime::now()),
+            retention_notice_at: None,
+            anonymized_at: None,
+            merged_into: None,
         };
 
-        let result = collection.insert_one(user_doc, None).await?;
+        let result = collection.insert_one(new_user, None).await?;
         let user_id = result.inserted_id.as_object_id().unwrap();
+        if !user.consents.is_empty() {
+            self.update_consents(user_id, &user.consents, "registration").await?;
+        }
 
-        // Generate JWT token
-        let expiration = chrono::Utc::now() + chrono::Duration::hours(24);
-        let claims = Claims {
-            sub: user_id.to_hex(),
-            role: "user".to_string(),
-            exp: expiration.timestamp() as usize,
-        };
-
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-        let token = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(secret.as_ref()))?;
+        let (token, refresh_token) = self.issue_session(user_id, "user", None, None).await?;
 
         let user_response = UserResponse {
             id: user_id.to_hex(),
@@ -93,45 +146,34 @@ impl MongoDB {
 
         Ok(AuthResponse {
             token,
+            refresh_token,
             user: user_response,
         })
     }
 
-    pub async fn authenticate_user(&self, credentials: &LoginRequest) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+    pub async fn authenticate_user(&self, credentials: &LoginRequest) -> Result<AuthResponse, AppError> {
         let collection = self.get_users_collection();
         
-        let user_doc = collection.find_one(doc! { "email": &credentials.email }, None).await?
-            .ok_or("Invalid credentials")?;
-
-        let user = bson::from_document::<User>(user_doc)?;
+        let user = collection.find_one(doc! { "email": &credentials.email }, None).await?
+            .ok_or_else(|| AppError::unauthorized("Invalid credentials"))?;
 
         if bcrypt::verify(&credentials.password, &user.password).map_err(|e| {
             error!("Bcrypt verification error: {}", e);
             e
         })? {
+            let user = self.resolve_merged_account(user).await?;
             let user_id = user.id.ok_or_else(|| {
                 error!("User document found for {} but missing ID", credentials.email);
                 "User ID not found"
             })?;
             
-            let expiration = chrono::Utc::now() + chrono::Duration::hours(168); // Match .env or use 168
-            let claims = Claims {
-                sub: user_id.to_hex(),
-                role: user.role.clone(),
-                exp: expiration.timestamp() as usize,
-            };
-
-            let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-            let token = jsonwebtoken::encode(
-                &jsonwebtoken::Header::default(), 
-                &claims, 
-                &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
-            ).map_err(|e| {
-                error!("JWT encoding error: {}", e);
+            let (token, refresh_token) = self.issue_session(user_id, &user.role, user.operator.clone(), None).await.map_err(|e| {
+                error!("Failed to issue session for {}: {}", user.email, e);
                 e
             })?;
 
             info!("User {} authenticated successfully", user.email);
+            self.record_login(user_id).await?;
             let user_response = UserResponse {
                 id: user_id.to_hex(),
                 username: user.username,
@@ -141,61 +183,60 @@ impl MongoDB {
 
             Ok(AuthResponse {
                 token,
+                refresh_token,
                 user: user_response,
             })
         } else {
             warn!("Invalid password attempt for email: {}", credentials.email);
-            Err("Invalid credentials".into())
+            Err(AppError::unauthorized("Invalid credentials"))
         }
     }
 
-    pub async fn google_login(&self, email: &str, name: &str) -> Result<AuthResponse, Box<dyn std::error::Error>> {
+    pub async fn google_login(&self, email: &str, name: &str) -> Result<AuthResponse, AppError> {
         let collection = self.get_users_collection();
         
         // Find existing user or create a new one
-        let user_doc = collection.find_one(doc! { "email": email }, None).await?;
+        let existing = collection.find_one(doc! { "email": email }, None).await.map_err(|e| {
+            error!("Failed to load Google user {}: {}", email, e);
+            e
+        })?;
         
-        let (user_id, username, user_email, role) = if let Some(doc) = user_doc {
-            let u = bson::from_document::<User>(doc).map_err(|e| {
-                error!("BSON deserialization error for Google user: {}", e);
-                e
-            })?;
+        let (user_id, username, user_email, role, operator) = if let Some(u) = existing {
+            let u = self.resolve_merged_account(u).await?;
             let uid = u.id.ok_or_else(|| {
                 error!("User found for Google account {} but missing ID", email);
                 "User ID not found"
             })?;
-            (uid, u.username, u.email, u.role)
+            self.record_login(uid).await?;
+            (uid, u.username, u.email, u.role, u.operator)
         } else {
             // Create new user
-            let new_user_doc = doc! {
-                "username": name,
-                "email": email,
-                "password": "", // No password for Google users
-                "role": "user",
-                "created_at": bson::DateTime::now(),
-                "updated_at": bson::DateTime::now(),
+            let new_user = User {
+                id: None,
+                username: name.to_string(),
+                email: email.to_string(),
+                password: String::new(), // No password for Google users
+                role: "user".to_string(),
+                created_at: Some(bson::DateTime::now()),
+                updated_at: Some(bson::DateTime::now()),
+                terms_acceptances: Vec::new(),
+                operator: None,
+                sessions_revoked_at: None,
+                language: None,
+                last_login_at: Some(bson::DateTime::now()),
+                retention_notice_at: None,
+                anonymized_at: None,
+                merged_into: None,
             };
-            let result = collection.insert_one(new_user_doc, None).await?;
-            (result.inserted_id.as_object_id().unwrap(), name.to_string(), email.to_string(), "user".to_string())
+            let result = collection.insert_one(new_user, None).await?;
+            (result.inserted_id.as_object_id().unwrap(), name.to_string(), email.to_string(), "user".to_string(), None)
         };
 
-        // Generate JWT token
-        let expiration = chrono::Utc::now() + chrono::Duration::hours(24);
-        let claims = Claims {
-            sub: user_id.to_hex(),
-            role: role.clone(),
-            exp: expiration.timestamp() as usize,
-        };
-
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-        let token = jsonwebtoken::encode(
-            &jsonwebtoken::Header::default(),
-            &claims,
-            &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
-        )?;
+        let (token, refresh_token) = self.issue_session(user_id, &role, operator, None).await?;
 
         Ok(AuthResponse {
             token,
+            refresh_token,
             user: UserResponse {
                 id: user_id.to_hex(),
                 username,
@@ -205,146 +246,44 @@ impl MongoDB {
         })
     }
 
-    pub async fn get_buses(&self) -> Result<Cursor<Bus>, mongodb::error::Error> {
-        let collection = self.get_buses_collection();
+    pub async fn get_buses(&self) -> Result<Cursor<Bus>, AppError> {
+        let collection = self.collection_for::<Bus>("buses", ReadWorkload::Search);
         let find_options = FindOptions::builder().build();
-        collection.find(None, find_options).await
+        Ok(collection.find(None, find_options).await?)
     }
 
-    pub async fn get_bus(&self, id: &str) -> Result<Option<Bus>, mongodb::error::Error> {
+    pub async fn get_bus(&self, id: &str) -> Result<Option<Bus>, AppError> {
         let collection = self.get_buses_collection();
         let object_id = self.string_to_id(id)?;
-        collection.find_one(doc! { "_id": object_id }, None).await
+        Ok(collection.find_one(doc! { "_id": object_id }, None).await?)
     }
 
-    pub async fn get_bus_seats(&self, bus_id: &str, date: &str) -> Result<Vec<Seat>, mongodb::error::Error> {
-        let collection = self.get_seat_availability_collection();
-        let object_id = self.string_to_id(bus_id)?;
-        
-        let doc = collection.find_one(
-            doc! { "bus_id": object_id, "travel_date": date },
-            None
-        ).await?;
-
-        if let Some(doc) = doc {
-            if let Ok(seats) = doc.get_array("seats") {
-                let seats: Vec<Seat> = seats.iter().filter_map(|s| {
-                    if let Some(seat_doc) = s.as_document() {
-                        Some(Seat {
-                            seat_number: seat_doc.get_str("seat_number").unwrap_or("").to_string(),
-                            is_available: seat_doc.get_bool("is_available").unwrap_or(false),
-                        })
-                    } else {
-                        None
-                    }
-                }).collect();
-                return Ok(seats);
-            }
-        }
+    pub async fn create_booking(&self, user_id: &str, req: &crate::models::booking::CreateBookingRequest, country: Option<&str>) -> Result<crate::models::Booking, AppError> {
+        let mut bookings = self.create_group_booking(user_id, &req.into(), country).await?;
+        bookings.pop().ok_or_else(|| AppError::internal("Booking failed"))
+    }
 
-        // If no specific availability found, return all seats as available
-        let bus = self.get_bus(bus_id).await?;
-        if let Some(bus) = bus {
-            let seats: Vec<Seat> = (1..=bus.total_seats)
-                .map(|i| Seat {
-                    seat_number: i.to_string(),
-                    is_available: true,
-                })
-                .collect();
-            Ok(seats)
-        } else {
-            Ok(vec![])
-        }
+    pub async fn get_booking(&self, booking_id: &str) -> Result<Option<Booking>, AppError> {
+        let booking_oid = self.string_to_id(booking_id)?;
+        Ok(self.get_bookings_collection().find_one(doc! { "_id": booking_oid }, None).await?)
     }
 
-    pub async fn create_booking(&self, user_id: &str, req: &crate::models::booking::CreateBookingRequest) -> Result<crate::models::Booking, Box<dyn std::error::Error>> {
-        let bus_id = self.string_to_id(&req.bus_id)?;
+    pub async fn get_user_booking(&self, booking_id: &str, user_id: &str) -> Result<Option<Booking>, AppError> {
+        let booking_oid = self.string_to_id(booking_id)?;
         let user_oid = self.string_to_id(user_id)?;
-        
-        // 1. Check if seat is available
-        let seats = self.get_bus_seats(&req.bus_id, &req.travel_date).await?;
-        let seat = seats.iter().find(|s| s.seat_number == req.seat_number)
-            .ok_or("Seat not found")?;
-        
-        if !seat.is_available {
-            return Err("Seat is already booked".into());
-        }
-
-        // 2. Create the booking
-        let booking = crate::models::Booking {
-            id: None,
-            user_id: user_oid,
-            bus_id,
-            seat_number: req.seat_number.clone(),
-            travel_date: req.travel_date.clone(),
-            booking_date: bson::DateTime::now(),
-            status: "Confirmed".to_string(),
-            passenger: req.passenger.clone(),
-        };
-
-        let collection = self.get_bookings_collection();
-        let result = collection.insert_one(&booking, None).await?;
-        let mut new_booking = booking;
-        new_booking.id = Some(result.inserted_id.as_object_id().unwrap());
+        Ok(self.get_bookings_collection().find_one(doc! { "_id": booking_oid, "user_id": user_oid }, None).await?)
+    }
 
-        // 3. Update seat availability
-        let availability_coll = self.get_seat_availability_collection();
-        
-        // Find current availability doc
-        let current_availability = availability_coll.find_one(
-            doc! { "bus_id": bus_id, "travel_date": &req.travel_date },
+    pub async fn record_booking_event(&self, booking_id: bson::oid::ObjectId, event: BookingEvent) -> Result<(), AppError> {
+        self.get_bookings_collection().update_one(
+            doc! { "_id": booking_id },
+            doc! { "$push": { "history": bson::to_bson(&event)? } },
             None
         ).await?;
-
-        if let Some(mut doc) = current_availability {
-            if let Ok(seats_array) = doc.get_array_mut("seats") {
-                for s in seats_array.iter_mut() {
-                    if let Some(s_doc) = s.as_document_mut() {
-                        if s_doc.get_str("seat_number").unwrap_or("") == req.seat_number {
-                            s_doc.insert("is_available", false);
-                        }
-                    }
-                }
-                availability_coll.replace_one(
-                    doc! { "_id": doc.get_object_id("_id")? },
-                    doc,
-                    None
-                ).await?;
-            }
-        } else {
-            // Create initial availability with this seat booked
-            let bus = self.get_bus(&req.bus_id).await?.ok_or("Bus not found")?;
-            let mut seats_doc = Vec::new();
-            for i in 1..=bus.total_seats {
-                let seat_num = i.to_string();
-                seats_doc.push(doc! {
-                    "seat_number": &seat_num,
-                    "is_available": seat_num != req.seat_number
-                });
-            }
-            availability_coll.insert_one(doc! {
-                "bus_id": bus_id,
-                "travel_date": &req.travel_date,
-                "seats": seats_doc
-            }, None).await?;
-        }
-
-        Ok(new_booking)
-    }
-
-    pub async fn get_user_bookings(&self, user_id: &str) -> Result<Vec<crate::models::Booking>, Box<dyn std::error::Error>> {
-        let user_oid = self.string_to_id(user_id)?;
-        let collection = self.get_bookings_collection();
-        let mut cursor = collection.find(doc! { "user_id": user_oid }, None).await?;
-        
-        let mut bookings = Vec::new();
-        while let Some(result) = cursor.next().await {
-            bookings.push(result?);
-        }
-        Ok(bookings)
+        Ok(())
     }
 
-    pub async fn cancel_booking(&self, booking_id: &str, user_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn cancel_booking(&self, booking_id: &str, user_id: &str, actor: &str) -> Result<(), AppError> {
         let booking_oid = self.string_to_id(booking_id)?;
         let user_oid = self.string_to_id(user_id)?;
         let collection = self.get_bookings_collection();
@@ -353,43 +292,32 @@ impl MongoDB {
         let booking = collection.find_one(
             doc! { "_id": booking_oid, "user_id": user_oid },
             None
-        ).await?.ok_or("Booking not found")?;
+        ).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
 
         // 2. Update booking status
+        let event = BookingEvent::new("cancelled", actor, None);
         collection.update_one(
             doc! { "_id": booking_oid },
-            doc! { "$set": { "status": "Cancelled" } },
+            doc! { "$set": { "status": "Cancelled" }, "$push": { "history": bson::to_bson(&event)? } },
             None
         ).await?;
 
         // 3. Update seat availability
-        let availability_coll = self.get_seat_availability_collection();
-        let current_availability = availability_coll.find_one(
-            doc! { "bus_id": booking.bus_id, "travel_date": &booking.travel_date },
-            None
-        ).await?;
+        self.release_seat(booking.bus_id, &booking.travel_date, &booking.seat_number).await?;
 
-        if let Some(mut doc) = current_availability {
-            if let Ok(seats_array) = doc.get_array_mut("seats") {
-                for s in seats_array.iter_mut() {
-                    if let Some(s_doc) = s.as_document_mut() {
-                        if s_doc.get_str("seat_number").unwrap_or("") == booking.seat_number {
-                            s_doc.insert("is_available", true);
-                        }
-                    }
-                }
-                availability_coll.replace_one(
-                    doc! { "_id": doc.get_object_id("_id")? },
-                    doc,
-                    None
-                ).await?;
-            }
-        }
+        // 4. The operator is no longer owed this fare
+        self.reverse_booking_split(booking_oid).await?;
+
+        // 5. Let subscribers know
+        self.publish_event(DomainEvent::BookingCancelled {
+            booking: BookingSnapshot::from(&booking).with_status("Cancelled"),
+            actor: actor.to_string(),
+        }).await;
 
         Ok(())
     }
 
-    pub async fn seed_data(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn seed_data(&self) -> Result<(), AppError> {
         let collection = self.get_buses_collection();
         
         // Force seed if env var is set
@@ -410,6 +338,8 @@ impl MongoDB {
                     bus_number: "Easy Coach - KCH 123A".to_string(),
                     bus_type: "Standard".to_string(),
                     total_seats: 44,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Kisumu".to_string(),
@@ -423,6 +353,8 @@ impl MongoDB {
                     bus_number: "Mash East Africa - KDA 456B".to_string(),
                     bus_type: "VIP Oxygen".to_string(),
                     total_seats: 36,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Mombasa".to_string(),
@@ -436,6 +368,8 @@ impl MongoDB {
                     bus_number: "Tahmeed - KDB 789C".to_string(),
                     bus_type: "Luxury Coach".to_string(),
                     total_seats: 32,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Mombasa".to_string(),
                         to: "Nairobi".to_string(),
@@ -449,6 +383,8 @@ impl MongoDB {
                     bus_number: "Dreamline - KDC 012D".to_string(),
                     bus_type: "Executive".to_string(),
                     total_seats: 40,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Eldoret".to_string(),
@@ -462,6 +398,8 @@ impl MongoDB {
                     bus_number: "Guardian Angel - KDD 345E".to_string(),
                     bus_type: "Standard".to_string(),
                     total_seats: 52,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Busia".to_string(),
@@ -475,6 +413,8 @@ impl MongoDB {
                     bus_number: "Modern Coast - KDE 678F".to_string(),
                     bus_type: "VIP".to_string(),
                     total_seats: 28,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Mombasa".to_string(),
@@ -488,6 +428,8 @@ impl MongoDB {
                     bus_number: "Super Metro - KDF 901G".to_string(),
                     bus_type: "Semi-Luxury".to_string(),
                     total_seats: 48,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Nakuru".to_string(),
@@ -499,8 +441,10 @@ impl MongoDB {
                 Bus {
                     id: None,
                     bus_number: "Transline Galaxy - KDG 234H".to_string(),
-                    bus_type: "Standard".to_string(),
+                    bus_type: "Shuttle".to_string(),
                     total_seats: 14,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Kisii".to_string(),
@@ -514,6 +458,8 @@ impl MongoDB {
                     bus_number: "Spanish - KDH 567I".to_string(),
                     bus_type: "Standard Coach".to_string(),
                     total_seats: 52,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Kakamega".to_string(),
@@ -527,6 +473,8 @@ impl MongoDB {
                     bus_number: "Mash East Africa - KDI 890J".to_string(),
                     bus_type: "Standard".to_string(),
                     total_seats: 52,
+                    policy_id: None,
+                    layout: None,
                     route: crate::models::bus::Route {
                         from: "Nairobi".to_string(),
                         to: "Malindi".to_string(),
@@ -537,11 +485,14 @@ impl MongoDB {
                 },
             ];
             
-            for bus in sample_buses {
-                collection.insert_one(bus, None).await?;
+            let report = self.insert_batched(&collection, sample_buses).await?;
+            for failure in &report.failed {
+                warn!("Failed to seed bus #{}: {}", failure.index, failure.message);
             }
-            println!("✅ Seeding complete with {} actual buses!", 10);
+            println!("✅ Seeding complete with {} actual buses!", report.inserted);
         }
+
+        self.seed_localizations().await?;
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use mongodb::{
+    bson::{self, doc, Document},
+    options::{IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::monitoring::MonitoringEvent;
+
+impl MongoDB {
+    fn get_monitoring_events_collection(&self) -> Collection<MonitoringEvent> {
+        self.database().collection("monitoring_events")
+    }
+
+    // Raw events are only needed for the sliding windows, so they expire after a week
+    pub async fn ensure_monitoring_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "at": 1 })
+            .options(IndexOptions::builder().expire_after(Duration::from_secs(7 * 24 * 3600)).build())
+            .build();
+        self.get_monitoring_events_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    fn get_monitoring_alerts_collection(&self) -> Collection<Document> {
+        self.database().collection("monitoring_alerts")
+    }
+
+    /// Marks an alert as sent unless one of its kind went out within `cooldown`. Kept in Mongo
+    /// rather than per instance, so whichever instance runs the check next sees the last page.
+    pub async fn claim_alert(&self, kind: &str, cooldown: chrono::Duration) -> Result<bool, AppError> {
+        let now = chrono::Utc::now();
+        let cutoff = bson::DateTime::from_millis((now - cooldown).timestamp_millis());
+        let options = UpdateOptions::builder().upsert(true).build();
+        match self.get_monitoring_alerts_collection().update_one(
+            doc! { "_id": kind, "last_alerted_at": { "$lt": cutoff } },
+            doc! { "$set": { "last_alerted_at": bson::DateTime::from_millis(now.timestamp_millis()) } },
+            options
+        ).await {
+            Ok(_) => Ok(true),
+            // The upsert collides with the existing record while it is still cooling down
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn record_monitoring_event(&self, kind: &str, detail: Option<String>) -> Result<(), AppError> {
+        let event = MonitoringEvent {
+            kind: kind.to_string(),
+            detail,
+            subject: None,
+            at: bson::DateTime::now(),
+        };
+        self.get_monitoring_events_collection().insert_one(event, None).await?;
+        Ok(())
+    }
+
+    /// Records an event against a user or device, for the per-subject counts fraud scoring reads.
+    pub async fn record_subject_event(&self, kind: &str, subject: &str, detail: Option<String>) -> Result<(), AppError> {
+        let event = MonitoringEvent {
+            kind: kind.to_string(),
+            detail,
+            subject: Some(subject.to_string()),
+            at: bson::DateTime::now(),
+        };
+        self.get_monitoring_events_collection().insert_one(event, None).await?;
+        Ok(())
+    }
+
+    pub async fn count_monitoring_events(&self, kind: &str, since: bson::DateTime) -> Result<u64, AppError> {
+        Ok(self.get_monitoring_events_collection()
+            .count_documents(doc! { "kind": kind, "at": { "$gte": since } }, None)
+            .await?)
+    }
+
+    pub async fn count_subject_events(&self, kind: &str, subject: &str, since: bson::DateTime) -> Result<u64, AppError> {
+        Ok(self.get_monitoring_events_collection()
+            .count_documents(doc! { "kind": kind, "subject": subject, "at": { "$gte": since } }, None)
+            .await?)
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::crypto;
+use crate::models::booking::NextOfKin;
+
+// Kept out of the booking document so no booking query or response can ever carry it
+#[derive(Serialize, Deserialize)]
+struct SealedNextOfKin {
+    booking_id: ObjectId,
+    sealed: String,
+    created_at: bson::DateTime,
+}
+
+impl MongoDB {
+    fn get_next_of_kin_collection(&self) -> Collection<SealedNextOfKin> {
+        self.database().collection("passenger_next_of_kin")
+    }
+
+    pub async fn ensure_next_of_kin_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "booking_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_next_of_kin_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    /// Encrypts the contact bound to its booking, so the sealed value is useless on any other record.
+    pub async fn store_next_of_kin(&self, booking_id: ObjectId, next_of_kin: &NextOfKin) -> Result<(), AppError> {
+        let sealed = crypto::seal(&serde_json::to_string(next_of_kin)?, &booking_id.to_hex())?;
+        self.get_next_of_kin_collection().insert_one(
+            SealedNextOfKin { booking_id, sealed, created_at: bson::DateTime::now() },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Decrypted contacts for the given bookings; ones that fail to open are logged and left out.
+    pub async fn get_next_of_kin(&self, booking_ids: &[ObjectId]) -> Result<HashMap<ObjectId, NextOfKin>, AppError> {
+        let mut cursor = self.get_next_of_kin_collection()
+            .find(doc! { "booking_id": { "$in": booking_ids.to_vec() } }, None)
+            .await?;
+
+        let mut contacts = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            let record = result?;
+            let opened = crypto::open(&record.sealed, &record.booking_id.to_hex())
+                .and_then(|json| Ok(serde_json::from_str::<NextOfKin>(&json)?));
+            match opened {
+                Ok(next_of_kin) => {
+                    contacts.insert(record.booking_id, next_of_kin);
+                }
+                Err(e) => error!("Failed to open next of kin for booking {}: {}", record.booking_id, e),
+            }
+        }
+        Ok(contacts)
+    }
+
+    pub async fn delete_next_of_kin(&self, booking_ids: &[ObjectId]) -> Result<u64, AppError> {
+        if booking_ids.is_empty() {
+            return Ok(0);
+        }
+        let result = self.get_next_of_kin_collection()
+            .delete_many(doc! { "booking_id": { "$in": booking_ids } }, None)
+            .await?;
+        Ok(result.deleted_count)
+    }
+}
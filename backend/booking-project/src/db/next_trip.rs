@@ -0,0 +1,146 @@
+use futures::stream::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::{FindOptions, ReplaceOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+use crate::models::next_trip::{NextTrip, UpcomingTrip};
+use crate::models::Booking;
+
+// How long a card is served without a rebuild, in case a booking change didn't reach it
+const REFRESH_MINUTES: i64 = 15;
+// Bookings looked at when rebuilding; nobody has more confirmed trips than this coming up soon
+const CANDIDATES: i64 = 20;
+
+impl MongoDB {
+    fn get_next_trips_collection(&self) -> Collection<NextTrip> {
+        self.database().collection("next_trips")
+    }
+
+    pub async fn ensure_next_trip_indexes(&self) -> Result<(), AppError> {
+        self.get_next_trips_collection().create_index(
+            IndexModel::builder().keys(doc! { "trip.bus_id": 1, "trip.travel_date": 1 }).build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// The user's nearest upcoming confirmed trip, from the cached card while it is still good.
+    pub async fn get_next_trip(&self, user_id: &str) -> Result<Option<UpcomingTrip>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let cached = self.get_next_trips_collection().find_one(doc! { "_id": user_oid }, None).await?;
+        match cached {
+            Some(card) if card.valid_until > bson::DateTime::now() => Ok(card.trip),
+            _ => Ok(self.refresh_next_trip(user_oid).await?.trip),
+        }
+    }
+
+    /// Rebuilds the user's card from their bookings.
+    pub async fn refresh_next_trip(&self, user_id: ObjectId) -> Result<NextTrip, AppError> {
+        let now = chrono::Utc::now();
+        // From yesterday, so an overnight bus that is still on the road counts
+        let since = (now.with_timezone(&local_offset()).date_naive() - chrono::Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string();
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "user_id": user_id, "status": "Confirmed", "travel_date": { "$gte": since } },
+            FindOptions::builder().sort(doc! { "travel_date": 1 }).limit(CANDIDATES).build()
+        ).await?;
+
+        let mut next: Option<UpcomingTrip> = None;
+        while let Some(booking) = cursor.next().await {
+            let Some(trip) = self.upcoming_trip(&booking?).await? else { continue };
+            if trip.is_over(now) || next.as_ref().is_some_and(|n| n.departure_at <= trip.departure_at) {
+                continue;
+            }
+            next = Some(trip);
+        }
+
+        let mut valid_until = (now + chrono::Duration::minutes(REFRESH_MINUTES)).timestamp_millis();
+        if let Some(arrival) = next.as_ref().and_then(|t| t.arrival_at) {
+            valid_until = valid_until.min(arrival.timestamp_millis());
+        }
+        let card = NextTrip {
+            user_id,
+            trip: next,
+            valid_until: bson::DateTime::from_millis(valid_until),
+            refreshed_at: bson::DateTime::from_millis(now.timestamp_millis()),
+        };
+        self.get_next_trips_collection().replace_one(
+            doc! { "_id": user_id },
+            &card,
+            ReplaceOptions::builder().upsert(true).build()
+        ).await?;
+        Ok(card)
+    }
+
+    /// Rebuilds the card after one of the user's bookings changed; failures only cost freshness.
+    pub async fn refresh_next_trip_quietly(&self, user_id: ObjectId) {
+        if let Err(e) = self.refresh_next_trip(user_id).await {
+            error!("Failed to refresh next trip for user {}: {}", user_id, e);
+        }
+    }
+
+    /// Rebuilds every card showing a trip, after its vehicle or seating changed.
+    pub(super) async fn refresh_next_trips_for_trip(&self, bus_id: ObjectId, travel_date: &str) -> Result<(), AppError> {
+        let mut cursor = self.get_next_trips_collection()
+            .find(doc! { "trip.bus_id": bus_id, "trip.travel_date": travel_date }, None)
+            .await?;
+        while let Some(card) = cursor.next().await {
+            self.refresh_next_trip_quietly(card?.user_id).await;
+        }
+        Ok(())
+    }
+
+    /// Carries a trip status update onto the cards showing that trip.
+    pub(super) async fn update_next_trip_status(&self, bus_id: ObjectId, travel_date: &str, status: &str, delay_minutes: i64) -> Result<(), AppError> {
+        let mut set = doc! { "trip.status": status, "trip.delay_minutes": delay_minutes };
+        // An arrived trip leaves the card, so the next read rebuilds it
+        if status == "arrived" {
+            set.insert("valid_until", bson::DateTime::now());
+        }
+        self.get_next_trips_collection().update_many(
+            doc! { "trip.bus_id": bus_id, "trip.travel_date": travel_date },
+            doc! { "$set": set },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    async fn upcoming_trip(&self, booking: &Booking) -> Result<Option<UpcomingTrip>, AppError> {
+        let (Some(booking_id), Some(bus)) = (booking.id, self.get_bus(&booking.bus_id.to_hex()).await?) else {
+            return Ok(None);
+        };
+        let Some(departure) = bus.route.departure_at(&booking.travel_date) else {
+            return Ok(None);
+        };
+        let status = self.get_trip_status_collection()
+            .find_one(doc! { "bus_id": booking.bus_id, "travel_date": &booking.travel_date }, None)
+            .await?;
+        // A swapped-in vehicle is the one the passenger has to look for
+        let bus_number = match self.get_trip_vehicle(booking.bus_id, &booking.travel_date).await? {
+            Some(vehicle) => vehicle.bus_number,
+            None => bus.bus_number.clone(),
+        };
+        Ok(Some(UpcomingTrip {
+            booking_id,
+            bus_id: booking.bus_id,
+            bus_number,
+            bus_type: bus.bus_type.clone(),
+            boarding_point: bus.route.from.clone(),
+            departure_at: bson::DateTime::from_millis(departure.timestamp_millis()),
+            arrival_at: bus.route.arrival_at(&booking.travel_date).map(|a| bson::DateTime::from_millis(a.timestamp_millis())),
+            from: bus.route.from,
+            to: bus.route.to,
+            travel_date: booking.travel_date.clone(),
+            seat_number: booking.seat_number.clone(),
+            status: status.as_ref().map(|s| s.status.clone()).unwrap_or_else(|| "scheduled".to_string()),
+            delay_minutes: status.map(|s| s.delay_minutes).unwrap_or(0),
+        }))
+    }
+}
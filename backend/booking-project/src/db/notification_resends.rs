@@ -0,0 +1,179 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::notification_resend::{
+    NotificationResend, ResendConfirmationsRequest, ResendTally, MAX_RECORDED_FAILURES, RESEND_COMPLETED, RESEND_QUEUED,
+    RESEND_RUNNING,
+};
+use crate::models::tenancy::Tenant;
+use crate::models::Booking;
+
+// Bookings handled between progress updates
+const RESEND_BATCH_SIZE: i64 = 100;
+// How long a worker owns a claimed run before another instance may pick it up where it stopped
+const RESEND_LEASE_SECS: i64 = 300;
+
+fn resend_lease() -> bson::DateTime {
+    bson::DateTime::from_millis((chrono::Utc::now() + chrono::Duration::seconds(RESEND_LEASE_SECS)).timestamp_millis())
+}
+
+impl MongoDB {
+    fn get_notification_resends_collection(&self) -> Collection<NotificationResend> {
+        self.database().collection("notification_resends")
+    }
+
+    pub async fn ensure_notification_resend_indexes(&self) -> Result<(), AppError> {
+        let resends = self.get_notification_resends_collection();
+        resends.create_index(IndexModel::builder().keys(doc! { "state": 1, "lease_until": 1 }).build(), None).await?;
+        resends.create_index(IndexModel::builder().keys(doc! { "operator": 1, "requested_at": -1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// Queues a resend of the confirmations for every matching booking; the resend job works
+    /// through it in the background.
+    pub async fn request_confirmation_resend(&self, tenant: &Tenant, req: &ResendConfirmationsRequest, actor: &str) -> Result<NotificationResend, AppError> {
+        req.validate()?;
+        let bus_id = match &req.bus_id {
+            Some(bus_id) => {
+                self.ensure_bus_owned(tenant, bus_id).await?;
+                Some(self.string_to_id(bus_id)?)
+            }
+            None => None,
+        };
+        let operator = match tenant {
+            Tenant::Platform => None,
+            Tenant::Operator(operator) => Some(operator.clone()),
+        };
+        let mut run = NotificationResend::new(req, operator, bus_id, 0, actor);
+
+        let resends = self.get_notification_resends_collection();
+        let unfinished = doc! {
+            "state": { "$in": [RESEND_QUEUED, RESEND_RUNNING] },
+            "operator": &run.operator,
+            "bus_id": run.bus_id,
+            "travel_date": &run.travel_date,
+            "booking_status": &run.booking_status,
+        };
+        if resends.find_one(unfinished, None).await?.is_some() {
+            return Err(AppError::conflict("A resend for these bookings is already in progress"));
+        }
+
+        let filter = self.scope_bookings_filter(tenant, run.booking_filter()).await?;
+        run.total = self.get_bookings_collection().count_documents(filter, None).await?;
+        if run.total == 0 {
+            return Err(AppError::not_found("No bookings match, so there is nothing to resend"));
+        }
+        let result = resends.insert_one(&run, None).await?;
+        run.id = result.inserted_id.as_object_id();
+
+        self.record_audit(
+            "notifications.resend_requested",
+            actor,
+            &format!("resend:{}", run.id.map(|id| id.to_hex()).unwrap_or_default()),
+            Some(format!("{} {} booking(s) by {}", run.total, run.booking_status, run.channels.join(", "))),
+        ).await?;
+        Ok(run)
+    }
+
+    /// The tenant's resends, newest first.
+    pub async fn list_notification_resends(&self, tenant: &Tenant) -> Result<Vec<NotificationResend>, AppError> {
+        let filter = match tenant {
+            Tenant::Platform => doc! {},
+            Tenant::Operator(operator) => doc! { "operator": operator },
+        };
+        let options = FindOptions::builder().sort(doc! { "requested_at": -1 }).limit(50).build();
+        let mut cursor = self.get_notification_resends_collection().find(filter, options).await?;
+        let mut runs = Vec::new();
+        while let Some(run) = cursor.next().await {
+            runs.push(run?);
+        }
+        Ok(runs)
+    }
+
+    // Another operator's runs read as missing
+    pub async fn get_notification_resend(&self, tenant: &Tenant, id: &str) -> Result<NotificationResend, AppError> {
+        let run_oid = ObjectId::parse_str(id).map_err(|_| AppError::not_found("Resend not found"))?;
+        match self.get_notification_resends_collection().find_one(doc! { "_id": run_oid }, None).await? {
+            Some(run) if *tenant == Tenant::Platform || run.operator.as_deref().is_some_and(|o| tenant.owns_operator(o)) => Ok(run),
+            _ => Err(AppError::not_found("Resend not found")),
+        }
+    }
+
+    /// Atomically claims the oldest run nobody is working on, including one whose worker died.
+    pub async fn claim_notification_resend(&self) -> Result<Option<NotificationResend>, AppError> {
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! { "requested_at": 1 })
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self.get_notification_resends_collection().find_one_and_update(
+            doc! { "state": { "$in": [RESEND_QUEUED, RESEND_RUNNING] }, "lease_until": { "$lte": bson::DateTime::now() } },
+            doc! { "$set": { "state": RESEND_RUNNING, "lease_until": resend_lease() } },
+            options
+        ).await?)
+    }
+
+    /// Resends the next batch of the run's bookings and records the progress, returning the run
+    /// as it now stands. A batch short of the batch size finishes the run.
+    pub async fn resend_confirmation_batch(&self, run: &NotificationResend) -> Result<NotificationResend, AppError> {
+        let run_id = run.id.ok_or_else(|| AppError::internal("Missing resend id"))?;
+        let tenant = match &run.operator {
+            Some(operator) => Tenant::Operator(operator.clone()),
+            None => Tenant::Platform,
+        };
+        let mut filter = run.booking_filter();
+        if let Some(after) = run.after {
+            filter.insert("_id", doc! { "$gt": after });
+        }
+        let filter = self.scope_bookings_filter(&tenant, filter).await?;
+        let options = FindOptions::builder().sort(doc! { "_id": 1 }).limit(RESEND_BATCH_SIZE).build();
+        let mut cursor = self.get_bookings_collection().find(filter, options).await?;
+
+        let mut tally = ResendTally::default();
+        let mut after = run.after;
+        let mut fetched = 0;
+        while let Some(booking) = cursor.next().await {
+            let booking = booking?;
+            fetched += 1;
+            after = booking.id.or(after);
+            match self.resend_confirmation(run, &booking).await {
+                Ok((email, sms)) => tally.record(email, sms),
+                Err(e) => tally.record_failure(&booking.id.map(|id| id.to_hex()).unwrap_or_default(), e.to_string()),
+            }
+        }
+
+        let mut set = doc! { "after": after, "lease_until": resend_lease() };
+        if fetched < RESEND_BATCH_SIZE {
+            set.insert("state", RESEND_COMPLETED);
+            set.insert("finished_at", bson::DateTime::now());
+        }
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        self.get_notification_resends_collection().find_one_and_update(
+            doc! { "_id": run_id },
+            doc! {
+                "$set": set,
+                "$inc": {
+                    "processed": tally.processed as i64,
+                    "emails_queued": tally.emails_queued as i64,
+                    "sms_queued": tally.sms_queued as i64,
+                    "skipped": tally.skipped as i64,
+                    "failed": tally.failures.len() as i64,
+                },
+                "$push": { "failures": { "$each": bson::to_bson(&tally.failures)?, "$slice": -MAX_RECORDED_FAILURES } },
+            },
+            options
+        ).await?.ok_or_else(|| AppError::not_found("Resend not found"))
+    }
+
+    // Which of the run's channels a confirmation went out on; a booking with nowhere to send it is neither
+    async fn resend_confirmation(&self, run: &NotificationResend, booking: &Booking) -> Result<(bool, bool), AppError> {
+        let email = run.sends("email") && self.queue_booking_confirmation_email(booking, None).await?;
+        let sms = run.sends("sms") && self.queue_booking_confirmation_sms(booking, None).await?;
+        Ok((email, sms))
+    }
+}
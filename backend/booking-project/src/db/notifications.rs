@@ -0,0 +1,69 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::Notification;
+
+impl MongoDB {
+    fn get_notifications_collection(&self) -> Collection<Notification> {
+        self.database().collection("notifications")
+    }
+
+    pub async fn notify_admins(&self, title: &str, message: &str) -> Result<(), AppError> {
+        info!("Admin notification: {}", title);
+        self.insert_notification("admin", None, title, message).await
+    }
+
+    pub async fn notify_user(&self, user_id: ObjectId, title: &str, message: &str) -> Result<(), AppError> {
+        self.insert_notification("user", Some(user_id), title, message).await
+    }
+
+    async fn insert_notification(
+        &self,
+        audience: &str,
+        user_id: Option<ObjectId>,
+        title: &str,
+        message: &str,
+    ) -> Result<(), AppError> {
+        let notification = Notification {
+            id: None,
+            audience: audience.to_string(),
+            user_id,
+            title: title.to_string(),
+            message: message.to_string(),
+            read: false,
+            created_at: bson::DateTime::now(),
+        };
+        self.get_notifications_collection().insert_one(notification, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_admin_notifications(&self) -> Result<Vec<Notification>, AppError> {
+        self.find_notifications(doc! { "audience": "admin" }).await
+    }
+
+    pub async fn get_user_notifications(&self, user_id: &str) -> Result<Vec<Notification>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        self.find_notifications(doc! { "audience": "user", "user_id": user_oid }).await
+    }
+
+    async fn find_notifications(&self, filter: bson::Document) -> Result<Vec<Notification>, AppError> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .limit(100)
+            .build();
+        let mut cursor = self.get_notifications_collection().find(filter, find_options).await?;
+
+        let mut notifications = Vec::new();
+        while let Some(result) = cursor.next().await {
+            notifications.push(result?);
+        }
+        Ok(notifications)
+    }
+}
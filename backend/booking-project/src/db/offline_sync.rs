@@ -0,0 +1,328 @@
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::{InventoryConfig, TermsConfig, TicketConfig};
+use crate::events::DomainEvent;
+use crate::models::booking::{BookingEvent, NextOfKin, Passenger};
+use crate::models::bus::{Bus, OPEN_SEATING};
+use crate::models::lookup::new_booking_reference;
+use crate::models::manifest::TripAccessClaims;
+use crate::models::offline_sync::{
+    offline_time, OfflineCashSale, OfflineCheckIn, OfflineManifest, OfflineTicket, ReconciliationReport, SyncItemResult, SyncRecord, SyncUploadRequest,
+    SYNC_APPLIED, SYNC_CLAIM_SECS, SYNC_CONFLICT, SYNC_DUPLICATE, SYNC_REJECTED,
+};
+use crate::models::ticket::{sign_ticket, ticket_hash, TicketClaims};
+use crate::models::Booking;
+
+fn to_bson_time(at: chrono::DateTime<chrono::Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(at.timestamp_millis())
+}
+
+impl MongoDB {
+    fn get_offline_sync_collection(&self) -> Collection<SyncRecord> {
+        self.database().collection("offline_sync_items")
+    }
+
+    pub async fn ensure_offline_sync_indexes(&self) -> Result<(), AppError> {
+        self.get_offline_sync_collection().create_index(
+            IndexModel::builder()
+                .keys(doc! { "bus_id": 1, "travel_date": 1, "client_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// The trip as the conductor app needs it to board passengers with no signal, for a token
+    /// that can upload until `upload_until`.
+    pub async fn get_offline_manifest(&self, access: &TripAccessClaims, upload_until: chrono::DateTime<chrono::Utc>) -> Result<OfflineManifest, AppError> {
+        let bus = self.get_bus(&access.bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let manifest = self.get_manifest(&access.bus_id, &access.travel_date).await?;
+        let signing_key = TicketConfig::from_env().signing_key;
+
+        let tickets = manifest.passengers.into_iter().map(|p| {
+            let claims = TicketClaims {
+                booking_id: p.booking_id.clone(),
+                bus_id: manifest.bus_id.clone(),
+                travel_date: manifest.travel_date.clone(),
+                seat_number: p.seat_number.clone(),
+            };
+            OfflineTicket {
+                qr_hash: ticket_hash(&sign_ticket(&claims, &signing_key)),
+                booking_id: p.booking_id,
+                seat_number: p.seat_number,
+                passenger_name: p.passenger_name,
+                checked_in: p.checked_in,
+            }
+        }).collect();
+        // Shuttles sold by headcount have no seats to list; their cash sales use the open seat
+        let free_seats = if InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) {
+            vec![]
+        } else {
+            self.get_bus_seats(&access.bus_id, &access.travel_date).await?
+                .into_iter()
+                .filter(|s| s.is_available)
+                .map(|s| s.seat_number)
+                .collect()
+        };
+
+        Ok(OfflineManifest {
+            bus_id: manifest.bus_id,
+            bus_number: manifest.bus_number,
+            travel_date: manifest.travel_date,
+            from: manifest.from,
+            to: manifest.to,
+            departure: manifest.departure,
+            fare: bus.route.price,
+            free_seats,
+            tickets,
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            upload_until: upload_until.to_rfc3339(),
+        })
+    }
+
+    /// Applies a batch recorded offline and reports how every item landed. Check-ins go first,
+    /// then cash sales in the order they were made, so when two handsets sold the same seat the
+    /// earlier sale keeps it. Items already synced get their first answer again.
+    pub async fn sync_offline_batch(&self, access: &TripAccessClaims, req: &SyncUploadRequest, device_id: Option<&str>) -> Result<ReconciliationReport, AppError> {
+        req.validate()?;
+        let bus = self.get_bus(&access.bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let now = chrono::Utc::now();
+
+        let mut results = Vec::new();
+        for check_in in &req.check_ins {
+            let result = match self.claim_sync_item(bus_oid, &access.travel_date, &check_in.client_id, "check_in", device_id).await? {
+                Some(earlier) => earlier,
+                None => {
+                    let applied = self.apply_offline_check_in(access, bus_oid, check_in, now).await;
+                    self.finish_sync_item(bus_oid, &access.travel_date, &check_in.client_id, applied).await?
+                }
+            };
+            results.push(result);
+        }
+
+        let mut sales: Vec<&OfflineCashSale> = req.cash_sales.iter().collect();
+        sales.sort_by_key(|s| offline_time(&s.sold_at, now).ok());
+        for sale in sales {
+            let result = match self.claim_sync_item(bus_oid, &access.travel_date, &sale.client_id, "cash_sale", device_id).await? {
+                Some(earlier) => earlier,
+                None => {
+                    let applied = self.apply_offline_cash_sale(access, &bus, sale, now).await;
+                    self.finish_sync_item(bus_oid, &access.travel_date, &sale.client_id, applied).await?
+                }
+            };
+            results.push(result);
+        }
+
+        let not_boarded = self.get_manifest(&access.bus_id, &access.travel_date).await?
+            .passengers
+            .into_iter()
+            .filter(|p| !p.checked_in)
+            .map(|p| p.seat_number)
+            .collect();
+        Ok(ReconciliationReport::new(&access.bus_id, &access.travel_date, results, not_boarded))
+    }
+
+    // Takes the item for this upload; Some with the earlier answer when it was already synced
+    async fn claim_sync_item(&self, bus_id: ObjectId, travel_date: &str, client_id: &str, kind: &str, device_id: Option<&str>) -> Result<Option<SyncItemResult>, AppError> {
+        let record = SyncRecord {
+            id: None,
+            bus_id,
+            travel_date: travel_date.to_string(),
+            client_id: client_id.to_string(),
+            device_id: device_id.map(str::to_string),
+            result: None,
+            synced_at: bson::DateTime::now(),
+            claimed_at: Some(bson::DateTime::now()),
+        };
+        match self.get_offline_sync_collection().insert_one(&record, None).await {
+            Ok(_) => Ok(None),
+            Err(e) if is_duplicate_key(&e) => {
+                // The upload that claimed it died before answering, so this one takes over
+                let stale = to_bson_time(chrono::Utc::now() - chrono::Duration::seconds(SYNC_CLAIM_SECS));
+                let taken_over = self.get_offline_sync_collection().find_one_and_update(
+                    doc! {
+                        "bus_id": bus_id,
+                        "travel_date": travel_date,
+                        "client_id": client_id,
+                        "result": bson::Bson::Null,
+                        "$or": [{ "claimed_at": { "$lt": stale } }, { "claimed_at": bson::Bson::Null }],
+                    },
+                    doc! { "$set": { "claimed_at": bson::DateTime::now(), "device_id": device_id } },
+                    None
+                ).await?;
+                if taken_over.is_some() {
+                    return Ok(None);
+                }
+
+                let earlier = self.get_offline_sync_collection()
+                    .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date, "client_id": client_id }, None)
+                    .await?
+                    .and_then(|r| r.result);
+                // Another upload of the same batch is still working on it
+                Ok(Some(earlier.unwrap_or_else(|| SyncItemResult::new(
+                    client_id, kind, SYNC_REJECTED, Some("This item is still being synced, send it again shortly".to_string()), None,
+                ))))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Stores the item's answer, or lets go of it when applying it failed so a resend can retry
+    async fn finish_sync_item(&self, bus_id: ObjectId, travel_date: &str, client_id: &str, applied: Result<SyncItemResult, AppError>) -> Result<SyncItemResult, AppError> {
+        let filter = doc! { "bus_id": bus_id, "travel_date": travel_date, "client_id": client_id };
+        match applied {
+            Ok(result) => {
+                self.get_offline_sync_collection()
+                    .update_one(filter, doc! { "$set": { "result": bson::to_bson(&result)? } }, None)
+                    .await?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.get_offline_sync_collection().delete_one(filter, None).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn apply_offline_check_in(&self, access: &TripAccessClaims, bus_id: ObjectId, check_in: &OfflineCheckIn, now: chrono::DateTime<chrono::Utc>) -> Result<SyncItemResult, AppError> {
+        let outcome = |outcome: &str, detail: Option<String>| {
+            SyncItemResult::new(&check_in.client_id, "check_in", outcome, detail, Some(check_in.booking_id.clone()))
+        };
+        let at = match offline_time(&check_in.at, now) {
+            Ok(at) => at,
+            Err(e) => return Ok(outcome(SYNC_REJECTED, Some(e))),
+        };
+        let Ok(booking_oid) = ObjectId::parse_str(&check_in.booking_id) else {
+            return Ok(outcome(SYNC_REJECTED, Some("Booking is not on this trip".to_string())));
+        };
+        let booking = self.get_bookings_collection()
+            .find_one(doc! { "_id": booking_oid, "bus_id": bus_id, "travel_date": &access.travel_date }, None)
+            .await?;
+        let Some(booking) = booking else {
+            return Ok(outcome(SYNC_REJECTED, Some("Booking is not on this trip".to_string())));
+        };
+        if booking.status != "Confirmed" {
+            return Ok(outcome(SYNC_REJECTED, Some(format!("Booking is {}", booking.status.to_lowercase()))));
+        }
+
+        let mut event = BookingEvent::new("checked_in", &format!("user:{}", access.sub), Some("Checked in offline".to_string()));
+        event.at = to_bson_time(at);
+        let result = self.get_bookings_collection().update_one(
+            doc! { "_id": booking_oid, "history.event": { "$ne": "checked_in" } },
+            doc! { "$push": { "history": bson::to_bson(&event)? } },
+            None
+        ).await?;
+        if result.modified_count == 0 {
+            return Ok(outcome(SYNC_DUPLICATE, Some(format!("Seat {} was already checked in", booking.seat_number))));
+        }
+        Ok(outcome(SYNC_APPLIED, None))
+    }
+
+    // A passenger who paid the conductor on board: booked, paid and checked in at once
+    async fn apply_offline_cash_sale(&self, access: &TripAccessClaims, bus: &Bus, sale: &OfflineCashSale, now: chrono::DateTime<chrono::Utc>) -> Result<SyncItemResult, AppError> {
+        let outcome = |outcome: &str, detail: Option<String>, booking_id: Option<String>| SyncItemResult {
+            fare: sale.fare.max(0.0),
+            ..SyncItemResult::new(&sale.client_id, "cash_sale", outcome, detail, booking_id)
+        };
+        let sold_at = match sale.validate().and_then(|()| offline_time(&sale.sold_at, now)) {
+            Ok(sold_at) => sold_at,
+            Err(e) => return Ok(outcome(SYNC_REJECTED, Some(e), None)),
+        };
+        let next_of_kin = match sale.next_of_kin.as_ref().map(NextOfKin::validated).transpose() {
+            Ok(next_of_kin) => next_of_kin,
+            Err(e) => return Ok(outcome(SYNC_REJECTED, Some(e), None)),
+        };
+        if next_of_kin.is_none() && self.get_operator_settings(&bus.operator_name()).await?.next_of_kin_required {
+            return Ok(outcome(SYNC_REJECTED, Some("This operator requires next of kin details for every passenger".to_string()), None));
+        }
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let seat_number = sale.seat_number.trim();
+        if seat_number == OPEN_SEATING {
+            self.open_headcount(bus, &access.travel_date).await?;
+        } else if !self.trip_has_seat(bus, &access.travel_date, seat_number).await? {
+            return Ok(outcome(SYNC_REJECTED, Some(format!("Seat {} not found", seat_number)), None));
+        }
+
+        if !self.reserve_seat(bus, &access.travel_date, seat_number).await? {
+            let holder = if seat_number == OPEN_SEATING {
+                None
+            } else {
+                self.get_bookings_collection().find_one(
+                    doc! {
+                        "bus_id": bus_id,
+                        "travel_date": &access.travel_date,
+                        "seat_number": seat_number,
+                        "status": { "$ne": "Cancelled" },
+                    },
+                    None
+                ).await?
+            };
+            let detail = match holder.as_ref().and_then(|b| b.reference.as_deref()) {
+                Some(reference) => format!("Seat {} was already sold, to {}", seat_number, reference),
+                None if seat_number == OPEN_SEATING => "The bus was already full".to_string(),
+                None => format!("Seat {} was already taken", seat_number),
+            };
+            return Ok(outcome(SYNC_CONFLICT, Some(detail), holder.and_then(|b| b.id).map(|id| id.to_hex())));
+        }
+
+        let actor = format!("user:{}", access.sub);
+        let sold_at = to_bson_time(sold_at);
+        let history: Vec<BookingEvent> = [
+            ("created", Some("Sold on board".to_string())),
+            ("paid", Some(format!("KES {:.2} cash to the conductor", sale.fare))),
+            ("checked_in", Some("Checked in offline".to_string())),
+        ].into_iter().map(|(event, note)| BookingEvent { at: sold_at, ..BookingEvent::new(event, &actor, note) }).collect();
+        let mut booking = Booking {
+            id: None,
+            user_id: ObjectId::parse_str(&access.sub)?,
+            bus_id,
+            seat_number: seat_number.to_string(),
+            travel_date: access.travel_date.clone(),
+            booking_date: sold_at,
+            status: "Confirmed".to_string(),
+            passenger: Some(Passenger {
+                name: sale.passenger_name.trim().to_string(),
+                age: String::new(),
+                gender: String::new(),
+                phone: sale.phone.clone(),
+                id_number: None,
+                next_of_kin: None,
+            }),
+            history,
+            terms_version: Some(TermsConfig::from_env().version),
+            policy_version: Some(self.resolve_policy(bus, &access.travel_date).await?.version()),
+            risk: None,
+            country: None,
+            reference: Some(new_booking_reference()?),
+        };
+        match self.get_bookings_collection().insert_one(&booking, None).await {
+            Ok(inserted) => booking.id = inserted.inserted_id.as_object_id(),
+            Err(e) => {
+                self.release_seat(bus_id, &access.travel_date, seat_number).await?;
+                return Err(e.into());
+            }
+        }
+        if let (Some(booking_id), Some(next_of_kin)) = (booking.id, &next_of_kin) {
+            if let Err(e) = self.store_next_of_kin(booking_id, next_of_kin).await {
+                self.unbook_seats(std::slice::from_ref(&booking)).await?;
+                return Err(e);
+            }
+        }
+
+        self.publish_event(DomainEvent::BookingCreated { booking: (&booking).into() }).await;
+        if let Err(e) = self.record_booking_split(&booking, bus).await {
+            error!("Failed to record financial split for booking {:?}: {}", booking.id, e);
+        }
+        Ok(outcome(SYNC_APPLIED, None, booking.id.map(|id| id.to_hex())))
+    }
+}
@@ -0,0 +1,89 @@
+use mongodb::{
+    bson::{self, doc},
+    options::{Collation, CollationStrength, FindOneAndUpdateOptions, FindOneOptions, ReturnDocument},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::email_template::{EmailTemplate, BOOKING_EMAIL_FIELDS};
+use crate::models::localization::{supported_language, SUPPORTED_LANGUAGES};
+use crate::models::operator::{OperatorSettings, OperatorSettingsRequest};
+
+// Operator names come from bus numbers, so match them the way `Tenant::owns_operator` does
+fn case_insensitive() -> Collation {
+    Collation::builder().locale("en").strength(CollationStrength::Secondary).build()
+}
+
+impl MongoDB {
+    fn get_operator_settings_collection(&self) -> Collection<OperatorSettings> {
+        self.database().collection("operator_settings")
+    }
+
+    pub async fn get_operator_settings(&self, operator: &str) -> Result<OperatorSettings, AppError> {
+        let operator = operator.trim();
+        let options = FindOneOptions::builder().collation(case_insensitive()).build();
+        let settings = self.get_operator_settings_collection()
+            .find_one(doc! { "operator": operator }, options)
+            .await?;
+        Ok(settings.unwrap_or_else(|| OperatorSettings { operator: operator.to_string(), ..Default::default() }))
+    }
+
+    pub async fn update_operator_settings(&self, operator: &str, req: &OperatorSettingsRequest) -> Result<OperatorSettings, AppError> {
+        let operator = operator.trim();
+        if operator.is_empty() {
+            return Err("Operator is required".into());
+        }
+        let mut set = doc! { "updated_at": bson::DateTime::now() };
+        if let Some(required) = req.next_of_kin_required {
+            set.insert("next_of_kin_required", required);
+        }
+        if let Some(language) = &req.default_language {
+            let language = supported_language(language)
+                .ok_or_else(|| format!("Unsupported language, expected one of: {}", SUPPORTED_LANGUAGES.join(", ")))?;
+            set.insert("default_language", language);
+        }
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .collation(case_insensitive())
+            .build();
+        let settings = self.get_operator_settings_collection().find_one_and_update(
+            doc! { "operator": operator },
+            doc! {
+                "$set": set,
+                "$setOnInsert": { "operator": operator },
+            },
+            options
+        ).await?;
+        settings.ok_or_else(|| AppError::internal("Failed to save operator settings"))
+    }
+
+    /// Sets the operator's booking email wording, or goes back to the built-in one with `None`.
+    pub async fn set_operator_booking_email(&self, operator: &str, template: Option<EmailTemplate>, actor: &str) -> Result<OperatorSettings, AppError> {
+        let operator = operator.trim();
+        if operator.is_empty() {
+            return Err("Operator is required".into());
+        }
+        let mut update = match &template {
+            Some(template) => {
+                template.validate(&BOOKING_EMAIL_FIELDS)?;
+                doc! { "$set": { "booking_email": bson::to_bson(template)?, "updated_at": bson::DateTime::now() } }
+            }
+            None => doc! { "$unset": { "booking_email": "" }, "$set": { "updated_at": bson::DateTime::now() } },
+        };
+        update.insert("$setOnInsert", doc! { "operator": operator });
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .collation(case_insensitive())
+            .build();
+        let settings = self.get_operator_settings_collection()
+            .find_one_and_update(doc! { "operator": operator }, update, options)
+            .await?
+            .ok_or_else(|| AppError::internal("Failed to save operator settings"))?;
+        let action = if template.is_some() { "booking_email.updated" } else { "booking_email.reset" };
+        self.record_audit(action, actor, operator, None).await?;
+        Ok(settings)
+    }
+}
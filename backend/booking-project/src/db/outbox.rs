@@ -0,0 +1,187 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::outbox::{DeadLetter, OutboxMessage, UpdateDeadLetterRequest, OUTBOX_CHANNELS};
+
+// How long a worker owns a claimed message before another instance may retry it
+const CLAIM_LEASE_SECS: i64 = 300;
+
+impl MongoDB {
+    fn get_outbox_collection(&self) -> Collection<OutboxMessage> {
+        self.database().collection("outbox")
+    }
+
+    fn get_dead_letters_collection(&self) -> Collection<DeadLetter> {
+        self.database().collection("dead_letters")
+    }
+
+    pub async fn enqueue_outbox(&self, message: OutboxMessage) -> Result<ObjectId, AppError> {
+        if !OUTBOX_CHANNELS.contains(&message.channel.as_str()) {
+            return Err(AppError::internal(format!("Unknown outbox channel: {}", message.channel)));
+        }
+        let result = self.get_outbox_collection().insert_one(message, None).await?;
+        result.inserted_id.as_object_id().ok_or_else(|| AppError::internal("Missing outbox message id"))
+    }
+
+    /// Atomically claims the next due message so concurrent workers never deliver it twice.
+    pub async fn claim_outbox_message(&self) -> Result<Option<OutboxMessage>, AppError> {
+        let now = chrono::Utc::now();
+        let lease = bson::DateTime::from_millis((now + chrono::Duration::seconds(CLAIM_LEASE_SECS)).timestamp_millis());
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! { "next_attempt_at": 1 })
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self.get_outbox_collection().find_one_and_update(
+            doc! { "status": "pending", "next_attempt_at": { "$lte": bson::DateTime::from_millis(now.timestamp_millis()) } },
+            doc! { "$set": { "next_attempt_at": lease } },
+            options
+        ).await?)
+    }
+
+    pub async fn mark_outbox_delivered(&self, id: ObjectId) -> Result<(), AppError> {
+        self.get_outbox_collection().update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": { "status": "delivered", "delivered_at": bson::DateTime::now(), "last_error": bson::Bson::Null },
+                "$inc": { "attempts": 1 },
+            },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Closes a promotional message whose recipient no longer consents; it is never sent.
+    pub async fn mark_outbox_suppressed(&self, id: ObjectId) -> Result<(), AppError> {
+        self.get_outbox_collection().update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "status": "suppressed", "last_error": "Recipient has not consented" } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Schedules a retry with exponential backoff, or parks the message in the dead-letter queue.
+    pub async fn mark_outbox_failed(&self, message: &OutboxMessage, error: &str) -> Result<bool, AppError> {
+        let id = message.id.ok_or_else(|| AppError::internal("Outbox message has no id"))?;
+        let attempts = message.attempts + 1;
+
+        if attempts >= message.max_attempts {
+            let dead_letter = DeadLetter {
+                id: None,
+                channel: message.channel.clone(),
+                recipient: message.recipient.clone(),
+                subject: message.subject.clone(),
+                body: message.body.clone(),
+                attempts,
+                error: error.to_string(),
+                original_created_at: message.created_at,
+                dead_at: bson::DateTime::now(),
+                user_id: message.user_id,
+                requires_consent: message.requires_consent.clone(),
+            };
+            self.get_dead_letters_collection().insert_one(dead_letter, None).await?;
+            self.get_outbox_collection().delete_one(doc! { "_id": id }, None).await?;
+            return Ok(true);
+        }
+
+        let backoff = chrono::Duration::seconds(30 * 2i64.pow(attempts as u32));
+        let next_attempt_at = bson::DateTime::from_millis((chrono::Utc::now() + backoff).timestamp_millis());
+        self.get_outbox_collection().update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "attempts": attempts, "last_error": error, "next_attempt_at": next_attempt_at } },
+            None
+        ).await?;
+        Ok(false)
+    }
+
+    pub async fn get_dead_letters(&self, channel: Option<&str>) -> Result<Vec<DeadLetter>, AppError> {
+        let filter = match channel {
+            Some(channel) => doc! { "channel": channel },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "dead_at": -1 }).limit(200).build();
+        let mut cursor = self.get_dead_letters_collection().find(filter, find_options).await?;
+
+        let mut dead_letters = Vec::new();
+        while let Some(result) = cursor.next().await {
+            dead_letters.push(result?);
+        }
+        Ok(dead_letters)
+    }
+
+    pub async fn get_dead_letter(&self, id: &str) -> Result<Option<DeadLetter>, AppError> {
+        let oid = self.string_to_id(id)?;
+        Ok(self.get_dead_letters_collection().find_one(doc! { "_id": oid }, None).await?)
+    }
+
+    pub async fn update_dead_letter(&self, id: &str, req: &UpdateDeadLetterRequest) -> Result<DeadLetter, AppError> {
+        let oid = self.string_to_id(id)?;
+        let mut set = doc! {};
+        if let Some(recipient) = &req.recipient {
+            set.insert("recipient", recipient);
+        }
+        if let Some(subject) = &req.subject {
+            set.insert("subject", subject);
+        }
+        if let Some(body) = &req.body {
+            set.insert("body", body);
+        }
+        if set.is_empty() {
+            return Err("Nothing to update".into());
+        }
+
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        self.get_dead_letters_collection()
+            .find_one_and_update(doc! { "_id": oid }, doc! { "$set": set }, options)
+            .await?
+            .ok_or_else(|| AppError::not_found("Dead letter not found"))
+    }
+
+    /// Moves a dead letter back onto the outbox with a fresh retry budget.
+    pub async fn requeue_dead_letter(&self, id: &str) -> Result<ObjectId, AppError> {
+        let oid = self.string_to_id(id)?;
+        let dead_letter = self.get_dead_letters_collection()
+            .find_one_and_delete(doc! { "_id": oid }, None)
+            .await?
+            .ok_or_else(|| AppError::not_found("Dead letter not found"))?;
+
+        let mut message = OutboxMessage::new(&dead_letter.channel, &dead_letter.recipient, dead_letter.subject, dead_letter.body);
+        if let (Some(user_id), Some(purpose)) = (dead_letter.user_id, dead_letter.requires_consent.as_deref()) {
+            message = message.requiring_consent(user_id, purpose);
+        }
+        self.enqueue_outbox(message).await
+    }
+
+    pub async fn delete_dead_letter(&self, id: &str) -> Result<bool, AppError> {
+        let oid = self.string_to_id(id)?;
+        let result = self.get_dead_letters_collection().delete_one(doc! { "_id": oid }, None).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    pub async fn get_outbox_depths(&self) -> Result<(u64, Vec<(String, u64)>), AppError> {
+        let pending = self.get_outbox_collection().count_documents(doc! { "status": "pending" }, None).await?;
+        let mut by_channel = Vec::new();
+        for channel in OUTBOX_CHANNELS {
+            let depth = self.get_dead_letters_collection().count_documents(doc! { "channel": channel }, None).await?;
+            by_channel.push((channel.to_string(), depth));
+        }
+        Ok((pending, by_channel))
+    }
+
+    /// Messages delivered on a channel since `since`, and ones currently retrying or dead-lettered since then.
+    pub async fn get_channel_delivery_counts(&self, channel: &str, since: bson::DateTime) -> Result<(u64, u64), AppError> {
+        let outbox = self.get_outbox_collection();
+        let delivered = outbox.count_documents(doc! { "channel": channel, "delivered_at": { "$gte": since } }, None).await?;
+        let retrying = outbox.count_documents(doc! { "channel": channel, "status": "pending", "attempts": { "$gt": 0 } }, None).await?;
+        let dead = self.get_dead_letters_collection()
+            .count_documents(doc! { "channel": channel, "dead_at": { "$gte": since } }, None)
+            .await?;
+        Ok((delivered, retrying + dead))
+    }
+}
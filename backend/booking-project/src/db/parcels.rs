@@ -0,0 +1,169 @@
+use futures::StreamExt;
+use log::{error, info};
+use mongodb::{
+    bson::{self, doc, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ParcelConfig;
+use crate::models::outbox::OutboxMessage;
+use crate::models::parcel::{
+    new_waybill, normalize_waybill, parcel_can_move, parcel_price, CreateParcelRequest, Parcel, ParcelEvent,
+    ParcelListQuery, ParcelStatusRequest, PARCEL_RECEIVED, PARCEL_STATUSES,
+};
+use crate::models::tenancy::Tenant;
+
+// A clash of two random waybill numbers is rare enough that a couple of retries always do
+const MAX_WAYBILL_ATTEMPTS: u32 = 3;
+
+impl MongoDB {
+    fn get_parcels_collection(&self) -> Collection<Parcel> {
+        self.database().collection("parcels")
+    }
+
+    pub async fn ensure_parcel_indexes(&self) -> Result<(), AppError> {
+        let parcels = self.get_parcels_collection();
+        parcels.create_index(
+            IndexModel::builder()
+                .keys(doc! { "waybill": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        parcels.create_index(IndexModel::builder().keys(doc! { "bus_id": 1, "travel_date": 1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// Books a parcel the office has taken in onto a trip, and texts the sender and recipient
+    /// its waybill number.
+    pub async fn create_parcel(&self, tenant: &Tenant, req: &CreateParcelRequest, actor: &str) -> Result<Parcel, AppError> {
+        let config = ParcelConfig::from_env();
+        let (sender, recipient) = req.validated_contacts(&config)?;
+        let bus = match self.get_bus(&req.bus_id).await? {
+            Some(bus) if tenant.owns_bus(&bus) => bus,
+            _ => return Err(AppError::not_found("Bus not found")),
+        };
+        let bus_id = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        self.ensure_bus_runs(&bus, &req.travel_date).await?;
+        let price = parcel_price(&req.size, req.weight_kg, &config).ok_or("Unknown parcel size")?;
+
+        let now = bson::DateTime::now();
+        let mut parcel = Parcel {
+            id: None,
+            waybill: new_waybill()?,
+            bus_id,
+            travel_date: req.travel_date.clone(),
+            from: bus.route.from.clone(),
+            to: bus.route.to.clone(),
+            sender,
+            recipient,
+            description: req.description.trim().to_string(),
+            size: req.size.clone(),
+            weight_kg: req.weight_kg,
+            price,
+            status: PARCEL_RECEIVED.to_string(),
+            history: vec![ParcelEvent { status: PARCEL_RECEIVED.to_string(), actor: actor.to_string(), note: None, at: now }],
+            created_at: now,
+            updated_at: now,
+        };
+        let parcels = self.get_parcels_collection();
+        let mut attempt = 1;
+        let result = loop {
+            match parcels.insert_one(&parcel, None).await {
+                Err(e) if attempt < MAX_WAYBILL_ATTEMPTS && super::locks::is_duplicate_key(&e) => {
+                    parcel.waybill = new_waybill()?;
+                    attempt += 1;
+                }
+                result => break result?,
+            }
+        };
+        parcel.id = result.inserted_id.as_object_id();
+        info!("Parcel {} booked on {} for {} by {}", parcel.waybill, bus.bus_number, parcel.travel_date, actor);
+        self.queue_parcel_sms(&parcel, PARCEL_RECEIVED).await;
+        Ok(parcel)
+    }
+
+    /// The tenant's parcels, newest first, optionally for one trip or status.
+    pub async fn list_parcels(&self, tenant: &Tenant, query: &ParcelListQuery) -> Result<Vec<Parcel>, AppError> {
+        let mut filter = Document::new();
+        if let Some(bus_id) = &query.bus_id {
+            filter.insert("bus_id", self.string_to_id(bus_id)?);
+        }
+        if let Some(travel_date) = &query.travel_date {
+            filter.insert("travel_date", travel_date);
+        }
+        if let Some(status) = &query.status {
+            if !PARCEL_STATUSES.contains(&status.as_str()) {
+                return Err(format!("status must be one of {}", PARCEL_STATUSES.join(", ")).into());
+            }
+            filter.insert("status", status);
+        }
+        let filter = self.scope_bookings_filter(tenant, filter).await?;
+        let mut cursor = self.get_parcels_collection().find(
+            filter,
+            FindOptions::builder().sort(doc! { "created_at": -1 }).build()
+        ).await?;
+        let mut parcels = Vec::new();
+        while let Some(parcel) = cursor.next().await {
+            parcels.push(parcel?);
+        }
+        Ok(parcels)
+    }
+
+    /// Moves a parcel on to its next tracking state, texting whoever needs to know.
+    pub async fn update_parcel_status(&self, tenant: &Tenant, waybill: &str, req: &ParcelStatusRequest, actor: &str) -> Result<Parcel, AppError> {
+        let parcel = self.track_parcel(waybill).await?.ok_or_else(|| AppError::not_found("Parcel not found"))?;
+        // Another operator's parcels read as missing
+        if self.ensure_bus_owned(tenant, &parcel.bus_id.to_hex()).await.is_err() {
+            return Err(AppError::not_found("Parcel not found"));
+        }
+        let status = req.status.trim().to_lowercase();
+        if !parcel_can_move(&parcel.status, &status) {
+            return Err(format!("A parcel that is {} can't be marked {}", parcel.status.replace('_', " "), status.replace('_', " ")).into());
+        }
+
+        let event = ParcelEvent {
+            status: status.clone(),
+            actor: actor.to_string(),
+            note: req.note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+            at: bson::DateTime::now(),
+        };
+        // Only from the state it was read in, so two clerks scanning it at once move it once
+        let moved = self.get_parcels_collection().update_one(
+            doc! { "_id": parcel.id, "status": &parcel.status },
+            doc! {
+                "$set": { "status": &status, "updated_at": event.at },
+                "$push": { "history": bson::to_bson(&event)? },
+            },
+            None
+        ).await?;
+        if moved.modified_count == 0 {
+            return Err(AppError::conflict("This parcel changed meanwhile, please reload it"));
+        }
+        let mut history = parcel.history;
+        history.push(event);
+        let parcel = Parcel { status: status.clone(), history, ..parcel };
+        self.queue_parcel_sms(&parcel, &status).await;
+        Ok(parcel)
+    }
+
+    /// The parcel behind a waybill number, in whatever form it was typed.
+    pub async fn track_parcel(&self, waybill: &str) -> Result<Option<Parcel>, AppError> {
+        let Some(waybill) = normalize_waybill(waybill) else {
+            return Ok(None);
+        };
+        Ok(self.get_parcels_collection().find_one(doc! { "waybill": waybill }, None).await?)
+    }
+
+    // A text that can't be queued mustn't undo the parcel's move
+    async fn queue_parcel_sms(&self, parcel: &Parcel, status: &str) {
+        for (phone, message) in parcel.status_sms(status) {
+            if let Err(e) = self.enqueue_outbox(OutboxMessage::new("sms", &phone, None, message)).await {
+                error!("Failed to queue SMS for parcel {}: {}", parcel.waybill, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::PartnerConfig;
+use crate::models::consent::MARKETING_EMAIL;
+use crate::models::outbox::OutboxMessage;
+use crate::models::partner::{
+    partner_landing_url, verify_link_signature, PartnerClick, PartnerConversionRequest, PartnerOffer, PartnerOfferRequest,
+    PartnerOfferResponse, PartnerReportRow,
+};
+use crate::models::terminal::terminal_id;
+use crate::models::Booking;
+
+#[derive(Deserialize)]
+struct ReportGroup {
+    #[serde(rename = "_id")]
+    offer_id: ObjectId,
+    partner: String,
+    clicks: i64,
+    conversions: i64,
+    revenue: f64,
+}
+
+impl MongoDB {
+    fn get_partner_offers_collection(&self) -> Collection<PartnerOffer> {
+        self.database().collection("partner_offers")
+    }
+
+    fn get_partner_clicks_collection(&self) -> Collection<PartnerClick> {
+        self.database().collection("partner_clicks")
+    }
+
+    pub async fn ensure_partner_indexes(&self) -> Result<(), AppError> {
+        self.get_partner_offers_collection()
+            .create_index(IndexModel::builder().keys(doc! { "city_key": 1, "active": 1 }).build(), None)
+            .await?;
+        self.get_partner_clicks_collection()
+            .create_index(IndexModel::builder().keys(doc! { "clicked_at": -1, "offer_id": 1 }).build(), None)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_partner_offers(&self, city: Option<&str>) -> Result<Vec<PartnerOffer>, AppError> {
+        let filter = match city {
+            Some(city) => doc! { "city_key": terminal_id(city) },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "city_key": 1, "partner": 1 }).build();
+        let mut cursor = self.get_partner_offers_collection().find(filter, find_options).await?;
+
+        let mut offers = Vec::new();
+        while let Some(result) = cursor.next().await {
+            offers.push(result?);
+        }
+        Ok(offers)
+    }
+
+    pub async fn create_partner_offer(&self, req: PartnerOfferRequest, actor: &str) -> Result<PartnerOffer, AppError> {
+        req.validate()?;
+        let mut offer = req.into_offer(None, bson::DateTime::now());
+        let result = self.get_partner_offers_collection().insert_one(&offer, None).await?;
+        offer.id = result.inserted_id.as_object_id();
+        let id = offer.id.map(|id| id.to_hex()).unwrap_or_default();
+        self.record_audit("partner_offer.created", actor, &id, Some(format!("{} in {}", offer.partner, offer.city))).await?;
+        Ok(offer)
+    }
+
+    pub async fn update_partner_offer(&self, offer_id: &str, req: PartnerOfferRequest, actor: &str) -> Result<Option<PartnerOffer>, AppError> {
+        req.validate()?;
+        let offer_oid = self.string_to_id(offer_id)?;
+        let collection = self.get_partner_offers_collection();
+        let Some(existing) = collection.find_one(doc! { "_id": offer_oid }, None).await? else {
+            return Ok(None);
+        };
+        let offer = req.into_offer(Some(offer_oid), existing.created_at);
+        collection.replace_one(doc! { "_id": offer_oid }, &offer, None).await?;
+        self.record_audit("partner_offer.updated", actor, offer_id, Some(format!("active: {}", offer.active))).await?;
+        Ok(Some(offer))
+    }
+
+    pub async fn delete_partner_offer(&self, offer_id: &str, actor: &str) -> Result<bool, AppError> {
+        let offer_oid = self.string_to_id(offer_id)?;
+        let result = self.get_partner_offers_collection().delete_one(doc! { "_id": offer_oid }, None).await?;
+        if result.deleted_count > 0 {
+            self.record_audit("partner_offer.deleted", actor, offer_id, None).await?;
+        }
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Active offers at the booking's destination, each with a click link tied to the booking.
+    pub async fn offers_for_booking(&self, booking: &Booking) -> Result<Vec<PartnerOfferResponse>, AppError> {
+        let Some(booking_id) = booking.id else {
+            return Ok(vec![]);
+        };
+        let Some(bus) = self.get_bus(&booking.bus_id.to_hex()).await? else {
+            return Ok(vec![]);
+        };
+        let config = PartnerConfig::from_env();
+        let find_options = FindOptions::builder().sort(doc! { "updated_at": -1 }).limit(config.max_offers).build();
+        let mut cursor = self.get_partner_offers_collection()
+            .find(doc! { "city_key": terminal_id(&bus.route.to), "active": true }, find_options)
+            .await?;
+
+        let mut offers = Vec::new();
+        while let Some(result) = cursor.next().await {
+            offers.push(PartnerOfferResponse::tracked(result?, &booking_id.to_hex(), &config));
+        }
+        Ok(offers)
+    }
+
+    /// Emails the destination offers to the booker, if they agreed to marketing email.
+    pub async fn queue_partner_offers_email(&self, booking: &Booking, offers: &[PartnerOfferResponse]) -> Result<bool, AppError> {
+        let Some(city) = offers.first().map(|o| o.city.clone()) else {
+            return Ok(false);
+        };
+        let Some(email) = self.user_blocklist_email(&booking.user_id.to_hex()).await? else {
+            return Ok(false);
+        };
+        let mut body = format!("Your trip to {} on {} is booked. Partners of ours can help once you arrive:\n", city, booking.travel_date);
+        for offer in offers {
+            body.push_str(&format!("\n{} ({})", offer.title, offer.partner));
+            if let Some(description) = &offer.description {
+                body.push_str(&format!("\n{}", description));
+            }
+            body.push_str(&format!("\n{}\n", offer.link));
+        }
+        let message = OutboxMessage::new("email", &email, Some(format!("Plan your arrival in {}", city)), body)
+            .requiring_consent(booking.user_id, MARKETING_EMAIL);
+        self.enqueue_outbox(message).await?;
+        Ok(true)
+    }
+
+    /// Counts a click on a link we issued and returns where to send the passenger. `None` for
+    /// forged links and offers that were since removed or paused.
+    pub async fn record_partner_click(&self, offer_id: &str, booking_id: &str, signature: &str) -> Result<Option<String>, AppError> {
+        if !verify_link_signature(&PartnerConfig::from_env().link_secret, offer_id, booking_id, signature) {
+            return Ok(None);
+        }
+        let (Ok(offer_oid), Ok(booking_oid)) = (self.string_to_id(offer_id), self.string_to_id(booking_id)) else {
+            return Ok(None);
+        };
+        let offer = self.get_partner_offers_collection()
+            .find_one(doc! { "_id": offer_oid, "active": true }, None)
+            .await?;
+        let Some(offer) = offer else {
+            return Ok(None);
+        };
+
+        let result = self.get_partner_clicks_collection().insert_one(PartnerClick {
+            id: None,
+            offer_id: offer_oid,
+            partner: offer.partner,
+            booking_id: booking_oid,
+            clicked_at: bson::DateTime::now(),
+            converted_at: None,
+            amount: None,
+            partner_reference: None,
+        }, None).await?;
+        let click_id = result.inserted_id.as_object_id().map(|id| id.to_hex()).unwrap_or_default();
+        Ok(Some(partner_landing_url(&offer.url, &click_id)))
+    }
+
+    /// Marks a click as converted. Each click converts once; false if it is unknown or was
+    /// already reported.
+    pub async fn record_partner_conversion(&self, req: &PartnerConversionRequest) -> Result<bool, AppError> {
+        if !req.amount.is_finite() || req.amount < 0.0 {
+            return Err("amount must be zero or more".into());
+        }
+        let click_oid = self.string_to_id(&req.click_id).map_err(|_| "Unknown ref")?;
+        let result = self.get_partner_clicks_collection().update_one(
+            doc! { "_id": click_oid, "converted_at": bson::Bson::Null },
+            doc! { "$set": {
+                "converted_at": bson::DateTime::now(),
+                "amount": req.amount,
+                "partner_reference": &req.partner_reference,
+            } },
+            None
+        ).await?;
+        Ok(result.modified_count == 1)
+    }
+
+    /// Clicks, conversions and converted revenue per offer over the last `days`.
+    pub async fn get_partner_report(&self, days: i64, partner: Option<&str>) -> Result<Vec<PartnerReportRow>, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let mut filter = doc! { "clicked_at": { "$gte": since } };
+        if let Some(partner) = partner {
+            filter.insert("partner", partner);
+        }
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": "$offer_id",
+                "partner": { "$first": "$partner" },
+                "clicks": { "$sum": 1_i64 },
+                "conversions": { "$sum": { "$cond": [{ "$gt": ["$converted_at", bson::Bson::Null] }, 1_i64, 0_i64] } },
+                "revenue": { "$sum": { "$ifNull": ["$amount", 0.0] } },
+            } },
+            doc! { "$sort": { "partner": 1, "_id": 1 } },
+        ];
+        let mut cursor = self.get_partner_clicks_collection().aggregate(pipeline, None).await?;
+
+        let mut rows = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let group: ReportGroup = bson::from_document(result?)?;
+            rows.push(PartnerReportRow {
+                offer_id: group.offer_id.to_hex(),
+                partner: group.partner,
+                clicks: group.clicks,
+                conversions: group.conversions,
+                revenue: group.revenue,
+            });
+        }
+        Ok(rows)
+    }
+}
@@ -0,0 +1,158 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::PassConfig;
+use crate::models::bus::Bus;
+use crate::models::pass::{pass_price, BuyPassRequest, Pass, PASS_ACTIVE, PASS_FAILED, PASS_PENDING};
+use crate::models::payment::Payment;
+use crate::models::webhook::WebhookOutcome;
+
+impl MongoDB {
+    pub(super) fn get_passes_collection(&self) -> Collection<Pass> {
+        self.database().collection("passes")
+    }
+
+    pub async fn ensure_pass_indexes(&self) -> Result<(), AppError> {
+        let passes = self.get_passes_collection();
+        passes.create_index(IndexModel::builder().keys(doc! { "user_id": 1, "created_at": -1 }).build(), None).await?;
+        passes.create_index(IndexModel::builder().keys(doc! { "redemptions.booking_id": 1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// Sells a pass for the route and prompts the customer's phone to pay for it. It is priced
+    /// from the dearest bus on the route, so every bus running it today is covered.
+    pub async fn buy_pass(&self, user_id: &str, req: &BuyPassRequest) -> Result<(Pass, Payment), AppError> {
+        let phone = req.validated_phone()?;
+        let user_oid = self.string_to_id(user_id)?;
+        let (from, to) = (req.from.trim(), req.to.trim());
+        let mut route: Option<Bus> = None;
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            let on_route = bus.route.from.eq_ignore_ascii_case(from) && bus.route.to.eq_ignore_ascii_case(to);
+            if on_route && route.as_ref().is_none_or(|r| bus.route.price > r.route.price) {
+                route = Some(bus);
+            }
+        }
+        let route = route.ok_or_else(|| format!("No buses run from {} to {}", from, to))?.route;
+
+        let config = PassConfig::from_env();
+        let mut pass = Pass {
+            id: None,
+            user_id: user_oid,
+            from: route.from,
+            to: route.to,
+            ride_fare: route.price,
+            rides_total: config.rides,
+            rides_left: config.rides,
+            price: pass_price(route.price, config.rides, config.discount_percent),
+            status: PASS_PENDING.to_string(),
+            redemptions: Vec::new(),
+            created_at: bson::DateTime::now(),
+            activated_at: None,
+            expires_at: None,
+        };
+        let passes = self.get_passes_collection();
+        let result = passes.insert_one(&pass, None).await?;
+        pass.id = result.inserted_id.as_object_id();
+        match self.initiate_pass_payment(&pass, phone).await {
+            Ok(payment) => Ok((pass, payment)),
+            Err(e) => {
+                // Nothing was charged, so there is no pass to keep
+                passes.delete_one(doc! { "_id": pass.id }, None).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// The user's passes, newest first.
+    pub async fn list_passes(&self, user_id: &str) -> Result<Vec<Pass>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let mut cursor = self.get_passes_collection().find(
+            doc! { "user_id": user_oid },
+            FindOptions::builder().sort(doc! { "created_at": -1 }).build()
+        ).await?;
+        let mut passes = Vec::new();
+        while let Some(pass) = cursor.next().await {
+            passes.push(pass?);
+        }
+        Ok(passes)
+    }
+
+    pub async fn get_user_pass(&self, user_id: &str, pass_id: &str) -> Result<Option<Pass>, AppError> {
+        let pass_oid = self.string_to_id(pass_id)?;
+        let user_oid = self.string_to_id(user_id)?;
+        Ok(self.get_passes_collection().find_one(doc! { "_id": pass_oid, "user_id": user_oid }, None).await?)
+    }
+
+    /// The user's pass, if it can pay for `rides` rides on the bus on that date. The rides are
+    /// only taken off it when the seats are booked.
+    pub(super) async fn usable_pass(&self, user_id: ObjectId, pass_id: &str, bus: &Bus, travel_date: &str, rides: usize) -> Result<ObjectId, AppError> {
+        let pass_oid = self.string_to_id(pass_id)?;
+        let pass = self.get_passes_collection()
+            .find_one(doc! { "_id": pass_oid, "user_id": user_id }, None).await?
+            .ok_or_else(|| AppError::not_found("Pass not found"))?;
+        pass.check_ride(bus, travel_date, rides, chrono::Utc::now())?;
+        Ok(pass_oid)
+    }
+
+    /// Activates a pass once its payment went through; a failed payment leaves it unusable.
+    pub(super) async fn settle_pass_payment(&self, pass_id: ObjectId, payment: &Payment, paid: bool, receipt: Option<String>, result: &str) -> Result<WebhookOutcome, AppError> {
+        let passes = self.get_passes_collection();
+        if !paid {
+            passes.update_one(
+                doc! { "_id": pass_id, "status": PASS_PENDING },
+                doc! { "$set": { "status": PASS_FAILED } },
+                None
+            ).await?;
+            self.notify_user(payment.user_id, "Pass not paid", &format!("Your M-Pesa payment for a commuter pass didn't go through: {}. You can buy it again.", result)).await?;
+            return Ok(WebhookOutcome::processed(format!("Pass payment failed: {}", result)));
+        }
+
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::days(PassConfig::from_env().valid_days);
+        let activated = passes.update_one(
+            doc! { "_id": pass_id, "status": PASS_PENDING },
+            doc! { "$set": {
+                "status": PASS_ACTIVE,
+                "activated_at": bson::DateTime::from_millis(now.timestamp_millis()),
+                "expires_at": bson::DateTime::from_millis(expires_at.timestamp_millis()),
+            } },
+            None
+        ).await?;
+        let receipt = receipt.unwrap_or_default();
+        if activated.modified_count == 0 {
+            return Ok(WebhookOutcome::processed(format!("Payment {} arrived for pass {} that is no longer awaiting it; it needs a manual refund", receipt, pass_id)));
+        }
+        info!("Pass {} activated by payment {}", pass_id, receipt);
+        self.notify_user(
+            payment.user_id,
+            "Commuter pass ready",
+            &format!("We received KES {:.2} (M-Pesa {}). Your pass is ready to use until {}.", payment.amount, receipt, expires_at.format("%Y-%m-%d")),
+        ).await?;
+        Ok(WebhookOutcome::processed(format!("Payment {} activated pass {}", receipt, pass_id)))
+    }
+
+    /// Puts the ride a booking took back on its pass. False when the booking didn't use a pass,
+    /// or its ride was already given back.
+    pub async fn return_pass_ride(&self, booking_id: ObjectId) -> Result<bool, AppError> {
+        // Matching on the redemption makes this safe to repeat
+        let result = self.get_passes_collection().update_one(
+            doc! { "redemptions.booking_id": booking_id },
+            doc! { "$inc": { "rides_left": 1 }, "$pull": { "redemptions": { "booking_id": booking_id } } },
+            None
+        ).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    pub async fn pass_for_booking(&self, booking_id: ObjectId) -> Result<Option<Pass>, AppError> {
+        Ok(self.get_passes_collection().find_one(doc! { "redemptions.booking_id": booking_id }, None).await?)
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::info;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::auth::{PasswordReset, ResetPasswordRequest};
+use crate::models::outbox::OutboxMessage;
+use crate::tokens::refresh_token_hash;
+
+const RESET_MINUTES: i64 = 60;
+const INVALID_RESET_LINK: &str = "This reset link is invalid or has expired";
+const GOOGLE_ONLY_ACCOUNT: &str = "Your account signs in with Google and has no password to reset. Use \"Continue with Google\" on the sign-in page instead.";
+
+fn reset_link(token: &str) -> String {
+    let base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+    format!("{}/reset-password?token={}", base_url.trim_end_matches('/'), token)
+}
+
+impl MongoDB {
+    fn get_password_resets_collection(&self) -> Collection<PasswordReset> {
+        self.database().collection("password_resets")
+    }
+
+    pub async fn ensure_password_reset_indexes(&self) -> Result<(), AppError> {
+        let resets = self.get_password_resets_collection();
+        resets.create_index(
+            IndexModel::builder()
+                .keys(doc! { "token_hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        resets.create_index(IndexModel::builder().keys(doc! { "user_id": 1 }).build(), None).await?;
+        resets.create_index(
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Emails a single-use reset link. An address without an account gets nothing, and the caller
+    /// answers the same either way so the form can't be used to find out who has one.
+    pub async fn request_password_reset(&self, email: &str) -> Result<(), AppError> {
+        let email = email.trim();
+        let Some(user) = self.get_users_collection().find_one(doc! { "email": email }, None).await? else {
+            return Ok(());
+        };
+        let user = self.resolve_merged_account(user).await?;
+        if user.anonymized_at.is_some() {
+            return Ok(());
+        }
+        // Told by email rather than in the response, which would show the address has an account
+        if user.password.is_empty() {
+            self.enqueue_outbox(OutboxMessage::new(
+                "email",
+                &user.email,
+                Some("Signing in to Burudani Mint Travels".to_string()),
+                format!(
+                    "Hi {},\n\nSomeone asked to reset the password for this address. {}\n\nIf you didn't ask, ignore this email.",
+                    user.username, GOOGLE_ONLY_ACCOUNT
+                ),
+            )).await?;
+            return Ok(());
+        }
+        let user_id = user.id.ok_or_else(|| AppError::internal("User ID not found"))?;
+
+        // Only the newest link works
+        let resets = self.get_password_resets_collection();
+        let now = bson::DateTime::now();
+        resets.update_many(
+            doc! { "user_id": user_id, "used_at": bson::Bson::Null },
+            doc! { "$set": { "expires_at": now } },
+            None
+        ).await?;
+
+        let mut secret = [0u8; 32];
+        SystemRandom::new().fill(&mut secret).map_err(|_| AppError::internal("Failed to generate reset token"))?;
+        let token = URL_SAFE_NO_PAD.encode(secret);
+        let expires_at = chrono::Utc::now() + chrono::Duration::minutes(RESET_MINUTES);
+        resets.insert_one(PasswordReset {
+            id: None,
+            user_id,
+            token_hash: refresh_token_hash(&token),
+            created_at: now,
+            expires_at: bson::DateTime::from_millis(expires_at.timestamp_millis()),
+            used_at: None,
+        }, None).await?;
+
+        self.enqueue_outbox(OutboxMessage::new(
+            "email",
+            &user.email,
+            Some("Reset your password".to_string()),
+            format!(
+                "Hi {},\n\nUse this link to choose a new password for Burudani Mint Travels:\n{}\n\nThe link works once and expires in {} minutes. If you didn't ask for this, ignore this email; your password hasn't changed.",
+                user.username, reset_link(&token), RESET_MINUTES
+            ),
+        )).await?;
+
+        info!("Password reset requested for user {}", user_id);
+        Ok(())
+    }
+
+    /// Sets a new password from a reset link and signs out every existing session.
+    pub async fn reset_password(&self, req: &ResetPasswordRequest) -> Result<(), AppError> {
+        req.validate()?;
+        let now = bson::DateTime::now();
+        // Claimed in one step, so the same link can't be used twice at once
+        let reset = self.get_password_resets_collection().find_one_and_update(
+            doc! {
+                "token_hash": refresh_token_hash(req.token.trim()),
+                "used_at": bson::Bson::Null,
+                "expires_at": { "$gt": now },
+            },
+            doc! { "$set": { "used_at": now } },
+            FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build()
+        ).await?.ok_or(INVALID_RESET_LINK)?;
+
+        let hashed_password = bcrypt::hash(&req.new_password, bcrypt::DEFAULT_COST)?;
+        let updated = self.get_users_collection().update_one(
+            doc! { "_id": reset.user_id, "anonymized_at": { "$exists": false } },
+            doc! { "$set": { "password": hashed_password, "updated_at": now, "sessions_revoked_at": now } },
+            None
+        ).await?;
+        if updated.matched_count == 0 {
+            return Err(INVALID_RESET_LINK.into());
+        }
+
+        let subject = format!("user:{}", reset.user_id.to_hex());
+        self.record_audit("password.reset", &subject, &subject, None).await?;
+        info!("Password reset for user {}", reset.user_id);
+        Ok(())
+    }
+}
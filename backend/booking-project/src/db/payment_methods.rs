@@ -0,0 +1,122 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::payment_method::{PaymentMethod, VaultAccess};
+use crate::payments::{PaymentMethodVault, ProviderToken};
+
+impl MongoDB {
+    fn get_payment_methods_collection(&self) -> Collection<PaymentMethod> {
+        self.database().collection("payment_methods")
+    }
+
+    fn get_vault_audit_collection(&self) -> Collection<VaultAccess> {
+        self.database().collection("vault_audit")
+    }
+
+    async fn record_vault_access(&self, user_id: ObjectId, payment_method_id: Option<ObjectId>, action: &str, provider: Option<&str>) -> Result<(), AppError> {
+        let access = VaultAccess {
+            id: None,
+            user_id,
+            payment_method_id,
+            action: action.to_string(),
+            actor: format!("user:{}", user_id.to_hex()),
+            provider: provider.map(str::to_string),
+            at: bson::DateTime::now(),
+        };
+        self.get_vault_audit_collection().insert_one(access, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_payment_methods(&self, user_id: &str) -> Result<Vec<PaymentMethod>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let find_options = FindOptions::builder().sort(doc! { "created_at": -1 }).build();
+        let mut cursor = self.get_payment_methods_collection().find(doc! { "user_id": user_oid }, find_options).await?;
+
+        let mut methods = Vec::new();
+        while let Some(result) = cursor.next().await {
+            methods.push(result?);
+        }
+        self.record_vault_access(user_oid, None, "list", None).await?;
+        Ok(methods)
+    }
+
+    pub async fn save_payment_method(&self, user_id: &str, vault: &dyn PaymentMethodVault, token: &ProviderToken) -> Result<PaymentMethod, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let collection = self.get_payment_methods_collection();
+        if collection.count_documents(doc! { "user_id": user_oid, "token": token.as_str() }, None).await? > 0 {
+            return Err(AppError::conflict("This payment method is already saved"));
+        }
+
+        let summary = vault.describe(token).await?;
+        let mut method = PaymentMethod {
+            id: None,
+            user_id: user_oid,
+            provider: vault.provider().to_string(),
+            token: token.clone(),
+            brand: summary.brand,
+            last4: summary.last4,
+            exp_month: summary.exp_month,
+            exp_year: summary.exp_year,
+            created_at: bson::DateTime::now(),
+        };
+        let result = collection.insert_one(&method, None).await?;
+        method.id = result.inserted_id.as_object_id();
+
+        self.record_vault_access(user_oid, method.id, "save", Some(vault.provider())).await?;
+        Ok(method)
+    }
+
+    /// A saved method, only if it belongs to the user.
+    pub async fn find_payment_method(&self, user_id: &str, method_id: &str) -> Result<Option<PaymentMethod>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let method_oid = self.string_to_id(method_id)?;
+        Ok(self.get_payment_methods_collection().find_one(doc! { "_id": method_oid, "user_id": user_oid }, None).await?)
+    }
+
+    /// Detaches the method at the provider, then forgets it.
+    pub async fn remove_payment_method(&self, method: &PaymentMethod, vault: &dyn PaymentMethodVault) -> Result<(), AppError> {
+        vault.remove(&method.token).await?;
+        self.get_payment_methods_collection().delete_one(doc! { "_id": method.id }, None).await?;
+        self.record_vault_access(method.user_id, method.id, "remove", Some(&method.provider)).await?;
+        Ok(())
+    }
+
+    pub async fn get_vault_audit(&self, user_id: Option<&str>) -> Result<Vec<VaultAccess>, AppError> {
+        let filter = match user_id {
+            Some(user_id) => doc! { "user_id": self.string_to_id(user_id)? },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder().sort(doc! { "at": -1 }).limit(500).build();
+        let mut cursor = self.get_vault_audit_collection().find(filter, find_options).await?;
+
+        let mut entries = Vec::new();
+        while let Some(result) = cursor.next().await {
+            entries.push(result?);
+        }
+        Ok(entries)
+    }
+
+    /// Forgets every saved method of the user, e.g. when the account is anonymized.
+    pub async fn purge_payment_methods(&self, user_id: ObjectId, actor: &str) -> Result<u64, AppError> {
+        let result = self.get_payment_methods_collection().delete_many(doc! { "user_id": user_id }, None).await?;
+        if result.deleted_count > 0 {
+            let access = VaultAccess {
+                id: None,
+                user_id,
+                payment_method_id: None,
+                action: "purge".to_string(),
+                actor: actor.to_string(),
+                provider: None,
+                at: bson::DateTime::now(),
+            };
+            self.get_vault_audit_collection().insert_one(access, None).await?;
+        }
+        Ok(result.deleted_count)
+    }
+}
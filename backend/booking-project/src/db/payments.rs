@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use log::error;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::MpesaConfig;
+use crate::events::{BookingSnapshot, DomainEvent};
+use crate::models::booking::BookingEvent;
+use crate::models::charter::Charter;
+use crate::models::monitoring::{PAYMENT_CONFIRMED, PAYMENT_FAILED as PAYMENT_FAILED_EVENT, PAYMENT_INITIATED};
+use crate::models::pass::Pass;
+use crate::models::settlement::round_money;
+use crate::models::payment::{booking_fare_due, pays_for, Payment, StkPushRequest, PAYMENT_FAILED, PAYMENT_PENDING, PAYMENT_SUCCEEDED};
+use crate::models::webhook::{MpesaStkCallback, WebhookOutcome};
+use crate::models::Booking;
+use crate::payments::mpesa::MpesaClient;
+
+// Daraja keeps a prompt on the customer's phone for about a minute
+const STK_PROMPT_SECS: i64 = 90;
+
+// What an STK Push pays for
+enum Purchase {
+    Bookings(Vec<ObjectId>),
+    Pass(ObjectId),
+    CharterDeposit(ObjectId),
+}
+
+impl Purchase {
+    // Shown on the customer's statement
+    fn reference(&self) -> String {
+        let id = match self {
+            Purchase::Bookings(booking_ids) => booking_ids[0],
+            Purchase::Pass(pass_id) => *pass_id,
+            Purchase::CharterDeposit(charter_id) => *charter_id,
+        };
+        id.to_hex()[12..].to_uppercase()
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Purchase::Bookings(_) => "Bus ticket",
+            Purchase::Pass(_) => "Commuter pass",
+            Purchase::CharterDeposit(_) => "Bus charter deposit",
+        }
+    }
+}
+
+impl MongoDB {
+    fn get_payments_collection(&self) -> Collection<Payment> {
+        self.database().collection("payments")
+    }
+
+    pub async fn ensure_payment_indexes(&self) -> Result<(), AppError> {
+        let payments = self.get_payments_collection();
+        payments.create_index(
+            IndexModel::builder()
+                .keys(doc! { "checkout_request_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        payments.create_index(IndexModel::builder().keys(doc! { "booking_ids": 1, "created_at": -1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// Prompts the customer's phone to pay for their bookings awaiting payment. The bookings are
+    /// confirmed when M-Pesa reports the result.
+    pub async fn initiate_stk_push(&self, user_id: &str, req: &StkPushRequest) -> Result<Payment, AppError> {
+        let phone = req.validated_phone()?;
+        let client = MpesaClient::from_config(&MpesaConfig::from_env()).ok_or("M-Pesa payments are not available")?;
+        let user_oid = self.string_to_id(user_id)?;
+        let booking_ids = req.booking_ids.iter()
+            .map(|id| self.string_to_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "_id": { "$in": &booking_ids }, "user_id": user_oid, "status": "PendingPayment" },
+            None
+        ).await?;
+        let mut bookings = Vec::new();
+        while let Some(booking) = cursor.next().await {
+            bookings.push(booking?);
+        }
+        if bookings.len() != booking_ids.len() {
+            return Err("Only your bookings awaiting payment can be paid".into());
+        }
+
+        let prompt_cutoff = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::seconds(STK_PROMPT_SECS)).timestamp_millis());
+        let in_flight = self.get_payments_collection().find_one(
+            doc! { "booking_ids": { "$in": &booking_ids }, "status": PAYMENT_PENDING, "created_at": { "$gte": prompt_cutoff } },
+            None
+        ).await?;
+        if in_flight.is_some() {
+            return Err("A payment prompt was just sent for these bookings; check your phone".into());
+        }
+
+        let mut amount = 0.0;
+        for booking in &bookings {
+            let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+            amount += bus.route.price;
+        }
+        self.send_stk_push(&client, user_oid, Purchase::Bookings(booking_ids), phone, amount).await
+    }
+
+    /// Prompts for the price of a commuter pass awaiting payment; it is activated when M-Pesa
+    /// reports the result.
+    pub(super) async fn initiate_pass_payment(&self, pass: &Pass, phone: String) -> Result<Payment, AppError> {
+        let client = MpesaClient::from_config(&MpesaConfig::from_env()).ok_or("M-Pesa payments are not available")?;
+        let pass_id = pass.id.ok_or_else(|| AppError::not_found("Pass not found"))?;
+        self.send_stk_push(&client, pass.user_id, Purchase::Pass(pass_id), phone, pass.price).await
+    }
+
+    /// Prompts for the deposit on an accepted charter; its bus is reserved when M-Pesa reports
+    /// the result.
+    pub(super) async fn initiate_charter_deposit(&self, charter: &Charter, phone: String) -> Result<Payment, AppError> {
+        let client = MpesaClient::from_config(&MpesaConfig::from_env()).ok_or("M-Pesa payments are not available")?;
+        let charter_id = charter.id.ok_or_else(|| AppError::not_found("Charter not found"))?;
+        let deposit = charter.quote.as_ref().ok_or("This charter hasn't been quoted")?.deposit;
+        self.send_stk_push(&client, charter.user_id, Purchase::CharterDeposit(charter_id), phone, deposit).await
+    }
+
+    async fn send_stk_push(&self, client: &MpesaClient, user_oid: ObjectId, purchase: Purchase, phone: String, amount: f64) -> Result<Payment, AppError> {
+        // M-Pesa only moves whole shillings
+        let charged = amount.ceil() as u64;
+        let push = match client.stk_push(&phone, charged, &purchase.reference(), purchase.description()).await {
+            Ok(push) => push,
+            Err(e) => {
+                let user_id = user_oid.to_hex();
+                if let Err(e) = self.record_subject_event(PAYMENT_FAILED_EVENT, &user_id, Some(e.to_string())).await {
+                    error!("Failed to record payment failure for {}: {}", user_id, e);
+                }
+                return Err(e);
+            }
+        };
+
+        let (booking_ids, pass_id, charter_id) = match purchase {
+            Purchase::Bookings(booking_ids) => (booking_ids, None, None),
+            Purchase::Pass(pass_id) => (Vec::new(), Some(pass_id), None),
+            Purchase::CharterDeposit(charter_id) => (Vec::new(), None, Some(charter_id)),
+        };
+        let now = bson::DateTime::now();
+        let mut payment = Payment {
+            id: None,
+            user_id: user_oid,
+            booking_ids,
+            pass_id,
+            charter_id,
+            provider: "mpesa".to_string(),
+            phone,
+            amount: charged as f64,
+            status: PAYMENT_PENDING.to_string(),
+            merchant_request_id: push.merchant_request_id,
+            checkout_request_id: push.checkout_request_id,
+            receipt_number: None,
+            result: push.customer_message,
+            created_at: now,
+            updated_at: now,
+        };
+        let result = self.get_payments_collection().insert_one(&payment, None).await?;
+        payment.id = result.inserted_id.as_object_id();
+        if let Err(e) = self.record_monitoring_event(PAYMENT_INITIATED, payment.id.map(|id| id.to_hex())).await {
+            error!("Failed to record payment initiation: {}", e);
+        }
+        Ok(payment)
+    }
+
+    pub async fn get_user_payment(&self, payment_id: &str, user_id: &str) -> Result<Option<Payment>, AppError> {
+        let payment_oid = self.string_to_id(payment_id)?;
+        let user_oid = self.string_to_id(user_id)?;
+        Ok(self.get_payments_collection().find_one(doc! { "_id": payment_oid, "user_id": user_oid }, None).await?)
+    }
+
+    /// What the customer is to pay for one booking, going by the fare recorded when it was made.
+    pub(super) async fn amount_due_for_booking(&self, booking: &Booking) -> Result<Option<f64>, AppError> {
+        let Some(booking_oid) = booking.id else {
+            return Ok(None);
+        };
+        let recorded = self.get_booking_splits_collection()
+            .find_one(doc! { "booking_id": booking_oid, "reversal": false }, None)
+            .await?
+            .map(|split| split.fare);
+        let list_price = match recorded {
+            Some(_) => None,
+            None => self.get_bus(&booking.bus_id.to_hex()).await?.map(|bus| bus.route.price),
+        };
+        Ok(booking_fare_due(recorded, list_price))
+    }
+
+    /// What the customer was charged for one booking: its share of the payment that paid for it,
+    /// or its sale in the ledger when it was paid some other way, e.g. cash on board.
+    pub(super) async fn amount_paid_for_booking(&self, booking_oid: ObjectId) -> Result<Option<f64>, AppError> {
+        let payment = self.get_payments_collection()
+            .find_one(doc! { "booking_ids": booking_oid, "status": PAYMENT_SUCCEEDED }, None)
+            .await?;
+        let Some(payment) = payment else {
+            return Ok(self.find_ledger_transaction("booking", &booking_oid.to_hex()).await?.map(|sale| sale.total()));
+        };
+        if payment.booking_ids.len() == 1 {
+            return Ok(Some(payment.amount));
+        }
+
+        // Bookings paid for together share the payment in proportion to their fares
+        let mut cursor = self.get_booking_splits_collection()
+            .find(doc! { "booking_id": { "$in": &payment.booking_ids }, "reversal": false }, None)
+            .await?;
+        let mut fares = HashMap::new();
+        while let Some(split) = cursor.next().await {
+            let split = split?;
+            fares.insert(split.booking_id, split.fare);
+        }
+        let total: f64 = fares.values().sum();
+        let share = match fares.get(&booking_oid) {
+            Some(fare) if fares.len() == payment.booking_ids.len() && total > 0.0 => payment.amount * fare / total,
+            _ => payment.amount / payment.booking_ids.len() as f64,
+        };
+        Ok(Some(round_money(share)))
+    }
+
+    /// Settles a payment from its STK Push result: paid bookings are confirmed, and a failed
+    /// payment leaves them awaiting payment so the customer can try again.
+    pub(super) async fn complete_stk_payment(&self, callback: &MpesaStkCallback) -> Result<WebhookOutcome, AppError> {
+        let payments = self.get_payments_collection();
+        let Some(payment) = payments.find_one(doc! { "checkout_request_id": &callback.checkout_request_id }, None).await? else {
+            return Ok(WebhookOutcome::ignored(format!("Unknown checkout request {}", callback.checkout_request_id)));
+        };
+        let paid = callback.succeeded() && callback.amount().is_some_and(|a| pays_for(a, payment.amount));
+        let status = if paid { PAYMENT_SUCCEEDED } else { PAYMENT_FAILED };
+        let result = match (callback.succeeded(), callback.amount()) {
+            (true, Some(amount)) if !paid => format!("Paid KES {:.2} of KES {:.2}", amount, payment.amount),
+            (true, None) => "M-Pesa reported success without an amount".to_string(),
+            _ => callback.result_desc.clone(),
+        };
+        // Settles only once, however many times the callback is delivered or replayed
+        let settled = payments.update_one(
+            doc! { "_id": payment.id, "status": PAYMENT_PENDING },
+            doc! { "$set": {
+                "status": status,
+                "receipt_number": callback.receipt_number(),
+                "result": &result,
+                "updated_at": bson::DateTime::now(),
+            } },
+            None
+        ).await?;
+        if settled.modified_count == 0 {
+            return Ok(WebhookOutcome::ignored(format!("Payment for checkout request {} is already settled", callback.checkout_request_id)));
+        }
+        if let Some(pass_id) = payment.pass_id {
+            return self.settle_pass_payment(pass_id, &payment, paid, callback.receipt_number(), &result).await;
+        }
+        if let Some(charter_id) = payment.charter_id {
+            return self.settle_charter_deposit(charter_id, &payment, paid, callback.receipt_number(), &result).await;
+        }
+
+        if !paid {
+            self.record_subject_event(PAYMENT_FAILED_EVENT, &payment.user_id.to_hex(), Some(result.clone())).await?;
+            self.notify_user(payment.user_id, "Payment not completed", &format!("Your M-Pesa payment didn't go through: {}. You can try again from your bookings.", result)).await?;
+            return Ok(WebhookOutcome::processed(format!("Payment failed: {}", result)));
+        }
+
+        let receipt = callback.receipt_number().unwrap_or_default();
+        let mut needs_refund = Vec::new();
+        for booking_id in &payment.booking_ids {
+            let event = BookingEvent::new(
+                "paid",
+                "system:webhook:mpesa_stk",
+                Some(format!("KES {:.2} via mpesa {} for {} booking(s)", payment.amount, receipt, payment.booking_ids.len())),
+            );
+            if !self.record_booking_paid(*booking_id, &event).await? {
+                needs_refund.push(booking_id.to_hex());
+            }
+        }
+        if let Err(e) = self.record_monitoring_event(PAYMENT_CONFIRMED, payment.id.map(|id| id.to_hex())).await {
+            error!("Failed to record payment confirmation: {}", e);
+        }
+        if needs_refund.is_empty() {
+            self.notify_user(payment.user_id, "Booking confirmed", &format!("We received KES {:.2} (M-Pesa {}). Your booking is confirmed.", payment.amount, receipt)).await?;
+            return Ok(WebhookOutcome::processed(format!("Payment {} confirmed {} booking(s)", receipt, payment.booking_ids.len())));
+        }
+
+        // The money is in but some of what it paid for is gone; someone has to send it back
+        let message = if needs_refund.len() == payment.booking_ids.len() {
+            format!("We received KES {:.2} (M-Pesa {}), but your booking had already been cancelled or paid for. We'll refund your payment.", payment.amount, receipt)
+        } else {
+            format!(
+                "We received KES {:.2} (M-Pesa {}). {} of your {} bookings had already been cancelled or paid for; the rest are confirmed and we'll refund the difference.",
+                payment.amount, receipt, needs_refund.len(), payment.booking_ids.len()
+            )
+        };
+        self.notify_user(payment.user_id, "Payment received after cancellation", &message).await?;
+        self.notify_admins(
+            "Payment needs a refund",
+            &format!("M-Pesa payment {} arrived after bookings {} were cancelled or already paid; refund the customer manually", receipt, needs_refund.join(", ")),
+        ).await?;
+        Ok(WebhookOutcome::processed(format!("Payment {} arrived after bookings {} were cancelled; they need a manual refund", receipt, needs_refund.join(", "))))
+    }
+
+    /// Records a payment on a booking and confirms it if it was waiting for one. False when the
+    /// booking was cancelled (or already paid), so nothing was recorded.
+    pub(super) async fn record_booking_paid(&self, booking_oid: ObjectId, event: &BookingEvent) -> Result<bool, AppError> {
+        let bookings = self.get_bookings_collection();
+        let after = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        // Conditional push so two deliveries of the same payment racing each other record it once
+        let Some(paid) = bookings.find_one_and_update(
+            doc! { "_id": booking_oid, "status": { "$ne": "Cancelled" }, "history.event": { "$ne": "paid" } },
+            doc! { "$push": { "history": bson::to_bson(event)? } },
+            after.clone()
+        ).await? else {
+            return Ok(false);
+        };
+        let confirmed = bookings.find_one_and_update(
+            doc! { "_id": booking_oid, "status": "PendingPayment" },
+            doc! { "$set": { "status": "Confirmed" } },
+            after
+        ).await?;
+        if let Some(booking) = &confirmed {
+            if let Err(e) = self.queue_booking_confirmation_sms(booking, None).await {
+                error!("Failed to queue booking confirmation SMS for {}: {}", booking_oid, e);
+            }
+            if let Err(e) = self.queue_booking_confirmation_email(booking, None).await {
+                error!("Failed to queue booking confirmation email for {}: {}", booking_oid, e);
+            }
+        }
+        let booking = confirmed.unwrap_or(paid);
+        self.post_booking_to_ledger(booking_oid, booking.user_id).await;
+        self.publish_event(DomainEvent::BookingPaid {
+            booking: (&booking).into(),
+            actor: event.actor.clone(),
+            note: event.note.clone(),
+        }).await;
+        Ok(true)
+    }
+
+    /// Cancels bookings whose payment window closed without payment and gives their seats back.
+    pub async fn expire_unpaid_bookings(&self) -> Result<usize, AppError> {
+        let timeout = chrono::Duration::minutes(MpesaConfig::from_env().payment_timeout_minutes);
+        let cutoff = bson::DateTime::from_millis((chrono::Utc::now() - timeout).timestamp_millis());
+        let bookings = self.get_bookings_collection();
+        let mut cursor = bookings.find(doc! { "status": "PendingPayment", "booking_date": { "$lt": cutoff } }, None).await?;
+        let mut due: Vec<Booking> = Vec::new();
+        while let Some(booking) = cursor.next().await {
+            let booking = booking?;
+            if booking.awaiting_payment_since() < cutoff {
+                due.push(booking);
+            }
+        }
+
+        let mut expired = 0;
+        for booking in due {
+            let Some(booking_oid) = booking.id else {
+                continue;
+            };
+            let event = BookingEvent::new("payment_expired", "system", None);
+            // A payment landing meanwhile confirms the booking first, and then it is left alone
+            let result = bookings.update_one(
+                doc! { "_id": booking_oid, "status": "PendingPayment" },
+                doc! { "$set": { "status": "Cancelled" }, "$push": { "history": bson::to_bson(&event)? } },
+                None
+            ).await?;
+            if result.modified_count == 0 {
+                continue;
+            }
+            self.release_seat(booking.bus_id, &booking.travel_date, &booking.seat_number).await?;
+            self.reverse_booking_split(booking_oid).await?;
+            self.publish_event(DomainEvent::BookingCancelled {
+                booking: BookingSnapshot::from(&booking).with_status("Cancelled"),
+                actor: "system".to_string(),
+            }).await;
+            self.notify_user(
+                booking.user_id,
+                "Booking cancelled",
+                &format!("Your booking for seat {} on {} was cancelled because it wasn't paid in time.", booking.seat_number, booking.travel_date),
+            ).await?;
+            expired += 1;
+        }
+        Ok(expired)
+    }
+}
@@ -0,0 +1,48 @@
+use mongodb::bson::doc;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::permissions::{permissions_for, AssignRoleRequest, EffectivePermissions};
+use crate::models::tenancy::OPERATOR_SCOPED_ROLES;
+use crate::models::user::ROLES;
+
+impl MongoDB {
+    pub async fn get_effective_permissions(&self, user_id: &str) -> Result<Option<EffectivePermissions>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let Some(user) = self.get_users_collection().find_one(doc! { "_id": user_oid }, None).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(EffectivePermissions {
+            user_id: user_oid.to_hex(),
+            email: user.email,
+            permissions: permissions_for(&user.role),
+            operator: user.operator,
+            role: user.role,
+        }))
+    }
+
+    /// Changes a user's role and ends their sessions so new tokens carry the new permissions.
+    pub async fn assign_role(&self, user_id: &str, req: &AssignRoleRequest) -> Result<EffectivePermissions, AppError> {
+        let role = req.role.as_str();
+        if !ROLES.contains(&role) {
+            return Err(format!("role must be one of: {}", ROLES.join(", ")).into());
+        }
+        let operator = req.operator.as_deref().map(str::trim).filter(|o| !o.is_empty());
+        if OPERATOR_SCOPED_ROLES.contains(&role) && operator.is_none() {
+            return Err(format!("An operator is required for the {} role", role).into());
+        }
+        let user_oid = self.string_to_id(user_id)?;
+        let now = mongodb::bson::DateTime::now();
+        let result = self.get_users_collection().update_one(
+            doc! { "_id": user_oid },
+            doc! { "$set": { "role": role, "operator": operator, "updated_at": now, "sessions_revoked_at": now } },
+            None
+        ).await?;
+        if result.matched_count == 0 {
+            return Err(AppError::not_found("User not found"));
+        }
+
+        self.get_effective_permissions(user_id).await?.ok_or_else(|| AppError::not_found("User not found"))
+    }
+}
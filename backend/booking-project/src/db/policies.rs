@@ -0,0 +1,187 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Bson},
+    options::{FindOptions, UpdateOptions},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::policy::{Policy, PolicyRequest, REFUND_METHODS};
+use crate::models::Bus;
+
+fn validate(req: &PolicyRequest) -> Result<(), AppError> {
+    if req.name.trim().is_empty() {
+        return Err("name is required".into());
+    }
+    if !REFUND_METHODS.contains(&req.refund_method.as_str()) {
+        return Err(format!("refund_method must be one of: {}", REFUND_METHODS.join(", ")).into());
+    }
+    let tiers = &req.cancellation.tiers;
+    if tiers.iter().any(|t| !(0.0..=100.0).contains(&t.refund_percent) || t.min_hours_before < 0) {
+        return Err("Cancellation tiers need 0-100% refunds and non-negative hours".into());
+    }
+    if tiers.windows(2).any(|w| w[0].min_hours_before <= w[1].min_hours_before) {
+        return Err("Cancellation tiers must be ordered from the longest notice down".into());
+    }
+    if req.amendment.fee < 0.0 || req.amendment.min_hours_before < 0 {
+        return Err("Amendment fee and notice must not be negative".into());
+    }
+    Ok(())
+}
+
+impl MongoDB {
+    fn get_policies_collection(&self) -> Collection<Policy> {
+        self.database().collection("policies")
+    }
+
+    pub async fn get_policies(&self) -> Result<Vec<Policy>, AppError> {
+        let options = FindOptions::builder().sort(doc! { "name": 1 }).build();
+        let mut cursor = self.get_policies_collection().find(None, options).await?;
+
+        let mut policies = Vec::new();
+        while let Some(result) = cursor.next().await {
+            policies.push(result?);
+        }
+        Ok(policies)
+    }
+
+    pub async fn get_policy(&self, id: &str) -> Result<Option<Policy>, AppError> {
+        let oid = self.string_to_id(id)?;
+        Ok(self.get_policies_collection().find_one(doc! { "_id": oid }, None).await?)
+    }
+
+    pub async fn create_policy(&self, req: &PolicyRequest) -> Result<Policy, AppError> {
+        validate(req)?;
+        self.ensure_single_operator_default(req.operator.as_deref(), None).await?;
+
+        let mut policy = Policy {
+            id: None,
+            name: req.name.trim().to_string(),
+            operator: req.operator.clone(),
+            cancellation: req.cancellation.clone(),
+            amendment: req.amendment.clone(),
+            refund_method: req.refund_method.clone(),
+            created_at: bson::DateTime::now(),
+            updated_at: bson::DateTime::now(),
+        };
+        let result = self.get_policies_collection().insert_one(&policy, None).await?;
+        policy.id = result.inserted_id.as_object_id();
+        Ok(policy)
+    }
+
+    pub async fn update_policy(&self, id: &str, req: &PolicyRequest) -> Result<Policy, AppError> {
+        validate(req)?;
+        let oid = self.string_to_id(id)?;
+        self.ensure_single_operator_default(req.operator.as_deref(), Some(oid)).await?;
+
+        let result = self.get_policies_collection().update_one(
+            doc! { "_id": oid },
+            doc! { "$set": {
+                "name": req.name.trim(),
+                "operator": &req.operator,
+                "cancellation": bson::to_bson(&req.cancellation)?,
+                "amendment": bson::to_bson(&req.amendment)?,
+                "refund_method": &req.refund_method,
+                "updated_at": bson::DateTime::now(),
+            } },
+            None
+        ).await?;
+        if result.matched_count == 0 {
+            return Err(AppError::not_found("Policy not found"));
+        }
+        self.get_policy(id).await?.ok_or_else(|| AppError::not_found("Policy not found"))
+    }
+
+    /// Deletes the policy and detaches it from routes and trips, which fall back to the next level.
+    pub async fn delete_policy(&self, id: &str) -> Result<bool, AppError> {
+        let oid = self.string_to_id(id)?;
+        let result = self.get_policies_collection().delete_one(doc! { "_id": oid }, None).await?;
+        if result.deleted_count == 0 {
+            return Ok(false);
+        }
+        self.get_buses_collection()
+            .update_many(doc! { "policy_id": oid }, doc! { "$unset": { "policy_id": "" } }, None)
+            .await?;
+        self.get_trip_status_collection()
+            .update_many(doc! { "policy_id": oid }, doc! { "$set": { "policy_id": Bson::Null } }, None)
+            .await?;
+        Ok(true)
+    }
+
+    async fn ensure_single_operator_default(&self, operator: Option<&str>, except: Option<ObjectId>) -> Result<(), AppError> {
+        let Some(operator) = operator else {
+            return Ok(());
+        };
+        let existing = self.get_policies_collection().find_one(doc! { "operator": operator }, None).await?;
+        match existing {
+            Some(p) if p.id != except => Err(AppError::conflict(format!("{} already has a default policy ({})", operator, p.name))),
+            _ => Ok(()),
+        }
+    }
+
+    async fn assignable_policy_id(&self, policy_id: Option<&str>) -> Result<Option<ObjectId>, AppError> {
+        match policy_id {
+            Some(id) => {
+                let policy = self.get_policy(id).await?.ok_or_else(|| AppError::not_found("Policy not found"))?;
+                Ok(policy.id)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set_bus_policy(&self, bus_id: &str, policy_id: Option<&str>) -> Result<(), AppError> {
+        let bus_oid = self.string_to_id(bus_id)?;
+        let update = match self.assignable_policy_id(policy_id).await? {
+            Some(policy_oid) => doc! { "$set": { "policy_id": policy_oid } },
+            None => doc! { "$unset": { "policy_id": "" } },
+        };
+        let result = self.get_buses_collection().update_one(doc! { "_id": bus_oid }, update, None).await?;
+        if result.matched_count == 0 {
+            return Err(AppError::not_found("Bus not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_trip_policy(&self, bus_id: &str, travel_date: &str, policy_id: Option<&str>) -> Result<(), AppError> {
+        let bus_oid = self.string_to_id(bus_id)?;
+        self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let policy_oid = self.assignable_policy_id(policy_id).await?;
+
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_trip_status_collection().update_one(
+            doc! { "bus_id": bus_oid, "travel_date": travel_date },
+            doc! {
+                "$set": { "policy_id": policy_oid, "updated_at": bson::DateTime::now() },
+                "$setOnInsert": { "status": "scheduled", "delay_minutes": 0_i64, "note": Bson::Null },
+            },
+            options
+        ).await?;
+        Ok(())
+    }
+
+    /// The policy governing a departure: trip override, then route, then operator default, then built-in.
+    pub async fn resolve_policy(&self, bus: &Bus, travel_date: &str) -> Result<Policy, AppError> {
+        let collection = self.get_policies_collection();
+
+        if let Some(bus_id) = bus.id {
+            let trip = self.get_trip_status_collection()
+                .find_one(doc! { "bus_id": bus_id, "travel_date": travel_date }, None)
+                .await?;
+            if let Some(policy_id) = trip.and_then(|t| t.policy_id) {
+                if let Some(policy) = collection.find_one(doc! { "_id": policy_id }, None).await? {
+                    return Ok(policy);
+                }
+            }
+        }
+        if let Some(policy_id) = bus.policy_id {
+            if let Some(policy) = collection.find_one(doc! { "_id": policy_id }, None).await? {
+                return Ok(policy);
+            }
+        }
+        if let Some(policy) = collection.find_one(doc! { "operator": bus.operator_name() }, None).await? {
+            return Ok(policy);
+        }
+        Ok(Policy::builtin())
+    }
+}
@@ -0,0 +1,66 @@
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOptions, IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::feed::RouteSummary;
+use crate::models::price_history::{price_day, RoutePrice};
+
+impl MongoDB {
+    fn get_route_prices_collection(&self) -> Collection<RoutePrice> {
+        self.database().collection("route_prices")
+    }
+
+    pub async fn ensure_price_history_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "route": 1, "date": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_route_prices_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    /// Folds the current starting price of each route into today's entry.
+    pub async fn record_route_prices(&self, routes: &[RouteSummary]) -> Result<(), AppError> {
+        let now = chrono::Utc::now();
+        let date = price_day(now);
+        let collection = self.get_route_prices_collection();
+        for route in routes {
+            collection.update_one(
+                doc! { "route": &route.slug, "date": &date },
+                doc! {
+                    "$set": {
+                        "from": &route.from,
+                        "to": &route.to,
+                        "price": route.starting_price,
+                        "currency": &route.currency,
+                        "updated_at": bson::DateTime::from_millis(now.timestamp_millis()),
+                    },
+                    "$min": { "lowest": route.starting_price },
+                    "$max": { "highest": route.starting_price },
+                },
+                UpdateOptions::builder().upsert(true).build()
+            ).await?;
+        }
+        Ok(())
+    }
+
+    /// A route's daily prices over the last `days` days, oldest first.
+    pub async fn get_price_history(&self, route: &str, days: i64) -> Result<Vec<RoutePrice>, AppError> {
+        let since = price_day(chrono::Utc::now() - chrono::Duration::days(days - 1));
+        let options = FindOptions::builder().sort(doc! { "date": 1 }).build();
+        let mut cursor = self.get_route_prices_collection()
+            .find(doc! { "route": route, "date": { "$gte": since } }, options)
+            .await?;
+
+        let mut prices = Vec::new();
+        while let Some(price) = cursor.next().await {
+            prices.push(price?);
+        }
+        Ok(prices)
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use mongodb::{
+    bson::{doc, Bson, Document},
+    event::command::{CommandEventHandler, CommandFailedEvent, CommandStartedEvent, CommandSucceededEvent},
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ProfilingConfig;
+use crate::metrics::metrics;
+
+// Commands whose plan `explain` can describe
+const EXPLAINABLE: [&str; 4] = ["find", "aggregate", "count", "distinct"];
+// Explains waiting for the profiling job; older ones are dropped rather than piling up
+const MAX_PENDING_EXPLAINS: usize = 50;
+
+struct InFlight {
+    collection: String,
+    shape: String,
+    explain: Option<Document>,
+}
+
+pub struct PendingExplain {
+    collection: String,
+    shape: String,
+    command: Document,
+}
+
+/// The filter with every value replaced by `?`, so queries group by shape rather than by customer data.
+pub fn filter_shape(value: &Bson) -> Bson {
+    match value {
+        Bson::Document(doc) => Bson::Document(doc.iter().map(|(k, v)| (k.clone(), filter_shape(v))).collect()),
+        Bson::Array(items) if items.iter().any(|i| matches!(i, Bson::Document(_))) => {
+            Bson::Array(items.iter().map(filter_shape).collect())
+        }
+        _ => Bson::String("?".to_string()),
+    }
+}
+
+fn command_shape(command_name: &str, command: &Document) -> String {
+    let filter = match command_name {
+        "find" | "count" | "distinct" => command.get("filter").or_else(|| command.get("query")).cloned(),
+        "aggregate" => command.get_array("pipeline").ok().and_then(|p| p.first()).cloned(),
+        "update" => command.get_array("updates").ok().and_then(|u| u.first()).and_then(|u| u.as_document()).and_then(|u| u.get("q")).cloned(),
+        "delete" => command.get_array("deletes").ok().and_then(|d| d.first()).and_then(|d| d.as_document()).and_then(|d| d.get("q")).cloned(),
+        "findAndModify" => command.get("query").cloned(),
+        _ => None,
+    };
+    filter.map(|f| filter_shape(&f).to_string()).unwrap_or_else(|| "{}".to_string())
+}
+
+/// Times every command the driver sends, so each repository method is covered without
+/// wrapping it by hand. Slow ones are logged by shape and counted in `/metrics`; with
+/// QUERY_PROFILING_DEBUG on, their plans are queued for `explain`.
+pub struct QueryProfiler {
+    config: ProfilingConfig,
+    in_flight: Mutex<HashMap<i32, InFlight>>,
+    pending_explains: Mutex<VecDeque<PendingExplain>>,
+}
+
+impl QueryProfiler {
+    pub fn new(config: ProfilingConfig) -> Self {
+        Self {
+            config,
+            in_flight: Mutex::new(HashMap::new()),
+            pending_explains: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn take_pending_explains(&self) -> Vec<PendingExplain> {
+        self.pending_explains.lock().map(|mut q| q.drain(..).collect()).unwrap_or_default()
+    }
+
+    fn finish(&self, request_id: i32, command_name: &str, succeeded: bool, duration: Duration) {
+        let Some(query) = self.in_flight.lock().ok().and_then(|mut m| m.remove(&request_id)) else {
+            return;
+        };
+        let metrics = metrics();
+        metrics.db_command_duration
+            .with_label_values(&[command_name, &query.collection])
+            .observe(duration.as_secs_f64());
+        if self.config.debug {
+            debug!("mongodb {} {} {} took {}ms", command_name, query.collection, query.shape, duration.as_millis());
+        }
+        if duration.as_millis() < self.config.slow_query_ms as u128 {
+            return;
+        }
+
+        metrics.db_slow_queries.with_label_values(&[command_name, &query.collection]).inc();
+        warn!("Slow query: {} on {} took {}ms, filter {}", command_name, query.collection, duration.as_millis(), query.shape);
+        let Some(command) = query.explain.filter(|_| succeeded) else {
+            return;
+        };
+        if let Ok(mut pending) = self.pending_explains.lock() {
+            if pending.len() >= MAX_PENDING_EXPLAINS {
+                pending.pop_front();
+            }
+            pending.push_back(PendingExplain { collection: query.collection, shape: query.shape, command });
+        }
+    }
+}
+
+impl CommandEventHandler for QueryProfiler {
+    fn handle_command_started_event(&self, event: CommandStartedEvent) {
+        // The profiler's own explains would otherwise be timed and explained in turn
+        if event.command_name == "explain" {
+            return;
+        }
+        let collection = event.command.get_str(&event.command_name).unwrap_or("-").to_string();
+        let explain = (self.config.debug && EXPLAINABLE.contains(&event.command_name.as_str())).then(|| {
+            // Session, cluster time and other driver fields don't belong in the explained command
+            event.command.iter()
+                .filter(|(k, _)| !k.starts_with('$') && k.as_str() != "lsid" && k.as_str() != "txnNumber")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Document>()
+        });
+        let query = InFlight {
+            shape: command_shape(&event.command_name, &event.command),
+            collection,
+            explain,
+        };
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.insert(event.request_id, query);
+        }
+    }
+
+    fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+        self.finish(event.request_id, &event.command_name, true, event.duration);
+    }
+
+    fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+        self.finish(event.request_id, &event.command_name, false, event.duration);
+    }
+}
+
+/// `FETCH > IXSCAN(bus_id_1_travel_date_1)` style summary of the winning plan in an explain reply.
+pub fn plan_summary(explain: &Document) -> Option<String> {
+    fn find_winning_plan(doc: &Document) -> Option<&Document> {
+        if let Ok(plan) = doc.get_document("winningPlan") {
+            return Some(plan.get_document("queryPlan").unwrap_or(plan));
+        }
+        doc.values().find_map(|v| match v {
+            Bson::Document(d) => find_winning_plan(d),
+            Bson::Array(items) => items.iter().find_map(|i| i.as_document().and_then(find_winning_plan)),
+            _ => None,
+        })
+    }
+
+    let mut stages = Vec::new();
+    let mut stage = find_winning_plan(explain);
+    while let Some(current) = stage {
+        let name = current.get_str("stage").unwrap_or("?");
+        stages.push(match current.get_str("indexName") {
+            Ok(index) => format!("{}({})", name, index),
+            Err(_) => name.to_string(),
+        });
+        stage = current.get_document("inputStage").ok().or_else(|| {
+            current.get_array("inputStages").ok().and_then(|s| s.first()).and_then(|s| s.as_document())
+        });
+    }
+    (!stages.is_empty()).then(|| stages.join(" > "))
+}
+
+impl MongoDB {
+    /// Runs `explain` for the slow queries captured since the last call and logs the plans they got.
+    pub async fn explain_slow_queries(&self) -> Result<usize, AppError> {
+        let pending = self.profiler().take_pending_explains();
+        let explained = pending.len();
+        for query in pending {
+            let reply = self.database()
+                .run_command(doc! { "explain": query.command, "verbosity": "queryPlanner" }, None)
+                .await?;
+            let plan = plan_summary(&reply).unwrap_or_else(|| "unknown plan".to_string());
+            if plan.contains("COLLSCAN") {
+                warn!("Slow query on {} {} is scanning the collection: {}", query.collection, query.shape, plan);
+            } else {
+                info!("Slow query on {} {} used {}", query.collection, query.shape, plan);
+            }
+        }
+        Ok(explained)
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use mongodb::bson;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::punctuality::{OperatorPunctuality, Punctuality, PunctualityReport, RoutePunctuality};
+
+// (operator, from, to)
+pub type RouteKey = (String, String, String);
+
+impl MongoDB {
+    /// Punctuality per operator route over the last `days`, from recorded actual departures.
+    pub async fn get_route_punctuality(&self, days: i64) -> Result<BTreeMap<RouteKey, Punctuality>, AppError> {
+        let mut buses = HashMap::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if let Some(id) = bus.id {
+                buses.insert(id, bus);
+            }
+        }
+
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let mut by_route: BTreeMap<RouteKey, Punctuality> = BTreeMap::new();
+        for trip in self.get_completed_trip_statuses(since).await? {
+            let (Some(bus), Some(actual)) = (buses.get(&trip.bus_id), trip.actual_departure) else {
+                continue;
+            };
+            let Some(scheduled) = bus.route.departure_at(&trip.travel_date) else {
+                continue;
+            };
+            let delay_minutes = (actual.timestamp_millis() - scheduled.timestamp_millis()) / 60_000;
+            by_route
+                .entry((bus.operator_name(), bus.route.from.clone(), bus.route.to.clone()))
+                .or_default()
+                .record(delay_minutes);
+        }
+        Ok(by_route)
+    }
+
+    pub async fn get_punctuality_report(&self, days: i64) -> Result<PunctualityReport, AppError> {
+        let by_route = self.get_route_punctuality(days).await?;
+
+        let mut by_operator: BTreeMap<String, Punctuality> = BTreeMap::new();
+        for ((operator, _, _), stats) in &by_route {
+            by_operator.entry(operator.clone()).or_default().merge(stats);
+        }
+
+        Ok(PunctualityReport {
+            days,
+            by_operator: by_operator
+                .into_iter()
+                .map(|(operator, stats)| OperatorPunctuality { operator, stats })
+                .collect(),
+            by_route: by_route
+                .into_iter()
+                .map(|((operator, from, to), stats)| RoutePunctuality { operator, from, to, stats })
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use log::warn;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument, UpdateOptions},
+    Collection, IndexModel,
+};
+
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::RateLimitConfig;
+use crate::models::rate_limit::{
+    is_rate_limit_key, AbuseBlock, LimitOverrideRequest, RateLimitCounter, RateLimitOverride,
+};
+
+impl MongoDB {
+    fn get_rate_limit_counters_collection(&self) -> Collection<RateLimitCounter> {
+        self.database().collection("rate_limit_counters")
+    }
+
+    fn get_rate_limit_overrides_collection(&self) -> Collection<RateLimitOverride> {
+        self.database().collection("rate_limit_overrides")
+    }
+
+    fn get_abuse_blocks_collection(&self) -> Collection<AbuseBlock> {
+        self.database().collection("abuse_blocks")
+    }
+
+    // Counters, overrides and blocks are all dropped by MongoDB once they lapse
+    pub async fn ensure_rate_limit_indexes(&self) -> Result<(), AppError> {
+        let expiring = || IndexOptions::builder().expire_after(Duration::from_secs(0)).build();
+        let counters = self.get_rate_limit_counters_collection();
+        counters.create_index(
+            IndexModel::builder()
+                .keys(doc! { "key": 1, "window_start": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        counters.create_index(IndexModel::builder().keys(doc! { "window_start": 1, "count": -1 }).build(), None).await?;
+        counters.create_index(IndexModel::builder().keys(doc! { "expires_at": 1 }).options(expiring()).build(), None).await?;
+        self.get_rate_limit_overrides_collection().create_index(
+            IndexModel::builder().keys(doc! { "expires_at": 1 }).options(expiring()).build(),
+            None
+        ).await?;
+        self.get_abuse_blocks_collection().create_index(
+            IndexModel::builder().keys(doc! { "blocked_until": 1 }).options(expiring()).build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Counts one request for `key` in the window starting at `window_start` and returns the total so far.
+    pub async fn hit_rate_limit(&self, key: &str, window_start: i64, window_secs: i64) -> Result<i64, AppError> {
+        let expires_at = bson::DateTime::from_millis((window_start + window_secs * 2) * 1000);
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let filter = doc! { "key": key, "window_start": window_start };
+        let update = doc! { "$inc": { "count": 1i64 }, "$setOnInsert": { "expires_at": expires_at } };
+        let counters = self.get_rate_limit_counters_collection();
+        // Two first hits in a window can race on the upsert; the loser finds the winner's document
+        let counter = match counters.find_one_and_update(filter.clone(), update.clone(), options.clone()).await {
+            Err(e) if is_duplicate_key(&e) => counters.find_one_and_update(filter, update, options).await?,
+            result => result?,
+        };
+        Ok(counter.map(|c| c.count).unwrap_or(1))
+    }
+
+    /// Busiest callers in the window, or one caller's count.
+    pub async fn get_rate_limit_usage(&self, key: Option<&str>, window_start: i64) -> Result<Vec<RateLimitCounter>, AppError> {
+        let mut filter = doc! { "window_start": window_start };
+        if let Some(key) = key {
+            filter.insert("key", key);
+        }
+        let find_options = FindOptions::builder().sort(doc! { "count": -1 }).limit(100).build();
+        let mut cursor = self.get_rate_limit_counters_collection().find(filter, find_options).await?;
+        let mut counters = Vec::new();
+        while let Some(result) = cursor.next().await {
+            counters.push(result?);
+        }
+        Ok(counters)
+    }
+
+    pub async fn get_rate_limit_overrides(&self) -> Result<Vec<RateLimitOverride>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "expires_at": 1 }).build();
+        let mut cursor = self.get_rate_limit_overrides_collection().find(
+            doc! { "expires_at": { "$gt": bson::DateTime::now() } },
+            find_options
+        ).await?;
+        let mut overrides = Vec::new();
+        while let Some(result) = cursor.next().await {
+            overrides.push(result?);
+        }
+        Ok(overrides)
+    }
+
+    /// Raises (or lowers) one caller's limit until the override expires; a second override for
+    /// the same key replaces the first.
+    pub async fn set_rate_limit_override(&self, req: &LimitOverrideRequest, actor: &str) -> Result<RateLimitOverride, AppError> {
+        req.validate()?;
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::hours(req.hours);
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let limit_override = self.get_rate_limit_overrides_collection().find_one_and_update(
+            doc! { "key": req.key.trim() },
+            doc! { "$set": {
+                "limit": req.limit,
+                "reason": req.reason.trim(),
+                "created_by": actor,
+                "created_at": bson::DateTime::from_millis(now.timestamp_millis()),
+                "expires_at": bson::DateTime::from_millis(expires_at.timestamp_millis()),
+            } },
+            options
+        ).await?.ok_or_else(|| AppError::internal("Failed to save override"))?;
+
+        self.record_audit(
+            "rate_limit.override_set",
+            actor,
+            &limit_override.key,
+            Some(format!("{} requests per window until {}: {}", req.limit, expires_at.to_rfc3339(), req.reason.trim())),
+        ).await?;
+        Ok(limit_override)
+    }
+
+    pub async fn remove_rate_limit_override(&self, id: &str, actor: &str) -> Result<(), AppError> {
+        let oid = self.string_to_id(id)?;
+        let removed = self.get_rate_limit_overrides_collection()
+            .find_one_and_delete(doc! { "_id": oid }, None)
+            .await?
+            .ok_or_else(|| AppError::not_found("Override not found"))?;
+        self.record_audit("rate_limit.override_removed", actor, &removed.key, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_abuse_blocks(&self) -> Result<Vec<AbuseBlock>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "blocked_at": -1 }).limit(500).build();
+        let mut cursor = self.get_abuse_blocks_collection().find(
+            doc! { "blocked_until": { "$gt": bson::DateTime::now() } },
+            find_options
+        ).await?;
+        let mut blocks = Vec::new();
+        while let Some(result) = cursor.next().await {
+            blocks.push(result?);
+        }
+        Ok(blocks)
+    }
+
+    pub async fn block_abusive_caller(&self, key: &str, hits: i64, config: &RateLimitConfig) -> Result<(), AppError> {
+        let now = bson::DateTime::now();
+        let blocked_until = bson::DateTime::from_millis(now.timestamp_millis() + config.block_secs * 1000);
+        self.get_abuse_blocks_collection().update_one(
+            doc! { "key": key },
+            doc! { "$set": { "hits": hits, "blocked_at": now, "blocked_until": blocked_until } },
+            UpdateOptions::builder().upsert(true).build()
+        ).await?;
+        warn!("Blocked {} for {}s after {} requests in one window", key, config.block_secs, hits);
+        Ok(())
+    }
+
+    /// Lifts the abuse block on `key`, or every block, and clears the matching counters so the
+    /// callers start the current window afresh. Returns how many blocks were lifted.
+    pub async fn reset_abuse_blocks(&self, key: Option<&str>, actor: &str) -> Result<u64, AppError> {
+        let filter = match key {
+            Some(key) if !is_rate_limit_key(key) => return Err("key must be user:<id> or ip:<address>".into()),
+            Some(key) => doc! { "key": key },
+            None => doc! {},
+        };
+        let lifted = self.get_abuse_blocks_collection().delete_many(filter.clone(), None).await?.deleted_count;
+        self.get_rate_limit_counters_collection().delete_many(filter, None).await?;
+        self.record_audit(
+            "rate_limit.blocks_reset",
+            actor,
+            key.unwrap_or("all"),
+            Some(format!("{} block(s) lifted", lifted)),
+        ).await?;
+        Ok(lifted)
+    }
+}
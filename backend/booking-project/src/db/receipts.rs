@@ -0,0 +1,97 @@
+use mongodb::bson::{self, doc};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+use crate::models::localization::{document_language, supported_language, SUPPORTED_LANGUAGES};
+use crate::models::outbox::OutboxMessage;
+use crate::models::receipt::BookingReceipt;
+use crate::models::Booking;
+use crate::notifications::valid_address;
+
+impl MongoDB {
+    /// The receipt for one of the user's bookings, in their saved language, else `requested`,
+    /// else the operator's default.
+    pub async fn get_booking_receipt(&self, booking_id: &str, user_id: &str, requested: Option<&str>) -> Result<BookingReceipt, AppError> {
+        let booking = self.get_user_booking(booking_id, user_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        self.booking_receipt(&booking, requested).await
+    }
+
+    pub(super) async fn booking_receipt(&self, booking: &Booking, requested: Option<&str>) -> Result<BookingReceipt, AppError> {
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let user = self.get_users_collection().find_one(doc! { "_id": booking.user_id }, None).await?;
+        let settings = self.get_operator_settings(&bus.operator_name()).await?;
+        let language = document_language(
+            user.as_ref().and_then(|u| u.language.as_deref()),
+            requested,
+            settings.default_language.as_deref(),
+        );
+        let localizer = self.get_localizer(language).await?;
+
+        Ok(BookingReceipt {
+            reference: booking.display_reference(),
+            passenger: booking.passenger.as_ref().map(|p| p.name.clone())
+                .or_else(|| user.as_ref().map(|u| u.username.clone()))
+                .unwrap_or_default(),
+            seat_number: booking.seat_number.clone(),
+            bus_number: bus.bus_number.clone(),
+            bus_type: localizer.name("bus_type", &bus.bus_type),
+            from: localizer.name("location", &bus.route.from),
+            to: localizer.name("location", &bus.route.to),
+            departure: bus.route.departure_at(&booking.travel_date)
+                .map(|d| d.format("%H:%M").to_string())
+                .unwrap_or_else(|| bus.route.departure_time.clone()),
+            travel_date: booking.travel_date.clone(),
+            fare: bus.route.price,
+            status: booking.status.clone(),
+            booked_on: chrono::DateTime::from_timestamp_millis(booking.booking_date.timestamp_millis())
+                .map(|d| d.with_timezone(&local_offset()).format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            language: language.to_string(),
+        })
+    }
+
+    /// Texts the passenger their booking details once the booking is confirmed, when they gave a
+    /// phone number.
+    pub async fn queue_booking_confirmation_sms(&self, booking: &Booking, requested: Option<&str>) -> Result<bool, AppError> {
+        let Some(phone) = booking.confirmation_sms_phone() else {
+            return Ok(false);
+        };
+        let receipt = self.booking_receipt(booking, requested).await?;
+        self.enqueue_outbox(OutboxMessage::new("sms", phone, None, receipt.confirmation_sms())).await?;
+        Ok(true)
+    }
+
+    /// Emails the booking's owner their ticket once the booking is confirmed, in the operator's
+    /// wording if they set one.
+    pub async fn queue_booking_confirmation_email(&self, booking: &Booking, requested: Option<&str>) -> Result<bool, AppError> {
+        if booking.status != "Confirmed" {
+            return Ok(false);
+        }
+        let user = self.get_users_collection().find_one(doc! { "_id": booking.user_id }, None).await?;
+        let Some(email) = user.map(|u| u.email).filter(|e| valid_address(e)) else {
+            return Ok(false);
+        };
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let template = self.get_operator_settings(&bus.operator_name()).await?.booking_email;
+        let receipt = self.booking_receipt(booking, requested).await?;
+        let (subject, body) = receipt.confirmation_email(template.as_ref());
+        self.enqueue_outbox(OutboxMessage::new("email", email.trim(), Some(subject), body)).await?;
+        Ok(true)
+    }
+
+    pub async fn set_user_language(&self, user_id: &str, language: &str) -> Result<&'static str, AppError> {
+        let language = supported_language(language)
+            .ok_or_else(|| format!("Unsupported language, expected one of: {}", SUPPORTED_LANGUAGES.join(", ")))?;
+        let user_oid = self.string_to_id(user_id)?;
+        let result = self.get_users_collection().update_one(
+            doc! { "_id": user_oid },
+            doc! { "$set": { "language": language, "updated_at": bson::DateTime::now() } },
+            None
+        ).await?;
+        if result.matched_count == 0 {
+            return Err(AppError::not_found("User not found"));
+        }
+        Ok(language)
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use log::warn;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::auth::RefreshToken;
+use crate::models::{AuthResponse, UserResponse};
+use crate::tokens::{refresh_token_hash, TokenService};
+
+const INVALID_REFRESH_TOKEN: &str = "Invalid refresh token";
+
+impl MongoDB {
+    fn get_refresh_tokens_collection(&self) -> Collection<RefreshToken> {
+        self.database().collection("refresh_tokens")
+    }
+
+    pub async fn ensure_refresh_token_indexes(&self) -> Result<(), AppError> {
+        let tokens = self.get_refresh_tokens_collection();
+        tokens.create_index(
+            IndexModel::builder()
+                .keys(doc! { "token_hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        tokens.create_index(IndexModel::builder().keys(doc! { "family": 1 }).build(), None).await?;
+        tokens.create_index(
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::from_secs(0)).build())
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// An access token and a stored refresh token for a signed-in user. `family` continues the
+    /// session a rotated refresh token belonged to; sign-ins start a new one.
+    pub(super) async fn issue_session(&self, user_id: ObjectId, role: &str, operator: Option<String>, family: Option<String>) -> Result<(String, String), AppError> {
+        let tokens = TokenService::from_env();
+        let access_token = tokens.issue_access(&user_id.to_hex(), role, operator)?;
+        let (refresh_token, claims) = tokens.issue_refresh(&user_id.to_hex())?;
+
+        self.get_refresh_tokens_collection().insert_one(RefreshToken {
+            id: None,
+            token_hash: refresh_token_hash(&refresh_token),
+            user_id,
+            family: family.unwrap_or_else(|| ObjectId::new().to_hex()),
+            created_at: bson::DateTime::now(),
+            expires_at: bson::DateTime::from_millis(claims.exp as i64 * 1000),
+            revoked_at: None,
+            revoked_reason: None,
+        }, None).await?;
+        Ok((access_token, refresh_token))
+    }
+
+    async fn revoke_refresh_family(&self, family: &str, reason: &str) -> Result<u64, AppError> {
+        let result = self.get_refresh_tokens_collection().update_many(
+            doc! { "family": family, "revoked_at": bson::Bson::Null },
+            doc! { "$set": { "revoked_at": bson::DateTime::now(), "revoked_reason": reason } },
+            None
+        ).await?;
+        Ok(result.modified_count)
+    }
+
+    /// Trades a refresh token for a new access and refresh pair, retiring the one presented.
+    /// A token that was already traded in is treated as stolen and ends its whole session.
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<AuthResponse, AppError> {
+        let claims = TokenService::from_env().decode_refresh(refresh_token).map_err(|_| AppError::unauthorized(INVALID_REFRESH_TOKEN))?;
+        let token_hash = refresh_token_hash(refresh_token);
+        let tokens = self.get_refresh_tokens_collection();
+
+        // Retiring before issuing means two concurrent uses of one token can't both succeed
+        let retired = tokens.find_one_and_update(
+            doc! { "token_hash": &token_hash, "revoked_at": bson::Bson::Null },
+            doc! { "$set": { "revoked_at": bson::DateTime::now(), "revoked_reason": "rotated" } },
+            None
+        ).await?;
+        let Some(retired) = retired else {
+            if let Some(reused) = tokens.find_one(doc! { "token_hash": &token_hash }, None).await? {
+                warn!("Refresh token reuse for user {}; ending session family {}", reused.user_id, reused.family);
+                self.revoke_refresh_family(&reused.family, "reuse").await?;
+            }
+            return Err(AppError::unauthorized(INVALID_REFRESH_TOKEN));
+        };
+
+        let user = self.get_users_collection()
+            .find_one(doc! { "_id": retired.user_id }, None).await?
+            .filter(|u| u.anonymized_at.is_none())
+            .ok_or_else(|| AppError::unauthorized(INVALID_REFRESH_TOKEN))?;
+        // Sessions of a merged account carry on under the account it was merged into
+        if self.is_session_revoked(&retired.user_id.to_hex(), claims.iat).await? {
+            self.revoke_refresh_family(&retired.family, "revoked").await?;
+            return Err(AppError::unauthorized("Your session has ended, please sign in again"));
+        }
+
+        let (token, refresh_token) = self.issue_session(retired.user_id, &user.role, user.operator.clone(), Some(retired.family)).await?;
+        Ok(AuthResponse {
+            token,
+            refresh_token,
+            user: UserResponse {
+                id: retired.user_id.to_hex(),
+                username: user.username,
+                email: user.email,
+                role: user.role,
+            },
+        })
+    }
+
+    /// Ends the session a refresh token belongs to. Unknown or invalid tokens are ignored, so
+    /// signing out always succeeds.
+    pub async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<(), AppError> {
+        if TokenService::from_env().decode_refresh(refresh_token).is_err() {
+            return Ok(());
+        }
+        let token = self.get_refresh_tokens_collection()
+            .find_one(doc! { "token_hash": refresh_token_hash(refresh_token) }, None)
+            .await?;
+        if let Some(token) = token {
+            self.revoke_refresh_family(&token.family, "logout").await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::BookingEvent;
+use crate::models::refund::CreateRefundRequest;
+use crate::models::RefundRequest;
+
+impl MongoDB {
+    fn get_refund_requests_collection(&self) -> Collection<RefundRequest> {
+        self.database().collection("refund_requests")
+    }
+
+    pub async fn request_refund(&self, user_id: &str, req: &CreateRefundRequest) -> Result<RefundRequest, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let booking_oid = self.string_to_id(&req.booking_id)?;
+
+        let booking = self.get_user_booking(&req.booking_id, user_id).await?
+            .ok_or_else(|| AppError::not_found("Booking not found"))?;
+
+        if booking.status != "Confirmed" {
+            return Err("Only confirmed bookings can be refunded".into());
+        }
+        if self.pass_for_booking(booking_oid).await?.is_some() {
+            return Err("This ride was taken off a commuter pass; cancel the booking to get the ride back".into());
+        }
+
+        let collection = self.get_refund_requests_collection();
+        let open_request = collection.find_one(
+            doc! { "booking_id": booking_oid, "status": { "$in": ["Requested", "Approved", "Paid"] } },
+            None
+        ).await?;
+        if open_request.is_some() {
+            return Err(AppError::conflict("A refund has already been requested for this booking"));
+        }
+
+        // Evaluate the cancellation policy against the time left before departure
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let departure = bus.route.departure_at(&booking.travel_date)
+            .ok_or("Unable to determine departure time for this booking")?;
+        let hours_before = (departure.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_hours();
+        let policy = self.resolve_policy(&bus, &booking.travel_date).await?;
+        let refund_percent = policy.cancellation
+            .refund_percent(hours_before)
+            .ok_or("This booking is too close to departure to be refunded")?;
+
+        // What the customer was actually charged, which isn't always the route price
+        let fare = self.amount_paid_for_booking(booking_oid).await?
+            .ok_or("There is no payment on this booking to refund")?;
+        let mut refund = RefundRequest {
+            id: None,
+            booking_id: booking_oid,
+            user_id: user_oid,
+            fare,
+            refund_percent,
+            amount: (fare * refund_percent / 100.0).round(),
+            reason: req.reason.clone(),
+            status: "Requested".to_string(),
+            refund_method: Some(policy.refund_method.clone()),
+            admin_note: None,
+            payout_reference: None,
+            requested_at: bson::DateTime::now(),
+            updated_at: bson::DateTime::now(),
+        };
+
+        let result = collection.insert_one(&refund, None).await?;
+        refund.id = result.inserted_id.as_object_id();
+
+        info!("Refund requested for booking {} ({}%)", req.booking_id, refund_percent);
+        self.record_booking_event(booking_oid, BookingEvent::new(
+            "refund_requested",
+            &format!("user:{}", user_id),
+            req.reason.clone(),
+        )).await?;
+        self.notify_admins(
+            "Refund request awaiting approval",
+            &format!(
+                "Booking {} on {} ({}) requests a refund of KES {:.0}",
+                req.booking_id, bus.bus_number, booking.travel_date, refund.amount
+            ),
+        ).await?;
+
+        Ok(refund)
+    }
+
+    pub async fn get_user_refund_requests(&self, user_id: &str) -> Result<Vec<RefundRequest>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        self.find_refund_requests(doc! { "user_id": user_oid }).await
+    }
+
+    pub async fn get_refund_requests(&self, status: Option<&str>) -> Result<Vec<RefundRequest>, AppError> {
+        let filter = match status {
+            Some(status) => doc! { "status": status },
+            None => doc! {},
+        };
+        self.find_refund_requests(filter).await
+    }
+
+    async fn find_refund_requests(&self, filter: bson::Document) -> Result<Vec<RefundRequest>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "requested_at": -1 }).build();
+        let mut cursor = self.get_refund_requests_collection().find(filter, find_options).await?;
+
+        let mut requests = Vec::new();
+        while let Some(result) = cursor.next().await {
+            requests.push(result?);
+        }
+        Ok(requests)
+    }
+
+    pub async fn approve_refund(&self, refund_id: &str, note: Option<String>) -> Result<RefundRequest, AppError> {
+        let refund = self.transition_refund(refund_id, "Requested", doc! {
+            "status": "Approved",
+            "admin_note": note,
+        }).await?;
+
+        // Approval cancels the booking and releases the seat; if that fails the request goes back
+        // to Requested so the approval can be retried
+        if let Err(e) = self.cancel_booking(&refund.booking_id.to_hex(), &refund.user_id.to_hex(), "admin").await {
+            self.transition_refund(refund_id, "Approved", doc! {
+                "status": "Requested",
+                "admin_note": bson::Bson::Null,
+            }).await?;
+            return Err(e);
+        }
+        self.notify_user(
+            refund.user_id,
+            "Refund approved",
+            &format!("Your refund of KES {:.0} has been approved and will be paid out shortly.", refund.amount),
+        ).await?;
+
+        Ok(refund)
+    }
+
+    pub async fn reject_refund(&self, refund_id: &str, note: Option<String>) -> Result<RefundRequest, AppError> {
+        let refund = self.transition_refund(refund_id, "Requested", doc! {
+            "status": "Rejected",
+            "admin_note": &note,
+        }).await?;
+
+        self.notify_user(
+            refund.user_id,
+            "Refund rejected",
+            &format!("Your refund request was rejected. {}", note.unwrap_or_default()),
+        ).await?;
+
+        Ok(refund)
+    }
+
+    pub async fn mark_refund_paid(&self, refund_id: &str, payout_reference: &str) -> Result<RefundRequest, AppError> {
+        let refund = self.transition_refund(refund_id, "Approved", doc! {
+            "status": "Paid",
+            "payout_reference": payout_reference,
+        }).await?;
+
+        self.notify_user(
+            refund.user_id,
+            "Refund paid",
+            &format!("KES {:.0} has been refunded (ref {}).", refund.amount, payout_reference),
+        ).await?;
+        self.record_booking_event(refund.booking_id, BookingEvent::new(
+            "refunded",
+            "admin",
+            Some(format!("KES {:.0}, ref {}", refund.amount, payout_reference)),
+        )).await?;
+        self.post_refund_to_ledger(&refund).await;
+
+        Ok(refund)
+    }
+
+    async fn transition_refund(&self, refund_id: &str, from_status: &str, mut update: bson::Document) -> Result<RefundRequest, AppError> {
+        let refund_oid = self.string_to_id(refund_id)?;
+        update.insert("updated_at", bson::DateTime::now());
+
+        let options = mongodb::options::FindOneAndUpdateOptions::builder()
+            .return_document(mongodb::options::ReturnDocument::After)
+            .build();
+        let refund = self.get_refund_requests_collection().find_one_and_update(
+            doc! { "_id": refund_oid, "status": from_status },
+            doc! { "$set": update },
+            options
+        ).await?;
+
+        refund.ok_or_else(|| AppError::not_found(format!("Refund request not found or not in {} state", from_status)))
+    }
+}
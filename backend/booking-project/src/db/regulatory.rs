@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ReadWorkload;
+use crate::models::regulatory::{
+    render_report, JurisdictionMapping, JurisdictionMappingRequest, RegulatoryExport, TripReportRow,
+};
+
+#[derive(Default)]
+struct TripTotals {
+    passengers: i64,
+    checked_in: i64,
+    incidents: i64,
+    serious_incidents: i64,
+}
+
+fn trip_key(group: &Document) -> Option<(String, ObjectId)> {
+    let key = group.get_document("_id").ok()?;
+    Some((key.get_str("travel_date").ok()?.to_string(), key.get_object_id("bus_id").ok()?))
+}
+
+fn count(group: &Document, field: &str) -> i64 {
+    group.get_i32(field).map(i64::from).or_else(|_| group.get_i64(field)).unwrap_or(0)
+}
+
+impl MongoDB {
+    fn get_jurisdiction_mappings_collection(&self) -> Collection<JurisdictionMapping> {
+        self.database().collection("regulatory_mappings")
+    }
+
+    fn get_regulatory_exports_collection(&self) -> Collection<RegulatoryExport> {
+        self.database().collection("regulatory_exports")
+    }
+
+    pub async fn get_jurisdiction_mapping(&self, code: &str) -> Result<JurisdictionMapping, AppError> {
+        let code = code.trim().to_uppercase();
+        let stored = self.get_jurisdiction_mappings_collection().find_one(doc! { "code": &code }, None).await?;
+        stored.or_else(|| JurisdictionMapping::builtin(&code))
+            .ok_or_else(|| AppError::not_found(format!("No field mapping is configured for jurisdiction {}", code)))
+    }
+
+    /// Stored mappings, plus the built-in ones nobody has overridden.
+    pub async fn get_jurisdiction_mappings(&self) -> Result<Vec<JurisdictionMapping>, AppError> {
+        let find_options = FindOptions::builder().sort(doc! { "code": 1 }).build();
+        let mut cursor = self.get_jurisdiction_mappings_collection().find(None, find_options).await?;
+        let mut mappings = Vec::new();
+        while let Some(result) = cursor.next().await {
+            mappings.push(result?);
+        }
+        if let Some(builtin) = JurisdictionMapping::builtin("KE") {
+            if !mappings.iter().any(|m| m.code == builtin.code) {
+                mappings.insert(0, builtin);
+            }
+        }
+        Ok(mappings)
+    }
+
+    pub async fn upsert_jurisdiction_mapping(&self, code: &str, req: &JurisdictionMappingRequest) -> Result<JurisdictionMapping, AppError> {
+        req.validate()?;
+        let code = code.trim().to_uppercase();
+        if code.is_empty() {
+            return Err("Jurisdiction code is required".into());
+        }
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let mapping = self.get_jurisdiction_mappings_collection().find_one_and_update(
+            doc! { "code": &code },
+            doc! { "$set": {
+                "name": req.name.trim(),
+                "delimiter": req.delimiter.clone().unwrap_or_else(|| ",".to_string()),
+                "columns": bson::to_bson(&req.columns)?,
+                "updated_at": bson::DateTime::now(),
+            } },
+            options
+        ).await?;
+        mapping.ok_or_else(|| AppError::internal("Failed to save mapping"))
+    }
+
+    /// Every trip operated between the two travel dates, with passenger and incident counts.
+    /// Cancelled trips didn't operate and are left out.
+    pub async fn get_trip_report_rows(&self, period_start: &str, period_end: &str) -> Result<Vec<TripReportRow>, AppError> {
+        let period = doc! { "$gte": period_start, "$lte": period_end };
+        let mut trips: BTreeMap<(String, ObjectId), TripTotals> = BTreeMap::new();
+
+        let pipeline = vec![
+            doc! { "$match": { "travel_date": &period, "status": "Confirmed" } },
+            doc! { "$group": {
+                "_id": { "travel_date": "$travel_date", "bus_id": "$bus_id" },
+                "passengers": { "$sum": 1 },
+                "checked_in": { "$sum": { "$cond": [
+                    { "$in": ["checked_in", { "$ifNull": ["$history.event", []] }] }, 1, 0
+                ] } },
+            } },
+        ];
+        let mut groups = self.collection_for::<Document>("bookings", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            if let Some(key) = trip_key(&group) {
+                let totals = trips.entry(key).or_default();
+                totals.passengers = count(&group, "passengers");
+                totals.checked_in = count(&group, "checked_in");
+            }
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "travel_date": &period } },
+            doc! { "$group": {
+                "_id": { "travel_date": "$travel_date", "bus_id": "$bus_id" },
+                "incidents": { "$sum": 1 },
+                "serious_incidents": { "$sum": { "$cond": [{ "$in": ["$severity", ["high", "critical"]] }, 1, 0] } },
+            } },
+        ];
+        let mut groups = self.collection_for::<Document>("incidents", ReadWorkload::Analytics).aggregate(pipeline, None).await?;
+        while let Some(group) = groups.next().await {
+            let group = group?;
+            if let Some(key) = trip_key(&group) {
+                let totals = trips.entry(key).or_default();
+                totals.incidents = count(&group, "incidents");
+                totals.serious_incidents = count(&group, "serious_incidents");
+            }
+        }
+
+        let mut statuses = HashMap::new();
+        let mut cursor = self.get_trip_status_collection().find(doc! { "travel_date": &period }, None).await?;
+        while let Some(result) = cursor.next().await {
+            let status = result?;
+            trips.entry((status.travel_date.clone(), status.bus_id)).or_default();
+            statuses.insert((status.travel_date.clone(), status.bus_id), status);
+        }
+
+        // Same-day swaps put a different vehicle on the road than the one scheduled
+        let mut vehicles = HashMap::new();
+        let mut cursor = self.get_seat_availability_collection()
+            .find(doc! { "travel_date": &period, "vehicle": { "$ne": null } }, None)
+            .await?;
+        while let Some(result) = cursor.next().await {
+            let availability = result?;
+            if let Some(vehicle) = availability.vehicle {
+                vehicles.insert((availability.travel_date, availability.bus_id), vehicle);
+            }
+        }
+
+        let mut buses = HashMap::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(result) = cursor.next().await {
+            let bus = result?;
+            if let Some(id) = bus.id {
+                buses.insert(id, bus);
+            }
+        }
+
+        let mut rows = Vec::new();
+        for ((travel_date, bus_id), totals) in trips {
+            let status = statuses.get(&(travel_date.clone(), bus_id));
+            if status.is_some_and(|s| s.status == "cancelled") {
+                continue;
+            }
+            let Some(bus) = buses.get(&bus_id) else {
+                continue;
+            };
+            let (vehicle, seats) = match vehicles.get(&(travel_date.clone(), bus_id)) {
+                Some(v) => (v.bus_number.clone(), v.total_seats),
+                None => (bus.bus_number.clone(), bus.total_seats),
+            };
+            rows.push(TripReportRow {
+                operator: bus.operator_name(),
+                vehicle,
+                route_from: bus.route.from.clone(),
+                route_to: bus.route.to.clone(),
+                departure: bus.route.departure_at(&travel_date).map(|d| d.format("%H:%M").to_string()).unwrap_or_default(),
+                status: status.map(|s| s.status.clone()).unwrap_or_else(|| "scheduled".to_string()),
+                delay_minutes: status.map(|s| s.delay_minutes).unwrap_or(0),
+                seats,
+                passengers: totals.passengers,
+                checked_in: totals.checked_in,
+                incidents: totals.incidents,
+                serious_incidents: totals.serious_incidents,
+                travel_date,
+            });
+        }
+        rows.sort_by(|a, b| (&a.operator, &a.travel_date, &a.departure).cmp(&(&b.operator, &b.travel_date, &b.departure)));
+        Ok(rows)
+    }
+
+    /// Builds and stores the return for one jurisdiction and period.
+    pub async fn generate_regulatory_export(&self, jurisdiction: &str, period_start: &str, period_end: &str, generated_by: &str) -> Result<RegulatoryExport, AppError> {
+        for date in [period_start, period_end] {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date))?;
+        }
+        if period_start > period_end {
+            return Err("period_start must not be after period_end".into());
+        }
+
+        let mapping = self.get_jurisdiction_mapping(jurisdiction).await?;
+        let rows = self.get_trip_report_rows(period_start, period_end).await?;
+        let mut export = RegulatoryExport {
+            id: None,
+            jurisdiction: mapping.code.clone(),
+            period_start: period_start.to_string(),
+            period_end: period_end.to_string(),
+            trips: rows.len() as i64,
+            passengers: rows.iter().map(|r| r.passengers).sum(),
+            incidents: rows.iter().map(|r| r.incidents).sum(),
+            generated_by: generated_by.to_string(),
+            generated_at: bson::DateTime::now(),
+            content: render_report(&mapping, &rows),
+        };
+        let result = self.get_regulatory_exports_collection().insert_one(&export, None).await?;
+        export.id = result.inserted_id.as_object_id();
+
+        info!("Regulatory export for {} {}..{} generated with {} trips", export.jurisdiction, period_start, period_end, export.trips);
+        Ok(export)
+    }
+
+    pub async fn has_regulatory_export(&self, jurisdiction: &str, period_start: &str, period_end: &str) -> Result<bool, AppError> {
+        let existing = self.get_regulatory_exports_collection().count_documents(
+            doc! { "jurisdiction": jurisdiction.trim().to_uppercase(), "period_start": period_start, "period_end": period_end },
+            None
+        ).await?;
+        Ok(existing > 0)
+    }
+
+    pub async fn get_regulatory_exports(&self, jurisdiction: Option<&str>) -> Result<Vec<RegulatoryExport>, AppError> {
+        let mut filter = doc! {};
+        if let Some(jurisdiction) = jurisdiction {
+            filter.insert("jurisdiction", jurisdiction.trim().to_uppercase());
+        }
+        let find_options = FindOptions::builder()
+            .sort(doc! { "period_end": -1, "generated_at": -1 })
+            .projection(doc! { "content": 0 })
+            .limit(200)
+            .build();
+        let mut cursor = self.get_regulatory_exports_collection().find(filter, find_options).await?;
+        let mut exports = Vec::new();
+        while let Some(result) = cursor.next().await {
+            exports.push(result?);
+        }
+        Ok(exports)
+    }
+
+    pub async fn get_regulatory_export(&self, id: &str) -> Result<Option<RegulatoryExport>, AppError> {
+        let oid = self.string_to_id(id)?;
+        Ok(self.get_regulatory_exports_collection().find_one(doc! { "_id": oid }, None).await?)
+    }
+}
@@ -0,0 +1,116 @@
+use mongodb::bson::{self, doc, oid::ObjectId};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::InventoryConfig;
+use crate::models::booking::{BookingEvent, ChangeBookingRequest, ReseatRequest};
+use crate::models::bus::OPEN_SEATING;
+use crate::models::Booking;
+
+impl MongoDB {
+    /// Moves a passenger to another seat, freeing the old one, and tells them about it. Operators
+    /// reseat whenever a trip needs it, e.g. a vehicle swap on the day, so amendment terms don't apply.
+    pub async fn reseat_booking(&self, booking_id: &str, req: &ReseatRequest) -> Result<Booking, AppError> {
+        let booking = self.get_booking(booking_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        let target_bus_id = match &req.bus_id {
+            Some(bus_id) => self.string_to_id(bus_id)?,
+            None => booking.bus_id,
+        };
+        let target_date = req.travel_date.clone().unwrap_or_else(|| booking.travel_date.clone());
+        let note = match &req.reason {
+            Some(reason) => format!("Seat {} → {} ({})", booking.seat_number, req.seat_number, reason),
+            None => format!("Seat {} → {}", booking.seat_number, req.seat_number),
+        };
+        let event = BookingEvent::new("reseated", "admin", Some(note));
+        self.move_booking(&booking, target_bus_id, &target_date, &req.seat_number, &event).await?;
+        self.get_booking(booking_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))
+    }
+
+    /// A passenger's own change of date, allowed only as far ahead of departure as the booking's
+    /// amendment terms say. The amendment fee is charged to them in the ledger.
+    pub async fn change_booking(&self, user_id: &str, booking_id: &str, req: &ChangeBookingRequest) -> Result<Booking, AppError> {
+        let booking = self.get_user_booking(booking_id, user_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        if booking.status != "Confirmed" {
+            return Err("Only confirmed bookings can be changed".into());
+        }
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let departure = bus.route.departure_at(&booking.travel_date)
+            .ok_or("Unable to determine departure time for this booking")?;
+        let hours_before = (departure.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_hours();
+        let amendment = self.resolve_policy(&bus, &booking.travel_date).await?.amendment;
+        if !amendment.accepts(hours_before) {
+            return Err(format!("Bookings cannot be changed less than {} hours before departure", amendment.min_hours_before).into());
+        }
+
+        let seat_number = req.seat_number.clone().unwrap_or_else(|| booking.seat_number.clone());
+        let mut note = format!("{} seat {} → {} seat {}", booking.travel_date, booking.seat_number, req.travel_date, seat_number);
+        if amendment.fee > 0.0 {
+            note.push_str(&format!(", amendment fee KES {:.0}", amendment.fee));
+        }
+        let event = BookingEvent::new("amended", &format!("user:{}", user_id), Some(note));
+        let booking_oid = self.move_booking(&booking, booking.bus_id, &req.travel_date, &seat_number, &event).await?;
+        if amendment.fee > 0.0 {
+            let reference = format!("{}:{}", booking_oid.to_hex(), event.at.timestamp_millis());
+            self.post_amendment_fee_to_ledger(booking_oid, booking.user_id, &reference, amendment.fee).await;
+        }
+        self.get_booking(booking_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))
+    }
+
+    // Takes the target seat, gives the old one back and records `event` on the booking
+    async fn move_booking(
+        &self,
+        booking: &Booking,
+        target_bus_id: ObjectId,
+        target_date: &str,
+        seat_number: &str,
+        event: &BookingEvent,
+    ) -> Result<ObjectId, AppError> {
+        if booking.status == "Cancelled" {
+            return Err("Cancelled bookings cannot be reseated".into());
+        }
+        if target_bus_id == booking.bus_id && target_date == booking.travel_date && seat_number == booking.seat_number {
+            return Err(AppError::conflict("Booking is already in this seat"));
+        }
+
+        let bus = self.get_bus(&target_bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        if booking.seat_number == OPEN_SEATING || InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) {
+            return Err("Trips sold by headcount have no seats to move between".into());
+        }
+        if target_bus_id != booking.bus_id || target_date != booking.travel_date {
+            self.ensure_bus_runs(&bus, target_date).await?;
+        }
+        if !self.trip_has_seat(&bus, target_date, seat_number).await? {
+            return Err(format!("Seat {} does not exist on {}", seat_number, bus.bus_number).into());
+        }
+
+        let lock_name = format!("seat:{}:{}:{}", target_bus_id.to_hex(), target_date, seat_number);
+        let _seat_lock = self.try_lock(&lock_name, std::time::Duration::from_secs(30)).await?
+            .ok_or_else(|| AppError::conflict("This seat is being booked by someone else, please try again"))?;
+        if !self.reserve_seat(&bus, target_date, seat_number).await? {
+            return Err(AppError::conflict(format!("Seat {} is not available", seat_number)));
+        }
+
+        let booking_oid = booking.id.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        let updated = self.get_bookings_collection().update_one(
+            doc! { "_id": booking_oid, "status": { "$ne": "Cancelled" } },
+            doc! {
+                "$set": { "bus_id": target_bus_id, "travel_date": target_date, "seat_number": seat_number },
+                "$push": { "history": bson::to_bson(event)? },
+            },
+            None
+        ).await?;
+        if updated.modified_count == 0 {
+            self.release_seat(target_bus_id, target_date, seat_number).await?;
+            return Err("Booking was cancelled while reseating".into());
+        }
+        self.release_seat(booking.bus_id, &booking.travel_date, &booking.seat_number).await?;
+
+        let message = format!(
+            "Your booking has been moved to seat {} on {} ({} to {}) departing {} at {}.",
+            seat_number, bus.bus_number, bus.route.from, bus.route.to, target_date, bus.route.departure_time
+        );
+        self.notify_user(booking.user_id, "Your seat has changed", &message).await?;
+        self.refresh_next_trip_quietly(booking.user_id).await;
+        Ok(booking_oid)
+    }
+}
@@ -0,0 +1,184 @@
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::FindOptions,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::RetentionConfig;
+use crate::models::consent::CONSENT_PURPOSES;
+use crate::models::outbox::OutboxMessage;
+use crate::models::User;
+
+const RETENTION_ACTOR: &str = "system:retention";
+
+fn bson_time(at: chrono::DateTime<chrono::Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(at.timestamp_millis())
+}
+
+/// Customer accounts, not yet anonymized, whose last sign-in (or sign-up) is before `before`.
+fn inactive_filter(before: bson::DateTime) -> Document {
+    doc! {
+        "role": "user",
+        "anonymized_at": { "$exists": false },
+        "merged_into": { "$exists": false },
+        "$or": [
+            { "last_login_at": { "$lt": before } },
+            { "last_login_at": { "$exists": false }, "created_at": { "$lt": before } },
+        ],
+    }
+}
+
+impl MongoDB {
+    /// Marks the account active; a pending anonymization notice no longer applies.
+    pub async fn record_login(&self, user_id: ObjectId) -> Result<(), AppError> {
+        self.get_users_collection().update_one(
+            doc! { "_id": user_id },
+            doc! { "$set": { "last_login_at": bson::DateTime::now() }, "$unset": { "retention_notice_at": "" } },
+            None
+        ).await?;
+        Ok(())
+    }
+
+    // Signing in isn't the only activity: bookings made since `since` keep the account too
+    async fn booked_since(&self, user_id: ObjectId, since: bson::DateTime) -> Result<bool, AppError> {
+        let bookings = self.get_bookings_collection()
+            .count_documents(doc! { "user_id": user_id, "booking_date": { "$gte": since } }, None)
+            .await?;
+        Ok(bookings > 0)
+    }
+
+    async fn find_users(&self, filter: Document, limit: i64) -> Result<Vec<User>, AppError> {
+        let find_options = FindOptions::builder().limit(limit).build();
+        let mut cursor = self.get_users_collection().find(filter, find_options).await?;
+        let mut users = Vec::new();
+        while let Some(result) = cursor.next().await {
+            users.push(result?);
+        }
+        Ok(users)
+    }
+
+    /// Emails accounts that will reach the inactivity limit within the notice period.
+    pub async fn send_retention_notices(&self, config: &RetentionConfig) -> Result<u64, AppError> {
+        let now = chrono::Utc::now();
+        let warn_before = bson_time(config.inactive_before(now) + chrono::Duration::days(config.notice_days));
+        let mut filter = inactive_filter(warn_before);
+        filter.insert("retention_notice_at", doc! { "$exists": false });
+
+        let mut notified = 0;
+        for user in self.find_users(filter, config.batch_size).await? {
+            let Some(user_id) = user.id else { continue };
+            if self.booked_since(user_id, warn_before).await? {
+                continue;
+            }
+            self.enqueue_outbox(OutboxMessage::new(
+                "email",
+                &user.email,
+                Some("Your Burudani Mint Travels account will be anonymized".to_string()),
+                format!(
+                    "Hi {},\n\nYou haven't used your account in almost {} years. To protect your personal data, we will anonymize it in {} days: your name, email address and passenger details will be removed, and you will no longer be able to sign in.\n\nTo keep your account, simply sign in before then.",
+                    user.username, config.inactive_years, config.notice_days
+                ),
+            )).await?;
+            self.get_users_collection().update_one(
+                doc! { "_id": user_id },
+                doc! { "$set": { "retention_notice_at": bson::DateTime::now() } },
+                None
+            ).await?;
+            self.record_audit(
+                "account.retention_notice",
+                RETENTION_ACTOR,
+                &format!("user:{}", user_id.to_hex()),
+                Some(format!("Anonymization due in {} days", config.notice_days)),
+            ).await?;
+            notified += 1;
+        }
+        Ok(notified)
+    }
+
+    /// Anonymizes accounts that were warned at least the notice period ago and stayed inactive.
+    pub async fn anonymize_stale_accounts(&self, config: &RetentionConfig) -> Result<u64, AppError> {
+        let now = chrono::Utc::now();
+        let inactive_before = bson_time(config.inactive_before(now));
+        let mut filter = inactive_filter(inactive_before);
+        filter.insert("retention_notice_at", doc! { "$lte": bson_time(now - chrono::Duration::days(config.notice_days)) });
+
+        let mut anonymized = 0;
+        for user in self.find_users(filter, config.batch_size).await? {
+            let Some(user_id) = user.id else { continue };
+            if self.booked_since(user_id, inactive_before).await? {
+                continue;
+            }
+            self.anonymize_user(user_id, RETENTION_ACTOR).await?;
+            anonymized += 1;
+        }
+        Ok(anonymized)
+    }
+
+    /// Scrubs the personal data of one account. Bookings stay, without passenger details, so
+    /// trip, revenue and settlement figures are unchanged.
+    pub async fn anonymize_user(&self, user_id: ObjectId, actor: &str) -> Result<(), AppError> {
+        let placeholder = format!("anonymized-{}", user_id.to_hex());
+        let now = bson::DateTime::now();
+        let result = self.get_users_collection().update_one(
+            doc! { "_id": user_id, "anonymized_at": { "$exists": false } },
+            doc! {
+                "$set": {
+                    "username": "Anonymized user",
+                    "email": format!("{}@anonymized.invalid", placeholder),
+                    "password": "",
+                    "anonymized_at": now,
+                    "sessions_revoked_at": now,
+                    "updated_at": now,
+                },
+                "$unset": { "language": "", "operator": "", "retention_notice_at": "" },
+            },
+            None
+        ).await?;
+        if result.modified_count == 0 {
+            return Ok(());
+        }
+
+        let bookings = self.get_bookings_collection();
+        let booking_ids: Vec<ObjectId> = bookings.distinct("_id", doc! { "user_id": user_id }, None)
+            .await?
+            .into_iter()
+            .filter_map(|id| id.as_object_id())
+            .collect();
+        // Age and gender only feed aggregate reports; everything identifying goes
+        bookings.update_many(
+            doc! { "user_id": user_id, "passenger": { "$type": "object" } },
+            doc! {
+                "$set": { "passenger.name": "Anonymized" },
+                "$unset": { "passenger.phone": "", "passenger.id_number": "" },
+            },
+            None
+        ).await?;
+        self.delete_next_of_kin(&booking_ids).await?;
+        let payment_methods = self.purge_payment_methods(user_id, actor).await?;
+        // The consent history stays as the record of what was agreed; queued promotions are suppressed
+        let withdrawn = CONSENT_PURPOSES.iter().map(|p| (p.to_string(), false)).collect();
+        self.update_consents(user_id, &withdrawn, "account_anonymized").await?;
+
+        let database = self.database();
+        for collection in ["notifications", "email_changes", "seat_holds"] {
+            database.collection::<Document>(collection).delete_many(doc! { "user_id": user_id }, None).await?;
+        }
+        database.collection::<Document>("search_log").update_many(
+            doc! { "user_id": user_id.to_hex() },
+            doc! { "$set": { "user_id": bson::Bson::Null } },
+            None
+        ).await?;
+
+        self.record_audit(
+            "account.anonymized",
+            actor,
+            &format!("user:{}", user_id.to_hex()),
+            Some(format!("{} booking(s) kept without passenger details, {} saved payment method(s) removed", booking_ids.len(), payment_methods)),
+        ).await?;
+        info!("Anonymized user {}", user_id.to_hex());
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use mongodb::{bson::{doc, oid::ObjectId}, options::ReplaceOptions, Collection};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::{departure_sort_key, Bus};
+use crate::models::schedule::{bus_runs_on, Schedule, ScheduleRequest, TripQuery, TripResponse};
+
+impl MongoDB {
+    fn get_schedules_collection(&self) -> Collection<Schedule> {
+        self.database().collection("bus_schedules")
+    }
+
+    pub async fn get_schedule(&self, bus_id: ObjectId) -> Result<Option<Schedule>, AppError> {
+        Ok(self.get_schedules_collection().find_one(doc! { "_id": bus_id }, None).await?)
+    }
+
+    /// Replaces the bus's schedule. Dates already booked stay bookable for those passengers;
+    /// only new bookings follow the schedule.
+    pub async fn set_schedule(&self, bus_id: &str, req: ScheduleRequest, actor: &str) -> Result<Schedule, AppError> {
+        req.validate()?;
+        let bus_oid = self.string_to_id(bus_id)?;
+        self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let schedule = req.into_schedule(bus_oid);
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_schedules_collection().replace_one(doc! { "_id": bus_oid }, &schedule, options).await?;
+        self.record_audit("schedule.updated", actor, &format!("bus:{}", bus_id), None).await?;
+        Ok(schedule)
+    }
+
+    /// Drops the schedule; the bus then runs every day again.
+    pub async fn delete_schedule(&self, bus_id: &str, actor: &str) -> Result<bool, AppError> {
+        let bus_oid = self.string_to_id(bus_id)?;
+        let result = self.get_schedules_collection().delete_one(doc! { "_id": bus_oid }, None).await?;
+        if result.deleted_count == 0 {
+            return Ok(false);
+        }
+        self.record_audit("schedule.deleted", actor, &format!("bus:{}", bus_id), None).await?;
+        Ok(true)
+    }
+
+    /// Refuses new seats on a date the bus doesn't run.
+    pub async fn ensure_bus_runs(&self, bus: &Bus, travel_date: &str) -> Result<(), AppError> {
+        let schedule = match bus.id {
+            Some(bus_id) => self.get_schedule(bus_id).await?,
+            None => None,
+        };
+        if !bus_runs_on(schedule.as_ref(), travel_date) {
+            return Err(format!("{} doesn't run on {}", bus.bus_number, travel_date).into());
+        }
+        Ok(())
+    }
+
+    /// Keeps the buses that run on the date.
+    pub async fn buses_running_on(&self, buses: Vec<Bus>, travel_date: &str) -> Result<Vec<Bus>, AppError> {
+        let bus_ids: Vec<ObjectId> = buses.iter().filter_map(|b| b.id).collect();
+        let mut schedules = HashMap::new();
+        let mut cursor = self.get_schedules_collection().find(doc! { "_id": { "$in": bus_ids } }, None).await?;
+        while let Some(schedule) = cursor.next().await {
+            let schedule = schedule?;
+            schedules.insert(schedule.bus_id, schedule);
+        }
+        Ok(buses.into_iter()
+            .filter(|b| bus_runs_on(b.id.and_then(|id| schedules.get(&id)), travel_date))
+            .collect())
+    }
+
+    /// The timetable for a date, optionally for one route, by departure time. Cancelled trips
+    /// are listed with their status so nobody waits for them.
+    pub async fn list_trips(&self, query: &TripQuery) -> Result<Vec<TripResponse>, AppError> {
+        chrono::NaiveDate::parse_from_str(&query.date, "%Y-%m-%d").map_err(|_| "date must look like 2026-12-20")?;
+        let matches = |place: &str, wanted: &Option<String>| {
+            wanted.as_deref().map(str::trim).filter(|w| !w.is_empty()).is_none_or(|w| place.eq_ignore_ascii_case(w))
+        };
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if matches(&bus.route.from, &query.from) && matches(&bus.route.to, &query.to) {
+                buses.push(bus);
+            }
+        }
+        let mut buses = self.buses_running_on(buses, &query.date).await?;
+        buses.sort_by_cached_key(|b| departure_sort_key(&b.route.departure_time));
+
+        let bus_ids: Vec<ObjectId> = buses.iter().filter_map(|b| b.id).collect();
+        let statuses = self.get_trip_statuses(&bus_ids, &query.date).await?;
+        let seats_left = self.get_seats_left(&query.date, None).await?;
+        Ok(buses.into_iter().filter_map(|bus| {
+            let bus_id = bus.id?;
+            let status = statuses.get(&bus_id);
+            Some(TripResponse {
+                id: format!("{}:{}", bus_id.to_hex(), query.date),
+                bus_id: bus_id.to_hex(),
+                departure: bus.route.departure_at(&query.date).map(|d| d.to_rfc3339()),
+                arrival: bus.route.arrival_at(&query.date).map(|a| a.to_rfc3339()),
+                seats_left: seats_left.get(&bus_id).copied().unwrap_or(bus.total_seats),
+                price: bus.route.price,
+                bus_number: bus.bus_number,
+                bus_type: bus.bus_type,
+                from: bus.route.from,
+                to: bus.route.to,
+                travel_date: query.date.clone(),
+                status: status.map(|s| s.status.clone()).unwrap_or_else(|| "scheduled".to_string()),
+                delay_minutes: status.map(|s| s.delay_minutes).unwrap_or(0),
+            })
+        }).collect())
+    }
+}
@@ -0,0 +1,127 @@
+use log::info;
+use mongodb::{
+    bson::{doc, Document},
+    options::{CreateCollectionOptions, ValidationAction, ValidationLevel},
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::BOOKING_STATUSES;
+use crate::models::bus::{SEAT_AVAILABLE, SEAT_BOOKED, SEAT_CHARTERED, SEAT_HELD};
+use crate::models::user::ROLES;
+
+fn users_schema() -> Document {
+    doc! {
+        "bsonType": "object",
+        "required": ["username", "email", "password", "role"],
+        "properties": {
+            "username": { "bsonType": "string", "minLength": 1 },
+            "email": { "bsonType": "string", "pattern": "^[^@\\s]+@[^@\\s]+$" },
+            "password": { "bsonType": "string" }, // empty for Google sign-ins
+            "role": { "enum": ROLES.to_vec() },
+            "created_at": { "bsonType": "date" },
+            "updated_at": { "bsonType": "date" },
+        },
+    }
+}
+
+fn buses_schema() -> Document {
+    doc! {
+        "bsonType": "object",
+        "required": ["bus_number", "bus_type", "total_seats", "route"],
+        "properties": {
+            "bus_number": { "bsonType": "string", "minLength": 1 },
+            "bus_type": { "bsonType": "string", "minLength": 1 },
+            "total_seats": { "bsonType": ["int", "long"], "minimum": 1, "maximum": 100 },
+            "route": {
+                "bsonType": "object",
+                "required": ["from", "to", "departure_time", "arrival_time", "price"],
+                "properties": {
+                    "from": { "bsonType": "string", "minLength": 1 },
+                    "to": { "bsonType": "string", "minLength": 1 },
+                    "departure_time": { "bsonType": "string" },
+                    "arrival_time": { "bsonType": "string" },
+                    "price": { "bsonType": ["double", "int", "long", "decimal"], "minimum": 0 },
+                },
+            },
+        },
+    }
+}
+
+fn bookings_schema() -> Document {
+    doc! {
+        "bsonType": "object",
+        "required": ["user_id", "bus_id", "seat_number", "travel_date", "booking_date", "status"],
+        "properties": {
+            "user_id": { "bsonType": "objectId" },
+            "bus_id": { "bsonType": "objectId" },
+            "seat_number": { "bsonType": "string", "minLength": 1 },
+            "travel_date": { "bsonType": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}$" },
+            "booking_date": { "bsonType": "date" },
+            "status": { "enum": BOOKING_STATUSES.to_vec() },
+            "history": { "bsonType": "array" },
+        },
+    }
+}
+
+fn seat_availability_schema() -> Document {
+    doc! {
+        "bsonType": "object",
+        "required": ["bus_id", "travel_date"],
+        "properties": {
+            "bus_id": { "bsonType": "objectId" },
+            "travel_date": { "bsonType": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}$" },
+        },
+    }
+}
+
+fn trip_seats_schema() -> Document {
+    doc! {
+        "bsonType": "object",
+        "required": ["bus_id", "travel_date", "seat_number", "state"],
+        "properties": {
+            "bus_id": { "bsonType": "objectId" },
+            "travel_date": { "bsonType": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}$" },
+            "seat_number": { "bsonType": "string", "minLength": 1 },
+            "state": { "enum": [SEAT_AVAILABLE, SEAT_HELD, SEAT_BOOKED, SEAT_CHARTERED] },
+            "hold_expiry": { "bsonType": "date" },
+        },
+    }
+}
+
+impl MongoDB {
+    /// Installs `$jsonSchema` validators so writes from scripts or older code can't store malformed documents.
+    /// Validation is `moderate`: documents that are already invalid can still be updated, new ones can't be.
+    pub async fn apply_schema_validators(&self) -> Result<(), AppError> {
+        let database = self.database();
+        let existing = database.list_collection_names(None).await?;
+        let schemas = [
+            ("users", users_schema()),
+            ("buses", buses_schema()),
+            ("bookings", bookings_schema()),
+            ("seat_availability", seat_availability_schema()),
+            ("trip_seats", trip_seats_schema()),
+        ];
+
+        for (name, schema) in schemas {
+            let validator = doc! { "$jsonSchema": schema };
+            if existing.iter().any(|c| c == name) {
+                database.run_command(doc! {
+                    "collMod": name,
+                    "validator": validator,
+                    "validationLevel": "moderate",
+                    "validationAction": "error",
+                }, None).await?;
+            } else {
+                let options = CreateCollectionOptions::builder()
+                    .validator(validator)
+                    .validation_level(ValidationLevel::Moderate)
+                    .validation_action(ValidationAction::Error)
+                    .build();
+                database.create_collection(name, options).await?;
+            }
+            info!("Schema validator applied to {}", name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use futures::StreamExt;
+use mongodb::bson::{self, doc};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::search_log::{PopularRoute, SearchLogEntry, ZeroResultSearch};
+
+impl MongoDB {
+    pub async fn log_search(&self, entry: &SearchLogEntry) -> Result<(), AppError> {
+        self.get_search_log_collection().insert_one(entry, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_popular_routes(&self, days: i64, limit: i64) -> Result<Vec<PopularRoute>, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let pipeline = vec![
+            doc! { "$match": { "created_at": { "$gte": since } } },
+            doc! { "$group": {
+                "_id": { "from": "$from", "to": "$to" },
+                "searches": { "$sum": 1 },
+                "zero_result_searches": { "$sum": { "$cond": [{ "$lte": ["$results_count", 0] }, 1, 0] } },
+            } },
+            doc! { "$sort": { "searches": -1 } },
+            doc! { "$limit": limit },
+        ];
+
+        let mut cursor = self.get_search_log_reporting_collection().aggregate(pipeline, None).await?;
+        let mut routes = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let group = result?;
+            let key = group.get_document("_id")?;
+            routes.push(PopularRoute {
+                from: key.get_str("from").unwrap_or_default().to_string(),
+                to: key.get_str("to").unwrap_or_default().to_string(),
+                searches: group.get_i32("searches").map(i64::from).unwrap_or(0),
+                zero_result_searches: group.get_i32("zero_result_searches").map(i64::from).unwrap_or(0),
+            });
+        }
+        Ok(routes)
+    }
+
+    pub async fn get_zero_result_searches(&self, days: i64, limit: i64) -> Result<Vec<ZeroResultSearch>, AppError> {
+        let since = bson::DateTime::from_millis((chrono::Utc::now() - chrono::Duration::days(days)).timestamp_millis());
+        let pipeline = vec![
+            doc! { "$match": { "created_at": { "$gte": since }, "results_count": { "$lte": 0 } } },
+            doc! { "$group": {
+                "_id": { "from": "$from", "to": "$to", "travel_date": "$travel_date" },
+                "searches": { "$sum": 1 },
+                "last_searched_at": { "$max": "$created_at" },
+            } },
+            doc! { "$sort": { "searches": -1, "last_searched_at": -1 } },
+            doc! { "$limit": limit },
+        ];
+
+        let mut cursor = self.get_search_log_reporting_collection().aggregate(pipeline, None).await?;
+        let mut searches = Vec::new();
+        while let Some(result) = cursor.next().await {
+            let group = result?;
+            let key = group.get_document("_id")?;
+            searches.push(ZeroResultSearch {
+                from: key.get_str("from").unwrap_or_default().to_string(),
+                to: key.get_str("to").unwrap_or_default().to_string(),
+                travel_date: key.get_str("travel_date").unwrap_or_default().to_string(),
+                searches: group.get_i32("searches").map(i64::from).unwrap_or(0),
+                last_searched_at: group.get_datetime("last_searched_at")
+                    .ok()
+                    .and_then(|t| t.try_to_rfc3339_string().ok())
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(searches)
+    }
+}
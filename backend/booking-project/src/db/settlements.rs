@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use futures::StreamExt;
+use log::info;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::FindOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::settlement::{
+    round_money, BookingSplit, OperatorStatement, Settlement, SettlementResponse, UnsettledTotals,
+};
+use crate::models::{Booking, Bus};
+
+impl MongoDB {
+    pub(super) fn get_booking_splits_collection(&self) -> Collection<BookingSplit> {
+        self.database().collection("booking_splits")
+    }
+
+    fn get_settlements_collection(&self) -> Collection<Settlement> {
+        self.database().collection("settlements")
+    }
+
+    /// Records the operator/platform split for a newly confirmed booking.
+    pub async fn record_booking_split(&self, booking: &Booking, bus: &Bus) -> Result<(), AppError> {
+        let booking_id = booking.id.ok_or("Booking has no id")?;
+        let fare = bus.route.price;
+        let rule = self.resolve_commission_rule(bus).await?;
+        let commission = rule.commission_for(fare);
+
+        let split = BookingSplit {
+            id: None,
+            booking_id,
+            bus_id: booking.bus_id,
+            operator: bus.operator_name(),
+            travel_date: booking.travel_date.clone(),
+            fare,
+            commission,
+            operator_share: round_money(fare - commission),
+            commission_rule_id: rule.id,
+            reversal: false,
+            settlement_id: None,
+            recorded_at: bson::DateTime::now(),
+        };
+        self.get_booking_splits_collection().insert_one(&split, None).await?;
+        // Bookings still awaiting payment or review reach the ledger when they're paid for
+        if booking.status == "Confirmed" {
+            self.post_booking_to_ledger(booking_id, booking.user_id).await;
+        }
+        Ok(())
+    }
+
+    /// Takes a cancelled booking out of the operator's earnings, and its sale out of the ledger.
+    pub async fn reverse_booking_split(&self, booking_id: ObjectId) -> Result<(), AppError> {
+        self.reverse_split(booking_id).await?;
+        self.reverse_booking_in_ledger(booking_id).await;
+        Ok(())
+    }
+
+    async fn reverse_split(&self, booking_id: ObjectId) -> Result<(), AppError> {
+        let collection = self.get_booking_splits_collection();
+
+        // Not yet settled: simply drop it from the next batch
+        let dropped = collection.delete_one(
+            doc! { "booking_id": booking_id, "reversal": false, "settlement_id": bson::Bson::Null },
+            None
+        ).await?;
+        if dropped.deleted_count > 0 {
+            return Ok(());
+        }
+
+        let Some(split) = collection.find_one(doc! { "booking_id": booking_id, "reversal": false }, None).await? else {
+            return Ok(());
+        };
+        if collection.count_documents(doc! { "booking_id": booking_id, "reversal": true }, None).await? > 0 {
+            return Ok(());
+        }
+        let reversal = BookingSplit {
+            id: None,
+            fare: -split.fare,
+            commission: -split.commission,
+            operator_share: -split.operator_share,
+            reversal: true,
+            settlement_id: None,
+            recorded_at: bson::DateTime::now(),
+            ..split
+        };
+        collection.insert_one(reversal, None).await?;
+        Ok(())
+    }
+
+    /// Batches every unsettled split with a travel date in the period into one settlement per operator.
+    pub async fn generate_settlements(&self, period_start: &str, period_end: &str) -> Result<Vec<Settlement>, AppError> {
+        for date in [period_start, period_end] {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| "Dates must be in YYYY-MM-DD format")?;
+        }
+        if period_start > period_end {
+            return Err("period_start must not be after period_end".into());
+        }
+        // Two runs at once would both pick up the same unsettled splits
+        let _guard = self.try_lock("settlements:generate", Duration::from_secs(300)).await?
+            .ok_or_else(|| AppError::conflict("Settlements are already being generated, try again shortly"))?;
+
+        let splits_collection = self.get_booking_splits_collection();
+        let mut cursor = splits_collection.find(
+            doc! {
+                "settlement_id": bson::Bson::Null,
+                "travel_date": { "$gte": period_start, "$lte": period_end },
+            },
+            None
+        ).await?;
+
+        let mut by_operator: BTreeMap<String, Vec<BookingSplit>> = BTreeMap::new();
+        while let Some(result) = cursor.next().await {
+            let split = result?;
+            by_operator.entry(split.operator.clone()).or_default().push(split);
+        }
+
+        let mut settlements = Vec::new();
+        for (operator, splits) in by_operator {
+            let split_ids: Vec<ObjectId> = splits.iter().filter_map(|s| s.id).collect();
+            let mut settlement = Settlement {
+                id: None,
+                operator,
+                period_start: period_start.to_string(),
+                period_end: period_end.to_string(),
+                booking_count: 0,
+                gross: 0.0,
+                commission: 0.0,
+                net_payout: 0.0,
+                status: "Pending".to_string(),
+                payout_reference: None,
+                created_at: bson::DateTime::now(),
+                paid_at: None,
+            };
+            settlement.set_totals(&splits);
+            let result = self.get_settlements_collection().insert_one(&settlement, None).await?;
+            let settlement_id = result.inserted_id.as_object_id().ok_or_else(|| AppError::internal("Failed to create settlement"))?;
+            let claimed = splits_collection.update_many(
+                doc! { "_id": { "$in": &split_ids }, "settlement_id": bson::Bson::Null },
+                doc! { "$set": { "settlement_id": settlement_id } },
+                None
+            ).await?;
+
+            // A split reversed away since it was read isn't in this settlement, so the totals come
+            // from what was actually claimed
+            if claimed.modified_count as usize != split_ids.len() {
+                let mut cursor = splits_collection.find(doc! { "settlement_id": settlement_id }, None).await?;
+                let mut claimed_splits = Vec::new();
+                while let Some(result) = cursor.next().await {
+                    claimed_splits.push(result?);
+                }
+                if claimed_splits.is_empty() {
+                    self.get_settlements_collection().delete_one(doc! { "_id": settlement_id }, None).await?;
+                    continue;
+                }
+                settlement.set_totals(&claimed_splits);
+                self.get_settlements_collection().update_one(
+                    doc! { "_id": settlement_id },
+                    doc! { "$set": {
+                        "booking_count": settlement.booking_count,
+                        "gross": settlement.gross,
+                        "commission": settlement.commission,
+                        "net_payout": settlement.net_payout,
+                    } },
+                    None
+                ).await?;
+            }
+
+            info!(
+                "Settlement {} for {}: KES {:.2} net over {} booking(s)",
+                settlement_id, settlement.operator, settlement.net_payout, settlement.booking_count
+            );
+            settlement.id = Some(settlement_id);
+            settlements.push(settlement);
+        }
+        Ok(settlements)
+    }
+
+    pub async fn get_settlements(&self, operator: Option<&str>, status: Option<&str>) -> Result<Vec<Settlement>, AppError> {
+        let mut filter = Document::new();
+        if let Some(operator) = operator {
+            filter.insert("operator", operator);
+        }
+        if let Some(status) = status {
+            filter.insert("status", status);
+        }
+
+        let find_options = FindOptions::builder().sort(doc! { "period_end": -1, "created_at": -1 }).build();
+        let mut cursor = self.get_settlements_collection().find(filter, find_options).await?;
+
+        let mut settlements = Vec::new();
+        while let Some(result) = cursor.next().await {
+            settlements.push(result?);
+        }
+        Ok(settlements)
+    }
+
+    pub async fn mark_settlement_paid(&self, settlement_id: &str, payout_reference: &str) -> Result<Settlement, AppError> {
+        let settlement_oid = self.string_to_id(settlement_id)?;
+        let options = mongodb::options::FindOneAndUpdateOptions::builder()
+            .return_document(mongodb::options::ReturnDocument::After)
+            .build();
+        let settlement = self.get_settlements_collection().find_one_and_update(
+            doc! { "_id": settlement_oid, "status": "Pending" },
+            doc! { "$set": {
+                "status": "Paid",
+                "payout_reference": payout_reference,
+                "paid_at": bson::DateTime::now(),
+            } },
+            options
+        ).await?;
+
+        let settlement = settlement.ok_or_else(|| AppError::not_found("Settlement not found or already paid"))?;
+        self.post_settlement_to_ledger(&settlement).await;
+        Ok(settlement)
+    }
+
+    pub async fn get_operator_statement(&self, operator: &str) -> Result<OperatorStatement, AppError> {
+        let mut cursor = self.get_booking_splits_collection().find(
+            doc! { "operator": operator, "settlement_id": bson::Bson::Null },
+            None
+        ).await?;
+
+        let mut unsettled = UnsettledTotals::default();
+        while let Some(result) = cursor.next().await {
+            let split = result?;
+            unsettled.booking_count += if split.reversal { -1 } else { 1 };
+            unsettled.gross += split.fare;
+            unsettled.commission += split.commission;
+            unsettled.net_payout += split.operator_share;
+        }
+        unsettled.gross = round_money(unsettled.gross);
+        unsettled.commission = round_money(unsettled.commission);
+        unsettled.net_payout = round_money(unsettled.net_payout);
+
+        let settlements = self.get_settlements(Some(operator), None).await?;
+        let total_paid = round_money(
+            settlements.iter().filter(|s| s.status == "Paid").map(|s| s.net_payout).sum()
+        );
+
+        Ok(OperatorStatement {
+            operator: operator.to_string(),
+            unsettled,
+            settlements: settlements.into_iter().map(SettlementResponse::from).collect(),
+            total_paid,
+        })
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, FixedOffset};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::booking::{ShareClaims, ShareLinkResponse, SharedTripResponse};
+use crate::models::bus::{local_offset, Route};
+
+const SHARE_SCOPE: &str = "booking:share";
+
+/// When a share link for a trip on `travel_date` stops working: the end of the travel date, or
+/// arrival for overnight trips.
+pub(crate) fn share_link_expiry(route: &Route, travel_date: &str) -> Result<DateTime<FixedOffset>, AppError> {
+    let end_of_day = chrono::NaiveDate::parse_from_str(travel_date, "%Y-%m-%d")?
+        .succ_opt()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(|d| d.and_local_timezone(local_offset()).single())
+        .ok_or("Invalid travel date")?;
+    Ok(match route.arrival_at(travel_date) {
+        Some(arrival) if arrival > end_of_day => arrival,
+        _ => end_of_day,
+    })
+}
+
+pub(crate) fn sign_share_token(booking_id: &str, expires_at: i64) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = ShareClaims {
+        booking_id: booking_id.to_string(),
+        scope: SHARE_SCOPE.to_string(),
+        exp: expires_at as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref()))
+}
+
+/// The booking a share token is for; other tokens signed with the same secret are refused.
+pub(crate) fn verify_share_token(token: &str) -> Result<String, AppError> {
+    let claims = decode::<ShareClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &Validation::new(Algorithm::HS256),
+    )?.claims;
+    if claims.scope != SHARE_SCOPE {
+        return Err(AppError::unauthorized("Invalid share link"));
+    }
+    Ok(claims.booking_id)
+}
+
+impl MongoDB {
+    pub async fn create_share_link(&self, booking_id: &str, user_id: &str) -> Result<ShareLinkResponse, AppError> {
+        let booking = self.get_user_booking(booking_id, user_id).await?
+            .ok_or_else(|| AppError::not_found("Booking not found"))?;
+        if booking.status == "Cancelled" {
+            return Err("Cancelled bookings cannot be shared".into());
+        }
+
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let expires_at = share_link_expiry(&bus.route, &booking.travel_date)?;
+        if expires_at <= chrono::Utc::now() {
+            return Err(AppError::conflict("This trip has already taken place"));
+        }
+
+        let token = sign_share_token(booking_id, expires_at.timestamp())?;
+
+        let base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+        Ok(ShareLinkResponse {
+            url: format!("{}/api/bookings/shared/{}", base_url.trim_end_matches('/'), token),
+            token,
+            expires_at: expires_at.to_rfc3339(),
+        })
+    }
+
+    pub async fn get_shared_trip(&self, token: &str) -> Result<Option<SharedTripResponse>, AppError> {
+        let booking_id = verify_share_token(token)?;
+        let booking = match self.get_booking(&booking_id).await? {
+            Some(booking) if booking.status != "Cancelled" => booking,
+            _ => return Ok(None),
+        };
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        Ok(Some(SharedTripResponse {
+            departure: bus.route.departure_at(&booking.travel_date).map(|d| d.to_rfc3339()),
+            arrival: bus.route.arrival_at(&booking.travel_date).map(|d| d.to_rfc3339()),
+            bus_number: bus.bus_number,
+            bus_type: bus.bus_type,
+            boarding_point: bus.route.from.clone(),
+            from: bus.route.from,
+            to: bus.route.to,
+            travel_date: booking.travel_date,
+            seat_number: booking.seat_number,
+            status: booking.status.to_lowercase(),
+        }))
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::ReplaceOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::ReadWorkload;
+use crate::models::stats::PublicStats;
+use crate::models::Booking;
+
+const PUBLIC_STATS_ID: &str = "public";
+
+impl MongoDB {
+    fn get_stats_collection(&self) -> Collection<PublicStats> {
+        self.database().collection("stats")
+    }
+
+    pub async fn get_public_stats(&self) -> Result<Option<PublicStats>, AppError> {
+        Ok(self.get_stats_collection().find_one(doc! { "_id": PUBLIC_STATS_ID }, None).await?)
+    }
+
+    /// Aggregates the marketing counters and stores them as a single snapshot document.
+    pub async fn refresh_public_stats(&self) -> Result<PublicStats, AppError> {
+        let mut routes = HashSet::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            routes.insert((bus.route.from, bus.route.to));
+        }
+
+        let bookings = self.collection_for::<Booking>("bookings", ReadWorkload::Analytics);
+        let seats_booked = bookings
+            .count_documents(doc! { "status": { "$ne": "Cancelled" } }, None)
+            .await?;
+
+        // A trip is one bus departing on one date; travel dates are stored as YYYY-MM-DD
+        let month_prefix = chrono::Utc::now().format("%Y-%m").to_string();
+        let pipeline = vec![
+            doc! { "$match": {
+                "status": { "$ne": "Cancelled" },
+                "travel_date": { "$regex": format!("^{}", month_prefix) },
+            } },
+            doc! { "$group": { "_id": { "bus_id": "$bus_id", "travel_date": "$travel_date" } } },
+            doc! { "$count": "trips" },
+        ];
+        let mut trips = bookings.aggregate(pipeline, None).await?;
+        let trips_this_month = match trips.next().await {
+            Some(result) => result?.get_i32("trips").unwrap_or(0) as u64,
+            None => 0,
+        };
+
+        let stats = PublicStats {
+            id: PUBLIC_STATS_ID.to_string(),
+            routes_served: routes.len() as u64,
+            trips_this_month,
+            seats_booked,
+            computed_at: bson::DateTime::now(),
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.get_stats_collection().replace_one(doc! { "_id": PUBLIC_STATS_ID }, &stats, options).await?;
+        Ok(stats)
+    }
+}
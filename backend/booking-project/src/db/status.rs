@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use log::warn;
+use mongodb::{
+    bson::{self, doc},
+    options::UpdateOptions,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::MonitoringConfig;
+use crate::models::monitoring::{PAYMENT_CONFIRMED, PAYMENT_INITIATED};
+use crate::models::status::{ComponentStatus, DependencyCheck, JobHeartbeat};
+
+// A job this far behind is treated as stopped rather than slow
+const JOB_OUTAGE_LAG_SECS: i64 = 15 * 60;
+
+// Well inside the probe timeouts orchestrators default to, so a hung ping reads as down, not as a timeout
+const READINESS_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl MongoDB {
+    fn get_job_heartbeats_collection(&self) -> Collection<JobHeartbeat> {
+        self.database().collection("job_heartbeats")
+    }
+
+    /// Called by each background job when it runs, so scheduler lag can be measured.
+    pub async fn record_job_heartbeat(&self, job: &str, interval_secs: u64) -> Result<(), AppError> {
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.get_job_heartbeats_collection().update_one(
+            doc! { "_id": job },
+            doc! { "$set": { "interval_secs": interval_secs as i64, "last_run_at": bson::DateTime::now() } },
+            options
+        ).await?;
+        Ok(())
+    }
+
+    /// Pings each dependency the API can't serve without, for the readiness probe.
+    pub async fn check_dependencies(&self) -> Vec<DependencyCheck> {
+        let started = Instant::now();
+        let ping = tokio::time::timeout(READINESS_PING_TIMEOUT, self.database().run_command(doc! { "ping": 1 }, None)).await;
+        let status = match ping {
+            Ok(Ok(_)) => "up",
+            Ok(Err(e)) => {
+                warn!("Readiness: MongoDB ping failed: {}", e);
+                "down"
+            }
+            Err(_) => {
+                warn!("Readiness: MongoDB ping timed out after {:?}", READINESS_PING_TIMEOUT);
+                "down"
+            }
+        };
+        vec![DependencyCheck { name: "mongodb", status, latency_ms: started.elapsed().as_millis() as u64 }]
+    }
+
+    /// Health of each subsystem a customer would notice, for the public status page.
+    pub async fn get_system_status(&self) -> Vec<ComponentStatus> {
+        let config = MonitoringConfig::from_env();
+        let since = bson::DateTime::from_millis(
+            (chrono::Utc::now() - chrono::Duration::minutes(config.window_minutes)).timestamp_millis(),
+        );
+
+        let database = match self.database().run_command(doc! { "ping": 1 }, None).await {
+            Ok(_) => "operational",
+            Err(_) => "outage",
+        };
+        let unknown = |r: Result<&'static str, AppError>| r.unwrap_or("unknown");
+
+        vec![
+            ComponentStatus { name: "Database", status: database },
+            ComponentStatus { name: "Payments", status: unknown(self.payments_status(&config, since).await) },
+            ComponentStatus { name: "SMS notifications", status: unknown(self.channel_status("sms", &config, since).await) },
+            ComponentStatus { name: "Email notifications", status: unknown(self.channel_status("email", &config, since).await) },
+            ComponentStatus { name: "Background processing", status: unknown(self.jobs_status().await) },
+        ]
+    }
+
+    // Same drop-off signal the anomaly monitor alerts on
+    async fn payments_status(&self, config: &MonitoringConfig, since: bson::DateTime) -> Result<&'static str, AppError> {
+        let initiated = self.count_monitoring_events(PAYMENT_INITIATED, since).await?;
+        let confirmed = self.count_monitoring_events(PAYMENT_CONFIRMED, since).await?;
+        if initiated < config.min_sample {
+            return Ok("operational");
+        }
+        let dropoff = 1.0 - confirmed.min(initiated) as f64 / initiated as f64;
+        Ok(if confirmed == 0 {
+            "outage"
+        } else if dropoff > config.payment_dropoff_ratio {
+            "degraded"
+        } else {
+            "operational"
+        })
+    }
+
+    // Outbox deliveries in the window against messages that are retrying or were dead-lettered
+    async fn channel_status(&self, channel: &str, config: &MonitoringConfig, since: bson::DateTime) -> Result<&'static str, AppError> {
+        let (delivered, failing) = self.get_channel_delivery_counts(channel, since).await?;
+        if failing + delivered < config.min_sample {
+            return Ok("operational");
+        }
+        Ok(if delivered == 0 {
+            "outage"
+        } else if failing as f64 / (failing + delivered) as f64 > config.booking_failure_ratio {
+            "degraded"
+        } else {
+            "operational"
+        })
+    }
+
+    async fn jobs_status(&self) -> Result<&'static str, AppError> {
+        let now = chrono::Utc::now();
+        let mut worst_lag = 0;
+        let mut cursor = self.get_job_heartbeats_collection().find(None, None).await?;
+        let mut late = false;
+        while let Some(result) = cursor.next().await {
+            let heartbeat = result?;
+            let lag = heartbeat.lag_secs(now);
+            // One missed tick is noise; two in a row means the scheduler is falling behind
+            late |= lag > heartbeat.interval_secs;
+            worst_lag = worst_lag.max(lag);
+        }
+        Ok(if worst_lag > JOB_OUTAGE_LAG_SECS {
+            "outage"
+        } else if late {
+            "degraded"
+        } else {
+            "operational"
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use futures::StreamExt;
+use mongodb::bson::{oid::ObjectId, Document};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::tenancy::Tenant;
+
+impl MongoDB {
+    pub async fn operator_bus_ids(&self, operator: &str) -> Result<Vec<ObjectId>, AppError> {
+        let mut ids = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if bus.operator_name().eq_ignore_ascii_case(operator) {
+                ids.extend(bus.id);
+            }
+        }
+        Ok(ids)
+    }
+
+    pub async fn scope_bookings_filter(&self, tenant: &Tenant, filter: Document) -> Result<Document, AppError> {
+        let own_bus_ids = match tenant {
+            Tenant::Platform => Vec::new(),
+            Tenant::Operator(operator) => self.operator_bus_ids(operator).await?,
+        };
+        Ok(tenant.scope_bookings(filter, &own_bus_ids))
+    }
+
+    // Another operator's records read as missing rather than forbidden, so ids can't be probed
+    pub async fn ensure_bus_owned(&self, tenant: &Tenant, bus_id: &str) -> Result<(), AppError> {
+        if *tenant == Tenant::Platform {
+            return Ok(());
+        }
+        match self.get_bus(bus_id).await? {
+            Some(bus) if tenant.owns_bus(&bus) => Ok(()),
+            _ => Err(AppError::not_found("Bus not found")),
+        }
+    }
+
+    pub async fn ensure_booking_owned(&self, tenant: &Tenant, booking_id: &str) -> Result<(), AppError> {
+        if *tenant == Tenant::Platform {
+            return Ok(());
+        }
+        let booking = self.get_booking(booking_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        match self.get_bus(&booking.bus_id.to_hex()).await? {
+            Some(bus) if tenant.owns_bus(&bus) => Ok(()),
+            _ => Err(AppError::not_found("Booking not found")),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use futures::StreamExt;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+use crate::models::terminal::{terminal_id, DepartureBoardEntry, DepartureBoardResponse, Terminal};
+
+// Buses open for boarding this long before departure
+const BOARDING_WINDOW_MINUTES: i64 = 30;
+
+impl MongoDB {
+    pub async fn get_terminals(&self) -> Result<Vec<Terminal>, AppError> {
+        let mut terminals = BTreeMap::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            terminals.insert(terminal_id(&bus.route.from), bus.route.from);
+        }
+
+        Ok(terminals.into_iter().map(|(id, name)| Terminal { id, name }).collect())
+    }
+
+    pub async fn get_departure_board(&self, terminal: &str, travel_date: &str) -> Result<Option<DepartureBoardResponse>, AppError> {
+        let mut buses = Vec::new();
+        let mut cursor = self.get_buses().await?;
+        while let Some(bus) = cursor.next().await {
+            let bus = bus?;
+            if terminal_id(&bus.route.from) == terminal {
+                buses.push(bus);
+            }
+        }
+        let Some(name) = buses.first().map(|b| b.route.from.clone()) else {
+            return Ok(None);
+        };
+
+        let bus_ids: Vec<_> = buses.iter().filter_map(|b| b.id).collect();
+        let statuses = self.get_trip_statuses(&bus_ids, travel_date).await?;
+        let now = chrono::Utc::now().with_timezone(&local_offset());
+
+        let mut departures = Vec::new();
+        for bus in buses {
+            let Some(scheduled) = bus.route.departure_at(travel_date) else {
+                continue;
+            };
+            let trip_status = bus.id.and_then(|id| statuses.get(&id));
+            let delay_minutes = trip_status.map(|s| s.delay_minutes).unwrap_or(0);
+            let expected = scheduled + chrono::Duration::minutes(delay_minutes);
+
+            // Explicit operator updates win; otherwise the status follows the clock
+            let status = match trip_status.map(|s| s.status.as_str()) {
+                Some(explicit @ ("cancelled" | "departed" | "boarding")) => explicit.to_string(),
+                _ if now >= expected => "departed".to_string(),
+                _ if now >= expected - chrono::Duration::minutes(BOARDING_WINDOW_MINUTES) => "boarding".to_string(),
+                _ if delay_minutes > 0 => "delayed".to_string(),
+                _ => "scheduled".to_string(),
+            };
+
+            departures.push(DepartureBoardEntry {
+                bus_id: bus.id.map(|id| id.to_hex()).unwrap_or_default(),
+                bus_number: bus.bus_number,
+                bus_type: bus.bus_type,
+                destination: bus.route.to,
+                scheduled_departure: scheduled.to_rfc3339(),
+                expected_departure: expected.to_rfc3339(),
+                status,
+                delay_minutes,
+                note: trip_status.and_then(|s| s.note.clone()),
+            });
+        }
+        departures.sort_by(|a, b| a.expected_departure.cmp(&b.expected_departure));
+
+        Ok(Some(DepartureBoardResponse {
+            terminal: Terminal { id: terminal.to_string(), name },
+            date: travel_date.to_string(),
+            generated_at: now.to_rfc3339(),
+            departures,
+        }))
+    }
+}
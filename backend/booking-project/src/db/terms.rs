@@ -0,0 +1,52 @@
+use mongodb::bson::{self, doc};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::TermsConfig;
+use crate::models::terms::TermsAcceptance;
+
+impl MongoDB {
+    pub async fn get_accepted_terms_version(&self, user_id: &str) -> Result<Option<String>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let user = self.get_users_collection().find_one(doc! { "_id": user_oid }, None).await?;
+        Ok(user.and_then(|u| u.terms_acceptances.last().map(|a| a.version.clone())))
+    }
+
+    pub async fn accept_terms(&self, user_id: &str, version: &str, context: &str) -> Result<(), AppError> {
+        let current = TermsConfig::from_env().version;
+        if version != current {
+            return Err(format!("Terms version {} is out of date; the current version is {}", version, current).into());
+        }
+
+        let acceptance = TermsAcceptance {
+            version: version.to_string(),
+            context: context.to_string(),
+            accepted_at: bson::DateTime::now(),
+        };
+        let user_oid = self.string_to_id(user_id)?;
+        let result = self.get_users_collection().update_one(
+            doc! { "_id": user_oid },
+            doc! { "$push": { "terms_acceptances": bson::to_bson(&acceptance)? } },
+            None
+        ).await?;
+        if result.matched_count == 0 {
+            return Err(AppError::not_found("User not found"));
+        }
+        Ok(())
+    }
+
+    /// True when the user is on the current terms, recording `offered` first if it is the current version.
+    pub async fn ensure_terms_accepted(&self, user_id: &str, offered: Option<&str>, context: &str) -> Result<bool, AppError> {
+        let current = TermsConfig::from_env().version;
+        if self.get_accepted_terms_version(user_id).await?.as_deref() == Some(current.as_str()) {
+            return Ok(true);
+        }
+        match offered {
+            Some(version) if version == current => {
+                self.accept_terms(user_id, version, context).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use mongodb::bson::doc;
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::TicketConfig;
+use crate::models::manifest::TripAccessClaims;
+use crate::models::ticket::{sign_ticket, ETicket, ScannedTicketResponse, TicketClaims};
+
+impl MongoDB {
+    /// The e-ticket for one of the user's confirmed bookings, worded like its receipt.
+    pub async fn get_booking_ticket(&self, booking_id: &str, user_id: &str, requested: Option<&str>) -> Result<ETicket, AppError> {
+        let booking = self.get_user_booking(booking_id, user_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        if booking.status != "Confirmed" {
+            return Err("Only confirmed bookings have a ticket".into());
+        }
+        let claims = TicketClaims {
+            booking_id: booking.id.map(|id| id.to_hex()).unwrap_or_default(),
+            bus_id: booking.bus_id.to_hex(),
+            travel_date: booking.travel_date.clone(),
+            seat_number: booking.seat_number.clone(),
+        };
+        Ok(ETicket {
+            receipt: self.booking_receipt(&booking, requested).await?,
+            qr_payload: sign_ticket(&claims, &TicketConfig::from_env().signing_key),
+        })
+    }
+
+    /// Checks in the passenger on a scanned ticket whose signature already checked out.
+    pub async fn scan_ticket(&self, access: &TripAccessClaims, ticket: &TicketClaims) -> Result<ScannedTicketResponse, AppError> {
+        let booking_oid = self.string_to_id(&ticket.booking_id)?;
+        let booking = self.get_bookings_collection()
+            .find_one(doc! { "_id": booking_oid }, None).await?
+            .ok_or_else(|| AppError::not_found("Booking not found"))?;
+        // A passenger moved since the ticket was issued must board with the new one
+        if booking.seat_number != ticket.seat_number {
+            return Err(format!("This ticket is out of date, the passenger is now in seat {}", booking.seat_number).into());
+        }
+        self.check_in_passenger(access, &ticket.booking_id).await?;
+        Ok(ScannedTicketResponse {
+            booking_id: ticket.booking_id.clone(),
+            passenger_name: booking.passenger.map(|p| p.name).unwrap_or_default(),
+            seat_number: booking.seat_number,
+        })
+    }
+}
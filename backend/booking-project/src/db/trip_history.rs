@@ -0,0 +1,221 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::locks::is_duplicate_key;
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::{local_offset, Bus};
+use crate::models::fare::quote_fare;
+use crate::models::schedule::bus_runs_on;
+use crate::models::trip_history::{
+    rating_prompt, PastTripResponse, RateTripRequest, RebookDate, RebookQuote, TripRating, MAX_HISTORY_PAGE,
+};
+use crate::models::Booking;
+
+// How far ahead the rebook shortcut looks, and how many dates it offers
+const REBOOK_SEARCH_DAYS: i64 = 30;
+const REBOOK_DATES: usize = 5;
+
+fn to_utc(time: chrono::DateTime<chrono::FixedOffset>) -> chrono::DateTime<chrono::Utc> {
+    time.with_timezone(&chrono::Utc)
+}
+
+impl MongoDB {
+    fn get_trip_ratings_collection(&self) -> Collection<TripRating> {
+        self.database().collection("trip_ratings")
+    }
+
+    pub async fn ensure_trip_rating_indexes(&self) -> Result<(), AppError> {
+        let ratings = self.get_trip_ratings_collection();
+        ratings.create_index(
+            IndexModel::builder()
+                .keys(doc! { "booking_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None
+        ).await?;
+        ratings.create_index(IndexModel::builder().keys(doc! { "operator": 1 }).build(), None).await?;
+        Ok(())
+    }
+
+    /// When the trip on a booking ended, or None if it hasn't: it arrived, or its scheduled
+    /// arrival has passed. Cancelled trips never end.
+    async fn trip_ended_at(&self, booking: &Booking, bus: &Bus) -> Result<Option<chrono::DateTime<chrono::Utc>>, AppError> {
+        let status = self.get_trip_status_collection()
+            .find_one(doc! { "bus_id": booking.bus_id, "travel_date": &booking.travel_date }, None)
+            .await?;
+        let now = chrono::Utc::now();
+        let ended_at = match status {
+            Some(s) if s.status == "cancelled" => None,
+            Some(s) if s.status == "arrived" => s.actual_arrival
+                .and_then(|a| chrono::DateTime::from_timestamp_millis(a.timestamp_millis()))
+                .or_else(|| bus.route.arrival_at(&booking.travel_date).map(to_utc)),
+            Some(s) => bus.route.arrival_at(&booking.travel_date)
+                .map(|a| to_utc(a) + chrono::Duration::minutes(s.delay_minutes)),
+            None => bus.route.arrival_at(&booking.travel_date).map(to_utc),
+        };
+        Ok(ended_at.filter(|at| *at <= now))
+    }
+
+    /// The user's completed trips, latest first, with whether to ask them for a rating.
+    pub async fn get_trip_history(&self, user_id: &str, limit: Option<i64>) -> Result<Vec<PastTripResponse>, AppError> {
+        let user_oid = self.string_to_id(user_id)?;
+        let limit = limit.unwrap_or(20).clamp(1, MAX_HISTORY_PAGE) as usize;
+        let today = chrono::Utc::now().with_timezone(&local_offset()).format("%Y-%m-%d").to_string();
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "user_id": user_oid, "status": "Confirmed", "travel_date": { "$lte": today } },
+            FindOptions::builder().sort(doc! { "travel_date": -1, "_id": -1 }).build()
+        ).await?;
+
+        let mut buses: HashMap<ObjectId, Option<Bus>> = HashMap::new();
+        let mut completed = Vec::new();
+        while let Some(booking) = cursor.next().await {
+            let booking = booking?;
+            if let Entry::Vacant(entry) = buses.entry(booking.bus_id) {
+                entry.insert(self.get_bus(&booking.bus_id.to_hex()).await?);
+            }
+            let Some(Some(bus)) = buses.get(&booking.bus_id) else { continue };
+            if let Some(ended_at) = self.trip_ended_at(&booking, bus).await? {
+                completed.push((booking, bus.clone(), ended_at));
+            }
+            if completed.len() == limit {
+                break;
+            }
+        }
+
+        let booking_ids: Vec<ObjectId> = completed.iter().filter_map(|(b, _, _)| b.id).collect();
+        let mut ratings = HashMap::new();
+        let mut cursor = self.get_trip_ratings_collection().find(doc! { "booking_id": { "$in": booking_ids } }, None).await?;
+        while let Some(rating) = cursor.next().await {
+            let rating = rating?;
+            ratings.insert(rating.booking_id, rating.score);
+        }
+
+        let now = chrono::Utc::now();
+        Ok(completed.into_iter().map(|(booking, bus, ended_at)| {
+            let rating = booking.id.and_then(|id| ratings.get(&id).copied());
+            PastTripResponse {
+                booking_id: booking.id.map(|id| id.to_hex()).unwrap_or_default(),
+                bus_id: booking.bus_id.to_hex(),
+                operator: bus.operator_name(),
+                departure: bus.route.departure_at(&booking.travel_date).map(|d| d.to_rfc3339()),
+                arrival: bus.route.arrival_at(&booking.travel_date).map(|a| a.to_rfc3339()),
+                bus_number: bus.bus_number,
+                from: bus.route.from,
+                to: bus.route.to,
+                travel_date: booking.travel_date,
+                seat_number: booking.seat_number,
+                rating_prompt: rating_prompt(rating.is_some(), ended_at, now).to_string(),
+                rating,
+            }
+        }).collect())
+    }
+
+    /// Rates the operator of a completed trip, once per booking and only while the prompt is open.
+    pub async fn rate_trip(&self, user_id: &str, booking_id: &str, req: &RateTripRequest) -> Result<TripRating, AppError> {
+        req.validate()?;
+        let booking = self.get_user_booking(booking_id, user_id).await?
+            .filter(|b| b.status == "Confirmed")
+            .ok_or_else(|| AppError::not_found("Booking not found"))?;
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let ended_at = self.trip_ended_at(&booking, &bus).await?.ok_or("This trip hasn't finished yet")?;
+        if rating_prompt(false, ended_at, chrono::Utc::now()) == "expired" {
+            return Err("Ratings close two weeks after the trip".into());
+        }
+
+        let mut rating = TripRating {
+            id: None,
+            booking_id: booking.id.ok_or_else(|| AppError::not_found("Booking not found"))?,
+            user_id: booking.user_id,
+            bus_id: booking.bus_id,
+            operator: bus.operator_name(),
+            score: req.score,
+            comment: req.comment.as_deref().map(str::trim).filter(|c| !c.is_empty()).map(str::to_string),
+            created_at: bson::DateTime::now(),
+        };
+        match self.get_trip_ratings_collection().insert_one(&rating, None).await {
+            Ok(result) => rating.id = result.inserted_id.as_object_id(),
+            Err(e) if is_duplicate_key(&e) => return Err(AppError::conflict("You have already rated this trip")),
+            Err(e) => return Err(e.into()),
+        }
+        Ok(rating)
+    }
+
+    /// Mean score per operator, for the route comparison.
+    pub async fn get_operator_ratings(&self) -> Result<HashMap<String, f64>, AppError> {
+        let mut cursor = self.get_trip_ratings_collection().aggregate(vec![
+            doc! { "$group": { "_id": "$operator", "average": { "$avg": "$score" } } },
+        ], None).await?;
+        let mut ratings = HashMap::new();
+        while let Some(row) = cursor.next().await {
+            let row: Document = row?;
+            if let (Ok(operator), Ok(average)) = (row.get_str("_id"), row.get_f64("average")) {
+                ratings.insert(operator.to_string(), (average * 10.0).round() / 10.0);
+            }
+        }
+        Ok(ratings)
+    }
+
+    /// A fresh quote for the trip on an earlier booking, with the next dates it still has seats.
+    pub async fn rebook_quote(&self, user_id: &str, booking_id: &str) -> Result<RebookQuote, AppError> {
+        let booking = self.get_user_booking(booking_id, user_id).await?.ok_or_else(|| AppError::not_found("Booking not found"))?;
+        let bus = self.get_bus(&booking.bus_id.to_hex()).await?.ok_or("This bus no longer runs")?;
+        let bus_id = bus.id.ok_or("This bus no longer runs")?;
+        let table = self.get_fare_table(&bus_id.to_hex()).await?;
+        let fare = quote_fare(&bus, table.as_ref(), None, None)?;
+        let now = chrono::Utc::now();
+
+        // Rebooking a trip that hasn't left yet is a change of date, so its amendment terms apply
+        let mut amendment_fee = 0.0;
+        if booking.status != "Cancelled" {
+            if let Some(departure) = bus.route.departure_at(&booking.travel_date).filter(|d| *d > now) {
+                let amendment = self.resolve_policy(&bus, &booking.travel_date).await?.amendment;
+                if !amendment.accepts((departure.with_timezone(&chrono::Utc) - now).num_hours()) {
+                    return Err(format!("Bookings cannot be changed less than {} hours before departure", amendment.min_hours_before).into());
+                }
+                amendment_fee = amendment.fee;
+            }
+        }
+
+        let schedule = self.get_schedule(bus_id).await?;
+        let today = now.with_timezone(&local_offset()).date_naive();
+        let mut dates = Vec::new();
+        for offset in 0..REBOOK_SEARCH_DAYS {
+            let travel_date = (today + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+            let Some(departure) = bus.route.departure_at(&travel_date).filter(|d| *d > now) else { continue };
+            if !bus_runs_on(schedule.as_ref(), &travel_date) {
+                continue;
+            }
+            let cancelled = self.get_trip_statuses(&[bus_id], &travel_date).await?
+                .get(&bus_id)
+                .is_some_and(|s| s.status == "cancelled");
+            let seats_left = self.get_seats_left(&travel_date, Some(bus_id)).await?
+                .get(&bus_id)
+                .copied()
+                .unwrap_or(bus.total_seats);
+            if cancelled || seats_left <= 0 {
+                continue;
+            }
+            dates.push(RebookDate { travel_date, departure: departure.to_rfc3339(), seats_left });
+            if dates.len() == REBOOK_DATES {
+                break;
+            }
+        }
+
+        Ok(RebookQuote {
+            bus_id: bus_id.to_hex(),
+            bus_number: bus.bus_number,
+            fare,
+            amendment_fee,
+            seat_number: booking.seat_number,
+            passenger: booking.passenger,
+            dates,
+        })
+    }
+}
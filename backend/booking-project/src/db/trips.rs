@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId},
+    options::FindOneAndUpdateOptions,
+    options::ReturnDocument,
+    Collection,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::events::{DomainEvent, TripSnapshot};
+use crate::models::booking::BookingEvent;
+use crate::models::trip::{TripStatus, UpdateTripStatusRequest, TRIP_STATUSES};
+
+impl MongoDB {
+    pub(super) fn get_trip_status_collection(&self) -> Collection<TripStatus> {
+        self.database().collection("trip_status")
+    }
+
+    pub async fn get_trip_statuses(&self, bus_ids: &[ObjectId], travel_date: &str) -> Result<HashMap<ObjectId, TripStatus>, AppError> {
+        let mut cursor = self.get_trip_status_collection().find(
+            doc! { "bus_id": { "$in": bus_ids }, "travel_date": travel_date },
+            None
+        ).await?;
+
+        let mut statuses = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            let status = result?;
+            statuses.insert(status.bus_id, status);
+        }
+        Ok(statuses)
+    }
+
+    pub async fn get_completed_trip_statuses(&self, since: bson::DateTime) -> Result<Vec<TripStatus>, AppError> {
+        let mut cursor = self.get_trip_status_collection().find(
+            doc! { "actual_departure": { "$gte": since } },
+            None
+        ).await?;
+
+        let mut statuses = Vec::new();
+        while let Some(result) = cursor.next().await {
+            statuses.push(result?);
+        }
+        Ok(statuses)
+    }
+
+    pub async fn set_trip_status(&self, bus_id: &str, travel_date: &str, req: &UpdateTripStatusRequest) -> Result<TripStatus, AppError> {
+        if !TRIP_STATUSES.contains(&req.status.as_str()) {
+            return Err(format!("status must be one of: {}", TRIP_STATUSES.join(", ")).into());
+        }
+        let bus_oid = self.string_to_id(bus_id)?;
+        self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let delay_minutes = req.delay_minutes.unwrap_or(0).max(0);
+        let mut set = doc! {
+            "status": &req.status,
+            "delay_minutes": delay_minutes,
+            "note": &req.note,
+            "updated_at": bson::DateTime::now(),
+        };
+
+        // Departure/arrival updates double as the actuals used for punctuality
+        let actual_time = match &req.actual_time {
+            Some(time) => bson::DateTime::from_millis(chrono::DateTime::parse_from_rfc3339(time)?.timestamp_millis()),
+            None => bson::DateTime::now(),
+        };
+        match req.status.as_str() {
+            "departed" => { set.insert("actual_departure", actual_time); },
+            "arrived" => { set.insert("actual_arrival", actual_time); },
+            _ => {}
+        }
+
+        // Only the first departure is news; corrections to the time aren't published again
+        let already_departed = req.status == "departed" && self.get_trip_status_collection()
+            .find_one(doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": "departed" }, None)
+            .await?
+            .is_some();
+
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let status = self.get_trip_status_collection().find_one_and_update(
+            doc! { "bus_id": bus_oid, "travel_date": travel_date },
+            doc! { "$set": set },
+            options
+        ).await?.ok_or_else(|| AppError::internal("Failed to update trip status"))?;
+        self.update_next_trip_status(bus_oid, travel_date, &status.status, delay_minutes).await?;
+
+        if req.status == "departed" && !already_departed {
+            self.publish_event(DomainEvent::TripDeparted {
+                trip: TripSnapshot {
+                    bus_id: bus_oid.to_hex(),
+                    travel_date: travel_date.to_string(),
+                    departed_at: actual_time.try_to_rfc3339_string().unwrap_or_default(),
+                    delay_minutes,
+                },
+            }).await;
+        }
+
+        // Delays land on every affected passenger's booking timeline
+        if req.status == "delayed" {
+            let event = BookingEvent::new(
+                "delayed",
+                "admin",
+                Some(format!("Departure delayed by {} minutes", delay_minutes)),
+            );
+            self.get_bookings_collection().update_many(
+                doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": { "$ne": "Cancelled" } },
+                doc! { "$push": { "history": bson::to_bson(&event)? } },
+                None
+            ).await?;
+        }
+
+        Ok(status)
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+
+use futures::StreamExt;
+use mongodb::bson::{self, doc};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::config::InventoryConfig;
+use crate::models::booking::BookingEvent;
+use crate::models::bus::OPEN_SEATING;
+use crate::models::trip::{SeatMove, TripVehicle, VehicleSwapRequest, VehicleSwapResult};
+use crate::models::Booking;
+
+impl MongoDB {
+    /// Puts a different vehicle on one trip. Passengers keep their seat when it exists on the new
+    /// vehicle, are moved to the lowest free seat otherwise, and are flagged when nothing is left.
+    /// On trips sold by headcount the latest bookings past the new capacity are flagged.
+    pub async fn swap_trip_vehicle(&self, bus_id: &str, travel_date: &str, req: &VehicleSwapRequest) -> Result<VehicleSwapResult, AppError> {
+        if req.total_seats < 1 {
+            return Err("total_seats must be at least 1".into());
+        }
+        let bus = self.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        let bus_oid = bus.id.ok_or_else(|| AppError::not_found("Bus not found"))?;
+
+        let mut cursor = self.get_bookings_collection().find(
+            doc! { "bus_id": bus_oid, "travel_date": travel_date, "status": { "$ne": "Cancelled" } },
+            None
+        ).await?;
+        let mut bookings: Vec<Booking> = Vec::new();
+        while let Some(result) = cursor.next().await {
+            bookings.push(result?);
+        }
+        bookings.sort_by_key(|b| b.booking_date);
+
+        let vehicle = TripVehicle {
+            bus_number: req.bus_number.clone(),
+            total_seats: req.total_seats,
+            reason: req.reason.clone(),
+            swapped_at: bson::DateTime::now(),
+        };
+        self.set_trip_vehicle(bus_oid, travel_date, &vehicle).await?;
+
+        let mut moves = Vec::new();
+        let mut unplaced = Vec::new();
+        if InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) {
+            self.set_trip_capacity(&bus, travel_date, req.total_seats).await?;
+            unplaced.extend(bookings.iter().skip(req.total_seats as usize));
+        } else {
+            self.resize_trip_seats(&bus, travel_date, req.total_seats).await?;
+
+            // Earliest bookings get first pick of the remaining seats; each is claimed on its own,
+            // so a booking made while swapping just takes that seat out of the running
+            let fits = |seat: &str| seat.parse::<i32>().is_ok_and(|n| n >= 1 && n <= req.total_seats);
+            let taken: BTreeSet<i32> = bookings
+                .iter()
+                .filter(|b| fits(&b.seat_number))
+                .filter_map(|b| b.seat_number.parse().ok())
+                .collect();
+            let mut free = (1..=req.total_seats).filter(|n| !taken.contains(n));
+            for booking in bookings.iter().filter(|b| !fits(&b.seat_number)) {
+                let mut placed = None;
+                for seat in free.by_ref() {
+                    if self.reserve_seat(&bus, travel_date, &seat.to_string()).await? {
+                        placed = Some(seat);
+                        break;
+                    }
+                }
+                match placed {
+                    Some(seat) => moves.push((booking, seat.to_string())),
+                    None => unplaced.push(booking),
+                }
+            }
+        }
+        let seats_left = self.get_seats_left(travel_date, Some(bus_oid)).await?
+            .get(&bus_oid)
+            .copied()
+            .unwrap_or(req.total_seats);
+
+        let reason = req.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+        let mut moved = Vec::new();
+        for (booking, seat) in moves {
+            let Some(booking_oid) = booking.id else { continue };
+            let event = BookingEvent::new(
+                "reseated",
+                "admin",
+                Some(format!("Seat {} → {} after vehicle swap to {}{}", booking.seat_number, seat, req.bus_number, reason)),
+            );
+            self.get_bookings_collection().update_one(
+                doc! { "_id": booking_oid },
+                doc! { "$set": { "seat_number": &seat }, "$push": { "history": bson::to_bson(&event)? } },
+                None
+            ).await?;
+            self.notify_user(booking.user_id, "Your seat has changed", &format!(
+                "A different bus ({}) will run your {} to {} trip on {}. Your new seat is {}.",
+                req.bus_number, bus.route.from, bus.route.to, travel_date, seat
+            )).await?;
+            moved.push(SeatMove { booking_id: booking_oid.to_hex(), from_seat: booking.seat_number.clone(), to_seat: seat });
+        }
+
+        let mut unplaced_ids = Vec::new();
+        for booking in unplaced {
+            let Some(booking_oid) = booking.id else { continue };
+            let detail = if booking.seat_number == OPEN_SEATING {
+                format!("No place left on replacement vehicle {}{}", req.bus_number, reason)
+            } else {
+                format!("Seat {} does not exist on replacement vehicle {}{}", booking.seat_number, req.bus_number, reason)
+            };
+            let event = BookingEvent::new("needs_reseat", "system", Some(detail));
+            self.record_booking_event(booking_oid, event).await?;
+            self.notify_user(booking.user_id, "Your trip has changed", &format!(
+                "A smaller bus will run your {} to {} trip on {}. Our team will contact you to rebook you or arrange a refund.",
+                bus.route.from, bus.route.to, travel_date
+            )).await?;
+            unplaced_ids.push(booking_oid.to_hex());
+        }
+        if !unplaced_ids.is_empty() {
+            self.notify_admins(
+                "Bookings need reseating after vehicle swap",
+                &format!(
+                    "{} on {}: {} booking(s) could not be placed on {} — reseat or refund: {}",
+                    bus.bus_number, travel_date, unplaced_ids.len(), req.bus_number, unplaced_ids.join(", ")
+                ),
+            ).await?;
+        }
+        self.refresh_next_trips_for_trip(bus_oid, travel_date).await?;
+
+        Ok(VehicleSwapResult {
+            bus_number: req.bus_number.clone(),
+            total_seats: req.total_seats,
+            seats_left,
+            moved,
+            unplaced: unplaced_ids,
+        })
+    }
+}
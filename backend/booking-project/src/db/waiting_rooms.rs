@@ -0,0 +1,298 @@
+use futures::StreamExt;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::info;
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::tenancy::Tenant;
+use crate::models::waiting_room::{
+    CreateWaitingRoomRequest, QueuePosition, UpdateWaitingRoomRequest, WaitingRoom, WaitingRoomClaims, WaitingRoomEntry,
+    ADMISSION_SCOPE, QUEUE_SCOPE,
+};
+
+fn sign(claims: &WaitingRoomClaims) -> Result<String, jsonwebtoken::errors::Error> {
+    encode(&Header::default(), claims, &EncodingKey::from_secret(jwt_secret().as_ref()))
+}
+
+/// Decodes a waiting-room token and checks it carries the expected scope.
+pub fn verify_waiting_room_token(token: &str, scope: &str) -> Result<WaitingRoomClaims, AppError> {
+    let claims = decode::<WaitingRoomClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &Validation::new(Algorithm::HS256),
+    )?.claims;
+    if claims.scope != scope {
+        return Err("Invalid waiting room token".into());
+    }
+    Ok(claims)
+}
+
+fn parse_time(value: &str, field: &str) -> Result<bson::DateTime, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| bson::DateTime::from_millis(t.timestamp_millis()))
+        .map_err(|_| format!("{} must be an RFC 3339 timestamp", field))
+}
+
+impl MongoDB {
+    fn get_waiting_rooms_collection(&self) -> Collection<WaitingRoom> {
+        self.database().collection("waiting_rooms")
+    }
+
+    fn get_waiting_room_entries_collection(&self) -> Collection<WaitingRoomEntry> {
+        self.database().collection("waiting_room_entries")
+    }
+
+    pub async fn ensure_waiting_room_indexes(&self) -> Result<(), AppError> {
+        let index = IndexModel::builder()
+            .keys(doc! { "room_id": 1, "holder": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.get_waiting_room_entries_collection().create_index(index, None).await?;
+        Ok(())
+    }
+
+    pub async fn create_waiting_room(&self, tenant: &Tenant, created_by: &str, req: &CreateWaitingRoomRequest) -> Result<WaitingRoom, AppError> {
+        self.ensure_bus_owned(tenant, &req.bus_id).await?;
+        let bus_id = self.string_to_id(&req.bus_id)?;
+        chrono::NaiveDate::parse_from_str(&req.travel_date, "%Y-%m-%d").map_err(|_| "travel_date must be YYYY-MM-DD")?;
+        let opens_at = parse_time(&req.opens_at, "opens_at")?;
+        let closes_at = parse_time(&req.closes_at, "closes_at")?;
+        if closes_at <= opens_at {
+            return Err("closes_at must be after opens_at".into());
+        }
+        if req.admit_per_minute < 1 {
+            return Err("admit_per_minute must be at least 1".into());
+        }
+        let admission_minutes = req.admission_minutes.unwrap_or(10);
+        if !(1..=120).contains(&admission_minutes) {
+            return Err("admission_minutes must be between 1 and 120".into());
+        }
+
+        let collection = self.get_waiting_rooms_collection();
+        let existing = collection.find_one(
+            doc! { "bus_id": bus_id, "travel_date": &req.travel_date, "status": "active", "closes_at": { "$gt": bson::DateTime::now() } },
+            None
+        ).await?;
+        if existing.is_some() {
+            return Err(AppError::conflict("This trip already has an active waiting room"));
+        }
+
+        let mut room = WaitingRoom {
+            id: None,
+            bus_id,
+            travel_date: req.travel_date.clone(),
+            opens_at,
+            closes_at,
+            admit_per_minute: req.admit_per_minute,
+            admission_minutes,
+            admitted_base: 0,
+            rate_changed_at: opens_at,
+            last_position: 0,
+            status: "active".to_string(),
+            created_by: created_by.to_string(),
+            created_at: bson::DateTime::now(),
+        };
+        let result = collection.insert_one(&room, None).await?;
+        room.id = result.inserted_id.as_object_id();
+        info!("Waiting room opened for bus {} on {} at {}/min", req.bus_id, req.travel_date, req.admit_per_minute);
+        Ok(room)
+    }
+
+    pub async fn get_waiting_rooms(&self, tenant: &Tenant) -> Result<Vec<WaitingRoom>, AppError> {
+        let mut filter = doc! { "closes_at": { "$gt": bson::DateTime::now() } };
+        if let Tenant::Operator(operator) = tenant {
+            filter.insert("bus_id", doc! { "$in": self.operator_bus_ids(operator).await? });
+        }
+        let find_options = FindOptions::builder().sort(doc! { "opens_at": 1 }).build();
+        let mut cursor = self.get_waiting_rooms_collection().find(filter, find_options).await?;
+        let mut rooms = Vec::new();
+        while let Some(result) = cursor.next().await {
+            rooms.push(result?);
+        }
+        Ok(rooms)
+    }
+
+    /// Rooms the seat-selection guard must enforce right now.
+    pub async fn get_enforced_waiting_rooms(&self) -> Result<Vec<WaitingRoom>, AppError> {
+        let mut cursor = self.get_waiting_rooms_collection()
+            .find(doc! { "status": "active", "closes_at": { "$gt": bson::DateTime::now() } }, None)
+            .await?;
+        let mut rooms = Vec::new();
+        while let Some(result) = cursor.next().await {
+            rooms.push(result?);
+        }
+        Ok(rooms)
+    }
+
+    pub async fn get_trip_waiting_room(&self, bus_id: &str, travel_date: &str) -> Result<Option<WaitingRoom>, AppError> {
+        let bus_oid = self.string_to_id(bus_id)?;
+        let find_options = mongodb::options::FindOneOptions::builder().sort(doc! { "created_at": -1 }).build();
+        Ok(self.get_waiting_rooms_collection()
+            .find_one(doc! { "bus_id": bus_oid, "travel_date": travel_date, "closes_at": { "$gt": bson::DateTime::now() } }, find_options)
+            .await?)
+    }
+
+    /// Changes the admission rate or closes the room; positions already admitted stay admitted.
+    pub async fn update_waiting_room(&self, tenant: &Tenant, room_id: &str, req: &UpdateWaitingRoomRequest) -> Result<WaitingRoom, AppError> {
+        let id = self.string_to_id(room_id)?;
+        let collection = self.get_waiting_rooms_collection();
+        let room = collection.find_one(doc! { "_id": id }, None).await?.ok_or_else(|| AppError::not_found("Waiting room not found"))?;
+        self.ensure_bus_owned(tenant, &room.bus_id.to_hex()).await.map_err(|_| AppError::not_found("Waiting room not found"))?;
+
+        let mut update = doc! {};
+        if let Some(rate) = req.admit_per_minute {
+            if rate < 1 {
+                return Err("admit_per_minute must be at least 1".into());
+            }
+            let now = chrono::Utc::now().timestamp_millis();
+            update.insert("admit_per_minute", rate);
+            update.insert("admitted_base", room.admitted_through(now).min(room.last_position));
+            update.insert("rate_changed_at", bson::DateTime::from_millis(now.max(room.opens_at.timestamp_millis())));
+        }
+        if let Some(closes_at) = &req.closes_at {
+            let closes_at = parse_time(closes_at, "closes_at")?;
+            if closes_at <= room.opens_at {
+                return Err("closes_at must be after opens_at".into());
+            }
+            update.insert("closes_at", closes_at);
+        }
+        if let Some(status) = &req.status {
+            if status != "active" && status != "closed" {
+                return Err("status must be active or closed".into());
+            }
+            update.insert("status", status);
+        }
+        if update.is_empty() {
+            return Ok(room);
+        }
+
+        // Guard on the position counter so a concurrent join doesn't get its rebase overwritten
+        let result = collection.update_one(doc! { "_id": id, "last_position": room.last_position }, doc! { "$set": update }, None).await?;
+        if result.modified_count == 0 {
+            return Err(AppError::conflict("The waiting room changed while updating, please retry"));
+        }
+        collection.find_one(doc! { "_id": id }, None).await?.ok_or_else(|| AppError::not_found("Waiting room not found"))
+    }
+
+    /// Puts the visitor in the queue, or returns the place they already have.
+    pub async fn join_waiting_room(&self, bus_id: &str, travel_date: &str, holder: &str) -> Result<QueuePosition, AppError> {
+        let room = self.get_trip_waiting_room(bus_id, travel_date).await?
+            .filter(|r| r.is_enforced(chrono::Utc::now().timestamp_millis()))
+            .ok_or_else(|| AppError::not_found("There is no waiting room for this trip"))?;
+        let room_id = room.id.ok_or_else(|| AppError::not_found("Waiting room not found"))?;
+        let entries = self.get_waiting_room_entries_collection();
+
+        if let Some(entry) = entries.find_one(doc! { "room_id": room_id, "holder": holder }, None).await? {
+            let expired = entry.admitted_at
+                .is_some_and(|at| at.timestamp_millis() + room.admission_minutes * 60_000 < chrono::Utc::now().timestamp_millis());
+            if !expired {
+                return self.queue_position(&room, &entry).await;
+            }
+            // Their admission lapsed unused; they go to the back of the queue
+            entries.delete_one(doc! { "_id": entry.id }, None).await?;
+        }
+
+        let collection = self.get_waiting_rooms_collection();
+        for _ in 0..5 {
+            let current = collection.find_one(doc! { "_id": room_id }, None).await?.ok_or_else(|| AppError::not_found("Waiting room not found"))?;
+            let now = chrono::Utc::now().timestamp_millis();
+            let mut set = doc! { "last_position": current.last_position + 1 };
+            // Capacity doesn't bank while the queue is empty: an idle hour must not admit a later burst at once
+            if now >= current.opens_at.timestamp_millis() && current.admitted_through(now) > current.last_position {
+                set.insert("admitted_base", current.last_position + 1);
+                set.insert("rate_changed_at", bson::DateTime::from_millis(now));
+            }
+            let claimed = collection.update_one(
+                doc! { "_id": room_id, "last_position": current.last_position },
+                doc! { "$set": set },
+                None
+            ).await?;
+            if claimed.modified_count == 0 {
+                continue;
+            }
+
+            let mut entry = WaitingRoomEntry {
+                id: None,
+                room_id,
+                holder: holder.to_string(),
+                position: current.last_position + 1,
+                joined_at: bson::DateTime::now(),
+                admitted_at: None,
+            };
+            entry.id = entries.insert_one(&entry, None).await?.inserted_id.as_object_id();
+            let room = collection.find_one(doc! { "_id": room_id }, None).await?.ok_or_else(|| AppError::not_found("Waiting room not found"))?;
+            return self.queue_position(&room, &entry).await;
+        }
+        Err(AppError::conflict("The waiting room is very busy, please try again"))
+    }
+
+    /// Where a queue token's holder stands; once admitted, includes their admission token.
+    pub async fn get_queue_position(&self, queue_token: &str, holder: &str) -> Result<QueuePosition, AppError> {
+        let claims = verify_waiting_room_token(queue_token, QUEUE_SCOPE)?;
+        if claims.holder != holder {
+            return Err(AppError::forbidden("This queue token belongs to someone else"));
+        }
+        let room_id = self.string_to_id(&claims.room_id)?;
+        let room = self.get_waiting_rooms_collection().find_one(doc! { "_id": room_id }, None).await?
+            .ok_or_else(|| AppError::not_found("Waiting room not found"))?;
+        let entry = self.get_waiting_room_entries_collection()
+            .find_one(doc! { "room_id": room_id, "holder": holder }, None).await?
+            .ok_or("You are no longer in this queue, please rejoin")?;
+        self.queue_position(&room, &entry).await
+    }
+
+    fn sign_position_token(&self, room: &WaitingRoom, entry: &WaitingRoomEntry, scope: &str, exp_ms: i64) -> Result<String, AppError> {
+        Ok(sign(&WaitingRoomClaims {
+            scope: scope.to_string(),
+            room_id: room.id.map(|id| id.to_hex()).unwrap_or_default(),
+            bus_id: room.bus_id.to_hex(),
+            travel_date: room.travel_date.clone(),
+            holder: entry.holder.clone(),
+            position: entry.position,
+            exp: (exp_ms / 1000) as usize,
+        })?)
+    }
+
+    async fn queue_position(&self, room: &WaitingRoom, entry: &WaitingRoomEntry) -> Result<QueuePosition, AppError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let admitted = room.is_enforced(now) && entry.position <= room.admitted_through(now);
+        let queue_token = self.sign_position_token(room, entry, QUEUE_SCOPE, room.closes_at.timestamp_millis())?;
+
+        let mut position = QueuePosition {
+            position: entry.position,
+            ahead: (entry.position - room.admitted_through(now) - 1).max(0),
+            estimated_wait_minutes: room.estimated_wait_minutes(entry.position, now),
+            admitted,
+            queue_token: Some(queue_token),
+            admission_token: None,
+            admission_expires_at: None,
+        };
+        if admitted {
+            // The admission window runs from the first time they're seen admitted, not from each check
+            let admitted_at = match entry.admitted_at {
+                Some(at) => at.timestamp_millis(),
+                None => {
+                    self.get_waiting_room_entries_collection().update_one(
+                        doc! { "_id": entry.id, "admitted_at": null },
+                        doc! { "$set": { "admitted_at": bson::DateTime::from_millis(now) } },
+                        None
+                    ).await?;
+                    now
+                }
+            };
+            let expires_ms = (admitted_at + room.admission_minutes * 60_000).min(room.closes_at.timestamp_millis());
+            if expires_ms <= now {
+                return Err("Your turn to choose seats has expired, please rejoin the queue".into());
+            }
+            position.admission_token = Some(self.sign_position_token(room, entry, ADMISSION_SCOPE, expires_ms)?);
+            position.admission_expires_at = Some(bson::DateTime::from_millis(expires_ms).to_string());
+        }
+        Ok(position)
+    }
+}
@@ -0,0 +1,187 @@
+use futures::StreamExt;
+use log::{error, info};
+use mongodb::{
+    bson::{self, doc},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use super::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::BookingEvent;
+use crate::models::monitoring::PAYMENT_CONFIRMED;
+use crate::models::payment::pays_for;
+use crate::models::webhook::{
+    external_id, MpesaConfirmation, MpesaStkCallback, StripeEvent, WebhookEvent, WebhookEventQuery, WebhookOutcome,
+};
+
+impl MongoDB {
+    fn get_webhook_events_collection(&self) -> Collection<WebhookEvent> {
+        self.database().collection("webhook_events")
+    }
+
+    pub async fn ensure_webhook_indexes(&self) -> Result<(), AppError> {
+        let events = self.get_webhook_events_collection();
+        events.create_index(
+            IndexModel::builder()
+                .keys(doc! { "provider": 1, "external_id": 1 })
+                .build(),
+            None
+        ).await?;
+        events.create_index(
+            IndexModel::builder()
+                .keys(doc! { "status": 1, "received_at": -1 })
+                .build(),
+            None
+        ).await?;
+        Ok(())
+    }
+
+    /// Stores a delivery before anything is done with it, so even one we reject can be looked at
+    /// and replayed later. `rejection` is why it failed authentication, if it did.
+    pub async fn record_webhook(&self, provider: &str, body: &[u8], signature: Option<String>, rejection: Option<String>) -> Result<WebhookEvent, AppError> {
+        let mut event = WebhookEvent {
+            id: None,
+            provider: provider.to_string(),
+            raw_body: String::from_utf8_lossy(body).into_owned(),
+            signature,
+            external_id: external_id(provider, body),
+            status: if rejection.is_some() { "rejected" } else { "received" }.to_string(),
+            outcome: rejection,
+            attempts: 0,
+            received_at: bson::DateTime::now(),
+            last_processed_at: None,
+            replayed_by: None,
+        };
+        let result = self.get_webhook_events_collection().insert_one(&event, None).await?;
+        event.id = result.inserted_id.as_object_id();
+        Ok(event)
+    }
+
+    async fn handle_webhook_payload(&self, event: &WebhookEvent) -> Result<WebhookOutcome, AppError> {
+        let body = event.raw_body.as_bytes();
+        match event.provider.as_str() {
+            "mpesa" => {
+                let confirmation = MpesaConfirmation::parse(body)?;
+                let amount = confirmation.amount().ok_or("M-Pesa confirmation has no valid TransAmount")?;
+                self.mark_booking_paid(confirmation.bill_ref_number.trim(), amount, "mpesa", &confirmation.trans_id).await
+            }
+            "mpesa_stk" => self.complete_stk_payment(&MpesaStkCallback::parse(body)?).await,
+            "stripe" => {
+                let stripe_event = StripeEvent::parse(body)?;
+                if stripe_event.kind != "payment_intent.succeeded" {
+                    return Ok(WebhookOutcome::ignored(format!("Unhandled event type {}", stripe_event.kind)));
+                }
+                let Some(booking_id) = stripe_event.booking_id() else {
+                    return Ok(WebhookOutcome::ignored("Payment intent has no booking_id metadata".to_string()));
+                };
+                let amount = stripe_event.amount_received().ok_or("Payment intent has no amount_received")?;
+                self.mark_booking_paid(booking_id, amount, "stripe", &stripe_event.id).await
+            }
+            other => Err(format!("Unknown webhook provider {}", other).into()),
+        }
+    }
+
+    /// Records payment against a booking. Safe to call for the same payment any number of
+    /// times: a booking that already has a `paid` event is left alone.
+    async fn mark_booking_paid(&self, booking_id: &str, amount: f64, provider: &str, external_id: &str) -> Result<WebhookOutcome, AppError> {
+        let Ok(booking_oid) = self.string_to_id(booking_id) else {
+            return Ok(WebhookOutcome::ignored(format!("Unknown booking reference '{}'", booking_id)));
+        };
+        let Some(booking) = self.get_bookings_collection().find_one(doc! { "_id": booking_oid }, None).await? else {
+            return Ok(WebhookOutcome::ignored(format!("Unknown booking reference '{}'", booking_id)));
+        };
+        if booking.status == "Cancelled" {
+            return Ok(WebhookOutcome::ignored(format!("Booking {} is cancelled; KES {:.2} needs a manual refund", booking_id, amount)));
+        }
+        if let Some(due) = self.amount_due_for_booking(&booking).await? {
+            if !pays_for(amount, due) {
+                return Ok(WebhookOutcome::ignored(format!("Paid KES {:.2} but the fare is KES {:.2}", amount, due)));
+            }
+        }
+
+        let event = BookingEvent::new(
+            "paid",
+            &format!("system:webhook:{}", provider),
+            Some(format!("KES {:.2} via {} {}", amount, provider, external_id)),
+        );
+        if !self.record_booking_paid(booking_oid, &event).await? {
+            return Ok(WebhookOutcome::ignored(format!("Booking {} is already paid", booking_id)));
+        }
+
+        if let Err(e) = self.record_monitoring_event(PAYMENT_CONFIRMED, Some(booking_id.to_string())).await {
+            error!("Failed to record payment confirmation for {}: {}", booking_id, e);
+        }
+        Ok(WebhookOutcome::processed(format!("Booking {} marked paid", booking_id)))
+    }
+
+    /// Runs a stored delivery through the handler and records how it went. Handler errors
+    /// leave the event `failed` rather than being returned, so they can be replayed.
+    pub async fn process_webhook_event(&self, event: &WebhookEvent, replayed_by: Option<&str>) -> Result<WebhookEvent, AppError> {
+        let outcome = match self.handle_webhook_payload(event).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                error!("Webhook {:?} from {} failed: {}", event.id, event.provider, e);
+                WebhookOutcome { status: "failed", message: e.to_string() }
+            }
+        };
+
+        let mut set = doc! {
+            "status": outcome.status,
+            "outcome": &outcome.message,
+            "last_processed_at": bson::DateTime::now(),
+        };
+        if let Some(actor) = replayed_by {
+            set.insert("replayed_by", actor);
+        }
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        let updated = self.get_webhook_events_collection().find_one_and_update(
+            doc! { "_id": event.id },
+            doc! { "$set": set, "$inc": { "attempts": 1 } },
+            options
+        ).await?;
+        updated.ok_or_else(|| AppError::not_found("Webhook event not found"))
+    }
+
+    /// Re-processes a stored delivery, e.g. callbacks that failed during an outage. The caller
+    /// has already re-checked the signature where the provider sends one.
+    pub async fn replay_webhook_event(&self, event: &WebhookEvent, actor: &str) -> Result<WebhookEvent, AppError> {
+        let replayed = self.process_webhook_event(event, Some(actor)).await?;
+        self.record_audit(
+            "webhook.replayed",
+            actor,
+            &format!("webhook:{}", replayed.id.map(|id| id.to_hex()).unwrap_or_default()),
+            Some(format!("{} {} -> {}", replayed.provider, replayed.external_id.as_deref().unwrap_or("-"), replayed.status)),
+        ).await?;
+        info!("Webhook {:?} replayed by {}: {}", replayed.id, actor, replayed.status);
+        Ok(replayed)
+    }
+
+    pub async fn get_webhook_events(&self, query: &WebhookEventQuery) -> Result<Vec<WebhookEvent>, AppError> {
+        let mut filter = doc! {};
+        if let Some(provider) = &query.provider {
+            filter.insert("provider", provider);
+        }
+        if let Some(status) = &query.status {
+            filter.insert("status", status);
+        }
+        if let Some(external_id) = &query.external_id {
+            filter.insert("external_id", external_id);
+        }
+        let find_options = FindOptions::builder()
+            .sort(doc! { "received_at": -1 })
+            .limit(200)
+            .build();
+        let mut cursor = self.get_webhook_events_collection().find(filter, find_options).await?;
+        let mut events = Vec::new();
+        while let Some(result) = cursor.next().await {
+            events.push(result?);
+        }
+        Ok(events)
+    }
+
+    pub async fn get_webhook_event(&self, id: &str) -> Result<Option<WebhookEvent>, AppError> {
+        let oid = self.string_to_id(id)?;
+        Ok(self.get_webhook_events_collection().find_one(doc! { "_id": oid }, None).await?)
+    }
+}
@@ -0,0 +1,262 @@
+use actix_web::{http::{header::RETRY_AFTER, StatusCode}, HttpResponse, ResponseError};
+use log::error;
+use serde_json::json;
+
+use crate::models::fraud::BLOCKED;
+use crate::models::lookup::CAPTCHA_REQUIRED;
+use crate::models::terms::TERMS_ACCEPTANCE_REQUIRED;
+use crate::openapi::ErrorResponse;
+
+/// Everything the data layer can fail with, sorted by what the client should hear: each kind
+/// answers with one status code and a machine-readable `code` beside the message.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    // The request clashes with the current state, e.g. a seat someone else booked
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    // What the request names existed once but is gone for good, e.g. an expired hold
+    Gone(String),
+    Validation(String),
+    // A feature that is switched off, or the service shedding load
+    Unavailable(String),
+    // Too much too fast from one caller; they may try again after `retry_after` seconds
+    TooManyRequests { message: String, retry_after: Option<i64> },
+    // Refusals the client is expected to act on, each under its own code
+    Blocked,
+    CaptchaRequired,
+    TermsNotAccepted { version: String, url: Option<String> },
+    WaitingRoom { queue: String },
+    Database(mongodb::error::Error),
+    // Anything else on our side: a provider, encryption, serialization
+    Internal(String),
+}
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        AppError::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        AppError::Forbidden(message.into())
+    }
+
+    pub fn gone(message: impl Into<String>) -> Self {
+        AppError::Gone(message.into())
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        AppError::Unavailable(message.into())
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal(message.into())
+    }
+
+    pub fn too_many_requests(message: impl Into<String>, retry_after: Option<i64>) -> Self {
+        AppError::TooManyRequests { message: message.into(), retry_after }
+    }
+
+    /// The `code` field of the error response.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Gone(_) => "gone",
+            AppError::Validation(_) => "validation",
+            AppError::Unavailable(_) => "unavailable",
+            AppError::TooManyRequests { .. } => "rate_limited",
+            AppError::Blocked => BLOCKED,
+            AppError::CaptchaRequired => CAPTCHA_REQUIRED,
+            AppError::TermsNotAccepted { .. } => TERMS_ACCEPTANCE_REQUIRED,
+            AppError::WaitingRoom { .. } => "waiting_room",
+            AppError::Database(_) => "database",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// The same error with `prefix` ahead of its message, e.g. to say which item of a batch failed.
+    pub fn prefixed(self, prefix: &str) -> Self {
+        match self {
+            AppError::NotFound(message) => AppError::NotFound(format!("{}: {}", prefix, message)),
+            AppError::Conflict(message) => AppError::Conflict(format!("{}: {}", prefix, message)),
+            AppError::Unauthorized(message) => AppError::Unauthorized(format!("{}: {}", prefix, message)),
+            AppError::Forbidden(message) => AppError::Forbidden(format!("{}: {}", prefix, message)),
+            AppError::Gone(message) => AppError::Gone(format!("{}: {}", prefix, message)),
+            AppError::Validation(message) => AppError::Validation(format!("{}: {}", prefix, message)),
+            AppError::Unavailable(message) => AppError::Unavailable(format!("{}: {}", prefix, message)),
+            AppError::Internal(message) => AppError::Internal(format!("{}: {}", prefix, message)),
+            AppError::TooManyRequests { message, retry_after } => {
+                AppError::TooManyRequests { message: format!("{}: {}", prefix, message), retry_after }
+            }
+            other => other,
+        }
+    }
+
+    /// The driver error underneath, for retry decisions on transactions.
+    pub fn as_database(&self) -> Option<&mongodb::error::Error> {
+        match self {
+            AppError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::Gone(message)
+            | AppError::Validation(message)
+            | AppError::Unavailable(message)
+            | AppError::Internal(message)
+            | AppError::TooManyRequests { message, .. } => f.write_str(message),
+            AppError::Blocked => f.write_str("This account can't be used right now. Please contact support."),
+            AppError::CaptchaRequired => f.write_str("Please complete the CAPTCHA"),
+            AppError::TermsNotAccepted { .. } => f.write_str("The terms of service have changed and must be accepted to continue"),
+            AppError::WaitingRoom { .. } => f.write_str("This sale has a waiting room, please join the queue"),
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) | AppError::TermsNotAccepted { .. } => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) | AppError::Blocked | AppError::CaptchaRequired => StatusCode::FORBIDDEN,
+            AppError::Gone(_) => StatusCode::GONE,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests { .. } | AppError::WaitingRoom { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Server-side failures are logged in full and described to the client only vaguely
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            AppError::Database(_) | AppError::Internal(_) => {
+                error!("{}", self);
+                "Something went wrong on our side, please try again".to_string()
+            }
+            _ => self.to_string(),
+        };
+        let mut body = json!(ErrorResponse { error: message, code: Some(self.code().to_string()) });
+        let mut response = HttpResponse::build(self.status_code());
+        // What the client needs to act on the refusal goes beside the code
+        match self {
+            AppError::TooManyRequests { retry_after: Some(secs), .. } => {
+                response.insert_header((RETRY_AFTER, (*secs).max(1).to_string()));
+            }
+            AppError::TermsNotAccepted { version, url } => {
+                body["current_version"] = json!(version);
+                body["url"] = json!(url);
+            }
+            AppError::WaitingRoom { queue } => body["waiting_room"] = json!(queue),
+            _ => {}
+        }
+        response.json(body)
+    }
+}
+
+// Plain messages are what the data layer has always refused bad input with
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Validation(message.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Validation(message)
+    }
+}
+
+impl From<mongodb::error::Error> for AppError {
+    fn from(e: mongodb::error::Error) -> Self {
+        AppError::Database(e)
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for AppError {
+    fn from(e: mongodb::bson::ser::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<mongodb::bson::de::Error> for AppError {
+    fn from(e: mongodb::bson::de::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+// A malformed id in the request never names anything that exists
+impl From<mongodb::bson::oid::Error> for AppError {
+    fn from(_: mongodb::bson::oid::Error) -> Self {
+        AppError::Validation("Invalid id".to_string())
+    }
+}
+
+impl From<mongodb::bson::document::ValueAccessError> for AppError {
+    fn from(e: mongodb::bson::document::ValueAccessError) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<bcrypt::BcryptError> for AppError {
+    fn from(e: bcrypt::BcryptError) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+// Times in requests that don't parse
+impl From<chrono::ParseError> for AppError {
+    fn from(e: chrono::ParseError) -> Self {
+        AppError::Validation(e.to_string())
+    }
+}
+
+// A token the client sent that doesn't check out is their problem; a key we can't sign with is ours
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match e.kind() {
+            ErrorKind::InvalidEcdsaKey | ErrorKind::InvalidRsaKey(_) | ErrorKind::RsaFailedSigning | ErrorKind::InvalidKeyFormat => {
+                AppError::Internal(e.to_string())
+            }
+            _ => AppError::Unauthorized(e.to_string()),
+        }
+    }
+}
+
+// A payment provider we couldn't reach or understand
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
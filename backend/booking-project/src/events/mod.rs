@@ -0,0 +1,150 @@
+//! Domain events: what happened to a booking or a trip, told to whichever subsystems care
+//! without the booking code knowing about them.
+
+pub mod nats;
+pub mod stream;
+pub mod subscribers;
+
+use std::future::Future;
+
+use log::warn;
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::models::Booking;
+
+// The booking as it was when the event happened
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BookingSnapshot {
+    pub booking_id: String,
+    pub user_id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub status: String,
+}
+
+impl From<&Booking> for BookingSnapshot {
+    fn from(booking: &Booking) -> Self {
+        Self {
+            booking_id: booking.id.map(|id| id.to_hex()).unwrap_or_default(),
+            user_id: booking.user_id.to_hex(),
+            bus_id: booking.bus_id.to_hex(),
+            travel_date: booking.travel_date.clone(),
+            seat_number: booking.seat_number.clone(),
+            status: booking.status.clone(),
+        }
+    }
+}
+
+impl BookingSnapshot {
+    /// The snapshot after a status change the booking in hand doesn't show yet.
+    pub fn with_status(mut self, status: &str) -> Self {
+        self.status = status.to_string();
+        self
+    }
+}
+
+// One bus leaving on one date
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TripSnapshot {
+    pub bus_id: String,
+    pub travel_date: String,
+    pub departed_at: String,
+    pub delay_minutes: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    BookingCreated { booking: BookingSnapshot },
+    BookingCancelled { booking: BookingSnapshot, actor: String },
+    BookingPaid { booking: BookingSnapshot, actor: String, note: Option<String> },
+    TripDeparted { trip: TripSnapshot },
+}
+
+impl DomainEvent {
+    /// The `type` it is serialized with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::BookingCreated { .. } => "booking_created",
+            DomainEvent::BookingCancelled { .. } => "booking_cancelled",
+            DomainEvent::BookingPaid { .. } => "booking_paid",
+            DomainEvent::TripDeparted { .. } => "trip_departed",
+        }
+    }
+
+    /// What the event is about, e.g. `booking:<id>`; events about the same thing share it.
+    pub fn subject(&self) -> String {
+        match self {
+            DomainEvent::BookingCreated { booking }
+            | DomainEvent::BookingCancelled { booking, .. }
+            | DomainEvent::BookingPaid { booking, .. } => format!("booking:{}", booking.booking_id),
+            DomainEvent::TripDeparted { trip } => format!("trip:{}:{}", trip.bus_id, trip.travel_date),
+        }
+    }
+}
+
+// An event as subscribers receive it; the id stays the same however often it is delivered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EventEnvelope {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub occurred_at: bson::DateTime,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+}
+
+impl EventEnvelope {
+    pub fn new(event: DomainEvent) -> Self {
+        Self { id: ObjectId::new(), occurred_at: bson::DateTime::now(), event }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+    persist: bool,
+}
+
+impl EventBus {
+    pub fn new(buffer: usize, persist: bool) -> Self {
+        let (sender, _) = broadcast::channel(buffer);
+        Self { sender, persist }
+    }
+
+    /// Whether events go through the `domain_events` outbox before subscribers see them.
+    pub fn persists(&self) -> bool {
+        self.persist
+    }
+
+    /// Hands the event to every current subscriber; returns how many there were.
+    pub fn dispatch(&self, envelope: EventEnvelope) -> usize {
+        // Nobody listening isn't an error
+        self.sender.send(envelope).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+
+    /// Runs `handler` on every event dispatched from now on, one at a time, in its own task.
+    /// Must be called from within the actix runtime.
+    pub fn spawn_subscriber<F, Fut>(&self, name: &'static str, handler: F)
+    where
+        F: Fn(EventEnvelope) -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let mut receiver = self.subscribe();
+        actix_web::rt::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => handler(envelope).await,
+                    Err(RecvError::Lagged(missed)) => warn!("Event subscriber {} fell behind and missed {} event(s)", name, missed),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::ServerName;
+
+use crate::notifications::smtp::tls_connector;
+
+// Covers connecting, the TLS handshake and waiting for the server to take the message
+const NATS_TIMEOUT: Duration = Duration::from_secs(15);
+const DEFAULT_PORT: u16 = 4222;
+
+// Just enough of the NATS client protocol to publish: connect, publish with headers, and PING so
+// the server's PONG confirms it processed everything before. `tls://` URLs upgrade after INFO
+pub struct NatsClient {
+    host: String,
+    port: u16,
+    tls: bool,
+    token: Option<String>,
+}
+
+/// The `HPUB` command for one message. `Nats-Msg-Id` lets a JetStream stream drop the copies a
+/// retry sends again.
+pub fn publish_frame(subject: &str, msg_id: &str, payload: &str) -> Result<String, String> {
+    if subject.is_empty() || subject.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("Invalid NATS subject {:?}", subject));
+    }
+    let msg_id = msg_id.replace(['\r', '\n'], "");
+    let headers = format!("NATS/1.0\r\nNats-Msg-Id: {}\r\n\r\n", msg_id);
+    Ok(format!(
+        "HPUB {} {} {}\r\n{}{}\r\n",
+        subject, headers.len(), headers.len() + payload.len(), headers, payload
+    ))
+}
+
+async fn read_line<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>) -> Result<String, String> {
+    let mut line = String::new();
+    if conn.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+        return Err("NATS server closed the connection".to_string());
+    }
+    Ok(line.trim_end().to_string())
+}
+
+async fn write<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>, data: &str) -> Result<(), String> {
+    let stream = conn.get_mut();
+    stream.write_all(data.as_bytes()).await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())
+}
+
+impl NatsClient {
+    /// From a `nats://host:port` or `tls://host:port` URL.
+    pub fn new(url: &str, token: Option<String>) -> Result<Self, String> {
+        let (tls, address) = match url.split_once("://") {
+            Some(("nats", address)) => (false, address),
+            Some(("tls", address)) => (true, address),
+            Some((scheme, _)) => return Err(format!("Unsupported NATS URL scheme {}", scheme)),
+            None => (false, url),
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("Invalid NATS port in {}", url))?),
+            None => (address, DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(format!("Invalid NATS URL {}", url));
+        }
+        Ok(Self { host: host.to_string(), port, tls, token })
+    }
+
+    pub async fn publish(&self, subject: &str, msg_id: &str, payload: &str) -> Result<(), String> {
+        let frame = publish_frame(subject, msg_id, payload)?;
+        tokio::time::timeout(NATS_TIMEOUT, self.publish_within_timeout(&frame))
+            .await
+            .map_err(|_| format!("NATS server {} timed out", self.host))?
+    }
+
+    async fn publish_within_timeout(&self, frame: &str) -> Result<(), String> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).await.map_err(|e| e.to_string())?;
+        let mut conn = BufReader::new(tcp);
+        let info = read_line(&mut conn).await?;
+        if !info.starts_with("INFO ") {
+            return Err(format!("Unexpected NATS greeting: {}", info));
+        }
+        if !self.tls {
+            return self.deliver(&mut conn, frame).await;
+        }
+        let server_name = ServerName::try_from(self.host.as_str()).map_err(|e| e.to_string())?;
+        let tls = tls_connector().connect(server_name, conn.into_inner()).await.map_err(|e| e.to_string())?;
+        self.deliver(&mut BufReader::new(tls), frame).await
+    }
+
+    async fn deliver<S: AsyncRead + AsyncWrite + Unpin>(&self, conn: &mut BufReader<S>, frame: &str) -> Result<(), String> {
+        let mut options = json!({
+            "verbose": false,
+            "pedantic": false,
+            "tls_required": self.tls,
+            "headers": true,
+            "name": "bus-book",
+            "lang": "rust",
+            "version": env!("CARGO_PKG_VERSION"),
+            "protocol": 1,
+        });
+        if let Some(token) = &self.token {
+            options["auth_token"] = json!(token);
+        }
+        write(conn, &format!("CONNECT {}\r\n{}PING\r\n", options, frame)).await?;
+        loop {
+            let line = read_line(conn).await?;
+            match line.split_whitespace().next().unwrap_or_default() {
+                "PONG" => return Ok(()),
+                "PING" => write(conn, "PONG\r\n").await?,
+                "-ERR" => return Err(format!("NATS server refused the message: {}", line.trim_start_matches("-ERR").trim())),
+                _ => {}
+            }
+        }
+    }
+}
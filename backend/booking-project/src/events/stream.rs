@@ -0,0 +1,117 @@
+//! Domain events for consumers outside the service, on Kafka or NATS.
+//!
+//! Each event becomes a [`StreamRecord`]: a JSON envelope naming the event type and the version
+//! of its schema, with the event itself under `data`. Records wait in the outbox and the outbox
+//! worker hands them to the configured [`EventPublisher`], so a broker outage only delays them;
+//! consumers should expect the odd redelivery and deduplicate on `id`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{nats::NatsClient, DomainEvent, EventEnvelope};
+use crate::config::EventStreamConfig;
+
+// Bumped when a field changes meaning or goes away; new fields don't need a bump
+const BOOKING_SCHEMA_VERSION: u32 = 1;
+const PAYMENT_SCHEMA_VERSION: u32 = 1;
+const TRIP_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct StreamRecord {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub schema_version: u32,
+    // Which deployment it came from, e.g. "bus-book/production"
+    pub source: String,
+    pub occurred_at: String,
+    pub subject: String,
+    pub data: serde_json::Value,
+}
+
+/// The public name of an event and the version of its schema.
+pub fn stream_type(event: &DomainEvent) -> (&'static str, u32) {
+    match event {
+        DomainEvent::BookingCreated { .. } => ("booking.created", BOOKING_SCHEMA_VERSION),
+        DomainEvent::BookingCancelled { .. } => ("booking.cancelled", BOOKING_SCHEMA_VERSION),
+        DomainEvent::BookingPaid { .. } => ("payment.confirmed", PAYMENT_SCHEMA_VERSION),
+        DomainEvent::TripDeparted { .. } => ("trip.departed", TRIP_SCHEMA_VERSION),
+    }
+}
+
+/// Where an event is published: `<prefix>.<type>.v<version>`, a Kafka topic or NATS subject.
+/// A breaking schema change moves to a new topic, so existing consumers keep working.
+pub fn topic(prefix: &str, event: &DomainEvent) -> String {
+    let (kind, version) = stream_type(event);
+    format!("{}.{}.v{}", prefix.trim_end_matches('.'), kind, version)
+}
+
+impl StreamRecord {
+    pub fn new(envelope: &EventEnvelope, environment: &str) -> Self {
+        let (kind, schema_version) = stream_type(&envelope.event);
+        let mut data = serde_json::to_value(&envelope.event).unwrap_or_default();
+        if let Some(fields) = data.as_object_mut() {
+            fields.remove("type");
+        }
+        Self {
+            id: envelope.id.to_hex(),
+            kind: kind.to_string(),
+            schema_version,
+            source: format!("bus-book/{}", environment),
+            occurred_at: envelope.occurred_at.try_to_rfc3339_string().unwrap_or_default(),
+            subject: envelope.event.subject(),
+            data,
+        }
+    }
+}
+
+pub enum EventPublisher {
+    // Through a Confluent-compatible REST proxy, so no Kafka client library is needed
+    Kafka { rest_url: String, credentials: Option<(String, String)> },
+    Nats(NatsClient),
+}
+
+impl EventPublisher {
+    pub fn from_config(config: &EventStreamConfig) -> Result<Self, String> {
+        match config.provider.as_deref() {
+            Some("kafka") => Ok(Self::Kafka {
+                rest_url: config.kafka_rest_url.clone().ok_or("KAFKA_REST_URL is not set")?,
+                credentials: config.kafka_username.clone().zip(config.kafka_password.clone()),
+            }),
+            Some("nats") => Ok(Self::Nats(NatsClient::new(
+                config.nats_url.as_deref().ok_or("NATS_URL is not set")?,
+                config.nats_token.clone(),
+            )?)),
+            Some(other) => Err(format!("Unknown event stream {}", other)),
+            None => Err("No event stream configured".to_string()),
+        }
+    }
+
+    /// Publishes one serialized [`StreamRecord`]. Kafka records are keyed by what they are about,
+    /// so a consumer sees a booking's events in order.
+    pub async fn publish(&self, client: &reqwest::Client, topic: &str, body: &str) -> Result<(), String> {
+        let record: StreamRecord = serde_json::from_str(body).map_err(|e| format!("Unreadable event record: {}", e))?;
+        match self {
+            Self::Kafka { rest_url, credentials } => {
+                let mut request = client.post(format!("{}/topics/{}", rest_url, topic))
+                    .header("Content-Type", "application/vnd.kafka.json.v2+json")
+                    .header("Accept", "application/vnd.kafka.v2+json")
+                    .body(json!({ "records": [{ "key": &record.subject, "value": &record }] }).to_string());
+                if let Some((username, password)) = credentials {
+                    request = request.basic_auth(username, Some(password));
+                }
+                let response = request.send().await.map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("Kafka REST proxy responded with {}", response.status()));
+                }
+                // The proxy answers 200 even when a record was refused; the reason is per record
+                let result: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+                match result["offsets"][0]["error"].as_str() {
+                    Some(error) => Err(format!("Kafka refused the record: {}", error)),
+                    None => Ok(()),
+                }
+            }
+            Self::Nats(nats) => nats.publish(topic, &record.id, body).await,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use log::{debug, error};
+use mongodb::bson::oid::ObjectId;
+
+use crate::config::EventStreamConfig;
+use crate::db::MongoDB;
+use crate::events::DomainEvent;
+use crate::metrics::metrics;
+
+/// Subscribes the built-in consumers; must be called from within the actix runtime.
+pub fn spawn_all(db: &MongoDB) {
+    db.events().spawn_subscriber("metrics", |envelope| async move {
+        metrics().domain_events.with_label_values(&[envelope.event.name()]).inc();
+        debug!("{} for {}", envelope.event.name(), envelope.event.subject());
+    });
+
+    // Keeps each customer's home screen card in step with their bookings
+    let next_trip_db = db.clone();
+    db.events().spawn_subscriber("next_trip", move |envelope| {
+        let db = next_trip_db.clone();
+        async move {
+            let booking = match &envelope.event {
+                DomainEvent::BookingCreated { booking }
+                | DomainEvent::BookingCancelled { booking, .. }
+                | DomainEvent::BookingPaid { booking, .. } => booking,
+                DomainEvent::TripDeparted { .. } => return,
+            };
+            if let Ok(user_id) = ObjectId::parse_str(&booking.user_id) {
+                db.refresh_next_trip_quietly(user_id).await;
+            }
+        }
+    });
+
+    // Cancelled rides go back on the commuter pass they were taken off
+    let passes_db = db.clone();
+    db.events().spawn_subscriber("passes", move |envelope| {
+        let db = passes_db.clone();
+        async move {
+            let DomainEvent::BookingCancelled { booking, .. } = &envelope.event else {
+                return;
+            };
+            let Ok(booking_id) = ObjectId::parse_str(&booking.booking_id) else {
+                return;
+            };
+            if let Err(e) = db.return_pass_ride(booking_id).await {
+                error!("Failed to return the pass ride for cancelled booking {}: {}", booking_id, e);
+            }
+        }
+    });
+
+    let config = EventStreamConfig::from_env();
+    if config.provider.is_some() {
+        let db = db.clone();
+        db.events().clone().spawn_subscriber("event_stream", move |envelope| {
+            let (db, config) = (db.clone(), config.clone());
+            async move {
+                if let Err(e) = db.enqueue_stream_record(&envelope, &config).await {
+                    error!("Failed to queue {} event {} for the event stream: {}", envelope.event.name(), envelope.id, e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,177 @@
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::auth::OptionalClaims;
+use crate::models::account::{EmailChangeRequest, EmailChangeResponse, LanguagePreferenceRequest};
+use crate::models::consent::{ConsentEventResponse, ConsentResponse, UpdateConsentsRequest};
+use mongodb::bson::oid::ObjectId;
+use serde_json::json;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/account/email",
+    tag = "account",
+    request_body = EmailChangeRequest,
+    responses(
+        (status = 202, description = "Accepted", body = EmailChangeResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn request_email_change(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<EmailChangeRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.request_email_change(&user_id, &payload).await {
+        Ok(change) => Ok(HttpResponse::Accepted().json(EmailChangeResponse::from(change))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/email/confirm/{token}",
+    tag = "account",
+    params(("token" = String, Path, description = "Token from the link")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn confirm_email_change(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.confirm_email_change(&path.into_inner()).await {
+        Ok(change) => Ok(HttpResponse::Ok().json(json!({
+            "message": "Your email address has been updated. Please sign in again.",
+            "change": EmailChangeResponse::from(change),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/email/undo/{token}",
+    tag = "account",
+    params(("token" = String, Path, description = "Token from the link")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn undo_email_change(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.undo_email_change(&path.into_inner()).await {
+        Ok(change) => Ok(HttpResponse::Ok().json(json!({
+            "message": "The email change has been undone.",
+            "change": EmailChangeResponse::from(change),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/account/language",
+    tag = "account",
+    request_body = LanguagePreferenceRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_language(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<LanguagePreferenceRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.set_user_language(&user_id, &payload.language).await {
+        Ok(language) => Ok(HttpResponse::Ok().json(json!({ "language": language }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn user_object_id(req: &HttpRequest) -> Option<ObjectId> {
+    OptionalClaims::of(req).user_id().and_then(|id| ObjectId::parse_str(id).ok())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/consents",
+    tag = "account",
+    responses(
+        (status = 200, description = "OK", body = ConsentResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_consents(req: HttpRequest, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let user_id = match user_object_id(&req) {
+        Some(id) => id,
+        None => return Err(AppError::unauthorized("Unauthorized").into()),
+    };
+
+    match db.get_consents(user_id).await {
+        Ok(consents) => Ok(HttpResponse::Ok().json(ConsentResponse::all(&consents))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/account/consents",
+    tag = "account",
+    request_body = UpdateConsentsRequest,
+    responses(
+        (status = 200, description = "OK", body = ConsentResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_consents(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<UpdateConsentsRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match user_object_id(&req) {
+        Some(id) => id,
+        None => return Err(AppError::unauthorized("Unauthorized").into()),
+    };
+
+    let source = payload.source.as_deref().unwrap_or("account_settings");
+    match db.update_consents(user_id, &payload.consents, source).await {
+        Ok(consents) => Ok(HttpResponse::Ok().json(ConsentResponse::all(&consents))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/consents/history",
+    tag = "account",
+    responses(
+        (status = 200, description = "OK", body = [ConsentEventResponse]),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_consent_history(req: HttpRequest, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let user_id = match user_object_id(&req) {
+        Some(id) => id,
+        None => return Err(AppError::unauthorized("Unauthorized").into()),
+    };
+
+    match db.get_consent_history(user_id).await {
+        Ok(events) => Ok(HttpResponse::Ok().json(events.into_iter().map(ConsentEventResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
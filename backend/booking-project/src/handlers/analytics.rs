@@ -0,0 +1,163 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::models::analytics::{HeatmapQuery, RevenueQuery};
+use crate::models::punctuality::PunctualityQuery;
+use crate::models::search_log::SearchLogQuery;
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/punctuality",
+    tag = "admin",
+    params(PunctualityQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_punctuality(
+    db: web::Data<MongoDB>,
+    query: web::Query<PunctualityQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(90).clamp(1, 365);
+    match db.get_punctuality_report(days).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/demand-heatmap",
+    tag = "admin",
+    params(HeatmapQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_demand_heatmap(
+    db: web::Data<MongoDB>,
+    query: web::Query<HeatmapQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(90).clamp(1, 365);
+    match db.get_demand_heatmap(days, query.from.as_deref(), query.to.as_deref()).await {
+        Ok(heatmap) => Ok(HttpResponse::Ok().json(heatmap)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/revenue",
+    tag = "admin",
+    params(RevenueQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_revenue(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    query: web::Query<RevenueQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_revenue_report(query.from.as_deref(), query.to.as_deref(), tenant.operator_filter(None)).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/incidents",
+    tag = "admin",
+    params(PunctualityQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_incidents(
+    db: web::Data<MongoDB>,
+    query: web::Query<PunctualityQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(90).clamp(1, 365);
+    match db.get_incident_report(days).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/countries",
+    tag = "admin",
+    params(PunctualityQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_countries(
+    db: web::Data<MongoDB>,
+    query: web::Query<PunctualityQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    match db.get_country_report(days).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/searches/popular-routes",
+    tag = "admin",
+    params(SearchLogQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_popular_routes(
+    db: web::Data<MongoDB>,
+    query: web::Query<SearchLogQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    match db.get_popular_routes(days, limit).await {
+        Ok(routes) => Ok(HttpResponse::Ok().json(routes)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/searches/zero-results",
+    tag = "admin",
+    params(SearchLogQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_zero_result_searches(
+    db: web::Data<MongoDB>,
+    query: web::Query<SearchLogQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    match db.get_zero_result_searches(days, limit).await {
+        Ok(searches) => Ok(HttpResponse::Ok().json(searches)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,23 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::models::audit::{AuditEntryResponse, AuditQuery};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit-log",
+    tag = "admin",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "OK", body = [AuditEntryResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_audit_log(db: web::Data<MongoDB>, query: web::Query<AuditQuery>) -> Result<HttpResponse, Error> {
+    match db.get_audit_log(query.action.as_deref(), query.subject.as_deref()).await {
+        Ok(entries) => Ok(HttpResponse::Ok().json(entries.into_iter().map(AuditEntryResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
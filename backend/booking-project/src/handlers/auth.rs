@@ -1,28 +1,129 @@
-use actix_web::{web, HttpResponse, Error};
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use log::error;
 use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::handlers::fraud::enforce_blocklist;
+use crate::middleware::auth::jwt_secret;
+use crate::models::auth::{AnonymousClaims, AnonymousSessionResponse, ForgotPasswordRequest, RefreshRequest, ResetPasswordRequest};
+use crate::models::AuthResponse;
+use crate::tokens::{TokenService, ANONYMOUS_SCOPE};
 use serde_json::json;
+use crate::openapi::ErrorResponse;
 
+// Resolves the anonymous session id from a valid X-Anonymous-Token header
+pub(crate) fn anonymous_id_from_request(req: &HttpRequest) -> Option<String> {
+    let token = req.headers().get("X-Anonymous-Token")?.to_str().ok()?;
+    let secret = jwt_secret();
+    let claims = decode::<AnonymousClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &Validation::new(Algorithm::HS256),
+    ).ok()?.claims;
+    (claims.scope == ANONYMOUS_SCOPE).then_some(claims.sub)
+}
+
+// Moves seats held before signing in over to the account
+async fn claim_anonymous_holds(req: &HttpRequest, db: &MongoDB, auth_response: &AuthResponse) {
+    if let Some(anon_id) = anonymous_id_from_request(req) {
+        if let Err(e) = db.migrate_anonymous_holds(&anon_id, &auth_response.user.id).await {
+            error!("Failed to migrate anonymous holds: {}", e);
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/anonymous",
+    tag = "auth",
+    responses(
+        (status = 201, description = "Created", body = AnonymousSessionResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn anonymous_session() -> Result<HttpResponse, Error> {
+    let anon_id = mongodb::bson::oid::ObjectId::new().to_hex();
+    let (token, expires_at) = TokenService::from_env()
+        .issue_anonymous(&anon_id)
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Created().json(AnonymousSessionResponse {
+        token,
+        anon_id,
+        expires_at: expires_at.to_rfc3339(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = crate::models::RegisterRequest,
+    responses(
+        (status = 200, description = "OK", body = crate::models::AuthResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
 pub async fn register(
+    req: HttpRequest,
     db: web::Data<MongoDB>,
     user: web::Json<crate::models::RegisterRequest>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(refusal) = enforce_blocklist(&db, &req, "register", Some(&user.email), None).await {
+        return Err(refusal.into());
+    }
+
     match db.create_user(&user).await {
-        Ok(auth_response) => Ok(HttpResponse::Ok().json(auth_response)),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({ "error": e.to_string() }))),
+        Ok(auth_response) => {
+            claim_anonymous_holds(&req, &db, &auth_response).await;
+            Ok(HttpResponse::Ok().json(auth_response))
+        },
+        Err(e) => Err(e.into()),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = crate::models::LoginRequest,
+    responses(
+        (status = 200, description = "OK", body = crate::models::AuthResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+)]
 pub async fn login(
+    req: HttpRequest,
     db: web::Data<MongoDB>,
     credentials: web::Json<crate::models::LoginRequest>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(refusal) = enforce_blocklist(&db, &req, "login", Some(&credentials.email), None).await {
+        return Err(refusal.into());
+    }
+
     match db.authenticate_user(&credentials).await {
-        Ok(auth_response) => Ok(HttpResponse::Ok().json(auth_response)),
-        Err(e) => Ok(HttpResponse::Unauthorized().json(json!({ "error": e.to_string() }))),
+        Ok(auth_response) => {
+            claim_anonymous_holds(&req, &db, &auth_response).await;
+            Ok(HttpResponse::Ok().json(auth_response))
+        },
+        Err(e) => Err(e.into()),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/google",
+    tag = "auth",
+    request_body = crate::models::GoogleLoginRequest,
+    responses(
+        (status = 200, description = "OK", body = crate::models::AuthResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
 pub async fn google_login(
+    req: HttpRequest,
     db: web::Data<MongoDB>,
     payload: web::Json<crate::models::GoogleLoginRequest>,
 ) -> Result<HttpResponse, Error> {
@@ -33,27 +134,125 @@ pub async fn google_login(
         .query(&[("id_token", &payload.token)])
         .send()
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(actix_web::error::ErrorInternalServerError)?;
 
     if !response.status().is_success() {
-        return Ok(HttpResponse::Unauthorized().json(json!({ "error": "Invalid Google token" })));
+        return Err(AppError::unauthorized("Invalid Google token").into());
     }
 
     let google_user: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(actix_web::error::ErrorInternalServerError)?;
 
     let email = google_user["email"].as_str().unwrap_or("");
     let name = google_user["name"].as_str().unwrap_or("Google User");
 
     if email.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(json!({ "error": "Email not found in Google token" })));
+        return Err(AppError::from("Email not found in Google token").into());
+    }
+
+    if let Some(refusal) = enforce_blocklist(&db, &req, "login", Some(email), None).await {
+        return Err(refusal.into());
     }
 
     // 2. Login or Register in DB
     match db.google_login(email, name).await {
+        Ok(auth_response) => {
+            claim_anonymous_holds(&req, &db, &auth_response).await;
+            Ok(HttpResponse::Ok().json(auth_response))
+        },
+        Err(e) => Err(e.into()),
+    }
+}
+/// New access and refresh tokens for a refresh token, which can't be used again afterwards.
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "OK", body = crate::models::AuthResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+)]
+pub async fn refresh(
+    db: web::Data<MongoDB>,
+    payload: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.refresh_session(&payload.refresh_token).await {
         Ok(auth_response) => Ok(HttpResponse::Ok().json(auth_response)),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({ "error": e.to_string() }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ends the session behind a refresh token; its access token lapses on its own shortly after.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 204, description = "No content"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn logout(
+    db: web::Data<MongoDB>,
+    payload: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.revoke_refresh_token(&payload.refresh_token).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
     }
-}
\ No newline at end of file
+}
+
+/// Emails a reset link. Answers the same whether or not the address has an account.
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "Accepted"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn forgot_password(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<ForgotPasswordRequest>,
+) -> Result<HttpResponse, Error> {
+    if let Some(refusal) = enforce_blocklist(&db, &req, "login", Some(&payload.email), None).await {
+        return Err(refusal.into());
+    }
+
+    match db.request_password_reset(&payload.email).await {
+        Ok(()) => Ok(HttpResponse::Accepted().json(json!({
+            "message": "If an account uses that address, a link to reset its password is on its way.",
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn reset_password(
+    db: web::Data<MongoDB>,
+    payload: web::Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.reset_password(&payload).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(json!({
+            "message": "Your password has been changed. Please sign in again.",
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,117 +1,544 @@
-use actix_web::{web, HttpResponse, Error, HttpRequest};
-use log::{debug, error};
+use actix_web::{web, HttpResponse, Error, HttpRequest, ResponseError};
+use log::error;
 use crate::db::MongoDB;
-use crate::models::booking::CreateBookingRequest;
-use crate::models::Claims;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use crate::error::AppError;
+use crate::models::booking::{Booking, BookingConfirmation, BookingDetailResponse, BookingPage, BookingPageQuery, BookingSearchQuery, ChangeBookingRequest, CreateBookingRequest, CreateGroupBookingRequest, ReseatRequest};
+use crate::handlers::buses::requested_document_language;
+use crate::handlers::fraud::enforce_blocklist;
+use crate::handlers::terms::acceptance_required;
+use crate::metrics::metrics;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::geoip::request_country;
+use crate::models::calendar::CalendarEvent;
+use crate::models::hold::ConfirmHoldsRequest;
+use crate::models::localization::DocumentQuery;
+use crate::models::trip::AnnouncementResponse;
+use crate::models::monitoring::{BOOKING_ATTEMPT, BOOKING_FAILURE};
+use crate::models::partner::PartnerOfferResponse;
+use crate::models::permissions::{authorize, BOOKINGS_READ};
+use crate::models::tenancy::Tenant;
 use serde_json::json;
+use crate::openapi::ErrorResponse;
 
-// Helper to extract user_id from JWT token in Authorization header
-fn get_user_id_from_token(req: &HttpRequest) -> Option<String> {
-    let auth_header = req.headers().get("Authorization");
-    if auth_header.is_none() {
-        debug!("Missing Authorization header");
-        return None;
-    }
-    
-    let auth_str = auth_header.unwrap().to_str().ok();
-    if auth_str.is_none() || !auth_str.unwrap().starts_with("Bearer ") {
-        debug!("Invalid Authorization header format");
-        return None;
-    }
-    
-    let token = &auth_str.unwrap()[7..];
-    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-    
-    debug!("Attempting to decode token with secret: {}...", &secret[..std::cmp::min(3, secret.len())]);
-    
-    match decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::new(Algorithm::HS256),
-    ) {
-        Ok(token_data) => {
-            debug!("Token decoded successfully for user: {}", token_data.claims.sub);
-            Some(token_data.claims.sub)
-        },
+// Feeds the anomaly monitor's booking failure ratio and the `bookings_total` counter
+fn record_booking_outcome(db: MongoDB, failure: Option<String>) {
+    metrics().bookings
+        .with_label_values(&[if failure.is_some() { "failed" } else { "succeeded" }])
+        .inc();
+    actix_web::rt::spawn(async move {
+        let mut result = db.record_monitoring_event(BOOKING_ATTEMPT, None).await;
+        if failure.is_some() {
+            result = result.and(db.record_monitoring_event(BOOKING_FAILURE, failure).await);
+        }
+        if let Err(e) = result {
+            error!("Failed to record booking outcome: {}", e);
+        }
+    });
+}
+
+// Destination offers for the confirmation, optionally emailed too; they never fail a booking
+async fn partner_offers(db: &MongoDB, booking: &Booking, email: bool) -> Vec<PartnerOfferResponse> {
+    let offers = match db.offers_for_booking(booking).await {
+        Ok(offers) => offers,
         Err(e) => {
-            error!("Token decoding failed: {:?}", e);
-            None
+            error!("Failed to load partner offers: {}", e);
+            return vec![];
+        }
+    };
+    if email {
+        if let Err(e) = db.queue_partner_offers_email(booking, &offers).await {
+            error!("Failed to queue partner offers email: {}", e);
         }
     }
+    offers
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/bookings",
+    tag = "bookings",
+    request_body = CreateBookingRequest,
+    responses(
+        (status = 201, description = "Created", body = BookingConfirmation),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 pub async fn create_booking(
     req: HttpRequest,
+    user: AuthenticatedUser,
     db: web::Data<MongoDB>,
     booking_req: web::Json<CreateBookingRequest>,
 ) -> Result<HttpResponse, Error> {
-    let user_id = match get_user_id_from_token(&req) {
-        Some(id) => id,
-        None => return Ok(HttpResponse::Unauthorized().json(json!({ "error": "Unauthorized" }))),
-    };
+    let user_id = user.id;
+
+    let email = db.user_blocklist_email(&user_id).await.ok().flatten();
+    if let Some(refusal) = enforce_blocklist(&db, &req, "booking", email.as_deref(), Some(&user_id)).await {
+        return Err(refusal.into());
+    }
 
-    match db.create_booking(&user_id, &booking_req).await {
-        Ok(booking) => Ok(HttpResponse::Created().json(booking)),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({ "error": e.to_string() }))),
+    match db.ensure_terms_accepted(&user_id, booking_req.accepted_terms_version.as_deref(), "booking").await {
+        Ok(true) => {}
+        Ok(false) => return Err(acceptance_required().into()),
+        Err(e) => return Err(e.into()),
+    }
+
+    let result = db.create_booking(&user_id, &booking_req, request_country(&req).as_deref()).await;
+    record_booking_outcome(db.get_ref().clone(), result.as_ref().err().map(|e| e.to_string()));
+
+    match result {
+        Ok(booking) => {
+            // The booking stands even if the text message or email can't be queued
+            let language = requested_document_language(&req, None);
+            if let Err(e) = db.queue_booking_confirmation_sms(&booking, language).await {
+                error!("Failed to queue booking confirmation SMS: {}", e);
+            }
+            if let Err(e) = db.queue_booking_confirmation_email(&booking, language).await {
+                error!("Failed to queue booking confirmation email: {}", e);
+            }
+            let partner_offers = partner_offers(&db, &booking, true).await;
+            Ok(HttpResponse::Created().json(BookingConfirmation::new(booking, partner_offers)))
+        }
+        Err(e) => Err(e.into()),
     }
 }
 
-pub async fn get_user_bookings(
+/// Books several seats on one trip together; if any of them is taken, none are booked.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/group",
+    tag = "bookings",
+    request_body = CreateGroupBookingRequest,
+    responses(
+        (status = 201, description = "Created", body = [BookingConfirmation]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_group_booking(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    booking_req: web::Json<CreateGroupBookingRequest>,
+) -> Result<HttpResponse, Error> {
+    Ok(book_group(&req, &user.id, &db, &booking_req).await)
+}
+
+/// Turns the caller's seat holds into bookings. Holds that ran out are refused rather than
+/// booked from scratch, so the customer knows to pick seats again.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/confirm",
+    tag = "bookings",
+    request_body = ConfirmHoldsRequest,
+    responses(
+        (status = 201, description = "Created", body = [BookingConfirmation]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 410, description = "Gone", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn confirm_holds(
     req: HttpRequest,
+    user: AuthenticatedUser,
     db: web::Data<MongoDB>,
+    confirm_req: web::Json<ConfirmHoldsRequest>,
 ) -> Result<HttpResponse, Error> {
-    let user_id = match get_user_id_from_token(&req) {
-        Some(id) => id,
-        None => return Ok(HttpResponse::Unauthorized().json(json!({ "error": "Unauthorized" }))),
+    let Ok(user_oid) = mongodb::bson::oid::ObjectId::parse_str(&user.id) else {
+        return Err(AppError::from("Invalid user ID").into());
+    };
+    let holds = match db.get_live_holds(user_oid, &confirm_req.hold_ids).await {
+        Ok(holds) => holds,
+        Err(e) => return Err(e.into()),
     };
+    match confirm_req.booking_request(&holds) {
+        Ok(booking_req) => Ok(book_group(&req, &user.id, &db, &booking_req).await),
+        Err(e) if holds.len() < confirm_req.hold_ids.len() => Err(AppError::gone(e).into()),
+        Err(e) => Err(AppError::from(e).into()),
+    }
+}
 
-    match db.get_user_bookings(&user_id).await {
+async fn book_group(req: &HttpRequest, user_id: &str, db: &web::Data<MongoDB>, booking_req: &CreateGroupBookingRequest) -> HttpResponse {
+    let email = db.user_blocklist_email(user_id).await.ok().flatten();
+    if let Some(refusal) = enforce_blocklist(db, req, "booking", email.as_deref(), Some(user_id)).await {
+        return refusal.error_response();
+    }
+
+    match db.ensure_terms_accepted(user_id, booking_req.accepted_terms_version.as_deref(), "booking").await {
+        Ok(true) => {}
+        Ok(false) => return acceptance_required().error_response(),
+        Err(e) => return e.error_response(),
+    }
+
+    let result = db.create_group_booking(user_id, booking_req, request_country(req).as_deref()).await;
+    record_booking_outcome(db.get_ref().clone(), result.as_ref().err().map(|e| e.to_string()));
+
+    match result {
         Ok(bookings) => {
-            let mut detailed_bookings = Vec::new();
-            for b in bookings {
-                let bus = db.get_bus(&b.bus_id.to_hex()).await.ok().flatten();
-                detailed_bookings.push(json!({
-                    "id": b.id.map(|id| id.to_hex()),
-                    "busId": b.bus_id.to_hex(),
-                    "busName": bus.as_ref().map(|b| b.bus_number.clone()).unwrap_or_else(|| "Unknown Bus".to_string()),
-                    "busType": bus.as_ref().map(|b| b.bus_type.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                    "from": bus.as_ref().map(|b| b.route.from.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                    "to": bus.as_ref().map(|b| b.route.to.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                    "departure": bus.as_ref().map(|b| b.route.departure_time.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                    "arrival": bus.as_ref().map(|b| b.route.arrival_time.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                    "totalPrice": bus.as_ref().map(|b| b.route.price).unwrap_or(0.0),
-                    "seats": vec![b.seat_number.clone()],
-                    "status": b.status.to_lowercase(),
-                    "date": b.travel_date,
-                    "bookingDate": b.booking_date.to_string(), // Simple string representation
-                    "bookingId": b.id.map(|id| id.to_hex().to_uppercase()).unwrap_or_else(|| "N/A".to_string()),
-                    "passengers": if let Some(p) = b.passenger {
-                        vec![json!({ "name": p.name, "seatNumber": b.seat_number, "age": p.age, "gender": p.gender })]
-                    } else {
-                        vec![json!({ "name": "User", "seatNumber": b.seat_number, "age": "N/A", "gender": "N/A" })]
-                    }
-                }));
+            let language = requested_document_language(req, None);
+            let mut confirmations = Vec::new();
+            for (i, booking) in bookings.into_iter().enumerate() {
+                if let Err(e) = db.queue_booking_confirmation_sms(&booking, language).await {
+                    error!("Failed to queue booking confirmation SMS: {}", e);
+                }
+                if let Err(e) = db.queue_booking_confirmation_email(&booking, language).await {
+                    error!("Failed to queue booking confirmation email: {}", e);
+                }
+                // One offers email per group, not one per seat
+                let partner_offers = partner_offers(db, &booking, i == 0).await;
+                confirmations.push(BookingConfirmation::new(booking, partner_offers));
             }
-            Ok(HttpResponse::Ok().json(detailed_bookings))
+            HttpResponse::Created().json(confirmations)
+        }
+        Err(e) => e.error_response(),
+    }
+}
+
+// Shapes a booking into the camelCase structure the frontend renders
+// The body stays a plain array; the cursor for the next page travels in `X-Next-Cursor`
+async fn booking_page_response(db: &MongoDB, page: BookingPage) -> HttpResponse {
+    let mut detailed_bookings = Vec::new();
+    for b in page.bookings {
+        let bus = db.get_bus(&b.bus_id.to_hex()).await.ok().flatten();
+        detailed_bookings.push(BookingDetailResponse::from((b, bus)));
+    }
+
+    let mut response = HttpResponse::Ok();
+    if let Some(cursor) = page.next_cursor {
+        response.insert_header(("X-Next-Cursor", cursor));
+    }
+    response.json(detailed_bookings)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/user",
+    tag = "bookings",
+    params(BookingPageQuery),
+    responses(
+        (status = 200, description = "OK", body = [BookingDetailResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_user_bookings(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    query: web::Query<BookingPageQuery>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.get_user_bookings(&user_id, &query).await {
+        Ok(page) => Ok(booking_page_response(&db, page).await),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/bookings",
+    tag = "admin",
+    params(BookingSearchQuery),
+    responses(
+        (status = 200, description = "OK", body = [BookingDetailResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn search_bookings(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    query: web::Query<BookingSearchQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.search_bookings(&query, &tenant).await {
+        Ok(page) => Ok(booking_page_response(&db, page).await),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "OK", body = BookingDetailResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_booking_detail(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    let claims = user.claims;
+
+    // Support staff can open any booking within their tenancy; passengers only their own
+    let staff_access = match Tenant::from_claims(&claims) {
+        Ok(tenant) if authorize(&claims, BOOKINGS_READ) => db.ensure_booking_owned(&tenant, &booking_id).await.is_ok(),
+        _ => false,
+    };
+    let booking = if staff_access {
+        db.get_booking(&booking_id).await
+    } else {
+        db.get_user_booking(&booking_id, &claims.sub).await
+    };
+
+    match booking {
+        Ok(Some(b)) => {
+            let bus = db.get_bus(&b.bus_id.to_hex()).await.ok().flatten();
+            let timeline = b.timeline();
+            // Late readers still see what was broadcast to the trip
+            let announcements = db.get_trip_announcements(b.bus_id, &b.travel_date).await.unwrap_or_default();
+            let mut detail = BookingDetailResponse::from((b, bus));
+            detail.timeline = Some(timeline);
+            detail.announcements = Some(announcements.into_iter().map(AnnouncementResponse::from).collect());
+            Ok(HttpResponse::Ok().json(detail))
         },
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({ "error": e.to_string() }))),
+        Ok(None) => Err(AppError::not_found("Booking not found").into()),
+        Err(e) => Err(e.into()),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/bookings/{id}",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 pub async fn cancel_booking(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    let user_id = user.id;
+
+    match db.cancel_booking(&booking_id, &user_id, &format!("user:{}", user_id)).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({ "success": true, "message": "Booking cancelled successfully" }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Moves the passenger's own booking to another date, charging the amendment fee in its terms.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/{id}/change",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    request_body = ChangeBookingRequest,
+    responses(
+        (status = 200, description = "OK", body = BookingDetailResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Seat taken", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn change_booking(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<ChangeBookingRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.change_booking(&user.id, &path.into_inner(), &payload).await {
+        Ok(booking) => {
+            let bus = db.get_bus(&booking.bus_id.to_hex()).await.ok().flatten();
+            Ok(HttpResponse::Ok().json(BookingDetailResponse::from((booking, bus))))
+        },
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bookings/{id}/share",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    responses(
+        (status = 201, description = "Created", body = crate::models::booking::ShareLinkResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn share_booking(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    let user_id = user.id;
+
+    match db.create_share_link(&booking_id, &user_id).await {
+        Ok(link) => Ok(HttpResponse::Created().json(link)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/shared/{token}",
+    tag = "bookings",
+    params(("token" = String, Path, description = "Token from the link")),
+    responses(
+        (status = 200, description = "OK", body = crate::models::booking::SharedTripResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 410, description = "Gone", body = ErrorResponse),
+    ),
+)]
+pub async fn get_shared_booking(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let token = path.into_inner();
+    match db.get_shared_trip(&token).await {
+        Ok(Some(trip)) => Ok(HttpResponse::Ok().json(trip)),
+        Ok(None) => Err(AppError::gone("This booking is no longer available").into()),
+        Err(AppError::Unauthorized(_)) => Err(AppError::not_found("Share link is invalid or has expired").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}/receipt.pdf",
+    tag = "bookings",
+    params(
+        ("id" = String, Path, description = "Booking id"),
+        DocumentQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", content_type = "application/pdf", body = String),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_booking_receipt(
     req: HttpRequest,
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<DocumentQuery>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    let user_id = user.id;
+
+    match db.get_booking_receipt(&booking_id, &user_id, requested_document_language(&req, query.lang.as_deref())).await {
+        Ok(receipt) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                actix_web::http::header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"receipt-{}.pdf\"", receipt.reference),
+            ))
+            .insert_header((actix_web::http::header::CONTENT_LANGUAGE, receipt.language.clone()))
+            .body(receipt.to_pdf())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}/calendar.ics",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "OK", content_type = "text/calendar; charset=utf-8", body = String),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Trip times unknown", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_booking_calendar(
+    user: AuthenticatedUser,
     db: web::Data<MongoDB>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, Error> {
     let booking_id = path.into_inner();
-    let user_id = match get_user_id_from_token(&req) {
-        Some(id) => id,
-        None => return Ok(HttpResponse::Unauthorized().json(json!({ "error": "Unauthorized" }))),
+    let user_id = user.id;
+
+    let booking = match db.get_user_booking(&booking_id, &user_id).await {
+        Ok(Some(booking)) => booking,
+        Ok(None) => return Err(AppError::not_found("Booking not found").into()),
+        Err(e) => return Err(e.into()),
+    };
+    let bus = match db.get_bus(&booking.bus_id.to_hex()).await {
+        Ok(Some(bus)) => bus,
+        Ok(None) => return Err(AppError::not_found("Bus not found").into()),
+        Err(e) => return Err(e.into()),
     };
 
-    match db.cancel_booking(&booking_id, &user_id).await {
-        Ok(_) => Ok(HttpResponse::Ok().json(json!({ "success": true, "message": "Booking cancelled successfully" }))),
-        Err(e) => Ok(HttpResponse::BadRequest().json(json!({ "error": e.to_string() }))),
+    let (departure, arrival) = match (
+        bus.route.departure_at(&booking.travel_date),
+        bus.route.arrival_at(&booking.travel_date),
+    ) {
+        (Some(departure), Some(arrival)) => (departure, arrival),
+        _ => return Err(AppError::conflict("Unable to determine trip times for this booking").into()),
+    };
+
+    let reference = booking.display_reference();
+    let event = CalendarEvent {
+        uid: format!("{}@bus-book", booking_id),
+        summary: format!("Bus trip: {} → {}", bus.route.from, bus.route.to),
+        location: format!("{} boarding point", bus.route.from),
+        description: format!(
+            "Booking reference: {}\nBus: {} ({})\nSeat: {}",
+            reference, bus.bus_number, bus.bus_type, booking.seat_number
+        ),
+        start: departure.with_timezone(&chrono::Utc),
+        end: arrival.with_timezone(&chrono::Utc),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"booking-{}.ics\"", reference),
+        ))
+        .body(event.to_ics()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/bookings/{id}/reseat",
+    tag = "admin",
+    params(("id" = String, Path, description = "Booking id")),
+    request_body = ReseatRequest,
+    responses(
+        (status = 200, description = "OK", body = BookingDetailResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn reseat_booking(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<ReseatRequest>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    db.ensure_booking_owned(&tenant, &booking_id).await?;
+    // Moving a passenger onto another operator's bus is as much a cross-tenant write as editing it
+    if let Some(bus_id) = &payload.bus_id {
+        db.ensure_bus_owned(&tenant, bus_id).await?;
+    }
+    match db.reseat_booking(&booking_id, &payload).await {
+        Ok(booking) => {
+            let bus = db.get_bus(&booking.bus_id.to_hex()).await.ok().flatten();
+            Ok(HttpResponse::Ok().json(BookingDetailResponse::from((booking, bus))))
+        },
+        Err(e) => Err(e.into()),
     }
 }
@@ -1,41 +1,203 @@
-use actix_web::{web, HttpResponse, Error};
+use actix_web::{web, HttpResponse, Error, HttpRequest};
 use futures::StreamExt;
+use crate::config::InventoryConfig;
 use crate::db::MongoDB;
-use crate::models::bus::SeatDateQuery;
+use crate::error::AppError;
+use crate::models::bus::{BusDetailQuery, BusListQuery, BusRequest, BusResponse, BusSearchQuery, SeatDateQuery};
+use crate::middleware::auth::OptionalClaims;
+use crate::handlers::experiments::{assign_experiments, experiment_subject};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::geoip::request_country;
+use crate::models::tenancy::Tenant;
+use crate::models::localization::{accepted_language, preferred_language, supported_language};
+use crate::models::search_log::SearchLogEntry;
+use log::warn;
+use crate::openapi::ErrorResponse;
 
-pub async fn get_buses(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
-    let mut cursor = db.get_buses().await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+// Punctuality shown in search results covers the last 90 days
+const PUNCTUALITY_WINDOW_DAYS: i64 = 90;
+
+// Language requested by the client through Accept-Language
+pub(crate) fn request_language(req: &HttpRequest) -> &'static str {
+    let header = req.headers()
+        .get(actix_web::http::header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok());
+    preferred_language(header)
+}
+
+// Language asked for explicitly with `?lang=` or through Accept-Language; None leaves it to the
+// reader's or operator's default
+pub(crate) fn requested_document_language(req: &HttpRequest, lang: Option<&str>) -> Option<&'static str> {
+    lang.and_then(supported_language).or_else(|| {
+        accepted_language(req.headers().get(actix_web::http::header::ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()))
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/buses",
+    tag = "buses",
+    params(BusListQuery),
+    responses(
+        (status = 200, description = "OK", body = [crate::models::bus::BusResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_buses(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    query: web::Query<BusListQuery>,
+) -> Result<HttpResponse, Error> {
+    let localizer = db.get_localizer(request_language(&req)).await?;
+    let punctuality = db.get_route_punctuality(PUNCTUALITY_WINDOW_DAYS).await?;
+    let seats_left = match &query.date {
+        Some(date) => Some(db.get_seats_left(date, None).await?),
+        None => None,
+    };
+    let mut cursor = db.get_buses().await?;
     
     let mut buses = Vec::new();
     while let Some(result) = cursor.next().await {
         match result {
             Ok(bus) => {
-                let resp: crate::models::bus::BusResponse = bus.into();
-                buses.push(resp);
+                let key = (bus.operator_name(), bus.route.from.clone(), bus.route.to.clone());
+                let left = seats_left.as_ref().map(|counts| {
+                    bus.id.and_then(|id| counts.get(&id).copied()).unwrap_or(bus.total_seats)
+                });
+                let mut resp: crate::models::bus::BusResponse = bus.into();
+                resp.seats_left = left;
+                resp.punctuality = punctuality.get(&key).cloned();
+                buses.push(resp.localized(&localizer));
             },
-            Err(e) => return Err(actix_web::error::ErrorInternalServerError(e)),
+            Err(e) => return Err(AppError::from(e).into()),
         }
     }
+
+    if let Some(min_on_time) = query.min_on_time {
+        buses.retain(|b| b.punctuality.as_ref().is_some_and(|p| p.on_time_percent >= min_on_time));
+    }
+    if query.sort.as_deref() == Some("punctuality") {
+        // Most punctual first; routes without measurements go last
+        let score = |b: &crate::models::bus::BusResponse| b.punctuality.as_ref().map(|p| p.on_time_percent).unwrap_or(-1.0);
+        buses.sort_by(|a, b| score(b).partial_cmp(&score(a)).unwrap_or(std::cmp::Ordering::Equal));
+    }
     
     Ok(HttpResponse::Ok().json(buses))
 }
 
-pub async fn get_bus(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+/// Buses on one route, matched in the database rather than by the client.
+#[utoipa::path(
+    get,
+    path = "/api/buses/search",
+    tag = "buses",
+    params(BusSearchQuery),
+    responses(
+        (status = 200, description = "OK", body = [crate::models::bus::BusResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn search_buses(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    query: web::Query<BusSearchQuery>,
+) -> Result<HttpResponse, Error> {
+    if query.from.trim().is_empty() || query.to.trim().is_empty() {
+        return Err(AppError::from("Both from and to are required").into());
+    }
+    if let Some(date) = &query.date {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(AppError::from("date must be YYYY-MM-DD").into());
+        }
+    }
+
+    let buses = db.search_buses(&query).await?;
+    let localizer = db.get_localizer(request_language(&req)).await?;
+    let punctuality = db.get_route_punctuality(PUNCTUALITY_WINDOW_DAYS).await?;
+    let seats_left = match &query.date {
+        Some(date) => Some(db.get_seats_left(date, None).await?),
+        None => None,
+    };
+
+    if let Some(date) = &query.date {
+        let (from, to) = (query.from.trim().to_string(), query.to.trim().to_string());
+        log_route_search(&req, db.get_ref().clone(), from, to, date.clone(), buses.len() as i64);
+    }
+
+    let results: Vec<_> = buses.into_iter().map(|bus| {
+        let key = (bus.operator_name(), bus.route.from.clone(), bus.route.to.clone());
+        let left = seats_left.as_ref().map(|counts| {
+            bus.id.and_then(|id| counts.get(&id).copied()).unwrap_or(bus.total_seats)
+        });
+        let mut resp: crate::models::bus::BusResponse = bus.into();
+        resp.seats_left = left;
+        resp.punctuality = punctuality.get(&key).cloned();
+        resp.localized(&localizer)
+    }).collect();
+    Ok(HttpResponse::Ok().json(results))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/buses/{id}",
+    tag = "buses",
+    params(
+        ("id" = String, Path, description = "Bus id"),
+        BusDetailQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", body = crate::models::bus::BusResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_bus(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<BusDetailQuery>,
+) -> Result<HttpResponse, Error> {
     let id = path.into_inner();
-    let bus = db.get_bus(&id).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let bus = db.get_bus(&id).await?;
     
     match bus {
         Some(bus) => {
-            let resp: crate::models::bus::BusResponse = bus.into();
-            Ok(HttpResponse::Ok().json(resp))
+            let localizer = db.get_localizer(request_language(&req)).await?;
+            let seats_left = match (&query.date, bus.id) {
+                (Some(date), Some(bus_id)) => Some(
+                    db.get_seats_left(date, Some(bus_id)).await?
+                        .get(&bus_id)
+                        .copied()
+                        .unwrap_or(bus.total_seats)
+                ),
+                _ => None,
+            };
+            let mut resp: crate::models::bus::BusResponse = bus.into();
+            resp.seats_left = seats_left;
+            if let Some(subject) = experiment_subject(&req) {
+                resp.experiments = assign_experiments(&db, &subject, "bus_detail").await;
+            }
+            Ok(HttpResponse::Ok().json(resp.localized(&localizer)))
         },
         None => Ok(HttpResponse::NotFound().body("Bus not found")),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/buses/{id}/seats",
+    tag = "buses",
+    params(
+        ("id" = String, Path, description = "Bus id"),
+        SeatDateQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", body = crate::models::bus::SeatAvailabilityResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
 pub async fn get_bus_seats(
+    req: HttpRequest,
     db: web::Data<MongoDB>,
     path: web::Path<String>,
     query: web::Query<SeatDateQuery>,
@@ -43,13 +205,192 @@ pub async fn get_bus_seats(
     let bus_id = path.into_inner();
     let seat_date = query.date.clone();
     
-    let seats = db.get_bus_seats(&bus_id, &seat_date).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    // Trips sold by headcount have no seat map, only a count of places
+    let bus = db.get_bus(&bus_id).await?;
+    let (seats, headcount, layout) = match bus {
+        Some(bus) if InventoryConfig::from_env().sells_by_headcount(&bus.bus_type) => {
+            let headcount = db.get_trip_headcount(&bus, &seat_date).await?;
+            (Vec::new(), Some(headcount), None)
+        }
+        bus => {
+            let seats = db.get_bus_seats(&bus_id, &seat_date).await?;
+            // A swapped-in vehicle has plain numbered seats instead of the bus's layout
+            let layout = match bus.and_then(|b| b.id.zip(b.layout)) {
+                Some((bus_oid, layout)) => {
+                    let swapped = db.get_trip_vehicle(bus_oid, &seat_date).await?
+                        .is_some();
+                    (!swapped).then_some(layout)
+                }
+                None => None,
+            };
+            (seats, None, layout)
+        }
+    };
+
+    let available = match &headcount {
+        Some(headcount) => headcount.available as i64,
+        None => seats.iter().filter(|s| s.is_available).count() as i64,
+    };
+    log_search(&req, db.get_ref().clone(), bus_id, seat_date.clone(), available);
     
     let response = crate::models::bus::SeatAvailabilityResponse {
         travel_date: seat_date,
         seats,
+        headcount,
+        layout,
     };
     
     Ok(HttpResponse::Ok().json(response))
-}
\ No newline at end of file
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/buses",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [crate::models::bus::BusResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_fleet(db: web::Data<MongoDB>, tenant: web::ReqData<Tenant>) -> Result<HttpResponse, Error> {
+    match db.list_fleet(&tenant).await {
+        Ok(buses) => Ok(HttpResponse::Ok().json(buses.into_iter().map(BusResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/buses",
+    tag = "admin",
+    request_body = BusRequest,
+    responses(
+        (status = 201, description = "Created", body = crate::models::bus::BusResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_bus(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<BusRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.create_bus(&tenant, &payload, &user.actor()).await {
+        Ok(bus) => Ok(HttpResponse::Created().json(BusResponse::from(bus))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/buses/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    request_body = BusRequest,
+    responses(
+        (status = 200, description = "OK", body = crate::models::bus::BusResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_bus(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<BusRequest>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.update_bus(&tenant, &bus_id, &payload, &user.actor()).await {
+        Ok(bus) => Ok(HttpResponse::Ok().json(BusResponse::from(bus))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/buses/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_bus(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.delete_bus(&bus_id, &user.actor()).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Who searched: the signed-in user or anonymous session, and where from
+fn searcher(req: &HttpRequest) -> (Option<String>, Option<String>, Option<String>) {
+    let anon_id = req.headers()
+        .get("X-Anonymous-Id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    (OptionalClaims::of(req).user_id(), anon_id, request_country(req))
+}
+
+// Records a route search in the search log without delaying the response
+fn log_route_search(req: &HttpRequest, db: MongoDB, from: String, to: String, travel_date: String, results_count: i64) {
+    let (user_id, anon_id, country) = searcher(req);
+    actix_web::rt::spawn(async move {
+        let entry = SearchLogEntry {
+            id: None,
+            from,
+            to,
+            travel_date,
+            departure_time: None,
+            results_count,
+            user_id,
+            anon_id,
+            country,
+            created_at: mongodb::bson::DateTime::now(),
+        };
+        if let Err(e) = db.log_search(&entry).await {
+            warn!("Failed to log search: {}", e);
+        }
+    });
+}
+
+// Records an availability lookup in the search log without delaying the response
+fn log_search(req: &HttpRequest, db: MongoDB, bus_id: String, travel_date: String, results_count: i64) {
+    let (user_id, anon_id, country) = searcher(req);
+
+    actix_web::rt::spawn(async move {
+        let bus = match db.get_bus(&bus_id).await {
+            Ok(Some(bus)) => bus,
+            _ => return,
+        };
+        let entry = SearchLogEntry {
+            id: None,
+            from: bus.route.from,
+            to: bus.route.to,
+            travel_date,
+            departure_time: Some(bus.route.departure_time),
+            results_count,
+            user_id,
+            anon_id,
+            country,
+            created_at: mongodb::bson::DateTime::now(),
+        };
+        if let Err(e) = db.log_search(&entry).await {
+            warn!("Failed to log search: {}", e);
+        }
+    });
+}
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpResponse, Error};
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::consent::PromotionRequest;
+use crate::openapi::ErrorResponse;
+
+// Only users who opted in on the channel are messaged; see db::consent
+#[utoipa::path(
+    post,
+    path = "/api/admin/promotions",
+    tag = "admin",
+    request_body = PromotionRequest,
+    responses(
+        (status = 202, description = "Accepted"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn send_promotion(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<PromotionRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.send_promotion(&payload, &format!("user:{}", user_id)).await {
+        Ok(queued) => Ok(HttpResponse::Accepted().json(json!({ "queued": queued }))),
+        Err(e) => Err(e.into()),
+    }
+}
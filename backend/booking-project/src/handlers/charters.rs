@@ -0,0 +1,214 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::charter::{
+    AcceptCharterRequest, Charter, CharterAcceptanceResponse, CharterListQuery, CharterQuoteRequest, CharterRequest,
+    CharterResponse, DeclineCharterRequest,
+};
+use crate::models::payment::PaymentResponse;
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+fn charter_json(charter: Charter) -> CharterResponse {
+    CharterResponse::new(charter, chrono::Utc::now())
+}
+
+/// Asks operators to quote for hiring a whole bus for a group.
+#[utoipa::path(
+    post,
+    path = "/api/charters",
+    tag = "charters",
+    request_body = CharterRequest,
+    responses(
+        (status = 201, description = "Created", body = CharterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn request_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<CharterRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.request_charter(&user.id, &payload).await {
+        Ok(charter) => Ok(HttpResponse::Created().json(charter_json(charter))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/charters",
+    tag = "charters",
+    responses(
+        (status = 200, description = "OK", body = [CharterResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_charters(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    match db.list_user_charters(&user.id).await {
+        Ok(charters) => Ok(HttpResponse::Ok().json(charters.into_iter().map(charter_json).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A charter with its quote, e.g. to poll until the deposit goes through.
+#[utoipa::path(
+    get,
+    path = "/api/charters/{id}",
+    tag = "charters",
+    params(("id" = String, Path, description = "Charter id")),
+    responses(
+        (status = 200, description = "OK", body = CharterResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_user_charter(&user.id, &path.into_inner()).await {
+        Ok(Some(charter)) => Ok(HttpResponse::Ok().json(charter_json(charter))),
+        Ok(None) => Err(AppError::not_found("Charter not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Accepts the quote. The bus is taken off sale straight away and reserved once the customer
+/// approves the M-Pesa prompt for the deposit.
+#[utoipa::path(
+    post,
+    path = "/api/charters/{id}/accept",
+    tag = "charters",
+    params(("id" = String, Path, description = "Charter id")),
+    request_body = AcceptCharterRequest,
+    responses(
+        (status = 202, description = "Accepted", body = CharterAcceptanceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn accept_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<AcceptCharterRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.accept_charter(&user.id, &path.into_inner(), &payload).await {
+        Ok((charter, payment)) => Ok(HttpResponse::Accepted().json(CharterAcceptanceResponse {
+            charter: charter_json(charter),
+            payment: PaymentResponse::from(payment),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/charters/{id}",
+    tag = "charters",
+    params(("id" = String, Path, description = "Charter id")),
+    responses(
+        (status = 200, description = "OK", body = CharterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn cancel_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.cancel_charter(&user.id, &path.into_inner()).await {
+        Ok(charter) => Ok(HttpResponse::Ok().json(charter_json(charter))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Charter requests waiting for a quote, and the ones the operator quoted.
+#[utoipa::path(
+    get,
+    path = "/api/admin/charters",
+    tag = "admin",
+    params(CharterListQuery),
+    responses(
+        (status = 200, description = "OK", body = [CharterResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_charter_requests(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    query: web::Query<CharterListQuery>,
+) -> Result<HttpResponse, Error> {
+    let status = match query.status() {
+        Ok(status) => status,
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+    match db.list_charters(&tenant, status).await {
+        Ok(charters) => Ok(HttpResponse::Ok().json(charters.into_iter().map(charter_json).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Quotes a price for the charter with one of the operator's buses.
+#[utoipa::path(
+    put,
+    path = "/api/admin/charters/{id}/quote",
+    tag = "admin",
+    params(("id" = String, Path, description = "Charter id")),
+    request_body = CharterQuoteRequest,
+    responses(
+        (status = 200, description = "OK", body = CharterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn quote_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<CharterQuoteRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.quote_charter(&tenant, &path.into_inner(), &payload, &user.actor()).await {
+        Ok(charter) => Ok(HttpResponse::Ok().json(charter_json(charter))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/charters/{id}/decline",
+    tag = "admin",
+    params(("id" = String, Path, description = "Charter id")),
+    request_body = DeclineCharterRequest,
+    responses(
+        (status = 200, description = "OK", body = CharterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn decline_charter(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<DeclineCharterRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.decline_charter(&tenant, &path.into_inner(), &payload.reason, &user.actor()).await {
+        Ok(charter) => Ok(HttpResponse::Ok().json(charter_json(charter))),
+        Err(e) => Err(e.into()),
+    }
+}
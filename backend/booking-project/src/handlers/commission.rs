@@ -0,0 +1,72 @@
+use actix_web::{web, HttpResponse, Error};
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::commission::{CommissionRuleQuery, CommissionRuleResponse, UpsertCommissionRuleRequest};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/commission-rules",
+    tag = "admin",
+    params(CommissionRuleQuery),
+    responses(
+        (status = 200, description = "OK", body = [CommissionRuleResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_commission_rules(
+    db: web::Data<MongoDB>,
+    query: web::Query<CommissionRuleQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_commission_rules(query.operator.as_deref()).await {
+        Ok(rules) => Ok(HttpResponse::Ok().json(rules.into_iter().map(CommissionRuleResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/commission-rules",
+    tag = "admin",
+    request_body = UpsertCommissionRuleRequest,
+    responses(
+        (status = 200, description = "OK", body = CommissionRuleResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn upsert_commission_rule(
+    db: web::Data<MongoDB>,
+    req: web::Json<UpsertCommissionRuleRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.upsert_commission_rule(&req).await {
+        Ok(rule) => Ok(HttpResponse::Ok().json(CommissionRuleResponse::from(rule))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/commission-rules/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Commission rule id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_commission_rule(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.delete_commission_rule(&path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Ok(false) => Err(AppError::not_found("Commission rule not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,39 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::compare::RouteCompareQuery;
+use crate::openapi::ErrorResponse;
+
+/// Operators on a route with their prices and track record, for the comparison table.
+#[utoipa::path(
+    get,
+    path = "/api/routes/compare",
+    tag = "routes",
+    params(RouteCompareQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn compare_route(
+    db: web::Data<MongoDB>,
+    query: web::Query<RouteCompareQuery>,
+) -> Result<HttpResponse, Error> {
+    if query.from.trim().is_empty() || query.to.trim().is_empty() {
+        return Err(AppError::from("Both from and to are required").into());
+    }
+    if let Some(date) = &query.date {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(AppError::from("date must be YYYY-MM-DD").into());
+        }
+    }
+
+    match db.compare_route(&query).await {
+        Ok(comparison) => Ok(HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "public, max-age=60"))
+            .json(comparison)),
+        Err(e) => Err(e.into()),
+    }
+}
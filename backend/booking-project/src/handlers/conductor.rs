@@ -0,0 +1,302 @@
+use actix_web::{web, HttpMessage, HttpResponse, Error, HttpRequest, http};
+use serde_json::json;
+
+use crate::config::OfflineSyncConfig;
+use crate::db::manifest::verify_trip_token;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::handlers::buses::requested_document_language;
+use crate::middleware::auth::{bearer_token, AuthenticatedUser};
+use crate::middleware::session::session_refusal;
+use crate::models::device::SignedDevice;
+use crate::models::localization::DocumentQuery;
+use crate::models::manifest::{CheckInRequest, PrintableManifest, TripAccessClaims};
+use crate::models::offline_sync::{OfflineManifest, ReconciliationReport, SyncUploadRequest};
+use crate::models::tenancy::Tenant;
+use crate::models::trip::TripLocationRequest;
+use crate::openapi::ErrorResponse;
+
+// Conductor actions only accept the trip token from `open_manifest`, never a login token
+pub(crate) async fn trip_access(req: &HttpRequest, db: &MongoDB, bus_id: &str, travel_date: &str) -> Result<TripAccessClaims, AppError> {
+    trip_access_within(req, db, bus_id, travel_date, 0).await
+}
+
+async fn trip_access_within(req: &HttpRequest, db: &MongoDB, bus_id: &str, travel_date: &str, grace_secs: u64) -> Result<TripAccessClaims, AppError> {
+    let token = req.headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(bearer_token)
+        .ok_or_else(|| AppError::unauthorized("Trip token required"))?;
+    let claims = verify_trip_token(token, bus_id, travel_date, grace_secs)
+        .map_err(|e| AppError::forbidden(e.to_string()))?;
+    if let Some(refusal) = session_refusal(db.is_session_revoked(&claims.sub, claims.iat).await) {
+        return Err(refusal);
+    }
+    // A handset signs for its own operator's trips only, whatever trip token it presents
+    let device = req.extensions().get::<SignedDevice>().cloned();
+    if let Some(device) = device {
+        let bus = db.get_bus(bus_id).await?.ok_or_else(|| AppError::not_found("Bus not found"))?;
+        if !device.serves(&bus) {
+            return Err(AppError::forbidden("This device is not provisioned for this trip's operator"));
+        }
+    }
+    Ok(claims)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/trips/{bus_id}/{date}/manifest",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn open_manifest(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    let claims = user.claims;
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+
+    match db.open_manifest(&claims, &bus_id, &travel_date).await {
+        Ok(opened) => Ok(HttpResponse::Ok().json(opened)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conductor/trips/{bus_id}/{date}/manifest",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn get_trip_manifest(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    trip_access(&req, &db, &bus_id, &travel_date).await?;
+
+    match db.get_manifest(&bus_id, &travel_date).await {
+        Ok(manifest) => Ok(HttpResponse::Ok().json(manifest)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/conductor/trips/{bus_id}/{date}/check-in",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = CheckInRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn check_in(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<CheckInRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    let claims = trip_access(&req, &db, &bus_id, &travel_date).await?;
+
+    match db.check_in_passenger(&claims, &payload.booking_id).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The bus's position from the crew's handset GPS. Fixes may arrive late and out of order;
+/// only the newest is kept.
+#[utoipa::path(
+    post,
+    path = "/api/conductor/trips/{bus_id}/{date}/location",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = TripLocationRequest,
+    responses(
+        (status = 204, description = "Recorded"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+    ),
+)]
+pub async fn report_location(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<TripLocationRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    let claims = trip_access(&req, &db, &bus_id, &travel_date).await?;
+
+    let device = req.extensions().get::<SignedDevice>().map(|d| d.device_id.clone());
+    match db.record_trip_location(&claims, &payload, device.as_deref()).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The trip for boarding offline: confirmed passengers with their QR hashes, the seats still
+/// free and the fare for cash sales. Download it before leaving coverage.
+#[utoipa::path(
+    get,
+    path = "/api/conductor/trips/{bus_id}/{date}/sync",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = OfflineManifest),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+    ),
+)]
+pub async fn download_offline_manifest(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    let claims = trip_access(&req, &db, &bus_id, &travel_date).await?;
+
+    let token_expiry = chrono::DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(chrono::Utc::now);
+    let upload_until = token_expiry + chrono::Duration::hours(OfflineSyncConfig::from_env().grace_hours);
+    match db.get_offline_manifest(&claims, upload_until).await {
+        Ok(manifest) => Ok(HttpResponse::Ok().json(manifest)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Uploads the check-ins and cash sales made offline. Each item is applied once however often
+/// the batch is sent; the report says how each landed and what cash is owed back.
+#[utoipa::path(
+    post,
+    path = "/api/conductor/trips/{bus_id}/{date}/sync",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = SyncUploadRequest,
+    responses(
+        (status = 200, description = "OK", body = ReconciliationReport),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+    ),
+)]
+pub async fn upload_offline_batch(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<SyncUploadRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    // The crew may only reach coverage well after the shift, when the trip token has run out
+    let grace_secs = OfflineSyncConfig::from_env().grace_hours as u64 * 3600;
+    let claims = trip_access_within(&req, &db, &bus_id, &travel_date, grace_secs).await?;
+
+    let device = req.extensions().get::<SignedDevice>().map(|d| d.device_id.clone());
+    match db.sync_offline_batch(&claims, &payload, device.as_deref()).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn manifest_pdf_response(manifest: PrintableManifest) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header((
+            http::header::CONTENT_DISPOSITION,
+            format!("inline; filename=\"manifest-{}-{}.pdf\"", manifest.vehicle.replace(' ', ""), manifest.travel_date),
+        ))
+        .body(manifest.to_pdf())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/trips/{bus_id}/{date}/manifest.pdf",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+        DocumentQuery,
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_manifest_pdf(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    query: web::Query<DocumentQuery>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+
+    match db.get_printable_manifest(&bus_id, &travel_date, requested_document_language(&req, query.lang.as_deref())).await {
+        Ok(manifest) => Ok(manifest_pdf_response(manifest)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/conductor/trips/{bus_id}/{date}/manifest.pdf",
+    tag = "conductor",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+        DocumentQuery,
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn get_trip_manifest_pdf(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+    query: web::Query<DocumentQuery>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    trip_access(&req, &db, &bus_id, &travel_date).await?;
+
+    match db.get_printable_manifest(&bus_id, &travel_date, requested_document_language(&req, query.lang.as_deref())).await {
+        Ok(manifest) => Ok(manifest_pdf_response(manifest)),
+        Err(e) => Err(e.into()),
+    }
+}
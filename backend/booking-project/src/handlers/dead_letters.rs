@@ -0,0 +1,136 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::outbox::{DeadLetterQuery, DeadLetterResponse, UpdateDeadLetterRequest};
+use serde_json::json;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/dead-letters",
+    tag = "admin",
+    params(DeadLetterQuery),
+    responses(
+        (status = 200, description = "OK", body = [DeadLetterResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_dead_letters(
+    db: web::Data<MongoDB>,
+    query: web::Query<DeadLetterQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_dead_letters(query.channel.as_deref()).await {
+        Ok(dead_letters) => Ok(HttpResponse::Ok().json(dead_letters.into_iter().map(DeadLetterResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/dead-letters/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Dead letter id")),
+    responses(
+        (status = 200, description = "OK", body = DeadLetterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_dead_letter(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.get_dead_letter(&path.into_inner()).await {
+        Ok(Some(dead_letter)) => Ok(HttpResponse::Ok().json(DeadLetterResponse::from(dead_letter))),
+        Ok(None) => Err(AppError::not_found("Dead letter not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/dead-letters/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Dead letter id")),
+    request_body = UpdateDeadLetterRequest,
+    responses(
+        (status = 200, description = "OK", body = DeadLetterResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_dead_letter(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<UpdateDeadLetterRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.update_dead_letter(&path.into_inner(), &payload).await {
+        Ok(dead_letter) => Ok(HttpResponse::Ok().json(DeadLetterResponse::from(dead_letter))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/dead-letters/{id}/requeue",
+    tag = "admin",
+    params(("id" = String, Path, description = "Dead letter id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn requeue_dead_letter(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.requeue_dead_letter(&path.into_inner()).await {
+        Ok(outbox_id) => Ok(HttpResponse::Ok().json(json!({ "success": true, "outbox_id": outbox_id.to_hex() }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/dead-letters/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Dead letter id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_dead_letter(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.delete_dead_letter(&path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Ok(false) => Err(AppError::not_found("Dead letter not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/dead-letters/metrics",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_queue_metrics(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_outbox_depths().await {
+        Ok((pending, dead_by_channel)) => {
+            let dead_total: u64 = dead_by_channel.iter().map(|(_, depth)| depth).sum();
+            let by_channel: serde_json::Map<String, serde_json::Value> = dead_by_channel
+                .into_iter()
+                .map(|(channel, depth)| (channel, json!(depth)))
+                .collect();
+            Ok(HttpResponse::Ok().json(json!({
+                "outbox_pending": pending,
+                "dead_letter_depth": dead_total,
+                "dead_letter_depth_by_channel": by_channel,
+            })))
+        },
+        Err(e) => Err(e.into()),
+    }
+}
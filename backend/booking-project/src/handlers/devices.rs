@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::device::{DeviceResponse, ProvisionDeviceRequest, ProvisionedDeviceResponse, RevokeDeviceRequest};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+/// Registers a crew handset. The response carries the device's signing key, which can't be
+/// read back later: load it onto the handset straight away.
+#[utoipa::path(
+    post,
+    path = "/api/admin/devices",
+    tag = "admin",
+    request_body = ProvisionDeviceRequest,
+    responses(
+        (status = 201, description = "Created", body = ProvisionedDeviceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn provision_device(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<ProvisionDeviceRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.provision_device(&tenant, &payload, &user.actor()).await {
+        Ok((device, key)) => Ok(HttpResponse::Created().json(ProvisionedDeviceResponse { device: DeviceResponse::from(device), key })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The operator's handsets, newest first, revoked ones included.
+#[utoipa::path(
+    get,
+    path = "/api/admin/devices",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [DeviceResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_devices(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+) -> Result<HttpResponse, Error> {
+    match db.list_devices(&tenant).await {
+        Ok(devices) => Ok(HttpResponse::Ok().json(devices.into_iter().map(DeviceResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Cuts off a lost or stolen handset; every request it signs is refused from then on.
+#[utoipa::path(
+    post,
+    path = "/api/admin/devices/{id}/revoke",
+    tag = "admin",
+    params(("id" = String, Path, description = "Device id")),
+    request_body = RevokeDeviceRequest,
+    responses(
+        (status = 200, description = "OK", body = DeviceResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_device(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<RevokeDeviceRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.revoke_device(&tenant, &path.into_inner(), payload.reason.as_deref(), &user.actor()).await {
+        Ok(device) => Ok(HttpResponse::Ok().json(DeviceResponse::from(device))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,45 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::duplicates::MergeAccountsRequest;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/duplicates",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_duplicate_accounts(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.find_duplicate_accounts().await {
+        Ok(groups) => Ok(HttpResponse::Ok().json(groups)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/merge",
+    tag = "admin",
+    request_body = MergeAccountsRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn merge_accounts(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<MergeAccountsRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.merge_accounts(&payload, &user.actor()).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(result)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use actix_web::{cookie::Cookie, web, HttpResponse, Error, HttpRequest};
+use log::warn;
+use crate::db::MongoDB;
+use crate::middleware::auth::OptionalClaims;
+use crate::models::experiment::UpsertExperimentRequest;
+use serde_json::json;
+use crate::openapi::ErrorResponse;
+
+pub(crate) const ANON_COOKIE: &str = "anon_id";
+
+// Bucketing subject: the signed-in user, else the anonymous browser id
+pub(crate) fn experiment_subject(req: &HttpRequest) -> Option<String> {
+    if let Some(user_id) = OptionalClaims::of(req).user_id() {
+        return Some(format!("user:{}", user_id));
+    }
+    req.cookie(ANON_COOKIE)
+        .map(|c| c.value().to_string())
+        .or_else(|| req.headers().get("X-Anonymous-Id").and_then(|v| v.to_str().ok()).map(|v| v.to_string()))
+        .map(|anon| format!("anon:{}", anon))
+}
+
+/// Assigns the subject to every active experiment and logs the exposures in the background.
+pub(crate) async fn assign_experiments(db: &MongoDB, subject: &str, context: &str) -> HashMap<String, String> {
+    let experiments = match db.get_experiments(true).await {
+        Ok(experiments) => experiments,
+        Err(e) => {
+            warn!("Failed to load experiments: {}", e);
+            return HashMap::new();
+        }
+    };
+
+    let assignments: HashMap<String, String> = experiments
+        .iter()
+        .filter_map(|e| e.assign(subject).map(|v| (e.key.clone(), v.name.clone())))
+        .collect();
+
+    let db = db.clone();
+    let (subject, context, logged) = (subject.to_string(), context.to_string(), assignments.clone());
+    actix_web::rt::spawn(async move {
+        for (experiment, variant) in logged {
+            if let Err(e) = db.record_exposure(&experiment, &variant, &subject, &context).await {
+                warn!("Failed to record exposure for {}: {}", experiment, e);
+            }
+        }
+    });
+
+    assignments
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/experiments/assignments",
+    tag = "experiments",
+    responses(
+        (status = 200, description = "OK"),
+    ),
+)]
+pub async fn get_assignments(req: HttpRequest, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    // First-time anonymous visitors get a stable id so their bucket survives page loads
+    let mut new_anon_id = None;
+    let subject = match experiment_subject(&req) {
+        Some(subject) => subject,
+        None => {
+            let anon_id = mongodb::bson::oid::ObjectId::new().to_hex();
+            new_anon_id = Some(anon_id.clone());
+            format!("anon:{}", anon_id)
+        }
+    };
+
+    let assignments = assign_experiments(&db, &subject, "assignments").await;
+    let mut response = HttpResponse::Ok();
+    if let Some(anon_id) = new_anon_id {
+        response.cookie(
+            Cookie::build(ANON_COOKIE, anon_id)
+                .path("/")
+                .http_only(true)
+                .max_age(actix_web::cookie::time::Duration::days(365))
+                .finish(),
+        );
+    }
+    Ok(response.json(json!({ "subject": subject, "assignments": assignments })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/experiments",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_experiments(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_experiments(false).await {
+        Ok(experiments) => Ok(HttpResponse::Ok().json(experiments.into_iter().map(|e| json!({
+            "key": e.key,
+            "description": e.description,
+            "variants": e.variants,
+            "active": e.active,
+            "updated_at": e.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        })).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/experiments",
+    tag = "admin",
+    request_body = UpsertExperimentRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn upsert_experiment(
+    db: web::Data<MongoDB>,
+    payload: web::Json<UpsertExperimentRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.upsert_experiment(&payload).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Err(e) => Err(e.into()),
+    }
+}
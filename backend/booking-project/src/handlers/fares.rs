@@ -0,0 +1,105 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::fare::{FareQuoteQuery, FareTableRequest, FareTableResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/buses/{id}/fares",
+    tag = "buses",
+    params(("id" = String, Path, description = "Bus id")),
+    responses(
+        (status = 200, description = "OK", body = FareTableResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+pub async fn get_fare_table(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.get_fare_table(&path.into_inner()).await {
+        Ok(Some(table)) => Ok(HttpResponse::Ok().json(FareTableResponse::from(table))),
+        Ok(None) => Err(AppError::not_found("This bus has no fare table").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/buses/{id}/quote",
+    tag = "buses",
+    params(
+        ("id" = String, Path, description = "Bus id"),
+        FareQuoteQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", body = crate::models::fare::FareQuote),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn quote_fare(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<FareQuoteQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.quote_bus_fare(&path.into_inner(), query.from.as_deref(), query.to.as_deref()).await {
+        Ok(quote) => Ok(HttpResponse::Ok().json(quote)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/buses/{id}/fares",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    request_body = FareTableRequest,
+    responses(
+        (status = 200, description = "OK", body = FareTableResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_fare_table(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<FareTableRequest>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.set_fare_table(&bus_id, payload.into_inner(), &user.actor()).await {
+        Ok(table) => Ok(HttpResponse::Ok().json(FareTableResponse::from(table))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/buses/{id}/fares",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_fare_table(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.delete_fare_table(&bus_id, &user.actor()).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(AppError::not_found("This bus has no fare table").into()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,52 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::feed::{render_routes_xml, RouteFeedResponse};
+use crate::openapi::ErrorResponse;
+
+// Served from the snapshot written by the feed job; only computed inline before the first run
+async fn route_feed(db: &MongoDB) -> Result<RouteFeedResponse, AppError> {
+    let feed = match db.get_route_feed().await? {
+        Some(feed) => feed,
+        None => db.refresh_route_feed().await?,
+    };
+    Ok(RouteFeedResponse::from(feed))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds/routes.xml",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "OK", content_type = "application/xml; charset=utf-8", body = String),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_routes_xml(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match route_feed(&db).await {
+        Ok(feed) => Ok(HttpResponse::Ok()
+            .content_type("application/xml; charset=utf-8")
+            .insert_header((header::CACHE_CONTROL, "public, max-age=900"))
+            .body(render_routes_xml(&feed))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds/routes.json",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_routes_json(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match route_feed(&db).await {
+        Ok(feed) => Ok(HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "public, max-age=900"))
+            .json(feed)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,185 @@
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use log::error;
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::booking::BookingDetailResponse;
+use crate::models::fraud::{BlocklistEntryResponse, BlocklistQuery, BlocklistRequest, EnforcementDecisionResponse, ReviewDecisionRequest};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+// Fingerprint computed client-side and sent on every request
+pub(crate) fn device_fingerprint(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("X-Device-Fingerprint")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .filter(|v| !v.is_empty())
+}
+
+/// The refusal when the email, device or account is blocklisted; lookups fail open.
+pub(crate) async fn enforce_blocklist(
+    db: &MongoDB,
+    req: &HttpRequest,
+    context: &str,
+    email: Option<&str>,
+    user_id: Option<&str>,
+) -> Option<AppError> {
+    let device = device_fingerprint(req);
+    let mut subjects = Vec::new();
+    if let Some(email) = email {
+        subjects.push(("email", email));
+    }
+    if let Some(device) = device.as_deref() {
+        subjects.push(("device", device));
+    }
+
+    let user_oid = user_id.and_then(|id| db.string_to_id(id).ok());
+    match db.check_blocklist(context, &subjects, user_oid).await {
+        Ok(Some(_)) => Some(AppError::Blocked),
+        Ok(None) => None,
+        Err(e) => {
+            error!("Blocklist check failed during {}: {}", context, e);
+            None
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/blocklist",
+    tag = "admin",
+    params(BlocklistQuery),
+    responses(
+        (status = 200, description = "OK", body = [BlocklistEntryResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_blocklist(
+    db: web::Data<MongoDB>,
+    query: web::Query<BlocklistQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_blocklist(query.kind.as_deref()).await {
+        Ok(entries) => Ok(HttpResponse::Ok().json(entries.into_iter().map(BlocklistEntryResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/blocklist",
+    tag = "admin",
+    request_body = BlocklistRequest,
+    responses(
+        (status = 201, description = "Created", body = BlocklistEntryResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn add_blocklist_entry(
+    db: web::Data<MongoDB>,
+    payload: web::Json<BlocklistRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.add_blocklist_entry(&payload, "admin").await {
+        Ok(entry) => Ok(HttpResponse::Created().json(BlocklistEntryResponse::from(entry))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/blocklist/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Blocklist entry id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_blocklist_entry(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.remove_blocklist_entry(&path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Ok(false) => Err(AppError::not_found("Blocklist entry not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/blocklist/decisions",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [EnforcementDecisionResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_decisions(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_fraud_decisions(500).await {
+        Ok(decisions) => Ok(HttpResponse::Ok().json(decisions.into_iter().map(EnforcementDecisionResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/bookings/review",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_review_queue(db: web::Data<MongoDB>, tenant: web::ReqData<Tenant>) -> Result<HttpResponse, Error> {
+    let bookings = match db.get_review_queue(&tenant).await {
+        Ok(bookings) => bookings,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut queue = Vec::new();
+    for booking in bookings {
+        let risk = booking.risk.clone();
+        let bus = db.get_bus(&booking.bus_id.to_hex()).await.ok().flatten();
+        queue.push(json!({
+            "booking": BookingDetailResponse::from((booking, bus)),
+            "risk": risk,
+        }));
+    }
+    Ok(HttpResponse::Ok().json(queue))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/bookings/{id}/review",
+    tag = "admin",
+    params(("id" = String, Path, description = "Booking id")),
+    request_body = ReviewDecisionRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn review_booking(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<ReviewDecisionRequest>,
+) -> Result<HttpResponse, Error> {
+    let booking_id = path.into_inner();
+    db.ensure_booking_owned(&tenant, &booking_id).await?;
+    let payload = payload.into_inner();
+    match db.review_booking(&booking_id, &payload.decision, payload.note).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,125 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::gtfs::{GtfsFeed, GtfsStopRequest};
+use crate::models::gtfs_import::GtfsImportQuery;
+use crate::openapi::ErrorResponse;
+
+fn gtfs_zip(feed: GtfsFeed, cache_control: &str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"gtfs.zip\""))
+        .insert_header((header::CACHE_CONTROL, cache_control.to_string()))
+        .insert_header(("X-GTFS-Trips", feed.trips.to_string()))
+        .insert_header(("X-GTFS-Missing-Stops", feed.missing_stops.join(",")))
+        .body(feed.data.bytes)
+}
+
+/// The published feed journey planners poll; built inline only before the first scheduled run.
+#[utoipa::path(
+    get,
+    path = "/api/feeds/gtfs.zip",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_gtfs_feed(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let feed = match db.get_gtfs_feed().await {
+        Ok(Some(feed)) => Ok(feed),
+        Ok(None) => db.refresh_gtfs_feed().await,
+        Err(e) => Err(e),
+    };
+    match feed {
+        Ok(feed) => Ok(gtfs_zip(feed, "public, max-age=3600")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Exports the current schedules right away, replacing the published feed.
+#[utoipa::path(
+    get,
+    path = "/api/admin/gtfs",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn export_gtfs(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.refresh_gtfs_feed().await {
+        Ok(feed) => Ok(gtfs_zip(feed, "no-store")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/gtfs/stops",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_gtfs_stops(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.list_gtfs_stops().await {
+        Ok(stops) => Ok(HttpResponse::Ok().json(stops)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/gtfs/stops/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Stop id")),
+    request_body = GtfsStopRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_gtfs_stop(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<GtfsStopRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.set_gtfs_stop(&path.into_inner(), payload.into_inner(), &user.actor()).await {
+        Ok(stop) => Ok(HttpResponse::Ok().json(stop)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Takes a GTFS archive as the raw request body.
+#[utoipa::path(
+    post,
+    path = "/api/admin/gtfs/import",
+    tag = "admin",
+    params(GtfsImportQuery),
+    request_body(content = String, description = "Raw request body"),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 201, description = "Created"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn import_gtfs(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    query: web::Query<GtfsImportQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    match db.import_gtfs(&body, &query, &user.actor()).await {
+        Ok(report) if report.dry_run => Ok(HttpResponse::Ok().json(report)),
+        Ok(report) => Ok(HttpResponse::Created().json(report)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,99 @@
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::handlers::auth::anonymous_id_from_request;
+use crate::middleware::auth::OptionalClaims;
+use crate::models::hold::{CreateHoldRequest, HoldOwner, HoldResponse};
+use crate::openapi::ErrorResponse;
+
+// Signed-in users own holds directly; visitors own them through their anonymous session
+pub(crate) fn hold_owner(req: &HttpRequest) -> Option<HoldOwner> {
+    if let Some(user_id) = OptionalClaims::of(req).user_id() {
+        let user_oid = mongodb::bson::oid::ObjectId::parse_str(&user_id).ok()?;
+        return Some(HoldOwner::User(user_oid));
+    }
+    anonymous_id_from_request(req).map(HoldOwner::Anonymous)
+}
+
+fn missing_owner() -> AppError {
+    AppError::unauthorized("Sign in or start an anonymous session to hold seats")
+}
+
+/// First step of checkout: sets the chosen seats aside while the customer pays. A request by
+/// `seat_number` answers with its hold; one by `seat_numbers` with a list, in the order asked.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/holds",
+    tag = "bookings",
+    request_body = CreateHoldRequest,
+    responses(
+        (status = 201, description = "Created", body = HoldResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+    ),
+)]
+pub async fn create_hold(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    hold_req: web::Json<CreateHoldRequest>,
+) -> Result<HttpResponse, Error> {
+    let Some(owner) = hold_owner(&req) else {
+        return Err(missing_owner().into());
+    };
+
+    let holds = db.create_holds(&owner, &hold_req).await?;
+    let mut holds: Vec<HoldResponse> = holds.into_iter().map(HoldResponse::from).collect();
+    match (&hold_req.seat_number, holds.len()) {
+        (Some(_), 1) => Ok(HttpResponse::Created().json(holds.remove(0))),
+        _ => Ok(HttpResponse::Created().json(holds)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookings/holds",
+    tag = "bookings",
+    responses(
+        (status = 200, description = "OK", body = [HoldResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_holds(req: HttpRequest, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let Some(owner) = hold_owner(&req) else {
+        return Err(missing_owner().into());
+    };
+
+    match db.get_holds(&owner).await {
+        Ok(holds) => Ok(HttpResponse::Ok().json(holds.into_iter().map(HoldResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/bookings/holds/{id}",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Hold id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+pub async fn release_hold(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let Some(owner) = hold_owner(&req) else {
+        return Err(missing_owner().into());
+    };
+
+    match db.release_hold(&owner, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Ok(false) => Err(AppError::not_found("Hold not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,103 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::models::incident::{IncidentAttachment, IncidentQuery, IncidentResponse, ReportIncidentRequest, ResolveIncidentRequest};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/trips/{bus_id}/{date}/incidents",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = ReportIncidentRequest,
+    responses(
+        (status = 201, description = "Created", body = IncidentResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn report_incident(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<ReportIncidentRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.report_incident(&bus_id, &travel_date, &payload, "admin").await {
+        Ok(incident) => Ok(HttpResponse::Created().json(IncidentResponse::from(incident))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/incidents",
+    tag = "admin",
+    params(IncidentQuery),
+    responses(
+        (status = 200, description = "OK", body = [IncidentResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_incidents(
+    db: web::Data<MongoDB>,
+    query: web::Query<IncidentQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_incidents(&query).await {
+        Ok(incidents) => Ok(HttpResponse::Ok().json(incidents.into_iter().map(IncidentResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/incidents/{id}/attachments",
+    tag = "admin",
+    params(("id" = String, Path, description = "Incident id")),
+    request_body = IncidentAttachment,
+    responses(
+        (status = 200, description = "OK", body = IncidentResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn add_attachment(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<IncidentAttachment>,
+) -> Result<HttpResponse, Error> {
+    match db.add_incident_attachment(&path.into_inner(), &payload).await {
+        Ok(incident) => Ok(HttpResponse::Ok().json(IncidentResponse::from(incident))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/incidents/{id}/resolve",
+    tag = "admin",
+    params(("id" = String, Path, description = "Incident id")),
+    request_body = ResolveIncidentRequest,
+    responses(
+        (status = 200, description = "OK", body = IncidentResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn resolve_incident(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<ResolveIncidentRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.resolve_incident(&path.into_inner(), &payload.resolution).await {
+        Ok(incident) => Ok(HttpResponse::Ok().json(IncidentResponse::from(incident))),
+        Err(e) => Err(e.into()),
+    }
+}
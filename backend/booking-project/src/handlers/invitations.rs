@@ -0,0 +1,119 @@
+use actix_web::{web, HttpResponse, Error};
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::invitation::{AcceptInvitationRequest, CreateInvitationRequest, InvitationResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/invitations",
+    tag = "admin",
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, description = "Created", body = InvitationResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_invitation(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<CreateInvitationRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.create_invitation(&tenant, &user_id, &payload).await {
+        Ok(invitation) => Ok(HttpResponse::Created().json(InvitationResponse::from(invitation))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/invitations",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [InvitationResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_invitations(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+) -> Result<HttpResponse, Error> {
+    match db.get_pending_invitations(&tenant).await {
+        Ok(invitations) => Ok(HttpResponse::Ok().json(invitations.into_iter().map(InvitationResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/invitations/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Invitation id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_invitation(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.revoke_invitation(&tenant, &path.into_inner()).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/invitations/{token}",
+    tag = "invitations",
+    params(("token" = String, Path, description = "Token from the link")),
+    responses(
+        (status = 200, description = "OK", body = InvitationResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn get_invitation(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_open_invitation(&path.into_inner()).await {
+        Ok(invitation) => Ok(HttpResponse::Ok().json(InvitationResponse::from(invitation))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/invitations/accept",
+    tag = "invitations",
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn accept_invitation(
+    db: web::Data<MongoDB>,
+    payload: web::Json<AcceptInvitationRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.accept_invitation(&payload).await {
+        Ok(invitation) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": "Invitation accepted, please sign in",
+            "invitation": InvitationResponse::from(invitation),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,64 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::models::ledger::{LedgerBalanceQuery, LedgerQuery, LedgerTransactionResponse};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/ledger",
+    tag = "admin",
+    params(LedgerQuery),
+    responses(
+        (status = 200, description = "OK", body = [LedgerTransactionResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_ledger_transactions(
+    db: web::Data<MongoDB>,
+    query: web::Query<LedgerQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_ledger_transactions(&query).await {
+        Ok(transactions) => Ok(HttpResponse::Ok().json(transactions.into_iter().map(LedgerTransactionResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/ledger/balances",
+    tag = "admin",
+    params(LedgerBalanceQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_ledger_balances(
+    db: web::Data<MongoDB>,
+    query: web::Query<LedgerBalanceQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_ledger_balances(query.kind.as_deref()).await {
+        Ok(balances) => Ok(HttpResponse::Ok().json(balances)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/ledger/check",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn check_ledger(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.check_ledger().await {
+        Ok(check) => Ok(HttpResponse::Ok().json(check)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use crate::db::MongoDB;
+use crate::handlers::buses::request_language;
+use crate::models::localization::{preferred_language, LocalizationQuery, UpsertLocalizationRequest};
+use serde_json::json;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/localizations",
+    tag = "localizations",
+    params(LocalizationQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_localizations(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    query: web::Query<LocalizationQuery>,
+) -> Result<HttpResponse, Error> {
+    // An explicit ?lang= wins over the Accept-Language header
+    let language = match query.lang.as_deref() {
+        Some(lang) => preferred_language(Some(lang)),
+        None => request_language(&req),
+    };
+
+    match db.get_localizations(query.kind.as_deref()).await {
+        Ok(localizations) => {
+            let mut names: HashMap<String, HashMap<String, String>> = HashMap::new();
+            for l in localizations {
+                let name = l.names.get(language)
+                    .or_else(|| l.names.get("en"))
+                    .cloned()
+                    .unwrap_or_else(|| l.key.clone());
+                names.entry(l.kind).or_default().insert(l.key, name);
+            }
+            Ok(HttpResponse::Ok().json(json!({ "language": language, "names": names })))
+        },
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/localizations",
+    tag = "admin",
+    request_body = UpsertLocalizationRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn upsert_localization(
+    db: web::Data<MongoDB>,
+    payload: web::Json<UpsertLocalizationRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.upsert_localization(&payload).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use actix_web::{web, HttpRequest, HttpResponse, Error};
+use log::{error, warn};
+use serde::Deserialize;
+
+use crate::config::{LookupConfig, RateLimitConfig};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::rate_limit::client_ip;
+use crate::models::booking::BookingDetailResponse;
+use crate::models::lookup::{lookup_reference_key, BookingReferenceResponse, GuestLookupRequest};
+use crate::openapi::ErrorResponse;
+
+#[derive(Deserialize)]
+struct CaptchaVerification {
+    success: bool,
+}
+
+// hCaptcha and reCAPTCHA share this siteverify contract; an unreachable provider counts as unsolved
+async fn captcha_solved(config: &LookupConfig, secret: &str, token: &str, ip: &str) -> bool {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().unwrap_or_default();
+    let response = client
+        .post(&config.captcha_verify_url)
+        .form(&[("secret", secret), ("response", token), ("remoteip", ip)])
+        .send()
+        .await;
+    match response {
+        Ok(response) => response.json::<CaptchaVerification>().await.map(|v| v.success).unwrap_or(false),
+        Err(e) => {
+            warn!("CAPTCHA verification failed: {}", e);
+            false
+        }
+    }
+}
+
+fn lookup_ip(req: &HttpRequest) -> String {
+    client_ip(req, RateLimitConfig::from_env().trust_forwarded)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn throttled(config: &LookupConfig) -> AppError {
+    AppError::too_many_requests("Too many attempts, please try again later", Some(config.window_minutes * 60))
+}
+
+/// Finds a booking by reference and the booker's email, without signing in. Every miss counts
+/// against the caller's IP and the reference, so guessing either runs into a CAPTCHA and then
+/// a wait long before it gets anywhere.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/lookup",
+    tag = "bookings",
+    request_body = GuestLookupRequest,
+    responses(
+        (status = 200, description = "OK", body = BookingDetailResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 429, description = "Too many requests", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn lookup_booking(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<GuestLookupRequest>,
+) -> Result<HttpResponse, Error> {
+    let config = LookupConfig::from_env();
+    let ip = lookup_ip(&req);
+    let reference = payload.reference();
+
+    let failures = match db.count_lookup_failures(&ip, &reference, &config).await {
+        Ok(failures) => failures,
+        Err(e) => return Err(e.into()),
+    };
+    if failures.throttled(&config) {
+        return Err(throttled(&config).into());
+    }
+    if let (true, Some(secret)) = (failures.needs_captcha(&config), config.captcha_secret.as_deref()) {
+        let solved = match payload.captcha_token.as_deref() {
+            Some(token) => captcha_solved(&config, secret, token, &ip).await,
+            None => false,
+        };
+        if !solved {
+            return Err(AppError::CaptchaRequired.into());
+        }
+    }
+
+    match db.find_booking_for_guest(&reference, &payload.email).await {
+        Ok(Some(found)) => Ok(HttpResponse::Ok().json(BookingDetailResponse::from(found))),
+        Ok(None) => {
+            if let Err(e) = db.record_lookup_failure(&ip, &reference, &config).await {
+                error!("Failed to record booking lookup failure from {}: {}", ip, e);
+            }
+            Err(AppError::not_found("No booking matches that reference and email").into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Looks up a booking by its short code, e.g. BB-7K2F9Q, without signing in, for passengers
+/// and boarding staff. Only what boarding needs is shown, and misses are throttled the same
+/// way as the email lookup.
+#[utoipa::path(
+    get,
+    path = "/api/bookings/ref/{code}",
+    tag = "bookings",
+    params(("code" = String, Path, description = "Booking reference, any case, with or without the BB- prefix")),
+    responses(
+        (status = 200, description = "OK", body = BookingReferenceResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 429, description = "Too many requests", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn lookup_booking_by_reference(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let config = LookupConfig::from_env();
+    let ip = lookup_ip(&req);
+    let code = lookup_reference_key(&path.into_inner());
+
+    let failures = match db.count_lookup_failures(&ip, &code, &config).await {
+        Ok(failures) => failures,
+        Err(e) => return Err(e.into()),
+    };
+    if failures.throttled(&config) {
+        return Err(throttled(&config).into());
+    }
+
+    match db.find_booking_by_reference(&code).await {
+        Ok(Some((booking, Some(bus)))) => Ok(HttpResponse::Ok().json(BookingReferenceResponse::new(booking, bus))),
+        Ok(_) => {
+            if let Err(e) = db.record_lookup_failure(&ip, &code, &config).await {
+                error!("Failed to record booking lookup failure from {}: {}", ip, e);
+            }
+            Err(AppError::not_found("No booking has that reference").into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
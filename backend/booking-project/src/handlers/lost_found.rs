@@ -0,0 +1,184 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::lost_found::{
+    FoundItemResponse, FoundItemStatusRequest, LogFoundItemRequest, LostFoundQuery, LostItemResponse, LostItemStatusRequest, ReportLostItemRequest,
+};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/lost-items",
+    tag = "lost-items",
+    request_body = ReportLostItemRequest,
+    responses(
+        (status = 201, description = "Created", body = LostItemResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn report_lost_item(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    report: web::Json<ReportLostItemRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.report_lost_item(&user_id, &report).await {
+        Ok(report) => Ok(HttpResponse::Created().json(LostItemResponse::from(report))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/lost-items",
+    tag = "lost-items",
+    responses(
+        (status = 200, description = "OK", body = [LostItemResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_user_lost_items(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.get_user_lost_items(&user_id).await {
+        Ok(reports) => Ok(HttpResponse::Ok().json(reports.into_iter().map(LostItemResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/lost-items",
+    tag = "admin",
+    params(LostFoundQuery),
+    responses(
+        (status = 200, description = "OK", body = [LostItemResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_lost_items(
+    db: web::Data<MongoDB>,
+    query: web::Query<LostFoundQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_lost_items(query.status.as_deref()).await {
+        Ok(reports) => Ok(HttpResponse::Ok().json(reports.into_iter().map(LostItemResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/lost-items/{id}/matches",
+    tag = "admin",
+    params(("id" = String, Path, description = "Lost item id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_lost_item_matches(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.suggest_found_items(&path.into_inner()).await {
+        Ok(suggestions) => Ok(HttpResponse::Ok().json(suggestions)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/lost-items/{id}/status",
+    tag = "admin",
+    params(("id" = String, Path, description = "Lost item id")),
+    request_body = LostItemStatusRequest,
+    responses(
+        (status = 200, description = "OK", body = LostItemResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_lost_item_status(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<LostItemStatusRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.update_lost_item_status(&path.into_inner(), &payload).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(LostItemResponse::from(report))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/found-items",
+    tag = "admin",
+    request_body = LogFoundItemRequest,
+    responses(
+        (status = 201, description = "Created", body = FoundItemResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn log_found_item(
+    db: web::Data<MongoDB>,
+    payload: web::Json<LogFoundItemRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.log_found_item(&payload, "admin").await {
+        Ok(item) => Ok(HttpResponse::Created().json(FoundItemResponse::from(item))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/found-items",
+    tag = "admin",
+    params(LostFoundQuery),
+    responses(
+        (status = 200, description = "OK", body = [FoundItemResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_found_items(
+    db: web::Data<MongoDB>,
+    query: web::Query<LostFoundQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_found_items(query.status.as_deref()).await {
+        Ok(items) => Ok(HttpResponse::Ok().json(items.into_iter().map(FoundItemResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/found-items/{id}/status",
+    tag = "admin",
+    params(("id" = String, Path, description = "Found item id")),
+    request_body = FoundItemStatusRequest,
+    responses(
+        (status = 200, description = "OK", body = FoundItemResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_found_item_status(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<FoundItemStatusRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.set_found_item_status(&path.into_inner(), &payload.status).await {
+        Ok(item) => Ok(HttpResponse::Ok().json(FoundItemResponse::from(item))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,29 @@
+use actix_web::{http::header, HttpRequest, HttpResponse, Error};
+
+use crate::metrics::metrics;
+use crate::openapi::ErrorResponse;
+
+// Scraped by Prometheus; when METRICS_TOKEN is set the scraper must send it as a bearer token
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "OK", content_type = "text/plain; version=0.0.4", body = String),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+)]
+pub async fn get_metrics(req: HttpRequest) -> Result<HttpResponse, Error> {
+    if let Ok(expected) = std::env::var("METRICS_TOKEN") {
+        let presented = req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
+        if presented != Some(expected.as_str()) {
+            return Ok(HttpResponse::Unauthorized().finish());
+        }
+    }
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics().render()))
+}
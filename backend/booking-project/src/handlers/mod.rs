@@ -1,6 +1,54 @@
+pub mod account;
+pub mod analytics;
+pub mod audit;
 pub mod auth;
 pub mod bookings;
 pub mod buses;
+pub mod campaigns;
+pub mod charters;
+pub mod commission;
+pub mod compare;
+pub mod conductor;
+pub mod dead_letters;
+pub mod devices;
+pub mod duplicates;
+pub mod experiments;
+pub mod fares;
+pub mod feeds;
+pub mod fraud;
+pub mod gtfs;
+pub mod holds;
+pub mod incidents;
+pub mod invitations;
+pub mod ledger;
+pub mod localization;
+pub mod lookup;
+pub mod lost_found;
+pub mod metrics;
+pub mod next_trip;
+pub mod notifications;
+pub mod operators;
+pub mod parcels;
+pub mod partners;
+pub mod passes;
+pub mod payment_methods;
+pub mod payments;
+pub mod permissions;
+pub mod policies;
+pub mod price_history;
+pub mod rate_limits;
+pub mod refunds;
+pub mod regulatory;
+pub mod settlements;
+pub mod stats;
+pub mod status;
+pub mod terminals;
+pub mod terms;
+pub mod tickets;
+pub mod trip_history;
+pub mod trips;
+pub mod waiting_rooms;
+pub mod webhooks;
 // Remove unused modules
 // pub mod bookings;
 // pub mod admin;
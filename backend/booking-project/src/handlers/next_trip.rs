@@ -0,0 +1,31 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::next_trip::{NextTripResponse, UpcomingTripResponse};
+use crate::openapi::ErrorResponse;
+
+/// The home screen card: the user's nearest upcoming confirmed trip, or `trip: null`.
+#[utoipa::path(
+    get,
+    path = "/api/users/me/next-trip",
+    tag = "users",
+    responses(
+        (status = 200, description = "OK", body = NextTripResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_next_trip(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    let base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+
+    match db.get_next_trip(&user.id).await {
+        Ok(trip) => Ok(HttpResponse::Ok().json(NextTripResponse {
+            trip: trip.map(|t| UpcomingTripResponse::new(t, chrono::Utc::now(), &base_url)),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,117 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::notification::NotificationResponse;
+use crate::models::notification_resend::{NotificationResendResponse, ResendConfirmationsRequest};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/notifications",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "OK", body = [NotificationResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_user_notifications(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.get_user_notifications(&user_id).await {
+        Ok(notifications) => Ok(HttpResponse::Ok().json(notifications.into_iter().map(NotificationResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/notifications",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [NotificationResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_admin_notifications(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_admin_notifications().await {
+        Ok(notifications) => Ok(HttpResponse::Ok().json(notifications.into_iter().map(NotificationResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sends the confirmations for a trip or travel date again, e.g. after an email provider outage
+/// dropped them. The resend runs in the background; poll it for progress.
+#[utoipa::path(
+    post,
+    path = "/api/admin/bookings/confirmation-resends",
+    tag = "admin",
+    request_body = ResendConfirmationsRequest,
+    responses(
+        (status = 202, description = "Accepted", body = NotificationResendResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "No matching bookings", body = ErrorResponse),
+        (status = 409, description = "Already in progress", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn resend_confirmations(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<ResendConfirmationsRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.request_confirmation_resend(&tenant, &payload, &user.actor()).await {
+        Ok(run) => Ok(HttpResponse::Accepted().json(NotificationResendResponse::from(run))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Recent confirmation resends, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/bookings/confirmation-resends",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [NotificationResendResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_confirmation_resends(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+) -> Result<HttpResponse, Error> {
+    match db.list_notification_resends(&tenant).await {
+        Ok(runs) => Ok(HttpResponse::Ok().json(runs.into_iter().map(NotificationResendResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// How far a confirmation resend has got.
+#[utoipa::path(
+    get,
+    path = "/api/admin/bookings/confirmation-resends/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Resend id")),
+    responses(
+        (status = 200, description = "OK", body = NotificationResendResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_confirmation_resend(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_notification_resend(&tenant, &path.into_inner()).await {
+        Ok(run) => Ok(HttpResponse::Ok().json(NotificationResendResponse::from(run))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,162 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::email_template::{EmailTemplate, EmailTemplateResponse, BOOKING_EMAIL_FIELDS};
+use crate::models::localization::{document_text, DEFAULT_LANGUAGE};
+use crate::models::operator::{OperatorSettings, OperatorSettingsRequest, OperatorSettingsResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/operators/{operator}/settings",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    responses(
+        (status = 200, description = "OK", body = OperatorSettingsResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_operator_settings(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.get_operator_settings(&operator).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(OperatorSettingsResponse::from(settings))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/operators/{operator}/settings",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    request_body = OperatorSettingsRequest,
+    responses(
+        (status = 200, description = "OK", body = OperatorSettingsResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_operator_settings(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<OperatorSettingsRequest>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.update_operator_settings(&operator, &payload).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(OperatorSettingsResponse::from(settings))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// The operator's wording, or the built-in English one they would be replacing
+fn booking_email_response(settings: OperatorSettings) -> EmailTemplateResponse {
+    let language = settings.default_language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+    let custom = settings.booking_email.is_some();
+    let template = settings.booking_email.unwrap_or_else(|| EmailTemplate {
+        subject: document_text(language, "email.booking_confirmed.subject"),
+        body: document_text(language, "email.booking_confirmed.body"),
+    });
+    EmailTemplateResponse { subject: template.subject, body: template.body, custom, fields: BOOKING_EMAIL_FIELDS.to_vec() }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/operators/{operator}/booking-email",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_booking_email(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.get_operator_settings(&operator).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(booking_email_response(settings))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/operators/{operator}/booking-email",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    request_body = EmailTemplate,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_booking_email(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<EmailTemplate>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.set_operator_booking_email(&operator, Some(payload.into_inner()), &user.actor()).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(booking_email_response(settings))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/operators/{operator}/booking-email",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn reset_booking_email(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.set_operator_booking_email(&operator, None, &user.actor()).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(booking_email_response(settings))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,105 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::parcel::{CreateParcelRequest, ParcelListQuery, ParcelResponse, ParcelStatusRequest, ParcelTrackingResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+/// Where a parcel is, by its waybill number. Open to anyone holding the number, so it leaves
+/// out who sent it and how to reach them.
+#[utoipa::path(
+    get,
+    path = "/api/parcels/{waybill}",
+    tag = "parcels",
+    params(("waybill" = String, Path, description = "Waybill number, e.g. PCL-7K2F9QXA")),
+    responses(
+        (status = 200, description = "OK", body = ParcelTrackingResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn track_parcel(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.track_parcel(&path.into_inner()).await {
+        Ok(Some(parcel)) => Ok(HttpResponse::Ok().json(ParcelTrackingResponse::from(parcel))),
+        Ok(None) => Err(AppError::not_found("Parcel not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Takes in a parcel at the booking office and puts it on a trip, priced by size and weight.
+#[utoipa::path(
+    post,
+    path = "/api/admin/parcels",
+    tag = "admin",
+    request_body = CreateParcelRequest,
+    responses(
+        (status = 201, description = "Created", body = ParcelResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_parcel(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<CreateParcelRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.create_parcel(&tenant, &payload, &user.actor()).await {
+        Ok(parcel) => Ok(HttpResponse::Created().json(ParcelResponse::from(parcel))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Parcels on the operator's buses, e.g. everything loading onto one trip.
+#[utoipa::path(
+    get,
+    path = "/api/admin/parcels",
+    tag = "admin",
+    params(ParcelListQuery),
+    responses(
+        (status = 200, description = "OK", body = [ParcelResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_parcels(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    query: web::Query<ParcelListQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.list_parcels(&tenant, &query).await {
+        Ok(parcels) => Ok(HttpResponse::Ok().json(parcels.into_iter().map(ParcelResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Moves a parcel on to in_transit, arrived or collected, texting the sender or recipient.
+#[utoipa::path(
+    put,
+    path = "/api/admin/parcels/{waybill}/status",
+    tag = "admin",
+    params(("waybill" = String, Path, description = "Waybill number")),
+    request_body = ParcelStatusRequest,
+    responses(
+        (status = 200, description = "OK", body = ParcelResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_parcel_status(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<ParcelStatusRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.update_parcel_status(&tenant, &path.into_inner(), &payload, &user.actor()).await {
+        Ok(parcel) => Ok(HttpResponse::Ok().json(ParcelResponse::from(parcel))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,197 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Error};
+use serde_json::json;
+
+use crate::config::PartnerConfig;
+use crate::crypto::constant_time_eq;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::{AuthenticatedUser, bearer_token};
+use crate::models::partner::{
+    PartnerClickQuery, PartnerConversionRequest, PartnerOfferQuery, PartnerOfferRequest, PartnerReportQuery,
+};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/partner-offers",
+    tag = "admin",
+    params(PartnerOfferQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_partner_offers(
+    db: web::Data<MongoDB>,
+    query: web::Query<PartnerOfferQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_partner_offers(query.city.as_deref()).await {
+        Ok(offers) => Ok(HttpResponse::Ok().json(offers)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/partner-offers",
+    tag = "admin",
+    request_body = PartnerOfferRequest,
+    responses(
+        (status = 201, description = "Created"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_partner_offer(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<PartnerOfferRequest>,
+) -> Result<HttpResponse, Error> {
+    let actor = user.actor();
+    match db.create_partner_offer(payload.into_inner(), &actor).await {
+        Ok(offer) => Ok(HttpResponse::Created().json(offer)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/partner-offers/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Partner offer id")),
+    request_body = PartnerOfferRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_partner_offer(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<PartnerOfferRequest>,
+) -> Result<HttpResponse, Error> {
+    let actor = user.actor();
+    match db.update_partner_offer(&path.into_inner(), payload.into_inner(), &actor).await {
+        Ok(Some(offer)) => Ok(HttpResponse::Ok().json(offer)),
+        Ok(None) => Err(AppError::not_found("Partner offer not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/partner-offers/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Partner offer id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_partner_offer(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let actor = user.actor();
+    match db.delete_partner_offer(&path.into_inner(), &actor).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Ok(false) => Err(AppError::not_found("Partner offer not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/partner-offers/report",
+    tag = "admin",
+    params(PartnerReportQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_partner_report(
+    db: web::Data<MongoDB>,
+    query: web::Query<PartnerReportQuery>,
+) -> Result<HttpResponse, Error> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    match db.get_partner_report(days, query.partner.as_deref()).await {
+        Ok(rows) => Ok(HttpResponse::Ok().json(json!({ "days": days, "offers": rows }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Counts the click and forwards the passenger to the partner.
+#[utoipa::path(
+    get,
+    path = "/api/partners/offers/{id}/click",
+    tag = "partners",
+    params(
+        ("id" = String, Path, description = "Offer id"),
+        PartnerClickQuery,
+    ),
+    responses(
+        (status = 302, description = "Redirect"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn follow_partner_offer(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<PartnerClickQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.record_partner_click(&path.into_inner(), &query.booking, &query.sig).await {
+        Ok(Some(url)) => Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, url))
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .finish()),
+        Ok(None) => Err(AppError::not_found("This offer is no longer available").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Partners report a passenger we sent them buying something, quoting the click `ref`.
+#[utoipa::path(
+    post,
+    path = "/api/partners/conversions",
+    tag = "partners",
+    request_body = PartnerConversionRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+        (status = 503, description = "Unavailable", body = ErrorResponse),
+    ),
+)]
+pub async fn report_partner_conversion(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<PartnerConversionRequest>,
+) -> Result<HttpResponse, Error> {
+    let Some(expected) = PartnerConfig::from_env().conversion_token else {
+        return Err(AppError::unavailable("Conversion reporting is not enabled").into());
+    };
+    let presented = req.headers().get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(bearer_token)
+        .unwrap_or_default();
+    if !constant_time_eq(presented.as_bytes(), expected.as_bytes()) {
+        return Err(AppError::unauthorized("Unauthorized").into());
+    }
+
+    match db.record_partner_conversion(&payload).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(json!({ "recorded": true }))),
+        Ok(false) => Err(AppError::conflict("Unknown ref, or its conversion was already reported").into()),
+        Err(e) => Err(e.into()),
+    }
+}
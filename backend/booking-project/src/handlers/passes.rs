@@ -0,0 +1,84 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::pass::{BuyPassRequest, PassPurchaseResponse, PassResponse};
+use crate::models::payment::PaymentResponse;
+use crate::openapi::ErrorResponse;
+
+/// Buys a commuter pass for a route. Like a booking payment it is accepted rather than created:
+/// the pass can be used once the customer approves the M-Pesa prompt.
+#[utoipa::path(
+    post,
+    path = "/api/passes",
+    tag = "passes",
+    request_body = BuyPassRequest,
+    responses(
+        (status = 202, description = "Accepted", body = PassPurchaseResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn buy_pass(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    pass_req: web::Json<BuyPassRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.buy_pass(&user.id, &pass_req).await {
+        Ok((pass, payment)) => Ok(HttpResponse::Accepted().json(PassPurchaseResponse {
+            pass: PassResponse::new(pass, chrono::Utc::now()),
+            payment: PaymentResponse::from(payment),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/passes",
+    tag = "passes",
+    responses(
+        (status = 200, description = "OK", body = [PassResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_passes(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    match db.list_passes(&user.id).await {
+        Ok(passes) => {
+            let now = chrono::Utc::now();
+            let passes: Vec<PassResponse> = passes.into_iter().map(|p| PassResponse::new(p, now)).collect();
+            Ok(HttpResponse::Ok().json(passes))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A pass with its rides left and status, e.g. to poll until its payment goes through.
+#[utoipa::path(
+    get,
+    path = "/api/passes/{id}",
+    tag = "passes",
+    params(("id" = String, Path, description = "Pass id")),
+    responses(
+        (status = 200, description = "OK", body = PassResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_pass(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_user_pass(&user.id, &path.into_inner()).await {
+        Ok(Some(pass)) => Ok(HttpResponse::Ok().json(PassResponse::new(pass, chrono::Utc::now()))),
+        Ok(None) => Err(AppError::not_found("Pass not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
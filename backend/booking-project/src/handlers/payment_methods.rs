@@ -0,0 +1,138 @@
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+use serde_json::json;
+
+use crate::config::GeoIpConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::geoip::request_country;
+use crate::models::payment_method::{PaymentMethodResponse, SavePaymentMethodRequest, VaultAccessResponse, VaultAuditQuery};
+use crate::payments::{vault_for, PAYMENT_PROVIDERS};
+use crate::openapi::ErrorResponse;
+
+fn provider_unavailable(provider: &str) -> AppError {
+    let message = if PAYMENT_PROVIDERS.contains(&provider) {
+        format!("{} is not configured", provider)
+    } else {
+        format!("provider must be one of: {}", PAYMENT_PROVIDERS.join(", "))
+    };
+    AppError::from(message)
+}
+
+/// Refuses payment initiation from countries outside the configured allow/deny lists.
+pub fn enforce_payment_country(req: &HttpRequest) -> Option<AppError> {
+    let country = request_country(req);
+    if GeoIpConfig::from_env().allows_payment_from(country.as_deref()) {
+        return None;
+    }
+    Some(AppError::forbidden("Payments are not available in your country"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/payment-methods",
+    tag = "payment-methods",
+    responses(
+        (status = 200, description = "OK", body = [PaymentMethodResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_payment_methods(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.get_payment_methods(&user_id).await {
+        Ok(methods) => Ok(HttpResponse::Ok().json(methods.into_iter().map(PaymentMethodResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/payment-methods",
+    tag = "payment-methods",
+    request_body = SavePaymentMethodRequest,
+    responses(
+        (status = 201, description = "Created", body = PaymentMethodResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn save_payment_method(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    method_req: web::Json<SavePaymentMethodRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+    if let Some(refusal) = enforce_payment_country(&req) {
+        return Err(refusal.into());
+    }
+    let Some(vault) = vault_for(&method_req.provider) else {
+        return Err(provider_unavailable(&method_req.provider).into());
+    };
+
+    match db.save_payment_method(&user_id, vault.as_ref(), &method_req.token).await {
+        Ok(method) => Ok(HttpResponse::Created().json(PaymentMethodResponse::from(method))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/payment-methods/{id}",
+    tag = "payment-methods",
+    params(("id" = String, Path, description = "Payment method id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 502, description = "Upstream error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_payment_method(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    let method = match db.find_payment_method(&user_id, &path.into_inner()).await {
+        Ok(Some(method)) => method,
+        Ok(None) => return Err(AppError::not_found("Payment method not found").into()),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(vault) = vault_for(&method.provider) else {
+        return Err(provider_unavailable(&method.provider).into());
+    };
+
+    match db.remove_payment_method(&method, vault.as_ref()).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(json!({ "success": true }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/payment-methods/audit",
+    tag = "admin",
+    params(VaultAuditQuery),
+    responses(
+        (status = 200, description = "OK", body = [VaultAccessResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_vault_audit(
+    db: web::Data<MongoDB>,
+    query: web::Query<VaultAuditQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_vault_audit(query.user_id.as_deref()).await {
+        Ok(entries) => Ok(HttpResponse::Ok().json(entries.into_iter().map(VaultAccessResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
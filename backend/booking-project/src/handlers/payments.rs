@@ -0,0 +1,53 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::payment::{PaymentResponse, StkPushRequest};
+use crate::openapi::ErrorResponse;
+
+// Accepted rather than created: the customer still has to approve the prompt on their phone
+#[utoipa::path(
+    post,
+    path = "/api/payments/mpesa/stk-push",
+    tag = "payments",
+    request_body = StkPushRequest,
+    responses(
+        (status = 202, description = "Accepted", body = PaymentResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn stk_push(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    push_req: web::Json<StkPushRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.initiate_stk_push(&user.id, &push_req).await {
+        Ok(payment) => Ok(HttpResponse::Accepted().json(PaymentResponse::from(payment))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/payments/{id}",
+    tag = "payments",
+    params(("id" = String, Path, description = "Payment id")),
+    responses(
+        (status = 200, description = "OK", body = PaymentResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_payment(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_user_payment(&path.into_inner(), &user.id).await {
+        Ok(Some(payment)) => Ok(HttpResponse::Ok().json(PaymentResponse::from(payment))),
+        Ok(None) => Err(AppError::not_found("Payment not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,70 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::permissions::{permissions_for, AssignRoleRequest, RolePermissions};
+use crate::models::user::ROLES;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/permissions",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_permission_matrix() -> Result<HttpResponse, Error> {
+    let matrix: Vec<RolePermissions> = ROLES
+        .iter()
+        .map(|role| RolePermissions { role: role.to_string(), permissions: permissions_for(role) })
+        .collect();
+    Ok(HttpResponse::Ok().json(matrix))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/permissions",
+    tag = "admin",
+    params(("id" = String, Path, description = "User id")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_user_permissions(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_effective_permissions(&path.into_inner()).await {
+        Ok(Some(permissions)) => Ok(HttpResponse::Ok().json(permissions)),
+        Ok(None) => Err(AppError::not_found("User not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{id}/role",
+    tag = "admin",
+    params(("id" = String, Path, description = "User id")),
+    request_body = AssignRoleRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn assign_role(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<AssignRoleRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.assign_role(&path.into_inner(), &payload).await {
+        Ok(permissions) => Ok(HttpResponse::Ok().json(permissions)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,194 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::policy::{AssignPolicyRequest, PolicyDateQuery, PolicyRequest, PolicyResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/policies",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [PolicyResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_policies(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_policies().await {
+        Ok(policies) => Ok(HttpResponse::Ok().json(policies.into_iter().map(PolicyResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Policy id")),
+    responses(
+        (status = 200, description = "OK", body = PolicyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_policy(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.get_policy(&path.into_inner()).await {
+        Ok(Some(policy)) => Ok(HttpResponse::Ok().json(PolicyResponse::from(policy))),
+        Ok(None) => Err(AppError::not_found("Policy not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/policies",
+    tag = "admin",
+    request_body = PolicyRequest,
+    responses(
+        (status = 201, description = "Created", body = PolicyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_policy(db: web::Data<MongoDB>, payload: web::Json<PolicyRequest>) -> Result<HttpResponse, Error> {
+    match db.create_policy(&payload).await {
+        Ok(policy) => Ok(HttpResponse::Created().json(PolicyResponse::from(policy))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Policy id")),
+    request_body = PolicyRequest,
+    responses(
+        (status = 200, description = "OK", body = PolicyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_policy(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<PolicyRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.update_policy(&path.into_inner(), &payload).await {
+        Ok(policy) => Ok(HttpResponse::Ok().json(PolicyResponse::from(policy))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Policy id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_policy(db: web::Data<MongoDB>, path: web::Path<String>) -> Result<HttpResponse, Error> {
+    match db.delete_policy(&path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(AppError::not_found("Policy not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/buses/{id}/policy",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    request_body = AssignPolicyRequest,
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn assign_bus_policy(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<AssignPolicyRequest>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.set_bus_policy(&bus_id, payload.policy_id.as_deref()).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/trips/{bus_id}/{date}/policy",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = AssignPolicyRequest,
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn assign_trip_policy(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<AssignPolicyRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.set_trip_policy(&bus_id, &travel_date, payload.policy_id.as_deref()).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Terms shown to passengers before they book or change a trip
+#[utoipa::path(
+    get,
+    path = "/api/buses/{id}/policy",
+    tag = "buses",
+    params(
+        ("id" = String, Path, description = "Bus id"),
+        PolicyDateQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", body = PolicyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_bus_policy(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<PolicyDateQuery>,
+) -> Result<HttpResponse, Error> {
+    let bus = match db.get_bus(&path.into_inner()).await {
+        Ok(Some(bus)) => bus,
+        Ok(None) => return Err(AppError::not_found("Bus not found").into()),
+        Err(e) => return Err(e.into()),
+    };
+    let travel_date = query.date.clone().unwrap_or_default();
+    match db.resolve_policy(&bus, &travel_date).await {
+        Ok(policy) => Ok(HttpResponse::Ok().json(PolicyResponse::from(policy))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,39 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::price_history::{PriceHistoryQuery, PriceHistoryResponse};
+use crate::openapi::ErrorResponse;
+
+/// Daily base fares for a route, identified by its feed slug (`nairobi-to-kisumu`).
+#[utoipa::path(
+    get,
+    path = "/api/routes/{id}/price-history",
+    tag = "routes",
+    params(
+        ("id" = String, Path, description = "Route id"),
+        PriceHistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_price_history(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<PriceHistoryQuery>,
+) -> Result<HttpResponse, Error> {
+    let route = path.into_inner().to_lowercase();
+    let days = query.days.unwrap_or(90).clamp(1, 365);
+    match db.get_price_history(&route, days).await {
+        Ok(prices) => match PriceHistoryResponse::new(days, prices) {
+            Some(history) => Ok(HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+                .json(history)),
+            None => Err(AppError::not_found("No price history for this route").into()),
+        },
+        Err(e) => Err(e.into()),
+    }
+}
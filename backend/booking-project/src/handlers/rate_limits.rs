@@ -0,0 +1,167 @@
+use actix_web::{web, HttpRequest, HttpResponse, Error};
+use serde_json::json;
+
+use crate::config::RateLimitConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::OptionalClaims;
+use crate::models::rate_limit::{
+    window_start, AbuseBlockResponse, LimitOverrideRequest, RateLimitKeyQuery, RateLimitOverrideResponse,
+    RateLimitUsageResponse,
+};
+use crate::openapi::ErrorResponse;
+
+fn actor(req: &HttpRequest) -> Option<String> {
+    OptionalClaims::of(req).user_id().map(|id| format!("user:{}", id))
+}
+
+fn unauthorized() -> AppError {
+    AppError::unauthorized("Unauthorized")
+}
+
+/// The configured limits and the overrides currently in force.
+#[utoipa::path(
+    get,
+    path = "/api/admin/rate-limits",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_rate_limits(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_rate_limit_overrides().await {
+        Ok(overrides) => Ok(HttpResponse::Ok().json(json!({
+            "config": RateLimitConfig::from_env(),
+            "overrides": overrides.into_iter().map(RateLimitOverrideResponse::from).collect::<Vec<_>>(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Request counts in the current window, busiest callers first, with the limit each is held to.
+#[utoipa::path(
+    get,
+    path = "/api/admin/rate-limits/usage",
+    tag = "admin",
+    params(RateLimitKeyQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_rate_limit_usage(
+    db: web::Data<MongoDB>,
+    query: web::Query<RateLimitKeyQuery>,
+) -> Result<HttpResponse, Error> {
+    let config = RateLimitConfig::from_env();
+    let window = window_start(chrono::Utc::now().timestamp(), config.window_secs);
+    let (counters, overrides) = match (db.get_rate_limit_usage(query.key.as_deref(), window).await, db.get_rate_limit_overrides().await) {
+        (Ok(counters), Ok(overrides)) => (counters, overrides),
+        (Err(e), _) | (_, Err(e)) => return Err(e.into()),
+    };
+
+    let usage: Vec<RateLimitUsageResponse> = counters.into_iter().map(|counter| {
+        let limit = overrides.iter()
+            .find(|o| o.key == counter.key)
+            .map(|o| o.limit)
+            .unwrap_or_else(|| config.default_limit(&counter.key));
+        RateLimitUsageResponse::new(counter, limit)
+    }).collect();
+    Ok(HttpResponse::Ok().json(usage))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/rate-limits/overrides",
+    tag = "admin",
+    request_body = LimitOverrideRequest,
+    responses(
+        (status = 201, description = "Created", body = RateLimitOverrideResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_override(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<LimitOverrideRequest>,
+) -> Result<HttpResponse, Error> {
+    let Some(actor) = actor(&req) else {
+        return Err(unauthorized().into());
+    };
+    match db.set_rate_limit_override(&payload, &actor).await {
+        Ok(limit_override) => Ok(HttpResponse::Created().json(RateLimitOverrideResponse::from(limit_override))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/rate-limits/overrides/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Override id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_override(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let Some(actor) = actor(&req) else {
+        return Err(unauthorized().into());
+    };
+    match db.remove_rate_limit_override(&path.into_inner(), &actor).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/rate-limits/blocks",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [AbuseBlockResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_abuse_blocks(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_abuse_blocks().await {
+        Ok(blocks) => Ok(HttpResponse::Ok().json(blocks.into_iter().map(AbuseBlockResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Lifts the block on `?key=`, or every block without one.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/rate-limits/blocks",
+    tag = "admin",
+    params(RateLimitKeyQuery),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn reset_abuse_blocks(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    query: web::Query<RateLimitKeyQuery>,
+) -> Result<HttpResponse, Error> {
+    let Some(actor) = actor(&req) else {
+        return Err(unauthorized().into());
+    };
+    match db.reset_abuse_blocks(query.key.as_deref(), &actor).await {
+        Ok(lifted) => Ok(HttpResponse::Ok().json(json!({ "lifted": lifted }))),
+        Err(e) => Err(e.into()),
+    }
+}
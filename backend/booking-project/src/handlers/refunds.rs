@@ -0,0 +1,144 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::refund::{CreateRefundRequest, RefundDecisionRequest, RefundPayoutRequest, RefundResponse, RefundStatusQuery};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/refunds",
+    tag = "refunds",
+    request_body = CreateRefundRequest,
+    responses(
+        (status = 201, description = "Created", body = RefundResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn request_refund(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    refund_req: web::Json<CreateRefundRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.request_refund(&user_id, &refund_req).await {
+        Ok(refund) => Ok(HttpResponse::Created().json(RefundResponse::from(refund))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/refunds",
+    tag = "refunds",
+    responses(
+        (status = 200, description = "OK", body = [RefundResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_user_refunds(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.get_user_refund_requests(&user_id).await {
+        Ok(refunds) => Ok(HttpResponse::Ok().json(refunds.into_iter().map(RefundResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/refunds",
+    tag = "admin",
+    params(RefundStatusQuery),
+    responses(
+        (status = 200, description = "OK", body = [RefundResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_refunds(
+    db: web::Data<MongoDB>,
+    query: web::Query<RefundStatusQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_refund_requests(query.status.as_deref()).await {
+        Ok(refunds) => Ok(HttpResponse::Ok().json(refunds.into_iter().map(RefundResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/refunds/{id}/approve",
+    tag = "admin",
+    params(("id" = String, Path, description = "Refund id")),
+    request_body = RefundDecisionRequest,
+    responses(
+        (status = 200, description = "OK", body = RefundResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn approve_refund(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    decision: web::Json<RefundDecisionRequest>,
+) -> Result<HttpResponse, Error> {
+    let refund_id = path.into_inner();
+    match db.approve_refund(&refund_id, decision.into_inner().note).await {
+        Ok(refund) => Ok(HttpResponse::Ok().json(RefundResponse::from(refund))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/refunds/{id}/reject",
+    tag = "admin",
+    params(("id" = String, Path, description = "Refund id")),
+    request_body = RefundDecisionRequest,
+    responses(
+        (status = 200, description = "OK", body = RefundResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn reject_refund(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    decision: web::Json<RefundDecisionRequest>,
+) -> Result<HttpResponse, Error> {
+    let refund_id = path.into_inner();
+    match db.reject_refund(&refund_id, decision.into_inner().note).await {
+        Ok(refund) => Ok(HttpResponse::Ok().json(RefundResponse::from(refund))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/refunds/{id}/paid",
+    tag = "admin",
+    params(("id" = String, Path, description = "Refund id")),
+    request_body = RefundPayoutRequest,
+    responses(
+        (status = 200, description = "OK", body = RefundResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn mark_refund_paid(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payout: web::Json<RefundPayoutRequest>,
+) -> Result<HttpResponse, Error> {
+    let refund_id = path.into_inner();
+    match db.mark_refund_paid(&refund_id, &payout.payout_reference).await {
+        Ok(refund) => Ok(HttpResponse::Ok().json(RefundResponse::from(refund))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,122 @@
+use actix_web::{http, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::regulatory::{ExportQuery, GenerateExportRequest, JurisdictionMappingRequest, RegulatoryExportResponse};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/regulatory/exports",
+    tag = "admin",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "OK", body = [RegulatoryExportResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_exports(
+    db: web::Data<MongoDB>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_regulatory_exports(query.jurisdiction.as_deref()).await {
+        Ok(exports) => Ok(HttpResponse::Ok().json(exports.into_iter().map(RegulatoryExportResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/regulatory/exports",
+    tag = "admin",
+    request_body = GenerateExportRequest,
+    responses(
+        (status = 201, description = "Created", body = RegulatoryExportResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn generate_export(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<GenerateExportRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    let generated_by = format!("user:{}", user_id);
+    match db.generate_regulatory_export(&payload.jurisdiction, &payload.period_start, &payload.period_end, &generated_by).await {
+        Ok(export) => Ok(HttpResponse::Created().json(RegulatoryExportResponse::from(export))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/regulatory/exports/{id}/download",
+    tag = "admin",
+    params(("id" = String, Path, description = "Export id")),
+    responses(
+        (status = 200, description = "OK", content_type = "text/csv; charset=utf-8", body = String),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn download_export(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.get_regulatory_export(&path.into_inner()).await {
+        Ok(Some(export)) => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}-{}-{}.csv\"", export.jurisdiction.to_lowercase(), export.period_start, export.period_end),
+            ))
+            .body(export.content)),
+        Ok(None) => Err(AppError::not_found("Export not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/regulatory/jurisdictions",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_mappings(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_jurisdiction_mappings().await {
+        Ok(mappings) => Ok(HttpResponse::Ok().json(mappings)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/regulatory/jurisdictions/{code}",
+    tag = "admin",
+    params(("code" = String, Path, description = "Jurisdiction code")),
+    request_body = JurisdictionMappingRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn upsert_mapping(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<JurisdictionMappingRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.upsert_jurisdiction_mapping(&path.into_inner(), &payload).await {
+        Ok(mapping) => Ok(HttpResponse::Ok().json(mapping)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::settlement::{GenerateSettlementsRequest, SettlementPaidRequest, SettlementQuery, SettlementResponse};
+use crate::models::tenancy::Tenant;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/settlements",
+    tag = "admin",
+    request_body = GenerateSettlementsRequest,
+    responses(
+        (status = 201, description = "Created", body = [SettlementResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn generate_settlements(
+    db: web::Data<MongoDB>,
+    req: web::Json<GenerateSettlementsRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.generate_settlements(&req.period_start, &req.period_end).await {
+        Ok(settlements) => Ok(HttpResponse::Created().json(settlements.into_iter().map(SettlementResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/settlements",
+    tag = "admin",
+    params(SettlementQuery),
+    responses(
+        (status = 200, description = "OK", body = [SettlementResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_settlements(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    query: web::Query<SettlementQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_settlements(tenant.operator_filter(query.operator.as_deref()), query.status.as_deref()).await {
+        Ok(settlements) => Ok(HttpResponse::Ok().json(settlements.into_iter().map(SettlementResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/settlements/{id}/paid",
+    tag = "admin",
+    params(("id" = String, Path, description = "Settlement id")),
+    request_body = SettlementPaidRequest,
+    responses(
+        (status = 200, description = "OK", body = SettlementResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn mark_settlement_paid(
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payout: web::Json<SettlementPaidRequest>,
+) -> Result<HttpResponse, Error> {
+    let settlement_id = path.into_inner();
+    match db.mark_settlement_paid(&settlement_id, &payout.payout_reference).await {
+        Ok(settlement) => Ok(HttpResponse::Ok().json(SettlementResponse::from(settlement))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/operators/{operator}/statement",
+    tag = "admin",
+    params(("operator" = String, Path, description = "Operator name")),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_operator_statement(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let operator = path.into_inner();
+    if !tenant.owns_operator(&operator) {
+        return Err(AppError::not_found("Operator not found").into());
+    }
+    match db.get_operator_statement(&operator).await {
+        Ok(statement) => Ok(HttpResponse::Ok().json(statement)),
+        Err(e) => Err(e.into()),
+    }
+}
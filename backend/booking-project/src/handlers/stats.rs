@@ -0,0 +1,29 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::models::stats::PublicStatsResponse;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/stats/public",
+    tag = "stats",
+    responses(
+        (status = 200, description = "OK", body = PublicStatsResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_public_stats(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    // Served from the snapshot written by the stats job; only computed inline before the first run
+    let stats = match db.get_public_stats().await {
+        Ok(Some(stats)) => Ok(stats),
+        Ok(None) => db.refresh_public_stats().await,
+        Err(e) => Err(e),
+    };
+
+    match stats {
+        Ok(stats) => Ok(HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+            .json(PublicStatsResponse::from(stats))),
+        Err(e) => Err(e.into()),
+    }
+}
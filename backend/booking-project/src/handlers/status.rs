@@ -0,0 +1,53 @@
+use actix_web::{http::header, web, HttpResponse, Error};
+
+use crate::db::MongoDB;
+use crate::models::status::{HealthResponse, ReadinessResponse, StatusResponse};
+
+/// Liveness: answers as long as the process is serving, without touching any dependency, so a
+/// database outage doesn't get every instance restarted. Also served at `/api/health`.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "status",
+    responses(
+        (status = 200, description = "OK", body = HealthResponse),
+    ),
+)]
+pub async fn health_check() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .json(HealthResponse::now())
+}
+
+/// Readiness: pings each dependency and answers 503 until they all respond, so the
+/// orchestrator holds traffic back from an instance that can't serve it.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "status",
+    responses(
+        (status = 200, description = "Ready", body = ReadinessResponse),
+        (status = 503, description = "A dependency is down", body = ReadinessResponse),
+    ),
+)]
+pub async fn readiness_check(db: web::Data<MongoDB>) -> HttpResponse {
+    let readiness = ReadinessResponse::new(db.check_dependencies().await);
+    let mut response = if readiness.is_ready() { HttpResponse::Ok() } else { HttpResponse::ServiceUnavailable() };
+    response.insert_header((header::CACHE_CONTROL, "no-store")).json(readiness)
+}
+
+// Public and unauthenticated, so a short shared cache absorbs status-page polling
+#[utoipa::path(
+    get,
+    path = "/api/status",
+    tag = "status",
+    responses(
+        (status = 200, description = "OK", body = crate::models::status::StatusResponse),
+    ),
+)]
+pub async fn get_status(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let status = StatusResponse::new(db.get_system_status().await);
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=30"))
+        .json(status))
+}
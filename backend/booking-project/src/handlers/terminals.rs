@@ -0,0 +1,75 @@
+use actix_web::{http::header, web, HttpResponse, Error, HttpRequest};
+use crate::crypto::fnv1a;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+use crate::models::terminal::DepartureBoardQuery;
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/terminals",
+    tag = "terminals",
+    responses(
+        (status = 200, description = "OK", body = [crate::models::terminal::Terminal]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_terminals(db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    match db.get_terminals().await {
+        Ok(terminals) => Ok(HttpResponse::Ok().json(terminals)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/terminals/{id}/departures",
+    tag = "terminals",
+    params(
+        ("id" = String, Path, description = "Terminal id"),
+        DepartureBoardQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", body = crate::models::terminal::DepartureBoardResponse),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+)]
+pub async fn get_departures(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<DepartureBoardQuery>,
+) -> Result<HttpResponse, Error> {
+    let terminal = path.into_inner();
+    let date = query.date.clone().unwrap_or_else(|| {
+        chrono::Utc::now().with_timezone(&local_offset()).format("%Y-%m-%d").to_string()
+    });
+
+    let board = match db.get_departure_board(&terminal, &date).await {
+        Ok(Some(board)) => board,
+        Ok(None) => return Err(AppError::not_found("Terminal not found").into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Kiosks poll every 30s; the ETag only changes when the board itself does
+    let etag = format!("\"{:x}\"", fnv1a(&serde_json::to_string(&board.departures).unwrap_or_default()));
+
+    let not_modified = req.headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').any(|tag| tag.trim() == etag))
+        .unwrap_or(false);
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .json(board))
+}
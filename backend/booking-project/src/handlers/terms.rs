@@ -0,0 +1,59 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::config::TermsConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::{AuthenticatedUser, OptionalClaims};
+use crate::models::terms::{AcceptTermsRequest, TermsResponse};
+use crate::openapi::ErrorResponse;
+
+// Clients show the current terms and retry with `accepted_terms_version` set
+pub(crate) fn acceptance_required() -> AppError {
+    let terms = TermsConfig::from_env();
+    AppError::TermsNotAccepted { version: terms.version, url: terms.url }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/terms",
+    tag = "terms",
+    responses(
+        (status = 200, description = "OK", body = TermsResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_current_terms(claims: OptionalClaims, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let terms = TermsConfig::from_env();
+    let accepted_version = match claims.user_id() {
+        Some(user_id) => db.get_accepted_terms_version(&user_id).await.ok().flatten(),
+        None => None,
+    };
+    Ok(HttpResponse::Ok().json(TermsResponse {
+        version: terms.version,
+        url: terms.url,
+        accepted_version,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/terms/accept",
+    tag = "terms",
+    request_body = AcceptTermsRequest,
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn accept_terms(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    payload: web::Json<AcceptTermsRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+
+    match db.accept_terms(&user_id, &payload.version, "reacceptance").await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
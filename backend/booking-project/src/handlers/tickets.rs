@@ -0,0 +1,82 @@
+use actix_web::{http, web, HttpRequest, HttpResponse, Error};
+
+use crate::config::TicketConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::handlers::buses::requested_document_language;
+use crate::handlers::conductor::trip_access;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::localization::DocumentQuery;
+use crate::models::ticket::{verify_ticket, ScanTicketRequest, ScannedTicketResponse};
+use crate::openapi::ErrorResponse;
+
+/// The e-ticket for a confirmed booking as a one-page PDF, with a signed QR code for boarding.
+#[utoipa::path(
+    get,
+    path = "/api/bookings/{id}/ticket",
+    tag = "bookings",
+    params(
+        ("id" = String, Path, description = "Booking id"),
+        DocumentQuery,
+    ),
+    responses(
+        (status = 200, description = "OK", content_type = "application/pdf", body = String),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_booking_ticket(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    query: web::Query<DocumentQuery>,
+) -> Result<HttpResponse, Error> {
+    let ticket = match db.get_booking_ticket(&path.into_inner(), &user.id, requested_document_language(&req, query.lang.as_deref())).await {
+        Ok(ticket) => ticket,
+        Err(e) => return Err(e.into()),
+    };
+    match ticket.to_pdf() {
+        Ok(pdf) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                http::header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"ticket-{}.pdf\"", ticket.receipt.reference),
+            ))
+            .insert_header((http::header::CONTENT_LANGUAGE, ticket.receipt.language.clone()))
+            .body(pdf)),
+        Err(e) => Err(AppError::internal(e).into()),
+    }
+}
+
+/// Checks a passenger in from their ticket's QR code. Takes the conductor's trip token, which
+/// must be for the trip the ticket is on.
+#[utoipa::path(
+    post,
+    path = "/api/checkin/scan",
+    tag = "conductor",
+    request_body = ScanTicketRequest,
+    responses(
+        (status = 200, description = "OK", body = ScannedTicketResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Not allowed", body = ErrorResponse),
+    ),
+)]
+pub async fn scan_ticket(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    payload: web::Json<ScanTicketRequest>,
+) -> Result<HttpResponse, Error> {
+    let ticket = match verify_ticket(&payload.qr, &TicketConfig::from_env().signing_key) {
+        Ok(ticket) => ticket,
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+    let access = trip_access(&req, &db, &ticket.bus_id, &ticket.travel_date).await?;
+
+    match db.scan_ticket(&access, &ticket).await {
+        Ok(scanned) => Ok(HttpResponse::Ok().json(scanned)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,77 @@
+use actix_web::{web, HttpResponse, Error};
+use serde_json::json;
+
+use crate::db::MongoDB;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::trip_history::{PastTripResponse, RateTripRequest, RebookQuote, TripHistoryQuery};
+use crate::openapi::ErrorResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me/trips/history",
+    tag = "users",
+    params(TripHistoryQuery),
+    responses(
+        (status = 200, description = "OK", body = [PastTripResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_trip_history(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    query: web::Query<TripHistoryQuery>,
+) -> Result<HttpResponse, Error> {
+    match db.get_trip_history(&user.id, query.limit).await {
+        Ok(trips) => Ok(HttpResponse::Ok().json(trips)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/bookings/{id}/rating",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    request_body = RateTripRequest,
+    responses(
+        (status = 201, description = "Created"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn rate_trip(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+    payload: web::Json<RateTripRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.rate_trip(&user.id, &path.into_inner(), &payload).await {
+        Ok(rating) => Ok(HttpResponse::Created().json(json!({ "score": rating.score, "comment": rating.comment }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Pre-fills booking the same trip again: today's fare, last time's seat and passenger, and
+/// the next dates with seats left. Nothing is reserved until the new booking is made.
+#[utoipa::path(
+    post,
+    path = "/api/bookings/{id}/rebook",
+    tag = "bookings",
+    params(("id" = String, Path, description = "Booking id")),
+    responses(
+        (status = 200, description = "OK", body = RebookQuote),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn rebook(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    match db.rebook_quote(&user.id, &path.into_inner()).await {
+        Ok(quote) => Ok(HttpResponse::Ok().json(quote)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,346 @@
+use actix_web::{web, HttpResponse, Error};
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::bus::operator_from_bus_number;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::crew::{AssignCrewRequest, CrewResponse};
+use crate::models::schedule::{ScheduleRequest, TripQuery, TripResponse};
+use crate::models::tenancy::Tenant;
+use crate::models::trip::{AnnouncementRequest, AnnouncementResponse, AnnouncementResult, UpdateTripStatusRequest, VehicleSwapRequest};
+use serde_json::json;
+use crate::openapi::ErrorResponse;
+
+/// The timetable: every trip running on a date, optionally on one route.
+#[utoipa::path(
+    get,
+    path = "/api/trips",
+    tag = "trips",
+    params(TripQuery),
+    responses(
+        (status = 200, description = "OK", body = [TripResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn list_trips(db: web::Data<MongoDB>, query: web::Query<TripQuery>) -> Result<HttpResponse, Error> {
+    match db.list_trips(&query).await {
+        Ok(trips) => Ok(HttpResponse::Ok().json(trips)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/buses/{id}/schedule",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    responses(
+        (status = 200, description = "OK", body = ScheduleRequest),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_bus_schedule(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    let schedule = match db.string_to_id(&bus_id) {
+        Ok(bus_oid) => db.get_schedule(bus_oid).await,
+        Err(e) => Err(e),
+    };
+    match schedule {
+        Ok(Some(schedule)) => Ok(HttpResponse::Ok().json(ScheduleRequest::from(schedule))),
+        Ok(None) => Err(AppError::not_found("This bus has no schedule and runs every day").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/buses/{id}/schedule",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    request_body = ScheduleRequest,
+    responses(
+        (status = 200, description = "OK", body = ScheduleRequest),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn set_bus_schedule(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<ScheduleRequest>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.set_schedule(&bus_id, payload.into_inner(), &user.actor()).await {
+        Ok(schedule) => Ok(HttpResponse::Ok().json(ScheduleRequest::from(schedule))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/buses/{id}/schedule",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bus id")),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_bus_schedule(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let bus_id = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.delete_schedule(&bus_id, &user.actor()).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(AppError::not_found("This bus has no schedule").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/trips/{bus_id}/{date}/status",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = UpdateTripStatusRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_trip_status(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<UpdateTripStatusRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.set_trip_status(&bus_id, &travel_date, &payload).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(json!({
+            "bus_id": status.bus_id.to_hex(),
+            "travel_date": status.travel_date,
+            "status": status.status,
+            "delay_minutes": status.delay_minutes,
+            "note": status.note,
+            "actual_departure": status.actual_departure.and_then(|t| t.try_to_rfc3339_string().ok()),
+            "actual_arrival": status.actual_arrival.and_then(|t| t.try_to_rfc3339_string().ok()),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/trips/{bus_id}/{date}/vehicle",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = VehicleSwapRequest,
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn swap_trip_vehicle(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<VehicleSwapRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    if !tenant.owns_operator(&operator_from_bus_number(&payload.bus_number)) {
+        return Err(AppError::from("Replacement vehicle must belong to your operator").into());
+    }
+    match db.swap_trip_vehicle(&bus_id, &travel_date, &payload).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(result)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/trips/{bus_id}/{date}/announcements",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = AnnouncementRequest,
+    responses(
+        (status = 201, description = "Created"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn post_announcement(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<AnnouncementRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.post_trip_announcement(&bus_id, &travel_date, &payload, "admin").await {
+        Ok((announcement, passengers_notified)) => Ok(HttpResponse::Created().json(AnnouncementResult {
+            announcement: AnnouncementResponse::from(announcement),
+            passengers_notified,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/trips/{bus_id}/{date}/announcements",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = [AnnouncementResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_announcements(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    let bus_oid = match db.string_to_id(&bus_id) {
+        Ok(oid) => oid,
+        Err(e) => return Err(e.into()),
+    };
+    match db.get_trip_announcements(bus_oid, &travel_date).await {
+        Ok(announcements) => Ok(HttpResponse::Ok().json(announcements.into_iter().map(AnnouncementResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/trips/{bus_id}/{date}/crew",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    request_body = AssignCrewRequest,
+    responses(
+        (status = 201, description = "Created", body = CrewResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn assign_crew(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+    payload: web::Json<AssignCrewRequest>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    match db.assign_crew(&bus_id, &travel_date, &payload).await {
+        Ok(assignment) => Ok(HttpResponse::Created().json(CrewResponse::from(assignment))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/trips/{bus_id}/{date}/crew",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = [CrewResponse]),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_crew(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    let bus_oid = match db.string_to_id(&bus_id) {
+        Ok(oid) => oid,
+        Err(e) => return Err(e.into()),
+    };
+    match db.get_trip_crew(bus_oid, &travel_date).await {
+        Ok(crew) => Ok(HttpResponse::Ok().json(crew.into_iter().map(CrewResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/trips/{bus_id}/{date}/crew/{id}",
+    tag = "admin",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+        ("id" = String, Path, description = "Crew assignment id"),
+    ),
+    responses(
+        (status = 204, description = "No content"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_crew(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<(String, String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date, assignment_id) = path.into_inner();
+    db.ensure_bus_owned(&tenant, &bus_id).await?;
+    let bus_oid = match db.string_to_id(&bus_id) {
+        Ok(oid) => oid,
+        Err(e) => return Err(e.into()),
+    };
+    match db.remove_crew(bus_oid, &travel_date, &assignment_id).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Err(AppError::not_found("Crew assignment not found").into()),
+        Err(e) => Err(e.into()),
+    }
+}
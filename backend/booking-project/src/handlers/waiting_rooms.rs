@@ -0,0 +1,166 @@
+use actix_web::{web, HttpResponse, Error, HttpRequest};
+
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::handlers::auth::anonymous_id_from_request;
+use crate::middleware::auth::OptionalClaims;
+use crate::models::tenancy::Tenant;
+use crate::models::waiting_room::{CreateWaitingRoomRequest, UpdateWaitingRoomRequest, WaitingRoomResponse, WAITING_ROOM_HEADER};
+use crate::openapi::ErrorResponse;
+
+/// Who holds a place in the queue; admission tokens only work for the same visitor.
+pub(crate) fn waiting_room_holder(req: &HttpRequest) -> Option<String> {
+    if let Some(user_id) = OptionalClaims::of(req).user_id() {
+        return Some(format!("user:{}", user_id));
+    }
+    anonymous_id_from_request(req).map(|id| format!("anon:{}", id))
+}
+
+fn missing_holder() -> AppError {
+    AppError::unauthorized("Sign in or start an anonymous session to join the queue")
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/waiting-room/{bus_id}/{date}",
+    tag = "waiting-room",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = WaitingRoomResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+pub async fn get_waiting_room(
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (bus_id, travel_date) = path.into_inner();
+    match db.get_trip_waiting_room(&bus_id, &travel_date).await {
+        Ok(Some(room)) => Ok(HttpResponse::Ok().json(WaitingRoomResponse::from(room))),
+        Ok(None) => Err(AppError::not_found("There is no waiting room for this trip").into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/waiting-room/{bus_id}/{date}/join",
+    tag = "waiting-room",
+    params(
+        ("bus_id" = String, Path, description = "Bus id"),
+        ("date" = String, Path, description = "Travel date, YYYY-MM-DD"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn join_waiting_room(
+    req: HttpRequest,
+    db: web::Data<MongoDB>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, Error> {
+    let Some(holder) = waiting_room_holder(&req) else {
+        return Err(missing_holder().into());
+    };
+    let (bus_id, travel_date) = path.into_inner();
+    match db.join_waiting_room(&bus_id, &travel_date, &holder).await {
+        Ok(position) => Ok(HttpResponse::Ok().json(position)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/waiting-room/position",
+    tag = "waiting-room",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+pub async fn get_queue_position(req: HttpRequest, db: web::Data<MongoDB>) -> Result<HttpResponse, Error> {
+    let Some(holder) = waiting_room_holder(&req) else {
+        return Err(missing_holder().into());
+    };
+    let Some(token) = req.headers().get(WAITING_ROOM_HEADER).and_then(|h| h.to_str().ok()) else {
+        return Err(AppError::from("Missing queue token").into());
+    };
+    match db.get_queue_position(token, &holder).await {
+        Ok(position) => Ok(HttpResponse::Ok().json(position)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/waiting-rooms",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = [WaitingRoomResponse]),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_waiting_rooms(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+) -> Result<HttpResponse, Error> {
+    match db.get_waiting_rooms(&tenant).await {
+        Ok(rooms) => Ok(HttpResponse::Ok().json(rooms.into_iter().map(WaitingRoomResponse::from).collect::<Vec<_>>())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/waiting-rooms",
+    tag = "admin",
+    request_body = CreateWaitingRoomRequest,
+    responses(
+        (status = 201, description = "Created", body = WaitingRoomResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_waiting_room(
+    user: AuthenticatedUser,
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    payload: web::Json<CreateWaitingRoomRequest>,
+) -> Result<HttpResponse, Error> {
+    let user_id = user.id;
+    match db.create_waiting_room(&tenant, &format!("user:{}", user_id), &payload).await {
+        Ok(room) => Ok(HttpResponse::Created().json(WaitingRoomResponse::from(room))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/waiting-rooms/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Waiting room id")),
+    request_body = UpdateWaitingRoomRequest,
+    responses(
+        (status = 200, description = "OK", body = WaitingRoomResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn update_waiting_room(
+    db: web::Data<MongoDB>,
+    tenant: web::ReqData<Tenant>,
+    path: web::Path<String>,
+    payload: web::Json<UpdateWaitingRoomRequest>,
+) -> Result<HttpResponse, Error> {
+    match db.update_waiting_room(&tenant, &path.into_inner(), &payload).await {
+        Ok(room) => Ok(HttpResponse::Ok().json(WaitingRoomResponse::from(room))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use log::error;
+
+use crate::config::EventsConfig;
+use crate::db::MongoDB;
+use crate::events::subscribers;
+
+// Events relayed per tick; more wait for the next one
+const RELAY_BATCH: usize = 500;
+
+// Subscribes the built-in consumers and, when events are persisted, relays them from the outbox
+pub fn spawn(db: MongoDB) {
+    subscribers::spawn_all(&db);
+    let config = EventsConfig::from_env();
+    if !config.persist {
+        return;
+    }
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.relay_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = db.record_job_heartbeat("event_relay", config.relay_interval_secs.max(1)).await {
+                error!("Failed to record event relay heartbeat: {}", e);
+            }
+            if let Err(e) = db.relay_domain_events(RELAY_BATCH).await {
+                error!("Failed to relay domain events: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::db::MongoDB;
+
+// Keeps the SEO routes feed in step with the fleet
+pub fn spawn(db: MongoDB) {
+    let every = std::env::var("FEED_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(every));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:route_feed", Duration::from_secs(60)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire route feed lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("route_feed", every).await {
+                error!("Failed to record route feed heartbeat: {}", e);
+            }
+            match db.refresh_route_feed().await {
+                Ok(feed) => {
+                    info!("Route feed refreshed: {} routes", feed.routes.len());
+                    // Each refresh also feeds the day's entry in the price history
+                    if let Err(e) = db.record_route_prices(&feed.routes).await {
+                        error!("Failed to record route prices: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to refresh route feed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use log::{error, info, warn};
+
+use crate::config::GtfsConfig;
+use crate::db::MongoDB;
+
+// Republishes the GTFS feed so its calendar keeps starting today
+pub fn spawn(db: MongoDB) {
+    let every = GtfsConfig::from_env().refresh_secs;
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(every));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:gtfs_feed", Duration::from_secs(300)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire GTFS feed lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("gtfs_feed", every).await {
+                error!("Failed to record GTFS feed heartbeat: {}", e);
+            }
+            match db.refresh_gtfs_feed().await {
+                Ok(feed) => {
+                    info!("GTFS feed published: {} trips", feed.trips);
+                    if !feed.missing_stops.is_empty() {
+                        warn!("GTFS feed left out terminals without coordinates: {}", feed.missing_stops.join(", "));
+                    }
+                }
+                Err(e) => error!("Failed to publish GTFS feed: {}", e),
+            }
+        }
+    });
+}
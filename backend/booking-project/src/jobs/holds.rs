@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::db::MongoDB;
+
+// Returns seats from lapsed holds to sale
+pub fn spawn(db: MongoDB) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:hold_sweeper", Duration::from_secs(60)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire hold sweeper lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("hold_sweeper", 30).await {
+                error!("Failed to record hold sweeper heartbeat: {}", e);
+            }
+            match db.sweep_expired_holds().await {
+                Ok(0) => {}
+                Ok(released) => info!("Released {} expired seat hold(s)", released),
+                Err(e) => error!("Failed to sweep expired holds: {}", e),
+            }
+        }
+    });
+}
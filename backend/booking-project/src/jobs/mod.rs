@@ -0,0 +1,41 @@
+pub mod events;
+pub mod feeds;
+pub mod gtfs;
+pub mod holds;
+pub mod monitoring;
+pub mod outbox;
+pub mod payments;
+pub mod profiling;
+pub mod regulatory;
+pub mod resends;
+pub mod retention;
+pub mod slo;
+pub mod stats;
+pub mod warmup;
+
+use crate::db::MongoDB;
+
+// Starts the background jobs; must be called from within the actix runtime
+pub fn spawn_all(db: MongoDB) {
+    let index_db = db.clone();
+    actix_web::rt::spawn(async move {
+        if let Err(e) = index_db.ensure_lock_indexes().await {
+            log::error!("Failed to create lock indexes: {}", e);
+        }
+    });
+
+    events::spawn(db.clone());
+    stats::spawn(db.clone());
+    feeds::spawn(db.clone());
+    gtfs::spawn(db.clone());
+    holds::spawn(db.clone());
+    monitoring::spawn(db.clone());
+    payments::spawn(db.clone());
+    profiling::spawn(db.clone());
+    regulatory::spawn(db.clone());
+    resends::spawn(db.clone());
+    retention::spawn(db.clone());
+    warmup::spawn(db.clone());
+    slo::spawn();
+    outbox::spawn(db);
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use log::{error, info, warn};
+use mongodb::bson;
+use serde_json::json;
+
+use crate::config::MonitoringConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::monitoring::{BOOKING_ATTEMPT, BOOKING_FAILURE, ENUMERATION_SUSPECTED, PAYMENT_CONFIRMED, PAYMENT_INITIATED};
+
+// Watches failure ratios over a sliding window and alerts the configured webhook on breaches
+pub fn spawn(db: MongoDB) {
+    let config = MonitoringConfig::from_env();
+    if config.webhook_url.is_none() {
+        info!("ALERT_WEBHOOK_URL not set; anomaly alerts will only be logged");
+    }
+
+    actix_web::rt::spawn(async move {
+        if let Err(e) = db.ensure_monitoring_indexes().await {
+            error!("Failed to create monitoring indexes: {}", e);
+        }
+
+        let client = reqwest::Client::new();
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.check_interval_secs));
+        loop {
+            interval.tick().await;
+            // Avoid every instance paging for the same breach
+            let _guard = match db.try_lock("jobs:anomaly_monitor", Duration::from_secs(60)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire anomaly monitor lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("anomaly_monitor", config.check_interval_secs).await {
+                error!("Failed to record anomaly monitor heartbeat: {}", e);
+            }
+            let alerts = match check(&db, &config).await {
+                Ok(alerts) => alerts,
+                Err(e) => {
+                    error!("Anomaly check failed: {}", e);
+                    continue;
+                }
+            };
+
+            for (name, message) in alerts {
+                // The cooldown is shared, since the next check may well run on another instance
+                match db.claim_alert(name, chrono::Duration::minutes(config.cooldown_minutes)).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        error!("Failed to check the {} alert cooldown: {}", name, e);
+                        continue;
+                    }
+                }
+
+                warn!("🚨 {}", message);
+                if let Some(url) = &config.webhook_url {
+                    let result = client.post(url).json(&json!({ "text": format!("🚨 {}", message) })).send().await;
+                    if let Err(e) = result.and_then(|r| r.error_for_status()) {
+                        error!("Failed to deliver alert to webhook: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn check(db: &MongoDB, config: &MonitoringConfig) -> Result<Vec<(&'static str, String)>, AppError> {
+    let since = bson::DateTime::from_millis(
+        (chrono::Utc::now() - chrono::Duration::minutes(config.window_minutes)).timestamp_millis(),
+    );
+    let mut alerts = Vec::new();
+
+    let attempts = db.count_monitoring_events(BOOKING_ATTEMPT, since).await?;
+    let failures = db.count_monitoring_events(BOOKING_FAILURE, since).await?;
+    if attempts >= config.min_sample {
+        let ratio = failures as f64 / attempts as f64;
+        if ratio > config.booking_failure_ratio {
+            alerts.push(("booking_failures", format!(
+                "Booking failures at {:.0}% ({}/{}) over the last {} minutes (threshold {:.0}%)",
+                ratio * 100.0, failures, attempts, config.window_minutes, config.booking_failure_ratio * 100.0
+            )));
+        }
+    }
+
+    let initiated = db.count_monitoring_events(PAYMENT_INITIATED, since).await?;
+    let confirmed = db.count_monitoring_events(PAYMENT_CONFIRMED, since).await?;
+    if initiated >= config.min_sample {
+        let dropoff = 1.0 - confirmed.min(initiated) as f64 / initiated as f64;
+        if dropoff > config.payment_dropoff_ratio {
+            alerts.push(("payment_dropoff", format!(
+                "Payment drop-off at {:.0}% ({} of {} initiated payments unconfirmed) over the last {} minutes — check payment callbacks",
+                dropoff * 100.0, initiated - confirmed.min(initiated), initiated, config.window_minutes
+            )));
+        }
+    }
+
+    // Each one is already a pattern of many failures, so any at all is worth a look
+    let enumeration = db.count_monitoring_events(ENUMERATION_SUSPECTED, since).await?;
+    if enumeration > 0 {
+        alerts.push(("lookup_enumeration", format!(
+            "{} suspected booking reference enumeration attempt(s) over the last {} minutes — see the fraud decision log",
+            enumeration, config.window_minutes
+        )));
+    }
+
+    Ok(alerts)
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use log::{error, info, warn};
+use serde_json::json;
+
+use crate::config::{EmailConfig, EventStreamConfig};
+use crate::db::MongoDB;
+use crate::events::stream::EventPublisher;
+use crate::models::outbox::OutboxMessage;
+use crate::notifications::EmailSender;
+
+// Drains due outbox messages, retrying failures and parking exhausted ones in the DLQ
+pub fn spawn(db: MongoDB) {
+    actix_web::rt::spawn(async move {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            if let Err(e) = db.record_job_heartbeat("outbox", 10).await {
+                error!("Failed to record outbox heartbeat: {}", e);
+            }
+            loop {
+                let message = match db.claim_outbox_message().await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Failed to claim outbox message: {}", e);
+                        break;
+                    }
+                };
+                process(&db, &client, message).await;
+            }
+        }
+    });
+}
+
+async fn process(db: &MongoDB, client: &reqwest::Client, message: OutboxMessage) {
+    let Some(id) = message.id else {
+        return;
+    };
+
+    if let (Some(user_id), Some(purpose)) = (message.user_id, message.requires_consent.as_deref()) {
+        match db.has_consent(user_id, purpose).await {
+            Ok(true) => {}
+            Ok(false) => {
+                if let Err(e) = db.mark_outbox_suppressed(id).await {
+                    error!("Failed to suppress outbox message {}: {}", id, e);
+                }
+                return;
+            }
+            // Promotions are never sent on a guess; the claim lease expires and it is retried
+            Err(e) => {
+                error!("Failed to check consent for outbox message {}: {}", id, e);
+                return;
+            }
+        }
+    }
+
+    match deliver(client, &message).await {
+        Ok(()) => {
+            if let Err(e) = db.mark_outbox_delivered(id).await {
+                error!("Failed to mark outbox message {} delivered: {}", id, e);
+            }
+        }
+        Err(delivery_error) => match db.mark_outbox_failed(&message, &delivery_error).await {
+            Ok(true) => warn!("Outbox message {} moved to dead-letter queue: {}", id, delivery_error),
+            Ok(false) => info!("Outbox message {} failed, will retry: {}", id, delivery_error),
+            Err(e) => error!("Failed to record outbox failure for {}: {}", id, e),
+        },
+    }
+}
+
+async fn deliver(client: &reqwest::Client, message: &OutboxMessage) -> Result<(), String> {
+    if message.channel == "email" {
+        let sender = EmailSender::from_config(&EmailConfig::from_env())?;
+        return sender.send(client, &message.recipient, message.subject.as_deref().unwrap_or_default(), &message.body).await;
+    }
+    if message.channel == "event_stream" {
+        let publisher = EventPublisher::from_config(&EventStreamConfig::from_env())?;
+        return publisher.publish(client, &message.recipient, &message.body).await;
+    }
+
+    // SMS goes through a provider relay configured per environment
+    let url = match message.channel.as_str() {
+        "webhook" => message.recipient.clone(),
+        "sms" => std::env::var("SMS_RELAY_URL").map_err(|_| "No SMS provider configured".to_string())?,
+        other => return Err(format!("Unknown channel: {}", other)),
+    };
+
+    let payload = if message.channel == "webhook" {
+        serde_json::from_str(&message.body).unwrap_or_else(|_| json!({ "text": message.body }))
+    } else {
+        json!({ "to": message.recipient, "subject": message.subject, "body": message.body })
+    };
+
+    let response = client.post(&url).json(&payload).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} responded with {}", message.channel, response.status()));
+    }
+    Ok(())
+}
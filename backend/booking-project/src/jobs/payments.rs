@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::db::MongoDB;
+
+// Cancels bookings nobody paid for in time, and reopens charters whose deposit wasn't paid, so
+// their seats go back on sale
+pub fn spawn(db: MongoDB) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:payment_expiry", Duration::from_secs(120)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire payment expiry lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("payment_expiry", 60).await {
+                error!("Failed to record payment expiry heartbeat: {}", e);
+            }
+            match db.expire_unpaid_bookings().await {
+                Ok(0) => {}
+                Ok(expired) => info!("Cancelled {} unpaid booking(s)", expired),
+                Err(e) => error!("Failed to expire unpaid bookings: {}", e),
+            }
+            match db.expire_unpaid_charters().await {
+                Ok(0) => {}
+                Ok(expired) => info!("Reopened {} charter(s) whose deposit wasn't paid", expired),
+                Err(e) => error!("Failed to expire unpaid charter deposits: {}", e),
+            }
+        }
+    });
+}
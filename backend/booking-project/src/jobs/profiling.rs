@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use log::error;
+
+use crate::config::ProfilingConfig;
+use crate::db::MongoDB;
+
+// Explains the slow queries this instance saw, off the request path; only with QUERY_PROFILING_DEBUG
+pub fn spawn(db: MongoDB) {
+    if !ProfilingConfig::from_env().debug {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            if let Err(e) = db.explain_slow_queries().await {
+                error!("Failed to explain slow queries: {}", e);
+            }
+        }
+    });
+}
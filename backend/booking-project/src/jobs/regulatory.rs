@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::config::RegulatoryConfig;
+use crate::db::MongoDB;
+use crate::models::regulatory::previous_month;
+
+// Files last month's return for each configured jurisdiction once the month has closed
+pub fn spawn(db: MongoDB) {
+    let config = RegulatoryConfig::from_env();
+    if config.jurisdictions.is_empty() {
+        info!("REGULATORY_JURISDICTIONS is empty; scheduled regulatory exports are off");
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.check_interval_secs));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:regulatory_export", Duration::from_secs(300)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire regulatory export lock: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = db.record_job_heartbeat("regulatory_export", config.check_interval_secs).await {
+                error!("Failed to record regulatory export heartbeat: {}", e);
+            }
+
+            let (period_start, period_end) = previous_month(chrono::Utc::now().date_naive());
+            for jurisdiction in &config.jurisdictions {
+                match db.has_regulatory_export(jurisdiction, &period_start, &period_end).await {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
+                        error!("Failed to check regulatory exports for {}: {}", jurisdiction, e);
+                        continue;
+                    }
+                }
+                if let Err(e) = db.generate_regulatory_export(jurisdiction, &period_start, &period_end, "schedule").await {
+                    error!("Failed to generate regulatory export for {}: {}", jurisdiction, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::db::MongoDB;
+
+// Works through bulk confirmation resends, one batch at a time so progress shows as it goes
+pub fn spawn(db: MongoDB) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            loop {
+                let mut run = match db.claim_notification_resend().await {
+                    Ok(Some(run)) => run,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Failed to claim notification resend: {}", e);
+                        break;
+                    }
+                };
+                let run_id = run.id.map(|id| id.to_hex()).unwrap_or_default();
+                // A failed batch is left to the lease; the run is picked up again from its last batch
+                while run.finished_at.is_none() {
+                    run = match db.resend_confirmation_batch(&run).await {
+                        Ok(run) => run,
+                        Err(e) => {
+                            error!("Failed to resend confirmations for {}: {}", run_id, e);
+                            break;
+                        }
+                    };
+                }
+                if run.finished_at.is_some() {
+                    info!(
+                        "Resend {} finished: {} email(s) and {} SMS queued for {} booking(s), {} failed",
+                        run_id, run.emails_queued, run.sms_queued, run.processed, run.failed
+                    );
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::config::RetentionConfig;
+use crate::db::MongoDB;
+
+// Warns, then anonymizes, customer accounts that have been inactive past the retention limit
+pub fn spawn(db: MongoDB) {
+    let config = RetentionConfig::from_env();
+    if config.inactive_years <= 0 {
+        info!("RETENTION_INACTIVE_YEARS is 0; account anonymization is off");
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.check_interval_secs));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:retention", Duration::from_secs(900)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire retention lock: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = db.record_job_heartbeat("retention", config.check_interval_secs).await {
+                error!("Failed to record retention heartbeat: {}", e);
+            }
+
+            match db.send_retention_notices(&config).await {
+                Ok(0) => {}
+                Ok(notified) => info!("Sent {} account anonymization notice(s)", notified),
+                Err(e) => error!("Failed to send retention notices: {}", e),
+            }
+            match db.anonymize_stale_accounts(&config).await {
+                Ok(0) => {}
+                Ok(anonymized) => info!("Anonymized {} inactive account(s)", anonymized),
+                Err(e) => error!("Failed to anonymize inactive accounts: {}", e),
+            }
+        }
+    });
+}
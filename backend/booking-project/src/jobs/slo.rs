@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::metrics::metrics;
+use crate::metrics::slo::{LONG_WINDOW_MINUTES, SHORT_WINDOW_MINUTES};
+
+// Publishes burn rates as gauges and warns while an objective's budget is burning too fast.
+// Each instance tracks its own traffic, so this runs everywhere rather than under a lock
+pub fn spawn() {
+    let metrics = metrics();
+    let config = metrics.slo.config().clone();
+    if config.objectives.is_empty() {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut last_warned: HashMap<String, chrono::DateTime<chrono::Utc>> = HashMap::new();
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.check_interval_secs));
+        loop {
+            interval.tick().await;
+            for reading in metrics.slo.readings() {
+                for (window, burn) in [("5m", reading.short_burn), ("1h", reading.long_burn)] {
+                    metrics.slo_burn_rate.with_label_values(&[&reading.name, window]).set(burn.unwrap_or(0.0));
+                }
+                metrics.slo_budget_remaining.with_label_values(&[&reading.name]).set(reading.budget_remaining.unwrap_or(1.0));
+
+                let (Some(short), Some(long)) = (reading.short_burn, reading.long_burn) else {
+                    continue;
+                };
+                if short < config.burn_rate_alert || long < config.burn_rate_alert {
+                    continue;
+                }
+                let now = chrono::Utc::now();
+                if last_warned.get(&reading.name).is_some_and(|at| now - *at < chrono::Duration::minutes(SHORT_WINDOW_MINUTES)) {
+                    continue;
+                }
+                last_warned.insert(reading.name.clone(), now);
+                warn!(
+                    "SLO {} is burning its latency budget at {:.1}x over {}m and {:.1}x over {}m (alert at {:.1}x); {:.0}% of the budget is left",
+                    reading.name, short, SHORT_WINDOW_MINUTES, long, LONG_WINDOW_MINUTES, config.burn_rate_alert,
+                    reading.budget_remaining.unwrap_or(1.0).max(0.0) * 100.0
+                );
+            }
+        }
+    });
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::db::MongoDB;
+
+// Recomputes the public marketing counters on a fixed interval
+pub fn spawn(db: MongoDB) {
+    let every = std::env::var("STATS_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900);
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(every));
+        loop {
+            interval.tick().await;
+            // Only one instance needs to do the recompute per tick
+            let _guard = match db.try_lock("jobs:public_stats", Duration::from_secs(60)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire stats lock: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = db.record_job_heartbeat("public_stats", every).await {
+                error!("Failed to record public stats heartbeat: {}", e);
+            }
+            match db.refresh_public_stats().await {
+                Ok(stats) => info!(
+                    "Public stats refreshed: {} routes, {} trips this month, {} seats booked",
+                    stats.routes_served, stats.trips_this_month, stats.seats_booked
+                ),
+                Err(e) => error!("Failed to refresh public stats: {}", e),
+            }
+        }
+    });
+}
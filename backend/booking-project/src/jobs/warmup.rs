@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::config::WarmupConfig;
+use crate::db::MongoDB;
+
+// Keeps the next week's seat maps created so the first booking of a date doesn't pay for it
+pub fn spawn(db: MongoDB) {
+    let config = WarmupConfig::from_env();
+    if config.days <= 0 {
+        info!("AVAILABILITY_WARMUP_DAYS is 0; availability warm-up is off");
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            let _guard = match db.try_lock("jobs:availability_warmup", Duration::from_secs(600)).await {
+                Ok(Some(guard)) => guard,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to acquire availability warm-up lock: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = db.record_job_heartbeat("availability_warmup", config.interval_secs).await {
+                error!("Failed to record availability warm-up heartbeat: {}", e);
+            }
+
+            let routes = match db.get_popular_routes(config.search_days, config.routes).await {
+                Ok(routes) => routes.into_iter().map(|r| (r.from, r.to)).collect::<Vec<_>>(),
+                Err(e) => {
+                    error!("Failed to load popular routes for warm-up: {}", e);
+                    continue;
+                }
+            };
+            match db.warm_up_availability(&routes, config.days).await {
+                Ok(0) => {}
+                Ok(created) => info!("Created seat availability for {} upcoming trip(s)", created),
+                Err(e) => error!("Failed to warm up availability: {}", e),
+            }
+        }
+    });
+}
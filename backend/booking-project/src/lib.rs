@@ -0,0 +1,22 @@
+//! The booking API as a library, so `main.rs` and the fuzz targets under `fuzz/` share one build.
+//! With the `client` feature it also carries a typed client for the public API.
+
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config;
+pub mod crypto;
+pub mod db;
+pub mod error;
+pub mod events;
+pub mod models;
+pub mod handlers;
+pub mod jobs;
+pub mod metrics;
+pub mod middleware;
+pub mod notifications;
+pub mod openapi;
+pub mod payments;
+pub mod smoke;
+pub mod tokens;
+#[cfg(test)]
+mod tests;
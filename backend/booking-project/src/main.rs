@@ -1,30 +1,37 @@
-mod db;
-mod models;
-mod handlers;
-mod middleware;
-
 use actix_cors::Cors;
-use actix_web::{http, web, App, HttpServer, Responder, HttpResponse};
-use actix_web::middleware::Logger;
-use db::mongodb::MongoDB;
-use handlers::{auth, buses, bookings};
-use middleware::auth::Auth;
-
-// Simple health check endpoint
-async fn health_check() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "ok",
-        "message": "Server is running",
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    }))
-}
+use actix_web::{http, web, App, HttpServer};
+use actix_web::middleware::{Compress, Condition, Logger};
+use bus_book::config::{CompressionConfig, GeoIpConfig, GtfsConfig, LoadSheddingConfig, RateLimitConfig, SmokeTestConfig};
+use bus_book::db::mongodb::MongoDB;
+use bus_book::handlers::{account, analytics, audit, auth, buses, bookings, campaigns, charters, commission, compare, conductor, dead_letters, devices, duplicates, experiments, fares, feeds, fraud, gtfs, holds, incidents, invitations, ledger, localization, lookup, lost_found, metrics as metrics_handler, next_trip, notifications, operators, parcels, partners, passes, payment_methods, payments, permissions, policies, price_history, rate_limits, refunds, regulatory, settlements, stats, status, terminals, terms, tickets, trip_history, trips, waiting_rooms, webhooks};
+use bus_book::middleware::auth::{AdminAuth, Auth};
+use bus_book::middleware::device_signature::DeviceSignatureGuard;
+use bus_book::middleware::geoip::GeoIp;
+use bus_book::middleware::load_shedding::LoadShedder;
+use bus_book::middleware::metrics::RequestMetrics;
+use bus_book::middleware::rate_limit::RateLimiter;
+use bus_book::middleware::session::SessionGuard;
+use bus_book::middleware::tenancy::TenancyGuard;
+use bus_book::middleware::waiting_room::WaitingRoomGuard;
+use bus_book::openapi::{audience_spec, ApiDoc, Audience};
+use bus_book::{jobs, smoke};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::{SwaggerUi, Url};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
+    // Uptime on /health and /ready counts from here
+    bus_book::models::status::started_at();
     std::env::set_var("RUST_LOG", "debug");
     std::env::set_var("RUST_BACKTRACE", "1");
     env_logger::init();
+
+    // Post-deploy check against an already running instance; see smoke.rs
+    if std::env::args().any(|arg| arg == "--smoke-test") {
+        let passed = smoke::run(SmokeTestConfig::from_env()).await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
     
     let database_url = std::env::var("DATABASE_URL")
         .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
@@ -37,18 +44,116 @@ async fn main() -> std::io::Result<()> {
     
     let db_data = web::Data::new(db.clone());
     
+    if let Err(e) = db.apply_schema_validators().await {
+        eprintln!("⚠️ Failed to apply schema validators: {}", e);
+    }
+
     // Seed data on startup
     if let Err(e) = db.seed_data().await {
         eprintln!("⚠️ Failed to seed data: {}", e);
     }
+
+    if let Err(e) = db.ensure_booking_indexes().await {
+        eprintln!("⚠️ Failed to create booking indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_bus_search_indexes().await {
+        eprintln!("⚠️ Failed to create bus search indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_seat_availability_indexes().await {
+        eprintln!("⚠️ Failed to prepare seat availability: {}", e);
+    }
+    if let Err(e) = db.ensure_blocklist_indexes().await {
+        eprintln!("⚠️ Failed to create blocklist indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_consent_indexes().await {
+        eprintln!("⚠️ Failed to create consent indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_lookup_indexes().await {
+        eprintln!("⚠️ Failed to create booking lookup indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_next_of_kin_indexes().await {
+        eprintln!("⚠️ Failed to create next of kin indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_partner_indexes().await {
+        eprintln!("⚠️ Failed to create partner indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_price_history_indexes().await {
+        eprintln!("⚠️ Failed to create price history indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_rate_limit_indexes().await {
+        eprintln!("⚠️ Failed to create rate limit indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_refresh_token_indexes().await {
+        eprintln!("⚠️ Failed to create refresh token indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_waiting_room_indexes().await {
+        eprintln!("⚠️ Failed to create waiting room indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_webhook_indexes().await {
+        eprintln!("⚠️ Failed to create webhook indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_payment_indexes().await {
+        eprintln!("⚠️ Failed to create payment indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_pass_indexes().await {
+        eprintln!("⚠️ Failed to create pass indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_charter_indexes().await {
+        eprintln!("⚠️ Failed to create charter indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_parcel_indexes().await {
+        eprintln!("⚠️ Failed to create parcel indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_domain_event_indexes().await {
+        eprintln!("⚠️ Failed to create domain event indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_password_reset_indexes().await {
+        eprintln!("⚠️ Failed to create password reset indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_next_trip_indexes().await {
+        eprintln!("⚠️ Failed to create next trip indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_trip_rating_indexes().await {
+        eprintln!("⚠️ Failed to create trip rating indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_device_indexes().await {
+        eprintln!("⚠️ Failed to create device indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_trip_location_indexes().await {
+        eprintln!("⚠️ Failed to create trip location indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_offline_sync_indexes().await {
+        eprintln!("⚠️ Failed to create offline sync indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_notification_resend_indexes().await {
+        eprintln!("⚠️ Failed to create notification resend indexes: {}", e);
+    }
+    if let Err(e) = db.ensure_ledger_indexes().await {
+        eprintln!("⚠️ Failed to create ledger indexes: {}", e);
+    }
+
+    jobs::spawn_all(db.clone());
     
     println!("🚀 Starting server on http://0.0.0.0:8080");
     println!("📡 Frontend should connect to: http://localhost:8080");
-    println!("🏥 Health check: http://localhost:8080/api/health");
+    println!("🏥 Health check: http://localhost:8080/health (readiness: /ready)");
     println!("🚌 Buses API: http://localhost:8080/api/buses");
+    println!("📖 API docs: http://localhost:8080/docs/");
     
+    let compression = CompressionConfig::from_env();
+    let geoip = GeoIp::from_config(&GeoIpConfig::from_env());
+    let load_shedder = LoadShedder::new(LoadSheddingConfig::from_env());
+    let rate_limiter = RateLimiter::new(RateLimitConfig::from_env());
+    let waiting_room_guard = WaitingRoomGuard::default();
+
+    let api_doc = ApiDoc::openapi();
+    let audience_docs: Vec<(Audience, utoipa::openapi::OpenApi)> = Audience::ALL.iter()
+        .map(|audience| (*audience, audience_spec(*audience)))
+        .collect();
+
     HttpServer::new(move || {
         App::new()
+            .wrap(Condition::new(compression.enabled, Compress::default()))
             .wrap(Logger::default())
             .wrap(
                 Cors::default()
@@ -59,31 +164,364 @@ async fn main() -> std::io::Result<()> {
                         http::header::AUTHORIZATION,
                         http::header::ACCEPT,
                         http::header::CONTENT_TYPE,
+                        http::header::ACCEPT_LANGUAGE,
+                        http::header::IF_NONE_MATCH,
+                        http::header::HeaderName::from_static("x-anonymous-id"),
+                        http::header::HeaderName::from_static("x-anonymous-token"),
+                        http::header::HeaderName::from_static("x-device-fingerprint"),
+                        http::header::HeaderName::from_static("x-device-id"),
+                        http::header::HeaderName::from_static("x-device-nonce"),
+                        http::header::HeaderName::from_static("x-device-signature"),
+                        http::header::HeaderName::from_static("x-device-timestamp"),
+                        http::header::HeaderName::from_static("x-waiting-room-token"),
+                    ])
+                    .expose_headers(vec![
+                        http::header::ETAG,
+                        http::header::HeaderName::from_static("x-next-cursor"),
+                        http::header::HeaderName::from_static("x-ratelimit-limit"),
+                        http::header::HeaderName::from_static("x-ratelimit-remaining"),
+                        http::header::RETRY_AFTER,
                     ])
                     .supports_credentials()
                     .max_age(3600)
             )
             .app_data(db_data.clone())
+            .wrap(RequestMetrics)
+            .route("/metrics", web::get().to(metrics_handler::get_metrics))
+            // Probes stay outside /api, clear of its rate limiting, load shedding and waiting room
+            .route("/health", web::get().to(status::health_check))
+            .route("/ready", web::get().to(status::readiness_check))
+            // Ahead of the /api scope, which would otherwise claim the spec's path
+            .service(SwaggerUi::new("/docs/{_:.*}").urls(
+                std::iter::once((Url::with_primary("all", "/api/openapi.json", true), api_doc.clone()))
+                    .chain(audience_docs.iter().map(|(audience, doc)| (Url::new(audience.name(), audience.spec_url()), doc.clone())))
+                    .collect()
+            ))
             .service(
                 web::scope("/api")
-                    .route("/health", web::get().to(health_check))
+                    .wrap(waiting_room_guard.clone())
+                    .wrap(SessionGuard)
+                    .wrap(geoip.clone())
+                    .wrap(rate_limiter.clone())
+                    .wrap(load_shedder.clone())
+                    .route("/health", web::get().to(status::health_check))
                     .service(
                         web::scope("/auth")
                             .route("/register", web::post().to(auth::register))
                             .route("/login", web::post().to(auth::login))
                             .route("/google", web::post().to(auth::google_login))
+                            .route("/anonymous", web::post().to(auth::anonymous_session))
+                            .route("/refresh", web::post().to(auth::refresh))
+                            .route("/logout", web::post().to(auth::logout))
+                            .route("/forgot-password", web::post().to(auth::forgot_password))
+                            .route("/reset-password", web::post().to(auth::reset_password))
                     )
                     .service(
                         web::scope("/buses")
                             .route("", web::get().to(buses::get_buses))
+                            .route("/search", web::get().to(buses::search_buses))
                             .route("/{id}", web::get().to(buses::get_bus))
                             .route("/{id}/seats", web::get().to(buses::get_bus_seats))
+                            .route("/{id}/fares", web::get().to(fares::get_fare_table))
+                            .route("/{id}/quote", web::get().to(fares::quote_fare))
+                            .route("/{id}/policy", web::get().to(policies::get_bus_policy))
                     )
+                    .route("/trips", web::get().to(trips::list_trips))
                     .service(
                         web::scope("/bookings")
                             .route("", web::post().to(bookings::create_booking))
+                            .route("/group", web::post().to(bookings::create_group_booking))
                             .route("/user", web::get().to(bookings::get_user_bookings))
+                            .route("/confirm", web::post().to(bookings::confirm_holds))
+                            .route("/holds", web::post().to(holds::create_hold))
+                            .route("/holds", web::get().to(holds::get_holds))
+                            .route("/holds/{id}", web::delete().to(holds::release_hold))
+                            .route("/lookup", web::post().to(lookup::lookup_booking))
+                            .route("/ref/{code}", web::get().to(lookup::lookup_booking_by_reference))
+                            .route("/shared/{token}", web::get().to(bookings::get_shared_booking))
+                            .route("/{id}", web::get().to(bookings::get_booking_detail))
                             .route("/{id}", web::delete().to(bookings::cancel_booking))
+                            .route("/{id}/change", web::post().to(bookings::change_booking))
+                            .route("/{id}/share", web::post().to(bookings::share_booking))
+                            .route("/{id}/calendar.ics", web::get().to(bookings::get_booking_calendar))
+                            .route("/{id}/receipt.pdf", web::get().to(bookings::get_booking_receipt))
+                            .route("/{id}/ticket", web::get().to(tickets::get_booking_ticket))
+                            .route("/{id}/rating", web::post().to(trip_history::rate_trip))
+                            .route("/{id}/rebook", web::post().to(trip_history::rebook))
+                    )
+                    .service(
+                        web::scope("/conductor/trips/{bus_id}/{date}")
+                            .wrap(DeviceSignatureGuard)
+                            .route("/manifest", web::get().to(conductor::get_trip_manifest))
+                            .route("/manifest.pdf", web::get().to(conductor::get_trip_manifest_pdf))
+                            .route("/check-in", web::post().to(conductor::check_in))
+                            .route("/location", web::post().to(conductor::report_location))
+                            .route("/sync", web::get().to(conductor::download_offline_manifest))
+                            .route("/sync", web::post().to(conductor::upload_offline_batch))
+                    )
+                    .service(
+                        web::resource("/checkin/scan")
+                            .wrap(DeviceSignatureGuard)
+                            .route(web::post().to(tickets::scan_ticket))
+                    )
+                    .service(
+                        web::scope("/waiting-room")
+                            .route("/position", web::get().to(waiting_rooms::get_queue_position))
+                            .route("/{bus_id}/{date}", web::get().to(waiting_rooms::get_waiting_room))
+                            .route("/{bus_id}/{date}/join", web::post().to(waiting_rooms::join_waiting_room))
+                    )
+                    .service(
+                        web::scope("/invitations")
+                            .route("/accept", web::post().to(invitations::accept_invitation))
+                            .route("/{token}", web::get().to(invitations::get_invitation))
+                    )
+                    .route("/webhooks/{provider}", web::post().to(webhooks::receive_webhook))
+                    .service(
+                        web::scope("/partners")
+                            .route("/offers/{id}/click", web::get().to(partners::follow_partner_offer))
+                            .route("/conversions", web::post().to(partners::report_partner_conversion))
+                    )
+                    .route("/routes/compare", web::get().to(compare::compare_route))
+                    .route("/routes/{id}/price-history", web::get().to(price_history::get_price_history))
+                    .route("/localizations", web::get().to(localization::get_localizations))
+                    .service(
+                        web::scope("/feeds")
+                            .route("/routes.xml", web::get().to(feeds::get_routes_xml))
+                            .route("/routes.json", web::get().to(feeds::get_routes_json))
+                            .route("/gtfs.zip", web::get().to(gtfs::get_gtfs_feed))
+                    )
+                    .route("/stats/public", web::get().to(stats::get_public_stats))
+                    .route("/status", web::get().to(status::get_status))
+                    .route("/experiments/assignments", web::get().to(experiments::get_assignments))
+                    .service(
+                        web::scope("/terminals")
+                            .route("", web::get().to(terminals::get_terminals))
+                            .route("/{id}/departures", web::get().to(terminals::get_departures))
+                    )
+                    .service(
+                        web::scope("/payments")
+                            .wrap(Auth)
+                            .route("/mpesa/stk-push", web::post().to(payments::stk_push))
+                            .route("/{id}", web::get().to(payments::get_payment))
+                    )
+                    .service(
+                        web::scope("/passes")
+                            .wrap(Auth)
+                            .route("", web::post().to(passes::buy_pass))
+                            .route("", web::get().to(passes::list_passes))
+                            .route("/{id}", web::get().to(passes::get_pass))
+                    )
+                    .service(
+                        web::scope("/charters")
+                            .wrap(Auth)
+                            .route("", web::post().to(charters::request_charter))
+                            .route("", web::get().to(charters::list_charters))
+                            .route("/{id}", web::get().to(charters::get_charter))
+                            .route("/{id}", web::delete().to(charters::cancel_charter))
+                            .route("/{id}/accept", web::post().to(charters::accept_charter))
+                    )
+                    .service(
+                        web::scope("/parcels")
+                            .route("/{waybill}", web::get().to(parcels::track_parcel))
+                    )
+                    .service(
+                        web::scope("/refunds")
+                            .wrap(Auth)
+                            .route("", web::post().to(refunds::request_refund))
+                            .route("", web::get().to(refunds::get_user_refunds))
+                    )
+                    .service(
+                        web::scope("/account")
+                            .route("/email/confirm/{token}", web::get().to(account::confirm_email_change))
+                            .route("/email/undo/{token}", web::get().to(account::undo_email_change))
+                            .service(
+                                web::resource("/email")
+                                    .wrap(Auth)
+                                    .route(web::post().to(account::request_email_change))
+                            )
+                            .service(
+                                web::resource("/language")
+                                    .wrap(Auth)
+                                    .route(web::put().to(account::set_language))
+                            )
+                            .service(
+                                web::resource("/consents")
+                                    .wrap(Auth)
+                                    .route(web::get().to(account::get_consents))
+                                    .route(web::put().to(account::update_consents))
+                            )
+                            .service(
+                                web::resource("/consents/history")
+                                    .wrap(Auth)
+                                    .route(web::get().to(account::get_consent_history))
+                            )
+                    )
+                    .route("/terms", web::get().to(terms::get_current_terms))
+                    .service(
+                        web::scope("/terms")
+                            .wrap(Auth)
+                            .route("/accept", web::post().to(terms::accept_terms))
+                    )
+                    .service(
+                        web::scope("/users/me")
+                            .wrap(Auth)
+                            .route("/next-trip", web::get().to(next_trip::get_next_trip))
+                            .route("/trips/history", web::get().to(trip_history::get_trip_history))
+                    )
+                    .service(
+                        web::scope("/lost-items")
+                            .wrap(Auth)
+                            .route("", web::post().to(lost_found::report_lost_item))
+                            .route("", web::get().to(lost_found::get_user_lost_items))
+                    )
+                    .service(
+                        web::scope("/payment-methods")
+                            .wrap(Auth)
+                            .route("", web::get().to(payment_methods::get_payment_methods))
+                            .route("", web::post().to(payment_methods::save_payment_method))
+                            .route("/{id}", web::delete().to(payment_methods::remove_payment_method))
+                    )
+                    .service(
+                        web::scope("/notifications")
+                            .wrap(Auth)
+                            .route("", web::get().to(notifications::get_user_notifications))
+                    )
+                    .service(
+                        web::scope("/admin")
+                            .wrap(TenancyGuard)
+                            .wrap(AdminAuth)
+                            .route("/bookings", web::get().to(bookings::search_bookings))
+                            .route("/bookings/review", web::get().to(fraud::get_review_queue))
+                            .route("/bookings/confirmation-resends", web::get().to(notifications::list_confirmation_resends))
+                            .route("/bookings/confirmation-resends", web::post().to(notifications::resend_confirmations))
+                            .route("/bookings/confirmation-resends/{id}", web::get().to(notifications::get_confirmation_resend))
+                            .route("/bookings/{id}/review", web::post().to(fraud::review_booking))
+                            .route("/bookings/{id}/reseat", web::post().to(bookings::reseat_booking))
+                            .route("/refunds", web::get().to(refunds::list_refunds))
+                            .route("/refunds/{id}/approve", web::post().to(refunds::approve_refund))
+                            .route("/refunds/{id}/reject", web::post().to(refunds::reject_refund))
+                            .route("/refunds/{id}/paid", web::post().to(refunds::mark_refund_paid))
+                            .route("/settlements", web::get().to(settlements::list_settlements))
+                            .route("/settlements", web::post().to(settlements::generate_settlements))
+                            .route("/settlements/{id}/paid", web::post().to(settlements::mark_settlement_paid))
+                            .route("/commission-rules", web::get().to(commission::list_commission_rules))
+                            .route("/commission-rules", web::put().to(commission::upsert_commission_rule))
+                            .route("/commission-rules/{id}", web::delete().to(commission::delete_commission_rule))
+                            .route("/lost-items", web::get().to(lost_found::list_lost_items))
+                            .route("/lost-items/{id}/matches", web::get().to(lost_found::get_lost_item_matches))
+                            .route("/lost-items/{id}/status", web::put().to(lost_found::update_lost_item_status))
+                            .route("/found-items", web::get().to(lost_found::list_found_items))
+                            .route("/found-items", web::post().to(lost_found::log_found_item))
+                            .route("/found-items/{id}/status", web::put().to(lost_found::update_found_item_status))
+                            .route("/blocklist", web::get().to(fraud::list_blocklist))
+                            .route("/blocklist", web::post().to(fraud::add_blocklist_entry))
+                            .route("/blocklist/decisions", web::get().to(fraud::list_decisions))
+                            .route("/blocklist/{id}", web::delete().to(fraud::remove_blocklist_entry))
+                            .route("/rate-limits", web::get().to(rate_limits::get_rate_limits))
+                            .route("/rate-limits/usage", web::get().to(rate_limits::get_rate_limit_usage))
+                            .route("/rate-limits/overrides", web::post().to(rate_limits::create_override))
+                            .route("/rate-limits/overrides/{id}", web::delete().to(rate_limits::remove_override))
+                            .route("/rate-limits/blocks", web::get().to(rate_limits::list_abuse_blocks))
+                            .route("/rate-limits/blocks", web::delete().to(rate_limits::reset_abuse_blocks))
+                            .route("/waiting-rooms", web::get().to(waiting_rooms::list_waiting_rooms))
+                            .route("/waiting-rooms", web::post().to(waiting_rooms::create_waiting_room))
+                            .route("/waiting-rooms/{id}", web::put().to(waiting_rooms::update_waiting_room))
+                            .route("/invitations", web::get().to(invitations::list_invitations))
+                            .route("/invitations", web::post().to(invitations::create_invitation))
+                            .route("/invitations/{id}", web::delete().to(invitations::revoke_invitation))
+                            .route("/permissions", web::get().to(permissions::get_permission_matrix))
+                            .route("/audit-log", web::get().to(audit::get_audit_log))
+                            .route("/promotions", web::post().to(campaigns::send_promotion))
+                            .route("/users/duplicates", web::get().to(duplicates::list_duplicate_accounts))
+                            .route("/users/merge", web::post().to(duplicates::merge_accounts))
+                            .route("/users/{id}/permissions", web::get().to(permissions::get_user_permissions))
+                            .route("/users/{id}/role", web::put().to(permissions::assign_role))
+                            .route("/payment-methods/audit", web::get().to(payment_methods::get_vault_audit))
+                            .route("/ledger", web::get().to(ledger::list_ledger_transactions))
+                            .route("/ledger/balances", web::get().to(ledger::get_ledger_balances))
+                            .route("/ledger/check", web::get().to(ledger::check_ledger))
+                            .route("/partner-offers", web::get().to(partners::list_partner_offers))
+                            .route("/partner-offers", web::post().to(partners::create_partner_offer))
+                            .route("/partner-offers/report", web::get().to(partners::get_partner_report))
+                            .route("/partner-offers/{id}", web::put().to(partners::update_partner_offer))
+                            .route("/partner-offers/{id}", web::delete().to(partners::delete_partner_offer))
+                            .route("/gtfs", web::get().to(gtfs::export_gtfs))
+                            .route("/gtfs/stops", web::get().to(gtfs::list_gtfs_stops))
+                            .route("/gtfs/stops/{id}", web::put().to(gtfs::set_gtfs_stop))
+                            .service(
+                                web::resource("/gtfs/import")
+                                    .app_data(web::PayloadConfig::new(GtfsConfig::from_env().max_upload_bytes))
+                                    .route(web::post().to(gtfs::import_gtfs))
+                            )
+                            .route("/webhooks", web::get().to(webhooks::list_webhook_events))
+                            .route("/webhooks/{id}", web::get().to(webhooks::get_webhook_event))
+                            .route("/webhooks/{id}/replay", web::post().to(webhooks::replay_webhook_event))
+                            .route("/operators/{operator}/statement", web::get().to(settlements::get_operator_statement))
+                            .route("/operators/{operator}/settings", web::get().to(operators::get_operator_settings))
+                            .route("/operators/{operator}/settings", web::put().to(operators::update_operator_settings))
+                            .route("/operators/{operator}/booking-email", web::get().to(operators::get_booking_email))
+                            .route("/operators/{operator}/booking-email", web::put().to(operators::set_booking_email))
+                            .route("/operators/{operator}/booking-email", web::delete().to(operators::reset_booking_email))
+                            .route("/regulatory/exports", web::get().to(regulatory::list_exports))
+                            .route("/regulatory/exports", web::post().to(regulatory::generate_export))
+                            .route("/regulatory/exports/{id}/download", web::get().to(regulatory::download_export))
+                            .route("/regulatory/jurisdictions", web::get().to(regulatory::list_mappings))
+                            .route("/regulatory/jurisdictions/{code}", web::put().to(regulatory::upsert_mapping))
+                            .route("/notifications", web::get().to(notifications::get_admin_notifications))
+                            .route("/localizations", web::put().to(localization::upsert_localization))
+                            .route("/trips/{bus_id}/{date}/status", web::put().to(trips::update_trip_status))
+                            .route("/trips/{bus_id}/{date}/vehicle", web::post().to(trips::swap_trip_vehicle))
+                            .route("/trips/{bus_id}/{date}/manifest", web::post().to(conductor::open_manifest))
+                            .route("/trips/{bus_id}/{date}/manifest.pdf", web::get().to(conductor::get_manifest_pdf))
+                            .route("/trips/{bus_id}/{date}/crew", web::get().to(trips::get_crew))
+                            .route("/trips/{bus_id}/{date}/crew", web::post().to(trips::assign_crew))
+                            .route("/trips/{bus_id}/{date}/crew/{id}", web::delete().to(trips::remove_crew))
+                            .route("/trips/{bus_id}/{date}/announcements", web::get().to(trips::get_announcements))
+                            .route("/trips/{bus_id}/{date}/announcements", web::post().to(trips::post_announcement))
+                            .route("/trips/{bus_id}/{date}/incidents", web::post().to(incidents::report_incident))
+                            .route("/incidents", web::get().to(incidents::list_incidents))
+                            .route("/incidents/{id}/attachments", web::post().to(incidents::add_attachment))
+                            .route("/incidents/{id}/resolve", web::put().to(incidents::resolve_incident))
+                            .route("/trips/{bus_id}/{date}/policy", web::put().to(policies::assign_trip_policy))
+                            .route("/buses", web::get().to(buses::list_fleet))
+                            .route("/buses", web::post().to(buses::create_bus))
+                            .route("/buses/{id}", web::put().to(buses::update_bus))
+                            .route("/buses/{id}", web::delete().to(buses::delete_bus))
+                            .route("/charters", web::get().to(charters::list_charter_requests))
+                            .route("/charters/{id}/quote", web::put().to(charters::quote_charter))
+                            .route("/charters/{id}/decline", web::post().to(charters::decline_charter))
+                            .route("/devices", web::get().to(devices::list_devices))
+                            .route("/devices", web::post().to(devices::provision_device))
+                            .route("/devices/{id}/revoke", web::post().to(devices::revoke_device))
+                            .route("/parcels", web::get().to(parcels::list_parcels))
+                            .route("/parcels", web::post().to(parcels::create_parcel))
+                            .route("/parcels/{waybill}/status", web::put().to(parcels::update_parcel_status))
+                            .route("/buses/{id}/schedule", web::get().to(trips::get_bus_schedule))
+                            .route("/buses/{id}/schedule", web::put().to(trips::set_bus_schedule))
+                            .route("/buses/{id}/schedule", web::delete().to(trips::delete_bus_schedule))
+                            .route("/buses/{id}/policy", web::put().to(policies::assign_bus_policy))
+                            .route("/buses/{id}/fares", web::put().to(fares::set_fare_table))
+                            .route("/buses/{id}/fares", web::delete().to(fares::delete_fare_table))
+                            .route("/policies", web::get().to(policies::list_policies))
+                            .route("/policies", web::post().to(policies::create_policy))
+                            .route("/policies/{id}", web::get().to(policies::get_policy))
+                            .route("/policies/{id}", web::put().to(policies::update_policy))
+                            .route("/policies/{id}", web::delete().to(policies::delete_policy))
+                            .route("/dead-letters", web::get().to(dead_letters::list_dead_letters))
+                            .route("/dead-letters/metrics", web::get().to(dead_letters::get_queue_metrics))
+                            .route("/dead-letters/{id}", web::get().to(dead_letters::get_dead_letter))
+                            .route("/dead-letters/{id}", web::put().to(dead_letters::update_dead_letter))
+                            .route("/dead-letters/{id}", web::delete().to(dead_letters::delete_dead_letter))
+                            .route("/dead-letters/{id}/requeue", web::post().to(dead_letters::requeue_dead_letter))
+                            .route("/experiments", web::get().to(experiments::list_experiments))
+                            .route("/experiments", web::put().to(experiments::upsert_experiment))
+                            .route("/analytics/punctuality", web::get().to(analytics::get_punctuality))
+                            .route("/analytics/countries", web::get().to(analytics::get_countries))
+                            .route("/analytics/demand-heatmap", web::get().to(analytics::get_demand_heatmap))
+                            .route("/analytics/incidents", web::get().to(analytics::get_incidents))
+                            .route("/analytics/revenue", web::get().to(analytics::get_revenue))
+                            .route("/analytics/searches/popular-routes", web::get().to(analytics::get_popular_routes))
+                            .route("/analytics/searches/zero-results", web::get().to(analytics::get_zero_result_searches))
                     )
             )
     })
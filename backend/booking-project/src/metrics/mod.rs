@@ -0,0 +1,109 @@
+//! Process-wide Prometheus registry, served in text format on `/metrics`.
+
+pub mod slo;
+
+use std::sync::OnceLock;
+
+use prometheus::{Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+
+use crate::config::SloConfig;
+use self::slo::SloTracker;
+
+pub struct Metrics {
+    registry: Registry,
+    pub request_duration: HistogramVec,
+    pub db_command_duration: HistogramVec,
+    pub db_slow_queries: IntCounterVec,
+    pub requests_shed: IntCounterVec,
+    pub requests_rate_limited: IntCounterVec,
+    pub slo_burn_rate: GaugeVec,
+    pub slo_budget_remaining: GaugeVec,
+    pub domain_events: IntCounterVec,
+    pub bookings: IntCounterVec,
+    pub slo: SloTracker,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route")
+                .buckets(vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.3, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["method", "route", "status"],
+        ).expect("valid histogram");
+        let db_command_duration = HistogramVec::new(
+            HistogramOpts::new("mongodb_command_duration_seconds", "MongoDB command latency by collection")
+                .buckets(vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.2, 0.5, 1.0, 5.0]),
+            &["command", "collection"],
+        ).expect("valid histogram");
+        let db_slow_queries = IntCounterVec::new(
+            Opts::new("mongodb_slow_queries_total", "MongoDB commands slower than SLOW_QUERY_MS"),
+            &["command", "collection"],
+        ).expect("valid counter");
+        let requests_shed = IntCounterVec::new(
+            Opts::new("http_requests_shed_total", "Requests refused with 503 while overloaded"),
+            &["area"],
+        ).expect("valid counter");
+        let requests_rate_limited = IntCounterVec::new(
+            Opts::new("http_requests_rate_limited_total", "Requests refused with 429 for going over a caller's limit"),
+            &["reason"],
+        ).expect("valid counter");
+        let slo_burn_rate = GaugeVec::new(
+            Opts::new("slo_burn_rate", "Error budget burn rate; 1 spends the budget exactly over the SLO window"),
+            &["slo", "window"],
+        ).expect("valid gauge");
+        let slo_budget_remaining = GaugeVec::new(
+            Opts::new("slo_error_budget_remaining_ratio", "Share of the SLO window's error budget left"),
+            &["slo"],
+        ).expect("valid gauge");
+        let domain_events = IntCounterVec::new(
+            Opts::new("domain_events_total", "Domain events handed to subscribers, by type"),
+            &["type"],
+        ).expect("valid counter");
+        let bookings = IntCounterVec::new(
+            Opts::new("bookings_total", "Booking attempts, single or group, by whether they went through"),
+            &["outcome"],
+        ).expect("valid counter");
+
+        for collector in [
+            Box::new(request_duration.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(db_command_duration.clone()),
+            Box::new(db_slow_queries.clone()),
+            Box::new(requests_shed.clone()),
+            Box::new(requests_rate_limited.clone()),
+            Box::new(slo_burn_rate.clone()),
+            Box::new(slo_budget_remaining.clone()),
+            Box::new(domain_events.clone()),
+            Box::new(bookings.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            request_duration,
+            db_command_duration,
+            db_slow_queries,
+            requests_shed,
+            requests_rate_limited,
+            slo_burn_rate,
+            slo_budget_remaining,
+            domain_events,
+            bookings,
+            slo: SloTracker::new(SloConfig::from_env()),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
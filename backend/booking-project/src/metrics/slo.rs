@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{SloConfig, SloObjective};
+
+// Multi-window alerting: a fast burn must show in both before it warns, so a single slow
+// request can't trip it and a sustained regression can't hide behind a good hour
+pub const SHORT_WINDOW_MINUTES: i64 = 5;
+pub const LONG_WINDOW_MINUTES: i64 = 60;
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    minute: i64,
+    total: u64,
+    slow: u64,
+}
+
+pub struct SloReading {
+    pub name: String,
+    pub short_burn: Option<f64>,
+    pub long_burn: Option<f64>,
+    pub budget_remaining: Option<f64>,
+}
+
+/// Per-minute counts of requests over and under each objective's latency threshold.
+pub struct SloTracker {
+    config: SloConfig,
+    buckets: Mutex<Vec<VecDeque<Bucket>>>,
+}
+
+impl SloTracker {
+    pub fn new(config: SloConfig) -> Self {
+        let buckets = Mutex::new(vec![VecDeque::new(); config.objectives.len()]);
+        Self { config, buckets }
+    }
+
+    pub fn config(&self) -> &SloConfig {
+        &self.config
+    }
+
+    pub fn record(&self, method: &str, route: &str, elapsed: Duration) {
+        self.record_at(method, route, elapsed, chrono::Utc::now().timestamp() / 60);
+    }
+
+    pub(crate) fn record_at(&self, method: &str, route: &str, elapsed: Duration, minute: i64) {
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return;
+        };
+        for (objective, series) in self.config.objectives.iter().zip(buckets.iter_mut()) {
+            if objective.method != method || objective.route != route {
+                continue;
+            }
+            if series.back().is_none_or(|b| b.minute != minute) {
+                series.push_back(Bucket { minute, total: 0, slow: 0 });
+            }
+            // Older buckets than the budget window are never read again
+            while series.front().is_some_and(|b| b.minute <= minute - self.config.budget_window_minutes) {
+                series.pop_front();
+            }
+            if let Some(bucket) = series.back_mut() {
+                bucket.total += 1;
+                if elapsed.as_millis() as u64 > objective.threshold_ms {
+                    bucket.slow += 1;
+                }
+            }
+        }
+    }
+
+    pub fn readings(&self) -> Vec<SloReading> {
+        self.readings_at(chrono::Utc::now().timestamp() / 60)
+    }
+
+    pub(crate) fn readings_at(&self, minute: i64) -> Vec<SloReading> {
+        let Ok(buckets) = self.buckets.lock() else {
+            return Vec::new();
+        };
+        self.config.objectives.iter().zip(buckets.iter()).map(|(objective, series)| {
+            let budget_burn = burn_rate(objective, series, minute, self.config.budget_window_minutes);
+            SloReading {
+                name: objective.name.clone(),
+                short_burn: burn_rate(objective, series, minute, SHORT_WINDOW_MINUTES),
+                long_burn: burn_rate(objective, series, minute, LONG_WINDOW_MINUTES),
+                budget_remaining: budget_burn.map(|burn| 1.0 - burn),
+            }
+        }).collect()
+    }
+}
+
+/// Observed slow ratio over the last `window` minutes divided by the allowed one; None without traffic.
+fn burn_rate(objective: &SloObjective, series: &VecDeque<Bucket>, minute: i64, window: i64) -> Option<f64> {
+    let (total, slow) = series
+        .iter()
+        .filter(|b| b.minute > minute - window)
+        .fold((0u64, 0u64), |(total, slow), b| (total + b.total, slow + b.slow));
+    if total == 0 {
+        return None;
+    }
+    let budget = (1.0 - objective.target).max(f64::EPSILON);
+    Some(slow as f64 / total as f64 / budget)
+}
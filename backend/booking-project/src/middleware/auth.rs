@@ -1,23 +1,125 @@
 use actix_web::{
     body::EitherBody,
-    dev::{Service, Transform, ServiceRequest, ServiceResponse, Response},
-    Error, HttpResponse, http
+    dev::{Payload, Service, Transform, ServiceRequest, ServiceResponse},
+    error::InternalError,
+    Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError, http
 };
 use futures::future::{Ready, LocalBoxFuture, ready};
 use std::task::{Context, Poll};
 use std::rc::Rc;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use jsonwebtoken::{decode, errors::ErrorKind, DecodingKey, Validation, Algorithm};
+use crate::config::TokenConfig;
+use crate::error::AppError;
 use crate::models::Claims;
+use crate::models::permissions::{admin_permission, authorize};
+
+// Every token the API signs or checks uses this one secret; see TokenConfig
+pub fn jwt_secret() -> String {
+    TokenConfig::from_env().secret
+}
+
+/// The token in an `Authorization` header value of the form `Bearer <token>`.
+pub fn bearer_token(header: &str) -> Option<&str> {
+    header.strip_prefix("Bearer ").map(str::trim).filter(|token| !token.is_empty())
+}
+
+/// Claims of an HS256 token signed with `secret`; expired or tampered tokens are rejected, and
+/// so are refresh tokens, which only buy a new access token.
+pub fn decode_claims(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &Validation::new(Algorithm::HS256))?.claims;
+    if !claims.authorizes_requests() {
+        return Err(ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
+}
+
+/// Claims from a raw `Authorization` header value, if it carries a valid bearer token.
+pub fn claims_from_header(header: &[u8]) -> Option<Claims> {
+    let token = std::str::from_utf8(header).ok().and_then(bearer_token)?;
+    decode_claims(token, &jwt_secret()).ok()
+}
+
+/// The caller's claims if the request carries a valid access token, for endpoints that anyone
+/// may call but that answer signed-in users differently. Never rejects the request.
+#[derive(Clone, Default)]
+pub struct OptionalClaims(pub Option<Claims>);
+
+impl OptionalClaims {
+    /// Reads the claims outside an extractor, e.g. in middleware or helpers given the request.
+    pub fn of(req: &HttpRequest) -> Self {
+        // The auth middlewares leave the claims they checked behind; elsewhere the header is read here
+        Self(req.extensions().get::<Claims>().cloned().or_else(|| {
+            req.headers().get(http::header::AUTHORIZATION).and_then(|h| claims_from_header(h.as_bytes()))
+        }))
+    }
+
+    pub fn user_id(self) -> Option<String> {
+        self.0.map(|claims| claims.sub)
+    }
+}
+
+impl FromRequest for OptionalClaims {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Self::of(req)))
+    }
+}
+
+/// The signed-in caller. Declaring it as a handler argument is the authentication check:
+/// without a valid access token the request is answered 401 before the handler runs.
+#[derive(Clone)]
+pub struct AuthenticatedUser {
+    pub id: String,
+    pub role: String,
+    pub claims: Claims,
+}
+
+impl AuthenticatedUser {
+    pub fn from_claims(claims: Claims) -> Self {
+        Self { id: claims.sub.clone(), role: claims.role.clone(), claims }
+    }
+
+    /// How the user is named in audit entries and booking history.
+    pub fn actor(&self) -> String {
+        format!("user:{}", self.id)
+    }
+
+    pub fn can(&self, permission: &str) -> bool {
+        authorize(&self.claims, permission)
+    }
+}
+
+fn unauthorized() -> HttpResponse {
+    AppError::unauthorized("Unauthorized").error_response()
+}
+
+fn forbidden(required: &str) -> HttpResponse {
+    AppError::forbidden(format!("Admin access required: {}", required)).error_response()
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match OptionalClaims::of(req).0 {
+            Some(claims) => Ok(Self::from_claims(claims)),
+            None => Err(InternalError::from_response("Unauthorized", unauthorized()).into()),
+        })
+    }
+}
 
 pub struct Auth;
 
 impl<S, B> Transform<S, ServiceRequest> for Auth
 where
-    S: Service<ServiceRequest, Response = Response<EitherBody<B>>, Error = Error> + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = Response<EitherBody<B>>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Transform = AuthMiddleware<S>;
     type InitError = ();
@@ -34,10 +136,10 @@ pub struct AuthMiddleware<S> {
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = Response<EitherBody<B>>, Error = Error> + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     B: 'static,
 {
-    type Response = Response<EitherBody<B>>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -50,60 +152,73 @@ where
         
         Box::pin(async move {
             let auth_header = req.headers().get(http::header::AUTHORIZATION);
-            
-            if let Some(auth_header) = auth_header {
-                if let Ok(auth_str) = auth_header.to_str() {
-                    if auth_str.starts_with("Bearer ") {
-                        let token = &auth_str[7..];
-                        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                        
-                        let validation = Validation::new(Algorithm::HS256);
-                        if decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &validation).is_ok() {
-                            return service.call(req).await;
-                        }
-                    }
-                }
+            if let Some(claims) = auth_header.and_then(|h| claims_from_header(h.as_bytes())) {
+                req.extensions_mut().insert(claims);
+                return service.call(req).await.map(ServiceResponse::map_into_left_body);
             }
             
             let (request, _pl) = req.into_parts();
-            let response = HttpResponse::Unauthorized()
-                .json(serde_json::json!({"error": "Unauthorized"}))
-                .map_into_right_body();
+            let response = unauthorized().map_into_right_body();
             
-            Ok(ServiceResponse::new(request, response).into())
+            Ok(ServiceResponse::new(request, response))
         })
     }
 }
 
+// Each admin route needs the permission `admin_permission` maps it to, not just the admin role
 pub struct AdminAuth;
 
+/// Guards a scope or route with one permission, e.g.
+/// `.wrap(RequirePermission(BOOKINGS_READ))`; the handlers behind it can then take an
+/// `AuthenticatedUser` knowing the check already happened.
+pub struct RequirePermission(pub &'static str);
+
 impl<S, B> Transform<S, ServiceRequest> for AdminAuth
 where
-    S: Service<ServiceRequest, Response = Response<EitherBody<B>>, Error = Error> + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = Response<EitherBody<B>>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
-    type Transform = AdminAuthMiddleware<S>;
+    type Transform = PermissionMiddleware<S>;
     type InitError = ();
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AdminAuthMiddleware { service: Rc::new(service) }))
+        ready(Ok(PermissionMiddleware { service: Rc::new(service), required: None }))
     }
 }
 
-pub struct AdminAuthMiddleware<S> {
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = PermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(PermissionMiddleware { service: Rc::new(service), required: Some(self.0) }))
+    }
+}
+
+pub struct PermissionMiddleware<S> {
     service: Rc<S>,
+    // None derives it from the admin path
+    required: Option<&'static str>,
 }
 
-impl<S, B> Service<ServiceRequest> for AdminAuthMiddleware<S>
+impl<S, B> Service<ServiceRequest> for PermissionMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = Response<EitherBody<B>>, Error = Error> + 'static,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     B: 'static,
 {
-    type Response = Response<EitherBody<B>>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -113,32 +228,27 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = Rc::clone(&self.service);
+        let required = self.required.unwrap_or_else(|| {
+            let path = req.path().strip_prefix("/api/admin").unwrap_or(req.path());
+            admin_permission(req.method().as_str(), path)
+        });
         
         Box::pin(async move {
             let auth_header = req.headers().get(http::header::AUTHORIZATION);
-            
-            if let Some(auth_header) = auth_header {
-                if let Ok(auth_str) = auth_header.to_str() {
-                    if auth_str.starts_with("Bearer ") {
-                        let token = &auth_str[7..];
-                        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                        
-                        let validation = Validation::new(Algorithm::HS256);
-                        if let Ok(token_data) = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &validation) {
-                            if token_data.claims.role == "admin" {
-                                return service.call(req).await;
-                            }
-                        }
-                    }
+            // No caller to speak of is a 401; a known caller without the permission is a 403
+            let response = match auth_header.and_then(|h| claims_from_header(h.as_bytes())) {
+                Some(claims) if authorize(&claims, required) => {
+                    req.extensions_mut().insert(claims);
+                    return service.call(req).await.map(ServiceResponse::map_into_left_body);
                 }
-            }
-            
+                Some(_) => forbidden(required),
+                None => AppError::unauthorized("Unauthorized").error_response(),
+            };
+
             let (request, _pl) = req.into_parts();
-            let response = HttpResponse::Forbidden()
-                .json(serde_json::json!({"error": "Admin access required"}))
-                .map_into_right_body();
+            let response = response.map_into_right_body();
             
-            Ok(ServiceResponse::new(request, response).into())
+            Ok(ServiceResponse::new(request, response))
         })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,153 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Payload, Service, Transform, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    web, Error, HttpMessage, ResponseError,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use futures::Stream;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use crate::config::DeviceSigningConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::models::device::{
+    DeviceSignature, SignedDevice, DEVICE_ID_HEADER, DEVICE_NONCE_HEADER, DEVICE_SIGNATURE_HEADER, DEVICE_TIMESTAMP_HEADER,
+};
+
+// The signing headers as sent: None when the request carries none of them
+fn signature_headers(req: &ServiceRequest) -> Option<Result<DeviceSignature, String>> {
+    let header = |name: &str| req.headers().get(name).and_then(|h| h.to_str().ok()).map(str::trim).filter(|h| !h.is_empty());
+    let names = [DEVICE_ID_HEADER, DEVICE_TIMESTAMP_HEADER, DEVICE_NONCE_HEADER, DEVICE_SIGNATURE_HEADER];
+    if names.iter().all(|name| header(name).is_none()) {
+        return None;
+    }
+    let missing: Vec<&str> = names.iter().copied().filter(|name| header(name).is_none()).collect();
+    if !missing.is_empty() {
+        return Some(Err(format!("Signed requests also need {}", missing.join(", "))));
+    }
+    let Some(timestamp) = header(DEVICE_TIMESTAMP_HEADER).and_then(|t| t.parse::<i64>().ok()) else {
+        return Some(Err(format!("{} must be a Unix time in seconds", DEVICE_TIMESTAMP_HEADER)));
+    };
+    Some(Ok(DeviceSignature {
+        device_id: header(DEVICE_ID_HEADER).unwrap_or_default().to_string(),
+        timestamp,
+        nonce: header(DEVICE_NONCE_HEADER).unwrap_or_default().to_string(),
+        signature: header(DEVICE_SIGNATURE_HEADER).unwrap_or_default().to_string(),
+    }))
+}
+
+/// Checks a signed request against the device's key and burns its nonce. The body is read
+/// here and put back for the handler.
+async fn verify_signed_request(req: &mut ServiceRequest, signature: &DeviceSignature, config: &DeviceSigningConfig) -> Result<Result<SignedDevice, String>, Error> {
+    let now = chrono::Utc::now().timestamp();
+    if let Err(e) = signature.validate(now, config.max_skew_secs) {
+        return Ok(Err(e));
+    }
+    let Some(db) = req.app_data::<web::Data<MongoDB>>().cloned() else {
+        return Ok(Err("Device signatures can't be checked right now".to_string()));
+    };
+
+    let body = req.extract::<web::Bytes>().await?;
+    let stream: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+        Box::pin(futures::stream::once(ready(Ok(body.clone()))));
+    req.set_payload(Payload::from(stream));
+
+    let (device, key) = match db.active_device(&signature.device_id).await {
+        Ok(Some(active)) => active,
+        // Unknown and revoked devices get the same answer, so a thief learns nothing
+        Ok(None) => return Ok(Err("This device is not allowed to sign requests".to_string())),
+        Err(e) => {
+            log::error!("Failed to load device {}: {}", signature.device_id, e);
+            return Ok(Err("Device signatures can't be checked right now".to_string()));
+        }
+    };
+    let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or(req.path()).to_string();
+    if !signature.verify(&key, req.method().as_str(), &path, &body) {
+        return Ok(Err("Device signature does not match".to_string()));
+    }
+
+    // Checked last, so a forged request can't use up a genuine device's nonces
+    let expires_at = mongodb::bson::DateTime::from_millis((now + 2 * config.max_skew_secs) * 1000);
+    match db.claim_device_nonce(&signature.device_id, &signature.nonce, expires_at).await {
+        Ok(true) => {}
+        Ok(false) => return Ok(Err("This request was already received".to_string())),
+        Err(e) => {
+            log::error!("Failed to record device nonce: {}", e);
+            return Ok(Err("Device signatures can't be checked right now".to_string()));
+        }
+    }
+    if let Err(e) = db.touch_device(&signature.device_id).await {
+        log::warn!("Failed to record device {} as seen: {}", signature.device_id, e);
+    }
+    Ok(Ok(SignedDevice { device_id: signature.device_id.clone(), operator: device.operator }))
+}
+
+// Guards the crew handset endpoints: a request signed with a provisioned, unrevoked device key
+// goes through with the device recorded on it; a bad signature never does, and an unsigned
+// request only if DEVICE_SIGNING_REQUIRED has been switched off
+pub struct DeviceSignatureGuard;
+
+impl<S, B> Transform<S, ServiceRequest> for DeviceSignatureGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = DeviceSignatureGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(DeviceSignatureGuardMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct DeviceSignatureGuardMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for DeviceSignatureGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let config = DeviceSigningConfig::from_env();
+            let verdict = match signature_headers(&req) {
+                None if config.required => Err("This endpoint only accepts requests signed by a provisioned device".to_string()),
+                None => Ok(None),
+                Some(Err(e)) => Err(e),
+                Some(Ok(signature)) => verify_signed_request(&mut req, &signature, &config).await?.map(Some),
+            };
+
+            match verdict {
+                Ok(device) => {
+                    if let Some(device) = device {
+                        req.extensions_mut().insert(device);
+                    }
+                    service.call(req).await.map(ServiceResponse::map_into_left_body)
+                }
+                Err(e) => {
+                    let (request, _pl) = req.into_parts();
+                    let response = AppError::unauthorized(e).error_response().map_into_right_body();
+                    Ok(ServiceResponse::new(request, response))
+                }
+            }
+        })
+    }
+}
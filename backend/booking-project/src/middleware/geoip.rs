@@ -0,0 +1,95 @@
+use actix_web::{
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    Error, HttpMessage, HttpRequest,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use maxminddb::{geoip2, Reader};
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use crate::config::GeoIpConfig;
+
+/// ISO 3166-1 alpha-2 country the request came from, when it could be resolved.
+#[derive(Clone)]
+pub struct RequestCountry(pub String);
+
+pub fn request_country(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<RequestCountry>().map(|c| c.0.clone())
+}
+
+// Annotates requests with the client's country; without a database every request passes through unannotated
+#[derive(Clone)]
+pub struct GeoIp {
+    reader: Option<Arc<Reader<Vec<u8>>>>,
+    trust_forwarded: bool,
+}
+
+impl GeoIp {
+    pub fn from_config(config: &GeoIpConfig) -> Self {
+        let reader = config.database_path.as_ref().and_then(|path| match Reader::open_readfile(path) {
+            Ok(reader) => Some(Arc::new(reader)),
+            Err(e) => {
+                log::warn!("GeoIP database {} could not be opened: {}", path, e);
+                None
+            }
+        });
+        Self { reader, trust_forwarded: config.trust_forwarded }
+    }
+
+    fn country_of(&self, req: &ServiceRequest) -> Option<String> {
+        let reader = self.reader.as_ref()?;
+        let ip = if self.trust_forwarded {
+            let info = req.connection_info();
+            let addr = info.realip_remote_addr()?;
+            addr.parse::<IpAddr>().ok().or_else(|| addr.parse::<SocketAddr>().ok().map(|a| a.ip()))?
+        } else {
+            req.peer_addr()?.ip()
+        };
+        let country: geoip2::Country = reader.lookup(ip).ok()?;
+        country.country?.iso_code.map(str::to_string)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for GeoIp
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = GeoIpMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(GeoIpMiddleware { service: Rc::new(service), geoip: self.clone() }))
+    }
+}
+
+pub struct GeoIpMiddleware<S> {
+    service: Rc<S>,
+    geoip: GeoIp,
+}
+
+impl<S, B> Service<ServiceRequest> for GeoIpMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(country) = self.geoip.country_of(&req) {
+            req.extensions_mut().insert(RequestCountry(country));
+        }
+        Box::pin(self.service.call(req))
+    }
+}
@@ -0,0 +1,155 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    http, Error, ResponseError,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use crate::config::LoadSheddingConfig;
+use crate::error::AppError;
+use crate::metrics::metrics;
+
+/// Endpoints that can be turned away under load without losing a sale: browsing, reporting and
+/// marketing reads. Bookings, holds, payments and auth are never shed.
+pub fn is_sheddable(method: &str, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["api", "buses"] => method == "GET",
+        ["api", "admin", "analytics", ..] | ["api", "admin", "regulatory", ..] => true,
+        ["api", "stats", ..] | ["api", "terminals", ..] | ["api", "experiments", ..] => true,
+        _ => false,
+    }
+}
+
+// Shared by every worker, so the limits apply to the whole process
+struct LoadState {
+    in_flight: AtomicUsize,
+    // Exponentially weighted moving average of request latency
+    latency_ewma_micros: AtomicU64,
+    // A stale average says nothing about now: if only shed requests arrive it would never recover
+    last_sample_secs: AtomicU64,
+    shedding: AtomicBool,
+}
+
+// Overload protection for flash sales: once too many requests are in flight or latency climbs
+// past the threshold, sheddable requests get a 503 with Retry-After so the rest stay fast
+#[derive(Clone)]
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    state: Arc<LoadState>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        let state = LoadState {
+            in_flight: AtomicUsize::new(0),
+            latency_ewma_micros: AtomicU64::new(0),
+            last_sample_secs: AtomicU64::new(0),
+            shedding: AtomicBool::new(false),
+        };
+        Self { config, state: Arc::new(state) }
+    }
+
+    fn overloaded(&self) -> bool {
+        let in_flight = self.state.in_flight.load(Ordering::Relaxed);
+        let stale = now_secs().saturating_sub(self.state.last_sample_secs.load(Ordering::Relaxed)) > self.config.retry_after_secs;
+        let latency_ms = if stale { 0 } else { self.state.latency_ewma_micros.load(Ordering::Relaxed) / 1000 };
+        let was_shedding = self.state.shedding.load(Ordering::Relaxed);
+        // Recover only once latency is well under the threshold, so shedding doesn't flap
+        let latency_limit = if was_shedding { self.config.latency_ms * 8 / 10 } else { self.config.latency_ms };
+        let overloaded = in_flight >= self.config.max_in_flight || latency_ms >= latency_limit;
+        if overloaded != was_shedding {
+            self.state.shedding.store(overloaded, Ordering::Relaxed);
+            if overloaded {
+                log::warn!("Load shedding on: {} requests in flight, {}ms average latency", in_flight, latency_ms);
+            } else {
+                log::info!("Load shedding off: {} requests in flight, {}ms average latency", in_flight, latency_ms);
+            }
+        }
+        overloaded
+    }
+
+    fn observe(&self, started: Instant) {
+        let sample = started.elapsed().as_micros() as u64;
+        self.state.last_sample_secs.store(now_secs(), Ordering::Relaxed);
+        let _ = self.state.latency_ewma_micros.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+            Some(if average == 0 { sample } else { (average * 9 + sample) / 10 })
+        });
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Keeps the in-flight count right even if the request future is dropped midway
+struct InFlightGuard(Arc<LoadState>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShedder
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = LoadShedderMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LoadShedderMiddleware { service: Rc::new(service), shedder: self.clone() }))
+    }
+}
+
+pub struct LoadShedderMiddleware<S> {
+    service: Rc<S>,
+    shedder: LoadShedder,
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedderMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let shedder = self.shedder.clone();
+
+        Box::pin(async move {
+            if shedder.config.enabled && is_sheddable(req.method().as_str(), req.path()) && shedder.overloaded() {
+                metrics().requests_shed.with_label_values(&[req.path().split('/').nth(2).unwrap_or("")]).inc();
+                let (request, _pl) = req.into_parts();
+                let mut response = AppError::unavailable("We're very busy right now, please try again shortly").error_response();
+                response.headers_mut().insert(http::header::RETRY_AFTER, http::header::HeaderValue::from(shedder.config.retry_after_secs));
+                let response = response.map_into_right_body();
+                return Ok(ServiceResponse::new(request, response));
+            }
+
+            shedder.state.in_flight.fetch_add(1, Ordering::Relaxed);
+            let _guard = InFlightGuard(Arc::clone(&shedder.state));
+            let started = Instant::now();
+            let res = service.call(req).await;
+            shedder.observe(started);
+            res.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
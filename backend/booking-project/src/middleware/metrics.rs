@@ -0,0 +1,67 @@
+use actix_web::{
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    Error,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use std::task::{Context, Poll};
+use std::rc::Rc;
+use std::time::Instant;
+use crate::metrics::metrics;
+
+// Times every request against its route pattern (`/api/buses/{id}/seats`, not the concrete
+// path) so label cardinality stays bounded, and feeds the SLO tracker
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let started = Instant::now();
+
+        Box::pin(async move {
+            let res = service.call(req).await?;
+            let elapsed = started.elapsed();
+
+            let method = res.request().method().as_str().to_string();
+            let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string());
+            let metrics = metrics();
+            metrics.request_duration
+                .with_label_values(&[&method, &route, res.status().as_str()])
+                .observe(elapsed.as_secs_f64());
+            metrics.slo.record(&method, &route, elapsed);
+            Ok(res)
+        })
+    }
+}
@@ -1 +1,9 @@
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod device_signature;
+pub mod geoip;
+pub mod load_shedding;
+pub mod metrics;
+pub mod rate_limit;
+pub mod session;
+pub mod tenancy;
+pub mod waiting_room;
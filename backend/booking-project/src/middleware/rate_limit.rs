@@ -0,0 +1,181 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    web, Error, HttpRequest, HttpResponse, ResponseError,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use crate::config::RateLimitConfig;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::OptionalClaims;
+use crate::metrics::metrics;
+use crate::models::rate_limit::window_start;
+
+// Overrides and blocks change rarely, so each process re-reads them at most this often;
+// admin changes take effect within this long
+const POLICY_CACHE_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Default)]
+struct Policy {
+    overrides: HashMap<String, i64>,
+    // Key to the Unix second the block lifts
+    blocked: HashMap<String, i64>,
+}
+
+type PolicyCache = Option<(Instant, Policy)>;
+
+// Fixed-window request limits per caller, counted in MongoDB so every instance shares them
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    policy: Arc<RwLock<PolicyCache>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, policy: Arc::new(RwLock::new(None)) }
+    }
+
+    async fn policy(&self, db: &MongoDB) -> Policy {
+        if let Ok(cache) = self.policy.read() {
+            if let Some((loaded_at, policy)) = cache.as_ref() {
+                if loaded_at.elapsed() < POLICY_CACHE_TTL {
+                    return policy.clone();
+                }
+            }
+        }
+        let loaded = match (db.get_rate_limit_overrides().await, db.get_abuse_blocks().await) {
+            (Ok(overrides), Ok(blocks)) => Policy {
+                overrides: overrides.into_iter().map(|o| (o.key, o.limit)).collect(),
+                blocked: blocks.into_iter().map(|b| (b.key, b.blocked_until.timestamp_millis() / 1000)).collect(),
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                log::error!("Failed to load rate limit overrides: {}", e);
+                Policy::default()
+            }
+        };
+        if let Ok(mut cache) = self.policy.write() {
+            *cache = Some((Instant::now(), loaded.clone()));
+        }
+        loaded
+    }
+
+    fn caller_key(&self, req: &ServiceRequest) -> Option<String> {
+        if let Some(user_id) = OptionalClaims::of(req.request()).user_id() {
+            return Some(format!("user:{}", user_id));
+        }
+        client_ip(req.request(), self.config.trust_forwarded).map(|ip| format!("ip:{}", ip))
+    }
+}
+
+/// The caller's address; X-Forwarded-For is only believed behind a proxy that sets it.
+pub fn client_ip(req: &HttpRequest, trust_forwarded: bool) -> Option<IpAddr> {
+    if trust_forwarded {
+        let info = req.connection_info();
+        let addr = info.realip_remote_addr()?;
+        addr.parse::<IpAddr>().ok().or_else(|| addr.parse::<SocketAddr>().ok().map(|a| a.ip()))
+    } else {
+        Some(req.peer_addr()?.ip())
+    }
+}
+
+fn too_many_requests(retry_after: i64, message: &str) -> HttpResponse {
+    AppError::too_many_requests(message, Some(retry_after)).error_response()
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware { service: Rc::new(service), limiter: self.clone() }))
+    }
+}
+
+pub struct RateLimiterMiddleware<S> {
+    service: Rc<S>,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let config = &limiter.config;
+            let db = req.app_data::<web::Data<MongoDB>>().cloned();
+            // Health checks come from load balancers polling on a schedule, and a throttled
+            // payment callback is a payment we never hear about
+            let enforced = config.enabled && req.path() != "/api/health" && !req.path().starts_with("/api/webhooks/");
+            let (Some(db), Some(key), true) = (db, limiter.caller_key(&req), enforced) else {
+                return service.call(req).await.map(ServiceResponse::map_into_left_body);
+            };
+
+            let policy = limiter.policy(&db).await;
+            let now = chrono::Utc::now().timestamp();
+            if let Some(until) = policy.blocked.get(&key).filter(|until| **until > now) {
+                metrics().requests_rate_limited.with_label_values(&["blocked"]).inc();
+                let (request, _pl) = req.into_parts();
+                let response = too_many_requests(until - now, "Too many requests, please try again later");
+                return Ok(ServiceResponse::new(request, response.map_into_right_body()));
+            }
+
+            let limit = policy.overrides.get(&key).copied().unwrap_or_else(|| config.default_limit(&key));
+            let window = window_start(now, config.window_secs);
+            let count = match db.hit_rate_limit(&key, window, config.window_secs).await {
+                Ok(count) => count,
+                // Failing open: throttling must not take the API down with the database
+                Err(e) => {
+                    log::error!("Failed to count request for {}: {}", key, e);
+                    return service.call(req).await.map(ServiceResponse::map_into_left_body);
+                }
+            };
+
+            if count > limit {
+                metrics().requests_rate_limited.with_label_values(&["limited"]).inc();
+                if count == limit * config.abuse_factor {
+                    if let Err(e) = db.block_abusive_caller(&key, count, config).await {
+                        log::error!("Failed to block {}: {}", key, e);
+                    }
+                }
+                let (request, _pl) = req.into_parts();
+                let response = too_many_requests(window + config.window_secs - now, "Too many requests, please slow down");
+                return Ok(ServiceResponse::new(request, response.map_into_right_body()));
+            }
+
+            let mut res = service.call(req).await?;
+            let headers = res.headers_mut();
+            headers.insert(HeaderName::from_static("x-ratelimit-limit"), HeaderValue::from(limit));
+            headers.insert(HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from(limit - count));
+            Ok(res.map_into_left_body())
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    web, Error, ResponseError, http
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use std::task::{Context, Poll};
+use std::rc::Rc;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::middleware::auth::claims_from_header;
+
+// Rejects bearer tokens issued before the user's sessions were revoked; requests without one pass through
+pub struct SessionGuard;
+
+/// The refusal for a token whose revocation check came back `revoked`. A check that could not
+/// be made refuses too: a revoked session must not slip through while the database is down.
+pub(crate) fn session_refusal(revoked: Result<bool, AppError>) -> Option<AppError> {
+    match revoked {
+        Ok(false) => None,
+        Ok(true) => Some(AppError::unauthorized("Your session has ended, please sign in again")),
+        Err(e) => {
+            log::error!("Failed to check session revocation: {}", e);
+            Some(AppError::unavailable("Could not verify your session, please try again"))
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SessionGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = SessionGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SessionGuardMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct SessionGuardMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for SessionGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let claims = req.headers()
+                .get(http::header::AUTHORIZATION)
+                .and_then(|h| claims_from_header(h.as_bytes()));
+
+            if let (Some(claims), Some(db)) = (claims, req.app_data::<web::Data<MongoDB>>()) {
+                if let Some(refusal) = session_refusal(db.is_session_revoked(&claims.sub, claims.iat).await) {
+                    let (request, _pl) = req.into_parts();
+                    let response = refusal.error_response().map_into_right_body();
+                    return Ok(ServiceResponse::new(request, response));
+                }
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, Transform, ServiceRequest, ServiceResponse},
+    Error, HttpMessage, ResponseError, http
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use std::task::{Context, Poll};
+use std::rc::Rc;
+use crate::error::AppError;
+use crate::middleware::auth::claims_from_header;
+use crate::models::tenancy::{is_tenant_aware, Tenant};
+
+// Resolves the caller's tenant for admin handlers (`web::ReqData<Tenant>`); operator accounts
+// only reach handlers that scope their queries, everything else is refused here
+pub struct TenancyGuard;
+
+impl<S, B> Transform<S, ServiceRequest> for TenancyGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = TenancyGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TenancyGuardMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct TenancyGuardMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for TenancyGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let claims = req.headers()
+                .get(http::header::AUTHORIZATION)
+                .and_then(|h| claims_from_header(h.as_bytes()));
+
+            let path = req.path().strip_prefix("/api/admin").unwrap_or(req.path());
+            let refusal = match claims.as_ref().map(Tenant::from_claims) {
+                None => Some("Admin access required"),
+                Some(Err(message)) => Some(message),
+                Some(Ok(Tenant::Operator(_))) if !is_tenant_aware(req.method().as_str(), path) => {
+                    Some("Not available to operator accounts")
+                }
+                Some(Ok(tenant)) => {
+                    req.extensions_mut().insert(tenant);
+                    None
+                }
+            };
+
+            if let Some(message) = refusal {
+                let (request, _pl) = req.into_parts();
+                let response = AppError::forbidden(message).error_response().map_into_right_body();
+                return Ok(ServiceResponse::new(request, response));
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
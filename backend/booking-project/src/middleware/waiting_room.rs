@@ -0,0 +1,165 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{Payload, Service, Transform, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    web, Error, ResponseError,
+};
+use futures::future::{Ready, LocalBoxFuture, ready};
+use futures::Stream;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use crate::db::waiting_rooms::verify_waiting_room_token;
+use crate::db::MongoDB;
+use crate::error::AppError;
+use crate::handlers::waiting_rooms::waiting_room_holder;
+use crate::models::waiting_room::{ADMISSION_SCOPE, WAITING_ROOM_HEADER};
+
+// Rooms are few and change rarely, so each process re-reads them at most this often
+const ROOM_CACHE_TTL: Duration = Duration::from_secs(10);
+
+#[derive(serde::Deserialize)]
+struct TripBody {
+    bus_id: String,
+    travel_date: String,
+}
+
+type RoomCache = Option<(Instant, Vec<EnforcedRoom>)>;
+
+#[derive(Clone)]
+struct EnforcedRoom {
+    room_id: String,
+    bus_id: String,
+    travel_date: String,
+}
+
+// Admits seat selection for trips behind a waiting room only with a matching admission token;
+// everything else, and every trip without a room, passes straight through
+#[derive(Clone, Default)]
+pub struct WaitingRoomGuard {
+    rooms: Arc<RwLock<RoomCache>>,
+}
+
+impl WaitingRoomGuard {
+    async fn enforced_rooms(&self, db: &MongoDB) -> Vec<EnforcedRoom> {
+        if let Ok(cache) = self.rooms.read() {
+            if let Some((loaded_at, rooms)) = cache.as_ref() {
+                if loaded_at.elapsed() < ROOM_CACHE_TTL {
+                    return rooms.clone();
+                }
+            }
+        }
+        let rooms: Vec<EnforcedRoom> = match db.get_enforced_waiting_rooms().await {
+            Ok(rooms) => rooms.into_iter().map(|r| EnforcedRoom {
+                room_id: r.id.map(|id| id.to_hex()).unwrap_or_default(),
+                bus_id: r.bus_id.to_hex(),
+                travel_date: r.travel_date,
+            }).collect(),
+            Err(e) => {
+                // Failing open: the room protects the database, it must not take sales down with it
+                log::error!("Failed to load waiting rooms: {}", e);
+                return Vec::new();
+            }
+        };
+        if let Ok(mut cache) = self.rooms.write() {
+            *cache = Some((Instant::now(), rooms.clone()));
+        }
+        rooms
+    }
+}
+
+/// The trip a seat-selection request is for: seat maps carry it in the path and query,
+/// holds and bookings in the JSON body, which is read here and put back for the handler.
+async fn requested_trip(req: &mut ServiceRequest) -> Result<Option<(String, String)>, Error> {
+    let segments: Vec<String> = req.path().trim_matches('/').split('/').map(str::to_string).collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    match (req.method().as_str(), segments.as_slice()) {
+        ("GET", ["api", "buses", bus_id, "seats"]) => {
+            let date = web::Query::<std::collections::HashMap<String, String>>::from_query(req.query_string())
+                .ok()
+                .and_then(|q| q.get("date").cloned());
+            Ok(date.map(|date| (bus_id.to_string(), date)))
+        }
+        ("POST", ["api", "bookings"]) | ("POST", ["api", "bookings", "holds" | "hold"]) => {
+            let body = req.extract::<web::Bytes>().await?;
+            let trip = serde_json::from_slice::<TripBody>(&body).ok().map(|t| (t.bus_id, t.travel_date));
+            let stream: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+                Box::pin(futures::stream::once(ready(Ok(body))));
+            req.set_payload(Payload::from(stream));
+            Ok(trip)
+        }
+        _ => Ok(None),
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for WaitingRoomGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = WaitingRoomGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(WaitingRoomGuardMiddleware { service: Rc::new(service), guard: self.clone() }))
+    }
+}
+
+pub struct WaitingRoomGuardMiddleware<S> {
+    service: Rc<S>,
+    guard: WaitingRoomGuard,
+}
+
+impl<S, B> Service<ServiceRequest> for WaitingRoomGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let guard = self.guard.clone();
+
+        Box::pin(async move {
+            let trip = requested_trip(&mut req).await?;
+            let db = req.app_data::<web::Data<MongoDB>>().cloned();
+            let room = match (trip, db) {
+                (Some((bus_id, travel_date)), Some(db)) => guard.enforced_rooms(&db).await
+                    .into_iter()
+                    .find(|r| r.bus_id == bus_id && r.travel_date == travel_date),
+                _ => None,
+            };
+
+            if let Some(room) = room {
+                let admitted = req.headers()
+                    .get(WAITING_ROOM_HEADER)
+                    .and_then(|h| h.to_str().ok())
+                    .and_then(|token| verify_waiting_room_token(token, ADMISSION_SCOPE).ok())
+                    .is_some_and(|claims| {
+                        claims.room_id == room.room_id && Some(claims.holder) == waiting_room_holder(req.request())
+                    });
+                if !admitted {
+                    let (request, _pl) = req.into_parts();
+                    let queue = format!("/api/waiting-room/{}/{}", room.bus_id, room.travel_date);
+                    let response = AppError::WaitingRoom { queue }.error_response().map_into_right_body();
+                    return Ok(ServiceResponse::new(request, response));
+                }
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
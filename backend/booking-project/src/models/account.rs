@@ -0,0 +1,59 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// A pending or completed change of login email; nothing changes on the user until confirmation
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EmailChange {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub old_email: String,
+    pub new_email: String,
+    pub status: String, // pending, confirmed, cancelled, reverted
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub confirmed_at: Option<bson::DateTime>,
+}
+
+/// Whether a token issued at `issued_at` (JWT seconds) predates a session revocation. The
+/// revocation is compared in whole seconds too, so the token issued straight after it, in the
+/// same second, still counts as newer.
+pub fn issued_before_revocation(issued_at: usize, revoked_at: bson::DateTime) -> bool {
+    (issued_at as i64) < revoked_at.timestamp_millis().div_euclid(1000)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailChangeClaims {
+    pub change_id: String,
+    pub scope: String, // email:confirm or email:undo
+    pub exp: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LanguagePreferenceRequest {
+    pub language: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EmailChangeRequest {
+    pub new_email: String,
+    pub password: Option<String>, // required unless the account signs in with Google only
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EmailChangeResponse {
+    pub status: String,
+    pub new_email: String,
+    pub expires_at: String,
+}
+
+impl From<EmailChange> for EmailChangeResponse {
+    fn from(c: EmailChange) -> Self {
+        Self {
+            status: c.status,
+            new_email: c.new_email,
+            expires_at: c.expires_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+#[derive(Serialize, Clone)]
+pub struct HeatmapCell {
+    pub from: String,
+    pub to: String,
+    pub day_of_week: String,
+    pub time_slot: String,
+    pub bookings: i64,
+    pub searches: i64,
+    pub zero_result_searches: i64,
+}
+
+#[derive(Serialize)]
+pub struct DemandHeatmap {
+    pub days: i64,
+    pub cells: Vec<HeatmapCell>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct HeatmapQuery {
+    pub days: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RevenueQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct OperatorRevenue {
+    pub operator: String,
+    pub bookings: i64,
+    pub gross: f64,
+    pub commission: f64,
+    pub operator_share: f64,
+}
+
+// Travel dates are inclusive; either bound may be open
+#[derive(Serialize)]
+pub struct RevenueReport {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub operators: Vec<OperatorRevenue>,
+    pub totals: OperatorRevenue,
+}
+
+// Requests without a resolved country are grouped under "unknown"
+#[derive(Serialize, Default)]
+pub struct CountryActivity {
+    pub country: String,
+    pub searches: i64,
+    pub bookings: i64,
+    pub held_for_review: i64,
+}
+
+/// Day-of-week label for a `YYYY-MM-DD` travel date.
+pub fn day_of_week(travel_date: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(travel_date, "%Y-%m-%d").ok()?;
+    Some(date.weekday().to_string())
+}
+
+/// Coarse departure slot for a "08:15 AM" style time.
+pub fn time_slot(departure_time: &str) -> String {
+    let Ok(time) = NaiveTime::parse_from_str(departure_time, "%I:%M %p") else {
+        return "unknown".to_string();
+    };
+    match time.hour() {
+        5..=11 => "morning",
+        12..=16 => "afternoon",
+        17..=20 => "evening",
+        _ => "night",
+    }
+    .to_string()
+}
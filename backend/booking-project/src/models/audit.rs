@@ -0,0 +1,44 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// One privacy- or compliance-relevant action, kept after the data it describes is gone
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub action: String, // e.g. account.retention_notice, account.anonymized
+    pub actor: String, // user:<id> or system:<job>
+    pub subject: String, // what the action was applied to, e.g. user:<id>
+    pub details: Option<String>,
+    pub at: bson::DateTime,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub subject: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditEntryResponse {
+    pub id: Option<String>,
+    pub action: String,
+    pub actor: String,
+    pub subject: String,
+    pub details: Option<String>,
+    pub at: String,
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(e: AuditEntry) -> Self {
+        Self {
+            id: e.id.map(|id| id.to_hex()),
+            action: e.action,
+            actor: e.actor,
+            subject: e.subject,
+            details: e.details,
+            at: e.at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -1,26 +1,109 @@
 use super::user::UserResponse;
+use mongodb::bson::{self, oid::ObjectId};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    #[serde(default)]
+    pub accepted_terms_version: Option<String>,
+    // Opt-ins ticked on the sign-up form, e.g. {"marketing_email": true}
+    #[serde(default)]
+    pub consents: std::collections::HashMap<String, bool>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GoogleLoginRequest {
     pub token: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct AuthResponse {
     pub token: String,
+    // Trade for a new pair at /api/auth/refresh before `token` expires
+    pub refresh_token: String,
     pub user: UserResponse,
 }
+
+#[derive(Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+// One issued refresh token, by hash. Each use rotates it for a new one in the same family;
+// presenting a rotated token again means it was copied, and the whole family is revoked
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub token_hash: String,
+    pub user_id: ObjectId,
+    pub family: String,
+    pub created_at: bson::DateTime,
+    pub expires_at: bson::DateTime,
+    pub revoked_at: Option<bson::DateTime>,
+    pub revoked_reason: Option<String>, // rotated, logout, reuse, revoked
+}
+
+// Lets a visitor hold seats before signing in; the holds move to their account on login
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnonymousClaims {
+    pub sub: String,
+    pub scope: String,
+    pub exp: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AnonymousSessionResponse {
+    pub token: String,
+    pub anon_id: String,
+    pub expires_at: String,
+}
+
+// A forgot-password request; only the hash of the emailed token is kept, and it works once
+#[derive(Serialize, Deserialize)]
+pub struct PasswordReset {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub token_hash: String,
+    pub created_at: bson::DateTime,
+    pub expires_at: bson::DateTime,
+    pub used_at: Option<bson::DateTime>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+impl ResetPasswordRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token.trim().is_empty() {
+            return Err("Reset token is required".to_string());
+        }
+        if self.new_password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(format!("The new password must be at least {} characters", MIN_PASSWORD_LENGTH));
+        }
+        Ok(())
+    }
+}
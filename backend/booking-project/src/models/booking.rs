@@ -1,28 +1,447 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize, Deserialize, Clone)]
+use super::bus::{Bus, OPEN_SEATING};
+use super::fraud::{normalize_blocklist_value, RiskAssessment};
+use super::partner::PartnerOfferResponse;
+use super::trip::AnnouncementResponse;
+
+// PendingReview bookings hold their seat until fraud review approves or rejects them, and
+// PendingPayment ones until they are paid or the payment window runs out
+pub const BOOKING_STATUSES: [&str; 4] = ["Confirmed", "PendingPayment", "PendingReview", "Cancelled"];
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct Passenger {
     pub name: String,
     pub age: String,
     pub gender: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    // National ID or passport number, printed on the regulatory manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_number: Option<String>,
+    // Accepted on booking but never serialized: it is stored sealed on its own and only
+    // decrypted for the regulatory manifest
+    #[serde(default, skip_serializing)]
+    pub next_of_kin: Option<NextOfKin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct NextOfKin {
+    pub name: String,
+    pub phone: String,
 }
 
-#[derive(Serialize, Deserialize)]
+impl NextOfKin {
+    /// Trimmed, with the phone in 2547.. form; rejects anything the manifest couldn't use.
+    pub fn validated(&self) -> Result<Self, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err("Next of kin name must be between 1 and 100 characters".to_string());
+        }
+        let phone = normalize_blocklist_value("phone", &self.phone);
+        if phone.len() != 12 || !phone.starts_with("254") {
+            return Err("Next of kin phone must be a valid Kenyan number".to_string());
+        }
+        Ok(Self { name: name.to_string(), phone })
+    }
+}
+
+// Manifest line for a passenger's next of kin
+impl std::fmt::Display for NextOfKin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.phone)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Booking {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<crate::openapi::ObjectIdJson>)]
     pub id: Option<mongodb::bson::oid::ObjectId>,
+    #[schema(value_type = crate::openapi::ObjectIdJson)]
     pub user_id: mongodb::bson::oid::ObjectId,
+    #[schema(value_type = crate::openapi::ObjectIdJson)]
     pub bus_id: mongodb::bson::oid::ObjectId,
     pub seat_number: String,
     pub travel_date: String,
+    #[schema(value_type = crate::openapi::DateTimeJson)]
     pub booking_date: mongodb::bson::DateTime,
     pub status: String,
     pub passenger: Option<Passenger>,
+    #[serde(default)]
+    pub history: Vec<BookingEvent>,
+    // Terms and cancellation policy in force when the booking was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terms_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(ignore)]
+    pub risk: Option<RiskAssessment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    // Short code like BB-7K2F9Q that passengers read out at boarding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+// What a successful booking returns: the booking plus partner offers at the destination
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct BookingConfirmation {
+    #[serde(flatten)]
+    pub booking: Booking,
+    pub partner_offers: Vec<PartnerOfferResponse>,
+}
+
+impl BookingConfirmation {
+    /// Drops the fraud assessment: its reasons would tell the customer which checks they tripped,
+    /// so it's only ever shown in the admin review queue.
+    pub fn new(mut booking: Booking, partner_offers: Vec<PartnerOfferResponse>) -> Self {
+        booking.risk = None;
+        Self { booking, partner_offers }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct BookingEvent {
+    pub event: String, // created, paid, amended, reseated, delayed, checked_in, cancelled, ...
+    pub actor: String, // "user:<id>", "admin" or "system"
+    pub note: Option<String>,
+    #[schema(value_type = crate::openapi::DateTimeJson)]
+    pub at: mongodb::bson::DateTime,
+}
+
+impl BookingEvent {
+    pub fn new(event: &str, actor: &str, note: Option<String>) -> Self {
+        Self {
+            event: event.to_string(),
+            actor: actor.to_string(),
+            note,
+            at: mongodb::bson::DateTime::now(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct TimelineEntry {
+    pub event: String,
+    pub label: String,
+    pub actor: String,
+    pub note: Option<String>,
+    pub at: String,
 }
 
-#[derive(Serialize, Deserialize)]
+impl From<&BookingEvent> for TimelineEntry {
+    fn from(e: &BookingEvent) -> Self {
+        let label = match e.event.as_str() {
+            "created" => "Booking created",
+            "paid" => "Payment received",
+            "amended" => "Booking amended",
+            "reseated" => "Seat changed",
+            "needs_reseat" => "Awaiting a new seat",
+            "delayed" => "Trip delayed",
+            "checked_in" => "Checked in",
+            "cancelled" => "Booking cancelled",
+            "refund_requested" => "Refund requested",
+            "refunded" => "Refund paid",
+            "held_for_review" => "Held for review",
+            "review_approved" => "Booking confirmed after review",
+            other => other,
+        };
+        Self {
+            event: e.event.clone(),
+            label: label.to_string(),
+            actor: e.actor.clone(),
+            note: e.note.clone(),
+            at: e.at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+impl Booking {
+    /// Chronological timeline, synthesizing the creation entry for bookings made before history was tracked.
+    pub fn timeline(&self) -> Vec<TimelineEntry> {
+        let mut events = self.history.clone();
+        if !events.iter().any(|e| e.event == "created") {
+            events.push(BookingEvent {
+                event: "created".to_string(),
+                actor: format!("user:{}", self.user_id.to_hex()),
+                note: None,
+                at: self.booking_date,
+            });
+        }
+        events.sort_by_key(|e| e.at);
+        events.iter().map(TimelineEntry::from).collect()
+    }
+
+    /// When the payment window of a PendingPayment booking opened: on booking, or on approval
+    /// for bookings that were held for review first.
+    pub fn awaiting_payment_since(&self) -> mongodb::bson::DateTime {
+        self.history.iter()
+            .filter(|e| e.event == "review_approved")
+            .map(|e| e.at)
+            .max()
+            .unwrap_or(self.booking_date)
+    }
+
+    /// The reference the passenger sees on their ticket, texts and emails; bookings made before
+    /// references existed show their id instead.
+    pub fn display_reference(&self) -> String {
+        self.reference.clone()
+            .or_else(|| self.id.map(|id| id.to_hex().to_uppercase()))
+            .unwrap_or_default()
+    }
+
+    /// Where to text the confirmation: the passenger's phone, once the booking is confirmed.
+    /// Bookings held for review or payment get theirs when that clears.
+    pub fn confirmation_sms_phone(&self) -> Option<&str> {
+        if self.status != "Confirmed" {
+            return None;
+        }
+        self.passenger.as_ref().and_then(|p| p.phone.as_deref()).map(str::trim).filter(|p| !p.is_empty())
+    }
+}
+
+// Wire format the frontend's booking screens are built against; keep field names stable
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub struct BookingDetailResponse {
+    pub id: Option<String>,
+    pub bus_id: String,
+    pub bus_name: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub departure: String,
+    pub arrival: String,
+    pub total_price: f64,
+    pub seats: Vec<String>,
+    pub status: String,
+    pub date: String,
+    pub booking_date: String,
+    pub booking_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    pub passengers: Vec<PassengerDetail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<Vec<TimelineEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announcements: Option<Vec<AnnouncementResponse>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub struct PassengerDetail {
+    pub name: String,
+    pub seat_number: String,
+    pub age: String,
+    pub gender: String,
+}
+
+impl From<(Booking, Option<Bus>)> for BookingDetailResponse {
+    fn from((b, bus): (Booking, Option<Bus>)) -> Self {
+        let unknown = |value: Option<&String>| value.cloned().unwrap_or_else(|| "Unknown".to_string());
+        let passenger = match b.passenger {
+            Some(p) => PassengerDetail { name: p.name, seat_number: b.seat_number.clone(), age: p.age, gender: p.gender },
+            None => PassengerDetail {
+                name: "User".to_string(),
+                seat_number: b.seat_number.clone(),
+                age: "N/A".to_string(),
+                gender: "N/A".to_string(),
+            },
+        };
+
+        Self {
+            id: b.id.map(|id| id.to_hex()),
+            bus_id: b.bus_id.to_hex(),
+            bus_name: bus.as_ref().map(|b| b.bus_number.clone()).unwrap_or_else(|| "Unknown Bus".to_string()),
+            bus_type: unknown(bus.as_ref().map(|b| &b.bus_type)),
+            from: unknown(bus.as_ref().map(|b| &b.route.from)),
+            to: unknown(bus.as_ref().map(|b| &b.route.to)),
+            departure: unknown(bus.as_ref().map(|b| &b.route.departure_time)),
+            arrival: unknown(bus.as_ref().map(|b| &b.route.arrival_time)),
+            total_price: bus.as_ref().map(|b| b.route.price).unwrap_or(0.0),
+            seats: vec![b.seat_number],
+            status: b.status.to_lowercase(),
+            date: b.travel_date,
+            booking_date: b.booking_date.to_string(),
+            booking_id: b.id.map(|id| id.to_hex().to_uppercase()).unwrap_or_else(|| "N/A".to_string()),
+            reference: b.reference,
+            passengers: vec![passenger],
+            timeline: None,
+            announcements: None,
+        }
+    }
+}
+
+// Moves a booking to another seat, optionally on another bus or date
+#[derive(Deserialize, ToSchema)]
+pub struct ReseatRequest {
+    pub seat_number: String,
+    pub bus_id: Option<String>,
+    pub travel_date: Option<String>,
+    pub reason: Option<String>,
+}
+
+// A passenger moving their own booking to another date, and optionally another seat; the
+// booking's amendment terms decide whether it's allowed and what it costs
+#[derive(Deserialize, ToSchema)]
+pub struct ChangeBookingRequest {
+    pub travel_date: String,
+    pub seat_number: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CreateBookingRequest {
     pub bus_id: String,
     pub seat_number: String,
     pub travel_date: String,
     pub passenger: Option<Passenger>,
+    #[serde(default)]
+    pub accepted_terms_version: Option<String>,
+    // Phone the payment will come from, compared with the passenger's
+    #[serde(default)]
+    pub payer_phone: Option<String>,
+    // A commuter pass to take the ride off instead of paying the fare
+    #[serde(default)]
+    pub pass_id: Option<String>,
+}
+
+pub const MAX_SEATS_PER_BOOKING: usize = 6;
+
+// Several seats on one trip, e.g. for a family; all of them are booked or none are
+#[derive(Deserialize, ToSchema)]
+pub struct CreateGroupBookingRequest {
+    pub bus_id: String,
+    pub seat_numbers: Vec<String>,
+    pub travel_date: String,
+    // One per seat, in the same order as `seat_numbers`
+    #[serde(default)]
+    pub passengers: Vec<Passenger>,
+    #[serde(default)]
+    pub accepted_terms_version: Option<String>,
+    #[serde(default)]
+    pub payer_phone: Option<String>,
+    // Places to book on a trip sold by headcount, instead of `seat_numbers`
+    #[serde(default)]
+    pub headcount: Option<usize>,
+    // A commuter pass to take one ride per seat off
+    #[serde(default)]
+    pub pass_id: Option<String>,
+}
+
+impl CreateGroupBookingRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.headcount.is_some() && !self.seat_numbers.is_empty() {
+            return Err("Give either seat_numbers or headcount, not both".to_string());
+        }
+        let places = self.places();
+        if places == 0 || places > MAX_SEATS_PER_BOOKING {
+            return Err(format!("Between 1 and {} seats can be booked at once", MAX_SEATS_PER_BOOKING));
+        }
+        let mut seen = std::collections::HashSet::new();
+        if let Some(seat) = self.seat_numbers.iter().find(|s| !seen.insert(s.as_str())) {
+            return Err(format!("Seat {} is listed more than once", seat));
+        }
+        if !self.passengers.is_empty() && self.passengers.len() != places {
+            return Err("Give one passenger per seat, or none".to_string());
+        }
+        Ok(())
+    }
+
+    /// How many people the booking is for.
+    pub fn places(&self) -> usize {
+        self.headcount.unwrap_or(self.seat_numbers.len())
+    }
+
+    /// Each seat with its passenger, if any.
+    pub fn seats(&self) -> Vec<(String, Option<Passenger>)> {
+        self.seat_numbers
+            .iter()
+            .enumerate()
+            .map(|(i, seat)| (seat.clone(), self.passengers.get(i).cloned()))
+            .collect()
+    }
+
+    /// One open place per person with their passenger, if any, for a trip sold by headcount.
+    /// Seat numbers picked anyway each count as one place.
+    pub fn open_places(&self) -> Vec<(String, Option<Passenger>)> {
+        (0..self.places())
+            .map(|i| (OPEN_SEATING.to_string(), self.passengers.get(i).cloned()))
+            .collect()
+    }
+}
+
+impl From<&CreateBookingRequest> for CreateGroupBookingRequest {
+    fn from(req: &CreateBookingRequest) -> Self {
+        Self {
+            bus_id: req.bus_id.clone(),
+            seat_numbers: vec![req.seat_number.clone()],
+            travel_date: req.travel_date.clone(),
+            passengers: req.passenger.clone().into_iter().collect(),
+            accepted_terms_version: req.accepted_terms_version.clone(),
+            payer_phone: req.payer_phone.clone(),
+            headcount: None,
+            pass_id: req.pass_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareClaims {
+    pub booking_id: String,
+    pub scope: String,
+    pub exp: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ShareLinkResponse {
+    pub token: String,
+    pub url: String,
+    pub expires_at: String,
+}
+
+// Read-only itinerary view for travel companions; carries no payer details
+#[derive(Serialize, ToSchema)]
+pub struct SharedTripResponse {
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub boarding_point: String,
+    pub travel_date: String,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub seat_number: String,
+    pub status: String,
+}
+
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+// Keyset pagination: `after` is the id of the last booking on the previous page
+#[derive(Deserialize, IntoParams)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct BookingPageQuery {
+    pub after: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct BookingSearchQuery {
+    pub status: Option<String>,
+    pub bus_id: Option<String>,
+    pub user_id: Option<String>,
+    pub travel_date: Option<String>,
+    pub after: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub struct BookingPage {
+    pub bookings: Vec<Booking>,
+    pub next_cursor: Option<String>,
 }
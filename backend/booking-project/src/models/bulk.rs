@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+/// Outcome of a batched insert; failures are reported per input document instead of aborting the run.
+#[derive(Serialize, Default)]
+pub struct BulkInsertReport {
+    pub inserted: usize,
+    pub failed: Vec<BulkInsertError>,
+}
+
+#[derive(Serialize)]
+pub struct BulkInsertError {
+    pub index: usize, // position in the submitted list, not the batch
+    pub message: String,
+}
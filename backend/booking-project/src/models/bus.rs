@@ -1,4 +1,12 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize, Serializer};
+use utoipa::{IntoParams, ToSchema};
+
+use super::localization::{DisplayNames, Localizer};
+use super::punctuality::Punctuality;
+use super::seat_layout::SeatLayout;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Bus {
@@ -12,6 +20,49 @@ pub struct Bus {
     pub bus_type: String,
     pub total_seats: i32,
     pub route: Route,
+    // Cancellation/amendment policy for this route; falls back to the operator default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_id: Option<mongodb::bson::oid::ObjectId>,
+    // Labelled seats in their places; without one seats are numbered 1..=total_seats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<SeatLayout>,
+}
+
+/// Operator part of an "Operator - PLATE" bus number.
+pub fn operator_from_bus_number(bus_number: &str) -> String {
+    bus_number
+        .split(" - ")
+        .next()
+        .unwrap_or(bus_number)
+        .trim()
+        .to_string()
+}
+
+impl Bus {
+    /// Operator name, taken from the "Operator - PLATE" bus number convention.
+    pub fn operator_name(&self) -> String {
+        operator_from_bus_number(&self.bus_number)
+    }
+
+    pub fn has_seat(&self, seat_number: &str) -> bool {
+        match &self.layout {
+            Some(layout) => layout.has_seat(seat_number),
+            None => seat_number.parse::<i32>().is_ok_and(|n| n >= 1 && n <= self.total_seats),
+        }
+    }
+
+    /// Every seat number on the bus, from the front.
+    pub fn seat_labels(&self) -> Vec<String> {
+        match &self.layout {
+            Some(layout) => layout.labels(),
+            None => numbered_seats(self.total_seats),
+        }
+    }
+}
+
+/// Seat numbers 1..=total_seats, as on a bus without a layout or a swapped-in vehicle.
+pub fn numbered_seats(total_seats: i32) -> Vec<String> {
+    (1..=total_seats).map(|n| n.to_string()).collect()
 }
 
 fn serialize_id_as_hex<S>(
@@ -27,7 +78,7 @@ where
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct Route {
     pub from: String,
     pub to: String,
@@ -36,13 +87,51 @@ pub struct Route {
     pub price: f64,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+// All schedules are published in Kenyan local time (EAT, UTC+3)
+pub fn local_offset() -> FixedOffset {
+    FixedOffset::east_opt(3 * 3600).unwrap()
+}
+
+impl Route {
+    /// Departure instant for a travel date in `YYYY-MM-DD` form.
+    pub fn departure_at(&self, travel_date: &str) -> Option<DateTime<FixedOffset>> {
+        let date = NaiveDate::parse_from_str(travel_date, "%Y-%m-%d").ok()?;
+        let time = NaiveTime::parse_from_str(&self.departure_time, "%I:%M %p").ok()?;
+        date.and_time(time).and_local_timezone(local_offset()).single()
+    }
+
+    /// Arrival instant, rolling over to the next day for overnight routes.
+    pub fn arrival_at(&self, travel_date: &str) -> Option<DateTime<FixedOffset>> {
+        let departure = self.departure_at(travel_date)?;
+        let time = NaiveTime::parse_from_str(&self.arrival_time, "%I:%M %p").ok()?;
+        let mut arrival = departure.date_naive().and_time(time)
+            .and_local_timezone(local_offset()).single()?;
+        if arrival <= departure {
+            arrival += chrono::Duration::days(1);
+        }
+        Some(arrival)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct BusResponse {
     pub id: String,
     pub bus_number: String,
     pub bus_type: String,
     pub total_seats: i32,
     pub route: Route,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<SeatLayout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_names: Option<DisplayNames>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub punctuality: Option<Punctuality>,
+    // Only present when the request names a travel date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seats_left: Option<i32>,
+    // Experiment key -> variant, used by the client to pick e.g. the pricing display
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub experiments: HashMap<String, String>,
 }
 
 impl From<Bus> for BusResponse {
@@ -53,22 +142,182 @@ impl From<Bus> for BusResponse {
             bus_type: bus.bus_type,
             total_seats: bus.total_seats,
             route: bus.route,
+            layout: bus.layout,
+            display_names: None,
+            punctuality: None,
+            seats_left: None,
+            experiments: HashMap::new(),
         }
     }
 }
-#[derive(Serialize, Deserialize, Clone)]
+
+impl BusResponse {
+    /// Attaches display names in the localizer's language; canonical fields stay untouched for filtering.
+    pub fn localized(mut self, localizer: &Localizer) -> Self {
+        self.display_names = Some(DisplayNames {
+            language: localizer.language.clone(),
+            from: localizer.name("location", &self.route.from),
+            to: localizer.name("location", &self.route.to),
+            bus_type: localizer.name("bus_type", &self.bus_type),
+        });
+        self
+    }
+}
+// Adds or edits a bus in the fleet; operator accounts may only use their own operator name
+#[derive(Deserialize, ToSchema)]
+pub struct BusRequest {
+    pub bus_number: String,
+    pub bus_type: String,
+    pub total_seats: i32,
+    pub route: Route,
+    // Seat labels and positions; total_seats must match its seat count
+    #[serde(default)]
+    pub layout: Option<SeatLayout>,
+}
+
+pub const MAX_BUS_SEATS: i32 = 100;
+
+impl BusRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let operator = operator_from_bus_number(&self.bus_number);
+        if operator.is_empty() || !self.bus_number.contains(" - ") {
+            return Err("bus_number must look like \"Operator - PLATE\"".to_string());
+        }
+        if self.bus_type.trim().is_empty() {
+            return Err("bus_type is required".to_string());
+        }
+        if !(1..=MAX_BUS_SEATS).contains(&self.total_seats) {
+            return Err(format!("total_seats must be between 1 and {}", MAX_BUS_SEATS));
+        }
+        if let Some(layout) = &self.layout {
+            layout.validate()?;
+            if layout.seats.len() != self.total_seats as usize {
+                return Err(format!("total_seats is {} but the layout has {} seats", self.total_seats, layout.seats.len()));
+            }
+        }
+        let route = &self.route;
+        if route.from.trim().is_empty() || route.to.trim().is_empty() || route.from.trim().eq_ignore_ascii_case(route.to.trim()) {
+            return Err("route must run between two different places".to_string());
+        }
+        for time in [&route.departure_time, &route.arrival_time] {
+            if NaiveTime::parse_from_str(time, "%I:%M %p").is_err() {
+                return Err(format!("{} is not a time like 08:15 AM", time));
+            }
+        }
+        if !route.price.is_finite() || route.price <= 0.0 {
+            return Err("route price must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct Seat {
     pub seat_number: String,
     pub is_available: bool,
 }
 
+pub const SEAT_AVAILABLE: &str = "available";
+pub const SEAT_HELD: &str = "held";
+pub const SEAT_BOOKED: &str = "booked";
+// Every seat of a trip someone hired the whole bus for
+pub const SEAT_CHARTERED: &str = "chartered";
+
+// The seat number of a booking on a trip sold by headcount, where nobody has a seat of their own
+pub const OPEN_SEATING: &str = "open";
+
+// One document per seat, bus and travel date in `trip_seats`, so a booking touches only its own seat
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripSeat {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<mongodb::bson::oid::ObjectId>,
+    pub bus_id: mongodb::bson::oid::ObjectId,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub state: String, // available, held, booked, chartered
+    // Held seats whose hold ran out are free again even before the sweep releases them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold_expiry: Option<mongodb::bson::DateTime>,
+}
+
+impl TripSeat {
+    pub fn is_available(&self, now: mongodb::bson::DateTime) -> bool {
+        self.state == SEAT_AVAILABLE || (self.state == SEAT_HELD && self.hold_expiry.is_none_or(|expiry| expiry <= now))
+    }
+}
+
+// One document per bus and travel date in `seat_availability`, for what applies to the whole trip
 #[derive(Serialize, Deserialize)]
+pub struct SeatAvailability {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<mongodb::bson::oid::ObjectId>,
+    pub bus_id: mongodb::bson::oid::ObjectId,
+    pub travel_date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle: Option<super::trip::TripVehicle>,
+    // Trips sold by headcount count their places here instead of in `trip_seats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headcount: Option<i32>,
+}
+
+// Places on a trip sold by headcount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct HeadcountSummary {
+    pub capacity: i32,
+    pub booked: i32,
+    pub available: i32,
+}
+
+impl HeadcountSummary {
+    pub fn new(capacity: i32, booked: i32) -> Self {
+        Self { capacity, booked, available: (capacity - booked).max(0) }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct SeatAvailabilityResponse {
     pub travel_date: String,
+    // Empty for trips sold by headcount, which carry `headcount` instead
     pub seats: Vec<Seat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headcount: Option<HeadcountSummary>,
+    // Where the seats sit, for buses that have a layout and run the trip themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<SeatLayout>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct BusListQuery {
+    pub sort: Option<String>, // "punctuality"
+    pub min_on_time: Option<f64>,
+    pub date: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct BusSearchQuery {
+    pub from: String,
+    pub to: String,
+    // YYYY-MM-DD; leaves out trips cancelled that day and adds seats left
+    pub date: Option<String>,
+    #[serde(rename = "type")]
+    pub bus_type: Option<String>,
+}
+
+/// Departure time as a sortable value; unparseable times sort last.
+pub fn departure_sort_key(departure_time: &str) -> (bool, Option<NaiveTime>) {
+    let time = NaiveTime::parse_from_str(departure_time, "%I:%M %p").ok();
+    (time.is_none(), time)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct BusDetailQuery {
+    pub date: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct SeatDateQuery {
     pub date: String,
 }
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub location: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl CalendarEvent {
+    /// Renders the event as a single-event iCalendar (RFC 5545) document.
+    pub fn to_ics(&self) -> String {
+        let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+        let lines = [
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//Burudani Mint Travels//Bus Booking//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", self.start.format("%Y%m%dT%H%M%SZ")),
+            format!("DTEND:{}", self.end.format("%Y%m%dT%H%M%SZ")),
+            format!("SUMMARY:{}", escape_text(&self.summary)),
+            format!("LOCATION:{}", escape_text(&self.location)),
+            format!("DESCRIPTION:{}", escape_text(&self.description)),
+            "END:VEVENT".to_string(),
+            "END:VCALENDAR".to_string(),
+        ];
+
+        lines.iter().map(|l| fold_line(l)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines longer than 75 octets are folded with CRLF + space
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::bus::MAX_BUS_SEATS;
+use super::payment::{mpesa_phone, PaymentResponse};
+
+pub const CHARTER_REQUESTED: &str = "Requested";
+pub const CHARTER_QUOTED: &str = "Quoted";
+// Accepted with its bus blocked, waiting on the deposit
+pub const CHARTER_ACCEPTED: &str = "Accepted";
+pub const CHARTER_RESERVED: &str = "Reserved";
+pub const CHARTER_DECLINED: &str = "Declined";
+pub const CHARTER_CANCELLED: &str = "Cancelled";
+
+pub const CHARTER_STATUSES: [&str; 6] = [
+    CHARTER_REQUESTED, CHARTER_QUOTED, CHARTER_ACCEPTED, CHARTER_RESERVED, CHARTER_DECLINED, CHARTER_CANCELLED,
+];
+
+pub const MAX_CHARTER_NOTES: usize = 500;
+
+// An operator's price for hiring one of its buses for the whole trip
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CharterQuote {
+    pub bus_id: ObjectId,
+    pub bus_number: String,
+    pub operator: String,
+    pub price: f64,
+    pub deposit: f64,
+    pub note: Option<String>,
+    pub quoted_by: String,
+    pub quoted_at: bson::DateTime,
+    pub expires_at: bson::DateTime,
+}
+
+// A group hiring a whole bus: asked for by the customer, priced by an operator, and once the
+// deposit is paid the bus's trip that day is theirs
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Charter {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub passengers: i32,
+    pub notes: Option<String>,
+    pub status: String, // Requested, Quoted, Accepted, Reserved, Declined, Cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<CharterQuote>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decline_reason: Option<String>,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accepted_at: Option<bson::DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserved_at: Option<bson::DateTime>,
+}
+
+impl Charter {
+    /// Where the charter stands now: `requested`, `quoted`, `quote_expired`, `awaiting_deposit`,
+    /// `reserved`, `declined` or `cancelled`.
+    pub fn state(&self, now: DateTime<Utc>) -> &'static str {
+        match self.status.as_str() {
+            CHARTER_REQUESTED => "requested",
+            CHARTER_QUOTED if self.quote.as_ref().is_none_or(|q| q.expires_at.timestamp_millis() <= now.timestamp_millis()) => "quote_expired",
+            CHARTER_QUOTED => "quoted",
+            CHARTER_ACCEPTED => "awaiting_deposit",
+            CHARTER_RESERVED => "reserved",
+            CHARTER_DECLINED => "declined",
+            _ => "cancelled",
+        }
+    }
+}
+
+/// The deposit on a charter price, in whole shillings.
+pub fn charter_deposit(price: f64, deposit_percent: f64) -> f64 {
+    (price * deposit_percent / 100.0).ceil()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CharterRequest {
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub passengers: i32,
+    // Pick-up point, return leg, luggage and the like
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl CharterRequest {
+    pub fn validate(&self, today: &str) -> Result<(), String> {
+        if self.from.trim().is_empty() || self.to.trim().is_empty() {
+            return Err("from and to are required".to_string());
+        }
+        if self.from.trim().eq_ignore_ascii_case(self.to.trim()) {
+            return Err("from and to must be different places".to_string());
+        }
+        if chrono::NaiveDate::parse_from_str(&self.travel_date, "%Y-%m-%d").is_err() {
+            return Err("travel_date must be YYYY-MM-DD".to_string());
+        }
+        if self.travel_date.as_str() <= today {
+            return Err("Charters must be requested at least a day ahead".to_string());
+        }
+        if !(1..=MAX_BUS_SEATS).contains(&self.passengers) {
+            return Err(format!("passengers must be between 1 and {}", MAX_BUS_SEATS));
+        }
+        if self.notes.as_ref().is_some_and(|n| n.chars().count() > MAX_CHARTER_NOTES) {
+            return Err(format!("notes can be at most {} characters", MAX_CHARTER_NOTES));
+        }
+        Ok(())
+    }
+}
+
+// An operator's answer to a charter request, with the bus it would send
+#[derive(Deserialize, ToSchema)]
+pub struct CharterQuoteRequest {
+    pub bus_id: String,
+    pub price: f64,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl CharterQuoteRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.price.is_finite() || self.price <= 0.0 {
+            return Err("price must be positive".to_string());
+        }
+        if self.note.as_ref().is_some_and(|n| n.chars().count() > MAX_CHARTER_NOTES) {
+            return Err(format!("note can be at most {} characters", MAX_CHARTER_NOTES));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CharterListQuery {
+    // requested, quoted, accepted, reserved, declined or cancelled
+    pub status: Option<String>,
+}
+
+impl CharterListQuery {
+    pub fn status(&self) -> Result<Option<&'static str>, String> {
+        let Some(status) = self.status.as_deref() else {
+            return Ok(None);
+        };
+        CHARTER_STATUSES.iter()
+            .find(|s| s.eq_ignore_ascii_case(status.trim()))
+            .map(|s| Some(*s))
+            .ok_or_else(|| format!("status must be one of {}", CHARTER_STATUSES.join(", ").to_lowercase()))
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeclineCharterRequest {
+    pub reason: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AcceptCharterRequest {
+    // The M-Pesa number to prompt for the deposit, in any common Kenyan format
+    pub phone: String,
+}
+
+impl AcceptCharterRequest {
+    pub fn validated_phone(&self) -> Result<String, String> {
+        mpesa_phone(&self.phone)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CharterQuoteResponse {
+    pub bus_id: String,
+    pub bus_number: String,
+    pub operator: String,
+    pub price: f64,
+    pub deposit: f64,
+    pub note: Option<String>,
+    pub expires_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CharterResponse {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub passengers: i32,
+    pub notes: Option<String>,
+    pub status: String,
+    pub quote: Option<CharterQuoteResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_reason: Option<String>,
+    pub created_at: String,
+}
+
+impl CharterResponse {
+    pub fn new(charter: Charter, now: DateTime<Utc>) -> Self {
+        Self {
+            id: charter.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: charter.state(now).to_string(),
+            created_at: charter.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            quote: charter.quote.map(|q| CharterQuoteResponse {
+                bus_id: q.bus_id.to_hex(),
+                bus_number: q.bus_number,
+                operator: q.operator,
+                price: q.price,
+                deposit: q.deposit,
+                note: q.note,
+                expires_at: q.expires_at.try_to_rfc3339_string().unwrap_or_default(),
+            }),
+            from: charter.from,
+            to: charter.to,
+            travel_date: charter.travel_date,
+            passengers: charter.passengers,
+            notes: charter.notes,
+            decline_reason: charter.decline_reason,
+        }
+    }
+}
+
+// An accepted charter waiting on the M-Pesa prompt for its deposit
+#[derive(Serialize, ToSchema)]
+pub struct CharterAcceptanceResponse {
+    pub charter: CharterResponse,
+    pub payment: PaymentResponse,
+}
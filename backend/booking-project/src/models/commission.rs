@@ -0,0 +1,80 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::settlement::round_money;
+
+pub const COMMISSION_KINDS: [&str; 2] = ["percentage", "flat"];
+
+// Platform share of each fare when no rule matches the operator or route
+pub const DEFAULT_COMMISSION_PERCENT: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommissionRule {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub operator: String,
+    // Set for a route override; operator-wide otherwise
+    pub bus_id: Option<ObjectId>,
+    pub kind: String, // percentage or flat (KES per ticket)
+    pub value: f64,
+    pub updated_at: bson::DateTime,
+}
+
+impl CommissionRule {
+    pub fn builtin(operator: &str) -> Self {
+        Self {
+            id: None,
+            operator: operator.to_string(),
+            bus_id: None,
+            kind: "percentage".to_string(),
+            value: DEFAULT_COMMISSION_PERCENT,
+            updated_at: bson::DateTime::from_millis(0),
+        }
+    }
+
+    /// Platform commission on one ticket; never more than the fare itself.
+    pub fn commission_for(&self, fare: f64) -> f64 {
+        let commission = match self.kind.as_str() {
+            "flat" => self.value,
+            _ => fare * self.value / 100.0,
+        };
+        round_money(commission.clamp(0.0, fare.max(0.0)))
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpsertCommissionRuleRequest {
+    pub operator: String,
+    pub bus_id: Option<String>,
+    pub kind: String,
+    pub value: f64,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CommissionRuleQuery {
+    pub operator: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CommissionRuleResponse {
+    pub id: Option<String>,
+    pub operator: String,
+    pub bus_id: Option<String>,
+    pub kind: String,
+    pub value: f64,
+    pub updated_at: Option<String>,
+}
+
+impl From<CommissionRule> for CommissionRuleResponse {
+    fn from(r: CommissionRule) -> Self {
+        Self {
+            id: r.id.map(|oid| oid.to_hex()),
+            operator: r.operator,
+            bus_id: r.bus_id.map(|oid| oid.to_hex()),
+            kind: r.kind,
+            value: r.value,
+            updated_at: r.id.and(r.updated_at.try_to_rfc3339_string().ok()),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use super::bus::Bus;
+use super::punctuality::Punctuality;
+
+#[derive(Deserialize, IntoParams)]
+pub struct RouteCompareQuery {
+    pub from: String,
+    pub to: String,
+    // YYYY-MM-DD; without it every scheduled trip counts
+    pub date: Option<String>,
+}
+
+// One operator's row in the comparison table
+#[derive(Serialize)]
+pub struct OperatorComparison {
+    pub operator: String,
+    pub trips: u32,
+    pub min_price: f64,
+    pub earliest_departure: String,
+    // Free seats across the operator's trips on the date; absent without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seats_left: Option<i32>,
+    // Mean of passengers' 1-5 ratings; null until someone has rated the operator
+    pub average_rating: Option<f64>,
+    pub punctuality: Option<Punctuality>,
+}
+
+#[derive(Serialize)]
+pub struct RouteComparison {
+    pub from: String,
+    pub to: String,
+    pub date: Option<String>,
+    // Cheapest operator first
+    pub operators: Vec<OperatorComparison>,
+}
+
+fn departure_order(time: &str) -> Option<chrono::NaiveTime> {
+    chrono::NaiveTime::parse_from_str(time, "%I:%M %p").ok()
+}
+
+/// Groups bookable trips by operator. `trips` pairs each bus with its free seats on the date,
+/// if one was given; `punctuality` is keyed by operator for this route.
+pub fn compare_operators(trips: &[(Bus, Option<i32>)], punctuality: &BTreeMap<String, Punctuality>) -> Vec<OperatorComparison> {
+    let mut operators: BTreeMap<String, OperatorComparison> = BTreeMap::new();
+    for (bus, seats_left) in trips {
+        let operator = bus.operator_name();
+        let row = operators.entry(operator.clone()).or_insert_with(|| OperatorComparison {
+            punctuality: punctuality.get(&operator).cloned(),
+            operator,
+            trips: 0,
+            min_price: bus.route.price,
+            earliest_departure: bus.route.departure_time.clone(),
+            seats_left: None,
+            average_rating: None,
+        });
+        row.trips += 1;
+        row.min_price = row.min_price.min(bus.route.price);
+        if departure_order(&bus.route.departure_time) < departure_order(&row.earliest_departure) {
+            row.earliest_departure = bus.route.departure_time.clone();
+        }
+        if let Some(left) = seats_left {
+            row.seats_left = Some(row.seats_left.unwrap_or(0) + left);
+        }
+    }
+
+    let mut rows: Vec<OperatorComparison> = operators.into_values().collect();
+    rows.sort_by(|a, b| a.min_price.partial_cmp(&b.min_price).unwrap_or(std::cmp::Ordering::Equal));
+    rows
+}
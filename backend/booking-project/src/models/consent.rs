@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const MARKETING_EMAIL: &str = "marketing_email";
+pub const MARKETING_SMS: &str = "marketing_sms";
+pub const ANALYTICS: &str = "analytics";
+pub const CONSENT_PURPOSES: [&str; 3] = [MARKETING_EMAIL, MARKETING_SMS, ANALYTICS];
+// Where a consent decision was made
+pub const CONSENT_SOURCES: [&str; 6] = [
+    "registration", "account_settings", "checkout", "support", "unsubscribe_link", "account_anonymized",
+];
+
+/// The consent a promotional message on `channel` needs, if the channel carries promotions at all.
+pub fn marketing_purpose(channel: &str) -> Option<&'static str> {
+    match channel {
+        "email" => Some(MARKETING_EMAIL),
+        "sms" => Some(MARKETING_SMS),
+        _ => None,
+    }
+}
+
+pub fn validate_consents(changes: &HashMap<String, bool>, source: &str) -> Result<(), String> {
+    if let Some(purpose) = changes.keys().find(|p| !CONSENT_PURPOSES.contains(&p.as_str())) {
+        return Err(format!("Unknown consent '{}', expected one of: {}", purpose, CONSENT_PURPOSES.join(", ")));
+    }
+    if !CONSENT_SOURCES.contains(&source) {
+        return Err(format!("Unknown source '{}', expected one of: {}", source, CONSENT_SOURCES.join(", ")));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConsentState {
+    pub granted: bool,
+    pub source: String,
+    pub updated_at: bson::DateTime,
+}
+
+// Current decisions of one user; purposes never decided on count as not granted
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserConsents {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    #[serde(default)]
+    pub purposes: HashMap<String, ConsentState>,
+}
+
+impl UserConsents {
+    pub fn is_granted(&self, purpose: &str) -> bool {
+        self.purposes.get(purpose).is_some_and(|c| c.granted)
+    }
+}
+
+// Every change is kept, so what a user had agreed to at any point can be shown
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConsentEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub purpose: String,
+    pub granted: bool,
+    pub source: String,
+    pub recorded_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateConsentsRequest {
+    pub consents: HashMap<String, bool>,
+    pub source: Option<String>,
+}
+
+// A promotional message broadcast to everyone who opted in on its channel
+#[derive(Deserialize, ToSchema)]
+pub struct PromotionRequest {
+    pub channel: String, // email, sms
+    pub subject: Option<String>,
+    pub body: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ConsentResponse {
+    pub purpose: String,
+    pub granted: bool,
+    pub source: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl ConsentResponse {
+    /// One entry per known purpose, in a fixed order.
+    pub fn all(consents: &UserConsents) -> Vec<Self> {
+        CONSENT_PURPOSES
+            .iter()
+            .map(|purpose| {
+                let state = consents.purposes.get(*purpose);
+                Self {
+                    purpose: purpose.to_string(),
+                    granted: state.is_some_and(|s| s.granted),
+                    source: state.map(|s| s.source.clone()),
+                    updated_at: state.and_then(|s| s.updated_at.try_to_rfc3339_string().ok()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ConsentEventResponse {
+    pub purpose: String,
+    pub granted: bool,
+    pub source: String,
+    pub recorded_at: String,
+}
+
+impl From<ConsentEvent> for ConsentEventResponse {
+    fn from(e: ConsentEvent) -> Self {
+        Self {
+            purpose: e.purpose,
+            granted: e.granted,
+            source: e.source,
+            recorded_at: e.recorded_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
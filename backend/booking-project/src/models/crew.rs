@@ -0,0 +1,52 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const CREW_ROLES: [&str; 2] = ["conductor", "driver"];
+
+// Who works a trip; conductors are linked to their staff account, drivers may just be named
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CrewAssignment {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub role: String,
+    pub user_id: Option<ObjectId>,
+    pub name: String,
+    pub phone: Option<String>,
+    pub licence_number: Option<String>,
+    pub assigned_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignCrewRequest {
+    pub role: String,
+    pub user_id: Option<String>,
+    pub name: Option<String>,
+    pub phone: Option<String>,
+    pub licence_number: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrewResponse {
+    pub id: String,
+    pub role: String,
+    pub user_id: Option<String>,
+    pub name: String,
+    pub phone: Option<String>,
+    pub licence_number: Option<String>,
+}
+
+impl From<CrewAssignment> for CrewResponse {
+    fn from(c: CrewAssignment) -> Self {
+        Self {
+            id: c.id.map(|id| id.to_hex()).unwrap_or_default(),
+            role: c.role,
+            user_id: c.user_id.map(|id| id.to_hex()),
+            name: c.name,
+            phone: c.phone,
+            licence_number: c.licence_number,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mongodb::bson::{self, oid::ObjectId};
+use ring::digest;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bus::Bus;
+use super::tenancy::Tenant;
+
+pub const DEVICE_ID_HEADER: &str = "x-device-id";
+pub const DEVICE_TIMESTAMP_HEADER: &str = "x-device-timestamp";
+pub const DEVICE_NONCE_HEADER: &str = "x-device-nonce";
+pub const DEVICE_SIGNATURE_HEADER: &str = "x-device-signature";
+
+// Long enough to be unguessable, short enough that a flaky app can't pad the nonce store
+const NONCE_LENGTH: std::ops::RangeInclusive<usize> = 16..=128;
+
+/// A conductor or driver handset, provisioned with its own signing key so a stolen one can be
+/// cut off without touching anyone else's.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Device {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub label: String,
+    // The operator whose crews carry it; None for platform-owned devices
+    pub operator: Option<String>,
+    // The signing key, encrypted with the record's id as context
+    pub sealed_key: String,
+    pub created_by: String,
+    pub created_at: bson::DateTime,
+    #[serde(default)]
+    pub last_seen_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub revoked_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub revoked_by: Option<String>,
+    #[serde(default)]
+    pub revocation_reason: Option<String>,
+}
+
+/// A fresh device key, shown to the operator once at provisioning.
+pub fn new_device_key() -> Result<String, String> {
+    let mut key = [0u8; 32];
+    SystemRandom::new().fill(&mut key).map_err(|_| "Failed to generate device key".to_string())?;
+    Ok(URL_SAFE_NO_PAD.encode(key))
+}
+
+/// What a device signs: the request line, when, a one-off nonce, and a hash of the body, one
+/// per line. The path includes the query string exactly as sent.
+pub fn signing_string(method: &str, path_and_query: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    let body_hash = URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, body));
+    format!("{}\n{}\n{}\n{}\n{}", method.to_uppercase(), path_and_query, timestamp, nonce, body_hash)
+}
+
+/// The `X-Device-Signature` value for a request: HMAC-SHA256 of its signing string, base64url.
+pub fn sign_request(key: &str, method: &str, path_and_query: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    let signed = signing_string(method, path_and_query, timestamp, nonce, body);
+    let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()), signed.as_bytes());
+    URL_SAFE_NO_PAD.encode(tag.as_ref())
+}
+
+/// The headers a device signs with, as sent.
+pub struct DeviceSignature {
+    pub device_id: String,
+    pub timestamp: i64,
+    pub nonce: String,
+    pub signature: String,
+}
+
+impl DeviceSignature {
+    /// Checks the parts that don't need the device's key: a fresh timestamp and a usable nonce.
+    pub fn validate(&self, now: i64, max_skew_secs: i64) -> Result<(), String> {
+        if (now - self.timestamp).abs() > max_skew_secs {
+            return Err("Request timestamp is too far from the server's clock".to_string());
+        }
+        if !NONCE_LENGTH.contains(&self.nonce.len()) || !self.nonce.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!(
+                "{} must be {} to {} letters, digits, - or _",
+                DEVICE_NONCE_HEADER, NONCE_LENGTH.start(), NONCE_LENGTH.end()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn verify(&self, key: &str, method: &str, path_and_query: &str, body: &[u8]) -> bool {
+        let Ok(tag) = URL_SAFE_NO_PAD.decode(self.signature.trim()) else {
+            return false;
+        };
+        let signed = signing_string(method, path_and_query, self.timestamp, &self.nonce, body);
+        hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()), signed.as_bytes(), &tag).is_ok()
+    }
+}
+
+/// The device a request was signed by, for handlers that record where data came from.
+#[derive(Clone)]
+pub struct SignedDevice {
+    pub device_id: String,
+    // The operator whose crews carry it; None for platform-owned devices
+    pub operator: Option<String>,
+}
+
+impl SignedDevice {
+    /// Whether this handset may work the bus's trips: an operator's devices only serve its own fleet.
+    pub fn serves(&self, bus: &Bus) -> bool {
+        match &self.operator {
+            Some(operator) => Tenant::Operator(operator.clone()).owns_bus(bus),
+            None => true,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ProvisionDeviceRequest {
+    // What the operator calls it, e.g. "KDC 220X conductor phone"
+    pub label: String,
+    // Platform admins provision for an operator; operator accounts always get their own
+    pub operator: Option<String>,
+}
+
+impl ProvisionDeviceRequest {
+    pub fn validated_label(&self) -> Result<String, String> {
+        let label = self.label.trim();
+        if label.is_empty() || label.chars().count() > 100 {
+            return Err("label is required, up to 100 characters".to_string());
+        }
+        Ok(label.to_string())
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RevokeDeviceRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeviceResponse {
+    pub id: String,
+    pub label: String,
+    pub operator: Option<String>,
+    // active or revoked
+    pub status: String,
+    pub created_at: String,
+    pub last_seen_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub revocation_reason: Option<String>,
+}
+
+impl From<Device> for DeviceResponse {
+    fn from(device: Device) -> Self {
+        let rfc3339 = |at: Option<bson::DateTime>| at.and_then(|at| at.try_to_rfc3339_string().ok());
+        Self {
+            id: device.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: if device.revoked_at.is_some() { "revoked" } else { "active" }.to_string(),
+            created_at: device.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            last_seen_at: rfc3339(device.last_seen_at),
+            revoked_at: rfc3339(device.revoked_at),
+            label: device.label,
+            operator: device.operator,
+            revocation_reason: device.revocation_reason,
+        }
+    }
+}
+
+// The key is only ever returned here; after provisioning nobody can read it back
+#[derive(Serialize, ToSchema)]
+pub struct ProvisionedDeviceResponse {
+    #[serde(flatten)]
+    pub device: DeviceResponse,
+    pub key: String,
+}
@@ -0,0 +1,118 @@
+use std::collections::{BTreeSet, HashMap};
+
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Names as people type them differently across sign-ups: case and spacing don't count.
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// Accounts tied together by one shared detail, and why
+pub struct DuplicateLink {
+    pub user_ids: BTreeSet<ObjectId>,
+    pub reason: String,
+}
+
+impl DuplicateLink {
+    /// Splits accounts sharing a payment number into those that also share a name; a shared
+    /// number alone is as likely a family paying for each other.
+    pub fn by_name(user_ids: &BTreeSet<ObjectId>, names: &HashMap<ObjectId, String>, reason: &str) -> Vec<DuplicateLink> {
+        let mut by_name: HashMap<String, BTreeSet<ObjectId>> = HashMap::new();
+        for user_id in user_ids {
+            if let Some(name) = names.get(user_id) {
+                by_name.entry(normalize_name(name)).or_default().insert(*user_id);
+            }
+        }
+        by_name.into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|user_ids| DuplicateLink { user_ids, reason: reason.to_string() })
+            .collect()
+    }
+}
+
+/// Joins links sharing an account into groups of accounts that are likely one person, each with
+/// every reason that tied it together. Groups come out oldest account first.
+pub fn cluster_duplicates(links: Vec<DuplicateLink>) -> Vec<(Vec<ObjectId>, Vec<String>)> {
+    let mut groups: Vec<(BTreeSet<ObjectId>, Vec<String>)> = Vec::new();
+    for link in links.into_iter().filter(|l| l.user_ids.len() > 1) {
+        let (mut user_ids, mut reasons) = (link.user_ids, vec![link.reason]);
+        // Every group this link touches folds into it
+        let (touching, rest): (Vec<_>, Vec<_>) = groups.into_iter().partition(|(ids, _)| !ids.is_disjoint(&user_ids));
+        for (ids, group_reasons) in touching {
+            user_ids.extend(ids);
+            reasons.extend(group_reasons);
+        }
+        reasons.sort();
+        reasons.dedup();
+        groups = rest;
+        groups.push((user_ids, reasons));
+    }
+    // ObjectIds start with their creation time
+    let mut groups: Vec<(Vec<ObjectId>, Vec<String>)> = groups.into_iter().map(|(ids, reasons)| (ids.into_iter().collect(), reasons)).collect();
+    groups.sort_by_key(|(ids, _)| ids.first().copied());
+    groups
+}
+
+#[derive(Serialize)]
+pub struct DuplicateAccount {
+    pub id: String,
+    pub username: String,
+    pub email: String,
+    // Google sign-ups have no password
+    pub has_password: bool,
+    pub bookings: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_login_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub accounts: Vec<DuplicateAccount>,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MergeAccountsRequest {
+    // The account that is kept; the duplicate's bookings and sessions move to it
+    pub survivor_id: String,
+    pub duplicate_id: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl MergeAccountsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.survivor_id == self.duplicate_id {
+            return Err("An account can't be merged into itself".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct MergeResult {
+    pub survivor_id: String,
+    pub merged_id: String,
+    pub bookings: u64,
+    pub payments: u64,
+    pub payment_methods: u64,
+    pub refund_requests: u64,
+    pub lost_items: u64,
+    pub notifications: u64,
+    pub seat_holds: u64,
+    pub sessions: u64,
+}
+
+impl MergeResult {
+    /// What moved, for the audit log.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} booking(s), {} payment(s), {} saved payment method(s), {} refund request(s), {} lost item report(s), {} notification(s), {} seat hold(s) and {} session(s) moved",
+            self.bookings, self.payments, self.payment_methods, self.refund_requests, self.lost_items, self.notifications, self.seat_holds, self.sessions
+        )
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Placeholders a booking email may use, written `{name}` as in the built-in wording
+pub const BOOKING_EMAIL_FIELDS: [&str; 11] = [
+    "reference", "passenger", "seat", "bus", "bus_type", "from", "to", "date", "time", "fare", "status",
+];
+
+const MAX_SUBJECT_CHARS: usize = 200;
+const MAX_BODY_CHARS: usize = 10_000;
+
+// An operator's own wording for an email, in place of the built-in one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Names of the `{placeholders}` in `text`, in order of appearance.
+pub fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        names.push(&rest[start + 1..start + 1 + len]);
+        rest = &rest[start + len + 2..];
+    }
+    names
+}
+
+/// Fills in `{name}` placeholders; unknown ones are left as written.
+pub fn render_template(text: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(text.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+impl EmailTemplate {
+    /// Checks the template only uses placeholders from `fields`, so a typo shows up when it's
+    /// saved rather than in a passenger's inbox.
+    pub fn validate(&self, fields: &[&str]) -> Result<(), String> {
+        if self.subject.trim().is_empty() || self.body.trim().is_empty() {
+            return Err("subject and body are required".to_string());
+        }
+        if self.subject.chars().count() > MAX_SUBJECT_CHARS || self.body.chars().count() > MAX_BODY_CHARS {
+            return Err(format!("Keep the subject under {} and the body under {} characters", MAX_SUBJECT_CHARS, MAX_BODY_CHARS));
+        }
+        if self.subject.contains(['\r', '\n']) {
+            return Err("subject must be a single line".to_string());
+        }
+        let subject = placeholders(&self.subject);
+        if let Some(unknown) = subject.iter().chain(&placeholders(&self.body)).find(|p| !fields.contains(p)) {
+            return Err(format!("Unknown placeholder {{{}}}; use any of: {}", unknown, fields.join(", ")));
+        }
+        Ok(())
+    }
+
+    pub fn render(&self, values: &[(&str, &str)]) -> (String, String) {
+        (render_template(&self.subject, values), render_template(&self.body, values))
+    }
+}
+
+#[derive(Serialize)]
+pub struct EmailTemplateResponse {
+    pub subject: String,
+    pub body: String,
+    // False while the operator uses the built-in wording
+    pub custom: bool,
+    pub fields: Vec<&'static str>,
+}
@@ -0,0 +1,62 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::crypto::fnv1a;
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct Variant {
+    pub name: String,
+    pub weight: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Experiment {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub key: String,
+    pub description: Option<String>,
+    pub variants: Vec<Variant>,
+    pub active: bool,
+    pub updated_at: bson::DateTime,
+}
+
+impl Experiment {
+    /// Deterministically picks a variant for a subject; the same subject always lands in the same bucket.
+    pub fn assign(&self, subject: &str) -> Option<&Variant> {
+        let total: u64 = self.variants.iter().map(|v| v.weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut bucket = fnv1a(&format!("{}:{}", self.key, subject)) % total;
+        for variant in &self.variants {
+            if bucket < variant.weight as u64 {
+                return Some(variant);
+            }
+            bucket -= variant.weight as u64;
+        }
+        None
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpsertExperimentRequest {
+    pub key: String,
+    pub description: Option<String>,
+    pub variants: Vec<Variant>,
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExperimentExposure {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub experiment: String,
+    pub variant: String,
+    pub subject: String,
+    pub context: String,
+    pub exposures: i64,
+    pub first_exposed_at: bson::DateTime,
+    pub last_exposed_at: bson::DateTime,
+}
@@ -0,0 +1,157 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::bus::Bus;
+
+// One priced pair of stops, travelling from `from` towards `to`
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SegmentFare {
+    pub from: String,
+    pub to: String,
+    pub price: f64,
+}
+
+// A bus's stops in travel order and what each stretch between them costs. The fare for the
+// whole route stays the bus's own price
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FareTable {
+    #[serde(rename = "_id")]
+    pub bus_id: ObjectId,
+    pub stops: Vec<String>,
+    pub fares: Vec<SegmentFare>,
+    pub updated_at: bson::DateTime,
+}
+
+impl FareTable {
+    fn stop_index(&self, name: &str) -> Option<usize> {
+        self.stops.iter().position(|s| s.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Price between two stops, if the table lists it.
+    pub fn fare(&self, from: &str, to: &str) -> Option<f64> {
+        self.fares.iter()
+            .find(|f| f.from.eq_ignore_ascii_case(from.trim()) && f.to.eq_ignore_ascii_case(to.trim()))
+            .map(|f| f.price)
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct FareTableRequest {
+    pub stops: Vec<String>,
+    pub fares: Vec<SegmentFare>,
+}
+
+impl FareTableRequest {
+    /// Checks the table against the bus's route: stops run from its origin to its destination,
+    /// and every fare is for a forward pair of them.
+    pub fn validate(&self, bus: &Bus) -> Result<(), String> {
+        let stops: Vec<&str> = self.stops.iter().map(|s| s.trim()).collect();
+        if stops.len() < 2 || stops.iter().any(|s| s.is_empty()) {
+            return Err("stops must list at least the origin and destination".to_string());
+        }
+        if !stops[0].eq_ignore_ascii_case(&bus.route.from) || !stops[stops.len() - 1].eq_ignore_ascii_case(&bus.route.to) {
+            return Err(format!("stops must run from {} to {}", bus.route.from, bus.route.to));
+        }
+        let index = |name: &str| stops.iter().position(|s| s.eq_ignore_ascii_case(name.trim()));
+        for (i, stop) in stops.iter().enumerate() {
+            if index(stop) != Some(i) {
+                return Err(format!("{} is listed more than once", stop));
+            }
+        }
+
+        for (i, fare) in self.fares.iter().enumerate() {
+            let (Some(from), Some(to)) = (index(&fare.from), index(&fare.to)) else {
+                return Err(format!("{} to {} is not between stops on this route", fare.from, fare.to));
+            };
+            if from >= to {
+                return Err(format!("{} comes after {} on this route", fare.from, fare.to));
+            }
+            if from == 0 && to == stops.len() - 1 {
+                return Err("The full route fare is the bus's price; leave it out of the table".to_string());
+            }
+            if !fare.price.is_finite() || fare.price <= 0.0 {
+                return Err(format!("{} to {} needs a positive price", fare.from, fare.to));
+            }
+            if self.fares[..i].iter().any(|f| index(&f.from) == Some(from) && index(&f.to) == Some(to)) {
+                return Err(format!("{} to {} is priced more than once", fare.from, fare.to));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_table(self, bus_id: ObjectId) -> FareTable {
+        let stops: Vec<String> = self.stops.iter().map(|s| s.trim().to_string()).collect();
+        // Stored under the stops' own spelling so lookups and responses agree
+        let canonical = |name: &str| stops.iter().find(|s| s.eq_ignore_ascii_case(name.trim())).cloned().unwrap_or_default();
+        let fares = self.fares.iter()
+            .map(|f| SegmentFare { from: canonical(&f.from), to: canonical(&f.to), price: f.price })
+            .collect();
+        FareTable { bus_id, stops, fares, updated_at: bson::DateTime::now() }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FareTableResponse {
+    pub bus_id: String,
+    pub stops: Vec<String>,
+    pub fares: Vec<SegmentFare>,
+    pub updated_at: String,
+}
+
+impl From<FareTable> for FareTableResponse {
+    fn from(table: FareTable) -> Self {
+        Self {
+            bus_id: table.bus_id.to_hex(),
+            stops: table.stops,
+            fares: table.fares,
+            updated_at: table.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct FareQuoteQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct FareQuote {
+    pub bus_id: String,
+    pub from: String,
+    pub to: String,
+    pub price: f64,
+    pub currency: String,
+    // Whether the price came from the fare table rather than the route's own fare
+    pub segment: bool,
+}
+
+/// Prices a journey on `bus`, by default the whole route. Shorter journeys need both stops on
+/// the bus's fare table and a fare for the pair.
+pub fn quote_fare(bus: &Bus, table: Option<&FareTable>, from: Option<&str>, to: Option<&str>) -> Result<FareQuote, String> {
+    let from = from.map(str::trim).filter(|s| !s.is_empty()).unwrap_or(&bus.route.from);
+    let to = to.map(str::trim).filter(|s| !s.is_empty()).unwrap_or(&bus.route.to);
+    let quote = |from: &str, to: &str, price: f64, segment: bool| FareQuote {
+        bus_id: bus.id.map(|id| id.to_hex()).unwrap_or_default(),
+        from: from.to_string(),
+        to: to.to_string(),
+        price,
+        currency: "KES".to_string(),
+        segment,
+    };
+
+    if from.eq_ignore_ascii_case(&bus.route.from) && to.eq_ignore_ascii_case(&bus.route.to) {
+        return Ok(quote(&bus.route.from, &bus.route.to, bus.route.price, false));
+    }
+    let table = table.ok_or("This bus only sells tickets for its whole route")?;
+    let (Some(start), Some(end)) = (table.stop_index(from), table.stop_index(to)) else {
+        return Err(format!("This bus doesn't stop at both {} and {}", from, to));
+    };
+    if start >= end {
+        return Err(format!("{} comes after {} on this route", from, to));
+    }
+    let (from, to) = (&table.stops[start], &table.stops[end]);
+    let price = table.fare(from, to).ok_or_else(|| format!("No fare is set from {} to {}", from, to))?;
+    Ok(quote(from, to, price, true))
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use chrono::NaiveTime;
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+
+use super::bus::{Bus, Route};
+
+/// URL-friendly route name, e.g. `nairobi-to-kisumu`, stable for as long as the town names are.
+pub fn route_slug(from: &str, to: &str) -> String {
+    let part = |name: &str| {
+        name.to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    };
+    format!("{}-to-{}", part(from), part(to))
+}
+
+// One origin-destination pair as the marketing site shows it, across every bus that runs it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RouteSummary {
+    pub slug: String,
+    pub from: String,
+    pub to: String,
+    pub operators: Vec<String>,
+    pub departures_per_day: i64,
+    // Departure times in schedule order, as published ("08:15 AM")
+    pub departure_times: Vec<String>,
+    pub shortest_trip_minutes: Option<i64>,
+    pub starting_price: f64,
+    pub currency: String,
+}
+
+fn trip_minutes(route: &Route) -> Option<i64> {
+    let departure = NaiveTime::parse_from_str(&route.departure_time, "%I:%M %p").ok()?;
+    let arrival = NaiveTime::parse_from_str(&route.arrival_time, "%I:%M %p").ok()?;
+    let minutes = (arrival - departure).num_minutes();
+    // Overnight routes arrive the next day
+    Some(if minutes <= 0 { minutes + 24 * 60 } else { minutes })
+}
+
+/// Rolls the fleet up into one summary per route, ordered by slug.
+pub fn summarize_routes(buses: &[Bus]) -> Vec<RouteSummary> {
+    let mut routes: BTreeMap<String, RouteSummary> = BTreeMap::new();
+    let mut departures: BTreeMap<String, Vec<(Option<NaiveTime>, String)>> = BTreeMap::new();
+    for bus in buses {
+        let slug = route_slug(&bus.route.from, &bus.route.to);
+        let summary = routes.entry(slug.clone()).or_insert_with(|| RouteSummary {
+            slug: slug.clone(),
+            from: bus.route.from.clone(),
+            to: bus.route.to.clone(),
+            operators: Vec::new(),
+            departures_per_day: 0,
+            departure_times: Vec::new(),
+            shortest_trip_minutes: None,
+            starting_price: bus.route.price,
+            currency: "KES".to_string(),
+        });
+        let operator = bus.operator_name();
+        if !summary.operators.contains(&operator) {
+            summary.operators.push(operator);
+        }
+        summary.departures_per_day += 1;
+        summary.starting_price = summary.starting_price.min(bus.route.price);
+        if let Some(minutes) = trip_minutes(&bus.route) {
+            summary.shortest_trip_minutes = Some(summary.shortest_trip_minutes.map_or(minutes, |m| m.min(minutes)));
+        }
+        let time = NaiveTime::parse_from_str(&bus.route.departure_time, "%I:%M %p").ok();
+        departures.entry(slug).or_default().push((time, bus.route.departure_time.clone()));
+    }
+
+    routes.into_iter().map(|(slug, mut summary)| {
+        let mut times = departures.remove(&slug).unwrap_or_default();
+        times.sort();
+        times.dedup_by(|a, b| a.1 == b.1);
+        summary.departure_times = times.into_iter().map(|(_, t)| t).collect();
+        summary.operators.sort();
+        summary
+    }).collect()
+}
+
+// Snapshot written by the feed job, so crawlers hitting the feed never touch the fleet
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RouteFeed {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub routes: Vec<RouteSummary>,
+    pub generated_at: bson::DateTime,
+}
+
+#[derive(Serialize)]
+pub struct RouteFeedResponse {
+    pub generated_at: String,
+    pub routes: Vec<RouteSummary>,
+}
+
+impl From<RouteFeed> for RouteFeedResponse {
+    fn from(feed: RouteFeed) -> Self {
+        Self {
+            generated_at: feed.generated_at.try_to_rfc3339_string().unwrap_or_default(),
+            routes: feed.routes,
+        }
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// The feed as XML, one `<route>` per summary.
+pub fn render_routes_xml(feed: &RouteFeedResponse) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(xml, "<routes generated_at=\"{}\">", xml_escape(&feed.generated_at));
+    for route in &feed.routes {
+        let _ = writeln!(xml, "  <route slug=\"{}\">", xml_escape(&route.slug));
+        let _ = writeln!(xml, "    <from>{}</from>", xml_escape(&route.from));
+        let _ = writeln!(xml, "    <to>{}</to>", xml_escape(&route.to));
+        for operator in &route.operators {
+            let _ = writeln!(xml, "    <operator>{}</operator>", xml_escape(operator));
+        }
+        let _ = writeln!(xml, "    <departures_per_day>{}</departures_per_day>", route.departures_per_day);
+        for time in &route.departure_times {
+            let _ = writeln!(xml, "    <departure>{}</departure>", xml_escape(time));
+        }
+        if let Some(minutes) = route.shortest_trip_minutes {
+            let _ = writeln!(xml, "    <shortest_trip_minutes>{}</shortest_trip_minutes>", minutes);
+        }
+        let _ = writeln!(xml, "    <starting_price currency=\"{}\">{:.2}</starting_price>", xml_escape(&route.currency), route.starting_price);
+        xml.push_str("  </route>\n");
+    }
+    xml.push_str("</routes>\n");
+    xml
+}
@@ -0,0 +1,173 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const BLOCKLIST_KINDS: [&str; 4] = ["email", "phone", "device", "mpesa"];
+
+// Error code returned to clients refused by the blocklist
+pub const BLOCKED: &str = "blocked";
+
+/// Canonical form of a blocklist value so lookups don't depend on formatting.
+pub fn normalize_blocklist_value(kind: &str, value: &str) -> String {
+    let value = value.trim();
+    match kind {
+        "email" => value.to_lowercase(),
+        // Kenyan numbers arrive as 07.., +2547.. or 2547..; store them all as 2547..
+        "phone" | "mpesa" => {
+            let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+            match digits.strip_prefix('0') {
+                Some(local) if digits.len() == 10 => format!("254{}", local),
+                _ => digits,
+            }
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlocklistEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: bson::DateTime,
+    pub expires_at: Option<bson::DateTime>,
+}
+
+// Audit trail of every request the blocklist refused
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnforcementDecision {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub context: String, // register, login, booking, booking_lookup
+    pub kind: String,
+    pub value: String,
+    pub entry_id: Option<ObjectId>,
+    pub user_id: Option<ObjectId>,
+    pub decision: String, // blocked, enumeration_suspected
+    pub at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BlocklistRequest {
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    // RFC 3339; permanent when omitted
+    pub expires_at: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct BlocklistQuery {
+    pub kind: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlocklistEntryResponse {
+    pub id: String,
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+}
+
+impl From<BlocklistEntry> for BlocklistEntryResponse {
+    fn from(e: BlocklistEntry) -> Self {
+        Self {
+            id: e.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            kind: e.kind,
+            value: e.value,
+            reason: e.reason,
+            created_by: e.created_by,
+            created_at: e.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            expires_at: e.expires_at.and_then(|d| d.try_to_rfc3339_string().ok()),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EnforcementDecisionResponse {
+    pub context: String,
+    pub kind: String,
+    pub value: String,
+    pub entry_id: Option<String>,
+    pub user_id: Option<String>,
+    pub decision: String,
+    pub at: String,
+}
+
+impl From<EnforcementDecision> for EnforcementDecisionResponse {
+    fn from(d: EnforcementDecision) -> Self {
+        Self {
+            context: d.context,
+            kind: d.kind,
+            value: d.value,
+            entry_id: d.entry_id.map(|oid| oid.to_hex()),
+            user_id: d.user_id.map(|oid| oid.to_hex()),
+            decision: d.decision,
+            at: d.at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+// Inputs to the booking risk score
+pub struct FraudSignals {
+    pub account_age_hours: Option<i64>,
+    pub bookings_last_hour: u64,
+    pub payer_phone_mismatch: bool,
+    pub failed_payments_24h: u64,
+    pub foreign_country: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct RiskAssessment {
+    pub score: u32, // 0-100
+    pub reasons: Vec<String>,
+}
+
+impl FraudSignals {
+    pub fn assess(&self) -> RiskAssessment {
+        let mut score = 0;
+        let mut reasons = Vec::new();
+        let mut add = |points: u32, reason: String| {
+            score += points;
+            reasons.push(reason);
+        };
+
+        match self.account_age_hours {
+            Some(hours) if hours < 1 => add(30, "account created within the last hour".to_string()),
+            Some(hours) if hours < 24 => add(15, "account created within the last day".to_string()),
+            None => add(15, "account age unknown".to_string()),
+            _ => {}
+        }
+        match self.bookings_last_hour {
+            n if n >= 5 => add(35, format!("{} bookings in the last hour", n)),
+            n if n >= 3 => add(20, format!("{} bookings in the last hour", n)),
+            _ => {}
+        }
+        if self.payer_phone_mismatch {
+            add(20, "payer phone differs from passenger phone".to_string());
+        }
+        match self.failed_payments_24h {
+            n if n >= 3 => add(30, format!("{} failed payments in the last day", n)),
+            n if n >= 1 => add(10, format!("{} failed payment(s) in the last day", n)),
+            _ => {}
+        }
+
+        if let Some(country) = &self.foreign_country {
+            add(15, format!("booked from outside home markets ({})", country));
+        }
+
+        RiskAssessment { score: score.min(100), reasons }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReviewDecisionRequest {
+    pub decision: String, // approve or reject
+    pub note: Option<String>,
+}
@@ -0,0 +1,211 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{Duration, NaiveDate, NaiveTime, Timelike};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bus::Bus;
+use super::feed::route_slug;
+use super::terminal::terminal_id;
+use super::zip::write_zip;
+use crate::config::GtfsConfig;
+
+// Schedules are published in Kenyan local time
+pub const GTFS_TIMEZONE: &str = "Africa/Nairobi";
+// GTFS route_type for buses
+const ROUTE_TYPE_BUS: u8 = 3;
+
+// Where a terminal is; GTFS requires coordinates for every stop and the fleet carries none
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GtfsStop {
+    // terminal_id of the city
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GtfsStopRequest {
+    // Defaults to the city name as the fleet spells it
+    #[serde(default)]
+    pub name: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GtfsStopRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.lat) || !(-180.0..=180.0).contains(&self.lon) {
+            return Err("lat must be within ±90 and lon within ±180".to_string());
+        }
+        Ok(())
+    }
+}
+
+// A terminal the fleet serves, with its coordinates if they were entered
+#[derive(Serialize)]
+pub struct GtfsStopResponse {
+    pub id: String,
+    pub name: String,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+}
+
+// The last scheduled export, served to journey planners as-is
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GtfsFeed {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub data: bson::Binary,
+    pub trips: i64,
+    // Terminals left out for lack of coordinates, along with every trip serving them
+    pub missing_stops: Vec<String>,
+    pub generated_at: bson::DateTime,
+}
+
+pub struct GtfsExport {
+    pub files: Vec<(&'static str, String)>,
+    pub trips: usize,
+    pub missing_stops: Vec<String>,
+}
+
+impl GtfsExport {
+    pub fn to_zip(&self, generated_at: chrono::NaiveDateTime) -> Vec<u8> {
+        let files: Vec<(&str, &[u8])> = self.files.iter().map(|(name, body)| (*name, body.as_bytes())).collect();
+        write_zip(&files, generated_at)
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(header: &str, rows: &[Vec<String>]) -> String {
+    let mut out = format!("{}\n", header);
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// GTFS time of day; trips arriving after midnight count past 24:00:00 from the service day.
+pub fn gtfs_time(time: NaiveTime, next_day: bool) -> String {
+    let hours = time.hour() + if next_day { 24 } else { 0 };
+    format!("{:02}:{:02}:{:02}", hours, time.minute(), time.second())
+}
+
+fn gtfs_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// The fleet as a GTFS feed. Every bus is a trip running daily from `start` for the configured
+/// number of days, less its cancelled dates (`cancellations` holds bus ids and `YYYY-MM-DD`
+/// dates). Buses whose terminals have no coordinates in `stops` are left out.
+pub fn build_gtfs(buses: &[Bus], stops: &HashMap<String, GtfsStop>, cancellations: &[(ObjectId, String)], start: NaiveDate, config: &GtfsConfig) -> GtfsExport {
+    let end = start + Duration::days(config.service_days - 1);
+    let mut agencies = BTreeMap::new();
+    let mut routes = BTreeMap::new();
+    let mut used_stops = BTreeSet::new();
+    let mut missing_stops = BTreeSet::new();
+    let mut trips = Vec::new();
+    let mut stop_times = Vec::new();
+    let mut calendar = Vec::new();
+
+    for bus in buses {
+        let Some(bus_id) = bus.id else {
+            continue;
+        };
+        let (from, to) = (terminal_id(&bus.route.from), terminal_id(&bus.route.to));
+        let missing: Vec<String> = [&from, &to].into_iter().filter(|s| !stops.contains_key(*s)).cloned().collect();
+        if !missing.is_empty() {
+            missing_stops.extend(missing);
+            continue;
+        }
+        let departure = NaiveTime::parse_from_str(&bus.route.departure_time, "%I:%M %p");
+        let arrival = NaiveTime::parse_from_str(&bus.route.arrival_time, "%I:%M %p");
+        let (Ok(departure), Ok(arrival)) = (departure, arrival) else {
+            continue;
+        };
+
+        let operator = bus.operator_name();
+        let agency_id = terminal_id(&operator);
+        agencies.entry(agency_id.clone()).or_insert(operator);
+        let route_id = format!("{}:{}", agency_id, route_slug(&bus.route.from, &bus.route.to));
+        routes.entry(route_id.clone()).or_insert_with(|| vec![
+            route_id.clone(),
+            agency_id,
+            String::new(),
+            format!("{} - {}", bus.route.from, bus.route.to),
+            ROUTE_TYPE_BUS.to_string(),
+        ]);
+        used_stops.insert(from.clone());
+        used_stops.insert(to.clone());
+
+        // Each bus has its own service so its cancellations can be listed against it
+        let trip_id = bus_id.to_hex();
+        trips.push(vec![route_id, trip_id.clone(), trip_id.clone(), bus.route.to.clone()]);
+        let departure_time = gtfs_time(departure, false);
+        let arrival_time = gtfs_time(arrival, arrival <= departure);
+        stop_times.push(vec![trip_id.clone(), departure_time.clone(), departure_time, from, "1".to_string()]);
+        stop_times.push(vec![trip_id.clone(), arrival_time.clone(), arrival_time, to, "2".to_string()]);
+        let mut row = vec![trip_id];
+        row.extend(vec!["1".to_string(); 7]);
+        row.extend([gtfs_date(start), gtfs_date(end)]);
+        calendar.push(row);
+    }
+
+    let exported: BTreeSet<String> = trips.iter().map(|t| t[2].clone()).collect();
+    let mut calendar_dates: Vec<Vec<String>> = cancellations.iter()
+        .filter_map(|(bus_id, date)| {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            let service_id = bus_id.to_hex();
+            (exported.contains(&service_id) && date >= start && date <= end)
+                .then(|| vec![service_id, gtfs_date(date), "2".to_string()])
+        })
+        .collect();
+    calendar_dates.sort();
+    calendar_dates.dedup();
+
+    let agency_rows: Vec<Vec<String>> = agencies.into_iter()
+        .map(|(id, name)| vec![id, name, config.publisher_url.clone(), GTFS_TIMEZONE.to_string(), "en".to_string()])
+        .collect();
+    let stop_rows: Vec<Vec<String>> = used_stops.iter()
+        .map(|id| {
+            let stop = &stops[id];
+            vec![id.clone(), stop.name.clone(), format!("{:.6}", stop.lat), format!("{:.6}", stop.lon)]
+        })
+        .collect();
+    let feed_info = vec![vec![
+        config.publisher_name.clone(),
+        config.publisher_url.clone(),
+        "en".to_string(),
+        gtfs_date(start),
+        gtfs_date(end),
+        gtfs_date(start),
+    ]];
+
+    GtfsExport {
+        trips: trips.len(),
+        files: vec![
+            ("agency.txt", csv("agency_id,agency_name,agency_url,agency_timezone,agency_lang", &agency_rows)),
+            ("stops.txt", csv("stop_id,stop_name,stop_lat,stop_lon", &stop_rows)),
+            ("routes.txt", csv("route_id,agency_id,route_short_name,route_long_name,route_type", &routes.into_values().collect::<Vec<_>>())),
+            ("trips.txt", csv("route_id,service_id,trip_id,trip_headsign", &trips)),
+            ("stop_times.txt", csv("trip_id,arrival_time,departure_time,stop_id,stop_sequence", &stop_times)),
+            ("calendar.txt", csv("service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date", &calendar)),
+            ("calendar_dates.txt", csv("service_id,date,exception_type", &calendar_dates)),
+            ("feed_info.txt", csv("feed_publisher_name,feed_publisher_url,feed_lang,feed_start_date,feed_end_date,feed_version", &feed_info)),
+        ],
+        missing_stops: missing_stops.into_iter().collect(),
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveTime;
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use super::bulk::BulkInsertError;
+use super::bus::{Bus, Route};
+use super::gtfs::GtfsStop;
+use super::terminal::terminal_id;
+
+const REQUIRED_FILES: [&str; 5] = ["agency.txt", "stops.txt", "routes.txt", "trips.txt", "stop_times.txt"];
+// Feeds don't say how big the vehicle is; a standard Kenyan intercity coach
+const DEFAULT_SEATS: i32 = 44;
+
+#[derive(Deserialize, IntoParams)]
+pub struct GtfsImportQuery {
+    // Validate and preview without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    // Fare for routes the feed has no fare for
+    pub price: Option<f64>,
+    pub seats: Option<i32>,
+    pub bus_type: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct GtfsIssue {
+    pub file: String,
+    // 1-based data row, not counting the header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<usize>,
+    pub message: String,
+}
+
+impl GtfsIssue {
+    fn new(file: &str, row: Option<usize>, message: impl Into<String>) -> Self {
+        Self { file: file.to_string(), row, message: message.into() }
+    }
+}
+
+// A schedule the import creates, in our terms
+#[derive(Serialize)]
+pub struct GtfsImportedBus {
+    pub trip_id: String,
+    pub bus_number: String,
+    pub from: String,
+    pub to: String,
+    pub departure_time: String,
+    pub arrival_time: String,
+    pub price: f64,
+}
+
+#[derive(Serialize, Default)]
+pub struct GtfsImportReport {
+    pub dry_run: bool,
+    pub agencies: Vec<String>,
+    pub routes: usize,
+    pub trips: usize,
+    pub buses: Vec<GtfsImportedBus>,
+    // Trips left out, each with the reason
+    pub skipped: Vec<GtfsIssue>,
+    // Feed details our model can't hold, imported as close as it allows
+    pub warnings: Vec<GtfsIssue>,
+    pub stops_added: usize,
+    pub inserted: usize,
+    pub failed: Vec<BulkInsertError>,
+}
+
+pub struct GtfsImportPlan {
+    pub buses: Vec<Bus>,
+    pub stops: Vec<GtfsStop>,
+    pub report: GtfsImportReport,
+}
+
+type Rows = Vec<HashMap<String, String>>;
+
+/// Parses a GTFS table into rows keyed by column name. Handles quoted fields, a leading BOM and
+/// CRLF line endings.
+pub fn parse_csv(text: &str) -> Rows {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    let mut records = records.into_iter().filter(|r| r.iter().any(|f| !f.trim().is_empty()));
+    let Some(header) = records.next() else {
+        return Vec::new();
+    };
+    let header: Vec<String> = header.into_iter().map(|h| h.trim().to_string()).collect();
+    records
+        .map(|r| header.iter().cloned().zip(r.into_iter().map(|f| f.trim().to_string())).collect())
+        .collect()
+}
+
+fn field<'a>(row: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    row.get(name).map(String::as_str).filter(|v| !v.is_empty())
+}
+
+/// A GTFS `HH:MM:SS` time as our schedules write it. Times past midnight of the service day
+/// (`25:10:00`) wrap around.
+pub fn schedule_time(gtfs: &str) -> Option<String> {
+    let mut parts = gtfs.split(':').map(|p| p.parse::<u32>().ok());
+    let (Some(Some(hours)), Some(Some(minutes))) = (parts.next(), parts.next()) else {
+        return None;
+    };
+    let time = NaiveTime::from_hms_opt(hours % 24, minutes, 0)?;
+    Some(time.format("%I:%M %p").to_string())
+}
+
+struct StopTime {
+    sequence: u32,
+    stop_id: String,
+    arrival: Option<String>,
+    departure: Option<String>,
+}
+
+/// Maps a feed's files (name to contents) onto our model: each trip becomes a bus running its
+/// first-to-last stop daily, under its agency as operator. Problems with single trips skip
+/// them and are reported; a feed missing a required table is refused.
+pub fn plan_gtfs_import(files: &HashMap<String, String>, query: &GtfsImportQuery) -> Result<GtfsImportPlan, String> {
+    let missing: Vec<&str> = REQUIRED_FILES.iter().copied().filter(|f| !files.contains_key(*f)).collect();
+    if !missing.is_empty() {
+        return Err(format!("Not a GTFS feed, missing: {}", missing.join(", ")));
+    }
+    let table = |name: &str| files.get(name).map(|t| parse_csv(t)).unwrap_or_default();
+    let mut report = GtfsImportReport { dry_run: query.dry_run, ..Default::default() };
+
+    let agencies = table("agency.txt");
+    let agency_names: HashMap<String, String> = agencies.iter()
+        .filter_map(|a| Some((field(a, "agency_id").unwrap_or_default().to_string(), field(a, "agency_name")?.to_string())))
+        .collect();
+    if agency_names.is_empty() {
+        return Err("agency.txt lists no agency".to_string());
+    }
+    // agency_id may be left out when the feed has a single agency
+    let sole_agency = (agency_names.len() == 1).then(|| agency_names.values().next().cloned()).flatten();
+    report.agencies = agency_names.values().cloned().collect();
+    report.agencies.sort();
+
+    let stops: HashMap<String, HashMap<String, String>> = table("stops.txt").into_iter()
+        .filter_map(|s| Some((field(&s, "stop_id")?.to_string(), s)))
+        .collect();
+
+    let fares: HashMap<String, f64> = table("fare_attributes.txt").iter()
+        .filter_map(|f| Some((field(f, "fare_id")?.to_string(), field(f, "price")?.parse().ok()?)))
+        .collect();
+    let route_fares: HashMap<String, f64> = table("fare_rules.txt").iter()
+        .filter_map(|r| Some((field(r, "route_id")?.to_string(), *fares.get(field(r, "fare_id")?)?)))
+        .collect();
+
+    let routes: HashMap<String, HashMap<String, String>> = table("routes.txt").into_iter()
+        .filter_map(|r| Some((field(&r, "route_id")?.to_string(), r)))
+        .collect();
+    report.routes = routes.len();
+
+    let partial_services: Vec<String> = table("calendar.txt").iter()
+        .filter(|c| ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"].iter().any(|d| field(c, d) != Some("1")))
+        .filter_map(|c| field(c, "service_id").map(str::to_string))
+        .collect();
+
+    let mut stop_times: HashMap<String, Vec<StopTime>> = HashMap::new();
+    for (index, row) in table("stop_times.txt").iter().enumerate() {
+        let (Some(trip_id), Some(stop_id), Some(sequence)) = (field(row, "trip_id"), field(row, "stop_id"), field(row, "stop_sequence").and_then(|s| s.parse().ok())) else {
+            report.warnings.push(GtfsIssue::new("stop_times.txt", Some(index + 1), "Row needs trip_id, stop_id and stop_sequence; ignored"));
+            continue;
+        };
+        stop_times.entry(trip_id.to_string()).or_default().push(StopTime {
+            sequence,
+            stop_id: stop_id.to_string(),
+            arrival: field(row, "arrival_time").map(str::to_string),
+            departure: field(row, "departure_time").map(str::to_string),
+        });
+    }
+
+    let trips = table("trips.txt");
+    report.trips = trips.len();
+    let mut buses = Vec::new();
+    let mut used_stops = BTreeMap::new();
+    let (mut multi_stop, mut part_week) = (0, 0);
+    for (index, trip) in trips.iter().enumerate() {
+        let row = Some(index + 1);
+        let skip = |message: String| GtfsIssue::new("trips.txt", row, message);
+        let Some(trip_id) = field(trip, "trip_id") else {
+            report.skipped.push(skip("Trip has no trip_id".to_string()));
+            continue;
+        };
+        let Some(route) = field(trip, "route_id").and_then(|id| routes.get(id)) else {
+            report.skipped.push(skip(format!("Trip {} refers to an unknown route", trip_id)));
+            continue;
+        };
+        let route_id = field(route, "route_id").unwrap_or_default();
+        let agency = match field(route, "agency_id") {
+            Some(id) => agency_names.get(id).cloned(),
+            None => sole_agency.clone(),
+        };
+        let Some(agency) = agency else {
+            report.skipped.push(skip(format!("Route {} has no known agency", route_id)));
+            continue;
+        };
+
+        let mut times = stop_times.remove(trip_id).unwrap_or_default();
+        times.sort_by_key(|t| t.sequence);
+        let (Some(first), Some(last)) = (times.first(), times.last()) else {
+            report.skipped.push(skip(format!("Trip {} has no stop times", trip_id)));
+            continue;
+        };
+        if times.len() < 2 {
+            report.skipped.push(skip(format!("Trip {} serves a single stop", trip_id)));
+            continue;
+        }
+        let departure = first.departure.as_deref().or(first.arrival.as_deref()).and_then(schedule_time);
+        let arrival = last.arrival.as_deref().or(last.departure.as_deref()).and_then(schedule_time);
+        let (Some(departure), Some(arrival)) = (departure, arrival) else {
+            report.skipped.push(skip(format!("Trip {} has no usable first departure or last arrival time", trip_id)));
+            continue;
+        };
+        let from = stops.get(&first.stop_id).and_then(|s| field(s, "stop_name"));
+        let to = stops.get(&last.stop_id).and_then(|s| field(s, "stop_name"));
+        let (Some(from), Some(to)) = (from, to) else {
+            report.skipped.push(skip(format!("Trip {} starts or ends at a stop missing from stops.txt", trip_id)));
+            continue;
+        };
+        let Some(price) = route_fares.get(route_id).copied().or(query.price) else {
+            report.skipped.push(skip(format!("No fare for route {}; pass a price to use for it", route_id)));
+            continue;
+        };
+
+        if times.len() > 2 {
+            multi_stop += 1;
+        }
+        if field(trip, "service_id").is_some_and(|s| partial_services.iter().any(|p| p == s)) {
+            part_week += 1;
+        }
+        for stop_id in [&first.stop_id, &last.stop_id] {
+            used_stops.entry(stop_id.clone()).or_insert_with(|| stops[stop_id].clone());
+        }
+
+        let bus_number = format!("{} - {}", agency, field(trip, "trip_short_name").unwrap_or(trip_id));
+        report.buses.push(GtfsImportedBus {
+            trip_id: trip_id.to_string(),
+            bus_number: bus_number.clone(),
+            from: from.to_string(),
+            to: to.to_string(),
+            departure_time: departure.clone(),
+            arrival_time: arrival.clone(),
+            price,
+        });
+        buses.push(Bus {
+            id: None,
+            bus_number,
+            bus_type: query.bus_type.clone().unwrap_or_else(|| "Standard".to_string()),
+            total_seats: query.seats.unwrap_or(DEFAULT_SEATS),
+            route: Route { from: from.to_string(), to: to.to_string(), departure_time: departure, arrival_time: arrival, price },
+            policy_id: None,
+            layout: None,
+        });
+    }
+    if multi_stop > 0 {
+        report.warnings.push(GtfsIssue::new("stop_times.txt", None, format!("{} trips call at intermediate stops; only their first and last stops were imported", multi_stop)));
+    }
+    if part_week > 0 {
+        report.warnings.push(GtfsIssue::new("calendar.txt", None, format!("{} trips don't run every day; they were imported as daily schedules", part_week)));
+    }
+
+    // Coordinates come along so the terminals can be published in our own feed
+    let stops = used_stops.into_values()
+        .filter_map(|s| Some(GtfsStop {
+            id: terminal_id(field(&s, "stop_name")?),
+            name: field(&s, "stop_name")?.to_string(),
+            lat: field(&s, "stop_lat")?.parse().ok()?,
+            lon: field(&s, "stop_lon")?.parse().ok()?,
+            updated_at: bson::DateTime::now(),
+        }))
+        .collect();
+    Ok(GtfsImportPlan { buses, stops, report })
+}
@@ -0,0 +1,128 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::booking::{CreateGroupBookingRequest, Passenger, MAX_SEATS_PER_BOOKING};
+
+pub const HOLD_MINUTES: i64 = 10;
+
+// A seat kept aside while someone finishes checking out; owned by a user or an anonymous session
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SeatHold {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub user_id: Option<ObjectId>,
+    pub anon_id: Option<String>,
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+}
+
+pub enum HoldOwner {
+    User(ObjectId),
+    Anonymous(String),
+}
+
+// One seat by `seat_number`, or several on the same trip by `seat_numbers`; all of them are
+// held or none are
+#[derive(Deserialize, ToSchema)]
+pub struct CreateHoldRequest {
+    pub bus_id: String,
+    pub travel_date: String,
+    #[serde(default)]
+    pub seat_number: Option<String>,
+    #[serde(default)]
+    pub seat_numbers: Vec<String>,
+}
+
+impl CreateHoldRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.seat_number.is_some() && !self.seat_numbers.is_empty() {
+            return Err("Give seat_number or seat_numbers, not both".to_string());
+        }
+        let seats = self.seats();
+        if seats.is_empty() || seats.len() > MAX_SEATS_PER_BOOKING {
+            return Err(format!("Between 1 and {} seats can be held at once", MAX_SEATS_PER_BOOKING));
+        }
+        let mut seen = std::collections::HashSet::new();
+        if let Some(seat) = seats.iter().find(|s| !seen.insert(s.as_str())) {
+            return Err(format!("Seat {} is listed more than once", seat));
+        }
+        Ok(())
+    }
+
+    /// The seats asked for, in the order given.
+    pub fn seats(&self) -> Vec<String> {
+        match &self.seat_number {
+            Some(seat) => vec![seat.clone()],
+            None => self.seat_numbers.clone(),
+        }
+    }
+}
+
+// Second step of checkout: turns the caller's live holds into bookings
+#[derive(Deserialize, ToSchema)]
+pub struct ConfirmHoldsRequest {
+    pub hold_ids: Vec<String>,
+    // One per hold, in the same order as `hold_ids`
+    #[serde(default)]
+    pub passengers: Vec<Passenger>,
+    #[serde(default)]
+    pub accepted_terms_version: Option<String>,
+    #[serde(default)]
+    pub payer_phone: Option<String>,
+    #[serde(default)]
+    pub pass_id: Option<String>,
+}
+
+impl ConfirmHoldsRequest {
+    /// The booking the holds stand for. `holds` must be the ones named in `hold_ids`, in any order.
+    pub fn booking_request(&self, holds: &[SeatHold]) -> Result<CreateGroupBookingRequest, String> {
+        let mut seat_numbers = Vec::with_capacity(self.hold_ids.len());
+        for hold_id in &self.hold_ids {
+            let hold = holds.iter()
+                .find(|h| h.id.is_some_and(|id| id.to_hex() == *hold_id))
+                .ok_or_else(|| format!("Hold {} has expired or isn't yours", hold_id))?;
+            seat_numbers.push(hold.seat_number.clone());
+        }
+        let first = holds.first().ok_or("Give at least one hold to confirm")?;
+        if holds.iter().any(|h| h.bus_id != first.bus_id || h.travel_date != first.travel_date) {
+            return Err("Held seats must all be on the same trip".to_string());
+        }
+        let req = CreateGroupBookingRequest {
+            bus_id: first.bus_id.to_hex(),
+            seat_numbers,
+            travel_date: first.travel_date.clone(),
+            passengers: self.passengers.clone(),
+            accepted_terms_version: self.accepted_terms_version.clone(),
+            payer_phone: self.payer_phone.clone(),
+            headcount: None,
+            pass_id: self.pass_id.clone(),
+        };
+        req.validate()?;
+        Ok(req)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HoldResponse {
+    pub id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub expires_at: String,
+}
+
+impl From<SeatHold> for HoldResponse {
+    fn from(h: SeatHold) -> Self {
+        Self {
+            id: h.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            bus_id: h.bus_id.to_hex(),
+            travel_date: h.travel_date,
+            seat_number: h.seat_number,
+            expires_at: h.expires_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
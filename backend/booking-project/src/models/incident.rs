@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const INCIDENT_KINDS: [&str; 5] = ["breakdown", "accident", "security", "medical", "other"];
+pub const INCIDENT_SEVERITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+
+/// Severities that page the admins as soon as they're logged.
+pub fn is_high_severity(severity: &str) -> bool {
+    matches!(severity, "high" | "critical")
+}
+
+// Photos/documents live in media storage; incidents only keep the reference
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct IncidentAttachment {
+    pub url: String,
+    pub content_type: Option<String>,
+    pub caption: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Incident {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub operator: String,
+    pub travel_date: String,
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub location: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<IncidentAttachment>,
+    pub reported_by: String,
+    pub status: String, // Open, Resolved
+    pub resolution: Option<String>,
+    pub occurred_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReportIncidentRequest {
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub location: Option<String>,
+    // RFC 3339; defaults to now
+    pub occurred_at: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<IncidentAttachment>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResolveIncidentRequest {
+    pub resolution: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct IncidentQuery {
+    pub operator: Option<String>,
+    pub severity: Option<String>,
+    pub status: Option<String>,
+    pub days: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IncidentResponse {
+    pub id: String,
+    pub bus_id: String,
+    pub operator: String,
+    pub travel_date: String,
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub location: Option<String>,
+    pub attachments: Vec<IncidentAttachment>,
+    pub reported_by: String,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub occurred_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Incident> for IncidentResponse {
+    fn from(i: Incident) -> Self {
+        Self {
+            id: i.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            bus_id: i.bus_id.to_hex(),
+            operator: i.operator,
+            travel_date: i.travel_date,
+            kind: i.kind,
+            severity: i.severity,
+            description: i.description,
+            location: i.location,
+            attachments: i.attachments,
+            reported_by: i.reported_by,
+            status: i.status,
+            resolution: i.resolution,
+            occurred_at: i.occurred_at.try_to_rfc3339_string().unwrap_or_default(),
+            created_at: i.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            updated_at: i.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct OperatorIncidentStats {
+    pub operator: String,
+    pub total: i64,
+    pub open: i64,
+    pub by_severity: BTreeMap<String, i64>,
+    pub by_kind: BTreeMap<String, i64>,
+}
+
+#[derive(Serialize)]
+pub struct IncidentReport {
+    pub days: i64,
+    pub operators: Vec<OperatorIncidentStats>,
+}
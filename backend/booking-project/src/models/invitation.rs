@@ -0,0 +1,69 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Roles an operator admin may hand out; platform admins can also invite operator admins
+pub const INVITABLE_ROLES: [&str; 4] = ["agent", "conductor", "finance", "operator"];
+pub const INVITATION_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Invitation {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub email: String,
+    pub role: String,
+    pub operator: Option<String>,
+    pub invited_by: ObjectId,
+    pub status: String, // pending, accepted, revoked
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub accepted_at: Option<bson::DateTime>,
+    pub user_id: Option<ObjectId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvitationClaims {
+    pub invitation_id: String,
+    pub scope: String,
+    pub exp: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    pub role: String,
+    pub operator: Option<String>, // ignored for operator admins, who always invite into their own operator
+}
+
+// Username and password are only needed when the invited email has no account yet
+#[derive(Deserialize, ToSchema)]
+pub struct AcceptInvitationRequest {
+    pub token: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InvitationResponse {
+    pub id: String,
+    pub email: String,
+    pub role: String,
+    pub operator: Option<String>,
+    pub status: String,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(i: Invitation) -> Self {
+        Self {
+            id: i.id.map(|id| id.to_hex()).unwrap_or_default(),
+            email: i.email,
+            role: i.role,
+            operator: i.operator,
+            status: i.status,
+            expires_at: i.expires_at.try_to_rfc3339_string().unwrap_or_default(),
+            created_at: i.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
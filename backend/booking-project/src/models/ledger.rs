@@ -0,0 +1,165 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const PLATFORM_COMMISSION_ACCOUNT: &str = "platform:commission";
+pub const PLATFORM_BANK_ACCOUNT: &str = "platform:bank";
+
+// Account names are "<kind>:<key>"; tax accounts are "tax:<code>"
+pub const ACCOUNT_KINDS: [&str; 4] = ["customer", "operator", "platform", "tax"];
+
+pub fn customer_account(user_id: &ObjectId) -> String {
+    format!("customer:{}", user_id.to_hex())
+}
+
+pub fn operator_account(operator: &str) -> String {
+    format!("operator:{}", operator)
+}
+
+// Amounts are compared in whole cents so float noise can't unbalance a transaction
+pub fn to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct LedgerEntry {
+    pub account: String,
+    pub debit: f64,
+    pub credit: f64,
+}
+
+impl LedgerEntry {
+    pub fn debit(account: &str, amount: f64) -> Self {
+        Self { account: account.to_string(), debit: amount, credit: 0.0 }
+    }
+
+    pub fn credit(account: &str, amount: f64) -> Self {
+        Self { account: account.to_string(), debit: 0.0, credit: amount }
+    }
+}
+
+// One monetary event; its entries always balance
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LedgerTransaction {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub kind: String, // booking, booking_reversal, refund, amendment, settlement_payout, ...
+    pub reference: String,
+    pub entries: Vec<LedgerEntry>,
+    pub created_at: bson::DateTime,
+}
+
+impl LedgerTransaction {
+    /// Builds a transaction, enforcing the double-entry invariants.
+    pub fn new(kind: &str, reference: &str, entries: Vec<LedgerEntry>) -> Result<Self, String> {
+        // Zero lines carry no information (e.g. a route with no commission)
+        let entries: Vec<LedgerEntry> = entries
+            .into_iter()
+            .filter(|e| to_cents(e.debit) != 0 || to_cents(e.credit) != 0)
+            .collect();
+        if entries.len() < 2 {
+            return Err("A ledger transaction needs at least two entries".to_string());
+        }
+        for entry in &entries {
+            let kind = entry.account.split(':').next().unwrap_or_default();
+            if !ACCOUNT_KINDS.contains(&kind) || !entry.account.contains(':') {
+                return Err(format!("Unknown ledger account: {}", entry.account));
+            }
+            if entry.debit < 0.0 || entry.credit < 0.0 {
+                return Err(format!("Negative amount on {}", entry.account));
+            }
+            if to_cents(entry.debit) != 0 && to_cents(entry.credit) != 0 {
+                return Err(format!("Entry on {} is both a debit and a credit", entry.account));
+            }
+        }
+        let debits: i64 = entries.iter().map(|e| to_cents(e.debit)).sum();
+        let credits: i64 = entries.iter().map(|e| to_cents(e.credit)).sum();
+        if debits != credits {
+            return Err(format!("Unbalanced transaction: debits {} != credits {} (cents)", debits, credits));
+        }
+
+        Ok(Self {
+            id: None,
+            kind: kind.to_string(),
+            reference: reference.to_string(),
+            entries,
+            created_at: bson::DateTime::now(),
+        })
+    }
+
+    /// What the transaction moved: the sum of its credits.
+    pub fn total(&self) -> f64 {
+        self.entries.iter().map(|e| to_cents(e.credit)).sum::<i64>() as f64 / 100.0
+    }
+
+    /// Credits `amount` to the accounts this transaction credits, in the same proportions, so a
+    /// fee kept on a sale is shared out the way the sale was. Rounding lands on the last account.
+    pub fn apportion_credits(&self, amount: f64) -> Vec<LedgerEntry> {
+        let credited: Vec<&LedgerEntry> = self.entries.iter().filter(|e| to_cents(e.credit) > 0).collect();
+        let total: i64 = credited.iter().map(|e| to_cents(e.credit)).sum();
+        let mut remaining = to_cents(amount);
+        let mut entries = Vec::new();
+        for (i, entry) in credited.iter().enumerate() {
+            let share = if i + 1 == credited.len() {
+                remaining
+            } else {
+                to_cents(amount) * to_cents(entry.credit) / total
+            };
+            remaining -= share;
+            entries.push(LedgerEntry::credit(&entry.account, share as f64 / 100.0));
+        }
+        entries
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LedgerQuery {
+    pub account: Option<String>,
+    pub kind: Option<String>,
+    pub reference: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LedgerTransactionResponse {
+    pub id: String,
+    pub kind: String,
+    pub reference: String,
+    pub entries: Vec<LedgerEntry>,
+    pub created_at: String,
+}
+
+impl From<LedgerTransaction> for LedgerTransactionResponse {
+    fn from(t: LedgerTransaction) -> Self {
+        Self {
+            id: t.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            kind: t.kind,
+            reference: t.reference,
+            entries: t.entries,
+            created_at: t.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LedgerBalanceQuery {
+    pub kind: Option<String>,
+}
+
+// Balance is debits minus credits
+#[derive(Serialize)]
+pub struct AccountBalance {
+    pub account: String,
+    pub debits: f64,
+    pub credits: f64,
+    pub balance: f64,
+}
+
+#[derive(Serialize)]
+pub struct LedgerCheck {
+    pub balanced: bool,
+    pub transactions: u64,
+    pub total_debits: f64,
+    pub total_credits: f64,
+    pub unbalanced_transactions: Vec<String>,
+}
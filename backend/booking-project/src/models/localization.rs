@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const SUPPORTED_LANGUAGES: [&str; 2] = ["en", "sw"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Localization {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub kind: String, // "location" or "bus_type"
+    pub key: String,  // canonical name as stored on buses
+    pub names: HashMap<String, String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpsertLocalizationRequest {
+    pub kind: String,
+    pub key: String,
+    pub names: HashMap<String, String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LocalizationQuery {
+    pub kind: Option<String>,
+    pub lang: Option<String>,
+}
+
+// `?lang=` on generated documents, taking precedence over Accept-Language
+#[derive(Deserialize, IntoParams)]
+pub struct DocumentQuery {
+    pub lang: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct DisplayNames {
+    pub language: String,
+    pub from: String,
+    pub to: String,
+    pub bus_type: String,
+}
+
+/// Resolves canonical names to display names for one language.
+pub struct Localizer {
+    pub language: String,
+    names: HashMap<(String, String), String>,
+}
+
+impl Localizer {
+    pub fn new(language: &str, localizations: Vec<Localization>) -> Self {
+        let names = localizations
+            .into_iter()
+            .filter_map(|l| {
+                let name = l.names.get(language).or_else(|| l.names.get(DEFAULT_LANGUAGE))?.clone();
+                Some(((l.kind, l.key), name))
+            })
+            .collect();
+        Self { language: language.to_string(), names }
+    }
+
+    pub fn name(&self, kind: &str, key: &str) -> String {
+        self.names
+            .get(&(kind.to_string(), key.to_string()))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// Picks the best supported language from an `Accept-Language` header value.
+pub fn preferred_language(accept_language: Option<&str>) -> &'static str {
+    accepted_language(accept_language).unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// The supported language an `Accept-Language` header asks for, if it names one.
+pub fn accepted_language(accept_language: Option<&str>) -> Option<&'static str> {
+    let header = accept_language?;
+
+    let mut candidates: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.trim().split(';');
+            let tag = pieces.next()?.trim();
+            let quality = pieces
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((tag, quality))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    candidates
+        .iter()
+        .filter(|(_, q)| *q > 0.0)
+        .find_map(|(tag, _)| {
+            let primary = tag.split('-').next().unwrap_or("").to_lowercase();
+            SUPPORTED_LANGUAGES.iter().find(|l| **l == primary).copied()
+        })
+}
+
+/// `language` if it is supported, in its canonical form.
+pub fn supported_language(language: &str) -> Option<&'static str> {
+    let language = language.trim().to_lowercase();
+    SUPPORTED_LANGUAGES.iter().find(|l| **l == language).copied()
+}
+
+/// Language for a generated document: the reader's own preference, then what their client
+/// asks for, then the operator's default.
+pub fn document_language(user: Option<&str>, requested: Option<&str>, operator: Option<&str>) -> &'static str {
+    [user, requested, operator]
+        .into_iter()
+        .flatten()
+        .find_map(supported_language)
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+// Fixed wording of tickets, receipts, manifests and SMS: key, English, Swahili
+const DOCUMENT_TEXT: [(&str, &str, &str); 39] = [
+    ("manifest.title", "PASSENGER MANIFEST", "ORODHA YA ABIRIA"),
+    ("manifest.vehicle", "Vehicle", "Gari"),
+    ("manifest.type", "Type", "Aina"),
+    ("manifest.capacity", "Capacity", "Viti"),
+    ("manifest.route", "Route", "Njia"),
+    ("manifest.to", "to", "hadi"),
+    ("manifest.date", "Date", "Tarehe"),
+    ("manifest.departure", "Departure", "Kuondoka"),
+    ("manifest.arrival", "Arrival", "Kuwasili"),
+    ("manifest.no_crew", "Crew: not assigned", "Wafanyakazi: hawajapangwa"),
+    ("manifest.passengers", "Passengers", "Abiria"),
+    ("manifest.seat", "Seat", "Kiti"),
+    ("manifest.passenger", "Passenger", "Abiria"),
+    ("manifest.gender", "Gender", "Jinsia"),
+    ("manifest.age", "Age", "Umri"),
+    ("manifest.id_number", "ID/Passport No.", "Kitambulisho"),
+    ("manifest.next_of_kin", "Next of kin", "Ndugu wa karibu"),
+    ("manifest.boarding_point", "Boarding point", "Kituo cha kupanda"),
+    ("manifest.boarded", "Boarded", "Amepanda"),
+    ("manifest.yes", "yes", "ndiyo"),
+    ("crew.driver", "Driver", "Dereva"),
+    ("crew.conductor", "Conductor", "Kondakta"),
+    ("pdf.page", "Page {page} of {pages}", "Ukurasa {page} kati ya {pages}"),
+    ("receipt.title", "BOOKING RECEIPT", "RISITI YA UHIFADHI"),
+    ("receipt.reference", "Booking reference", "Nambari ya uhifadhi"),
+    ("receipt.bus", "Bus", "Basi"),
+    ("receipt.fare", "Fare", "Nauli"),
+    ("receipt.status", "Status", "Hali"),
+    ("receipt.booked_on", "Booked on", "Imehifadhiwa"),
+    ("receipt.arrive_early", "Please be at the boarding point 30 minutes before departure.", "Tafadhali fika kituoni dakika 30 kabla ya kuondoka."),
+    ("ticket.title", "E-TICKET", "TIKETI"),
+    ("ticket.show_code", "Show this code to the conductor when you board.", "Mwonyeshe kondakta msimbo huu unapopanda."),
+    ("status.Confirmed", "Confirmed", "Imethibitishwa"),
+    ("status.PendingPayment", "Awaiting payment", "Inasubiri malipo"),
+    ("status.PendingReview", "Pending review", "Inasubiri ukaguzi"),
+    ("status.Cancelled", "Cancelled", "Imeghairiwa"),
+    (
+        "sms.booking_confirmed",
+        "Booking confirmed: {from} to {to} on {date} at {time}, seat {seat}, bus {bus}. Ref {reference}",
+        "Uhifadhi umethibitishwa: {from} hadi {to} tarehe {date} saa {time}, kiti {seat}, basi {bus}. Kumb. {reference}",
+    ),
+    (
+        "email.booking_confirmed.subject",
+        "Your ticket: {from} to {to} on {date}",
+        "Tiketi yako: {from} hadi {to} tarehe {date}",
+    ),
+    (
+        "email.booking_confirmed.body",
+        "Hello {passenger},\n\nYour booking is confirmed.\n\nBooking reference: {reference}\nRoute: {from} to {to}\nDate: {date}\nDeparture: {time}\nSeat: {seat}\nBus: {bus} ({bus_type})\nFare: {fare}\n\nPlease be at the boarding point 30 minutes before departure and show this reference to the conductor.",
+        "Habari {passenger},\n\nUhifadhi wako umethibitishwa.\n\nNambari ya uhifadhi: {reference}\nNjia: {from} hadi {to}\nTarehe: {date}\nKuondoka: {time}\nKiti: {seat}\nBasi: {bus} ({bus_type})\nNauli: {fare}\n\nTafadhali fika kituoni dakika 30 kabla ya kuondoka na umwonyeshe kondakta nambari hii.",
+    ),
+];
+
+/// Fixed document wording in `language`; unknown keys come back as the key itself.
+pub fn document_text(language: &str, key: &str) -> String {
+    DOCUMENT_TEXT
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, en, sw)| if language == "sw" { *sw } else { *en })
+        .unwrap_or(key)
+        .to_string()
+}
+
+/// Document wording with `{name}` placeholders filled in.
+pub fn document_text_with(language: &str, key: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(document_text(language, key), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
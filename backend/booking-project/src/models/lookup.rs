@@ -0,0 +1,161 @@
+use mongodb::bson;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bus::Bus;
+use super::Booking;
+use crate::config::LookupConfig;
+use crate::crypto::constant_time_eq;
+
+const REFERENCE_PREFIX: &str = "BB-";
+// No 0/O, 1/I/L: references get read out over the phone and copied off paper tickets
+const REFERENCE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+const REFERENCE_LENGTH: usize = 6;
+
+/// `length` random characters people can read out and copy without mixing them up.
+pub fn readable_code(length: usize) -> Result<String, String> {
+    let rng = SystemRandom::new();
+    // Only bytes below a multiple of the alphabet size, so every character is equally likely
+    let limit = 256 - 256 % REFERENCE_ALPHABET.len();
+    let mut code = String::with_capacity(length);
+    let mut bytes = [0u8; 16];
+    while code.len() < length {
+        rng.fill(&mut bytes).map_err(|_| "Failed to generate a random code")?;
+        for byte in bytes.iter().map(|b| *b as usize).filter(|b| *b < limit) {
+            if code.len() < length {
+                code.push(REFERENCE_ALPHABET[byte % REFERENCE_ALPHABET.len()] as char);
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Whether every character of `code` could have come from `readable_code`.
+pub fn is_readable_code(code: &str) -> bool {
+    code.bytes().all(|b| REFERENCE_ALPHABET.contains(&b))
+}
+
+/// A fresh random booking reference like BB-7K2F9Q.
+pub fn new_booking_reference() -> Result<String, String> {
+    Ok(format!("{}{}", REFERENCE_PREFIX, readable_code(REFERENCE_LENGTH)?))
+}
+
+/// A booking reference as typed, in its stored form: any case, with or without the prefix,
+/// spaces or dashes. None when it can't be one.
+pub fn normalize_booking_reference(code: &str) -> Option<String> {
+    let compact: String = code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    let body = match compact.len() {
+        REFERENCE_LENGTH => compact.as_str(),
+        _ => compact.strip_prefix(REFERENCE_PREFIX.trim_end_matches('-'))?,
+    };
+    let valid = body.len() == REFERENCE_LENGTH && is_readable_code(body);
+    valid.then(|| format!("{}{}", REFERENCE_PREFIX, body))
+}
+
+/// What failed lookups of a reference are counted under. Every way of typing one short code is
+/// the same reference, so format variants can't each get their own allowance of guesses.
+pub fn lookup_reference_key(reference: &str) -> String {
+    normalize_booking_reference(reference).unwrap_or_else(|| reference.trim().to_lowercase())
+}
+
+// Error code clients use to show a CAPTCHA instead of a generic error
+pub const CAPTCHA_REQUIRED: &str = "captcha_required";
+
+// Find a booking without signing in, e.g. from the confirmation email on another device
+#[derive(Deserialize, ToSchema)]
+pub struct GuestLookupRequest {
+    pub reference: String,
+    pub email: String,
+    #[serde(default)]
+    pub captcha_token: Option<String>,
+}
+
+impl GuestLookupRequest {
+    pub fn reference(&self) -> String {
+        lookup_reference_key(&self.reference)
+    }
+}
+
+/// Whether `given` is the account email, ignoring case and surrounding space.
+pub fn email_matches(given: &str, on_file: &str) -> bool {
+    constant_time_eq(given.trim().to_lowercase().as_bytes(), on_file.trim().to_lowercase().as_bytes())
+}
+
+// One failed lookup; expires with the throttling window
+#[derive(Serialize, Deserialize)]
+pub struct LookupFailure {
+    pub ip: String,
+    pub reference: String,
+    pub at: bson::DateTime,
+    pub expires_at: bson::DateTime,
+}
+
+/// Failed lookups in the current window from this caller and against this reference.
+pub struct LookupFailures {
+    pub by_ip: u64,
+    pub by_reference: u64,
+}
+
+impl LookupFailures {
+    pub fn throttled(&self, config: &LookupConfig) -> bool {
+        self.by_ip >= config.max_failures_per_ip || self.by_reference >= config.max_failures_per_reference
+    }
+
+    pub fn needs_captcha(&self, config: &LookupConfig) -> bool {
+        config.captcha_secret.is_some() && self.by_ip.max(self.by_reference) >= config.captcha_after
+    }
+}
+
+/// A passenger's name as boarding staff may see it without signing in: initials, then the
+/// last name, e.g. "J. W. Kamau".
+pub fn masked_name(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let Some((last, first)) = words.split_last() else {
+        return String::new();
+    };
+    first.iter()
+        .filter_map(|w| w.chars().next())
+        .map(|c| format!("{}. ", c.to_uppercase()))
+        .chain(std::iter::once(last.to_string()))
+        .collect()
+}
+
+// What anyone holding a booking reference may see: enough to board, nothing to reach the booker by
+#[derive(Serialize, ToSchema)]
+pub struct BookingReferenceResponse {
+    pub reference: String,
+    pub status: String,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub boarding_point: String,
+    pub travel_date: String,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub seat_number: String,
+    pub passenger: Option<String>,
+}
+
+impl BookingReferenceResponse {
+    pub fn new(booking: Booking, bus: Bus) -> Self {
+        Self {
+            reference: booking.reference.unwrap_or_default(),
+            status: booking.status.to_lowercase(),
+            departure: bus.route.departure_at(&booking.travel_date).map(|d| d.to_rfc3339()),
+            arrival: bus.route.arrival_at(&booking.travel_date).map(|a| a.to_rfc3339()),
+            bus_number: bus.bus_number,
+            bus_type: bus.bus_type,
+            boarding_point: bus.route.from.clone(),
+            from: bus.route.from,
+            to: bus.route.to,
+            travel_date: booking.travel_date,
+            seat_number: booking.seat_number,
+            passenger: booking.passenger.map(|p| masked_name(&p.name)).filter(|n| !n.is_empty()),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const ITEM_CATEGORIES: [&str; 7] = ["phone", "bag", "wallet", "documents", "clothing", "electronics", "other"];
+pub const LOST_REPORT_STATUSES: [&str; 4] = ["Open", "Matched", "Returned", "Closed"];
+pub const FOUND_ITEM_STATUSES: [&str; 4] = ["Unclaimed", "Matched", "Returned", "Disposed"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LostItemReport {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub booking_id: ObjectId,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub category: String,
+    pub description: String,
+    pub status: String, // Open, Matched, Returned, Closed
+    pub found_item_id: Option<ObjectId>,
+    pub note: Option<String>,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FoundItem {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub seat_number: Option<String>,
+    pub category: String,
+    pub description: String,
+    pub status: String, // Unclaimed, Matched, Returned, Disposed
+    pub logged_by: String,
+    pub created_at: bson::DateTime,
+}
+
+impl FoundItem {
+    /// How likely this item is the one reported lost, from 0 to 1.
+    pub fn match_score(&self, report: &LostItemReport) -> f64 {
+        let mut score = 0.0;
+        if self.bus_id == report.bus_id && self.travel_date == report.travel_date {
+            score += 0.4;
+        }
+        if self.category == report.category {
+            score += 0.3;
+        }
+        if self.seat_number.as_deref() == Some(report.seat_number.as_str()) {
+            score += 0.1;
+        }
+        score + 0.2 * word_overlap(&self.description, &report.description)
+    }
+}
+
+// Share of the shorter description's words that appear in the other
+fn word_overlap(a: &str, b: &str) -> f64 {
+    let words = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.len() > 2)
+            .map(|w| w.to_lowercase())
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / smaller as f64
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReportLostItemRequest {
+    pub booking_id: String,
+    pub category: String,
+    pub description: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LogFoundItemRequest {
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: Option<String>,
+    pub category: String,
+    pub description: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LostItemStatusRequest {
+    pub status: String,
+    pub found_item_id: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct FoundItemStatusRequest {
+    pub status: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LostFoundQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LostItemResponse {
+    pub id: String,
+    pub booking_id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub category: String,
+    pub description: String,
+    pub status: String,
+    pub found_item_id: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<LostItemReport> for LostItemResponse {
+    fn from(r: LostItemReport) -> Self {
+        Self {
+            id: r.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            booking_id: r.booking_id.to_hex(),
+            bus_id: r.bus_id.to_hex(),
+            travel_date: r.travel_date,
+            seat_number: r.seat_number,
+            category: r.category,
+            description: r.description,
+            status: r.status,
+            found_item_id: r.found_item_id.map(|oid| oid.to_hex()),
+            note: r.note,
+            created_at: r.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            updated_at: r.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FoundItemResponse {
+    pub id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: Option<String>,
+    pub category: String,
+    pub description: String,
+    pub status: String,
+    pub logged_by: String,
+    pub created_at: String,
+}
+
+impl From<FoundItem> for FoundItemResponse {
+    fn from(f: FoundItem) -> Self {
+        Self {
+            id: f.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            bus_id: f.bus_id.to_hex(),
+            travel_date: f.travel_date,
+            seat_number: f.seat_number,
+            category: f.category,
+            description: f.description,
+            status: f.status,
+            logged_by: f.logged_by,
+            created_at: f.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct MatchSuggestion {
+    pub score: f64,
+    pub item: FoundItemResponse,
+}
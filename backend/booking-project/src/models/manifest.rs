@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::crew::CrewAssignment;
+use super::localization::document_text;
+use super::pdf::{column, render_text_pdf, CHARS_PER_LINE};
+
+pub const TRIP_ACCESS_SCOPE: &str = "trip:conductor";
+// Conductors can open a manifest from three hours before departure until two hours after arrival
+pub const SHIFT_LEAD_HOURS: i64 = 3;
+pub const SHIFT_GRACE_HOURS: i64 = 2;
+
+/// Short-lived credential bound to one trip, issued when a conductor opens its manifest.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TripAccessClaims {
+    pub sub: String,
+    pub scope: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub exp: usize,
+    pub iat: usize,
+}
+
+#[derive(Serialize)]
+pub struct ManifestEntry {
+    pub booking_id: String,
+    pub seat_number: String,
+    pub passenger_name: String,
+    pub status: String,
+    pub checked_in: bool,
+}
+
+#[derive(Serialize)]
+pub struct Manifest {
+    pub bus_id: String,
+    pub bus_number: String,
+    pub travel_date: String,
+    pub from: String,
+    pub to: String,
+    pub departure: Option<String>,
+    pub passengers: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+pub struct OpenedManifest {
+    #[serde(flatten)]
+    pub manifest: Manifest,
+    pub trip_token: String,
+    pub trip_token_expires_at: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CheckInRequest {
+    pub booking_id: String,
+}
+
+pub struct ManifestRow {
+    pub seat_number: String,
+    pub name: String,
+    pub gender: String,
+    pub age: String,
+    pub id_number: String,
+    pub next_of_kin: String,
+    pub boarding_point: String,
+    pub checked_in: bool,
+}
+
+/// Everything printed on the physical manifest carried on board.
+pub struct PrintableManifest {
+    pub operator: String,
+    pub vehicle: String,
+    pub bus_type: String,
+    pub seats: i32,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub crew: Vec<CrewAssignment>,
+    pub rows: Vec<ManifestRow>,
+    // Language the printed copy is worded in; names and passenger details are never translated
+    pub language: String,
+}
+
+const MANIFEST_COLUMNS: [(&str, usize); 8] = [
+    ("manifest.seat", 6),
+    ("manifest.passenger", 30),
+    ("manifest.gender", 8),
+    ("manifest.age", 5),
+    ("manifest.id_number", 18),
+    ("manifest.next_of_kin", 40),
+    ("manifest.boarding_point", 18),
+    ("manifest.boarded", 8),
+];
+
+impl PrintableManifest {
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let t = |key: &str| document_text(&self.language, key);
+        let line = |values: [&str; 8]| {
+            MANIFEST_COLUMNS.iter().zip(values).map(|((_, width), v)| column(v, *width)).collect::<String>()
+        };
+        let rule = "-".repeat(CHARS_PER_LINE.min(MANIFEST_COLUMNS.iter().map(|(_, w)| w).sum()));
+
+        let mut header = vec![
+            format!("{} - {}", t("manifest.title"), self.operator.to_uppercase()),
+            format!(
+                "{}: {}   {}: {}   {}: {}",
+                t("manifest.vehicle"), self.vehicle, t("manifest.type"), self.bus_type, t("manifest.capacity"), self.seats
+            ),
+            format!(
+                "{}: {} {} {}   {}: {}   {}: {}   {}: {}",
+                t("manifest.route"), self.from, t("manifest.to"), self.to,
+                t("manifest.date"), self.travel_date,
+                t("manifest.departure"), self.departure.as_deref().unwrap_or("-"),
+                t("manifest.arrival"), self.arrival.as_deref().unwrap_or("-"),
+            ),
+        ];
+        for member in &self.crew {
+            let role = match t(&format!("crew.{}", member.role)) {
+                key if key.starts_with("crew.") => capitalize(&member.role),
+                role => role,
+            };
+            let mut details = vec![format!("{}: {}", role, member.name)];
+            details.extend(member.phone.as_ref().map(|p| format!("Tel {}", p)));
+            details.extend(member.licence_number.as_ref().map(|l| format!("Licence {}", l)));
+            header.push(details.join("   "));
+        }
+        if self.crew.is_empty() {
+            header.push(t("manifest.no_crew"));
+        }
+        header.push(format!("{}: {}", t("manifest.passengers"), self.rows.len()));
+        header.push(String::new());
+        let titles = MANIFEST_COLUMNS.map(|(key, _)| t(key));
+        header.push(line(titles.each_ref().map(String::as_str)));
+        header.push(rule);
+
+        let boarded = t("manifest.yes");
+        let rows: Vec<String> = self.rows.iter().map(|r| line([
+            &r.seat_number,
+            &r.name,
+            &r.gender,
+            &r.age,
+            &r.id_number,
+            &r.next_of_kin,
+            &r.boarding_point,
+            if r.checked_in { &boarded } else { "[ ]" },
+        ])).collect();
+
+        render_text_pdf(&header, &rows, &self.language)
+    }
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
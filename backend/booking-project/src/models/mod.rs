@@ -1,10 +1,75 @@
+pub mod account;
+pub mod analytics;
+pub mod audit;
 pub mod auth;
 pub mod booking;
+pub mod bulk;
 pub mod bus;
+pub mod calendar;
+pub mod charter;
+pub mod commission;
+pub mod compare;
+pub mod consent;
+pub mod crew;
+pub mod device;
+pub mod duplicates;
+pub mod email_template;
+pub mod experiment;
+pub mod fare;
+pub mod feed;
+pub mod fraud;
+pub mod gtfs;
+pub mod gtfs_import;
+pub mod hold;
+pub mod incident;
+pub mod invitation;
+pub mod ledger;
+pub mod localization;
+pub mod lookup;
+pub mod lost_found;
+pub mod manifest;
+pub mod monitoring;
+pub mod next_trip;
+pub mod notification;
+pub mod notification_resend;
+pub mod offline_sync;
+pub mod operator;
+pub mod outbox;
+pub mod parcel;
+pub mod partner;
+pub mod pass;
+pub mod payment;
+pub mod payment_method;
+pub mod pdf;
+pub mod permissions;
+pub mod policy;
+pub mod price_history;
+pub mod punctuality;
+pub mod rate_limit;
+pub mod receipt;
+pub mod refund;
+pub mod regulatory;
+pub mod schedule;
+pub mod search_log;
+pub mod seat_layout;
+pub mod settlement;
+pub mod stats;
+pub mod status;
+pub mod tenancy;
+pub mod terms;
+pub mod ticket;
+pub mod terminal;
+pub mod trip;
+pub mod trip_history;
 pub mod user;
+pub mod waiting_room;
+pub mod webhook;
+pub mod zip;
 
 // Re-export all the models that are used in other modules
 pub use auth::{AuthResponse, GoogleLoginRequest, LoginRequest, RegisterRequest};
 pub use booking::Booking;
-pub use bus::{Bus, Seat, SeatAvailabilityResponse, SeatDateQuery};
-pub use user::{Claims, User, UserResponse};
+pub use bus::{Bus, Seat};
+pub use notification::Notification;
+pub use refund::RefundRequest;
+pub use user::{Claims, TokenType, User, UserResponse};
@@ -0,0 +1,21 @@
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+
+pub const BOOKING_ATTEMPT: &str = "booking_attempt";
+pub const BOOKING_FAILURE: &str = "booking_failure";
+pub const PAYMENT_INITIATED: &str = "payment_initiated";
+pub const PAYMENT_CONFIRMED: &str = "payment_confirmed";
+// Recorded per payer by payment callbacks; feeds fraud scoring
+pub const PAYMENT_FAILED: &str = "payment_failed";
+// Guessing at booking references; also the decision recorded in the fraud log
+pub const ENUMERATION_SUSPECTED: &str = "enumeration_suspected";
+
+#[derive(Serialize, Deserialize)]
+pub struct MonitoringEvent {
+    pub kind: String,
+    pub detail: Option<String>,
+    // User the event concerns, when there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub at: bson::DateTime,
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Precomputed home-screen card, one per user and keyed by their id, so the app's most frequent
+// call is a single read. Rebuilt when one of their bookings changes or `valid_until` passes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NextTrip {
+    #[serde(rename = "_id")]
+    pub user_id: ObjectId,
+    // None when nothing confirmed is coming up
+    pub trip: Option<UpcomingTrip>,
+    pub valid_until: bson::DateTime,
+    pub refreshed_at: bson::DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UpcomingTrip {
+    pub booking_id: ObjectId,
+    pub bus_id: ObjectId,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub boarding_point: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub departure_at: bson::DateTime,
+    pub arrival_at: Option<bson::DateTime>,
+    // Kept current from trip status updates
+    pub status: String,
+    pub delay_minutes: i64,
+}
+
+fn to_utc(time: bson::DateTime) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(time.timestamp_millis()).unwrap_or_default()
+}
+
+impl UpcomingTrip {
+    /// When the bus is now expected to leave, counting any delay.
+    pub fn expected_departure(&self) -> DateTime<Utc> {
+        to_utc(self.departure_at) + chrono::Duration::minutes(self.delay_minutes)
+    }
+
+    /// Finished trips drop off the card; one without an arrival time is over a day after leaving.
+    pub fn is_over(&self, now: DateTime<Utc>) -> bool {
+        if self.status == "arrived" {
+            return true;
+        }
+        let end = match self.arrival_at {
+            Some(arrival) => to_utc(arrival) + chrono::Duration::minutes(self.delay_minutes),
+            None => self.expected_departure() + chrono::Duration::days(1),
+        };
+        end <= now
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UpcomingTripResponse {
+    pub booking_id: String,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub boarding_point: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub departure: String,
+    pub expected_departure: String,
+    pub arrival: Option<String>,
+    pub status: String,
+    pub delay_minutes: i64,
+    // Until the expected departure; 0 once it has passed
+    pub departs_in_seconds: i64,
+    pub ticket_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NextTripResponse {
+    pub trip: Option<UpcomingTripResponse>,
+}
+
+impl UpcomingTripResponse {
+    /// The countdown is worked out here rather than stored, so the cached card never goes stale.
+    pub fn new(trip: UpcomingTrip, now: DateTime<Utc>, base_url: &str) -> Self {
+        let expected_departure = trip.expected_departure();
+        let booking_id = trip.booking_id.to_hex();
+        Self {
+            ticket_url: format!("{}/api/bookings/{}/receipt.pdf", base_url.trim_end_matches('/'), booking_id),
+            booking_id,
+            bus_number: trip.bus_number,
+            bus_type: trip.bus_type,
+            from: trip.from,
+            to: trip.to,
+            boarding_point: trip.boarding_point,
+            travel_date: trip.travel_date,
+            seat_number: trip.seat_number,
+            departure: trip.departure_at.try_to_rfc3339_string().unwrap_or_default(),
+            expected_departure: expected_departure.to_rfc3339(),
+            arrival: trip.arrival_at.and_then(|a| a.try_to_rfc3339_string().ok()),
+            status: trip.status,
+            delay_minutes: trip.delay_minutes,
+            departs_in_seconds: (expected_departure - now).num_seconds().max(0),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Notification {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub audience: String, // "user" or "admin"
+    pub user_id: Option<ObjectId>,
+    pub title: String,
+    pub message: String,
+    pub read: bool,
+    pub created_at: bson::DateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NotificationResponse {
+    pub id: String,
+    pub title: String,
+    pub message: String,
+    pub read: bool,
+    pub created_at: String,
+}
+
+impl From<Notification> for NotificationResponse {
+    fn from(n: Notification) -> Self {
+        Self {
+            id: n.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            title: n.title,
+            message: n.message,
+            read: n.read,
+            created_at: n.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
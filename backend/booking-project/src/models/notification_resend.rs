@@ -0,0 +1,222 @@
+use chrono::NaiveDate;
+use mongodb::bson::{self, doc, oid::ObjectId, Document};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::booking::BOOKING_STATUSES;
+
+pub const RESEND_CHANNELS: [&str; 2] = ["email", "sms"];
+pub const RESEND_QUEUED: &str = "queued";
+pub const RESEND_RUNNING: &str = "running";
+pub const RESEND_COMPLETED: &str = "completed";
+// Only the most recent failures are kept on the run; the rest are only counted
+pub const MAX_RECORDED_FAILURES: i32 = 50;
+
+/// Which bookings get their confirmation again, and by which channels.
+#[derive(Deserialize, ToSchema)]
+pub struct ResendConfirmationsRequest {
+    pub bus_id: Option<String>,
+    pub travel_date: Option<String>,
+    // Defaults to Confirmed
+    pub status: Option<String>,
+    // Defaults to every channel
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl ResendConfirmationsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        // Nobody means to message every passenger there ever was
+        if self.bus_id.is_none() && self.travel_date.is_none() {
+            return Err("bus_id or travel_date is required".to_string());
+        }
+        if self.travel_date.as_deref().is_some_and(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err()) {
+            return Err("travel_date must be YYYY-MM-DD".to_string());
+        }
+        match self.status.as_deref() {
+            Some("Cancelled") => return Err("Cancelled bookings have no confirmation to resend".to_string()),
+            Some(status) if !BOOKING_STATUSES.contains(&status) => {
+                return Err(format!("Unknown status '{}', expected one of: {}", status, BOOKING_STATUSES.join(", ")));
+            }
+            _ => {}
+        }
+        if let Some(channel) = self.channels.iter().find(|c| !RESEND_CHANNELS.contains(&c.as_str())) {
+            return Err(format!("Unknown channel '{}', expected one of: {}", channel, RESEND_CHANNELS.join(", ")));
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> &str {
+        self.status.as_deref().unwrap_or("Confirmed")
+    }
+
+    pub fn channels(&self) -> Vec<String> {
+        if self.channels.is_empty() {
+            return RESEND_CHANNELS.iter().map(|c| c.to_string()).collect();
+        }
+        RESEND_CHANNELS.iter().filter(|c| self.channels.iter().any(|own| own == *c)).map(|c| c.to_string()).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct ResendFailure {
+    pub booking_id: String,
+    pub message: String,
+}
+
+/// A bulk resend of booking confirmations, worked through in batches by the resend job. The
+/// counters are its progress report.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NotificationResend {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    // The operator who asked, whose buses the run is confined to; None for platform staff
+    pub operator: Option<String>,
+    pub bus_id: Option<ObjectId>,
+    pub travel_date: Option<String>,
+    pub booking_status: String,
+    pub channels: Vec<String>,
+    pub state: String, // queued, running, completed
+    // Bookings matching when the run was requested
+    pub total: u64,
+    pub processed: u64,
+    pub emails_queued: u64,
+    pub sms_queued: u64,
+    // Bookings with no address on file for any of the channels
+    pub skipped: u64,
+    pub failed: u64,
+    #[serde(default)]
+    pub failures: Vec<ResendFailure>,
+    // The last booking handled; bookings are worked through in id order so a restarted run resumes here
+    pub after: Option<ObjectId>,
+    // When the worker holding the run lets another instance take it over
+    pub lease_until: bson::DateTime,
+    pub requested_by: String,
+    pub requested_at: bson::DateTime,
+    pub finished_at: Option<bson::DateTime>,
+}
+
+impl NotificationResend {
+    pub fn new(req: &ResendConfirmationsRequest, operator: Option<String>, bus_id: Option<ObjectId>, total: u64, actor: &str) -> Self {
+        let now = bson::DateTime::now();
+        Self {
+            id: None,
+            operator,
+            bus_id,
+            travel_date: req.travel_date.clone(),
+            booking_status: req.status().to_string(),
+            channels: req.channels(),
+            state: RESEND_QUEUED.to_string(),
+            total,
+            processed: 0,
+            emails_queued: 0,
+            sms_queued: 0,
+            skipped: 0,
+            failed: 0,
+            failures: Vec::new(),
+            after: None,
+            lease_until: now,
+            requested_by: actor.to_string(),
+            requested_at: now,
+            finished_at: None,
+        }
+    }
+
+    /// The bookings the run covers, before tenant scoping.
+    pub fn booking_filter(&self) -> Document {
+        let mut filter = doc! { "status": &self.booking_status };
+        if let Some(bus_id) = self.bus_id {
+            filter.insert("bus_id", bus_id);
+        }
+        if let Some(travel_date) = &self.travel_date {
+            filter.insert("travel_date", travel_date);
+        }
+        filter
+    }
+
+    pub fn sends(&self, channel: &str) -> bool {
+        self.channels.iter().any(|c| c == channel)
+    }
+
+    // Bookings made after the run was requested can push processed past the total
+    pub fn progress_percent(&self) -> u8 {
+        if self.state == RESEND_COMPLETED {
+            return 100;
+        }
+        if self.total == 0 {
+            return 0;
+        }
+        (self.processed.min(self.total) * 100 / self.total) as u8
+    }
+}
+
+/// What one batch of the run did, added onto its counters.
+#[derive(Default)]
+pub struct ResendTally {
+    pub processed: u64,
+    pub emails_queued: u64,
+    pub sms_queued: u64,
+    pub skipped: u64,
+    pub failures: Vec<ResendFailure>,
+}
+
+impl ResendTally {
+    pub fn record(&mut self, email: bool, sms: bool) {
+        self.processed += 1;
+        self.emails_queued += email as u64;
+        self.sms_queued += sms as u64;
+        if !email && !sms {
+            self.skipped += 1;
+        }
+    }
+
+    pub fn record_failure(&mut self, booking_id: &str, message: String) {
+        self.processed += 1;
+        self.failures.push(ResendFailure { booking_id: booking_id.to_string(), message });
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NotificationResendResponse {
+    pub id: Option<String>,
+    pub bus_id: Option<String>,
+    pub travel_date: Option<String>,
+    pub booking_status: String,
+    pub channels: Vec<String>,
+    pub state: String,
+    pub total: u64,
+    pub processed: u64,
+    pub progress_percent: u8,
+    pub emails_queued: u64,
+    pub sms_queued: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    pub failures: Vec<ResendFailure>,
+    pub requested_by: String,
+    pub requested_at: String,
+    pub finished_at: Option<String>,
+}
+
+impl From<NotificationResend> for NotificationResendResponse {
+    fn from(r: NotificationResend) -> Self {
+        Self {
+            id: r.id.map(|id| id.to_hex()),
+            bus_id: r.bus_id.map(|id| id.to_hex()),
+            progress_percent: r.progress_percent(),
+            travel_date: r.travel_date,
+            booking_status: r.booking_status,
+            channels: r.channels,
+            state: r.state,
+            total: r.total,
+            processed: r.processed,
+            emails_queued: r.emails_queued,
+            sms_queued: r.sms_queued,
+            skipped: r.skipped,
+            failed: r.failed,
+            failures: r.failures,
+            requested_by: r.requested_by,
+            requested_at: r.requested_at.try_to_rfc3339_string().unwrap_or_default(),
+            finished_at: r.finished_at.and_then(|t| t.try_to_rfc3339_string().ok()),
+        }
+    }
+}
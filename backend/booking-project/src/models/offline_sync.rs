@@ -0,0 +1,217 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::booking::NextOfKin;
+
+// A day's worth of scans and sales for a full coach, with room to spare
+pub const MAX_SYNC_ITEMS: usize = 500;
+
+pub const SYNC_APPLIED: &str = "applied";
+pub const SYNC_DUPLICATE: &str = "duplicate";
+pub const SYNC_CONFLICT: &str = "conflict";
+pub const SYNC_REJECTED: &str = "rejected";
+// Longer than any one item takes to apply
+pub const SYNC_CLAIM_SECS: i64 = 120;
+
+/// A confirmed passenger as the conductor app keeps it offline. `qr_hash` is the SHA-256 of
+/// the ticket's QR text, so the app can match a scan without holding the signing key.
+#[derive(Serialize, ToSchema)]
+pub struct OfflineTicket {
+    pub booking_id: String,
+    pub seat_number: String,
+    pub passenger_name: String,
+    pub qr_hash: String,
+    pub checked_in: bool,
+}
+
+/// Everything the conductor app needs to board a trip with no signal.
+#[derive(Serialize, ToSchema)]
+pub struct OfflineManifest {
+    pub bus_id: String,
+    pub bus_number: String,
+    pub travel_date: String,
+    pub from: String,
+    pub to: String,
+    pub departure: Option<String>,
+    // The route fare, for cash sales on board
+    pub fare: f64,
+    // Seats nobody had booked at download; sold offline on a first-come basis
+    pub free_seats: Vec<String>,
+    pub tickets: Vec<OfflineTicket>,
+    pub generated_at: String,
+    // The trip token still uploads a batch until then
+    pub upload_until: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct OfflineCheckIn {
+    // Unique per item on the handset, so a batch sent twice is only applied once
+    pub client_id: String,
+    pub booking_id: String,
+    // RFC 3339 time the passenger was let on
+    pub at: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct OfflineCashSale {
+    pub client_id: String,
+    pub seat_number: String,
+    pub passenger_name: String,
+    pub phone: Option<String>,
+    // Required by operators that insist on next of kin for every passenger
+    #[serde(default)]
+    pub next_of_kin: Option<NextOfKin>,
+    // What the conductor actually took, in KES
+    pub fare: f64,
+    pub sold_at: String,
+}
+
+impl OfflineCashSale {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.passenger_name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err("passenger_name is required, up to 100 characters".to_string());
+        }
+        if self.seat_number.trim().is_empty() {
+            return Err("seat_number is required".to_string());
+        }
+        if !self.fare.is_finite() || self.fare < 0.0 {
+            return Err("fare must be zero or more".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SyncUploadRequest {
+    #[serde(default)]
+    pub check_ins: Vec<OfflineCheckIn>,
+    #[serde(default)]
+    pub cash_sales: Vec<OfflineCashSale>,
+}
+
+impl SyncUploadRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let items = self.check_ins.len() + self.cash_sales.len();
+        if items == 0 {
+            return Err("Nothing to sync".to_string());
+        }
+        if items > MAX_SYNC_ITEMS {
+            return Err(format!("Upload at most {} items at a time", MAX_SYNC_ITEMS));
+        }
+        let mut ids = std::collections::HashSet::new();
+        let client_ids = self.check_ins.iter().map(|c| &c.client_id).chain(self.cash_sales.iter().map(|s| &s.client_id));
+        for client_id in client_ids {
+            if client_id.trim().is_empty() || client_id.len() > 64 {
+                return Err("Every item needs a client_id of up to 64 characters".to_string());
+            }
+            if !ids.insert(client_id.as_str()) {
+                return Err(format!("client_id {} appears more than once", client_id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// When something happened on the handset, which must not be after `now` beyond a little
+/// clock drift.
+pub fn offline_time(value: &str, now: chrono::DateTime<chrono::Utc>) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let at = chrono::DateTime::parse_from_rfc3339(value.trim())
+        .map_err(|_| format!("{} is not an RFC 3339 time", value))?
+        .with_timezone(&chrono::Utc);
+    if at > now + chrono::Duration::minutes(5) {
+        return Err(format!("{} is in the future", value));
+    }
+    Ok(at)
+}
+
+/// What became of one uploaded item: applied, duplicate (already done, nothing changed),
+/// conflict (someone else got there first) or rejected.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SyncItemResult {
+    pub client_id: String,
+    // check_in or cash_sale
+    pub kind: String,
+    pub outcome: String,
+    pub detail: Option<String>,
+    // The booking checked in, created by the sale, or standing in its way
+    pub booking_id: Option<String>,
+    #[serde(default)]
+    pub fare: f64,
+}
+
+impl SyncItemResult {
+    pub fn new(client_id: &str, kind: &str, outcome: &str, detail: Option<String>, booking_id: Option<String>) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            kind: kind.to_string(),
+            outcome: outcome.to_string(),
+            detail,
+            booking_id,
+            fare: 0.0,
+        }
+    }
+}
+
+// One processed item, kept so a resent batch gets the same answers
+#[derive(Serialize, Deserialize)]
+pub struct SyncRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub client_id: String,
+    pub device_id: Option<String>,
+    pub result: Option<SyncItemResult>,
+    pub synced_at: bson::DateTime,
+    // When an upload took the item on; a claim with no result after SYNC_CLAIM_SECS was
+    // abandoned and may be taken over
+    #[serde(default)]
+    pub claimed_at: Option<bson::DateTime>,
+}
+
+/// What the conductor hands in with the cash: how each item landed, and what needs sorting out.
+#[derive(Serialize, ToSchema)]
+pub struct ReconciliationReport {
+    pub bus_id: String,
+    pub travel_date: String,
+    pub results: Vec<SyncItemResult>,
+    pub checked_in: usize,
+    pub duplicate_check_ins: usize,
+    pub cash_sales: usize,
+    // Cash taken for sales that now hold a seat
+    pub cash_collected: f64,
+    // Cash taken for sales that lost their seat or were refused, owed back to the passenger
+    pub cash_to_refund: f64,
+    pub conflicts: usize,
+    pub rejected: usize,
+    // Confirmed passengers not checked in after this upload
+    pub not_boarded: Vec<String>,
+}
+
+impl ReconciliationReport {
+    pub fn new(bus_id: &str, travel_date: &str, results: Vec<SyncItemResult>, not_boarded: Vec<String>) -> Self {
+        let count = |kind: &str, outcome: &str| results.iter().filter(|r| r.kind == kind && r.outcome == outcome).count();
+        let cash = |applied: bool| {
+            let total: f64 = results.iter()
+                .filter(|r| r.kind == "cash_sale" && (r.outcome == SYNC_APPLIED) == applied)
+                .map(|r| r.fare)
+                .sum();
+            (total * 100.0).round() / 100.0
+        };
+        Self {
+            bus_id: bus_id.to_string(),
+            travel_date: travel_date.to_string(),
+            checked_in: count("check_in", SYNC_APPLIED),
+            duplicate_check_ins: count("check_in", SYNC_DUPLICATE),
+            cash_sales: count("cash_sale", SYNC_APPLIED),
+            cash_collected: cash(true),
+            cash_to_refund: cash(false),
+            conflicts: results.iter().filter(|r| r.outcome == SYNC_CONFLICT).count(),
+            rejected: results.iter().filter(|r| r.outcome == SYNC_REJECTED).count(),
+            not_boarded,
+            results,
+        }
+    }
+}
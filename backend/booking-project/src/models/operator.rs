@@ -0,0 +1,49 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::email_template::EmailTemplate;
+
+// Per-operator booking rules; operators without a document get the defaults
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct OperatorSettings {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub operator: String,
+    // Some regulators want an emergency contact for every passenger on the manifest
+    #[serde(default)]
+    pub next_of_kin_required: bool,
+    // Tickets, manifests and SMS for this operator's trips use it unless the reader asks otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_language: Option<String>,
+    // Wording of booking confirmation emails, in place of the built-in text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub booking_email: Option<EmailTemplate>,
+    pub updated_at: Option<bson::DateTime>,
+}
+
+// Settings left out are kept as they are
+#[derive(Deserialize, ToSchema)]
+pub struct OperatorSettingsRequest {
+    pub next_of_kin_required: Option<bool>,
+    pub default_language: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OperatorSettingsResponse {
+    pub operator: String,
+    pub next_of_kin_required: bool,
+    pub default_language: String,
+    pub updated_at: Option<String>,
+}
+
+impl From<OperatorSettings> for OperatorSettingsResponse {
+    fn from(s: OperatorSettings) -> Self {
+        Self {
+            operator: s.operator,
+            next_of_kin_required: s.next_of_kin_required,
+            default_language: s.default_language.unwrap_or_else(|| super::localization::DEFAULT_LANGUAGE.to_string()),
+            updated_at: s.updated_at.map(|d| d.to_string()),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const OUTBOX_CHANNELS: [&str; 4] = ["email", "event_stream", "sms", "webhook"];
+
+// A side effect waiting to be delivered by the outbox worker
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OutboxMessage {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub channel: String, // email, event_stream, sms, webhook
+    pub recipient: String, // address, phone number, URL or event topic
+    pub subject: Option<String>,
+    pub body: String,
+    pub status: String, // pending, delivered, suppressed
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub delivered_at: Option<bson::DateTime>,
+    // Promotional messages name the user and the consent they need; without it they are
+    // suppressed at delivery time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_consent: Option<String>,
+}
+
+impl OutboxMessage {
+    pub fn new(channel: &str, recipient: &str, subject: Option<String>, body: String) -> Self {
+        Self {
+            id: None,
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            subject,
+            body,
+            status: "pending".to_string(),
+            attempts: 0,
+            max_attempts: 5,
+            last_error: None,
+            next_attempt_at: bson::DateTime::now(),
+            created_at: bson::DateTime::now(),
+            delivered_at: None,
+            user_id: None,
+            requires_consent: None,
+        }
+    }
+
+    pub fn requiring_consent(mut self, user_id: ObjectId, purpose: &str) -> Self {
+        self.user_id = Some(user_id);
+        self.requires_consent = Some(purpose.to_string());
+        self
+    }
+}
+
+// An outbox message that exhausted its retries, parked for manual handling
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeadLetter {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub channel: String,
+    pub recipient: String,
+    pub subject: Option<String>,
+    pub body: String,
+    pub attempts: i32,
+    pub error: String,
+    pub original_created_at: bson::DateTime,
+    pub dead_at: bson::DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_consent: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateDeadLetterRequest {
+    pub recipient: Option<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct DeadLetterQuery {
+    pub channel: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeadLetterResponse {
+    pub id: String,
+    pub channel: String,
+    pub recipient: String,
+    pub subject: Option<String>,
+    pub body: String,
+    pub attempts: i32,
+    pub error: String,
+    pub original_created_at: String,
+    pub dead_at: String,
+}
+
+impl From<DeadLetter> for DeadLetterResponse {
+    fn from(d: DeadLetter) -> Self {
+        Self {
+            id: d.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            channel: d.channel,
+            recipient: d.recipient,
+            subject: d.subject,
+            body: d.body,
+            attempts: d.attempts,
+            error: d.error,
+            original_created_at: d.original_created_at.try_to_rfc3339_string().unwrap_or_default(),
+            dead_at: d.dead_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::lookup::{is_readable_code, masked_name, readable_code};
+use super::payment::mpesa_phone;
+use crate::config::ParcelConfig;
+
+pub const PARCEL_RECEIVED: &str = "received";
+pub const PARCEL_IN_TRANSIT: &str = "in_transit";
+pub const PARCEL_ARRIVED: &str = "arrived";
+pub const PARCEL_COLLECTED: &str = "collected";
+
+// In the order a parcel goes through them
+pub const PARCEL_STATUSES: [&str; 4] = [PARCEL_RECEIVED, PARCEL_IN_TRANSIT, PARCEL_ARRIVED, PARCEL_COLLECTED];
+
+// Sizes the booking office sorts parcels into, with what each adds to the base fee
+pub const PARCEL_SIZES: [(&str, f64); 4] = [("envelope", 0.0), ("small", 100.0), ("medium", 250.0), ("large", 500.0)];
+
+const WAYBILL_PREFIX: &str = "PCL-";
+const WAYBILL_LENGTH: usize = 8;
+
+/// A fresh random waybill number like PCL-7K2F9QXA.
+pub fn new_waybill() -> Result<String, String> {
+    Ok(format!("{}{}", WAYBILL_PREFIX, readable_code(WAYBILL_LENGTH)?))
+}
+
+/// A waybill number as typed, in its stored form. None when it can't be one.
+pub fn normalize_waybill(waybill: &str) -> Option<String> {
+    let compact: String = waybill.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    let body = compact.strip_prefix(WAYBILL_PREFIX.trim_end_matches('-')).unwrap_or(&compact);
+    (body.len() == WAYBILL_LENGTH && is_readable_code(body)).then(|| format!("{}{}", WAYBILL_PREFIX, body))
+}
+
+/// What a parcel costs to send, in whole shillings: every started kilogram is charged.
+pub fn parcel_price(size: &str, weight_kg: f64, config: &ParcelConfig) -> Option<f64> {
+    let (_, surcharge) = PARCEL_SIZES.iter().find(|(s, _)| *s == size)?;
+    Some((config.base_fee + surcharge + config.per_kg * weight_kg.ceil()).ceil())
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct ParcelContact {
+    pub name: String,
+    // Texted as the parcel moves, in any common Kenyan format
+    pub phone: String,
+}
+
+impl ParcelContact {
+    fn validated(&self, who: &str) -> Result<ParcelContact, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(format!("{} name is required", who));
+        }
+        let phone = mpesa_phone(&self.phone).map_err(|_| format!("{} phone must be a Kenyan mobile number", who))?;
+        Ok(ParcelContact { name: name.to_string(), phone })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ParcelEvent {
+    pub status: String,
+    pub actor: String,
+    pub note: Option<String>,
+    pub at: bson::DateTime,
+}
+
+// Something sent in a bus's luggage hold from the office at one end of its route to the other
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Parcel {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub waybill: String,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub from: String,
+    pub to: String,
+    pub sender: ParcelContact,
+    pub recipient: ParcelContact,
+    pub description: String,
+    pub size: String,
+    pub weight_kg: f64,
+    pub price: f64,
+    pub status: String, // received, in_transit, arrived, collected
+    pub history: Vec<ParcelEvent>,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+impl Parcel {
+    /// The texts to send when the parcel reaches `status`, as (phone, message) pairs.
+    pub fn status_sms(&self, status: &str) -> Vec<(String, String)> {
+        let (sender, recipient) = (self.sender.phone.clone(), self.recipient.phone.clone());
+        match status {
+            PARCEL_RECEIVED => vec![
+                (sender, format!(
+                    "Parcel {} to {} goes from {} to {} on {}. Paid KES {:.0}. Track it with the waybill number.",
+                    self.waybill, self.recipient.name, self.from, self.to, self.travel_date, self.price,
+                )),
+                (recipient, format!(
+                    "{} sent you parcel {} from {}, due in {} on {}.",
+                    self.sender.name, self.waybill, self.from, self.to, self.travel_date,
+                )),
+            ],
+            PARCEL_IN_TRANSIT => vec![
+                (recipient, format!("Parcel {} has left {} and is on its way to {}.", self.waybill, self.from, self.to)),
+            ],
+            PARCEL_ARRIVED => vec![
+                (recipient, format!("Parcel {} has arrived in {}. Collect it at the booking office with your ID.", self.waybill, self.to)),
+                (sender, format!("Parcel {} to {} has arrived in {}.", self.waybill, self.recipient.name, self.to)),
+            ],
+            PARCEL_COLLECTED => vec![
+                (sender, format!("Parcel {} was collected by {} in {}.", self.waybill, self.recipient.name, self.to)),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateParcelRequest {
+    pub bus_id: String,
+    pub travel_date: String,
+    pub sender: ParcelContact,
+    pub recipient: ParcelContact,
+    pub description: String,
+    pub size: String,
+    pub weight_kg: f64,
+}
+
+impl CreateParcelRequest {
+    /// The sender and recipient with their phones in 2547.. form, once the request is usable.
+    pub fn validated_contacts(&self, config: &ParcelConfig) -> Result<(ParcelContact, ParcelContact), String> {
+        if chrono::NaiveDate::parse_from_str(&self.travel_date, "%Y-%m-%d").is_err() {
+            return Err("travel_date must be YYYY-MM-DD".to_string());
+        }
+        if self.description.trim().is_empty() || self.description.chars().count() > 200 {
+            return Err("description is required, up to 200 characters".to_string());
+        }
+        if !PARCEL_SIZES.iter().any(|(s, _)| *s == self.size) {
+            let sizes: Vec<&str> = PARCEL_SIZES.iter().map(|(s, _)| *s).collect();
+            return Err(format!("size must be one of {}", sizes.join(", ")));
+        }
+        if !self.weight_kg.is_finite() || self.weight_kg <= 0.0 || self.weight_kg > config.max_weight_kg {
+            return Err(format!("weight_kg must be more than 0 and at most {}", config.max_weight_kg));
+        }
+        Ok((self.sender.validated("sender")?, self.recipient.validated("recipient")?))
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ParcelStatusRequest {
+    pub status: String,
+    // E.g. the ID number shown at collection
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Whether a parcel may go from `current` to `next`: forward only, and never past collected.
+pub fn parcel_can_move(current: &str, next: &str) -> bool {
+    let position = |status: &str| PARCEL_STATUSES.iter().position(|s| *s == status);
+    matches!((position(current), position(next)), (Some(from), Some(to)) if to > from)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ParcelListQuery {
+    pub bus_id: Option<String>,
+    pub travel_date: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ParcelEventResponse {
+    pub status: String,
+    pub note: Option<String>,
+    pub at: String,
+}
+
+impl From<&ParcelEvent> for ParcelEventResponse {
+    fn from(event: &ParcelEvent) -> Self {
+        Self {
+            status: event.status.clone(),
+            note: event.note.clone(),
+            at: event.at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+// The full parcel, for the booking office
+#[derive(Serialize, ToSchema)]
+pub struct ParcelResponse {
+    pub id: String,
+    pub waybill: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub from: String,
+    pub to: String,
+    pub sender: ParcelContact,
+    pub recipient: ParcelContact,
+    pub description: String,
+    pub size: String,
+    pub weight_kg: f64,
+    pub price: f64,
+    pub status: String,
+    pub history: Vec<ParcelEventResponse>,
+    pub created_at: String,
+}
+
+impl From<Parcel> for ParcelResponse {
+    fn from(parcel: Parcel) -> Self {
+        Self {
+            id: parcel.id.map(|id| id.to_hex()).unwrap_or_default(),
+            bus_id: parcel.bus_id.to_hex(),
+            history: parcel.history.iter().map(ParcelEventResponse::from).collect(),
+            created_at: parcel.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            waybill: parcel.waybill,
+            travel_date: parcel.travel_date,
+            from: parcel.from,
+            to: parcel.to,
+            sender: parcel.sender,
+            recipient: parcel.recipient,
+            description: parcel.description,
+            size: parcel.size,
+            weight_kg: parcel.weight_kg,
+            price: parcel.price,
+            status: parcel.status,
+        }
+    }
+}
+
+// What anyone holding the waybill number may see: where it is, not who sent it or how to reach them
+#[derive(Serialize, ToSchema)]
+pub struct ParcelTrackingResponse {
+    pub waybill: String,
+    pub status: String,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub recipient: String,
+    pub history: Vec<ParcelEventResponse>,
+}
+
+impl From<Parcel> for ParcelTrackingResponse {
+    fn from(parcel: Parcel) -> Self {
+        Self {
+            recipient: masked_name(&parcel.recipient.name),
+            // Staff notes stay with the booking office
+            history: parcel.history.iter()
+                .map(|e| ParcelEventResponse { note: None, ..ParcelEventResponse::from(e) })
+                .collect(),
+            waybill: parcel.waybill,
+            status: parcel.status,
+            from: parcel.from,
+            to: parcel.to,
+            travel_date: parcel.travel_date,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mongodb::bson::{self, oid::ObjectId};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::terminal::terminal_id;
+use crate::config::PartnerConfig;
+use crate::crypto::constant_time_eq;
+
+pub const PARTNER_OFFER_KINDS: [&str; 3] = ["hotel", "taxi", "activity"];
+
+// A partner service offered to passengers arriving in a city
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartnerOffer {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub partner: String,
+    pub kind: String, // hotel, taxi, activity
+    pub city: String,
+    // terminal_id of the city, which is what bookings are matched on
+    pub city_key: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Partner deep link; the click id is appended as `ref` for conversion reports
+    pub url: String,
+    pub active: bool,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PartnerOfferRequest {
+    pub partner: String,
+    pub kind: String,
+    pub city: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub url: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl PartnerOfferRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.partner.trim().is_empty() || self.title.trim().is_empty() || self.city.trim().is_empty() {
+            return Err("partner, city and title are required".to_string());
+        }
+        if !PARTNER_OFFER_KINDS.contains(&self.kind.as_str()) {
+            return Err(format!("kind must be one of: {}", PARTNER_OFFER_KINDS.join(", ")));
+        }
+        if !self.url.starts_with("https://") {
+            return Err("url must be an https link".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn into_offer(self, id: Option<ObjectId>, created_at: bson::DateTime) -> PartnerOffer {
+        PartnerOffer {
+            id,
+            city_key: terminal_id(&self.city),
+            partner: self.partner.trim().to_string(),
+            kind: self.kind,
+            city: self.city.trim().to_string(),
+            title: self.title.trim().to_string(),
+            description: self.description.filter(|d| !d.trim().is_empty()),
+            url: self.url,
+            active: self.active,
+            created_at,
+            updated_at: bson::DateTime::now(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PartnerOfferQuery {
+    pub city: Option<String>,
+}
+
+fn link_signature(secret: &str, offer_id: &str, booking_id: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    URL_SAFE_NO_PAD.encode(hmac::sign(&key, format!("{}:{}", offer_id, booking_id).as_bytes()).as_ref())
+}
+
+/// Whether a click link was issued by us for this offer and booking.
+pub fn verify_link_signature(secret: &str, offer_id: &str, booking_id: &str, signature: &str) -> bool {
+    constant_time_eq(link_signature(secret, offer_id, booking_id).as_bytes(), signature.as_bytes())
+}
+
+/// The link passengers follow: it counts the click, then forwards them to the partner.
+pub fn tracked_link(config: &PartnerConfig, offer_id: &str, booking_id: &str) -> String {
+    format!(
+        "{}/api/partners/offers/{}/click?booking={}&sig={}",
+        config.public_base_url,
+        offer_id,
+        booking_id,
+        link_signature(&config.link_secret, offer_id, booking_id)
+    )
+}
+
+/// The partner's URL with the click id the partner quotes back when reporting a conversion.
+pub fn partner_landing_url(url: &str, click_id: &str) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}ref={}", url, separator, click_id)
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct PartnerOfferResponse {
+    pub id: String,
+    pub partner: String,
+    pub kind: String,
+    pub city: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub link: String,
+}
+
+impl PartnerOfferResponse {
+    pub fn tracked(offer: PartnerOffer, booking_id: &str, config: &PartnerConfig) -> Self {
+        let id = offer.id.map(|id| id.to_hex()).unwrap_or_default();
+        Self {
+            link: tracked_link(config, &id, booking_id),
+            id,
+            partner: offer.partner,
+            kind: offer.kind,
+            city: offer.city,
+            title: offer.title,
+            description: offer.description,
+        }
+    }
+}
+
+// One followed link; a conversion reported by the partner is recorded on it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartnerClick {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub offer_id: ObjectId,
+    pub partner: String,
+    pub booking_id: ObjectId,
+    pub clicked_at: bson::DateTime,
+    #[serde(default)]
+    pub converted_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub amount: Option<f64>,
+    #[serde(default)]
+    pub partner_reference: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PartnerClickQuery {
+    pub booking: String,
+    pub sig: String,
+}
+
+// Sent by a partner when a passenger it received from us buys something
+#[derive(Deserialize, ToSchema)]
+pub struct PartnerConversionRequest {
+    // The `ref` the partner landing URL carried
+    #[serde(rename = "ref")]
+    pub click_id: String,
+    pub amount: f64,
+    #[serde(default)]
+    pub partner_reference: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PartnerReportQuery {
+    pub days: Option<i64>,
+    pub partner: Option<String>,
+}
+
+// Per-offer totals partners reconcile their commission statements against
+#[derive(Serialize, Deserialize)]
+pub struct PartnerReportRow {
+    pub offer_id: String,
+    pub partner: String,
+    pub clicks: i64,
+    pub conversions: i64,
+    pub revenue: f64,
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bus::{local_offset, Bus};
+use super::payment::{mpesa_phone, PaymentResponse};
+
+pub const PASS_PENDING: &str = "PendingPayment";
+pub const PASS_ACTIVE: &str = "Active";
+pub const PASS_FAILED: &str = "PaymentFailed";
+
+// One ride taken on a pass; cancelling the booking gives the ride back
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PassRedemption {
+    pub booking_id: ObjectId,
+    pub at: bson::DateTime,
+}
+
+// A commuter's bundle of prepaid rides from one place to another
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Pass {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub from: String,
+    pub to: String,
+    // The single fare the pass was priced from; buses dearer than that aren't covered
+    pub ride_fare: f64,
+    pub rides_total: i32,
+    pub rides_left: i32,
+    pub price: f64,
+    pub status: String, // PendingPayment, Active, PaymentFailed
+    #[serde(default)]
+    pub redemptions: Vec<PassRedemption>,
+    pub created_at: bson::DateTime,
+    // Both set once it is paid for
+    pub activated_at: Option<bson::DateTime>,
+    pub expires_at: Option<bson::DateTime>,
+}
+
+fn to_utc(time: bson::DateTime) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(time.timestamp_millis())
+}
+
+impl Pass {
+    /// What the pass is good for now: `pending_payment`, `payment_failed`, `active`, `used_up`
+    /// or `expired`.
+    pub fn state(&self, now: DateTime<Utc>) -> &'static str {
+        match self.status.as_str() {
+            PASS_PENDING => "pending_payment",
+            PASS_ACTIVE if self.expires_at.and_then(to_utc).is_none_or(|e| e <= now) => "expired",
+            PASS_ACTIVE if self.rides_left <= 0 => "used_up",
+            PASS_ACTIVE => "active",
+            _ => "payment_failed",
+        }
+    }
+
+    /// Why `rides` rides on the bus on that date can't come off the pass, if they can't.
+    pub fn check_ride(&self, bus: &Bus, travel_date: &str, rides: usize, now: DateTime<Utc>) -> Result<(), String> {
+        match self.state(now) {
+            "active" => {}
+            "expired" => return Err("This pass has expired".to_string()),
+            "used_up" => return Err("This pass has no rides left".to_string()),
+            _ => return Err("This pass hasn't been paid for".to_string()),
+        }
+        if !(bus.route.from.eq_ignore_ascii_case(&self.from) && bus.route.to.eq_ignore_ascii_case(&self.to)) {
+            return Err(format!("This pass is for {} to {}", self.from, self.to));
+        }
+        if bus.route.price > self.ride_fare {
+            return Err(format!("This pass covers fares up to KES {:.0}; {} costs KES {:.0}", self.ride_fare, bus.bus_number, bus.route.price));
+        }
+        if (self.rides_left as usize) < rides {
+            return Err(format!("This pass has {} ride(s) left", self.rides_left));
+        }
+        let last_day = self.expires_at.and_then(to_utc)
+            .map(|e| e.with_timezone(&local_offset()).format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        if travel_date > last_day.as_str() {
+            return Err(format!("This pass can only be used for trips up to {}", last_day));
+        }
+        Ok(())
+    }
+}
+
+/// The price of a pass of `rides` rides at `discount_percent` off the single fare, in whole
+/// shillings rounded in the commuter's favour.
+pub fn pass_price(ride_fare: f64, rides: i32, discount_percent: f64) -> f64 {
+    (ride_fare * rides as f64 * (100.0 - discount_percent) / 100.0).floor()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BuyPassRequest {
+    pub from: String,
+    pub to: String,
+    // The M-Pesa number to prompt, in any common Kenyan format
+    pub phone: String,
+}
+
+impl BuyPassRequest {
+    /// The phone in 2547.. form, once the request is usable.
+    pub fn validated_phone(&self) -> Result<String, String> {
+        if self.from.trim().is_empty() || self.to.trim().is_empty() {
+            return Err("from and to are required".to_string());
+        }
+        mpesa_phone(&self.phone)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PassResponse {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub ride_fare: f64,
+    pub price: f64,
+    pub rides_total: i32,
+    pub rides_left: i32,
+    pub status: String,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+}
+
+impl PassResponse {
+    pub fn new(pass: Pass, now: DateTime<Utc>) -> Self {
+        Self {
+            id: pass.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: pass.state(now).to_string(),
+            created_at: pass.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            expires_at: pass.expires_at.and_then(|e| e.try_to_rfc3339_string().ok()),
+            from: pass.from,
+            to: pass.to,
+            ride_fare: pass.ride_fare,
+            price: pass.price,
+            rides_total: pass.rides_total,
+            rides_left: pass.rides_left,
+        }
+    }
+}
+
+// A pass waiting on the M-Pesa prompt that pays for it
+#[derive(Serialize, ToSchema)]
+pub struct PassPurchaseResponse {
+    pub pass: PassResponse,
+    pub payment: PaymentResponse,
+}
@@ -0,0 +1,111 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::booking::MAX_SEATS_PER_BOOKING;
+use super::fraud::normalize_blocklist_value;
+
+pub const PAYMENT_PENDING: &str = "Pending";
+pub const PAYMENT_SUCCEEDED: &str = "Succeeded";
+pub const PAYMENT_FAILED: &str = "Failed";
+
+// One attempt to collect the fare for some bookings; a customer may retry after a failure, so a
+// booking can have several
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Payment {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub booking_ids: Vec<ObjectId>,
+    // Set instead of bookings when the payment buys a commuter pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pass_id: Option<ObjectId>,
+    // Or when it is the deposit on a charter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charter_id: Option<ObjectId>,
+    pub provider: String, // mpesa
+    pub phone: String,
+    pub amount: f64,
+    pub status: String, // Pending, Succeeded, Failed
+    pub merchant_request_id: String,
+    pub checkout_request_id: String,
+    #[serde(default)]
+    pub receipt_number: Option<String>,
+    #[serde(default)]
+    pub result: Option<String>,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+/// Whether `paid` settles `due`, allowing for rounding to the cent.
+pub fn pays_for(paid: f64, due: f64) -> bool {
+    paid + 0.005 >= due
+}
+
+/// What one booking costs the customer: the fare recorded when it was booked, which promotions
+/// or a later price change can leave away from today's list price, or the list price for
+/// bookings made before fares were recorded.
+pub fn booking_fare_due(recorded_fare: Option<f64>, list_price: Option<f64>) -> Option<f64> {
+    recorded_fare.or(list_price)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct StkPushRequest {
+    pub booking_ids: Vec<String>,
+    // The M-Pesa number to prompt, in any common Kenyan format
+    pub phone: String,
+}
+
+impl StkPushRequest {
+    /// The phone in 2547.. form, once the request is usable.
+    pub fn validated_phone(&self) -> Result<String, String> {
+        if self.booking_ids.is_empty() || self.booking_ids.len() > MAX_SEATS_PER_BOOKING {
+            return Err(format!("Pay for between 1 and {} bookings at a time", MAX_SEATS_PER_BOOKING));
+        }
+        mpesa_phone(&self.phone)
+    }
+}
+
+/// A phone in any common Kenyan format, in the 2547.. form M-Pesa prompts.
+pub fn mpesa_phone(phone: &str) -> Result<String, String> {
+    let phone = normalize_blocklist_value("phone", phone);
+    if phone.len() != 12 || !(phone.starts_with("2547") || phone.starts_with("2541")) {
+        return Err("phone must be a Kenyan mobile number".to_string());
+    }
+    Ok(phone)
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PaymentResponse {
+    pub id: String,
+    pub status: String,
+    pub amount: f64,
+    pub phone: String,
+    pub booking_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pass_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charter_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    pub created_at: String,
+}
+
+impl From<Payment> for PaymentResponse {
+    fn from(payment: Payment) -> Self {
+        Self {
+            id: payment.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: payment.status,
+            amount: payment.amount,
+            phone: payment.phone,
+            booking_ids: payment.booking_ids.iter().map(|id| id.to_hex()).collect(),
+            pass_id: payment.pass_id.map(|id| id.to_hex()),
+            charter_id: payment.charter_id.map(|id| id.to_hex()),
+            receipt_number: payment.receipt_number,
+            result: payment.result,
+            created_at: payment.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::payments::ProviderToken;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PaymentMethod {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub provider: String,
+    pub token: ProviderToken,
+    pub brand: String,
+    pub last4: Option<String>,
+    pub exp_month: Option<i32>,
+    pub exp_year: Option<i32>,
+    pub created_at: bson::DateTime,
+}
+
+// Every read or change of a saved method, for PCI access reviews
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VaultAccess {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub payment_method_id: Option<ObjectId>,
+    pub action: String, // list, save, remove
+    pub actor: String,
+    pub provider: Option<String>,
+    pub at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SavePaymentMethodRequest {
+    pub provider: String,
+    pub token: ProviderToken,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct VaultAuditQuery {
+    pub user_id: Option<String>,
+}
+
+// The provider token stays server-side
+#[derive(Serialize, ToSchema)]
+pub struct PaymentMethodResponse {
+    pub id: String,
+    pub provider: String,
+    pub brand: String,
+    pub last4: Option<String>,
+    pub exp_month: Option<i32>,
+    pub exp_year: Option<i32>,
+    pub created_at: String,
+}
+
+impl From<PaymentMethod> for PaymentMethodResponse {
+    fn from(m: PaymentMethod) -> Self {
+        Self {
+            id: m.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            provider: m.provider,
+            brand: m.brand,
+            last4: m.last4,
+            exp_month: m.exp_month,
+            exp_year: m.exp_year,
+            created_at: m.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VaultAccessResponse {
+    pub user_id: String,
+    pub payment_method_id: Option<String>,
+    pub action: String,
+    pub actor: String,
+    pub provider: Option<String>,
+    pub at: String,
+}
+
+impl From<VaultAccess> for VaultAccessResponse {
+    fn from(a: VaultAccess) -> Self {
+        Self {
+            user_id: a.user_id.to_hex(),
+            payment_method_id: a.payment_method_id.map(|oid| oid.to_hex()),
+            action: a.action,
+            actor: a.actor,
+            provider: a.provider,
+            at: a.at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use super::localization::document_text_with;
+
+// Bare-bones PDF 1.4 writer for print-ready text documents: A4 landscape, Courier, one text block per page
+const PAGE_WIDTH: f32 = 842.0;
+const PAGE_HEIGHT: f32 = 595.0;
+const MARGIN: f32 = 36.0;
+const FONT_SIZE: f32 = 8.0;
+const LEADING: f32 = 10.0;
+
+// E-tickets go on an A6 portrait page
+const TICKET_WIDTH: f32 = 298.0;
+const TICKET_HEIGHT: f32 = 420.0;
+const TICKET_MARGIN: f32 = 18.0;
+const QR_MAX_MODULE: f32 = 5.0;
+const TICKET_CHARS_PER_LINE: usize = ((TICKET_WIDTH - 2.0 * TICKET_MARGIN) / (FONT_SIZE * 0.6)) as usize;
+
+pub const LINES_PER_PAGE: usize = ((PAGE_HEIGHT - 2.0 * MARGIN) / LEADING) as usize;
+// Courier glyphs are 0.6em wide
+pub const CHARS_PER_LINE: usize = ((PAGE_WIDTH - 2.0 * MARGIN) / (FONT_SIZE * 0.6)) as usize;
+
+/// Lays `lines` out over as many pages as needed, repeating `header` at the top of each page.
+/// Page numbers in the footer are worded in `language`.
+pub fn render_text_pdf(header: &[String], lines: &[String], language: &str) -> Vec<u8> {
+    let body_lines = LINES_PER_PAGE.saturating_sub(header.len() + 2).max(1);
+    let chunks: Vec<&[String]> = if lines.is_empty() { vec![&[]] } else { lines.chunks(body_lines).collect() };
+    let page_count = chunks.len();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        Vec::new(), // page tree, filled in once the page object numbers are known
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    let mut kids = Vec::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let mut content = format!("BT /F1 {} Tf {} TL {} {} Td\n", FONT_SIZE, LEADING, MARGIN, PAGE_HEIGHT - MARGIN - FONT_SIZE).into_bytes();
+        let footer = document_text_with(language, "pdf.page", &[
+            ("page", &(index + 1).to_string()),
+            ("pages", &page_count.to_string()),
+        ]);
+        for line in header.iter().chain(chunk.iter()).chain([String::new(), footer].iter()) {
+            content.push(b'(');
+            content.extend(escape(line));
+            content.extend(b") Tj T*\n");
+        }
+        content.extend(b"ET");
+
+        let content_id = objects.len() + 2;
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, content_id
+        ).into_bytes());
+        kids.push(format!("{} 0 R", objects.len()));
+        objects.push(stream);
+    }
+    objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_count).into_bytes();
+    assemble(&objects)
+}
+
+/// A single small portrait page: `lines` of text on top and the QR code's dark modules below,
+/// sized to scan from a phone screen or a print-out.
+pub fn render_ticket_pdf(lines: &[String], qr_modules: &[Vec<bool>]) -> Vec<u8> {
+    let mut content = format!("BT /F1 {} Tf {} TL {} {} Td\n", FONT_SIZE, LEADING, TICKET_MARGIN, TICKET_HEIGHT - TICKET_MARGIN - FONT_SIZE).into_bytes();
+    for line in lines.iter().flat_map(|line| wrap(line, TICKET_CHARS_PER_LINE)) {
+        content.push(b'(');
+        content.extend(escape(&line));
+        content.extend(b") Tj T*\n");
+    }
+    content.extend(b"ET\n0 0 0 rg\n");
+
+    // Four light modules of quiet zone on every side, as scanners expect
+    let size = qr_modules.len() as f32 + 8.0;
+    let module = ((TICKET_WIDTH - 2.0 * TICKET_MARGIN) / size).min(QR_MAX_MODULE);
+    let left = (TICKET_WIDTH - size * module) / 2.0 + 4.0 * module;
+    let top = TICKET_MARGIN + size * module - 4.0 * module;
+    for (row, modules) in qr_modules.iter().enumerate() {
+        for (col, dark) in modules.iter().enumerate() {
+            if *dark {
+                let x = left + col as f32 * module;
+                let y = top - (row + 1) as f32 * module;
+                content.extend(format!("{:.2} {:.2} {:.2} {:.2} re\n", x, y, module, module).into_bytes());
+            }
+        }
+    }
+    content.extend(b"f");
+
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend(content);
+    stream.extend(b"\nendstream");
+    assemble(&[
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [4 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents 5 0 R >>",
+            TICKET_WIDTH, TICKET_HEIGHT
+        ).into_bytes(),
+        stream,
+    ])
+}
+
+// Numbers the objects in order and writes the cross-reference table that points at them
+fn assemble(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref_at = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_at).into_bytes());
+    pdf
+}
+
+// Breaks at spaces so no line runs past `width` characters; a longer word gets a line of its own
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split(' ') {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+// WinAnsi covers Latin-1; anything outside it prints as '?'
+fn escape(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend([b'\\', c as u8]),
+            c if (c as u32) < 0x20 => bytes.push(b' '),
+            c if (c as u32) <= 0xFF => bytes.push(c as u32 as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+/// Left-aligns `value` in a fixed-width column, truncating what doesn't fit.
+pub fn column(value: &str, width: usize) -> String {
+    let truncated: String = value.chars().take(width.saturating_sub(1)).collect();
+    format!("{:<width$}", truncated, width = width)
+}
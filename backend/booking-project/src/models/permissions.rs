@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::Claims;
+
+pub const BOOKINGS_READ: &str = "bookings:read";
+pub const BOOKINGS_WRITE: &str = "bookings:write";
+pub const BOOKINGS_REFUND: &str = "bookings:refund";
+pub const BUSES_WRITE: &str = "buses:write";
+pub const TRIPS_OPERATE: &str = "trips:operate";
+pub const ANALYTICS_READ: &str = "analytics:read";
+pub const FINANCE_READ: &str = "finance:read";
+pub const FINANCE_WRITE: &str = "finance:write";
+pub const FRAUD_MANAGE: &str = "fraud:manage";
+pub const SUPPORT_MANAGE: &str = "support:manage";
+pub const PLATFORM_MANAGE: &str = "platform:manage";
+pub const PERMISSIONS_MANAGE: &str = "permissions:manage";
+pub const STAFF_INVITE: &str = "staff:invite";
+pub const MANIFESTS_OPEN: &str = "manifests:open";
+
+pub const ALL_PERMISSIONS: [&str; 14] = [
+    BOOKINGS_READ,
+    BOOKINGS_WRITE,
+    BOOKINGS_REFUND,
+    BUSES_WRITE,
+    TRIPS_OPERATE,
+    ANALYTICS_READ,
+    FINANCE_READ,
+    FINANCE_WRITE,
+    FRAUD_MANAGE,
+    SUPPORT_MANAGE,
+    PLATFORM_MANAGE,
+    PERMISSIONS_MANAGE,
+    STAFF_INVITE,
+    MANIFESTS_OPEN,
+];
+
+/// The role/permission matrix; unknown roles get nothing.
+pub fn permissions_for(role: &str) -> Vec<&'static str> {
+    match role {
+        "admin" => ALL_PERMISSIONS.to_vec(),
+        "operator_admin" => vec![BOOKINGS_READ, BOOKINGS_WRITE, BUSES_WRITE, TRIPS_OPERATE, ANALYTICS_READ, FINANCE_READ, STAFF_INVITE, MANIFESTS_OPEN],
+        // Runs the operator's own fleet day to day, without its money or staff
+        "operator" => vec![BOOKINGS_READ, BUSES_WRITE, TRIPS_OPERATE, MANIFESTS_OPEN],
+        "finance" => vec![BOOKINGS_READ, BOOKINGS_REFUND, ANALYTICS_READ, FINANCE_READ, FINANCE_WRITE],
+        "agent" => vec![BOOKINGS_READ, BOOKINGS_WRITE, SUPPORT_MANAGE],
+        // Everything else a conductor does goes through the trip token issued with the manifest
+        "conductor" => vec![MANIFESTS_OPEN],
+        _ => Vec::new(),
+    }
+}
+
+/// The single place permission checks go through.
+pub fn authorize(claims: &Claims, permission: &str) -> bool {
+    // Tokens minted before scopes were embedded fall back to the role's current permissions
+    if claims.scopes.is_empty() {
+        return permissions_for(&claims.role).contains(&permission);
+    }
+    claims.scopes.iter().any(|s| s == permission)
+}
+
+/// Permission an `/api/admin` request needs, from its method and the path below the admin scope.
+pub fn admin_permission(method: &str, path: &str) -> &'static str {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let read = method == "GET";
+    match segments[0] {
+        "bookings" if read => BOOKINGS_READ,
+        "bookings" => BOOKINGS_WRITE,
+        "charters" if read => BOOKINGS_READ,
+        "charters" => BUSES_WRITE,
+        "refunds" => BOOKINGS_REFUND,
+        "buses" | "policies" | "gtfs" => BUSES_WRITE,
+        "trips" => match segments.get(3).copied() {
+            Some("manifest" | "manifest.pdf") => MANIFESTS_OPEN,
+            Some("announcements" | "incidents" | "status" | "crew") => TRIPS_OPERATE,
+            _ => BUSES_WRITE,
+        },
+        "incidents" | "waiting-rooms" | "parcels" | "devices" => TRIPS_OPERATE,
+        "analytics" if segments.get(1) == Some(&"revenue") => FINANCE_READ,
+        "analytics" => ANALYTICS_READ,
+        "regulatory" if read => ANALYTICS_READ,
+        "operators" if matches!(segments.get(2), Some(&"settings" | &"booking-email")) => BUSES_WRITE,
+        "settlements" | "commission-rules" | "ledger" | "operators" | "payment-methods" | "webhooks" | "partner-offers" if read => FINANCE_READ,
+        "settlements" | "commission-rules" | "ledger" | "operators" | "payment-methods" | "webhooks" | "partner-offers" => FINANCE_WRITE,
+        "blocklist" => FRAUD_MANAGE,
+        "lost-items" | "found-items" | "notifications" => SUPPORT_MANAGE,
+        "users" if matches!(segments.get(1), Some(&"duplicates" | &"merge")) => SUPPORT_MANAGE,
+        "permissions" | "users" => PERMISSIONS_MANAGE,
+        "invitations" => STAFF_INVITE,
+        // dead letters, experiments, localizations and anything new default to the narrowest grant
+        _ => PLATFORM_MANAGE,
+    }
+}
+
+#[derive(Serialize)]
+pub struct RolePermissions {
+    pub role: String,
+    pub permissions: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct EffectivePermissions {
+    pub user_id: String,
+    pub email: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    pub permissions: Vec<&'static str>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignRoleRequest {
+    pub role: String,
+    // Required for operator-scoped roles
+    #[serde(default)]
+    pub operator: Option<String>,
+}
@@ -0,0 +1,107 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::refund::CancellationPolicy;
+
+pub const REFUND_METHODS: [&str; 3] = ["original_payment", "mpesa", "voucher"];
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct AmendmentPolicy {
+    pub fee: f64,
+    // Changes are not accepted closer to departure than this
+    pub min_hours_before: i64,
+}
+
+impl Default for AmendmentPolicy {
+    fn default() -> Self {
+        Self { fee: 200.0, min_hours_before: 6 }
+    }
+}
+
+impl AmendmentPolicy {
+    pub fn accepts(&self, hours_before: i64) -> bool {
+        hours_before >= self.min_hours_before
+    }
+}
+
+// Resolved per booking: trip override, then route, then the operator default, then the built-in terms
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Policy {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    pub operator: Option<String>, // operator this is the default for, if any
+    pub cancellation: CancellationPolicy,
+    pub amendment: AmendmentPolicy,
+    pub refund_method: String,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+impl Policy {
+    pub fn builtin() -> Self {
+        Self {
+            id: None,
+            name: "Standard terms".to_string(),
+            operator: None,
+            cancellation: CancellationPolicy::default(),
+            amendment: AmendmentPolicy::default(),
+            refund_method: "original_payment".to_string(),
+            created_at: bson::DateTime::now(),
+            updated_at: bson::DateTime::now(),
+        }
+    }
+
+    /// Identifies the exact revision, so a booking can be matched to the terms it was sold under.
+    pub fn version(&self) -> String {
+        match self.id {
+            Some(id) => format!("{}@{}", id.to_hex(), self.updated_at.timestamp_millis()),
+            None => "standard".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PolicyRequest {
+    pub name: String,
+    pub operator: Option<String>,
+    pub cancellation: CancellationPolicy,
+    pub amendment: AmendmentPolicy,
+    pub refund_method: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignPolicyRequest {
+    pub policy_id: Option<String>, // null clears the assignment
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PolicyDateQuery {
+    pub date: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PolicyResponse {
+    pub id: Option<String>,
+    pub name: String,
+    pub operator: Option<String>,
+    pub cancellation: CancellationPolicy,
+    pub amendment: AmendmentPolicy,
+    pub refund_method: String,
+    pub updated_at: String,
+}
+
+impl From<Policy> for PolicyResponse {
+    fn from(p: Policy) -> Self {
+        Self {
+            id: p.id.map(|oid| oid.to_hex()),
+            name: p.name,
+            operator: p.operator,
+            cancellation: p.cancellation,
+            amendment: p.amendment,
+            refund_method: p.refund_method,
+            updated_at: p.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use super::bus::local_offset;
+
+/// Today's date in schedule time, the day a price observation counts towards.
+pub fn price_day(at: chrono::DateTime<chrono::Utc>) -> String {
+    at.with_timezone(&local_offset()).format("%Y-%m-%d").to_string()
+}
+
+// A route's base fare over one day: the last one seen, and the range it moved in
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RoutePrice {
+    // Route slug, as in the routes feed
+    pub route: String,
+    pub date: String,
+    pub from: String,
+    pub to: String,
+    pub price: f64,
+    pub lowest: f64,
+    pub highest: f64,
+    pub currency: String,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PriceHistoryQuery {
+    pub days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct PricePoint {
+    pub date: String,
+    pub price: f64,
+    pub lowest: f64,
+    pub highest: f64,
+}
+
+impl From<RoutePrice> for PricePoint {
+    fn from(p: RoutePrice) -> Self {
+        Self { date: p.date, price: p.price, lowest: p.lowest, highest: p.highest }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PriceHistoryResponse {
+    pub route: String,
+    pub from: String,
+    pub to: String,
+    pub currency: String,
+    pub days: i64,
+    // Oldest first; days nobody recorded a price are absent
+    pub prices: Vec<PricePoint>,
+}
+
+impl PriceHistoryResponse {
+    pub fn new(days: i64, prices: Vec<RoutePrice>) -> Option<Self> {
+        let latest = prices.last()?.clone();
+        Some(Self {
+            route: latest.route,
+            from: latest.from,
+            to: latest.to,
+            currency: latest.currency,
+            days,
+            prices: prices.into_iter().map(PricePoint::from).collect(),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// A departure within this many minutes of schedule counts as on time
+pub const ON_TIME_THRESHOLD_MINUTES: i64 = 15;
+
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct Punctuality {
+    pub trips_measured: u32,
+    pub on_time_trips: u32,
+    pub on_time_percent: f64,
+    pub average_delay_minutes: f64,
+}
+
+impl Punctuality {
+    pub fn record(&mut self, delay_minutes: i64) {
+        let total_delay = self.total_delay() + delay_minutes.max(0) as f64;
+        self.trips_measured += 1;
+        if delay_minutes <= ON_TIME_THRESHOLD_MINUTES {
+            self.on_time_trips += 1;
+        }
+        self.recompute(total_delay);
+    }
+
+    pub fn merge(&mut self, other: &Punctuality) {
+        let total_delay = self.total_delay() + other.total_delay();
+        self.trips_measured += other.trips_measured;
+        self.on_time_trips += other.on_time_trips;
+        self.recompute(total_delay);
+    }
+
+    fn total_delay(&self) -> f64 {
+        self.average_delay_minutes * self.trips_measured as f64
+    }
+
+    fn recompute(&mut self, total_delay: f64) {
+        if self.trips_measured == 0 {
+            return;
+        }
+        self.on_time_percent = (self.on_time_trips as f64 * 1000.0 / self.trips_measured as f64).round() / 10.0;
+        self.average_delay_minutes = (total_delay / self.trips_measured as f64 * 10.0).round() / 10.0;
+    }
+}
+
+#[derive(Serialize)]
+pub struct OperatorPunctuality {
+    pub operator: String,
+    #[serde(flatten)]
+    pub stats: Punctuality,
+}
+
+#[derive(Serialize)]
+pub struct RoutePunctuality {
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub stats: Punctuality,
+}
+
+#[derive(Serialize)]
+pub struct PunctualityReport {
+    pub days: i64,
+    pub by_operator: Vec<OperatorPunctuality>,
+    pub by_route: Vec<RoutePunctuality>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct PunctualityQuery {
+    pub days: Option<i64>,
+}
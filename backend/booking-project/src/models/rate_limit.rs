@@ -0,0 +1,147 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Start of the fixed window `now_secs` falls in.
+pub fn window_start(now_secs: i64, window_secs: i64) -> i64 {
+    now_secs - now_secs.rem_euclid(window_secs.max(1))
+}
+
+/// Callers are counted as `user:<id>` when signed in and `ip:<address>` otherwise.
+pub fn is_rate_limit_key(key: &str) -> bool {
+    key.split_once(':').is_some_and(|(kind, id)| matches!(kind, "user" | "ip") && !id.trim().is_empty())
+}
+
+// Requests one caller made in one window
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimitCounter {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub key: String,
+    pub window_start: i64, // Unix seconds
+    pub count: i64,
+    pub expires_at: bson::DateTime,
+}
+
+// A temporary limit for one caller, e.g. a partner running a bulk import
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimitOverride {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub key: String,
+    pub limit: i64,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: bson::DateTime,
+    pub expires_at: bson::DateTime,
+}
+
+// A caller refused outright for kept hammering after being throttled
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AbuseBlock {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub key: String,
+    pub hits: i64,
+    pub blocked_at: bson::DateTime,
+    pub blocked_until: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LimitOverrideRequest {
+    pub key: String,
+    pub limit: i64,
+    pub hours: i64,
+    pub reason: String,
+}
+
+impl LimitOverrideRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_rate_limit_key(&self.key) {
+            return Err("key must be user:<id> or ip:<address>".to_string());
+        }
+        if self.limit < 1 {
+            return Err("limit must be at least 1".to_string());
+        }
+        // Raises are meant to be temporary; anything longer belongs in the configuration
+        if !(1..=24 * 30).contains(&self.hours) {
+            return Err("hours must be between 1 and 720".to_string());
+        }
+        if self.reason.trim().is_empty() {
+            return Err("reason is required".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RateLimitKeyQuery {
+    pub key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RateLimitUsageResponse {
+    pub key: String,
+    pub window_start: String,
+    pub count: i64,
+    pub limit: i64,
+    pub remaining: i64,
+}
+
+impl RateLimitUsageResponse {
+    pub fn new(counter: RateLimitCounter, limit: i64) -> Self {
+        Self {
+            window_start: chrono::DateTime::from_timestamp(counter.window_start, 0).map(|d| d.to_rfc3339()).unwrap_or_default(),
+            remaining: (limit - counter.count).max(0),
+            key: counter.key,
+            count: counter.count,
+            limit,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RateLimitOverrideResponse {
+    pub id: Option<String>,
+    pub key: String,
+    pub limit: i64,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+impl From<RateLimitOverride> for RateLimitOverrideResponse {
+    fn from(o: RateLimitOverride) -> Self {
+        Self {
+            id: o.id.map(|id| id.to_hex()),
+            key: o.key,
+            limit: o.limit,
+            reason: o.reason,
+            created_by: o.created_by,
+            created_at: o.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            expires_at: o.expires_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AbuseBlockResponse {
+    pub id: Option<String>,
+    pub key: String,
+    pub hits: i64,
+    pub blocked_at: String,
+    pub blocked_until: String,
+}
+
+impl From<AbuseBlock> for AbuseBlockResponse {
+    fn from(b: AbuseBlock) -> Self {
+        Self {
+            id: b.id.map(|id| id.to_hex()),
+            key: b.key,
+            hits: b.hits,
+            blocked_at: b.blocked_at.try_to_rfc3339_string().unwrap_or_default(),
+            blocked_until: b.blocked_until.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
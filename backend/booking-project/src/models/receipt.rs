@@ -0,0 +1,88 @@
+use super::email_template::EmailTemplate;
+use super::localization::{document_text, document_text_with};
+use super::pdf::render_text_pdf;
+
+/// A booking as printed for the passenger, with place names already in `language`.
+pub struct BookingReceipt {
+    pub reference: String,
+    pub passenger: String,
+    pub seat_number: String,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub departure: String,
+    pub fare: f64,
+    pub status: String,
+    pub booked_on: String,
+    pub language: String,
+}
+
+impl BookingReceipt {
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let t = |key: &str| document_text(&self.language, key);
+        let header = vec![
+            format!("{} - {}", t("receipt.title"), self.reference),
+            String::new(),
+        ];
+        let lines = vec![
+            format!("{}: {}", t("receipt.reference"), self.reference),
+            format!("{}: {}", t("manifest.passenger"), self.passenger),
+            format!("{}: {}", t("manifest.seat"), self.seat_number),
+            format!("{}: {} ({})", t("receipt.bus"), self.bus_number, self.bus_type),
+            format!("{}: {} {} {}", t("manifest.route"), self.from, t("manifest.to"), self.to),
+            format!("{}: {}   {}: {}", t("manifest.date"), self.travel_date, t("manifest.departure"), self.departure),
+            format!("{}: KES {:.2}", t("receipt.fare"), self.fare),
+            format!("{}: {}", t("receipt.status"), self.status_label()),
+            format!("{}: {}", t("receipt.booked_on"), self.booked_on),
+            String::new(),
+            t("receipt.arrive_early"),
+        ];
+        render_text_pdf(&header, &lines, &self.language)
+    }
+
+    fn status_label(&self) -> String {
+        match document_text(&self.language, &format!("status.{}", self.status)) {
+            label if label.starts_with("status.") => self.status.clone(),
+            label => label,
+        }
+    }
+
+    fn template_values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("reference", self.reference.clone()),
+            ("passenger", self.passenger.clone()),
+            ("seat", self.seat_number.clone()),
+            ("bus", self.bus_number.clone()),
+            ("bus_type", self.bus_type.clone()),
+            ("from", self.from.clone()),
+            ("to", self.to.clone()),
+            ("date", self.travel_date.clone()),
+            ("time", self.departure.clone()),
+            ("fare", format!("KES {:.2}", self.fare)),
+            ("status", self.status_label()),
+        ]
+    }
+
+    /// The confirmation text message, short enough for a single SMS in either language.
+    pub fn confirmation_sms(&self) -> String {
+        let values = self.template_values();
+        let values: Vec<(&str, &str)> = values.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        document_text_with(&self.language, "sms.booking_confirmed", &values)
+    }
+
+    /// Subject and body of the confirmation email, in the operator's wording when they set
+    /// their own.
+    pub fn confirmation_email(&self, template: Option<&EmailTemplate>) -> (String, String) {
+        let values = self.template_values();
+        let values: Vec<(&str, &str)> = values.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        match template {
+            Some(template) => template.render(&values),
+            None => (
+                document_text_with(&self.language, "email.booking_confirmed.subject", &values),
+                document_text_with(&self.language, "email.booking_confirmed.body", &values),
+            ),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct RefundTier {
+    pub min_hours_before: i64,
+    pub refund_percent: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct CancellationPolicy {
+    // Ordered from the most generous tier down
+    pub tiers: Vec<RefundTier>,
+}
+
+impl Default for CancellationPolicy {
+    fn default() -> Self {
+        Self {
+            tiers: vec![
+                RefundTier { min_hours_before: 24, refund_percent: 80.0 },
+                RefundTier { min_hours_before: 6, refund_percent: 50.0 },
+            ],
+        }
+    }
+}
+
+impl CancellationPolicy {
+    /// Refund percentage for a cancellation made `hours_before` departure, if any tier applies.
+    pub fn refund_percent(&self, hours_before: i64) -> Option<f64> {
+        self.tiers
+            .iter()
+            .find(|t| hours_before >= t.min_hours_before)
+            .map(|t| t.refund_percent)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RefundRequest {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub booking_id: ObjectId,
+    pub user_id: ObjectId,
+    pub fare: f64,
+    pub refund_percent: f64,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub status: String, // Requested, Approved, Rejected, Paid
+    #[serde(default)]
+    pub refund_method: Option<String>,
+    pub admin_note: Option<String>,
+    pub payout_reference: Option<String>,
+    pub requested_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateRefundRequest {
+    pub booking_id: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefundDecisionRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefundPayoutRequest {
+    pub payout_reference: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RefundStatusQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RefundResponse {
+    pub id: String,
+    pub booking_id: String,
+    pub user_id: String,
+    pub fare: f64,
+    pub refund_percent: f64,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub status: String,
+    pub refund_method: Option<String>,
+    pub admin_note: Option<String>,
+    pub payout_reference: Option<String>,
+    pub requested_at: String,
+    pub updated_at: String,
+}
+
+impl From<RefundRequest> for RefundResponse {
+    fn from(r: RefundRequest) -> Self {
+        Self {
+            id: r.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            booking_id: r.booking_id.to_hex(),
+            user_id: r.user_id.to_hex(),
+            fare: r.fare,
+            refund_percent: r.refund_percent,
+            amount: r.amount,
+            reason: r.reason,
+            status: r.status,
+            refund_method: r.refund_method,
+            admin_note: r.admin_note,
+            payout_reference: r.payout_reference,
+            requested_at: r.requested_at.try_to_rfc3339_string().unwrap_or_default(),
+            updated_at: r.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
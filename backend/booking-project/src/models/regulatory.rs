@@ -0,0 +1,208 @@
+use chrono::{Datelike, NaiveDate};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// Trip values a jurisdiction's columns can be mapped from
+pub const REPORT_FIELDS: [&str; 13] = [
+    "operator", "vehicle", "route_from", "route_to", "travel_date", "departure", "status", "seats",
+    "passengers", "checked_in", "delay_minutes", "incidents", "serious_incidents",
+];
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct FieldMapping {
+    pub header: String,
+    pub field: String,
+}
+
+// How one regulator wants the return laid out; stored mappings override the built-in ones
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JurisdictionMapping {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub code: String,
+    pub name: String,
+    pub delimiter: String,
+    pub columns: Vec<FieldMapping>,
+    pub updated_at: Option<bson::DateTime>,
+}
+
+impl JurisdictionMapping {
+    /// The NTSA monthly return, used for Kenya until an admin stores a different layout.
+    pub fn builtin(code: &str) -> Option<Self> {
+        if !code.eq_ignore_ascii_case("KE") {
+            return None;
+        }
+        let columns = [
+            ("Operator/SACCO", "operator"),
+            ("Vehicle Reg No", "vehicle"),
+            ("Origin", "route_from"),
+            ("Destination", "route_to"),
+            ("Date of Travel", "travel_date"),
+            ("Departure Time", "departure"),
+            ("Licensed Capacity", "seats"),
+            ("Passengers Carried", "passengers"),
+            ("Incidents Reported", "incidents"),
+        ];
+        Some(Self {
+            id: None,
+            code: "KE".to_string(),
+            name: "National Transport and Safety Authority".to_string(),
+            delimiter: ",".to_string(),
+            columns: columns.iter().map(|(header, field)| FieldMapping { header: header.to_string(), field: field.to_string() }).collect(),
+            updated_at: None,
+        })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct JurisdictionMappingRequest {
+    pub name: String,
+    pub delimiter: Option<String>,
+    pub columns: Vec<FieldMapping>,
+}
+
+impl JurisdictionMappingRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.delimiter.as_deref().is_some_and(|d| d.chars().count() != 1) {
+            return Err("delimiter must be a single character".to_string());
+        }
+        if self.columns.is_empty() {
+            return Err("At least one column is required".to_string());
+        }
+        if let Some(column) = self.columns.iter().find(|c| !REPORT_FIELDS.contains(&c.field.as_str())) {
+            return Err(format!("Unknown field '{}', expected one of: {}", column.field, REPORT_FIELDS.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+// One trip operated in the period
+#[derive(Default)]
+pub struct TripReportRow {
+    pub operator: String,
+    pub vehicle: String,
+    pub route_from: String,
+    pub route_to: String,
+    pub travel_date: String,
+    pub departure: String,
+    pub status: String,
+    pub seats: i32,
+    pub passengers: i64,
+    pub checked_in: i64,
+    pub delay_minutes: i64,
+    pub incidents: i64,
+    pub serious_incidents: i64,
+}
+
+impl TripReportRow {
+    fn field(&self, name: &str) -> String {
+        match name {
+            "operator" => self.operator.clone(),
+            "vehicle" => self.vehicle.clone(),
+            "route_from" => self.route_from.clone(),
+            "route_to" => self.route_to.clone(),
+            "travel_date" => self.travel_date.clone(),
+            "departure" => self.departure.clone(),
+            "status" => self.status.clone(),
+            "seats" => self.seats.to_string(),
+            "passengers" => self.passengers.to_string(),
+            "checked_in" => self.checked_in.to_string(),
+            "delay_minutes" => self.delay_minutes.to_string(),
+            "incidents" => self.incidents.to_string(),
+            "serious_incidents" => self.serious_incidents.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn escape(value: &str, delimiter: &str) -> String {
+    if value.contains(delimiter) || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Delimited text with one header line and a line per trip, in the mapping's column order.
+pub fn render_report(mapping: &JurisdictionMapping, rows: &[TripReportRow]) -> String {
+    let delimiter = mapping.delimiter.as_str();
+    let line = |values: Vec<String>| values.iter().map(|v| escape(v, delimiter)).collect::<Vec<_>>().join(delimiter);
+
+    let mut out = line(mapping.columns.iter().map(|c| c.header.clone()).collect());
+    out.push_str("\r\n");
+    for row in rows {
+        out.push_str(&line(mapping.columns.iter().map(|c| row.field(&c.field)).collect()));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// First and last day of the month before `today`, as travel dates.
+pub fn previous_month(today: NaiveDate) -> (String, String) {
+    let first_of_this = today.with_day(1).unwrap_or(today);
+    let last = first_of_this.pred_opt().unwrap_or(first_of_this);
+    let first = last.with_day(1).unwrap_or(last);
+    (first.format("%Y-%m-%d").to_string(), last.format("%Y-%m-%d").to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RegulatoryExport {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub jurisdiction: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub trips: i64,
+    pub passengers: i64,
+    pub incidents: i64,
+    pub generated_by: String, // "schedule" or user:<id>
+    pub generated_at: bson::DateTime,
+    // Left out of listings
+    #[serde(default)]
+    pub content: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GenerateExportRequest {
+    pub jurisdiction: String,
+    pub period_start: String,
+    pub period_end: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ExportQuery {
+    pub jurisdiction: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RegulatoryExportResponse {
+    pub id: Option<String>,
+    pub jurisdiction: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub trips: i64,
+    pub passengers: i64,
+    pub incidents: i64,
+    pub generated_by: String,
+    pub generated_at: String,
+}
+
+impl From<RegulatoryExport> for RegulatoryExportResponse {
+    fn from(e: RegulatoryExport) -> Self {
+        Self {
+            id: e.id.map(|id| id.to_hex()),
+            jurisdiction: e.jurisdiction,
+            period_start: e.period_start,
+            period_end: e.period_end,
+            trips: e.trips,
+            passengers: e.passengers,
+            incidents: e.incidents,
+            generated_by: e.generated_by,
+            generated_at: e.generated_at.to_string(),
+        }
+    }
+}
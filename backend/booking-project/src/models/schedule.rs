@@ -0,0 +1,135 @@
+use chrono::{Datelike, NaiveDate};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// Which dates a bus runs its route. A trip is one bus on one of those dates, identified as
+// `<bus_id>:<date>` like everywhere else trips are keyed; buses without a schedule run daily
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Schedule {
+    #[serde(rename = "_id")]
+    pub bus_id: ObjectId,
+    // ISO weekdays, 1 = Monday .. 7 = Sunday; empty means every day
+    pub weekdays: Vec<u32>,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+    // One-off runs outside the pattern, and dates the pattern skips, e.g. public holidays
+    #[serde(default)]
+    pub extra_dates: Vec<String>,
+    #[serde(default)]
+    pub skip_dates: Vec<String>,
+    pub updated_at: bson::DateTime,
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+impl Schedule {
+    pub fn runs_on(&self, travel_date: &str) -> bool {
+        let Some(date) = parse_date(travel_date) else {
+            return false;
+        };
+        if self.skip_dates.iter().any(|d| d == travel_date) {
+            return false;
+        }
+        if self.extra_dates.iter().any(|d| d == travel_date) {
+            return true;
+        }
+        let in_range = self.valid_from.as_deref().and_then(parse_date).is_none_or(|from| date >= from)
+            && self.valid_until.as_deref().and_then(parse_date).is_none_or(|until| date <= until);
+        in_range && (self.weekdays.is_empty() || self.weekdays.contains(&date.weekday().number_from_monday()))
+    }
+}
+
+/// Whether a bus with this schedule, if any, runs on the date.
+pub fn bus_runs_on(schedule: Option<&Schedule>, travel_date: &str) -> bool {
+    schedule.is_none_or(|s| s.runs_on(travel_date))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScheduleRequest {
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+    #[serde(default)]
+    pub extra_dates: Vec<String>,
+    #[serde(default)]
+    pub skip_dates: Vec<String>,
+}
+
+impl ScheduleRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(day) = self.weekdays.iter().find(|d| !(1..=7).contains(*d)) {
+            return Err(format!("weekdays run from 1 (Monday) to 7 (Sunday), not {}", day));
+        }
+        let dates = self.valid_from.iter().chain(&self.valid_until).chain(&self.extra_dates).chain(&self.skip_dates);
+        for date in dates {
+            if parse_date(date).is_none() {
+                return Err(format!("{} is not a date like 2026-12-20", date));
+            }
+        }
+        if let (Some(from), Some(until)) = (&self.valid_from, &self.valid_until) {
+            if from > until {
+                return Err("valid_from must not be after valid_until".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_schedule(self, bus_id: ObjectId) -> Schedule {
+        let mut weekdays = self.weekdays;
+        weekdays.sort_unstable();
+        weekdays.dedup();
+        Schedule {
+            bus_id,
+            weekdays,
+            valid_from: self.valid_from,
+            valid_until: self.valid_until,
+            extra_dates: self.extra_dates,
+            skip_dates: self.skip_dates,
+            updated_at: bson::DateTime::now(),
+        }
+    }
+}
+
+impl From<Schedule> for ScheduleRequest {
+    fn from(s: Schedule) -> Self {
+        Self {
+            weekdays: s.weekdays,
+            valid_from: s.valid_from,
+            valid_until: s.valid_until,
+            extra_dates: s.extra_dates,
+            skip_dates: s.skip_dates,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct TripQuery {
+    pub date: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// One departure on the timetable for a date
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct TripResponse {
+    // `<bus_id>:<date>`
+    pub id: String,
+    pub bus_id: String,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub price: f64,
+    pub status: String,
+    pub delay_minutes: i64,
+    pub seats_left: i32,
+}
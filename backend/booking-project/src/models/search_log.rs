@@ -0,0 +1,42 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchLogEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub departure_time: Option<String>,
+    pub results_count: i64,
+    pub user_id: Option<String>,
+    pub anon_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub created_at: bson::DateTime,
+}
+
+#[derive(Serialize)]
+pub struct PopularRoute {
+    pub from: String,
+    pub to: String,
+    pub searches: i64,
+    pub zero_result_searches: i64,
+}
+
+#[derive(Serialize)]
+pub struct ZeroResultSearch {
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub searches: i64,
+    pub last_searched_at: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct SearchLogQuery {
+    pub days: Option<i64>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bus::OPEN_SEATING;
+
+pub const SEAT_CLASSES: [&str; 3] = ["standard", "business", "vip"];
+pub const MAX_LAYOUT_ROWS: u32 = 30;
+pub const MAX_LAYOUT_COLUMNS: u32 = 6;
+
+// Where each seat sits in the cabin, so clients can draw the seat picker. Rows run front to
+// back and columns left to right as seen from the driver's seat; an aisle runs after each
+// column listed in `aisles`. Positions without a seat are left empty, e.g. for the door
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SeatLayout {
+    pub rows: u32,
+    pub columns: u32,
+    #[serde(default)]
+    pub aisles: Vec<u32>,
+    pub seats: Vec<LayoutSeat>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct LayoutSeat {
+    // The seat number passengers book, e.g. "1A"
+    pub label: String,
+    // Both 1-based
+    pub row: u32,
+    pub column: u32,
+    #[serde(default = "standard_class")]
+    pub class: String,
+}
+
+fn standard_class() -> String {
+    "standard".to_string()
+}
+
+impl SeatLayout {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_LAYOUT_ROWS).contains(&self.rows) {
+            return Err(format!("layout rows must be between 1 and {}", MAX_LAYOUT_ROWS));
+        }
+        if !(1..=MAX_LAYOUT_COLUMNS).contains(&self.columns) {
+            return Err(format!("layout columns must be between 1 and {}", MAX_LAYOUT_COLUMNS));
+        }
+        if let Some(aisle) = self.aisles.iter().find(|a| !(1..self.columns).contains(*a)) {
+            return Err(format!("An aisle can't run after column {}", aisle));
+        }
+        if self.seats.is_empty() {
+            return Err("layout needs at least one seat".to_string());
+        }
+        let mut labels = HashSet::new();
+        let mut positions = HashSet::new();
+        for seat in &self.seats {
+            let label = seat.label.as_str();
+            if label.is_empty() || label.len() > 8 || !label.chars().all(|c| c.is_ascii_alphanumeric()) || label == OPEN_SEATING {
+                return Err(format!("\"{}\" is not a seat label like 1A", label));
+            }
+            if !labels.insert(label) {
+                return Err(format!("Seat {} is in the layout more than once", label));
+            }
+            if !(1..=self.rows).contains(&seat.row) || !(1..=self.columns).contains(&seat.column) {
+                return Err(format!("Seat {} is outside the {}x{} layout", label, self.rows, self.columns));
+            }
+            if !positions.insert((seat.row, seat.column)) {
+                return Err(format!("Seat {} shares row {} column {} with another seat", label, seat.row, seat.column));
+            }
+            if !SEAT_CLASSES.contains(&seat.class.as_str()) {
+                return Err(format!("Seat class must be one of {}", SEAT_CLASSES.join(", ")));
+            }
+        }
+        Ok(())
+    }
+
+    /// The seat labels from front to back.
+    pub fn labels(&self) -> Vec<String> {
+        let mut seats: Vec<&LayoutSeat> = self.seats.iter().collect();
+        seats.sort_by_key(|s| (s.row, s.column));
+        seats.into_iter().map(|s| s.label.clone()).collect()
+    }
+
+    pub fn has_seat(&self, label: &str) -> bool {
+        self.seats.iter().any(|s| s.label == label)
+    }
+}
@@ -0,0 +1,125 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Rounds a KES amount to cents.
+pub fn round_money(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+// How one booking's fare divides between the operator and the platform
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BookingSplit {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub booking_id: ObjectId,
+    pub bus_id: ObjectId,
+    pub operator: String,
+    pub travel_date: String,
+    pub fare: f64,
+    pub commission: f64,
+    pub operator_share: f64,
+    // Commission rule in force when the booking was made; None for the built-in default
+    #[serde(default)]
+    pub commission_rule_id: Option<ObjectId>,
+    // Reversals carry negated amounts so a cancelled, already-settled booking nets out next period
+    #[serde(default)]
+    pub reversal: bool,
+    pub settlement_id: Option<ObjectId>,
+    pub recorded_at: bson::DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Settlement {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub operator: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub booking_count: i64,
+    pub gross: f64,
+    pub commission: f64,
+    pub net_payout: f64,
+    pub status: String, // Pending, Paid
+    pub payout_reference: Option<String>,
+    pub created_at: bson::DateTime,
+    pub paid_at: Option<bson::DateTime>,
+}
+
+impl Settlement {
+    /// Totals the settlement over its splits; a reversal counts one booking less.
+    pub fn set_totals(&mut self, splits: &[BookingSplit]) {
+        self.booking_count = splits.iter().map(|s| if s.reversal { -1 } else { 1 }).sum();
+        self.gross = round_money(splits.iter().map(|s| s.fare).sum());
+        self.commission = round_money(splits.iter().map(|s| s.commission).sum());
+        self.net_payout = round_money(splits.iter().map(|s| s.operator_share).sum());
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GenerateSettlementsRequest {
+    pub period_start: String,
+    pub period_end: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SettlementPaidRequest {
+    pub payout_reference: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct SettlementQuery {
+    pub operator: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SettlementResponse {
+    pub id: String,
+    pub operator: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub booking_count: i64,
+    pub gross: f64,
+    pub commission: f64,
+    pub net_payout: f64,
+    pub status: String,
+    pub payout_reference: Option<String>,
+    pub created_at: String,
+    pub paid_at: Option<String>,
+}
+
+impl From<Settlement> for SettlementResponse {
+    fn from(s: Settlement) -> Self {
+        Self {
+            id: s.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            operator: s.operator,
+            period_start: s.period_start,
+            period_end: s.period_end,
+            booking_count: s.booking_count,
+            gross: s.gross,
+            commission: s.commission,
+            net_payout: s.net_payout,
+            status: s.status,
+            payout_reference: s.payout_reference,
+            created_at: s.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            paid_at: s.paid_at.and_then(|d| d.try_to_rfc3339_string().ok()),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct UnsettledTotals {
+    pub booking_count: i64,
+    pub gross: f64,
+    pub commission: f64,
+    pub net_payout: f64,
+}
+
+#[derive(Serialize)]
+pub struct OperatorStatement {
+    pub operator: String,
+    pub unsettled: UnsettledTotals,
+    pub settlements: Vec<SettlementResponse>,
+    pub total_paid: f64,
+}
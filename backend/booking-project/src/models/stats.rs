@@ -0,0 +1,32 @@
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublicStats {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub routes_served: u64,
+    pub trips_this_month: u64,
+    pub seats_booked: u64,
+    pub computed_at: bson::DateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PublicStatsResponse {
+    pub routes_served: u64,
+    pub trips_this_month: u64,
+    pub seats_booked: u64,
+    pub updated_at: String,
+}
+
+impl From<PublicStats> for PublicStatsResponse {
+    fn from(s: PublicStats) -> Self {
+        Self {
+            routes_served: s.routes_served,
+            trips_this_month: s.trips_this_month,
+            seats_booked: s.seats_booked,
+            updated_at: s.computed_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
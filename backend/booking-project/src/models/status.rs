@@ -0,0 +1,127 @@
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Ordered from best to worst; the overall status is the worst component's
+pub const COMPONENT_STATES: [&str; 4] = ["operational", "degraded", "outage", "unknown"];
+
+/// Severity used to pick the overall status; "unknown" never outranks a real reading.
+fn rank(state: &str) -> usize {
+    match state {
+        "unknown" => 0,
+        _ => COMPONENT_STATES.iter().position(|s| *s == state).unwrap_or(0),
+    }
+}
+
+// Last time a background job ran, and how often it's meant to
+#[derive(Serialize, Deserialize)]
+pub struct JobHeartbeat {
+    #[serde(rename = "_id")]
+    pub job: String,
+    pub interval_secs: i64,
+    pub last_run_at: bson::DateTime,
+}
+
+impl JobHeartbeat {
+    /// Seconds the job is overdue past its next expected run.
+    pub fn lag_secs(&self, now: chrono::DateTime<chrono::Utc>) -> i64 {
+        let since = (now.timestamp_millis() - self.last_run_at.timestamp_millis()) / 1000;
+        (since - self.interval_secs).max(0)
+    }
+}
+
+// Public view of one subsystem; deliberately no error text, hostnames or counts
+#[derive(Serialize, Clone, ToSchema)]
+pub struct ComponentStatus {
+    pub name: &'static str,
+    pub status: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatusResponse {
+    pub status: &'static str,
+    pub components: Vec<ComponentStatus>,
+    pub updated_at: String,
+}
+
+impl StatusResponse {
+    pub fn new(components: Vec<ComponentStatus>) -> Self {
+        let status = components.iter().map(|c| c.status).max_by_key(|s| rank(s)).unwrap_or("unknown");
+        Self {
+            status,
+            components,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// When the process started; call once early in main so uptime counts from then.
+pub fn started_at() -> Instant {
+    static STARTED_AT: OnceLock<Instant> = OnceLock::new();
+    *STARTED_AT.get_or_init(Instant::now)
+}
+
+/// The version of this build: BUILD_VERSION as set by the release pipeline, else the crate's.
+pub fn build_version() -> &'static str {
+    option_env!("BUILD_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"))
+}
+
+// Liveness: the process is up and serving, nothing more is checked
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub message: &'static str,
+    pub version: &'static str,
+    pub uptime_secs: u64,
+    pub timestamp: String,
+}
+
+impl HealthResponse {
+    pub fn now() -> Self {
+        Self {
+            status: "ok",
+            message: "Server is running",
+            version: build_version(),
+            uptime_secs: started_at().elapsed().as_secs(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+// One dependency as the readiness probe found it; "up" or "down", never the error itself
+#[derive(Serialize, Clone, ToSchema)]
+pub struct DependencyCheck {
+    pub name: &'static str,
+    pub status: &'static str,
+    pub latency_ms: u64,
+}
+
+// Readiness: whether this instance should get traffic, i.e. every dependency answered
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub uptime_secs: u64,
+    pub dependencies: Vec<DependencyCheck>,
+    pub timestamp: String,
+}
+
+impl ReadinessResponse {
+    pub fn new(dependencies: Vec<DependencyCheck>) -> Self {
+        let ready = dependencies.iter().all(|d| d.status == "up");
+        Self {
+            status: if ready { "ready" } else { "not_ready" },
+            version: build_version(),
+            uptime_secs: started_at().elapsed().as_secs(),
+            dependencies,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
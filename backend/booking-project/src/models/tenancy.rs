@@ -0,0 +1,66 @@
+use mongodb::bson::{doc, oid::ObjectId, Document};
+
+use super::bus::Bus;
+use super::user::Claims;
+
+// These roles only ever act for the operator recorded on their account; other staff may optionally have one
+pub const OPERATOR_SCOPED_ROLES: [&str; 3] = ["operator", "operator_admin", "conductor"];
+
+/// Whose data an admin request may touch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tenant {
+    Platform,
+    Operator(String),
+}
+
+impl Tenant {
+    pub fn from_claims(claims: &Claims) -> Result<Self, &'static str> {
+        if claims.role == "admin" {
+            return Ok(Tenant::Platform);
+        }
+        match claims.operator.as_deref().map(str::trim) {
+            Some(operator) if !operator.is_empty() => Ok(Tenant::Operator(operator.to_string())),
+            _ if OPERATOR_SCOPED_ROLES.contains(&claims.role.as_str()) => Err("No operator is assigned to this account"),
+            _ => Ok(Tenant::Platform),
+        }
+    }
+
+    pub fn owns_operator(&self, operator: &str) -> bool {
+        match self {
+            Tenant::Platform => true,
+            Tenant::Operator(own) => own.eq_ignore_ascii_case(operator.trim()),
+        }
+    }
+
+    pub fn owns_bus(&self, bus: &Bus) -> bool {
+        self.owns_operator(&bus.operator_name())
+    }
+
+    /// Restricts a bookings filter to the tenant's buses. The restriction sits in `$and`
+    /// so caller-supplied keys such as `bus_id` can narrow it but never widen it.
+    pub fn scope_bookings(&self, filter: Document, own_bus_ids: &[ObjectId]) -> Document {
+        match self {
+            Tenant::Platform => filter,
+            Tenant::Operator(_) => doc! { "$and": [filter, { "bus_id": { "$in": own_bus_ids.to_vec() } }] },
+        }
+    }
+
+    /// The operator to report on: operator tenants are pinned to their own regardless of the request.
+    pub fn operator_filter<'a>(&'a self, requested: Option<&'a str>) -> Option<&'a str> {
+        match self {
+            Tenant::Platform => requested,
+            Tenant::Operator(own) => Some(own),
+        }
+    }
+}
+
+/// Admin paths whose handlers scope their queries by tenant; operator accounts are refused everywhere else.
+pub fn is_tenant_aware(method: &str, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["bookings", ..] | ["charters", ..] | ["devices", ..] | ["parcels", ..] | ["trips", ..] | ["buses", ..] | ["invitations", ..] | ["waiting-rooms", ..] => true,
+        ["settlements"] => method == "GET",
+        ["operators", _, "statement" | "settings"] | ["analytics", "revenue"] => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, ToSchema)]
+pub struct Terminal {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DepartureBoardEntry {
+    pub bus_id: String,
+    pub bus_number: String,
+    pub bus_type: String,
+    pub destination: String,
+    pub scheduled_departure: String,
+    pub expected_departure: String,
+    pub status: String,
+    pub delay_minutes: i64,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DepartureBoardResponse {
+    pub terminal: Terminal,
+    pub date: String,
+    pub generated_at: String,
+    pub departures: Vec<DepartureBoardEntry>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct DepartureBoardQuery {
+    pub date: Option<String>,
+}
+
+/// Terminals are identified by a URL-safe slug of the city name, e.g. "nairobi".
+pub fn terminal_id(city: &str) -> String {
+    city.trim()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
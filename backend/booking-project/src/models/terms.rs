@@ -0,0 +1,26 @@
+use mongodb::bson;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const TERMS_ACCEPTANCE_REQUIRED: &str = "TERMS_ACCEPTANCE_REQUIRED";
+
+// Kept on the user for dispute handling; the last entry is the version currently in force for them
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TermsAcceptance {
+    pub version: String,
+    pub context: String, // registration, booking, reacceptance
+    pub accepted_at: bson::DateTime,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TermsResponse {
+    pub version: String,
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accepted_version: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AcceptTermsRequest {
+    pub version: String,
+}
@@ -0,0 +1,110 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use qrcode::{Color, EcLevel, QrCode};
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::localization::document_text;
+use super::pdf::render_ticket_pdf;
+use super::receipt::BookingReceipt;
+
+// Bumped if the payload layout ever changes, so old scanners refuse rather than misread
+const TICKET_PREFIX: &str = "BBT1";
+
+/// What a ticket's QR code vouches for: this booking, on this trip, in this seat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketClaims {
+    pub booking_id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub seat_number: String,
+}
+
+fn signature(key: &str, signed: &str) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()), signed.as_bytes())
+}
+
+/// The QR payload for `claims`: the fields in the clear, then their HMAC, all dot-separated
+/// so a scanner can read it offline and the server can still tell a forgery.
+pub fn sign_ticket(claims: &TicketClaims, key: &str) -> String {
+    let signed = format!("{}.{}.{}.{}.{}", TICKET_PREFIX, claims.booking_id, claims.bus_id, claims.travel_date, claims.seat_number);
+    let tag = signature(key, &signed);
+    format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(tag.as_ref()))
+}
+
+/// The claims in a scanned payload, once its signature checks out.
+pub fn verify_ticket(payload: &str, key: &str) -> Result<TicketClaims, String> {
+    let forged = || "This ticket is not genuine".to_string();
+    let (signed, tag) = payload.trim().rsplit_once('.').ok_or_else(forged)?;
+    let tag = URL_SAFE_NO_PAD.decode(tag).map_err(|_| forged())?;
+    hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()), signed.as_bytes(), &tag).map_err(|_| forged())?;
+
+    // Seat labels are the only field that could hold a dot, so they take whatever is left
+    let fields: Vec<&str> = signed.splitn(5, '.').collect();
+    match fields.as_slice() {
+        [TICKET_PREFIX, booking_id, bus_id, travel_date, seat_number] => Ok(TicketClaims {
+            booking_id: booking_id.to_string(),
+            bus_id: bus_id.to_string(),
+            travel_date: travel_date.to_string(),
+            seat_number: seat_number.to_string(),
+        }),
+        _ => Err(forged()),
+    }
+}
+
+/// SHA-256 of a ticket's QR text, base64url: what the conductor app matches scans against
+/// when it has no signal to verify them.
+pub fn ticket_hash(payload: &str) -> String {
+    URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, payload.trim().as_bytes()))
+}
+
+/// The QR code for `payload` as rows of dark (true) and light modules.
+pub fn qr_modules(payload: &str) -> Result<Vec<Vec<bool>>, String> {
+    let code = QrCode::with_error_correction_level(payload, EcLevel::M).map_err(|e| e.to_string())?;
+    let width = code.width();
+    Ok(code.to_colors()
+        .chunks(width)
+        .map(|row| row.iter().map(|c| *c == Color::Dark).collect())
+        .collect())
+}
+
+/// A confirmed booking's e-ticket: the receipt's details plus the signed QR code the conductor scans.
+pub struct ETicket {
+    pub receipt: BookingReceipt,
+    pub qr_payload: String,
+}
+
+impl ETicket {
+    pub fn to_pdf(&self) -> Result<Vec<u8>, String> {
+        let r = &self.receipt;
+        let t = |key: &str| document_text(&r.language, key);
+        let lines = vec![
+            format!("{} - {}", t("ticket.title"), r.reference),
+            String::new(),
+            format!("{}: {}", t("manifest.passenger"), r.passenger),
+            format!("{}: {}", t("manifest.seat"), r.seat_number),
+            format!("{}: {} ({})", t("receipt.bus"), r.bus_number, r.bus_type),
+            format!("{}: {} {} {}", t("manifest.route"), r.from, t("manifest.to"), r.to),
+            format!("{}: {}", t("manifest.date"), r.travel_date),
+            format!("{}: {}", t("manifest.departure"), r.departure),
+            String::new(),
+            t("ticket.show_code"),
+            t("receipt.arrive_early"),
+        ];
+        Ok(render_ticket_pdf(&lines, &qr_modules(&self.qr_payload)?))
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ScanTicketRequest {
+    // The text read from the ticket's QR code
+    pub qr: String,
+}
+
+// Who the conductor just let on, to check against the person in front of them
+#[derive(Serialize, ToSchema)]
+pub struct ScannedTicketResponse {
+    pub booking_id: String,
+    pub passenger_name: String,
+    pub seat_number: String,
+}
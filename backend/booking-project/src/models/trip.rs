@@ -0,0 +1,161 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Operational status of one bus departing on one date
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripStatus {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub status: String, // scheduled, boarding, delayed, departed, arrived, cancelled
+    pub delay_minutes: i64,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub actual_departure: Option<bson::DateTime>,
+    #[serde(default)]
+    pub actual_arrival: Option<bson::DateTime>,
+    // Overrides the route's policy for this departure only
+    #[serde(default)]
+    pub policy_id: Option<ObjectId>,
+    pub updated_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTripStatusRequest {
+    pub status: String,
+    pub delay_minutes: Option<i64>,
+    pub note: Option<String>,
+    // RFC 3339 time the bus actually departed/arrived (e.g. from GPS); defaults to now
+    pub actual_time: Option<String>,
+}
+
+// Vehicle actually running a trip when it differs from the scheduled bus
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripVehicle {
+    pub bus_number: String,
+    pub total_seats: i32,
+    pub reason: Option<String>,
+    pub swapped_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct VehicleSwapRequest {
+    pub bus_number: String,
+    pub total_seats: i32,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SeatMove {
+    pub booking_id: String,
+    pub from_seat: String,
+    pub to_seat: String,
+}
+
+#[derive(Serialize)]
+pub struct VehicleSwapResult {
+    pub bus_number: String,
+    pub total_seats: i32,
+    pub seats_left: i32,
+    pub moved: Vec<SeatMove>,
+    // Bookings that didn't fit and need a manual reseat or refund
+    pub unplaced: Vec<String>,
+}
+
+pub const TRIP_STATUSES: [&str; 6] = ["scheduled", "boarding", "delayed", "departed", "arrived", "cancelled"];
+
+// Broadcast to everyone booked on a trip, e.g. "departure moved to gate 3"
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripAnnouncement {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub message: String,
+    pub author: String,
+    pub created_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AnnouncementRequest {
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementResponse {
+    pub id: String,
+    pub message: String,
+    pub created_at: String,
+}
+
+impl From<TripAnnouncement> for AnnouncementResponse {
+    fn from(a: TripAnnouncement) -> Self {
+        Self {
+            id: a.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+            message: a.message,
+            created_at: a.created_at.try_to_rfc3339_string().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AnnouncementResult {
+    pub announcement: AnnouncementResponse,
+    pub passengers_notified: usize,
+}
+
+// Where a bus last reported itself, from the crew's handset GPS
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripLocation {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub speed_kmh: Option<f64>,
+    pub heading: Option<f64>,
+    pub device_id: Option<String>,
+    pub recorded_at: bson::DateTime,
+    pub received_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TripLocationRequest {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub speed_kmh: Option<f64>,
+    // Degrees clockwise from north
+    pub heading: Option<f64>,
+    // RFC 3339 time of the fix, which may be a while before it was sent; defaults to now
+    pub recorded_at: Option<String>,
+}
+
+impl TripLocationRequest {
+    /// When the fix was taken, once the coordinates are usable.
+    pub fn validated_time(&self, now: chrono::DateTime<chrono::Utc>) -> Result<chrono::DateTime<chrono::Utc>, String> {
+        if !(-90.0..=90.0).contains(&self.latitude) || !(-180.0..=180.0).contains(&self.longitude) {
+            return Err("latitude must be within ±90 and longitude within ±180".to_string());
+        }
+        if self.speed_kmh.is_some_and(|s| !(0.0..=300.0).contains(&s)) {
+            return Err("speed_kmh must be between 0 and 300".to_string());
+        }
+        if self.heading.is_some_and(|h| !(0.0..360.0).contains(&h)) {
+            return Err("heading must be at least 0 and under 360".to_string());
+        }
+        let Some(recorded_at) = self.recorded_at.as_deref() else {
+            return Ok(now);
+        };
+        let recorded_at = chrono::DateTime::parse_from_rfc3339(recorded_at)
+            .map_err(|_| "recorded_at must be an RFC 3339 time".to_string())?
+            .with_timezone(&chrono::Utc);
+        if recorded_at > now + chrono::Duration::minutes(5) {
+            return Err("recorded_at is in the future".to_string());
+        }
+        Ok(recorded_at)
+    }
+}
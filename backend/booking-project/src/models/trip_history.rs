@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::booking::Passenger;
+use super::fare::FareQuote;
+
+// Passengers are asked to rate a trip for two weeks after it ends
+pub const RATING_WINDOW_DAYS: i64 = 14;
+pub const MAX_HISTORY_PAGE: i64 = 50;
+
+// A passenger's score for the operator that ran one of their trips; one per booking
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TripRating {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub booking_id: ObjectId,
+    pub user_id: ObjectId,
+    pub bus_id: ObjectId,
+    pub operator: String,
+    pub score: i32,
+    pub comment: Option<String>,
+    pub created_at: bson::DateTime,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RateTripRequest {
+    pub score: i32,
+    pub comment: Option<String>,
+}
+
+impl RateTripRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=5).contains(&self.score) {
+            return Err("score must be between 1 and 5".to_string());
+        }
+        if self.comment.as_deref().is_some_and(|c| c.chars().count() > 500) {
+            return Err("comment must be at most 500 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Whether the app should ask for a rating: `rate` while it is due, then `rated` or `expired`.
+pub fn rating_prompt(rated: bool, ended_at: DateTime<Utc>, now: DateTime<Utc>) -> &'static str {
+    if rated {
+        "rated"
+    } else if now - ended_at <= chrono::Duration::days(RATING_WINDOW_DAYS) {
+        "rate"
+    } else {
+        "expired"
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TripHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PastTripResponse {
+    pub booking_id: String,
+    pub bus_id: String,
+    pub bus_number: String,
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub travel_date: String,
+    pub seat_number: String,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub rating_prompt: String,
+    pub rating: Option<i32>,
+}
+
+// One date the same trip can be booked again
+#[derive(Serialize, ToSchema)]
+pub struct RebookDate {
+    pub travel_date: String,
+    pub departure: String,
+    pub seats_left: i32,
+}
+
+// Everything the booking form needs to book the same trip again, short of picking a date
+#[derive(Serialize, ToSchema)]
+pub struct RebookQuote {
+    pub bus_id: String,
+    pub bus_number: String,
+    pub fare: FareQuote,
+    // What moving this booking with /change would cost instead, while it hasn't travelled yet
+    pub amendment_fee: f64,
+    // The seat they had last time, to try first
+    pub seat_number: String,
+    pub passenger: Option<Passenger>,
+    // Soonest first
+    pub dates: Vec<RebookDate>,
+}
@@ -1,9 +1,12 @@
 use mongodb::bson;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const ROLES: [&str; 7] = ["user", "conductor", "agent", "operator", "operator_admin", "finance", "admin"];
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct User {
-    #[serde(rename = "_id")]
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<bson::oid::ObjectId>,
     pub username: String,
     pub email: String,
@@ -11,9 +14,30 @@ pub struct User {
     pub role: String,
     pub created_at: Option<bson::DateTime>,
     pub updated_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub terms_acceptances: Vec<super::terms::TermsAcceptance>,
+    // Operator an operator, operator_admin or conductor works for, matching the bus number prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    // Tokens issued before this are rejected, e.g. after an email change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions_revoked_at: Option<bson::DateTime>,
+    // Language for receipts and SMS; without one the request's Accept-Language decides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_login_at: Option<bson::DateTime>,
+    // Set when the account was warned it will be anonymized; signing in clears it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_notice_at: Option<bson::DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anonymized_at: Option<bson::DateTime>,
+    // Set on a duplicate account folded into another; signing in to it opens that one instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<bson::oid::ObjectId>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: String,
     pub username: String,
@@ -21,9 +45,74 @@ pub struct UserResponse {
     pub role: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Bumped whenever claims gain fields; tokens from before versioning decode as version 1
+pub const CLAIMS_VERSION: u32 = 2;
+
+fn legacy_claims_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    #[default]
+    Access,
+    Refresh,
+    // Issued to an admin acting as another user; `act` names the admin
+    Impersonation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
+    #[serde(default = "legacy_claims_version")]
+    pub ver: u32,
     pub sub: String,
     pub role: String,
     pub exp: usize,
+    #[serde(default)]
+    pub iat: usize,
+    // Permissions granted by the role when the token was issued
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(default)]
+    pub typ: TokenType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    // Unique id of a refresh token, so every one issued hashes differently
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl Claims {
+    pub fn new(sub: String, role: String, exp: usize) -> Self {
+        let scopes = super::permissions::permissions_for(&role).into_iter().map(str::to_string).collect();
+        Self {
+            ver: CLAIMS_VERSION,
+            sub,
+            role,
+            exp,
+            iat: chrono::Utc::now().timestamp() as usize,
+            scopes,
+            operator: None,
+            typ: TokenType::Access,
+            act: None,
+            iss: None,
+            jti: None,
+        }
+    }
+
+    pub fn with_operator(mut self, operator: Option<String>) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    /// Whether the token may authenticate an API request; refresh tokens are only good for
+    /// getting a new access token, and versions from the future are not trusted.
+    pub fn authorizes_requests(&self) -> bool {
+        self.ver <= CLAIMS_VERSION && self.typ != TokenType::Refresh
+    }
 }
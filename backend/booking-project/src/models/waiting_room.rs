@@ -0,0 +1,143 @@
+use mongodb::bson::{self, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const QUEUE_SCOPE: &str = "waiting_room:queue";
+pub const ADMISSION_SCOPE: &str = "waiting_room:admitted";
+// Header carrying either token; the queue token for position checks, the admission one for seat selection
+pub const WAITING_ROOM_HEADER: &str = "x-waiting-room-token";
+
+// A queue in front of one trip's seat selection for an announced sale. People are admitted in
+// the order they joined at `admit_per_minute`; `admitted_base` and `rate_changed_at` let the
+// rate change mid-sale without re-admitting or skipping anyone
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WaitingRoom {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub bus_id: ObjectId,
+    pub travel_date: String,
+    pub opens_at: bson::DateTime,
+    pub closes_at: bson::DateTime,
+    pub admit_per_minute: i64,
+    // How long an admitted visitor may select seats before rejoining
+    pub admission_minutes: i64,
+    pub admitted_base: i64,
+    pub rate_changed_at: bson::DateTime,
+    // Last position handed out
+    pub last_position: i64,
+    pub status: String, // active, closed
+    pub created_by: String,
+    pub created_at: bson::DateTime,
+}
+
+impl WaitingRoom {
+    /// Highest queue position admitted at `now_ms`.
+    pub fn admitted_through(&self, now_ms: i64) -> i64 {
+        let since = self.rate_changed_at.timestamp_millis().max(self.opens_at.timestamp_millis());
+        if now_ms < self.opens_at.timestamp_millis() {
+            return self.admitted_base;
+        }
+        self.admitted_base + (now_ms - since).max(0) * self.admit_per_minute / 60_000
+    }
+
+    pub fn is_enforced(&self, now_ms: i64) -> bool {
+        self.status == "active" && now_ms < self.closes_at.timestamp_millis()
+    }
+
+    /// Minutes until `position` is admitted at the current rate.
+    pub fn estimated_wait_minutes(&self, position: i64, now_ms: i64) -> i64 {
+        let ahead = position - self.admitted_through(now_ms);
+        if ahead <= 0 {
+            return 0;
+        }
+        let until_open = (self.opens_at.timestamp_millis() - now_ms).max(0) / 60_000;
+        until_open + (ahead + self.admit_per_minute - 1) / self.admit_per_minute.max(1)
+    }
+}
+
+// One visitor's place in a waiting room
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WaitingRoomEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub room_id: ObjectId,
+    pub holder: String, // user:<id> or anon:<id>
+    pub position: i64,
+    pub joined_at: bson::DateTime,
+    pub admitted_at: Option<bson::DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitingRoomClaims {
+    pub scope: String,
+    pub room_id: String,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub holder: String,
+    pub position: i64,
+    pub exp: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateWaitingRoomRequest {
+    pub bus_id: String,
+    pub travel_date: String,
+    pub opens_at: String, // RFC 3339
+    pub closes_at: String,
+    pub admit_per_minute: i64,
+    pub admission_minutes: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateWaitingRoomRequest {
+    pub admit_per_minute: Option<i64>,
+    pub closes_at: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WaitingRoomResponse {
+    pub id: Option<String>,
+    pub bus_id: String,
+    pub travel_date: String,
+    pub opens_at: String,
+    pub closes_at: String,
+    pub admit_per_minute: i64,
+    pub admission_minutes: i64,
+    pub queue_length: i64,
+    pub admitted_through: i64,
+    pub status: String,
+}
+
+impl From<WaitingRoom> for WaitingRoomResponse {
+    fn from(room: WaitingRoom) -> Self {
+        let admitted_through = room.admitted_through(chrono::Utc::now().timestamp_millis()).min(room.last_position);
+        Self {
+            id: room.id.map(|id| id.to_hex()),
+            bus_id: room.bus_id.to_hex(),
+            travel_date: room.travel_date,
+            opens_at: room.opens_at.to_string(),
+            closes_at: room.closes_at.to_string(),
+            admit_per_minute: room.admit_per_minute,
+            admission_minutes: room.admission_minutes,
+            queue_length: room.last_position,
+            admitted_through,
+            status: room.status,
+        }
+    }
+}
+
+// What a visitor sees while queueing; the admission token appears once their turn comes
+#[derive(Serialize)]
+pub struct QueuePosition {
+    pub position: i64,
+    pub ahead: i64,
+    pub estimated_wait_minutes: i64,
+    pub admitted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admission_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admission_expires_at: Option<String>,
+}
@@ -0,0 +1,276 @@
+use mongodb::bson::{self, oid::ObjectId};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::crypto::constant_time_eq;
+
+// `mpesa` carries C2B paybill confirmations, `mpesa_stk` the results of STK Push prompts
+pub const WEBHOOK_PROVIDERS: [&str; 3] = ["mpesa", "mpesa_stk", "stripe"];
+pub const STRIPE_SIGNATURE_HEADER: &str = "stripe-signature";
+
+// One inbound payment callback exactly as it arrived. The raw body is kept byte for byte so
+// a delivery can be run through the handler again once whatever broke it is fixed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebhookEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub provider: String,
+    pub raw_body: String,
+    // Stripe-Signature header; M-Pesa deliveries are authenticated by the callback URL token instead
+    pub signature: Option<String>,
+    // TransID or Stripe event id, when the body could be read
+    pub external_id: Option<String>,
+    pub status: String, // received, processed, ignored, failed, rejected
+    pub outcome: Option<String>,
+    pub attempts: i64,
+    pub received_at: bson::DateTime,
+    pub last_processed_at: Option<bson::DateTime>,
+    pub replayed_by: Option<String>,
+}
+
+/// Where processing left a delivery; `ignored` covers callbacks that are valid but need no
+/// action, such as a second notification for a booking that is already paid.
+pub struct WebhookOutcome {
+    pub status: &'static str,
+    pub message: String,
+}
+
+impl WebhookOutcome {
+    pub fn processed(message: String) -> Self {
+        Self { status: "processed", message }
+    }
+
+    pub fn ignored(message: String) -> Self {
+        Self { status: "ignored", message }
+    }
+}
+
+// Safaricom C2B confirmation; customers pay to the paybill with the booking id as account number
+#[derive(Deserialize)]
+pub struct MpesaConfirmation {
+    #[serde(rename = "TransID")]
+    pub trans_id: String,
+    #[serde(rename = "TransAmount")]
+    pub trans_amount: String,
+    #[serde(rename = "BillRefNumber")]
+    pub bill_ref_number: String,
+    #[serde(rename = "MSISDN", default)]
+    pub msisdn: Option<String>,
+    #[serde(rename = "TransTime", default)]
+    pub trans_time: Option<String>,
+}
+
+impl MpesaConfirmation {
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        let confirmation: Self = serde_json::from_slice(body).map_err(|e| format!("Unreadable M-Pesa confirmation: {}", e))?;
+        if confirmation.trans_id.trim().is_empty() {
+            return Err("M-Pesa confirmation has no TransID".to_string());
+        }
+        Ok(confirmation)
+    }
+
+    pub fn amount(&self) -> Option<f64> {
+        self.trans_amount.trim().parse().ok().filter(|a: &f64| a.is_finite() && *a > 0.0)
+    }
+}
+
+#[derive(Deserialize)]
+struct StkEnvelope {
+    #[serde(rename = "Body")]
+    body: StkBody,
+}
+
+#[derive(Deserialize)]
+struct StkBody {
+    #[serde(rename = "stkCallback")]
+    callback: MpesaStkCallback,
+}
+
+// Outcome of an STK Push prompt, matched to the payment by its CheckoutRequestID
+#[derive(Deserialize)]
+pub struct MpesaStkCallback {
+    #[serde(rename = "CheckoutRequestID")]
+    pub checkout_request_id: String,
+    #[serde(rename = "ResultCode")]
+    pub result_code: i64,
+    #[serde(rename = "ResultDesc", default)]
+    pub result_desc: String,
+    #[serde(rename = "CallbackMetadata", default)]
+    metadata: Option<StkMetadata>,
+}
+
+#[derive(Deserialize)]
+struct StkMetadata {
+    #[serde(rename = "Item", default)]
+    items: Vec<StkItem>,
+}
+
+#[derive(Deserialize)]
+struct StkItem {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Value", default)]
+    value: Option<serde_json::Value>,
+}
+
+impl MpesaStkCallback {
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        let envelope: StkEnvelope = serde_json::from_slice(body).map_err(|e| format!("Unreadable M-Pesa STK callback: {}", e))?;
+        Ok(envelope.body.callback)
+    }
+
+    /// The customer paid; any other result code means they cancelled, timed out or lacked funds.
+    pub fn succeeded(&self) -> bool {
+        self.result_code == 0
+    }
+
+    fn item(&self, name: &str) -> Option<&serde_json::Value> {
+        self.metadata.as_ref()?.items.iter().find(|i| i.name == name)?.value.as_ref()
+    }
+
+    pub fn amount(&self) -> Option<f64> {
+        self.item("Amount")?.as_f64().filter(|a| a.is_finite() && *a > 0.0)
+    }
+
+    pub fn receipt_number(&self) -> Option<String> {
+        self.item("MpesaReceiptNumber")?.as_str().map(str::to_string)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StripeEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub data: StripeEventData,
+}
+
+#[derive(Deserialize)]
+pub struct StripeEventData {
+    pub object: serde_json::Value,
+}
+
+impl StripeEvent {
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(body).map_err(|e| format!("Unreadable Stripe event: {}", e))
+    }
+
+    /// The booking a payment intent was created for, from its metadata.
+    pub fn booking_id(&self) -> Option<&str> {
+        self.data.object.get("metadata")?.get("booking_id")?.as_str()
+    }
+
+    /// Amount received in major units; Stripe reports cents.
+    pub fn amount_received(&self) -> Option<f64> {
+        self.data.object.get("amount_received")?.as_i64().map(|cents| cents as f64 / 100.0)
+    }
+}
+
+/// The provider's own id for a delivery, if the body can be read at all.
+pub fn external_id(provider: &str, body: &[u8]) -> Option<String> {
+    match provider {
+        "mpesa" => MpesaConfirmation::parse(body).ok().map(|c| c.trans_id),
+        "mpesa_stk" => MpesaStkCallback::parse(body).ok().map(|c| c.checkout_request_id),
+        "stripe" => StripeEvent::parse(body).ok().map(|e| e.id),
+        _ => None,
+    }
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| value.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+/// Checks a `Stripe-Signature` header (`t=<unix>,v1=<hex>,...`) against the body and returns
+/// the signed timestamp. Callers decide how old a timestamp may be; replays of stored
+/// deliveries are legitimately old.
+pub fn verify_stripe_signature(header: &str, body: &[u8], secret: &str) -> Result<i64, String> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.extend(decode_hex(value)),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or("Signature header has no timestamp")?;
+    if signatures.is_empty() {
+        return Err("Signature header has no v1 signature".to_string());
+    }
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut signed = format!("{}.", timestamp).into_bytes();
+    signed.extend_from_slice(body);
+    if signatures.iter().any(|s| hmac::verify(&key, &signed, s).is_ok()) {
+        Ok(timestamp)
+    } else {
+        Err("Signature does not match".to_string())
+    }
+}
+
+/// Compares a callback URL token without leaking how much of it matched.
+pub fn token_matches(given: &str, expected: &str) -> bool {
+    constant_time_eq(given.as_bytes(), expected.as_bytes())
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct WebhookEventQuery {
+    pub provider: Option<String>,
+    pub status: Option<String>,
+    pub external_id: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct MpesaCallbackQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WebhookEventResponse {
+    pub id: Option<String>,
+    pub provider: String,
+    pub external_id: Option<String>,
+    pub status: String,
+    pub outcome: Option<String>,
+    pub attempts: i64,
+    pub received_at: String,
+    pub last_processed_at: Option<String>,
+    pub replayed_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_body: Option<String>,
+}
+
+impl WebhookEventResponse {
+    /// Without the payload, for listings.
+    pub fn summary(event: WebhookEvent) -> Self {
+        let mut response = Self::full(event);
+        response.signature = None;
+        response.raw_body = None;
+        response
+    }
+
+    pub fn full(event: WebhookEvent) -> Self {
+        Self {
+            id: event.id.map(|id| id.to_hex()),
+            provider: event.provider,
+            external_id: event.external_id,
+            status: event.status,
+            outcome: event.outcome,
+            attempts: event.attempts,
+            received_at: event.received_at.try_to_rfc3339_string().unwrap_or_default(),
+            last_processed_at: event.last_processed_at.and_then(|at| at.try_to_rfc3339_string().ok()),
+            replayed_by: event.replayed_by,
+            signature: event.signature,
+            raw_body: Some(event.raw_body),
+        }
+    }
+}
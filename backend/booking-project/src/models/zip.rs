@@ -0,0 +1,158 @@
+use std::io::Read;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+// Bare-bones ZIP support. Archives we write are stored uncompressed, which every unzip tool and
+// feed consumer accepts; archives we read may also be deflated, as most tools produce them
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 (IEEE), as ZIP entries carry it.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| CRC_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+// MS-DOS date and time; the format can't express anything before 1980
+fn dos_timestamp(at: NaiveDateTime) -> (u16, u16) {
+    let year = at.year().clamp(1980, 2107) as u16;
+    let date = ((year - 1980) << 9) | ((at.month() as u16) << 5) | at.day() as u16;
+    let time = ((at.hour() as u16) << 11) | ((at.minute() as u16) << 5) | (at.second() as u16 / 2);
+    (date, time)
+}
+
+/// Packs `files` (name, contents) into an archive, every entry stamped with `modified`.
+pub fn write_zip(files: &[(&str, &[u8])], modified: NaiveDateTime) -> Vec<u8> {
+    let (date, time) = dos_timestamp(modified);
+    let mut zip = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        zip.extend(0x0403_4b50u32.to_le_bytes());
+        zip.extend(20u16.to_le_bytes()); // version needed
+        zip.extend(0u16.to_le_bytes()); // flags
+        zip.extend(0u16.to_le_bytes()); // stored
+        zip.extend(time.to_le_bytes());
+        zip.extend(date.to_le_bytes());
+        zip.extend(crc.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend((name.len() as u16).to_le_bytes());
+        zip.extend(0u16.to_le_bytes()); // extra field length
+        zip.extend(name.as_bytes());
+        zip.extend(*data);
+
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        central.extend(20u16.to_le_bytes()); // version made by
+        central.extend(20u16.to_le_bytes()); // version needed
+        central.extend(0u16.to_le_bytes());
+        central.extend(0u16.to_le_bytes());
+        central.extend(time.to_le_bytes());
+        central.extend(date.to_le_bytes());
+        central.extend(crc.to_le_bytes());
+        central.extend(size.to_le_bytes());
+        central.extend(size.to_le_bytes());
+        central.extend((name.len() as u16).to_le_bytes());
+        central.extend([0u8; 12]); // extra, comment, disk, internal and external attributes
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let central_at = zip.len() as u32;
+    let entries = files.len() as u16;
+    zip.extend(&central);
+    zip.extend(0x0605_4b50u32.to_le_bytes());
+    zip.extend([0u8; 4]); // this disk, disk with the central directory
+    zip.extend(entries.to_le_bytes());
+    zip.extend(entries.to_le_bytes());
+    zip.extend((central.len() as u32).to_le_bytes());
+    zip.extend(central_at.to_le_bytes());
+    zip.extend(0u16.to_le_bytes()); // comment length
+    zip
+}
+
+// Generous for any schedule feed; anything bigger is refused rather than inflated
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16, String> {
+    data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, String> {
+    data.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+/// Unpacks an archive into (name, contents) pairs, in central directory order. Entries must be
+/// stored or deflated; directories are skipped.
+pub fn read_zip(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    // The end of central directory record sits before an optional comment of up to 64 KiB
+    let search_from = data.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_from..data.len().saturating_sub(21))
+        .rev()
+        .find(|&at| data[at..].starts_with(&0x0605_4b50u32.to_le_bytes()))
+        .ok_or("Not a zip archive")?;
+    let entries = u16_at(data, end + 10)?;
+    let mut at = u32_at(data, end + 16)? as usize;
+
+    let mut files = Vec::with_capacity(entries as usize);
+    for _ in 0..entries {
+        if u32_at(data, at)? != 0x0201_4b50 {
+            return Err("Corrupt zip central directory".to_string());
+        }
+        let method = u16_at(data, at + 10)?;
+        let crc = u32_at(data, at + 16)?;
+        let compressed = u32_at(data, at + 20)? as usize;
+        let size = u32_at(data, at + 24)? as u64;
+        let name_len = u16_at(data, at + 28)? as usize;
+        let extra_len = u16_at(data, at + 30)? as usize;
+        let comment_len = u16_at(data, at + 32)? as usize;
+        let local = u32_at(data, at + 42)? as usize;
+        let name = data.get(at + 46..at + 46 + name_len).ok_or("Truncated zip archive")?;
+        let name = String::from_utf8_lossy(name).to_string();
+        at += 46 + name_len + extra_len + comment_len;
+        if name.ends_with('/') {
+            continue;
+        }
+        if size > MAX_ENTRY_BYTES {
+            return Err(format!("{} is too large", name));
+        }
+
+        let start = local + 30 + u16_at(data, local + 26)? as usize + u16_at(data, local + 28)? as usize;
+        let raw = data.get(start..start + compressed).ok_or("Truncated zip archive")?;
+        let contents = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut contents = Vec::with_capacity(size as usize);
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(size + 1)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| format!("{}: {}", name, e))?;
+                contents
+            }
+            _ => return Err(format!("{} uses an unsupported compression method", name)),
+        };
+        if contents.len() as u64 != size || crc32(&contents) != crc {
+            return Err(format!("{} is corrupt", name));
+        }
+        files.push((name, contents));
+    }
+    Ok(files)
+}
@@ -0,0 +1,86 @@
+//! Outgoing email.
+//!
+//! Messages are queued in the outbox and the outbox worker hands each one to the configured
+//! [`EmailSender`]: SendGrid's mail API, any SMTP server through [`smtp`], or an internal relay
+//! that takes the message as JSON. Wording lives with the documents it belongs to (receipts,
+//! operator templates); this module only moves finished messages.
+
+pub mod smtp;
+
+use serde_json::json;
+
+use crate::config::EmailConfig;
+
+pub enum EmailSender {
+    Relay { url: String },
+    SendGrid { api_key: String, api_base: String, from_address: String, from_name: String },
+    Smtp(smtp::SmtpClient),
+}
+
+/// Rejects addresses that could smuggle extra headers or SMTP commands.
+pub fn valid_address(address: &str) -> bool {
+    let address = address.trim();
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !address.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ',' | ';'))
+        }
+        None => false,
+    }
+}
+
+impl EmailSender {
+    pub fn from_config(config: &EmailConfig) -> Result<Self, String> {
+        match config.provider.as_deref() {
+            Some("sendgrid") => Ok(Self::SendGrid {
+                api_key: config.sendgrid_api_key.clone().ok_or("SENDGRID_API_KEY is not set")?,
+                api_base: config.sendgrid_api_base.clone(),
+                from_address: config.from_address.clone(),
+                from_name: config.from_name.clone(),
+            }),
+            Some("smtp") => Ok(Self::Smtp(smtp::SmtpClient::from_config(config)?)),
+            Some("relay") => Ok(Self::Relay { url: config.relay_url.clone().ok_or("EMAIL_RELAY_URL is not set")? }),
+            Some(other) => Err(format!("Unknown email provider {}", other)),
+            None => Err("No email provider configured".to_string()),
+        }
+    }
+
+    /// Sends a plain text email.
+    pub async fn send(&self, client: &reqwest::Client, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        if !valid_address(to) {
+            return Err(format!("Invalid email address {}", to));
+        }
+        match self {
+            Self::Relay { url } => {
+                let response = client.post(url)
+                    .json(&json!({ "to": to, "subject": subject, "body": body }))
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("email responded with {}", response.status()));
+                }
+                Ok(())
+            }
+            Self::SendGrid { api_key, api_base, from_address, from_name } => {
+                let response = client.post(format!("{}/v3/mail/send", api_base))
+                    .bearer_auth(api_key)
+                    .json(&json!({
+                        "personalizations": [{ "to": [{ "email": to }] }],
+                        "from": { "email": from_address, "name": from_name },
+                        "subject": subject,
+                        "content": [{ "type": "text/plain", "value": body }],
+                    }))
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("SendGrid responded with {}", response.status()));
+                }
+                Ok(())
+            }
+            Self::Smtp(smtp) => smtp.send(to, subject, body).await,
+        }
+    }
+}
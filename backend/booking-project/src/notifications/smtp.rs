@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, FixedOffset};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+
+use crate::config::EmailConfig;
+use crate::models::bus::local_offset;
+
+// Covers connecting, the TLS handshake and the whole conversation
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Just enough SMTP to hand a message to a submission server: TLS is required (implicit, or
+// STARTTLS which the server must offer) so credentials never cross the wire in the clear
+pub struct SmtpClient {
+    host: String,
+    port: u16,
+    implicit_tls: bool,
+    credentials: Option<(String, String)>,
+    from_address: String,
+    from_name: String,
+}
+
+/// Credentials for `AUTH PLAIN`: base64 of an empty authorization id, the user and password.
+pub fn auth_plain(username: &str, password: &str) -> String {
+    STANDARD.encode(format!("\0{}\0{}", username, password))
+}
+
+/// A header value safe to send: line breaks flattened, and non-ASCII text as an RFC 2047
+/// encoded word.
+pub fn encode_header(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// The message as sent after `DATA`, terminator included. ASCII bodies go as they are, with
+/// dot-stuffing; anything else is base64 so servers without 8BITMIME take it too.
+pub fn format_message(from: &str, to: &str, subject: &str, body: &str, date: DateTime<FixedOffset>, message_id: &str) -> String {
+    let ascii = body.is_ascii();
+    let mut message = format!(
+        "From: {}\r\nTo: <{}>\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}>\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: {}\r\n\r\n",
+        from, to, encode_header(subject), date.to_rfc2822(), message_id, if ascii { "7bit" } else { "base64" },
+    );
+    if ascii {
+        for line in body.replace("\r\n", "\n").split('\n') {
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+    } else {
+        let encoded = STANDARD.encode(body);
+        for chunk in encoded.as_bytes().chunks(76) {
+            message.push_str(&String::from_utf8_lossy(chunk));
+            message.push_str("\r\n");
+        }
+    }
+    message.push_str(".\r\n");
+    message
+}
+
+async fn reply<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>) -> Result<(u16, String), String> {
+    let mut text = String::new();
+    loop {
+        let mut line = String::new();
+        if conn.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("SMTP server closed the connection".to_string());
+        }
+        let code = line.get(..3)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| format!("Unexpected SMTP reply: {}", line.trim_end()))?;
+        text.push_str(line.get(4..).unwrap_or_default().trim_end());
+        text.push('\n');
+        // "250-" continues a multiline reply, "250 " ends it
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, text));
+        }
+    }
+}
+
+// Expects a reply in the given class: 2 for completion, 3 for "go on" (DATA)
+async fn expect<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>, class: u16) -> Result<String, String> {
+    let (code, text) = reply(conn).await?;
+    if code / 100 != class {
+        return Err(format!("SMTP server replied {} {}", code, text.trim()));
+    }
+    Ok(text)
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(conn: &mut BufReader<S>, line: &str, class: u16) -> Result<String, String> {
+    let stream = conn.get_mut();
+    stream.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+    stream.write_all(b"\r\n").await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+    expect(conn, class).await
+}
+
+pub(crate) fn tls_connector() -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+impl SmtpClient {
+    pub fn from_config(config: &EmailConfig) -> Result<Self, String> {
+        Ok(Self {
+            host: config.smtp_host.clone().ok_or("SMTP_HOST is not set")?,
+            port: config.smtp_port,
+            implicit_tls: config.smtp_implicit_tls,
+            credentials: config.smtp_username.clone().zip(config.smtp_password.clone()),
+            from_address: config.from_address.clone(),
+            from_name: config.from_name.clone(),
+        })
+    }
+
+    fn domain(&self) -> &str {
+        self.from_address.rsplit('@').next().unwrap_or("localhost")
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        tokio::time::timeout(SMTP_TIMEOUT, self.send_within_timeout(to, subject, body))
+            .await
+            .map_err(|_| format!("SMTP server {} timed out", self.host))?
+    }
+
+    async fn send_within_timeout(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).await.map_err(|e| e.to_string())?;
+        let server_name = ServerName::try_from(self.host.as_str()).map_err(|e| e.to_string())?;
+        if self.implicit_tls {
+            let tls = tls_connector().connect(server_name, tcp).await.map_err(|e| e.to_string())?;
+            let mut conn = BufReader::new(tls);
+            expect(&mut conn, 2).await?;
+            return self.deliver(&mut conn, to, subject, body).await;
+        }
+
+        let mut conn = BufReader::new(tcp);
+        expect(&mut conn, 2).await?;
+        let extensions = command(&mut conn, &format!("EHLO {}", self.domain()), 2).await?;
+        if !extensions.lines().any(|l| l.trim().eq_ignore_ascii_case("STARTTLS")) {
+            return Err(format!("SMTP server {} doesn't offer STARTTLS", self.host));
+        }
+        command(&mut conn, "STARTTLS", 2).await?;
+        let tls = tls_connector().connect(server_name, conn.into_inner()).await.map_err(|e| e.to_string())?;
+        self.deliver(&mut BufReader::new(tls), to, subject, body).await
+    }
+
+    // Everything after the secure channel is up: the server has already greeted us
+    async fn deliver<S: AsyncRead + AsyncWrite + Unpin>(&self, conn: &mut BufReader<S>, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        command(conn, &format!("EHLO {}", self.domain()), 2).await?;
+        if let Some((username, password)) = &self.credentials {
+            command(conn, &format!("AUTH PLAIN {}", auth_plain(username, password)), 2).await?;
+        }
+        command(conn, &format!("MAIL FROM:<{}>", self.from_address), 2).await?;
+        command(conn, &format!("RCPT TO:<{}>", to.trim()), 2).await?;
+        command(conn, "DATA", 3).await?;
+
+        let from = format!("{} <{}>", encode_header(&self.from_name), self.from_address);
+        let message_id = format!("{}@{}", mongodb::bson::oid::ObjectId::new().to_hex(), self.domain());
+        let date = chrono::Utc::now().with_timezone(&local_offset());
+        let message = format_message(&from, to.trim(), subject, body, date, &message_id);
+        let stream = conn.get_mut();
+        stream.write_all(message.as_bytes()).await.map_err(|e| e.to_string())?;
+        stream.flush().await.map_err(|e| e.to_string())?;
+        expect(conn, 2).await?;
+        // The message is accepted; a failed goodbye doesn't change that
+        let _ = command(conn, "QUIT", 2).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,442 @@
+//! The API described as OpenAPI 3, served as JSON at `/api/openapi.json` and browsable with
+//! Swagger UI at `/docs`. Each handler documents itself with `#[utoipa::path]`; this lists them
+//! and adds what they share. Each audience also gets a spec of just what it can call, at
+//! `/api/openapi/{public,partner,admin}.json`.
+
+use std::collections::{BTreeSet, HashSet};
+
+#[cfg(feature = "client")]
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::openapi::extensions::Extensions;
+use utoipa::openapi::path::{Operation, PathItem};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::handlers;
+use crate::models::manifest::TRIP_ACCESS_SCOPE;
+use crate::models::permissions::admin_permission;
+
+// The body of every error response
+#[derive(Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct ErrorResponse {
+    pub error: String,
+    // One of the codes in AppError::code, e.g. not_found, conflict or rate_limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+// A raw ObjectId as serde writes it: {"$oid": "<24 hex digits>"}
+#[derive(Serialize, ToSchema)]
+pub struct ObjectIdJson {
+    #[serde(rename = "$oid")]
+    pub oid: String,
+}
+
+// A raw BSON date as serde writes it: {"$date": {"$numberLong": "<ms since the epoch>"}}
+#[derive(Serialize, ToSchema)]
+pub struct DateTimeJson {
+    #[serde(rename = "$date")]
+    pub date: serde_json::Value,
+}
+
+// Access tokens from /api/auth/login and friends, sent as `Authorization: Bearer <token>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// Who calls an endpoint, which decides the spec variant it is listed in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Audience {
+    // Customer apps, signed in or not
+    Public,
+    // Affiliates reporting conversions and aggregators pulling route feeds
+    Partner,
+    // Operator staff and conductors
+    Admin,
+}
+
+impl Audience {
+    pub const ALL: [Audience; 3] = [Audience::Public, Audience::Partner, Audience::Admin];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Audience::Public => "public",
+            Audience::Partner => "partner",
+            Audience::Admin => "admin",
+        }
+    }
+
+    pub fn spec_url(&self) -> &'static str {
+        match self {
+            Audience::Public => "/api/openapi/public.json",
+            Audience::Partner => "/api/openapi/partner.json",
+            Audience::Admin => "/api/openapi/admin.json",
+        }
+    }
+
+    /// The audience for an OpenAPI path. None for callers outside all three, i.e. payment
+    /// providers' webhooks and the metrics scraper, which only the full spec lists.
+    pub fn of(path: &str) -> Option<Audience> {
+        if path.starts_with("/api/admin/") || path.starts_with("/api/conductor/") || path.starts_with("/api/checkin/") {
+            Some(Audience::Admin)
+        } else if path.starts_with("/api/partners/conversions") || path.starts_with("/api/feeds/") {
+            Some(Audience::Partner)
+        } else if path.starts_with("/api/webhooks/") || !path.starts_with("/api/") {
+            None
+        } else {
+            Some(Audience::Public)
+        }
+    }
+}
+
+fn operations_mut(item: &mut PathItem) -> Vec<(&'static str, &mut Operation)> {
+    [
+        ("GET", item.get.as_mut()),
+        ("PUT", item.put.as_mut()),
+        ("POST", item.post.as_mut()),
+        ("DELETE", item.delete.as_mut()),
+        ("PATCH", item.patch.as_mut()),
+    ]
+    .into_iter()
+    .filter_map(|(method, operation)| operation.map(|operation| (method, operation)))
+    .collect()
+}
+
+// Marks each operation with its audience and, for staff endpoints, the permission or token scope
+// it takes, straight from the checks the middleware makes
+struct AccessTags;
+
+impl Modify for AccessTags {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let audience = Audience::of(path);
+            for (method, operation) in operations_mut(item) {
+                let mut extensions = Extensions::default();
+                if let Some(audience) = audience {
+                    extensions.insert("x-audience".to_string(), audience.name().into());
+                }
+                let requirement = if let Some(admin_path) = path.strip_prefix("/api/admin") {
+                    Some(("x-required-permission", admin_permission(method, admin_path), "permission"))
+                } else if audience == Some(Audience::Admin) {
+                    Some(("x-required-scope", TRIP_ACCESS_SCOPE, "trip token from the manifest, scope"))
+                } else {
+                    None
+                };
+                if let Some((key, value, label)) = requirement {
+                    extensions.insert(key.to_string(), value.into());
+                    let note = format!("Requires the `{}` {}.", value, label);
+                    operation.description = Some(match operation.description.take() {
+                        Some(description) => format!("{}\n\n{}", description, note),
+                        None => note,
+                    });
+                }
+                operation.extensions.get_or_insert_with(Extensions::default).merge(extensions);
+            }
+        }
+    }
+}
+
+fn collect_refs(value: &serde_json::Value, refs: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value.as_str().and_then(|r| r.strip_prefix("#/components/schemas/"))) {
+                    ("$ref", Some(name)) => {
+                        refs.insert(name.to_string());
+                    }
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter().for_each(|item| collect_refs(item, refs)),
+        _ => {}
+    }
+}
+
+/// The spec cut down to what `audience` can call, with only the tags and schemas those
+/// endpoints use.
+pub fn audience_spec(audience: Audience) -> utoipa::openapi::OpenApi {
+    let mut spec = ApiDoc::openapi();
+    spec.info.title = format!("{} ({})", spec.info.title, audience.name());
+    spec.paths.paths.retain(|path, _| Audience::of(path) == Some(audience));
+
+    let mut used_tags = HashSet::new();
+    for item in spec.paths.paths.values_mut() {
+        for (_, operation) in operations_mut(item) {
+            used_tags.extend(operation.tags.iter().flatten().cloned());
+        }
+    }
+    if let Some(tags) = spec.tags.as_mut() {
+        tags.retain(|tag| used_tags.contains(&tag.name));
+    }
+
+    // Schemas reachable from the remaining paths, following references between schemas too
+    if let Some(components) = spec.components.as_mut() {
+        let mut reachable = BTreeSet::new();
+        collect_refs(&serde_json::to_value(&spec.paths).unwrap_or_default(), &mut reachable);
+        let mut pending: Vec<String> = reachable.iter().cloned().collect();
+        while let Some(name) = pending.pop() {
+            let mut refs = BTreeSet::new();
+            if let Some(schema) = components.schemas.get(&name) {
+                collect_refs(&serde_json::to_value(schema).unwrap_or_default(), &mut refs);
+            }
+            pending.extend(refs.into_iter().filter(|r| reachable.insert(r.clone())));
+        }
+        components.schemas.retain(|name, _| reachable.contains(name));
+    }
+    spec
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Bus Book API", description = "Bus search, seat booking and payments, plus the operator and admin tools behind them."),
+    paths(
+        handlers::metrics::get_metrics,
+        handlers::status::health_check,
+        handlers::status::readiness_check,
+        handlers::auth::register,
+        handlers::auth::login,
+        handlers::auth::google_login,
+        handlers::auth::anonymous_session,
+        handlers::auth::refresh,
+        handlers::auth::logout,
+        handlers::auth::forgot_password,
+        handlers::auth::reset_password,
+        handlers::buses::get_buses,
+        handlers::buses::search_buses,
+        handlers::buses::get_bus,
+        handlers::buses::get_bus_seats,
+        handlers::fares::get_fare_table,
+        handlers::fares::quote_fare,
+        handlers::policies::get_bus_policy,
+        handlers::trips::list_trips,
+        handlers::bookings::create_booking,
+        handlers::bookings::create_group_booking,
+        handlers::bookings::get_user_bookings,
+        handlers::bookings::confirm_holds,
+        handlers::holds::create_hold,
+        handlers::holds::get_holds,
+        handlers::holds::release_hold,
+        handlers::lookup::lookup_booking,
+        handlers::lookup::lookup_booking_by_reference,
+        handlers::bookings::get_shared_booking,
+        handlers::bookings::get_booking_detail,
+        handlers::bookings::cancel_booking,
+        handlers::bookings::change_booking,
+        handlers::bookings::share_booking,
+        handlers::bookings::get_booking_calendar,
+        handlers::bookings::get_booking_receipt,
+        handlers::tickets::get_booking_ticket,
+        handlers::trip_history::rate_trip,
+        handlers::trip_history::rebook,
+        handlers::conductor::get_trip_manifest,
+        handlers::conductor::get_trip_manifest_pdf,
+        handlers::conductor::check_in,
+        handlers::conductor::report_location,
+        handlers::conductor::download_offline_manifest,
+        handlers::conductor::upload_offline_batch,
+        handlers::tickets::scan_ticket,
+        handlers::waiting_rooms::get_queue_position,
+        handlers::waiting_rooms::get_waiting_room,
+        handlers::waiting_rooms::join_waiting_room,
+        handlers::invitations::accept_invitation,
+        handlers::invitations::get_invitation,
+        handlers::webhooks::receive_webhook,
+        handlers::partners::follow_partner_offer,
+        handlers::partners::report_partner_conversion,
+        handlers::compare::compare_route,
+        handlers::price_history::get_price_history,
+        handlers::localization::get_localizations,
+        handlers::feeds::get_routes_xml,
+        handlers::feeds::get_routes_json,
+        handlers::gtfs::get_gtfs_feed,
+        handlers::stats::get_public_stats,
+        handlers::status::get_status,
+        handlers::experiments::get_assignments,
+        handlers::terminals::get_terminals,
+        handlers::terminals::get_departures,
+        handlers::payments::stk_push,
+        handlers::payments::get_payment,
+        handlers::passes::buy_pass,
+        handlers::passes::list_passes,
+        handlers::passes::get_pass,
+        handlers::charters::request_charter,
+        handlers::charters::list_charters,
+        handlers::charters::get_charter,
+        handlers::charters::cancel_charter,
+        handlers::charters::accept_charter,
+        handlers::parcels::track_parcel,
+        handlers::refunds::request_refund,
+        handlers::refunds::get_user_refunds,
+        handlers::account::confirm_email_change,
+        handlers::account::undo_email_change,
+        handlers::account::request_email_change,
+        handlers::account::set_language,
+        handlers::account::get_consents,
+        handlers::account::update_consents,
+        handlers::account::get_consent_history,
+        handlers::terms::get_current_terms,
+        handlers::terms::accept_terms,
+        handlers::next_trip::get_next_trip,
+        handlers::trip_history::get_trip_history,
+        handlers::lost_found::report_lost_item,
+        handlers::lost_found::get_user_lost_items,
+        handlers::payment_methods::get_payment_methods,
+        handlers::payment_methods::save_payment_method,
+        handlers::payment_methods::remove_payment_method,
+        handlers::notifications::get_user_notifications,
+        handlers::bookings::search_bookings,
+        handlers::fraud::get_review_queue,
+        handlers::fraud::review_booking,
+        handlers::bookings::reseat_booking,
+        handlers::refunds::list_refunds,
+        handlers::refunds::approve_refund,
+        handlers::refunds::reject_refund,
+        handlers::refunds::mark_refund_paid,
+        handlers::settlements::list_settlements,
+        handlers::settlements::generate_settlements,
+        handlers::settlements::mark_settlement_paid,
+        handlers::commission::list_commission_rules,
+        handlers::commission::upsert_commission_rule,
+        handlers::commission::delete_commission_rule,
+        handlers::lost_found::list_lost_items,
+        handlers::lost_found::get_lost_item_matches,
+        handlers::lost_found::update_lost_item_status,
+        handlers::lost_found::list_found_items,
+        handlers::lost_found::log_found_item,
+        handlers::lost_found::update_found_item_status,
+        handlers::fraud::list_blocklist,
+        handlers::fraud::add_blocklist_entry,
+        handlers::fraud::list_decisions,
+        handlers::fraud::remove_blocklist_entry,
+        handlers::rate_limits::get_rate_limits,
+        handlers::rate_limits::get_rate_limit_usage,
+        handlers::rate_limits::create_override,
+        handlers::rate_limits::remove_override,
+        handlers::rate_limits::list_abuse_blocks,
+        handlers::rate_limits::reset_abuse_blocks,
+        handlers::waiting_rooms::list_waiting_rooms,
+        handlers::waiting_rooms::create_waiting_room,
+        handlers::waiting_rooms::update_waiting_room,
+        handlers::invitations::list_invitations,
+        handlers::invitations::create_invitation,
+        handlers::invitations::revoke_invitation,
+        handlers::permissions::get_permission_matrix,
+        handlers::audit::get_audit_log,
+        handlers::campaigns::send_promotion,
+        handlers::duplicates::list_duplicate_accounts,
+        handlers::duplicates::merge_accounts,
+        handlers::permissions::get_user_permissions,
+        handlers::permissions::assign_role,
+        handlers::payment_methods::get_vault_audit,
+        handlers::ledger::list_ledger_transactions,
+        handlers::ledger::get_ledger_balances,
+        handlers::ledger::check_ledger,
+        handlers::partners::list_partner_offers,
+        handlers::partners::create_partner_offer,
+        handlers::partners::get_partner_report,
+        handlers::partners::update_partner_offer,
+        handlers::partners::delete_partner_offer,
+        handlers::gtfs::export_gtfs,
+        handlers::gtfs::list_gtfs_stops,
+        handlers::gtfs::set_gtfs_stop,
+        handlers::gtfs::import_gtfs,
+        handlers::webhooks::list_webhook_events,
+        handlers::webhooks::get_webhook_event,
+        handlers::webhooks::replay_webhook_event,
+        handlers::settlements::get_operator_statement,
+        handlers::operators::get_operator_settings,
+        handlers::operators::update_operator_settings,
+        handlers::operators::get_booking_email,
+        handlers::operators::set_booking_email,
+        handlers::operators::reset_booking_email,
+        handlers::regulatory::list_exports,
+        handlers::regulatory::generate_export,
+        handlers::regulatory::download_export,
+        handlers::regulatory::list_mappings,
+        handlers::regulatory::upsert_mapping,
+        handlers::notifications::get_admin_notifications,
+        handlers::notifications::resend_confirmations,
+        handlers::notifications::list_confirmation_resends,
+        handlers::notifications::get_confirmation_resend,
+        handlers::localization::upsert_localization,
+        handlers::trips::update_trip_status,
+        handlers::trips::swap_trip_vehicle,
+        handlers::conductor::open_manifest,
+        handlers::conductor::get_manifest_pdf,
+        handlers::trips::get_crew,
+        handlers::trips::assign_crew,
+        handlers::trips::remove_crew,
+        handlers::trips::get_announcements,
+        handlers::trips::post_announcement,
+        handlers::incidents::report_incident,
+        handlers::incidents::list_incidents,
+        handlers::incidents::add_attachment,
+        handlers::incidents::resolve_incident,
+        handlers::policies::assign_trip_policy,
+        handlers::buses::list_fleet,
+        handlers::buses::create_bus,
+        handlers::buses::update_bus,
+        handlers::buses::delete_bus,
+        handlers::charters::list_charter_requests,
+        handlers::charters::quote_charter,
+        handlers::charters::decline_charter,
+        handlers::devices::provision_device,
+        handlers::devices::list_devices,
+        handlers::devices::revoke_device,
+        handlers::parcels::create_parcel,
+        handlers::parcels::list_parcels,
+        handlers::parcels::update_parcel_status,
+        handlers::trips::get_bus_schedule,
+        handlers::trips::set_bus_schedule,
+        handlers::trips::delete_bus_schedule,
+        handlers::policies::assign_bus_policy,
+        handlers::fares::set_fare_table,
+        handlers::fares::delete_fare_table,
+        handlers::policies::list_policies,
+        handlers::policies::create_policy,
+        handlers::policies::get_policy,
+        handlers::policies::update_policy,
+        handlers::policies::delete_policy,
+        handlers::dead_letters::list_dead_letters,
+        handlers::dead_letters::get_queue_metrics,
+        handlers::dead_letters::get_dead_letter,
+        handlers::dead_letters::update_dead_letter,
+        handlers::dead_letters::delete_dead_letter,
+        handlers::dead_letters::requeue_dead_letter,
+        handlers::experiments::list_experiments,
+        handlers::experiments::upsert_experiment,
+        handlers::analytics::get_punctuality,
+        handlers::analytics::get_countries,
+        handlers::analytics::get_demand_heatmap,
+        handlers::analytics::get_incidents,
+        handlers::analytics::get_revenue,
+        handlers::analytics::get_popular_routes,
+        handlers::analytics::get_zero_result_searches,
+    ),
+    modifiers(&BearerAuth, &AccessTags),
+    tags(
+        (name = "auth", description = "Sign-up, sign-in and sessions"),
+        (name = "buses", description = "Search buses, seats and fares"),
+        (name = "trips", description = "The timetable of departures by date"),
+        (name = "bookings", description = "Book, hold, share and cancel seats"),
+        (name = "payments", description = "Pay for bookings"),
+        (name = "passes", description = "Commuter passes of prepaid rides on one route"),
+        (name = "charters", description = "Hire a whole bus for a group"),
+        (name = "parcels", description = "Track parcels sent on the buses"),
+        (name = "account", description = "The signed-in customer's account"),
+        (name = "users", description = "The signed-in customer's trips at a glance"),
+        (name = "admin", description = "Operator and staff tools; needs an admin token"),
+    ),
+)]
+pub struct ApiDoc;
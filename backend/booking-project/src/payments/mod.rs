@@ -0,0 +1,120 @@
+//! Saved payment methods, kept PCI-light.
+//!
+//! The crate never sees card numbers or security codes: clients tokenize cards directly with
+//! the provider (Stripe Elements, PayPal vault) and hand us the resulting reference. Everything
+//! we persist goes through [`ProviderToken`], which refuses values that look like a card number,
+//! and `clippy.toml` disallows the usual binding names for raw card data so they fail the lint gate.
+//!
+//! M-Pesa needs no stored method: [`mpesa`] prompts the customer's phone to pay (STK Push) and
+//! the result comes back through the webhook endpoint.
+
+pub mod mpesa;
+pub mod paypal;
+pub mod stripe;
+
+use std::fmt;
+
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::config::PaymentsConfig;
+use crate::error::AppError;
+use utoipa::ToSchema;
+
+pub const PAYMENT_PROVIDERS: [&str; 2] = ["stripe", "paypal"];
+
+pub type VaultResult<T> = Result<T, AppError>;
+
+/// An opaque provider reference to a stored card or account (e.g. Stripe `pm_...`).
+#[derive(Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProviderToken(String);
+
+impl ProviderToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for ProviderToken {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim().to_string();
+        if value.is_empty() || value.len() > 255 {
+            return Err("Invalid payment token".to_string());
+        }
+        if looks_like_card_number(&value) {
+            return Err("Card numbers must be tokenized with the payment provider, never sent to this API".to_string());
+        }
+        Ok(Self(value))
+    }
+}
+
+impl From<ProviderToken> for String {
+    fn from(token: ProviderToken) -> Self {
+        token.0
+    }
+}
+
+// Tokens are not secrets to the provider, but keep them out of logs anyway
+impl fmt::Debug for ProviderToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tail: String = self.0.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+        write!(f, "ProviderToken(…{})", tail)
+    }
+}
+
+// 12-19 digits (ignoring spaces/dashes) passing the Luhn check
+fn looks_like_card_number(value: &str) -> bool {
+    let digits: Vec<u32> = value
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_digit(10))
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+    if !(12..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { *d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Display-safe details of a stored method; the only card data we keep.
+pub struct MethodSummary {
+    pub brand: String,
+    pub last4: Option<String>,
+    pub exp_month: Option<i32>,
+    pub exp_year: Option<i32>,
+}
+
+/// Implemented by each payment provider that can hold customer payment methods.
+///
+/// Implementations talk only in provider tokens: `describe` verifies a client-supplied token
+/// and returns display details, `remove` detaches it on the provider side. Calls are audited
+/// by the caller, not the provider.
+pub trait PaymentMethodVault {
+    fn provider(&self) -> &'static str;
+
+    fn describe<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<MethodSummary>>;
+
+    fn remove<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<()>>;
+}
+
+/// The configured vault for a provider, if its credentials are set.
+pub fn vault_for(provider: &str) -> Option<Box<dyn PaymentMethodVault>> {
+    let config = PaymentsConfig::from_env();
+    match provider {
+        "stripe" => config.stripe_secret_key.map(|key| Box::new(stripe::StripeVault::new(key)) as Box<dyn PaymentMethodVault>),
+        "paypal" => match (config.paypal_client_id, config.paypal_client_secret) {
+            (Some(id), Some(secret)) => Some(Box::new(paypal::PayPalVault::new(config.paypal_api_base, id, secret))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
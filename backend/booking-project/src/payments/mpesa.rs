@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::MpesaConfig;
+use crate::error::AppError;
+use crate::models::bus::local_offset;
+
+pub struct MpesaClient {
+    client: reqwest::Client,
+    api_base: String,
+    consumer_key: String,
+    consumer_secret: String,
+    shortcode: String,
+    passkey: String,
+    callback_url: String,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    access_token: String,
+}
+
+// Daraja's answer to a push request; the payment result arrives later on the callback URL
+#[derive(Deserialize)]
+pub struct StkPushResponse {
+    #[serde(rename = "MerchantRequestID")]
+    pub merchant_request_id: String,
+    #[serde(rename = "CheckoutRequestID")]
+    pub checkout_request_id: String,
+    #[serde(rename = "ResponseCode")]
+    pub response_code: String,
+    #[serde(rename = "CustomerMessage", default)]
+    pub customer_message: Option<String>,
+}
+
+/// Password for an STK Push: base64 of shortcode, passkey and the request timestamp.
+pub fn stk_password(shortcode: &str, passkey: &str, timestamp: &str) -> String {
+    STANDARD.encode(format!("{}{}{}", shortcode, passkey, timestamp))
+}
+
+impl MpesaClient {
+    /// A client for the configured shortcode, if STK Push credentials are set.
+    pub fn from_config(config: &MpesaConfig) -> Option<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            client,
+            api_base: config.api_base.clone(),
+            consumer_key: config.consumer_key.clone()?,
+            consumer_secret: config.consumer_secret.clone()?,
+            shortcode: config.shortcode.clone()?,
+            passkey: config.passkey.clone()?,
+            callback_url: config.callback_url.clone(),
+        })
+    }
+
+    async fn access_token(&self) -> Result<String, AppError> {
+        let response = self.client
+            .get(format!("{}/oauth/v1/generate?grant_type=client_credentials", self.api_base))
+            .basic_auth(&self.consumer_key, Some(&self.consumer_secret))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::internal(format!("M-Pesa authentication failed ({})", response.status())));
+        }
+        Ok(response.json::<AccessToken>().await?.access_token)
+    }
+
+    /// Sends a payment prompt to `phone` (2547.. form) for `amount` whole shillings.
+    pub async fn stk_push(&self, phone: &str, amount: u64, account_reference: &str, description: &str) -> Result<StkPushResponse, AppError> {
+        let timestamp = chrono::Utc::now().with_timezone(&local_offset()).format("%Y%m%d%H%M%S").to_string();
+        let response = self.client
+            .post(format!("{}/mpesa/stkpush/v1/processrequest", self.api_base))
+            .bearer_auth(self.access_token().await?)
+            .json(&json!({
+                "BusinessShortCode": self.shortcode,
+                "Password": stk_password(&self.shortcode, &self.passkey, &timestamp),
+                "Timestamp": timestamp,
+                "TransactionType": "CustomerPayBillOnline",
+                "Amount": amount,
+                "PartyA": phone,
+                "PartyB": self.shortcode,
+                "PhoneNumber": phone,
+                "CallBackURL": self.callback_url,
+                "AccountReference": account_reference,
+                "TransactionDesc": description,
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::internal(format!("M-Pesa rejected the payment request ({})", response.status())));
+        }
+        let push: StkPushResponse = response.json().await?;
+        if push.response_code != "0" {
+            return Err(AppError::internal(format!("M-Pesa rejected the payment request (code {})", push.response_code)));
+        }
+        Ok(push)
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use serde::Deserialize;
+
+use super::{MethodSummary, PaymentMethodVault, ProviderToken, VaultResult};
+use crate::error::AppError;
+
+pub struct PayPalVault {
+    client: reqwest::Client,
+    api_base: String,
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct PaymentToken {
+    payment_source: PaymentSource,
+}
+
+#[derive(Deserialize)]
+struct PaymentSource {
+    card: Option<PayPalCard>,
+}
+
+#[derive(Deserialize)]
+struct PayPalCard {
+    brand: Option<String>,
+    last_digits: Option<String>,
+    // YYYY-MM
+    expiry: Option<String>,
+}
+
+impl PayPalVault {
+    pub fn new(api_base: String, client_id: String, client_secret: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        Self { client, api_base, client_id, client_secret }
+    }
+
+    async fn access_token(&self) -> VaultResult<String> {
+        let response = self.client
+            .post(format!("{}/v1/oauth2/token", self.api_base))
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body("grant_type=client_credentials")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::internal(format!("PayPal authentication failed ({})", response.status())));
+        }
+        Ok(response.json::<AccessToken>().await?.access_token)
+    }
+}
+
+impl PaymentMethodVault for PayPalVault {
+    fn provider(&self) -> &'static str {
+        "paypal"
+    }
+
+    fn describe<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<MethodSummary>> {
+        async move {
+            let access_token = self.access_token().await?;
+            let response = self.client
+                .get(format!("{}/v3/vault/payment-tokens/{}", self.api_base, token.as_str()))
+                .bearer_auth(access_token)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(AppError::internal(format!("PayPal rejected the payment token ({})", response.status())));
+            }
+            let token: PaymentToken = response.json().await?;
+            Ok(match token.payment_source.card {
+                Some(card) => {
+                    let expiry = card.expiry.unwrap_or_default();
+                    let mut parts = expiry.split('-');
+                    let exp_year = parts.next().and_then(|y| y.parse().ok());
+                    let exp_month = parts.next().and_then(|m| m.parse().ok());
+                    MethodSummary {
+                        brand: card.brand.unwrap_or_else(|| "card".to_string()).to_lowercase(),
+                        last4: card.last_digits,
+                        exp_month,
+                        exp_year,
+                    }
+                }
+                None => MethodSummary { brand: "paypal".to_string(), last4: None, exp_month: None, exp_year: None },
+            })
+        }
+        .boxed_local()
+    }
+
+    fn remove<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<()>> {
+        async move {
+            let access_token = self.access_token().await?;
+            let response = self.client
+                .delete(format!("{}/v3/vault/payment-tokens/{}", self.api_base, token.as_str()))
+                .bearer_auth(access_token)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(AppError::internal(format!("PayPal failed to delete the payment token ({})", response.status())));
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
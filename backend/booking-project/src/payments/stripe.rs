@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use serde::Deserialize;
+
+use super::{MethodSummary, PaymentMethodVault, ProviderToken, VaultResult};
+use crate::error::AppError;
+
+const STRIPE_API: &str = "https://api.stripe.com/v1";
+
+pub struct StripeVault {
+    client: reqwest::Client,
+    secret_key: String,
+}
+
+#[derive(Deserialize)]
+struct StripePaymentMethod {
+    card: Option<StripeCard>,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct StripeCard {
+    brand: String,
+    last4: String,
+    exp_month: i32,
+    exp_year: i32,
+}
+
+impl StripeVault {
+    pub fn new(secret_key: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        Self { client, secret_key }
+    }
+}
+
+impl PaymentMethodVault for StripeVault {
+    fn provider(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn describe<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<MethodSummary>> {
+        async move {
+            if !token.as_str().starts_with("pm_") {
+                return Err("Stripe payment methods start with pm_".into());
+            }
+            let response = self.client
+                .get(format!("{}/payment_methods/{}", STRIPE_API, token.as_str()))
+                .bearer_auth(&self.secret_key)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(AppError::internal(format!("Stripe rejected the payment method ({})", response.status())));
+            }
+            let method: StripePaymentMethod = response.json().await?;
+            Ok(match method.card {
+                Some(card) => MethodSummary {
+                    brand: card.brand,
+                    last4: Some(card.last4),
+                    exp_month: Some(card.exp_month),
+                    exp_year: Some(card.exp_year),
+                },
+                None => MethodSummary { brand: method.kind, last4: None, exp_month: None, exp_year: None },
+            })
+        }
+        .boxed_local()
+    }
+
+    fn remove<'a>(&'a self, token: &'a ProviderToken) -> LocalBoxFuture<'a, VaultResult<()>> {
+        async move {
+            let response = self.client
+                .post(format!("{}/payment_methods/{}/detach", STRIPE_API, token.as_str()))
+                .bearer_auth(&self.secret_key)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(AppError::internal(format!("Stripe failed to detach the payment method ({})", response.status())));
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,265 @@
+//! Post-deploy smoke test: `bus-book --smoke-test` walks one throwaway customer through the
+//! whole journey against a running instance (register, search, hold, book, pay, fetch the
+//! ticket, cancel) and prints each step's latency. Exits non-zero if any step fails.
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::config::SmokeTestConfig;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+pub struct StepResult {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub elapsed: Duration,
+    pub detail: String,
+}
+
+/// The id of a document as the API returns it: a plain string or extended JSON `{"$oid": ...}`.
+pub fn document_id(value: &Value) -> Option<String> {
+    let id = value.get("id").or_else(|| value.get("_id"))?;
+    id.as_str().or_else(|| id.get("$oid").and_then(Value::as_str)).map(str::to_string)
+}
+
+/// First bus in a search response with a seat left on the travel date.
+pub fn bus_with_seats(buses: &Value) -> Option<String> {
+    buses.as_array()?
+        .iter()
+        .find(|bus| bus.get("seats_left").and_then(Value::as_i64).is_none_or(|left| left > 0))
+        .and_then(document_id)
+}
+
+pub fn first_available_seat(seat_map: &Value) -> Option<String> {
+    seat_map.get("seats")?
+        .as_array()?
+        .iter()
+        .find(|seat| seat.get("is_available").and_then(Value::as_bool) == Some(true))
+        .and_then(|seat| seat.get("seat_number")?.as_str().map(str::to_string))
+}
+
+/// One line per step, then the verdict.
+pub fn report(results: &[StepResult]) -> String {
+    let mut out = String::new();
+    for step in results {
+        let outcome = match step.outcome {
+            Outcome::Passed => "ok",
+            Outcome::Failed => "FAILED",
+            Outcome::Skipped => "skipped",
+        };
+        out.push_str(&format!("{:<10} {:<8} {:>6}ms  {}\n", step.name, outcome, step.elapsed.as_millis(), step.detail));
+    }
+    let failed = results.iter().filter(|s| s.outcome == Outcome::Failed).count();
+    let total: Duration = results.iter().map(|s| s.elapsed).sum();
+    if failed == 0 {
+        out.push_str(&format!("Smoke test passed in {}ms\n", total.as_millis()));
+    } else {
+        out.push_str(&format!("Smoke test FAILED: {} step(s) failed\n", failed));
+    }
+    out
+}
+
+struct Journey {
+    client: Client,
+    config: SmokeTestConfig,
+    token: Option<String>,
+    results: Vec<StepResult>,
+}
+
+impl Journey {
+    async fn send(&self, method: Method, path: &str, body: Option<Value>) -> Result<(StatusCode, reqwest::Response), String> {
+        let mut request = self.client.request(method, format!("{}{}", self.config.base_url, path));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        Ok((response.status(), response))
+    }
+
+    /// Sends the request and returns the JSON body when the status is one of `expected`.
+    async fn call(&self, method: Method, path: &str, body: Option<Value>, expected: &[StatusCode]) -> Result<Value, String> {
+        let (status, response) = self.send(method.clone(), path, body).await?;
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !expected.contains(&status) {
+            let snippet: String = text.chars().take(200).collect();
+            return Err(format!("{} {} returned {}: {}", method, path, status, snippet));
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+
+    fn record<T>(&mut self, name: &'static str, started: Instant, result: Result<(T, String), String>) -> Option<T> {
+        let elapsed = started.elapsed();
+        let (outcome, detail, value) = match result {
+            Ok((value, detail)) => (Outcome::Passed, detail, Some(value)),
+            Err(e) => (Outcome::Failed, e, None),
+        };
+        self.results.push(StepResult { name, outcome, elapsed, detail });
+        value
+    }
+
+    fn skip(&mut self, name: &'static str, reason: &str) {
+        self.results.push(StepResult { name, outcome: Outcome::Skipped, elapsed: Duration::ZERO, detail: reason.to_string() });
+    }
+
+    async fn register(&mut self) -> Result<((), String), String> {
+        let terms = self.call(Method::GET, "/api/terms", None, &[StatusCode::OK]).await?;
+        let stamp = chrono::Utc::now().timestamp_millis();
+        let email = format!("smoke+{}@{}", stamp, self.config.email_domain);
+        let auth = self.call(Method::POST, "/api/auth/register", Some(json!({
+            "username": format!("smoke-{}", stamp),
+            "email": email,
+            "password": format!("Smoke-{}-pass", stamp),
+            "accepted_terms_version": terms.get("version"),
+        })), &[StatusCode::OK, StatusCode::CREATED]).await?;
+        let token = auth.get("token").and_then(Value::as_str).ok_or("No token in the register response")?;
+        self.token = Some(token.to_string());
+        Ok(((), email))
+    }
+
+    async fn search(&self, travel_date: &str) -> Result<(String, String), String> {
+        let buses = self.call(Method::GET, &format!("/api/buses?date={}", travel_date), None, &[StatusCode::OK]).await?;
+        let bus_id = bus_with_seats(&buses).ok_or("No bus has seats left")?;
+        let found = buses.as_array().map(Vec::len).unwrap_or(0);
+        Ok((bus_id, format!("{} bus(es)", found)))
+    }
+
+    async fn hold(&self, bus_id: &str, travel_date: &str) -> Result<(String, String), String> {
+        let seat_map = self.call(Method::GET, &format!("/api/buses/{}/seats?date={}", bus_id, travel_date), None, &[StatusCode::OK]).await?;
+        let seat = first_available_seat(&seat_map).ok_or("No seat is available")?;
+        let hold = self.call(Method::POST, "/api/bookings/holds", Some(json!({
+            "bus_id": bus_id,
+            "travel_date": travel_date,
+            "seat_number": seat,
+        })), &[StatusCode::OK, StatusCode::CREATED]).await?;
+        let expires_at = hold.get("expires_at").and_then(Value::as_str).unwrap_or("?").to_string();
+        Ok((seat.clone(), format!("seat {} until {}", seat, expires_at)))
+    }
+
+    async fn book(&self, bus_id: &str, travel_date: &str, seat: &str) -> Result<(String, String), String> {
+        let booking = self.call(Method::POST, "/api/bookings", Some(json!({
+            "bus_id": bus_id,
+            "travel_date": travel_date,
+            "seat_number": seat,
+            "passenger": { "name": "Smoke Test", "age": "30", "gender": "Other" },
+        })), &[StatusCode::OK, StatusCode::CREATED]).await?;
+        let booking_id = document_id(&booking).ok_or("No id in the booking response")?;
+        let status = booking.get("status").and_then(Value::as_str).unwrap_or("?");
+        Ok((booking_id.clone(), format!("booking {} {}", booking_id, status)))
+    }
+
+    /// Stores the provider's test token and removes it again; the booking flow itself takes no payment yet.
+    async fn pay(&self, token: &str) -> Result<((), String), String> {
+        let method = self.call(Method::POST, "/api/payment-methods", Some(json!({
+            "provider": self.config.payment_provider,
+            "token": token,
+        })), &[StatusCode::OK, StatusCode::CREATED]).await?;
+        let method_id = document_id(&method).ok_or("No id in the payment method response")?;
+        self.call(Method::DELETE, &format!("/api/payment-methods/{}", method_id), None, &[StatusCode::OK, StatusCode::NO_CONTENT]).await?;
+        Ok(((), format!("{} payment method accepted", self.config.payment_provider)))
+    }
+
+    async fn ticket(&self, booking_id: &str) -> Result<((), String), String> {
+        let (status, response) = self.send(Method::GET, &format!("/api/bookings/{}/receipt.pdf", booking_id), None).await?;
+        if status != StatusCode::OK {
+            return Err(format!("Receipt returned {}", status));
+        }
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        if !body.starts_with(b"%PDF") {
+            return Err("Receipt is not a PDF".to_string());
+        }
+        Ok(((), format!("{} byte PDF", body.len())))
+    }
+
+    async fn cancel(&self, booking_id: &str) -> Result<((), String), String> {
+        self.call(Method::DELETE, &format!("/api/bookings/{}", booking_id), None, &[StatusCode::OK, StatusCode::NO_CONTENT]).await?;
+        Ok(((), "cancelled".to_string()))
+    }
+}
+
+/// Runs the journey and prints the report; true when every step that ran passed.
+pub async fn run(config: SmokeTestConfig) -> bool {
+    let client = match Client::builder().timeout(Duration::from_secs(config.timeout_secs)).build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Smoke test could not start: {}", e);
+            return false;
+        }
+    };
+    let travel_date = config.travel_date.clone().unwrap_or_else(|| {
+        (chrono::Utc::now() + chrono::Duration::days(7)).format("%Y-%m-%d").to_string()
+    });
+    println!("🧪 Smoke test against {} for {}", config.base_url, travel_date);
+    let mut journey = Journey { client, config, token: None, results: Vec::new() };
+
+    let started = Instant::now();
+    let result = journey.register().await;
+    let registered = journey.record("register", started, result).is_some();
+
+    let mut bus_id = None;
+    if registered {
+        let started = Instant::now();
+        let result = journey.search(&travel_date).await;
+        bus_id = journey.record("search", started, result);
+    } else {
+        journey.skip("search", "not signed in");
+    }
+
+    let mut seat = None;
+    match &bus_id {
+        Some(bus_id) => {
+            let started = Instant::now();
+            let result = journey.hold(bus_id, &travel_date).await;
+            seat = journey.record("hold", started, result);
+        }
+        None => journey.skip("hold", "no bus found"),
+    }
+
+    let mut booking_id = None;
+    match (&bus_id, &seat) {
+        (Some(bus_id), Some(seat)) => {
+            let started = Instant::now();
+            let result = journey.book(bus_id, &travel_date, seat).await;
+            booking_id = journey.record("book", started, result);
+        }
+        _ => journey.skip("book", "no seat held"),
+    }
+
+    match (&booking_id, journey.config.payment_token.clone()) {
+        (Some(_), Some(token)) => {
+            let started = Instant::now();
+            let result = journey.pay(&token).await;
+            journey.record("pay", started, result);
+        }
+        (Some(_), None) => journey.skip("pay", "SMOKE_TEST_PAYMENT_TOKEN not set"),
+        (None, _) => journey.skip("pay", "no booking"),
+    }
+
+    match &booking_id {
+        Some(booking_id) => {
+            let started = Instant::now();
+            let result = journey.ticket(booking_id).await;
+            journey.record("ticket", started, result);
+            // Always attempted once a booking exists, so smoke runs don't leave seats taken
+            let started = Instant::now();
+            let result = journey.cancel(booking_id).await;
+            journey.record("cancel", started, result);
+        }
+        None => {
+            journey.skip("ticket", "no booking");
+            journey.skip("cancel", "no booking");
+        }
+    }
+
+    print!("{}", report(&journey.results));
+    !journey.results.iter().any(|s| s.outcome == Outcome::Failed)
+}
@@ -0,0 +1,67 @@
+use actix_web::{body::to_bytes, http::{header::RETRY_AFTER, StatusCode}, ResponseError};
+
+use crate::error::AppError;
+
+async fn body_of(e: AppError) -> serde_json::Value {
+    let bytes = to_bytes(e.error_response().into_body()).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[test]
+fn each_kind_answers_with_its_own_status() {
+    assert_eq!(AppError::not_found("Booking not found").status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(AppError::conflict("Seat is already booked").status_code(), StatusCode::CONFLICT);
+    assert_eq!(AppError::unauthorized("Invalid credentials").status_code(), StatusCode::UNAUTHORIZED);
+    assert_eq!(AppError::forbidden("Driver is not assigned to this trip").status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(AppError::gone("This booking is no longer available").status_code(), StatusCode::GONE);
+    assert_eq!(AppError::unavailable("Conversion reporting is not enabled").status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(AppError::from("Seat number is required").status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(AppError::internal("Failed to encrypt").status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[actix_web::test]
+async fn client_errors_keep_their_message() {
+    let body = body_of(AppError::conflict("Seat is already booked")).await;
+    assert_eq!(body["error"], "Seat is already booked");
+    assert_eq!(body["code"], "conflict");
+}
+
+#[actix_web::test]
+async fn server_errors_hide_the_details() {
+    let body = body_of(AppError::internal("Failed to reach mongodb://admin:secret@db")).await;
+    assert_eq!(body["code"], "internal");
+    assert!(!body["error"].as_str().unwrap().contains("secret"));
+}
+
+#[test]
+fn library_errors_land_in_the_right_kind() {
+    let bad_id: AppError = mongodb::bson::oid::ObjectId::parse_str("nope").unwrap_err().into();
+    assert_eq!(bad_id.code(), "validation");
+    let bad_date: AppError = chrono::NaiveDate::parse_from_str("20-11-2026", "%Y-%m-%d").unwrap_err().into();
+    assert_eq!(bad_date.code(), "validation");
+    let bad_json: AppError = serde_json::from_str::<u32>("{").unwrap_err().into();
+    assert_eq!(bad_json.code(), "internal");
+    assert!(bad_json.as_database().is_none());
+    let bad_token: AppError = jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::ExpiredSignature).into();
+    assert_eq!(bad_token.code(), "unauthorized");
+}
+
+#[actix_web::test]
+async fn refusals_carry_what_the_client_acts_on() {
+    let limited = AppError::too_many_requests("Too many requests, please slow down", Some(0));
+    let response = limited.error_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "1");
+    assert_eq!(body_of(limited).await["code"], "rate_limited");
+
+    let terms = body_of(AppError::TermsNotAccepted { version: "2026-03".to_string(), url: None }).await;
+    assert_eq!(terms["code"], "TERMS_ACCEPTANCE_REQUIRED");
+    assert_eq!(terms["current_version"], "2026-03");
+
+    let queue = "/api/waiting-room/65f1c0ffee0000000000b05e/2026-12-20".to_string();
+    let room = body_of(AppError::WaitingRoom { queue: queue.clone() }).await;
+    assert_eq!((room["code"].as_str(), room["waiting_room"].as_str()), (Some("waiting_room"), Some(queue.as_str())));
+
+    assert_eq!(AppError::CaptchaRequired.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(body_of(AppError::Blocked).await["code"], "blocked");
+}
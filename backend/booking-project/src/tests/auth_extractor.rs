@@ -0,0 +1,84 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{call_and_read_body, call_service, init_service, TestRequest};
+use actix_web::{web, App, HttpResponse, ResponseError};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::oid::ObjectId;
+
+use crate::error::AppError;
+use crate::middleware::auth::{jwt_secret, AuthenticatedUser, OptionalClaims, RequirePermission};
+use crate::middleware::session::session_refusal;
+use crate::models::permissions::{FINANCE_READ, FINANCE_WRITE};
+use crate::models::Claims;
+
+fn bearer(role: &str) -> (String, String) {
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    let claims = Claims::new(ObjectId::new().to_hex(), role.to_string(), exp);
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref())).unwrap();
+    (claims.sub, format!("Bearer {}", token))
+}
+
+async fn whoami(user: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{} {} {}", user.actor(), user.role, user.can(FINANCE_WRITE)))
+}
+
+async fn greeting(claims: OptionalClaims) -> HttpResponse {
+    HttpResponse::Ok().body(claims.user_id().unwrap_or_else(|| "guest".to_string()))
+}
+
+#[actix_web::test]
+async fn extractor_identifies_the_caller_or_refuses() {
+    let app = init_service(App::new().route("/me", web::get().to(whoami))).await;
+
+    let (id, header) = bearer("finance");
+    let req = TestRequest::get().uri("/me").insert_header(("Authorization", header)).to_request();
+    assert_eq!(call_and_read_body(&app, req).await, format!("user:{} finance true", id));
+
+    let anonymous = call_service(&app, TestRequest::get().uri("/me").to_request()).await;
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let forged = TestRequest::get().uri("/me").insert_header(("Authorization", "Bearer a.b.c")).to_request();
+    assert_eq!(call_service(&app, forged).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn permission_guard_is_declared_on_the_route() {
+    let app = init_service(
+        App::new().service(web::resource("/ledger").wrap(RequirePermission(FINANCE_READ)).route(web::get().to(whoami))),
+    )
+    .await;
+    let get = |header: String| TestRequest::get().uri("/ledger").insert_header(("Authorization", header)).to_request();
+
+    let (_, finance) = bearer("finance");
+    assert_eq!(call_service(&app, get(finance)).await.status(), StatusCode::OK);
+
+    let (_, agent) = bearer("agent");
+    assert_eq!(call_service(&app, get(agent)).await.status(), StatusCode::FORBIDDEN);
+
+    let anonymous = call_service(&app, TestRequest::get().uri("/ledger").to_request()).await;
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let forged = call_service(&app, get("Bearer not-a-jwt".to_string())).await;
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn optional_claims_let_everyone_through() {
+    let app = init_service(App::new().route("/hello", web::get().to(greeting))).await;
+
+    let (id, header) = bearer("customer");
+    let req = TestRequest::get().uri("/hello").insert_header(("Authorization", header)).to_request();
+    assert_eq!(call_and_read_body(&app, req).await, id);
+
+    assert_eq!(call_and_read_body(&app, TestRequest::get().uri("/hello").to_request()).await, "guest");
+
+    let forged = TestRequest::get().uri("/hello").insert_header(("Authorization", "Bearer a.b.c")).to_request();
+    assert_eq!(call_and_read_body(&app, forged).await, "guest");
+}
+
+#[test]
+fn sessions_that_cannot_be_checked_are_refused() {
+    assert!(session_refusal(Ok(false)).is_none());
+    assert!(matches!(session_refusal(Ok(true)), Some(AppError::Unauthorized(_))));
+    let refusal = session_refusal(Err(AppError::Database(std::io::Error::from(std::io::ErrorKind::TimedOut).into()))).unwrap();
+    assert_eq!(refusal.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,28 @@
+// Malformed Authorization headers must be refused, never panic; fuzz/ explores the same path at scale.
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::oid::ObjectId;
+
+use crate::middleware::auth::{bearer_token, claims_from_header, jwt_secret};
+use crate::models::Claims;
+
+#[test]
+fn only_well_formed_bearer_values_yield_a_token() {
+    assert_eq!(bearer_token("Bearer abc.def.ghi"), Some("abc.def.ghi"));
+    assert_eq!(bearer_token("Bearer "), None);
+    assert_eq!(bearer_token("Bearer"), None);
+    assert_eq!(bearer_token("bearer abc"), None);
+    assert_eq!(bearer_token("Basic dXNlcjpwYXNz"), None);
+}
+
+#[test]
+fn garbage_headers_are_refused() {
+    for header in [&b""[..], b"Bearer \xff\xfe", b"Bearer a.b", b"Bearer ....", b"Bearer \xc3\x28.e30.x"] {
+        assert!(claims_from_header(header).is_none());
+    }
+
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    let claims = Claims::new(ObjectId::new().to_hex(), "user".to_string(), exp);
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref())).unwrap();
+    assert_eq!(claims_from_header(format!("Bearer {}", token).as_bytes()).map(|c| c.sub), Some(claims.sub.clone()));
+    assert!(claims_from_header(format!("Bearer {}x", token).as_bytes()).is_none());
+}
@@ -0,0 +1,196 @@
+// The booking screens in the frontend read these exact keys; a rename here breaks them silently.
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde_json::json;
+
+use crate::models::booking::{Booking, BookingDetailResponse, BookingEvent, CreateBookingRequest, CreateGroupBookingRequest, NextOfKin, Passenger};
+use crate::models::bus::Bus;
+use crate::tests::fixtures;
+
+fn bus(id: ObjectId) -> Bus {
+    Bus { id: Some(id), ..fixtures::bus() }
+}
+
+fn booking(id: Option<ObjectId>, bus_id: ObjectId, passenger: Option<Passenger>) -> Booking {
+    Booking {
+        id,
+        user_id: ObjectId::new(),
+        bus_id,
+        seat_number: "12".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        booking_date: DateTime::from_millis(1_765_000_000_000),
+        status: "Confirmed".to_string(),
+        passenger,
+        history: Vec::new(),
+        terms_version: None,
+        policy_version: None,
+        risk: None,
+        country: None,
+        reference: None,
+    }
+}
+
+fn to_json(response: BookingDetailResponse) -> serde_json::Value {
+    serde_json::to_value(response).unwrap()
+}
+
+#[test]
+fn serializes_full_booking_in_camel_case() {
+    let (id, bus_id) = (ObjectId::new(), ObjectId::new());
+    let passenger = Passenger { name: "Achieng Otieno".to_string(), age: "29".to_string(), gender: "female".to_string(), phone: None, id_number: None, next_of_kin: None };
+    let b = booking(Some(id), bus_id, Some(passenger));
+    let booking_date = b.booking_date.to_string();
+
+    let value = to_json(BookingDetailResponse::from((b, Some(bus(bus_id)))));
+
+    assert_eq!(value, json!({
+        "id": id.to_hex(),
+        "busId": bus_id.to_hex(),
+        "busName": "Easy Coach - KCH 123A",
+        "busType": "Standard",
+        "from": "Nairobi",
+        "to": "Kisumu",
+        "departure": "08:15 AM",
+        "arrival": "04:30 PM",
+        "totalPrice": 1450.0,
+        "seats": ["12"],
+        "status": "confirmed",
+        "date": "2026-12-20",
+        "bookingDate": booking_date,
+        "bookingId": id.to_hex().to_uppercase(),
+        "passengers": [
+            { "name": "Achieng Otieno", "seatNumber": "12", "age": "29", "gender": "female" }
+        ],
+    }));
+}
+
+#[test]
+fn falls_back_when_bus_and_passenger_are_missing() {
+    let bus_id = ObjectId::new();
+    let value = to_json(BookingDetailResponse::from((booking(None, bus_id, None), None)));
+
+    assert_eq!(value["id"], json!(null));
+    assert_eq!(value["bookingId"], "N/A");
+    assert_eq!(value["busName"], "Unknown Bus");
+    for key in ["busType", "from", "to", "departure", "arrival"] {
+        assert_eq!(value[key], "Unknown", "{} fallback", key);
+    }
+    assert_eq!(value["totalPrice"], json!(0.0));
+    assert_eq!(value["passengers"], json!([
+        { "name": "User", "seatNumber": "12", "age": "N/A", "gender": "N/A" }
+    ]));
+}
+
+#[test]
+fn lowercases_status() {
+    let bus_id = ObjectId::new();
+    let mut b = booking(Some(ObjectId::new()), bus_id, None);
+    b.status = "Cancelled".to_string();
+
+    assert_eq!(to_json(BookingDetailResponse::from((b, Some(bus(bus_id)))))["status"], "cancelled");
+}
+
+#[test]
+fn includes_timeline_only_when_set() {
+    let bus_id = ObjectId::new();
+    let mut b = booking(Some(ObjectId::new()), bus_id, None);
+    b.history.push(BookingEvent::new("cancelled", "admin", None));
+
+    let timeline = b.timeline();
+    let mut detail = BookingDetailResponse::from((b, Some(bus(bus_id))));
+    assert!(serde_json::to_value(&detail).unwrap().get("timeline").is_none());
+
+    detail.timeline = Some(timeline);
+    let value = to_json(detail);
+    let events: Vec<&str> = value["timeline"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, ["created", "cancelled"]);
+}
+
+#[test]
+fn never_serializes_next_of_kin() {
+    let bus_id = ObjectId::new();
+    let passenger: Passenger = serde_json::from_value(json!({
+        "name": "Achieng Otieno", "age": "29", "gender": "female",
+        "next_of_kin": { "name": "Otieno Ouma", "phone": "0712345678" },
+    })).unwrap();
+    assert!(passenger.next_of_kin.is_some());
+
+    let b = booking(Some(ObjectId::new()), bus_id, Some(passenger));
+    let raw = serde_json::to_string(&b).unwrap();
+    assert!(!raw.contains("Otieno Ouma") && !raw.contains("next_of_kin"));
+    let detail = serde_json::to_string(&BookingDetailResponse::from((b, Some(bus(bus_id))))).unwrap();
+    assert!(!detail.contains("Otieno Ouma"));
+}
+
+#[test]
+fn validates_next_of_kin() {
+    let kin = NextOfKin { name: "  Otieno Ouma ".to_string(), phone: "+254 712 345 678".to_string() };
+    let valid = kin.validated().unwrap();
+    assert_eq!(valid.to_string(), "Otieno Ouma (254712345678)");
+
+    assert!(NextOfKin { name: " ".to_string(), phone: "0712345678".to_string() }.validated().is_err());
+    assert!(NextOfKin { name: "Otieno".to_string(), phone: "12345".to_string() }.validated().is_err());
+}
+
+#[test]
+fn group_bookings_name_each_seat_once() {
+    let group = |seats: serde_json::Value, passengers: serde_json::Value| -> CreateGroupBookingRequest {
+        serde_json::from_value(json!({
+            "bus_id": ObjectId::new().to_hex(),
+            "seat_numbers": seats,
+            "travel_date": "2026-12-20",
+            "passengers": passengers,
+        })).unwrap()
+    };
+    let passenger = json!({ "name": "Achieng", "age": "34", "gender": "F" });
+
+    let family = group(json!(["4", "5", "6"]), json!([]));
+    assert!(family.validate().is_ok());
+    assert!(family.seats().iter().all(|(_, p)| p.is_none()));
+    let named = group(json!(["4", "5"]), json!([passenger, { "name": "Baraka", "age": "8", "gender": "M" }]));
+    assert!(named.validate().is_ok());
+    assert_eq!(named.seats()[1].1.as_ref().unwrap().name, "Baraka");
+
+    assert!(group(json!([]), json!([])).validate().is_err());
+    assert!(group(json!(["4", "5", "4"]), json!([])).validate().is_err());
+    assert!(group(json!(["1", "2", "3", "4", "5", "6", "7"]), json!([])).validate().is_err());
+    assert!(group(json!(["4", "5"]), json!([passenger])).validate().is_err());
+
+    let single: CreateBookingRequest = serde_json::from_value(json!({
+        "bus_id": ObjectId::new().to_hex(),
+        "seat_number": "12",
+        "travel_date": "2026-12-20",
+        "passenger": passenger,
+    })).unwrap();
+    let as_group = CreateGroupBookingRequest::from(&single);
+    assert!(as_group.validate().is_ok());
+    assert_eq!(as_group.seat_numbers, vec!["12"]);
+    assert_eq!(as_group.passengers.len(), 1);
+}
+
+#[test]
+fn only_confirmed_bookings_are_texted_a_confirmation() {
+    let with_phone = |status: &str| {
+        let passenger = Passenger { name: "Achieng Otieno".to_string(), age: "29".to_string(), gender: "female".to_string(), phone: Some(" 0712345678 ".to_string()), id_number: None, next_of_kin: None };
+        Booking { status: status.to_string(), ..booking(Some(ObjectId::new()), ObjectId::new(), Some(passenger)) }
+    };
+    assert_eq!(with_phone("Confirmed").confirmation_sms_phone(), Some("0712345678"));
+    // Held for payment or review: the SMS goes out when that clears
+    for held in ["PendingPayment", "PendingReview", "Cancelled"] {
+        assert_eq!(with_phone(held).confirmation_sms_phone(), None, "{} was texted", held);
+    }
+    assert_eq!(booking(Some(ObjectId::new()), ObjectId::new(), None).confirmation_sms_phone(), None);
+}
+
+#[test]
+fn passengers_see_the_booking_reference_where_there_is_one() {
+    let id = ObjectId::new();
+    let referenced = Booking { reference: Some("BB-7KQ2MX".to_string()), ..booking(Some(id), ObjectId::new(), None) };
+    assert_eq!(referenced.display_reference(), "BB-7KQ2MX");
+    // Booked before references existed
+    assert_eq!(booking(Some(id), ObjectId::new(), None).display_reference(), id.to_hex().to_uppercase());
+}
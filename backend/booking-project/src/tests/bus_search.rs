@@ -0,0 +1,21 @@
+use actix_web::web::Query;
+
+use crate::models::bus::{departure_sort_key, BusSearchQuery};
+
+#[test]
+fn search_query_takes_type_as_a_parameter() {
+    let query = Query::<BusSearchQuery>::from_query("from=nairobi&to=KISUMU&date=2026-12-20&type=VIP").unwrap();
+    assert_eq!(query.from, "nairobi");
+    assert_eq!(query.date.as_deref(), Some("2026-12-20"));
+    assert_eq!(query.bus_type.as_deref(), Some("VIP"));
+
+    let query = Query::<BusSearchQuery>::from_query("from=Nairobi&to=Kisumu").unwrap();
+    assert!(query.date.is_none() && query.bus_type.is_none());
+}
+
+#[test]
+fn results_run_in_departure_order() {
+    let mut times = vec!["08:00 PM", "soon", "07:30 AM", "12:15 PM"];
+    times.sort_by_cached_key(|t| departure_sort_key(t));
+    assert_eq!(times, vec!["07:30 AM", "12:15 PM", "08:00 PM", "soon"]);
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::models::charter::{
+    charter_deposit, Charter, CharterListQuery, CharterQuote, CharterRequest, CHARTER_ACCEPTED, CHARTER_QUOTED,
+    CHARTER_REQUESTED, CHARTER_RESERVED,
+};
+use crate::models::permissions::{admin_permission, BOOKINGS_READ, BUSES_WRITE};
+use crate::models::tenancy::is_tenant_aware;
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+fn millis(time: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(time.timestamp_millis())
+}
+
+fn charter(status: &str, quote_expires: Option<&str>) -> Charter {
+    Charter {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        from: "Nairobi".to_string(),
+        to: "Naivasha".to_string(),
+        travel_date: "2026-12-12".to_string(),
+        passengers: 40,
+        notes: Some("Pick-up at the church on Ngong Road".to_string()),
+        status: status.to_string(),
+        quote: quote_expires.map(|expires| CharterQuote {
+            bus_id: ObjectId::new(),
+            bus_number: "Mash Poa - KDC 220X".to_string(),
+            operator: "Mash Poa".to_string(),
+            price: 45000.0,
+            deposit: charter_deposit(45000.0, 30.0),
+            note: None,
+            quoted_by: "user:65f1c0ffee0000000000a11c".to_string(),
+            quoted_at: millis(at("2026-11-01T08:00:00+03:00")),
+            expires_at: millis(at(expires)),
+        }),
+        decline_reason: None,
+        created_at: millis(at("2026-11-01T07:00:00+03:00")),
+        updated_at: millis(at("2026-11-01T08:00:00+03:00")),
+        accepted_at: None,
+        reserved_at: None,
+    }
+}
+
+#[test]
+fn charter_deposits_round_up_to_whole_shillings() {
+    assert_eq!(charter_deposit(45000.0, 30.0), 13500.0);
+    assert_eq!(charter_deposit(33333.0, 30.0), 10000.0);
+    assert_eq!(charter_deposit(1000.0, 100.0), 1000.0);
+}
+
+#[test]
+fn a_quote_can_only_be_accepted_until_it_expires() {
+    let now = at("2026-11-02T08:00:00+03:00");
+    assert_eq!(charter(CHARTER_REQUESTED, None).state(now), "requested");
+    assert_eq!(charter(CHARTER_QUOTED, Some("2026-11-03T08:00:00+03:00")).state(now), "quoted");
+    assert_eq!(charter(CHARTER_QUOTED, Some("2026-11-02T08:00:00+03:00")).state(now), "quote_expired");
+    // Once accepted the quote's expiry no longer matters
+    assert_eq!(charter(CHARTER_ACCEPTED, Some("2026-11-01T09:00:00+03:00")).state(now), "awaiting_deposit");
+    assert_eq!(charter(CHARTER_RESERVED, Some("2026-11-01T09:00:00+03:00")).state(now), "reserved");
+}
+
+#[test]
+fn charters_are_requested_ahead_for_a_bus_sized_group() {
+    let req = |travel_date: &str, passengers: i32| CharterRequest {
+        from: "Nairobi".to_string(),
+        to: "Naivasha".to_string(),
+        travel_date: travel_date.to_string(),
+        passengers,
+        notes: None,
+    };
+    assert!(req("2026-12-12", 40).validate("2026-11-01").is_ok());
+    assert!(req("2026-11-01", 40).validate("2026-11-01").is_err());
+    assert!(req("12/12/2026", 40).validate("2026-11-01").is_err());
+    assert!(req("2026-12-12", 0).validate("2026-11-01").is_err());
+    assert!(req("2026-12-12", 101).validate("2026-11-01").is_err());
+
+    let mut round_trip = req("2026-12-12", 40);
+    round_trip.to = "nairobi".to_string();
+    assert!(round_trip.validate("2026-11-01").is_err());
+}
+
+#[test]
+fn operators_answer_charters_from_their_own_admin_scope() {
+    assert_eq!(admin_permission("GET", "/charters"), BOOKINGS_READ);
+    assert_eq!(admin_permission("PUT", "/charters/65f1c0ffee0000000000a11c/quote"), BUSES_WRITE);
+    assert!(is_tenant_aware("POST", "/charters/65f1c0ffee0000000000a11c/decline"));
+
+    let query = |status: Option<&str>| CharterListQuery { status: status.map(str::to_string) };
+    assert_eq!(query(Some("requested")).status(), Ok(Some(CHARTER_REQUESTED)));
+    assert_eq!(query(None).status(), Ok(None));
+    assert!(query(Some("pending")).status().is_err());
+}
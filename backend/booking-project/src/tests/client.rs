@@ -0,0 +1,60 @@
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::client::{BusBookClient, ClientError};
+use crate::models::booking::{BookingConfirmation, BookingPageQuery, Passenger};
+use crate::models::bus::BusSearchQuery;
+use crate::models::Booking;
+
+fn query_string<Q: serde::Serialize>(query: &Q) -> String {
+    reqwest::Client::new().get("http://localhost/api").query(query).build().unwrap().url().query().unwrap_or_default().to_string()
+}
+
+#[test]
+fn booking_confirmations_decode_from_what_the_server_sends() {
+    let booking = Booking {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        bus_id: ObjectId::new(),
+        seat_number: "14".to_string(),
+        travel_date: "2026-11-20".to_string(),
+        booking_date: bson::DateTime::now(),
+        status: "Confirmed".to_string(),
+        passenger: Some(Passenger {
+            name: "Wanjiru Kamau".to_string(),
+            age: "34".to_string(),
+            gender: "female".to_string(),
+            phone: None,
+            id_number: None,
+            next_of_kin: None,
+        }),
+        history: Vec::new(),
+        terms_version: None,
+        policy_version: None,
+        risk: None,
+        country: None,
+        reference: Some("BB-7K2F9Q".to_string()),
+    };
+    let (id, booking_date) = (booking.id, booking.booking_date);
+    let sent = serde_json::to_string(&BookingConfirmation { booking, partner_offers: Vec::new() }).unwrap();
+    let received: BookingConfirmation = serde_json::from_str(&sent).unwrap();
+    assert_eq!(received.booking.id, id);
+    assert_eq!(received.booking.booking_date, booking_date);
+    assert_eq!(received.booking.reference.as_deref(), Some("BB-7K2F9Q"));
+}
+
+#[test]
+fn queries_encode_the_way_handlers_read_them() {
+    let search = BusSearchQuery { from: "Nairobi".to_string(), to: "Mombasa".to_string(), date: None, bus_type: Some("VIP".to_string()) };
+    assert_eq!(query_string(&search), "from=Nairobi&to=Mombasa&type=VIP");
+    let page = BookingPageQuery { after: Some("65f1c0ffee0000000000b001".to_string()), limit: Some(20) };
+    assert_eq!(query_string(&page), "after=65f1c0ffee0000000000b001&limit=20");
+}
+
+#[test]
+fn clients_start_signed_out_until_given_a_token() {
+    let client = BusBookClient::new("https://api.example.com/");
+    assert!(client.token().is_none());
+    assert_eq!(client.with_token("abc").token(), Some("abc"));
+    let error = ClientError::Api { status: reqwest::StatusCode::NOT_FOUND, message: "Booking not found".to_string() };
+    assert_eq!(error.to_string(), "404 Not Found: Booking not found");
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use crate::models::bus::Bus;
+use crate::tests::fixtures::{self, route};
+use crate::models::compare::compare_operators;
+use crate::models::punctuality::Punctuality;
+
+fn bus(bus_number: &str, departure: &str, price: f64) -> Bus {
+    Bus {
+        id: None,
+        bus_number: bus_number.to_string(),
+        route: route("Nairobi", "Mombasa", departure, "11:00 PM", price),
+        ..fixtures::bus()
+    }
+}
+
+#[test]
+fn operators_are_compared_cheapest_first() {
+    let trips = vec![
+        (bus("Modern Coast - KBZ 001D", "09:00 AM", 1600.0), Some(12)),
+        (bus("Dreamline - KCA 200A", "08:30 PM", 1500.0), Some(40)),
+        (bus("Modern Coast - KBZ 002D", "07:00 AM", 1800.0), Some(3)),
+    ];
+    let mut punctuality = BTreeMap::new();
+    let mut on_time = Punctuality::default();
+    on_time.record(5);
+    on_time.record(30);
+    punctuality.insert("Modern Coast".to_string(), on_time);
+
+    let rows = compare_operators(&trips, &punctuality);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].operator, "Dreamline");
+    assert!(rows[0].punctuality.is_none());
+
+    let modern = &rows[1];
+    assert_eq!(modern.trips, 2);
+    assert_eq!(modern.min_price, 1600.0);
+    assert_eq!(modern.earliest_departure, "07:00 AM");
+    assert_eq!(modern.seats_left, Some(15));
+    assert_eq!(modern.punctuality.as_ref().unwrap().on_time_percent, 50.0);
+    assert!(modern.average_rating.is_none());
+}
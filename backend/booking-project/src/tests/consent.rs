@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::models::consent::{marketing_purpose, validate_consents, ConsentResponse, ConsentState, UserConsents};
+
+#[test]
+fn undecided_purposes_are_reported_as_not_granted() {
+    let mut purposes = HashMap::new();
+    purposes.insert("marketing_sms".to_string(), ConsentState {
+        granted: true,
+        source: "checkout".to_string(),
+        updated_at: bson::DateTime::now(),
+    });
+    let consents = UserConsents { id: None, user_id: ObjectId::new(), purposes };
+
+    let listed = ConsentResponse::all(&consents);
+    let granted: Vec<_> = listed.iter().map(|c| (c.purpose.as_str(), c.granted)).collect();
+    assert_eq!(granted, vec![("marketing_email", false), ("marketing_sms", true), ("analytics", false)]);
+    assert_eq!(listed[1].source.as_deref(), Some("checkout"));
+    assert!(listed[0].source.is_none());
+    assert!(!consents.is_granted("marketing_email"));
+}
+
+#[test]
+fn promotions_need_the_consent_for_their_channel() {
+    assert_eq!(marketing_purpose("email"), Some("marketing_email"));
+    assert_eq!(marketing_purpose("sms"), Some("marketing_sms"));
+    assert_eq!(marketing_purpose("push"), None);
+
+    let mut changes = HashMap::new();
+    changes.insert("analytics".to_string(), true);
+    assert!(validate_consents(&changes, "registration").is_ok());
+    assert!(validate_consents(&changes, "newsletter_popup").is_err());
+    changes.insert("third_party_sharing".to_string(), true);
+    assert!(validate_consents(&changes, "registration").is_err());
+}
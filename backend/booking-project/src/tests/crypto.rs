@@ -0,0 +1,22 @@
+use crate::crypto::{open, seal};
+
+#[test]
+fn round_trips_with_a_fresh_nonce() {
+    let first = seal("Otieno Ouma", "booking-1").unwrap();
+    let second = seal("Otieno Ouma", "booking-1").unwrap();
+    assert_ne!(first, second);
+    assert!(!first.contains("Otieno"));
+    assert_eq!(open(&first, "booking-1").unwrap(), "Otieno Ouma");
+}
+
+#[test]
+fn refuses_another_context_or_tampering() {
+    let sealed = seal("Otieno Ouma", "booking-1").unwrap();
+    assert!(open(&sealed, "booking-2").is_err());
+
+    let mut tampered = sealed.clone().into_bytes();
+    let last = tampered.len() - 3;
+    tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+    assert!(open(&String::from_utf8(tampered).unwrap(), "booking-1").is_err());
+    assert!(open("v0:abcd", "booking-1").is_err());
+}
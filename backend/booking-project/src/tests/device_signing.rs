@@ -0,0 +1,88 @@
+use crate::models::bus::Bus;
+use crate::models::device::{new_device_key, sign_request, signing_string, DeviceSignature, SignedDevice};
+use crate::models::trip::TripLocationRequest;
+use crate::tests::fixtures;
+
+const PATH: &str = "/api/conductor/trips/65f1c0ffee0000000000b0b5/2026-11-20/check-in";
+const BODY: &[u8] = br#"{"seat_number":"14"}"#;
+const NOW: i64 = 1_795_000_000;
+
+fn signed(key: &str) -> DeviceSignature {
+    let nonce = "c0ffee00-1234-abcd".to_string();
+    DeviceSignature {
+        device_id: "65f1c0ffee0000000000d001".to_string(),
+        timestamp: NOW,
+        signature: sign_request(key, "POST", PATH, NOW, &nonce, BODY),
+        nonce,
+    }
+}
+
+fn fix(latitude: f64, longitude: f64) -> TripLocationRequest {
+    TripLocationRequest { latitude, longitude, speed_kmh: None, heading: None, recorded_at: None }
+}
+
+#[test]
+fn signing_strings_cover_the_request_line_time_nonce_and_body() {
+    let signed = signing_string("post", PATH, NOW, "abc", BODY);
+    let lines: Vec<&str> = signed.split('\n').collect();
+    assert_eq!(lines[..4], ["POST", PATH, "1795000000", "abc"]);
+    assert_ne!(lines[4], signing_string("POST", PATH, NOW, "abc", b"").split('\n').nth(4).unwrap());
+}
+
+#[test]
+fn signatures_verify_only_for_the_request_they_were_made_for() {
+    let key = new_device_key().unwrap();
+    let signature = signed(&key);
+    assert!(signature.verify(&key, "POST", PATH, BODY));
+    assert!(!signature.verify(&key, "POST", PATH, br#"{"seat_number":"15"}"#));
+    assert!(!signature.verify(&key, "POST", &format!("{}?x=1", PATH), BODY));
+    assert!(!signature.verify(&key, "GET", PATH, BODY));
+    assert!(!signature.verify(&new_device_key().unwrap(), "POST", PATH, BODY));
+}
+
+#[test]
+fn stale_timestamps_and_odd_nonces_are_refused() {
+    let signature = signed("key");
+    assert!(signature.validate(NOW + 299, 300).is_ok());
+    assert!(signature.validate(NOW - 301, 300).is_err());
+    assert!(signature.validate(NOW + 301, 300).is_err());
+
+    let short = DeviceSignature { nonce: "abc".to_string(), ..signed("key") };
+    assert!(short.validate(NOW, 300).is_err());
+    let spaced = DeviceSignature { nonce: "c0ffee00 1234 abcd".to_string(), ..signed("key") };
+    assert!(spaced.validate(NOW, 300).is_err());
+}
+
+#[test]
+fn device_keys_are_fresh_each_time() {
+    let key = new_device_key().unwrap();
+    assert_eq!(key.len(), 43);
+    assert_ne!(key, new_device_key().unwrap());
+}
+
+#[test]
+fn location_fixes_need_real_coordinates_and_a_past_time() {
+    let now = chrono::Utc::now();
+    assert_eq!(fix(-1.2921, 36.8219).validated_time(now), Ok(now));
+    assert!(fix(91.0, 36.8).validated_time(now).is_err());
+    assert!(fix(-1.29, 181.0).validated_time(now).is_err());
+
+    let late = TripLocationRequest { recorded_at: Some("2026-01-10T08:30:00+03:00".to_string()), ..fix(-1.29, 36.82) };
+    assert_eq!(late.validated_time(now).unwrap().to_rfc3339(), "2026-01-10T05:30:00+00:00");
+    let ahead = TripLocationRequest { recorded_at: Some((now + chrono::Duration::hours(1)).to_rfc3339()), ..fix(-1.29, 36.82) };
+    assert!(ahead.validated_time(now).is_err());
+    let backwards = TripLocationRequest { heading: Some(360.0), ..fix(-1.29, 36.82) };
+    assert!(backwards.validated_time(now).is_err());
+}
+
+#[test]
+fn devices_only_serve_their_own_operators_trips() {
+    let easy_coach = fixtures::bus();
+    let modern_coast = Bus { bus_number: "Modern Coast - KDB 220C".to_string(), ..fixtures::bus() };
+    let device = |operator: Option<&str>| SignedDevice { device_id: "65f1c0ffee0000000000d001".to_string(), operator: operator.map(str::to_string) };
+
+    assert!(device(Some("easy coach")).serves(&easy_coach));
+    assert!(!device(Some("Easy Coach")).serves(&modern_coast));
+    // Platform handsets are shared across the fleet
+    assert!(device(None).serves(&modern_coast));
+}
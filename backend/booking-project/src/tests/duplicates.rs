@@ -0,0 +1,64 @@
+use std::collections::{BTreeSet, HashMap};
+
+use mongodb::bson::oid::ObjectId;
+
+use crate::models::duplicates::{cluster_duplicates, normalize_name, DuplicateLink, MergeAccountsRequest};
+use crate::models::permissions::{admin_permission, PERMISSIONS_MANAGE, SUPPORT_MANAGE};
+
+fn link(user_ids: &[ObjectId], reason: &str) -> DuplicateLink {
+    DuplicateLink { user_ids: user_ids.iter().copied().collect(), reason: reason.to_string() }
+}
+
+#[test]
+fn names_match_regardless_of_case_and_spacing() {
+    assert_eq!(normalize_name("  Achieng   Odhiambo "), "achieng odhiambo");
+    assert_eq!(normalize_name("ACHIENG Odhiambo"), normalize_name("achieng odhiambo"));
+}
+
+#[test]
+fn a_shared_payment_number_needs_a_shared_name() {
+    let (a, b, c) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+    let names = HashMap::from([
+        (a, "Achieng Odhiambo".to_string()),
+        (b, "achieng  odhiambo".to_string()),
+        (c, "Baraka Odhiambo".to_string()),
+    ]);
+    let users: BTreeSet<ObjectId> = [a, b, c].into_iter().collect();
+    let links = DuplicateLink::by_name(&users, &names, "Same name and M-Pesa number 254712345678");
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].user_ids, [a, b].into_iter().collect());
+
+    // A family sharing a number, with nobody sharing a name, isn't flagged
+    let family: BTreeSet<ObjectId> = [a, c].into_iter().collect();
+    assert!(DuplicateLink::by_name(&family, &names, "Same name and M-Pesa number 254712345678").is_empty());
+}
+
+#[test]
+fn links_sharing_an_account_form_one_group() {
+    let ids: Vec<ObjectId> = (0..5).map(|_| ObjectId::new()).collect();
+    let groups = cluster_duplicates(vec![
+        link(&[ids[0], ids[1]], "Same phone 254712345678"),
+        link(&[ids[3], ids[4]], "Same phone 254700000001"),
+        link(&[ids[1], ids[2]], "Same name and saved card VISA ending 4242"),
+        link(&[ids[0], ids[2]], "Same phone 254712345678"),
+        link(&[ids[4]], "Same phone 254700000002"),
+    ]);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].0, ids[..3]);
+    assert_eq!(groups[0].1, ["Same name and saved card VISA ending 4242", "Same phone 254712345678"]);
+    assert_eq!(groups[1].0, ids[3..]);
+}
+
+#[test]
+fn accounts_cannot_be_merged_into_themselves() {
+    let id = ObjectId::new().to_hex();
+    let req = MergeAccountsRequest { survivor_id: id.clone(), duplicate_id: id, reason: None };
+    assert!(req.validate().is_err());
+}
+
+#[test]
+fn support_staff_handle_duplicate_accounts() {
+    assert_eq!(admin_permission("GET", "/users/duplicates"), SUPPORT_MANAGE);
+    assert_eq!(admin_permission("POST", "/users/merge"), SUPPORT_MANAGE);
+    assert_eq!(admin_permission("PUT", "/users/6761d0a1c2b3a4f5e6d7c8b9/role"), PERMISSIONS_MANAGE);
+}
@@ -0,0 +1,91 @@
+use chrono::{FixedOffset, TimeZone};
+
+use crate::models::email_template::{placeholders, EmailTemplate, BOOKING_EMAIL_FIELDS};
+use crate::models::receipt::BookingReceipt;
+use crate::notifications::smtp::{auth_plain, encode_header, format_message};
+use crate::notifications::valid_address;
+
+fn receipt(language: &str) -> BookingReceipt {
+    BookingReceipt {
+        reference: "65F1C0FFEE".to_string(),
+        passenger: "Wanjiru Kamau".to_string(),
+        seat_number: "12".to_string(),
+        bus_number: "Modern Coast - KBX 123A".to_string(),
+        bus_type: "Executive".to_string(),
+        from: "Nairobi".to_string(),
+        to: "Mombasa".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        departure: "08:00".to_string(),
+        fare: 1500.0,
+        status: "Confirmed".to_string(),
+        booked_on: "2026-12-01".to_string(),
+        language: language.to_string(),
+    }
+}
+
+fn template(subject: &str, body: &str) -> EmailTemplate {
+    EmailTemplate { subject: subject.to_string(), body: body.to_string() }
+}
+
+#[test]
+fn built_in_confirmation_email_carries_the_ticket() {
+    let (subject, body) = receipt("en").confirmation_email(None);
+    assert_eq!(subject, "Your ticket: Nairobi to Mombasa on 2026-12-20");
+    for detail in ["65F1C0FFEE", "Seat: 12", "Modern Coast - KBX 123A (Executive)", "Departure: 08:00", "KES 1500.00"] {
+        assert!(body.contains(detail), "missing {}", detail);
+    }
+    assert!(!body.contains('{'));
+
+    let (subject, body) = receipt("sw").confirmation_email(None);
+    assert_eq!(subject, "Tiketi yako: Nairobi hadi Mombasa tarehe 2026-12-20");
+    assert!(body.starts_with("Habari Wanjiru Kamau"));
+}
+
+#[test]
+fn operators_can_word_the_email_themselves() {
+    let custom = template("Safari njema, {passenger}!", "Ref {reference}: seat {seat} on {bus}, {status}.");
+    assert!(custom.validate(&BOOKING_EMAIL_FIELDS).is_ok());
+    let (subject, body) = receipt("en").confirmation_email(Some(&custom));
+    assert_eq!(subject, "Safari njema, Wanjiru Kamau!");
+    assert_eq!(body, "Ref 65F1C0FFEE: seat 12 on Modern Coast - KBX 123A, Confirmed.");
+}
+
+#[test]
+fn templates_are_checked_when_saved() {
+    assert_eq!(placeholders("{a} and {b} but not {c"), ["a", "b"]);
+    assert!(template("Ticket {referense}", "Hi").validate(&BOOKING_EMAIL_FIELDS).is_err());
+    assert!(template("Ticket", "Seat {seat_no}").validate(&BOOKING_EMAIL_FIELDS).is_err());
+    assert!(template("Ticket\r\nBcc: x@example.com", "Hi").validate(&BOOKING_EMAIL_FIELDS).is_err());
+    assert!(template(" ", "Hi").validate(&BOOKING_EMAIL_FIELDS).is_err());
+}
+
+#[test]
+fn addresses_that_could_inject_headers_are_refused() {
+    assert!(valid_address("wanjiru@example.co.ke"));
+    assert!(!valid_address("wanjiru@localhost"));
+    assert!(!valid_address("wanjiru@example.com>\r\nRCPT TO:<x@example.com"));
+    assert!(!valid_address("a@example.com, b@example.com"));
+    assert!(!valid_address("no-at-sign.example.com"));
+}
+
+#[test]
+fn smtp_messages_are_dot_stuffed_with_crlf_line_endings() {
+    let date = FixedOffset::east_opt(3 * 3600).unwrap().with_ymd_and_hms(2026, 12, 1, 9, 30, 0).unwrap();
+    let message = format_message("Bus Book <tickets@busbook.co.ke>", "wanjiru@example.co.ke", "Your ticket", "Hello\n.hidden\nBye", date, "1@busbook.co.ke");
+    assert!(message.starts_with("From: Bus Book <tickets@busbook.co.ke>\r\nTo: <wanjiru@example.co.ke>\r\nSubject: Your ticket\r\n"));
+    assert!(message.contains("Date: Tue, 1 Dec 2026 09:30:00 +0300\r\n"));
+    assert!(message.ends_with("\r\n\r\nHello\r\n..hidden\r\nBye\r\n.\r\n"));
+    assert!(!message.replace("\r\n", "").contains('\n'));
+}
+
+#[test]
+fn non_ascii_text_is_encoded_for_any_server() {
+    assert_eq!(encode_header("Tiketi yako"), "Tiketi yako");
+    assert_eq!(encode_header("Line\r\nBcc: x"), "Line  Bcc: x");
+    assert_eq!(encode_header("Karibu ☺"), "=?UTF-8?B?S2FyaWJ1IOKYug==?=");
+
+    let date = FixedOffset::east_opt(3 * 3600).unwrap().with_ymd_and_hms(2026, 12, 1, 9, 30, 0).unwrap();
+    let message = format_message("tickets@busbook.co.ke", "a@example.com", "Hi", "Karibu ☺", date, "1@busbook.co.ke");
+    assert!(message.contains("Content-Transfer-Encoding: base64\r\n\r\nS2FyaWJ1IOKYug==\r\n.\r\n"));
+    assert_eq!(auth_plain("user", "pass"), "AHVzZXIAcGFzcw==");
+}
@@ -0,0 +1,27 @@
+use mongodb::bson::DateTime;
+
+use crate::db::email_change::{sign_change_link, verify_change_link, CONFIRM_SCOPE, UNDO_SCOPE};
+use crate::error::AppError;
+use crate::models::account::issued_before_revocation;
+
+#[test]
+fn links_only_work_for_the_step_they_were_sent_for() {
+    let token = sign_change_link("change-1", CONFIRM_SCOPE, chrono::Duration::hours(24)).unwrap();
+    assert_eq!(verify_change_link(&token, CONFIRM_SCOPE).unwrap(), "change-1");
+    assert!(verify_change_link(&token, UNDO_SCOPE).is_err());
+}
+
+#[test]
+fn expired_links_are_unauthorized() {
+    let token = sign_change_link("change-1", UNDO_SCOPE, chrono::Duration::hours(-2)).unwrap();
+    assert!(matches!(verify_change_link(&token, UNDO_SCOPE), Err(AppError::Unauthorized(_))));
+}
+
+#[test]
+fn revocation_is_compared_in_whole_seconds() {
+    let revoked = DateTime::from_millis(1_700_000_000_750);
+    // A login in the same second as the revocation may have come straight after it
+    assert!(!issued_before_revocation(1_700_000_000, revoked));
+    assert!(!issued_before_revocation(1_700_000_001, revoked));
+    assert!(issued_before_revocation(1_699_999_999, revoked));
+}
@@ -0,0 +1,71 @@
+use crate::events::nats::{publish_frame, NatsClient};
+use crate::events::stream::{topic, StreamRecord};
+use crate::events::{BookingSnapshot, DomainEvent, EventEnvelope, TripSnapshot};
+
+fn paid() -> DomainEvent {
+    DomainEvent::BookingPaid {
+        booking: BookingSnapshot {
+            booking_id: "6761d0a1c2b3a4f5e6d7c8b9".to_string(),
+            user_id: "6761d0a1c2b3a4f5e6d7c8ba".to_string(),
+            bus_id: "6761d0a1c2b3a4f5e6d7c8bb".to_string(),
+            travel_date: "2026-12-20".to_string(),
+            seat_number: "12".to_string(),
+            status: "Confirmed".to_string(),
+        },
+        actor: "system:webhook:mpesa_stk".to_string(),
+        note: None,
+    }
+}
+
+#[test]
+fn records_name_their_type_and_schema_version() {
+    let envelope = EventEnvelope::new(paid());
+    let record = StreamRecord::new(&envelope, "staging");
+    assert_eq!(record.kind, "payment.confirmed");
+    assert_eq!(record.schema_version, 1);
+    assert_eq!(record.id, envelope.id.to_hex());
+    assert_eq!(record.source, "bus-book/staging");
+    assert_eq!(record.subject, "booking:6761d0a1c2b3a4f5e6d7c8b9");
+    assert_eq!(record.data["booking"]["seat_number"], "12");
+    // The type is the record's, not repeated in the data
+    assert!(record.data.get("type").is_none());
+
+    let body = serde_json::to_string(&record).unwrap();
+    assert_eq!(serde_json::from_str::<StreamRecord>(&body).unwrap(), record);
+}
+
+#[test]
+fn topics_carry_the_environment_prefix_and_version() {
+    assert_eq!(topic("busbook", &paid()), "busbook.payment.confirmed.v1");
+    let departed = DomainEvent::TripDeparted {
+        trip: TripSnapshot {
+            bus_id: "6761d0a1c2b3a4f5e6d7c8bb".to_string(),
+            travel_date: "2026-12-20".to_string(),
+            departed_at: "2026-12-20T05:42:00Z".to_string(),
+            delay_minutes: 12,
+        },
+    };
+    assert_eq!(topic("staging.busbook.", &departed), "staging.busbook.trip.departed.v1");
+    assert_eq!(departed.subject(), "trip:6761d0a1c2b3a4f5e6d7c8bb:2026-12-20");
+}
+
+#[test]
+fn nats_messages_are_framed_with_a_dedup_id() {
+    let frame = publish_frame("busbook.booking.created.v1", "abc123", "{\"a\":1}").unwrap();
+    let headers = "NATS/1.0\r\nNats-Msg-Id: abc123\r\n\r\n";
+    assert_eq!(
+        frame,
+        format!("HPUB busbook.booking.created.v1 {} {}\r\n{}{{\"a\":1}}\r\n", headers.len(), headers.len() + 7, headers)
+    );
+    assert!(publish_frame("busbook booking", "abc123", "{}").is_err());
+    assert!(publish_frame("", "abc123", "{}").is_err());
+}
+
+#[test]
+fn nats_urls_need_a_known_scheme_and_port() {
+    assert!(NatsClient::new("nats://nats.internal:4222", None).is_ok());
+    assert!(NatsClient::new("tls://nats.internal", Some("s3cret".to_string())).is_ok());
+    assert!(NatsClient::new("nats.internal", None).is_ok());
+    assert!(NatsClient::new("http://nats.internal", None).is_err());
+    assert!(NatsClient::new("nats://nats.internal:port", None).is_err());
+}
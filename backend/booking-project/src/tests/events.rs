@@ -0,0 +1,58 @@
+use mongodb::bson::{self, doc, oid::ObjectId};
+use serde_json::json;
+
+use crate::events::{BookingSnapshot, DomainEvent, EventBus, EventEnvelope};
+
+fn snapshot() -> BookingSnapshot {
+    BookingSnapshot {
+        booking_id: "6761d0a1c2b3a4f5e6d7c8b9".to_string(),
+        user_id: "6761d0a1c2b3a4f5e6d7c8ba".to_string(),
+        bus_id: "6761d0a1c2b3a4f5e6d7c8bb".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        seat_number: "12".to_string(),
+        status: "Confirmed".to_string(),
+    }
+}
+
+#[test]
+fn events_are_tagged_with_their_type() {
+    let event = DomainEvent::BookingCancelled { booking: snapshot().with_status("Cancelled"), actor: "user:6761d0a1c2b3a4f5e6d7c8ba".to_string() };
+    assert_eq!(event.name(), "booking_cancelled");
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(value["type"], "booking_cancelled");
+    assert_eq!(value["booking"]["status"], "Cancelled");
+    assert_eq!(value["actor"], "user:6761d0a1c2b3a4f5e6d7c8ba");
+
+    let paid = DomainEvent::BookingPaid { booking: snapshot(), actor: "system:webhook:mpesa_stk".to_string(), note: None };
+    assert_eq!(serde_json::to_value(&paid).unwrap()["type"], json!(paid.name()));
+}
+
+#[test]
+fn stored_events_read_back_unchanged() {
+    let envelope = EventEnvelope::new(DomainEvent::BookingCreated { booking: snapshot() });
+    let mut document = bson::to_document(&envelope).unwrap();
+    assert_eq!(document.get_str("type").unwrap(), "booking_created");
+    assert!(document.get_object_id("_id").is_ok());
+
+    // Relay bookkeeping stored alongside doesn't get in the way
+    document.insert("status", "pending");
+    let read: EventEnvelope = bson::from_document(document).unwrap();
+    assert_eq!(read, envelope);
+
+    let unknown = doc! { "_id": ObjectId::new(), "occurred_at": bson::DateTime::now(), "type": "booking_teleported" };
+    assert!(bson::from_document::<EventEnvelope>(unknown).is_err());
+}
+
+#[test]
+fn every_subscriber_gets_each_event() {
+    let bus = EventBus::new(8, false);
+    assert_eq!(bus.dispatch(EventEnvelope::new(DomainEvent::BookingCreated { booking: snapshot() })), 0);
+
+    let mut first = bus.subscribe();
+    let mut second = bus.subscribe();
+    let envelope = EventEnvelope::new(DomainEvent::BookingCreated { booking: snapshot() });
+    assert_eq!(bus.dispatch(envelope.clone()), 2);
+    assert_eq!(first.try_recv().unwrap(), envelope);
+    assert_eq!(second.try_recv().unwrap().id, envelope.id);
+    assert!(first.try_recv().is_err());
+}
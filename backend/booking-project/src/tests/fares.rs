@@ -0,0 +1,61 @@
+use crate::models::bus::Bus;
+use crate::tests::fixtures::{self, route};
+use crate::models::fare::{quote_fare, FareTableRequest, SegmentFare};
+
+fn bus() -> Bus {
+    Bus { route: route("Nairobi", "Eldoret", "08:00 AM", "02:30 PM", 1500.0), ..fixtures::bus() }
+}
+
+fn fare(from: &str, to: &str, price: f64) -> SegmentFare {
+    SegmentFare { from: from.to_string(), to: to.to_string(), price }
+}
+
+fn request(fares: Vec<SegmentFare>) -> FareTableRequest {
+    FareTableRequest {
+        stops: vec!["Nairobi".to_string(), "Naivasha".to_string(), "Nakuru".to_string(), "Eldoret".to_string()],
+        fares,
+    }
+}
+
+#[test]
+fn fare_tables_follow_the_route() {
+    let bus = bus();
+    assert!(request(vec![fare("Nairobi", "Nakuru", 800.0), fare("nakuru", "eldoret", 700.0)]).validate(&bus).is_ok());
+
+    let mut reversed = request(Vec::new());
+    reversed.stops.reverse();
+    assert!(reversed.validate(&bus).is_err());
+    let mut repeated = request(Vec::new());
+    repeated.stops.insert(2, "naivasha".to_string());
+    assert!(repeated.validate(&bus).is_err());
+
+    assert!(request(vec![fare("Nakuru", "Nairobi", 800.0)]).validate(&bus).is_err());
+    assert!(request(vec![fare("Nairobi", "Kisumu", 800.0)]).validate(&bus).is_err());
+    assert!(request(vec![fare("Nairobi", "Eldoret", 1400.0)]).validate(&bus).is_err());
+    assert!(request(vec![fare("Nairobi", "Nakuru", 0.0)]).validate(&bus).is_err());
+    assert!(request(vec![fare("Nairobi", "Nakuru", 800.0), fare("NAIROBI", "Nakuru", 750.0)]).validate(&bus).is_err());
+}
+
+#[test]
+fn segments_are_quoted_from_the_fare_table() {
+    let bus = bus();
+    let table = request(vec![fare("Nairobi", "Nakuru", 800.0), fare("nakuru", "eldoret", 700.0)]).into_table(bus.id.unwrap());
+
+    let quote = quote_fare(&bus, Some(&table), Some("nairobi "), Some("NAKURU")).unwrap();
+    assert_eq!((quote.from.as_str(), quote.to.as_str(), quote.price, quote.segment), ("Nairobi", "Nakuru", 800.0, true));
+    assert_eq!(quote_fare(&bus, Some(&table), Some("Nakuru"), Some("Eldoret")).unwrap().price, 700.0);
+
+    // Stops on the route without a fare between them, or travelled backwards, can't be sold
+    assert!(quote_fare(&bus, Some(&table), Some("Naivasha"), Some("Nakuru")).is_err());
+    assert!(quote_fare(&bus, Some(&table), Some("Nakuru"), Some("Nairobi")).is_err());
+    assert!(quote_fare(&bus, Some(&table), Some("Nairobi"), Some("Kisumu")).is_err());
+}
+
+#[test]
+fn whole_route_quotes_use_the_bus_price() {
+    let bus = bus();
+    let quote = quote_fare(&bus, None, None, None).unwrap();
+    assert_eq!((quote.price, quote.segment), (1500.0, false));
+    assert_eq!(quote_fare(&bus, None, Some("nairobi"), Some("Eldoret")).unwrap().price, 1500.0);
+    assert!(quote_fare(&bus, None, Some("Nairobi"), Some("Nakuru")).is_err());
+}
@@ -0,0 +1,52 @@
+use crate::models::bus::Bus;
+use crate::tests::fixtures::{self, route};
+use crate::models::feed::{render_routes_xml, route_slug, summarize_routes, RouteFeedResponse};
+
+fn bus(bus_number: &str, from: &str, to: &str, departure: &str, arrival: &str, price: f64) -> Bus {
+    Bus {
+        id: None,
+        bus_number: bus_number.to_string(),
+        total_seats: 40,
+        route: route(from, to, departure, arrival, price),
+        ..fixtures::bus()
+    }
+}
+
+#[test]
+fn slugs_are_url_safe() {
+    assert_eq!(route_slug("Nairobi", "Kisumu"), "nairobi-to-kisumu");
+    assert_eq!(route_slug(" Nairobi CBD ", "Mombasa (Likoni)"), "nairobi-cbd-to-mombasa-likoni");
+}
+
+#[test]
+fn buses_on_the_same_route_roll_up_into_one_summary() {
+    let routes = summarize_routes(&[
+        bus("Easy Coach - KDA 123A", "Nairobi", "Kisumu", "08:00 PM", "04:30 AM", 1500.0),
+        bus("Guardian - KCB 456B", "Nairobi", "Kisumu", "07:30 AM", "02:00 PM", 1200.0),
+        bus("Easy Coach - KDC 789C", "Nairobi", "Kisumu", "07:30 AM", "01:00 PM", 1400.0),
+        bus("Modern Coast - KBZ 001D", "Nairobi", "Mombasa", "09:00 AM", "05:00 PM", 1600.0),
+    ]);
+
+    assert_eq!(routes.len(), 2);
+    let kisumu = &routes[0];
+    assert_eq!(kisumu.slug, "nairobi-to-kisumu");
+    assert_eq!(kisumu.operators, vec!["Easy Coach", "Guardian"]);
+    assert_eq!(kisumu.departures_per_day, 3);
+    assert_eq!(kisumu.departure_times, vec!["07:30 AM", "08:00 PM"]);
+    assert_eq!(kisumu.shortest_trip_minutes, Some(330));
+    assert_eq!(kisumu.starting_price, 1200.0);
+    assert_eq!(routes[1].slug, "nairobi-to-mombasa");
+}
+
+#[test]
+fn xml_feed_escapes_names() {
+    let feed = RouteFeedResponse {
+        generated_at: "2026-10-16T00:00:00Z".to_string(),
+        routes: summarize_routes(&[bus("A&B <Express> - KDA 1", "Nairobi", "Kisumu", "08:00 AM", "02:00 PM", 1200.0)]),
+    };
+    let xml = render_routes_xml(&feed);
+    assert!(xml.contains("<route slug=\"nairobi-to-kisumu\">"));
+    assert!(xml.contains("<operator>A&amp;B &lt;Express&gt;</operator>"));
+    assert!(xml.contains("<starting_price currency=\"KES\">1200.00</starting_price>"));
+    assert!(xml.contains("<shortest_trip_minutes>360</shortest_trip_minutes>"));
+}
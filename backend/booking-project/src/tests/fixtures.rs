@@ -0,0 +1,28 @@
+// Shared test data. Tests start from these and override only the fields they are about, so a new
+// field on a model is added here once.
+use mongodb::bson::oid::ObjectId;
+
+use crate::models::bus::{Bus, Route};
+
+/// A standard 44-seater on the Nairobi–Kisumu day run.
+pub fn bus() -> Bus {
+    Bus {
+        id: Some(ObjectId::new()),
+        bus_number: "Easy Coach - KCH 123A".to_string(),
+        bus_type: "Standard".to_string(),
+        total_seats: 44,
+        policy_id: None,
+        layout: None,
+        route: route("Nairobi", "Kisumu", "08:15 AM", "04:30 PM", 1450.0),
+    }
+}
+
+pub fn route(from: &str, to: &str, departure: &str, arrival: &str, price: f64) -> Route {
+    Route {
+        from: from.to_string(),
+        to: to.to_string(),
+        departure_time: departure.to_string(),
+        arrival_time: arrival.to_string(),
+        price,
+    }
+}
@@ -0,0 +1,27 @@
+use mongodb::bson::doc;
+
+use crate::db::fraud::blocklist_candidates;
+use crate::models::fraud::normalize_blocklist_value;
+
+#[test]
+fn phone_numbers_match_whatever_prefix_they_arrive_with() {
+    for number in ["0712 345 678", "+254712345678", "254712345678", " 0712-345-678 "] {
+        assert_eq!(normalize_blocklist_value("phone", number), "254712345678");
+        assert_eq!(normalize_blocklist_value("mpesa", number), "254712345678");
+    }
+    // Not a local mobile number, so left as dialled
+    assert_eq!(normalize_blocklist_value("phone", "020 123 456"), "020123456");
+}
+
+#[test]
+fn emails_match_regardless_of_case_but_devices_exactly() {
+    assert_eq!(normalize_blocklist_value("email", "  Fraudster@Example.COM "), "fraudster@example.com");
+    assert_eq!(normalize_blocklist_value("device", " AbC123 "), "AbC123");
+}
+
+#[test]
+fn blank_subjects_are_not_looked_up() {
+    let candidates = blocklist_candidates(&[("email", "Fraudster@Example.com"), ("device", "  "), ("phone", "n/a")]);
+    assert_eq!(candidates, vec![doc! { "kind": "email", "value": "fraudster@example.com" }]);
+    assert!(blocklist_candidates(&[]).is_empty());
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveTime};
+use mongodb::bson;
+
+use crate::config::GtfsConfig;
+use crate::models::bus::Bus;
+use crate::tests::fixtures::{self, route};
+use crate::models::gtfs::{build_gtfs, gtfs_time, GtfsStop};
+use crate::models::gtfs_import::{parse_csv, plan_gtfs_import, schedule_time, GtfsImportQuery};
+use crate::models::zip::{crc32, read_zip, write_zip};
+
+fn bus(bus_number: &str, from: &str, to: &str, departure: &str, arrival: &str) -> Bus {
+    Bus {
+        bus_number: bus_number.to_string(),
+        total_seats: 40,
+        route: route(from, to, departure, arrival, 1500.0),
+        ..fixtures::bus()
+    }
+}
+
+fn stop(id: &str, name: &str, lat: f64, lon: f64) -> (String, GtfsStop) {
+    (id.to_string(), GtfsStop { id: id.to_string(), name: name.to_string(), lat, lon, updated_at: bson::DateTime::now() })
+}
+
+fn config() -> GtfsConfig {
+    GtfsConfig {
+        publisher_name: "Bus Book".to_string(),
+        publisher_url: "https://busbook.example".to_string(),
+        service_days: 30,
+        refresh_secs: 86400,
+        max_upload_bytes: 1024 * 1024,
+    }
+}
+
+#[test]
+fn overnight_arrivals_run_past_midnight() {
+    let time = |t| NaiveTime::parse_from_str(t, "%H:%M").unwrap();
+    assert_eq!(gtfs_time(time("20:00"), false), "20:00:00");
+    assert_eq!(gtfs_time(time("04:30"), true), "28:30:00");
+}
+
+#[test]
+fn fleet_exports_as_gtfs_tables() {
+    let overnight = bus("Easy Coach - KDA 123A", "Nairobi", "Kisumu", "08:00 PM", "04:30 AM");
+    let daytime = bus("Easy Coach - KDC 789C", "Nairobi", "Kisumu", "07:30 AM", "01:00 PM");
+    let unmapped = bus("Modern Coast - KBZ 001D", "Nairobi", "Malindi", "09:00 AM", "07:00 PM");
+    let stops: HashMap<_, _> = [stop("nairobi", "Nairobi", -1.286389, 36.817223), stop("kisumu", "Kisumu", -0.091702, 34.767956)].into();
+    let start = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+    let cancellations = vec![
+        (overnight.id.unwrap(), "2026-03-05".to_string()),
+        (overnight.id.unwrap(), "2026-05-01".to_string()), // beyond the calendar
+        (unmapped.id.unwrap(), "2026-03-05".to_string()),
+    ];
+
+    let export = build_gtfs(&[overnight.clone(), daytime, unmapped], &stops, &cancellations, start, &config());
+    let file = |name: &str| export.files.iter().find(|(n, _)| *n == name).map(|(_, body)| body.clone()).unwrap();
+
+    assert_eq!(export.trips, 2);
+    assert_eq!(export.missing_stops, vec!["malindi"]);
+    assert_eq!(
+        file("agency.txt"),
+        "agency_id,agency_name,agency_url,agency_timezone,agency_lang\neasy-coach,Easy Coach,https://busbook.example,Africa/Nairobi,en\n"
+    );
+    assert_eq!(file("routes.txt").lines().count(), 2);
+    assert!(file("routes.txt").contains("easy-coach:nairobi-to-kisumu,easy-coach,,Nairobi - Kisumu,3"));
+    assert!(file("stops.txt").contains("kisumu,Kisumu,-0.091702,34.767956"));
+
+    let trip = overnight.id.unwrap().to_hex();
+    assert!(file("stop_times.txt").contains(&format!("{},20:00:00,20:00:00,nairobi,1", trip)));
+    assert!(file("stop_times.txt").contains(&format!("{},28:30:00,28:30:00,kisumu,2", trip)));
+    assert!(file("calendar.txt").contains(&format!("{},1,1,1,1,1,1,1,20260301,20260330", trip)));
+    assert_eq!(file("calendar_dates.txt"), format!("service_id,date,exception_type\n{},20260305,2\n", trip));
+}
+
+#[test]
+fn zip_entries_are_stored_with_their_checksums() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    let modified = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let zip = write_zip(&[("agency.txt", b"agency_id\n"), ("stops.txt", b"")], modified);
+    assert_eq!(&zip[..4], b"PK\x03\x04");
+    assert_eq!(&zip[30..40], b"agency.txt");
+    assert_eq!(&zip[40..50], b"agency_id\n");
+
+    // End of central directory: two entries, no comment
+    let end = &zip[zip.len() - 22..];
+    assert_eq!(&end[..4], b"PK\x05\x06");
+    assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+    let central_at = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+    assert_eq!(&zip[central_at..central_at + 4], b"PK\x01\x02");
+}
+
+#[test]
+fn archives_read_back_including_deflated_entries() {
+    use std::io::Write;
+
+    let modified = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let stored = write_zip(&[("feed/agency.txt", b"agency_name\nEasy Coach\n")], modified);
+    assert_eq!(read_zip(&stored).unwrap(), vec![("feed/agency.txt".to_string(), b"agency_name\nEasy Coach\n".to_vec())]);
+
+    // Rewrite the entry as deflated, the way most zip tools produce feeds
+    let contents = b"stop_id,stop_name\nnbo,Nairobi\n".repeat(50);
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&contents).unwrap();
+    let deflated = encoder.finish().unwrap();
+    let mut zip = write_zip(&[("stops.txt", &deflated)], modified);
+    zip[8] = 8; // local header method
+    zip[18..22].copy_from_slice(&(deflated.len() as u32).to_le_bytes());
+    zip[22..26].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+    zip[14..18].copy_from_slice(&crc32(&contents).to_le_bytes());
+    let central = 30 + "stops.txt".len() + deflated.len();
+    zip[central + 10] = 8;
+    zip[central + 16..central + 20].copy_from_slice(&crc32(&contents).to_le_bytes());
+    zip[central + 24..central + 28].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+    assert_eq!(read_zip(&zip).unwrap()[0].1, contents);
+
+    zip[central + 16] ^= 0xFF;
+    assert!(read_zip(&zip).is_err());
+    assert!(read_zip(b"not a zip").is_err());
+}
+
+#[test]
+fn csv_handles_quotes_bom_and_crlf() {
+    let rows = parse_csv("\u{feff}stop_id,stop_name\r\n1,\"Mombasa, Likoni \"\"Ferry\"\"\"\r\n\r\n2,Kisumu");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["stop_name"], "Mombasa, Likoni \"Ferry\"");
+    assert_eq!(rows[1]["stop_id"], "2");
+    assert_eq!(schedule_time("25:10:00").as_deref(), Some("01:10 AM"));
+    assert_eq!(schedule_time("07:05:00").as_deref(), Some("07:05 AM"));
+    assert_eq!(schedule_time("noon"), None);
+}
+
+fn feed(extra: &[(&str, &str)]) -> HashMap<String, String> {
+    let mut files: HashMap<String, String> = [
+        ("agency.txt", "agency_name,agency_url,agency_timezone\nGuardian,https://guardian.example,Africa/Nairobi\n"),
+        ("stops.txt", "stop_id,stop_name,stop_lat,stop_lon\nnbo,Nairobi,-1.2864,36.8172\nnak,Nakuru,-0.3031,36.0800\nksm,Kisumu,-0.0917,34.7680\n"),
+        ("routes.txt", "route_id,route_short_name,route_long_name,route_type\nr1,,Nairobi - Kisumu,3\nr2,,Nairobi - Nakuru,3\n"),
+        ("trips.txt", "route_id,service_id,trip_id,trip_short_name\nr1,daily,t1,KCB 456B\nr2,daily,t2,\nr1,daily,t3,\n"),
+        ("stop_times.txt", "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+            t1,22:00:00,22:00:00,nbo,1\nt1,01:30:00,01:30:00,nak,2\nt1,28:15:00,28:15:00,ksm,3\n\
+            t2,08:00:00,08:00:00,nbo,1\nt2,10:30:00,10:30:00,nak,2\n\
+            t3,09:00:00,09:00:00,nbo,1\n"),
+        ("fare_attributes.txt", "fare_id,price,currency_type\nf1,1800,KES\n"),
+        ("fare_rules.txt", "fare_id,route_id\nf1,r1\n"),
+    ].into_iter().map(|(n, c)| (n.to_string(), c.to_string())).collect();
+    files.extend(extra.iter().map(|(n, c)| (n.to_string(), c.to_string())));
+    files
+}
+
+#[test]
+fn gtfs_trips_become_daily_schedules_with_a_report() {
+    let query = GtfsImportQuery { dry_run: true, price: None, seats: Some(49), bus_type: None };
+    let plan = plan_gtfs_import(&feed(&[]), &query).unwrap();
+
+    assert_eq!(plan.report.agencies, vec!["Guardian"]);
+    assert_eq!(plan.buses.len(), 1);
+    let bus = &plan.buses[0];
+    assert_eq!(bus.bus_number, "Guardian - KCB 456B");
+    assert_eq!((bus.route.from.as_str(), bus.route.to.as_str()), ("Nairobi", "Kisumu"));
+    assert_eq!((bus.route.departure_time.as_str(), bus.route.arrival_time.as_str()), ("10:00 PM", "04:15 AM"));
+    assert_eq!((bus.route.price, bus.total_seats), (1800.0, 49));
+    assert_eq!(plan.stops.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["kisumu", "nairobi"]);
+
+    let skipped: Vec<&str> = plan.report.skipped.iter().map(|i| i.message.as_str()).collect();
+    assert_eq!(skipped, vec!["No fare for route r2; pass a price to use for it", "Trip t3 serves a single stop"]);
+    assert_eq!(plan.report.warnings.len(), 1);
+
+    // A fallback price covers routes the feed has no fare for
+    let query = GtfsImportQuery { price: Some(700.0), ..query };
+    let plan = plan_gtfs_import(&feed(&[("calendar.txt", "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\ndaily,1,1,1,1,1,0,0,20260101,20261231\n")]), &query).unwrap();
+    assert_eq!(plan.buses.len(), 2);
+    assert_eq!(plan.buses[1].bus_number, "Guardian - t2");
+    assert_eq!(plan.buses[1].route.price, 700.0);
+    assert!(plan.report.warnings.iter().any(|w| w.file == "calendar.txt"));
+
+    let mut incomplete = feed(&[]);
+    incomplete.remove("stop_times.txt");
+    assert_eq!(plan_gtfs_import(&incomplete, &query).err().as_deref(), Some("Not a GTFS feed, missing: stop_times.txt"));
+}
@@ -0,0 +1,103 @@
+use actix_web::test::TestRequest;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::handlers::holds::hold_owner;
+use crate::models::hold::{ConfirmHoldsRequest, CreateHoldRequest, HoldOwner, SeatHold};
+use crate::tokens::TokenService;
+
+fn hold(bus_id: ObjectId, travel_date: &str, seat_number: &str) -> SeatHold {
+    SeatHold {
+        id: Some(ObjectId::new()),
+        bus_id,
+        travel_date: travel_date.to_string(),
+        seat_number: seat_number.to_string(),
+        user_id: Some(ObjectId::new()),
+        anon_id: None,
+        expires_at: DateTime::from_millis(1_765_000_600_000),
+        created_at: DateTime::from_millis(1_765_000_000_000),
+    }
+}
+
+fn confirm(holds: &[&SeatHold]) -> ConfirmHoldsRequest {
+    ConfirmHoldsRequest {
+        hold_ids: holds.iter().map(|h| h.id.unwrap().to_hex()).collect(),
+        passengers: Vec::new(),
+        accepted_terms_version: None,
+        payer_phone: None,
+        pass_id: None,
+    }
+}
+
+#[test]
+fn seat_holds_are_split_per_seat() {
+    let req = CreateHoldRequest {
+        bus_id: ObjectId::new().to_hex(),
+        travel_date: "2026-12-20".to_string(),
+        seat_number: None,
+        seat_numbers: vec!["12".to_string(), "11".to_string()],
+    };
+    assert!(req.validate().is_ok());
+    assert_eq!(req.seats(), ["12", "11"]);
+
+    let single = CreateHoldRequest { seat_number: Some("4".to_string()), seat_numbers: Vec::new(), ..req };
+    assert!(single.validate().is_ok());
+    assert_eq!(single.seats(), ["4"]);
+    let both = CreateHoldRequest { seat_numbers: vec!["5".to_string()], ..single };
+    assert!(both.validate().is_err());
+
+    let duplicated = CreateHoldRequest { seat_number: None, seat_numbers: vec!["12".to_string(), "12".to_string()], ..both };
+    assert!(duplicated.validate().is_err());
+    let empty = CreateHoldRequest { seat_numbers: Vec::new(), ..duplicated };
+    assert!(empty.validate().is_err());
+}
+
+#[test]
+fn confirmed_holds_book_their_seats_in_request_order() {
+    let bus_id = ObjectId::new();
+    let (a, b) = (hold(bus_id, "2026-12-20", "12"), hold(bus_id, "2026-12-20", "11"));
+    let req = confirm(&[&b, &a]).booking_request(&[a.clone(), b.clone()]).unwrap();
+    assert_eq!(req.bus_id, bus_id.to_hex());
+    assert_eq!(req.travel_date, "2026-12-20");
+    assert_eq!(req.seat_numbers, ["11", "12"]);
+}
+
+#[test]
+fn holds_must_be_live_and_on_one_trip() {
+    let bus_id = ObjectId::new();
+    let (a, b) = (hold(bus_id, "2026-12-20", "12"), hold(bus_id, "2026-12-20", "11"));
+    // b ran out, so it isn't among the live holds
+    assert!(confirm(&[&a, &b]).booking_request(std::slice::from_ref(&a)).is_err());
+    assert!(confirm(&[]).booking_request(&[]).is_err());
+
+    let other_day = hold(bus_id, "2026-12-21", "12");
+    assert!(confirm(&[&a, &other_day]).booking_request(&[a.clone(), other_day.clone()]).is_err());
+}
+
+#[test]
+fn visitors_hold_seats_through_their_anonymous_session() {
+    let tokens = TokenService::from_env();
+    let (anon_token, _) = tokens.issue_anonymous("anon-1").unwrap();
+    let req = TestRequest::default().insert_header(("X-Anonymous-Token", anon_token.clone())).to_http_request();
+    assert!(matches!(hold_owner(&req), Some(HoldOwner::Anonymous(id)) if id == "anon-1"));
+
+    // Once signed in, the account owns new holds even if the anonymous token is still sent
+    let user_id = ObjectId::new();
+    let access = tokens.issue_access(&user_id.to_hex(), "customer", None).unwrap();
+    let signed_in = TestRequest::default()
+        .insert_header(("Authorization", format!("Bearer {}", access)))
+        .insert_header(("X-Anonymous-Token", anon_token))
+        .to_http_request();
+    assert!(matches!(hold_owner(&signed_in), Some(HoldOwner::User(id)) if id == user_id));
+
+    assert!(hold_owner(&TestRequest::default().to_http_request()).is_none());
+}
+
+#[test]
+fn only_anonymous_tokens_name_an_anonymous_owner() {
+    let access = TokenService::from_env().issue_access(&ObjectId::new().to_hex(), "customer", None).unwrap();
+    let req = TestRequest::default().insert_header(("X-Anonymous-Token", access)).to_http_request();
+    assert!(hold_owner(&req).is_none());
+
+    let forged = TestRequest::default().insert_header(("X-Anonymous-Token", "a.b.c")).to_http_request();
+    assert!(hold_owner(&forged).is_none());
+}
@@ -0,0 +1,74 @@
+use serde_json::json;
+
+use crate::config::InventoryConfig;
+use crate::models::booking::CreateGroupBookingRequest;
+use crate::models::bus::{HeadcountSummary, SeatAvailabilityResponse, OPEN_SEATING};
+
+fn group(body: serde_json::Value) -> CreateGroupBookingRequest {
+    serde_json::from_value(body).unwrap()
+}
+
+#[test]
+fn bus_types_sold_by_headcount_match_case_insensitively() {
+    let inventory = InventoryConfig { headcount_bus_types: vec!["shuttle".to_string(), "matatu".to_string()] };
+    assert!(inventory.sells_by_headcount("Shuttle"));
+    assert!(inventory.sells_by_headcount(" MATATU "));
+    assert!(!inventory.sells_by_headcount("Standard"));
+}
+
+#[test]
+fn headcount_bookings_take_open_places() {
+    let passenger = json!({ "name": "Wanjiru", "age": "34", "gender": "F" });
+    let req = group(json!({
+        "bus_id": "6761d0a1c2b3a4f5e6d7c8b9",
+        "seat_numbers": [],
+        "travel_date": "2026-12-20",
+        "headcount": 3,
+        "passengers": [passenger, passenger, passenger],
+    }));
+    assert!(req.validate().is_ok());
+    let places = req.open_places();
+    assert_eq!(places.len(), 3);
+    assert!(places.iter().all(|(seat, passenger)| seat == OPEN_SEATING && passenger.is_some()));
+
+    // Seats picked on a trip sold by headcount still count one place each
+    let picked = group(json!({ "bus_id": "6761d0a1c2b3a4f5e6d7c8b9", "seat_numbers": ["4", "5"], "travel_date": "2026-12-20" }));
+    assert_eq!(picked.open_places().len(), 2);
+}
+
+#[test]
+fn headcount_requests_are_validated() {
+    let both = group(json!({ "bus_id": "x", "seat_numbers": ["1"], "travel_date": "2026-12-20", "headcount": 1 }));
+    assert!(both.validate().is_err());
+    let none = group(json!({ "bus_id": "x", "seat_numbers": [], "travel_date": "2026-12-20", "headcount": 0 }));
+    assert!(none.validate().is_err());
+    let too_many = group(json!({ "bus_id": "x", "seat_numbers": [], "travel_date": "2026-12-20", "headcount": 7 }));
+    assert!(too_many.validate().is_err());
+    let passengers = group(json!({
+        "bus_id": "x",
+        "seat_numbers": [],
+        "travel_date": "2026-12-20",
+        "headcount": 2,
+        "passengers": [{ "name": "Otieno", "age": "40", "gender": "M" }],
+    }));
+    assert!(passengers.validate().is_err());
+}
+
+#[test]
+fn headcount_trips_return_a_summary_instead_of_seats() {
+    assert_eq!(HeadcountSummary::new(14, 9).available, 5);
+    // A smaller vehicle swapped in can leave a trip over capacity
+    assert_eq!(HeadcountSummary::new(10, 12).available, 0);
+
+    let response = SeatAvailabilityResponse {
+        travel_date: "2026-12-20".to_string(),
+        seats: Vec::new(),
+        headcount: Some(HeadcountSummary::new(14, 9)),
+        layout: None,
+    };
+    assert_eq!(serde_json::to_value(response).unwrap(), json!({
+        "travel_date": "2026-12-20",
+        "seats": [],
+        "headcount": { "capacity": 14, "booked": 9, "available": 5 },
+    }));
+}
@@ -0,0 +1,81 @@
+use crate::models::ledger::{to_cents, LedgerEntry, LedgerTransaction, PLATFORM_COMMISSION_ACCOUNT};
+
+fn sale() -> LedgerTransaction {
+    LedgerTransaction::new("booking", "65f0c0ffee", vec![
+        LedgerEntry::debit("customer:65f0aa", 1500.0),
+        LedgerEntry::credit("operator:Easy Coach", 1350.0),
+        LedgerEntry::credit(PLATFORM_COMMISSION_ACCOUNT, 150.0),
+    ]).unwrap()
+}
+
+#[test]
+fn transactions_must_balance() {
+    let sale = sale();
+    assert_eq!(sale.entries.len(), 3);
+    assert_eq!(sale.total(), 1500.0);
+
+    let short = LedgerTransaction::new("booking", "r", vec![
+        LedgerEntry::debit("customer:65f0aa", 1500.0),
+        LedgerEntry::credit("operator:Easy Coach", 1350.0),
+    ]);
+    assert!(short.is_err());
+
+    // Float noise below a cent doesn't unbalance anything
+    let noisy = LedgerTransaction::new("booking", "r", vec![
+        LedgerEntry::debit("customer:65f0aa", 0.1 + 0.2),
+        LedgerEntry::credit("operator:Easy Coach", 0.3),
+    ]);
+    assert!(noisy.is_ok());
+}
+
+#[test]
+fn non_positive_amounts_are_rejected() {
+    let negative = LedgerTransaction::new("refund", "r", vec![
+        LedgerEntry::debit("customer:65f0aa", -100.0),
+        LedgerEntry::credit("operator:Easy Coach", -100.0),
+    ]);
+    assert!(negative.is_err());
+
+    // Zero lines are dropped, which leaves nothing to post
+    let empty = LedgerTransaction::new("refund", "r", vec![
+        LedgerEntry::debit("customer:65f0aa", 0.0),
+        LedgerEntry::credit(PLATFORM_COMMISSION_ACCOUNT, 0.0),
+    ]);
+    assert!(empty.is_err());
+
+    let dropped = LedgerTransaction::new("booking", "r", vec![
+        LedgerEntry::debit("customer:65f0aa", 1500.0),
+        LedgerEntry::credit("operator:Easy Coach", 1500.0),
+        LedgerEntry::credit(PLATFORM_COMMISSION_ACCOUNT, 0.0),
+    ]).unwrap();
+    assert_eq!(dropped.entries.len(), 2);
+}
+
+#[test]
+fn entries_name_known_accounts_on_one_side() {
+    let unknown = LedgerTransaction::new("booking", "r", vec![
+        LedgerEntry::debit("wallet:65f0aa", 100.0),
+        LedgerEntry::credit("operator:Easy Coach", 100.0),
+    ]);
+    assert!(unknown.is_err());
+
+    let both = LedgerTransaction::new("booking", "r", vec![
+        LedgerEntry { account: "customer:65f0aa".to_string(), debit: 100.0, credit: 50.0 },
+        LedgerEntry::credit("operator:Easy Coach", 50.0),
+    ]);
+    assert!(both.is_err());
+}
+
+#[test]
+fn fees_are_shared_out_like_the_sale() {
+    let shares = sale().apportion_credits(300.0);
+    let amounts: Vec<(&str, f64)> = shares.iter().map(|e| (e.account.as_str(), e.credit)).collect();
+    assert_eq!(amounts, vec![("operator:Easy Coach", 270.0), (PLATFORM_COMMISSION_ACCOUNT, 30.0)]);
+
+    // Whatever doesn't divide evenly lands on the last account, so the fee still balances
+    let odd = sale().apportion_credits(0.07);
+    assert_eq!(odd.iter().map(|e| to_cents(e.credit)).collect::<Vec<_>>(), vec![6, 1]);
+    let mut entries = vec![LedgerEntry::debit("customer:65f0aa", 0.07)];
+    entries.extend(odd);
+    assert!(LedgerTransaction::new("refund", "r", entries).is_ok());
+}
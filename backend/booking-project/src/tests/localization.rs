@@ -0,0 +1,45 @@
+use crate::models::localization::{document_language, document_text, preferred_language};
+use crate::models::receipt::BookingReceipt;
+
+fn receipt(language: &str) -> BookingReceipt {
+    BookingReceipt {
+        reference: "65F1C0FFEE".to_string(),
+        passenger: "Wanjiru Kamau".to_string(),
+        seat_number: "12".to_string(),
+        bus_number: "Modern Coast - KBX 123A".to_string(),
+        bus_type: "Executive".to_string(),
+        from: "Nairobi".to_string(),
+        to: "Mombasa".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        departure: "08:00".to_string(),
+        fare: 1500.0,
+        status: "Confirmed".to_string(),
+        booked_on: "2026-12-01".to_string(),
+        language: language.to_string(),
+    }
+}
+
+#[test]
+fn user_preference_beats_request_beats_operator_default() {
+    assert_eq!(document_language(Some("sw"), Some("en"), Some("en")), "sw");
+    assert_eq!(document_language(None, Some("sw"), Some("en")), "sw");
+    assert_eq!(document_language(None, None, Some("SW")), "sw");
+    assert_eq!(document_language(Some("fr"), None, None), "en");
+    assert_eq!(preferred_language(Some("fr-FR, sw;q=0.5")), "sw");
+}
+
+#[test]
+fn confirmation_sms_is_worded_in_the_receipt_language() {
+    let sms = receipt("sw").confirmation_sms();
+    assert!(sms.starts_with("Uhifadhi umethibitishwa: Nairobi hadi Mombasa tarehe 2026-12-20 saa 08:00"));
+    assert!(!sms.contains('{'));
+    assert!(receipt("en").confirmation_sms().starts_with("Booking confirmed: Nairobi to Mombasa"));
+    assert!(sms.len() <= 160);
+}
+
+#[test]
+fn unknown_keys_fall_back_to_the_key() {
+    assert_eq!(document_text("sw", "manifest.title"), "ORODHA YA ABIRIA");
+    assert_eq!(document_text("en", "no.such.key"), "no.such.key");
+    assert!(receipt("sw").to_pdf().starts_with(b"%PDF-1.4"));
+}
@@ -0,0 +1,88 @@
+use crate::config::LookupConfig;
+use crate::crypto::constant_time_eq;
+use crate::models::lookup::{
+    email_matches, lookup_reference_key, masked_name, new_booking_reference, normalize_booking_reference, GuestLookupRequest, LookupFailures,
+};
+
+fn config(captcha_secret: Option<&str>) -> LookupConfig {
+    LookupConfig {
+        window_minutes: 15,
+        max_failures_per_ip: 20,
+        max_failures_per_reference: 5,
+        captcha_after: 3,
+        captcha_secret: captcha_secret.map(str::to_string),
+        captcha_verify_url: "http://127.0.0.1:9/siteverify".to_string(),
+        enumeration_references: 10,
+    }
+}
+
+#[test]
+fn references_and_emails_match_however_they_were_typed() {
+    let request: GuestLookupRequest = serde_json::from_value(serde_json::json!({
+        "reference": " 65F1C0FFEE000000000B00C1 ",
+        "email": "Achieng@Example.com ",
+    })).unwrap();
+    assert_eq!(request.reference(), "65f1c0ffee000000000b00c1");
+    assert!(email_matches(&request.email, "achieng@example.com"));
+    assert!(!email_matches("achieng@example.co", "achieng@example.com"));
+    assert!(!email_matches("", "achieng@example.com"));
+
+    assert!(constant_time_eq(b"same", b"same"));
+    assert!(!constant_time_eq(b"same", b"sane"));
+    assert!(!constant_time_eq(b"same", b"same "));
+}
+
+#[test]
+fn misses_on_one_reference_count_together_however_it_was_typed() {
+    for typed in ["BB-7K2F9Q", "bb7k2f9q", " 7K2F9Q", "7k2-f9q", "BB - 7K2 F9Q"] {
+        assert_eq!(lookup_reference_key(typed), "BB-7K2F9Q", "{:?}", typed);
+    }
+    // Anything that isn't a short code is counted as typed, less case and space
+    assert_eq!(lookup_reference_key(" NOT-A-CODE "), "not-a-code");
+}
+
+#[test]
+fn repeated_misses_lead_to_a_captcha_then_a_wait() {
+    let failures = |by_ip, by_reference| LookupFailures { by_ip, by_reference };
+    let with_captcha = config(Some("captcha-secret"));
+
+    assert!(!failures(2, 2).needs_captcha(&with_captcha));
+    assert!(failures(3, 0).needs_captcha(&with_captcha));
+    assert!(failures(0, 3).needs_captcha(&with_captcha));
+    assert!(!failures(3, 3).needs_captcha(&config(None)));
+
+    assert!(!failures(19, 4).throttled(&with_captcha));
+    assert!(failures(20, 0).throttled(&with_captcha));
+    assert!(failures(0, 5).throttled(&config(None)));
+}
+
+#[test]
+fn booking_references_avoid_lookalike_characters() {
+    for _ in 0..200 {
+        let reference = new_booking_reference().unwrap();
+        let code = reference.strip_prefix("BB-").unwrap();
+        assert_eq!(code.len(), 6);
+        assert!(code.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()));
+        assert!(!code.contains(['0', '1', 'O', 'I', 'L']), "{}", reference);
+    }
+    assert_ne!(new_booking_reference().unwrap(), new_booking_reference().unwrap());
+}
+
+#[test]
+fn booking_references_are_found_however_they_were_typed() {
+    assert_eq!(normalize_booking_reference("BB-7K2F9Q").as_deref(), Some("BB-7K2F9Q"));
+    assert_eq!(normalize_booking_reference(" bb-7k2f9q ").as_deref(), Some("BB-7K2F9Q"));
+    assert_eq!(normalize_booking_reference("7k2 f9q").as_deref(), Some("BB-7K2F9Q"));
+    assert_eq!(normalize_booking_reference("BB7K2F9Q").as_deref(), Some("BB-7K2F9Q"));
+    assert_eq!(normalize_booking_reference("BB-7K2F9"), None);
+    assert_eq!(normalize_booking_reference("BB-0K2F9Q"), None);
+    assert_eq!(normalize_booking_reference("65f1c0ffee000000000b00c1"), None);
+}
+
+#[test]
+fn boarding_lookups_only_show_initials() {
+    assert_eq!(masked_name("Achieng Otieno"), "A. Otieno");
+    assert_eq!(masked_name(" john  wafula Kamau "), "J. W. Kamau");
+    assert_eq!(masked_name("Wanjiru"), "Wanjiru");
+    assert_eq!(masked_name(" "), "");
+}
@@ -0,0 +1,24 @@
+use crate::metrics::metrics;
+
+#[test]
+fn booking_outcomes_are_counted_separately() {
+    let bookings = &metrics().bookings;
+    let (succeeded, failed) = (bookings.with_label_values(&["succeeded"]).get(), bookings.with_label_values(&["failed"]).get());
+    bookings.with_label_values(&["failed"]).inc();
+    assert!(bookings.with_label_values(&["failed"]).get() > failed);
+    assert!(bookings.with_label_values(&["succeeded"]).get() >= succeeded);
+}
+
+#[test]
+fn exposition_covers_requests_bookings_and_database() {
+    let metrics = metrics();
+    metrics.request_duration.with_label_values(&["GET", "/api/buses/{id}", "200"]).observe(0.02);
+    metrics.bookings.with_label_values(&["succeeded"]).inc();
+    metrics.db_command_duration.with_label_values(&["find", "buses"]).observe(0.004);
+
+    let text = metrics.render();
+    // Request counts per route come from the histogram's `_count` series
+    assert!(text.contains(r#"http_request_duration_seconds_count{method="GET",route="/api/buses/{id}",status="200"}"#));
+    assert!(text.contains(r#"bookings_total{outcome="succeeded"}"#));
+    assert!(text.contains(r#"mongodb_command_duration_seconds_bucket{collection="buses",command="find""#));
+}
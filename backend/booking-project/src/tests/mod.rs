@@ -0,0 +1,57 @@
+mod app_error;
+mod auth_extractor;
+mod auth_header;
+mod booking_response;
+mod bus_search;
+mod charters;
+#[cfg(feature = "client")]
+mod client;
+mod compare;
+mod consent;
+mod crypto;
+mod device_signing;
+mod duplicates;
+mod email;
+mod email_change;
+mod event_stream;
+mod events;
+mod fares;
+mod feeds;
+mod fixtures;
+mod fraud;
+mod gtfs;
+mod holds;
+mod inventory;
+mod ledger;
+mod localization;
+mod lookup;
+mod metrics;
+mod next_trip;
+mod notification_resend;
+mod offline_sync;
+mod openapi;
+mod parcels;
+mod partners;
+mod passes;
+mod password_reset;
+mod payment_methods;
+mod payments;
+mod payload_budgets;
+mod permissions;
+mod price_history;
+mod profiling;
+mod rate_limit;
+mod response_snapshots;
+mod schedule;
+mod seat_layout;
+mod settlements;
+mod sharing;
+mod slo;
+mod smoke;
+mod status;
+mod tenancy;
+mod tickets;
+mod tokens;
+mod trip_history;
+mod trip_tokens;
+mod webhooks;
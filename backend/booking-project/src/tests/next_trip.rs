@@ -0,0 +1,53 @@
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::models::next_trip::{UpcomingTrip, UpcomingTripResponse};
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+fn trip(status: &str, delay_minutes: i64) -> UpcomingTrip {
+    UpcomingTrip {
+        booking_id: ObjectId::parse_str("6761d0a1c2b3a4f5e6d7c8b9").unwrap(),
+        bus_id: ObjectId::new(),
+        bus_number: "Mash Poa - KCB 123A".to_string(),
+        bus_type: "Executive".to_string(),
+        from: "Nairobi".to_string(),
+        to: "Mombasa".to_string(),
+        boarding_point: "Nairobi".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        seat_number: "12".to_string(),
+        departure_at: bson::DateTime::from_millis(at("2026-12-20T08:00:00+03:00").timestamp_millis()),
+        arrival_at: Some(bson::DateTime::from_millis(at("2026-12-20T16:00:00+03:00").timestamp_millis())),
+        status: status.to_string(),
+        delay_minutes,
+    }
+}
+
+#[test]
+fn countdown_runs_to_the_expected_departure() {
+    let now = at("2026-12-20T07:00:00+03:00");
+    let card = UpcomingTripResponse::new(trip("delayed", 30), now, "https://busbook.example/");
+    assert_eq!(card.departs_in_seconds, 90 * 60);
+    assert_eq!(card.expected_departure, at("2026-12-20T08:30:00+03:00").to_rfc3339());
+    assert_eq!(card.boarding_point, "Nairobi");
+    assert_eq!(card.ticket_url, "https://busbook.example/api/bookings/6761d0a1c2b3a4f5e6d7c8b9/receipt.pdf");
+
+    let on_the_road = UpcomingTripResponse::new(trip("departed", 0), now + Duration::hours(3), "");
+    assert_eq!(on_the_road.departs_in_seconds, 0);
+}
+
+#[test]
+fn trips_leave_the_card_once_they_arrive() {
+    let trip = trip("departed", 45);
+    assert!(!trip.is_over(at("2026-12-20T16:30:00+03:00")));
+    assert!(trip.is_over(at("2026-12-20T16:45:00+03:00")));
+
+    let arrived = UpcomingTrip { status: "arrived".to_string(), ..trip.clone() };
+    assert!(arrived.is_over(at("2026-12-20T12:00:00+03:00")));
+
+    let no_arrival_time = UpcomingTrip { arrival_at: None, ..trip };
+    assert!(!no_arrival_time.is_over(at("2026-12-21T08:00:00+03:00")));
+    assert!(no_arrival_time.is_over(at("2026-12-21T09:00:00+03:00")));
+}
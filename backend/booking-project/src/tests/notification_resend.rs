@@ -0,0 +1,81 @@
+use mongodb::bson::{doc, oid::ObjectId};
+
+use crate::models::notification_resend::{
+    NotificationResend, NotificationResendResponse, ResendConfirmationsRequest, ResendTally, RESEND_COMPLETED, RESEND_QUEUED,
+};
+
+fn request(bus_id: Option<&str>, travel_date: Option<&str>) -> ResendConfirmationsRequest {
+    ResendConfirmationsRequest {
+        bus_id: bus_id.map(str::to_string),
+        travel_date: travel_date.map(str::to_string),
+        status: None,
+        channels: vec![],
+    }
+}
+
+#[test]
+fn resends_must_name_a_trip_or_date() {
+    assert!(request(None, None).validate().is_err());
+    assert!(request(Some("65f1c0ffee0000000000b0b5"), None).validate().is_ok());
+    assert!(request(None, Some("2026-01-10")).validate().is_ok());
+    assert!(request(None, Some("10/01/2026")).validate().is_err());
+}
+
+#[test]
+fn cancelled_bookings_and_unknown_channels_are_refused() {
+    let cancelled = ResendConfirmationsRequest { status: Some("Cancelled".to_string()), ..request(None, Some("2026-01-10")) };
+    assert!(cancelled.validate().unwrap_err().contains("Cancelled"));
+    let unknown = ResendConfirmationsRequest { status: Some("Boarded".to_string()), ..request(None, Some("2026-01-10")) };
+    assert!(unknown.validate().is_err());
+    let pigeon = ResendConfirmationsRequest { channels: vec!["pigeon".to_string()], ..request(None, Some("2026-01-10")) };
+    assert!(pigeon.validate().is_err());
+}
+
+#[test]
+fn runs_default_to_confirmed_bookings_on_every_channel() {
+    let req = request(None, Some("2026-01-10"));
+    let run = NotificationResend::new(&req, None, None, 12, "user:admin");
+    assert_eq!(run.state, RESEND_QUEUED);
+    assert_eq!(run.booking_filter(), doc! { "status": "Confirmed", "travel_date": "2026-01-10" });
+    assert!(run.sends("email") && run.sends("sms"));
+
+    let sms_only = ResendConfirmationsRequest { channels: vec!["sms".to_string(), "sms".to_string()], ..request(None, Some("2026-01-10")) };
+    assert_eq!(sms_only.channels(), vec!["sms"]);
+}
+
+#[test]
+fn trip_runs_filter_by_bus() {
+    let bus_id = ObjectId::new();
+    let req = ResendConfirmationsRequest { status: Some("PendingPayment".to_string()), ..request(Some(&bus_id.to_hex()), None) };
+    let run = NotificationResend::new(&req, Some("Easy Coach".to_string()), Some(bus_id), 3, "user:operator");
+    assert_eq!(run.booking_filter(), doc! { "status": "PendingPayment", "bus_id": bus_id });
+}
+
+#[test]
+fn tallies_count_bookings_with_nowhere_to_send_as_skipped() {
+    let mut tally = ResendTally::default();
+    tally.record(true, true);
+    tally.record(true, false);
+    tally.record(false, false);
+    tally.record_failure("65f1c0ffee0000000000b001", "Bus not found".to_string());
+    assert_eq!(tally.processed, 4);
+    assert_eq!(tally.emails_queued, 2);
+    assert_eq!(tally.sms_queued, 1);
+    assert_eq!(tally.skipped, 1);
+    assert_eq!(tally.failures.len(), 1);
+}
+
+#[test]
+fn progress_is_capped_until_the_run_completes() {
+    let mut run = NotificationResend::new(&request(None, Some("2026-01-10")), None, None, 200, "user:admin");
+    assert_eq!(NotificationResendResponse::from(run.clone()).progress_percent, 0);
+    run.processed = 50;
+    assert_eq!(run.progress_percent(), 25);
+    // Bookings made after the request are resent too
+    run.processed = 230;
+    assert_eq!(run.progress_percent(), 100);
+    run.total = 0;
+    assert_eq!(run.progress_percent(), 0);
+    run.state = RESEND_COMPLETED.to_string();
+    assert_eq!(run.progress_percent(), 100);
+}
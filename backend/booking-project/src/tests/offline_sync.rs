@@ -0,0 +1,104 @@
+use crate::models::offline_sync::{
+    offline_time, OfflineCashSale, OfflineCheckIn, ReconciliationReport, SyncItemResult, SyncUploadRequest, MAX_SYNC_ITEMS,
+    SYNC_APPLIED, SYNC_CONFLICT, SYNC_DUPLICATE, SYNC_REJECTED,
+};
+use crate::models::ticket::{sign_ticket, ticket_hash, TicketClaims};
+
+fn check_in(client_id: &str) -> OfflineCheckIn {
+    OfflineCheckIn {
+        client_id: client_id.to_string(),
+        booking_id: "65f1c0ffee0000000000b001".to_string(),
+        at: "2026-01-10T08:40:00+03:00".to_string(),
+    }
+}
+
+fn sale(client_id: &str, fare: f64) -> OfflineCashSale {
+    OfflineCashSale {
+        client_id: client_id.to_string(),
+        seat_number: "22".to_string(),
+        passenger_name: "Otieno Ouma".to_string(),
+        phone: None,
+        next_of_kin: None,
+        fare,
+        sold_at: "2026-01-10T09:15:00+03:00".to_string(),
+    }
+}
+
+fn sale_result(outcome: &str, fare: f64) -> SyncItemResult {
+    SyncItemResult { fare, ..SyncItemResult::new("s", "cash_sale", outcome, None, None) }
+}
+
+#[test]
+fn qr_hashes_match_the_scanned_text() {
+    let claims = TicketClaims {
+        booking_id: "65f1c0ffee0000000000b001".to_string(),
+        bus_id: "65f1c0ffee0000000000b0b5".to_string(),
+        travel_date: "2026-11-20".to_string(),
+        seat_number: "14".to_string(),
+    };
+    let payload = sign_ticket(&claims, "ticket-test-key");
+    assert_eq!(ticket_hash(&payload), ticket_hash(&format!(" {}\n", payload)));
+    assert_ne!(ticket_hash(&payload), ticket_hash(&sign_ticket(&TicketClaims { seat_number: "15".to_string(), ..claims }, "ticket-test-key")));
+    assert_eq!(ticket_hash(&payload).len(), 43);
+}
+
+#[test]
+fn batches_need_items_with_distinct_client_ids() {
+    let empty = SyncUploadRequest { check_ins: vec![], cash_sales: vec![] };
+    assert!(empty.validate().is_err());
+
+    let batch = SyncUploadRequest { check_ins: vec![check_in("c1")], cash_sales: vec![sale("s1", 1500.0)] };
+    assert!(batch.validate().is_ok());
+    let repeated = SyncUploadRequest { check_ins: vec![check_in("x")], cash_sales: vec![sale("x", 1500.0)] };
+    assert!(repeated.validate().unwrap_err().contains("more than once"));
+    let unnamed = SyncUploadRequest { check_ins: vec![check_in(" ")], cash_sales: vec![] };
+    assert!(unnamed.validate().is_err());
+
+    let oversized = SyncUploadRequest {
+        check_ins: (0..=MAX_SYNC_ITEMS).map(|i| check_in(&format!("c{}", i))).collect(),
+        cash_sales: vec![],
+    };
+    assert!(oversized.validate().is_err());
+}
+
+#[test]
+fn cash_sales_need_a_passenger_seat_and_fare() {
+    assert!(sale("s1", 1500.0).validate().is_ok());
+    assert!(sale("s1", 0.0).validate().is_ok());
+    assert!(sale("s1", -10.0).validate().is_err());
+    assert!(sale("s1", f64::NAN).validate().is_err());
+    assert!(OfflineCashSale { passenger_name: "  ".to_string(), ..sale("s1", 1500.0) }.validate().is_err());
+    assert!(OfflineCashSale { seat_number: String::new(), ..sale("s1", 1500.0) }.validate().is_err());
+}
+
+#[test]
+fn offline_times_may_be_late_but_not_ahead() {
+    let now = chrono::Utc::now();
+    assert_eq!(offline_time("2026-01-10T08:40:00+03:00", now).unwrap().to_rfc3339(), "2026-01-10T05:40:00+00:00");
+    assert!(offline_time(&(now + chrono::Duration::minutes(3)).to_rfc3339(), now).is_ok());
+    assert!(offline_time(&(now + chrono::Duration::hours(1)).to_rfc3339(), now).is_err());
+    assert!(offline_time("yesterday", now).is_err());
+}
+
+#[test]
+fn reconciliation_splits_cash_kept_from_cash_owed_back() {
+    let results = vec![
+        SyncItemResult::new("c1", "check_in", SYNC_APPLIED, None, None),
+        SyncItemResult::new("c2", "check_in", SYNC_DUPLICATE, None, None),
+        SyncItemResult::new("c3", "check_in", SYNC_REJECTED, Some("Booking is cancelled".to_string()), None),
+        sale_result(SYNC_APPLIED, 1500.0),
+        sale_result(SYNC_APPLIED, 1200.5),
+        sale_result(SYNC_CONFLICT, 1500.0),
+        sale_result(SYNC_REJECTED, 800.0),
+    ];
+    let report = ReconciliationReport::new("bus", "2026-11-20", results, vec!["3".to_string()]);
+    assert_eq!(report.checked_in, 1);
+    assert_eq!(report.duplicate_check_ins, 1);
+    assert_eq!(report.cash_sales, 2);
+    assert_eq!(report.cash_collected, 2700.5);
+    assert_eq!(report.cash_to_refund, 2300.0);
+    assert_eq!(report.conflicts, 1);
+    assert_eq!(report.rejected, 2);
+    assert_eq!(report.not_boarded, vec!["3"]);
+    assert_eq!(report.results.len(), 7);
+}
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use utoipa::OpenApi;
+
+use crate::models::manifest::TRIP_ACCESS_SCOPE;
+use crate::models::permissions::BOOKINGS_REFUND;
+use crate::openapi::{audience_spec, ApiDoc, Audience};
+
+fn spec() -> serde_json::Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap()
+}
+
+fn operations(spec: &serde_json::Value) -> Vec<(String, String, serde_json::Value)> {
+    let mut operations = Vec::new();
+    for (path, methods) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in methods.as_object().unwrap() {
+            operations.push((path.clone(), method.clone(), operation.clone()));
+        }
+    }
+    operations
+}
+
+#[test]
+fn every_operation_has_its_own_id() {
+    let spec = spec();
+    let operations = operations(&spec);
+    let ids: HashSet<&str> = operations.iter().map(|(_, _, op)| op["operationId"].as_str().unwrap()).collect();
+    assert_eq!(ids.len(), operations.len());
+    assert!(operations.len() > 150);
+}
+
+#[test]
+fn admin_endpoints_need_a_bearer_token() {
+    let spec = spec();
+    assert_eq!(spec["components"]["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+    for (path, method, operation) in operations(&spec) {
+        if path.starts_with("/api/admin") {
+            assert_eq!(operation["security"][0]["bearer_auth"], serde_json::json!([]), "{} {}", method, path);
+        }
+    }
+    assert!(spec["paths"]["/api/buses/search"]["get"].get("security").is_none());
+}
+
+#[test]
+fn booking_flow_is_typed_end_to_end() {
+    let spec = spec();
+    let search = &spec["paths"]["/api/buses/search"]["get"];
+    let params: Vec<&str> = search["parameters"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+    assert!(params.contains(&"from") && params.contains(&"date"));
+    assert_eq!(search["responses"]["200"]["content"]["application/json"]["schema"]["items"]["$ref"], "#/components/schemas/BusResponse");
+
+    let book = &spec["paths"]["/api/bookings"]["post"];
+    assert_eq!(book["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/CreateBookingRequest");
+    assert_eq!(book["responses"]["400"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/ErrorResponse");
+    assert!(spec["components"]["schemas"]["BookingConfirmation"].is_object());
+}
+
+#[test]
+fn each_audience_sees_only_its_own_surface() {
+    let full = spec();
+    let variants: Vec<(Audience, serde_json::Value)> = Audience::ALL.iter()
+        .map(|audience| (*audience, serde_json::to_value(audience_spec(*audience)).unwrap()))
+        .collect();
+    for (path, _, _) in operations(&full) {
+        let listed_in = variants.iter().filter(|(_, spec)| spec["paths"].get(&path).is_some()).count();
+        assert_eq!(listed_in, usize::from(Audience::of(&path).is_some()), "{}", path);
+    }
+
+    let [(_, public), (_, partner), (_, admin)] = variants.as_slice() else { unreachable!() };
+    assert!(public["paths"]["/api/bookings"].is_object());
+    assert!(public["paths"]["/api/webhooks/{provider}"].is_null());
+    assert!(partner["paths"]["/api/partners/conversions"].is_object());
+    assert!(partner["paths"]["/api/bookings"].is_null());
+    assert!(admin["paths"]["/api/checkin/scan"].is_object());
+    assert!(admin["paths"]["/api/buses/search"].is_null());
+}
+
+#[test]
+fn audience_specs_keep_just_the_schemas_and_tags_they_use() {
+    let partner = serde_json::to_value(audience_spec(Audience::Partner)).unwrap();
+    let schemas = partner["components"]["schemas"].as_object().unwrap();
+    assert!(schemas.contains_key("ErrorResponse"));
+    assert!(!schemas.contains_key("CharterResponse"));
+    let tags: Vec<&str> = partner["tags"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert!(!tags.contains(&"admin"));
+
+    for audience in Audience::ALL {
+        let spec = serde_json::to_value(audience_spec(audience)).unwrap();
+        let text = spec.to_string();
+        for name in text.split("\"#/components/schemas/").skip(1).map(|rest| &rest[..rest.find('"').unwrap()]) {
+            assert!(spec["components"]["schemas"].get(name).is_some(), "{} misses {}", audience.name(), name);
+        }
+    }
+}
+
+#[test]
+fn staff_endpoints_name_the_permission_they_take() {
+    let spec = spec();
+    let refund = &spec["paths"]["/api/admin/refunds/{id}/approve"]["post"];
+    assert_eq!(refund["x-audience"], "admin");
+    assert_eq!(refund["x-required-permission"], BOOKINGS_REFUND);
+    assert!(refund["description"].as_str().unwrap().contains(BOOKINGS_REFUND));
+    assert_eq!(spec["paths"]["/api/checkin/scan"]["post"]["x-required-scope"], TRIP_ACCESS_SCOPE);
+    assert_eq!(spec["paths"]["/api/buses/search"]["get"]["x-audience"], "public");
+    assert!(spec["paths"]["/api/buses/search"]["get"].get("x-required-permission").is_none());
+}
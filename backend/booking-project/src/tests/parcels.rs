@@ -0,0 +1,131 @@
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::config::ParcelConfig;
+use crate::models::parcel::{
+    new_waybill, normalize_waybill, parcel_can_move, parcel_price, CreateParcelRequest, Parcel, ParcelContact,
+    ParcelEvent, ParcelTrackingResponse, PARCEL_ARRIVED, PARCEL_COLLECTED, PARCEL_IN_TRANSIT, PARCEL_RECEIVED,
+};
+use crate::models::permissions::{admin_permission, TRIPS_OPERATE};
+use crate::models::tenancy::is_tenant_aware;
+
+fn config() -> ParcelConfig {
+    ParcelConfig { base_fee: 150.0, per_kg: 30.0, max_weight_kg: 50.0 }
+}
+
+fn contact(name: &str, phone: &str) -> ParcelContact {
+    ParcelContact { name: name.to_string(), phone: phone.to_string() }
+}
+
+fn request(size: &str, weight_kg: f64) -> CreateParcelRequest {
+    CreateParcelRequest {
+        bus_id: ObjectId::new().to_hex(),
+        travel_date: "2026-11-20".to_string(),
+        sender: contact(" Wanjiru Kamau ", "0712 345 678"),
+        recipient: contact("Otieno Ouma", "+254 733 000 111"),
+        description: "Box of books".to_string(),
+        size: size.to_string(),
+        weight_kg,
+    }
+}
+
+fn parcel(status: &str) -> Parcel {
+    let now = bson::DateTime::now();
+    Parcel {
+        id: Some(ObjectId::new()),
+        waybill: "PCL-7K2F9QXA".to_string(),
+        bus_id: ObjectId::new(),
+        travel_date: "2026-11-20".to_string(),
+        from: "Nairobi".to_string(),
+        to: "Kisumu".to_string(),
+        sender: contact("Wanjiru Kamau", "254712345678"),
+        recipient: contact("Otieno Ouma", "254733000111"),
+        description: "Box of books".to_string(),
+        size: "small".to_string(),
+        weight_kg: 4.2,
+        price: 400.0,
+        status: status.to_string(),
+        history: vec![ParcelEvent {
+            status: PARCEL_RECEIVED.to_string(),
+            actor: "user:65f1c0ffee0000000000a11c".to_string(),
+            note: Some("ID 12345678".to_string()),
+            at: now,
+        }],
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+#[test]
+fn parcels_are_priced_by_size_and_every_started_kilogram() {
+    let config = config();
+    assert_eq!(parcel_price("envelope", 0.2, &config), Some(180.0));
+    assert_eq!(parcel_price("small", 4.2, &config), Some(400.0));
+    assert_eq!(parcel_price("large", 20.0, &config), Some(1250.0));
+    assert_eq!(parcel_price("crate", 1.0, &config), None);
+}
+
+#[test]
+fn waybills_are_readable_and_accepted_however_they_are_typed() {
+    let waybill = new_waybill().unwrap();
+    assert!(waybill.starts_with("PCL-"));
+    assert_eq!(normalize_waybill(&waybill), Some(waybill.clone()));
+    assert_eq!(normalize_waybill(" pcl 7k2f 9qxa "), Some("PCL-7K2F9QXA".to_string()));
+    assert_eq!(normalize_waybill("7K2F9QXA"), Some("PCL-7K2F9QXA".to_string()));
+    // Too short, and letters that are never issued
+    assert_eq!(normalize_waybill("PCL-7K2F9QX"), None);
+    assert_eq!(normalize_waybill("PCL-7K2F9Q0I"), None);
+}
+
+#[test]
+fn parcel_requests_are_validated_and_phones_normalized() {
+    let config = config();
+    let (sender, recipient) = request("small", 4.2).validated_contacts(&config).unwrap();
+    assert_eq!(sender.name, "Wanjiru Kamau");
+    assert_eq!(sender.phone, "254712345678");
+    assert_eq!(recipient.phone, "254733000111");
+
+    assert!(request("crate", 4.2).validated_contacts(&config).is_err());
+    assert!(request("small", 0.0).validated_contacts(&config).is_err());
+    assert!(request("small", 51.0).validated_contacts(&config).is_err());
+    let mut no_phone = request("small", 4.2);
+    no_phone.recipient.phone = "12345".to_string();
+    assert!(no_phone.validated_contacts(&config).is_err_and(|e| e.starts_with("recipient")));
+}
+
+#[test]
+fn parcels_only_move_forward() {
+    assert!(parcel_can_move(PARCEL_RECEIVED, PARCEL_IN_TRANSIT));
+    assert!(parcel_can_move(PARCEL_IN_TRANSIT, PARCEL_ARRIVED));
+    // A parcel handed over at the office it was dropped at skips the trip states
+    assert!(parcel_can_move(PARCEL_RECEIVED, PARCEL_COLLECTED));
+    assert!(!parcel_can_move(PARCEL_ARRIVED, PARCEL_IN_TRANSIT));
+    assert!(!parcel_can_move(PARCEL_COLLECTED, PARCEL_COLLECTED));
+    assert!(!parcel_can_move(PARCEL_RECEIVED, "lost"));
+}
+
+#[test]
+fn each_tracking_state_texts_the_right_people() {
+    let parcel = parcel(PARCEL_RECEIVED);
+    let phones = |status: &str| parcel.status_sms(status).into_iter().map(|(phone, _)| phone).collect::<Vec<_>>();
+    assert_eq!(phones(PARCEL_RECEIVED), ["254712345678", "254733000111"]);
+    assert_eq!(phones(PARCEL_IN_TRANSIT), ["254733000111"]);
+    assert_eq!(phones(PARCEL_ARRIVED), ["254733000111", "254712345678"]);
+    assert_eq!(phones(PARCEL_COLLECTED), ["254712345678"]);
+    assert!(parcel.status_sms(PARCEL_RECEIVED)[0].1.contains("PCL-7K2F9QXA"));
+}
+
+#[test]
+fn public_tracking_hides_contacts_and_staff_notes() {
+    let tracking = serde_json::to_value(ParcelTrackingResponse::from(parcel(PARCEL_IN_TRANSIT))).unwrap();
+    assert_eq!(tracking["status"], "in_transit");
+    assert_ne!(tracking["recipient"], "Otieno Ouma");
+    assert!(tracking["history"][0]["note"].is_null());
+    assert!(!tracking.to_string().contains("2547"));
+}
+
+#[test]
+fn parcel_desk_is_scoped_to_the_operator() {
+    assert_eq!(admin_permission("POST", "/parcels"), TRIPS_OPERATE);
+    assert_eq!(admin_permission("GET", "/parcels"), TRIPS_OPERATE);
+    assert!(is_tenant_aware("PUT", "/parcels/PCL-7K2F9QXA/status"));
+}
@@ -0,0 +1,101 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde_json::json;
+
+use crate::config::PartnerConfig;
+use crate::models::booking::{Booking, BookingConfirmation};
+use crate::models::fraud::RiskAssessment;
+use crate::models::partner::{partner_landing_url, tracked_link, verify_link_signature, PartnerOfferRequest};
+
+fn config() -> PartnerConfig {
+    PartnerConfig {
+        link_secret: "partner-secret".to_string(),
+        conversion_token: None,
+        public_base_url: "https://api.example.com".to_string(),
+        max_offers: 3,
+    }
+}
+
+fn offer_request(kind: &str, url: &str) -> PartnerOfferRequest {
+    serde_json::from_value(json!({
+        "partner": "Lakeside Hotel",
+        "kind": kind,
+        "city": " Kisumu ",
+        "title": "10% off your first night",
+        "url": url,
+    })).unwrap()
+}
+
+#[test]
+fn offers_need_a_known_kind_and_an_https_link() {
+    let valid = offer_request("hotel", "https://lakeside.example/book");
+    assert!(valid.validate().is_ok());
+    let offer = valid.into_offer(None, DateTime::now());
+    assert_eq!(offer.city, "Kisumu");
+    assert_eq!(offer.city_key, "kisumu");
+    assert!(offer.active);
+
+    assert!(offer_request("spa", "https://lakeside.example/book").validate().is_err());
+    assert!(offer_request("taxi", "http://lakeside.example/book").validate().is_err());
+}
+
+#[test]
+fn click_links_are_signed_per_offer_and_booking() {
+    let (offer, booking) = (ObjectId::new().to_hex(), ObjectId::new().to_hex());
+    let link = tracked_link(&config(), &offer, &booking);
+    assert!(link.starts_with(&format!("https://api.example.com/api/partners/offers/{}/click?booking={}&sig=", offer, booking)));
+
+    let sig = link.rsplit("sig=").next().unwrap();
+    assert!(verify_link_signature("partner-secret", &offer, &booking, sig));
+    assert!(!verify_link_signature("partner-secret", &offer, &ObjectId::new().to_hex(), sig));
+    assert!(!verify_link_signature("other-secret", &offer, &booking, sig));
+
+    assert_eq!(partner_landing_url("https://taxi.example/ride", "abc"), "https://taxi.example/ride?ref=abc");
+    assert_eq!(partner_landing_url("https://taxi.example/ride?city=kisumu", "abc"), "https://taxi.example/ride?city=kisumu&ref=abc");
+}
+
+#[test]
+fn confirmation_keeps_the_booking_fields_at_the_top_level() {
+    let booking = Booking {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        bus_id: ObjectId::new(),
+        seat_number: "12".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        booking_date: DateTime::now(),
+        status: "Confirmed".to_string(),
+        passenger: None,
+        history: vec![],
+        terms_version: None,
+        policy_version: None,
+        risk: None,
+        country: None,
+        reference: None,
+    };
+    let value = serde_json::to_value(BookingConfirmation { booking, partner_offers: vec![] }).unwrap();
+    assert_eq!(value["seat_number"], "12");
+    assert_eq!(value["status"], "Confirmed");
+    assert_eq!(value["partner_offers"], json!([]));
+}
+
+#[test]
+fn confirmation_leaves_out_the_fraud_assessment() {
+    let booking = Booking {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        bus_id: ObjectId::new(),
+        seat_number: "12".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        booking_date: DateTime::now(),
+        status: "PendingReview".to_string(),
+        passenger: None,
+        history: vec![],
+        terms_version: None,
+        policy_version: None,
+        risk: Some(RiskAssessment { score: 70, reasons: vec!["4 bookings in the last hour".to_string()] }),
+        country: None,
+        reference: None,
+    };
+    let value = serde_json::to_value(BookingConfirmation::new(booking, vec![])).unwrap();
+    assert_eq!(value["status"], "PendingReview");
+    assert!(value.get("risk").is_none());
+}
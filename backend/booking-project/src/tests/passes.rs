@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::models::booking::{CreateBookingRequest, CreateGroupBookingRequest};
+use crate::models::bus::Bus;
+use crate::tests::fixtures::{self, route};
+use crate::models::pass::{pass_price, BuyPassRequest, Pass, PASS_ACTIVE, PASS_FAILED, PASS_PENDING};
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+fn millis(time: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(time.timestamp_millis())
+}
+
+fn bus(from: &str, to: &str, price: f64) -> Bus {
+    Bus {
+        bus_number: "Molo Line - KDA 411B".to_string(),
+        bus_type: "Shuttle".to_string(),
+        total_seats: 14,
+        route: route(from, to, "06:30 AM", "09:00 AM", price),
+        ..fixtures::bus()
+    }
+}
+
+fn pass(status: &str, rides_left: i32, expires_at: Option<DateTime<Utc>>) -> Pass {
+    Pass {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        from: "Nakuru".to_string(),
+        to: "Nairobi".to_string(),
+        ride_fare: 500.0,
+        rides_total: 10,
+        rides_left,
+        price: pass_price(500.0, 10, 15.0),
+        status: status.to_string(),
+        redemptions: Vec::new(),
+        created_at: millis(at("2026-11-01T08:00:00+03:00")),
+        activated_at: expires_at.map(|_| millis(at("2026-11-01T08:01:00+03:00"))),
+        expires_at: expires_at.map(millis),
+    }
+}
+
+#[test]
+fn passes_are_priced_at_a_discount_in_whole_shillings() {
+    assert_eq!(pass_price(500.0, 10, 15.0), 4250.0);
+    assert_eq!(pass_price(450.0, 10, 12.5), 3937.0);
+    assert_eq!(pass_price(500.0, 10, 0.0), 5000.0);
+}
+
+#[test]
+fn pass_state_follows_payment_rides_and_expiry() {
+    let now = at("2026-11-10T07:00:00+03:00");
+    let expires = Some(at("2026-12-31T08:01:00+03:00"));
+    assert_eq!(pass(PASS_PENDING, 10, None).state(now), "pending_payment");
+    assert_eq!(pass(PASS_FAILED, 10, None).state(now), "payment_failed");
+    assert_eq!(pass(PASS_ACTIVE, 3, expires).state(now), "active");
+    assert_eq!(pass(PASS_ACTIVE, 0, expires).state(now), "used_up");
+    assert_eq!(pass(PASS_ACTIVE, 3, expires).state(at("2027-01-01T00:00:00+03:00")), "expired");
+}
+
+#[test]
+fn rides_come_off_a_pass_only_on_its_route_and_fare() {
+    let now = at("2026-11-10T07:00:00+03:00");
+    let active = pass(PASS_ACTIVE, 2, Some(at("2026-12-31T08:01:00+03:00")));
+    assert!(active.check_ride(&bus("Nakuru", "Nairobi", 500.0), "2026-11-11", 2, now).is_ok());
+    assert!(active.check_ride(&bus("nakuru", "NAIROBI", 450.0), "2026-12-31", 1, now).is_ok());
+    assert!(active.check_ride(&bus("Nairobi", "Nakuru", 500.0), "2026-11-11", 1, now).is_err());
+    assert!(active.check_ride(&bus("Nakuru", "Nairobi", 650.0), "2026-11-11", 1, now).is_err());
+    assert!(active.check_ride(&bus("Nakuru", "Nairobi", 500.0), "2026-11-11", 3, now).is_err());
+    // Trips after the pass runs out can't be booked on it
+    assert!(active.check_ride(&bus("Nakuru", "Nairobi", 500.0), "2027-01-01", 1, now).is_err());
+    assert!(pass(PASS_PENDING, 10, None).check_ride(&bus("Nakuru", "Nairobi", 500.0), "2026-11-11", 1, now).is_err());
+}
+
+#[test]
+fn buying_a_pass_needs_a_route_and_an_mpesa_number() {
+    let req = |from: &str, phone: &str| BuyPassRequest { from: from.to_string(), to: "Nairobi".to_string(), phone: phone.to_string() };
+    assert_eq!(req("Nakuru", "0712 345 678").validated_phone().unwrap(), "254712345678");
+    assert!(req(" ", "0712345678").validated_phone().is_err());
+    assert!(req("Nakuru", "12345").validated_phone().is_err());
+}
+
+#[test]
+fn a_booking_names_the_pass_it_rides_on() {
+    let req: CreateBookingRequest = serde_json::from_value(serde_json::json!({
+        "bus_id": ObjectId::new().to_hex(),
+        "seat_number": "4",
+        "travel_date": "2026-11-11",
+        "pass_id": "6560f1a2b3c4d5e6f7a8b9c0",
+    })).unwrap();
+    let group = CreateGroupBookingRequest::from(&req);
+    assert_eq!(group.pass_id.as_deref(), Some("6560f1a2b3c4d5e6f7a8b9c0"));
+}
@@ -0,0 +1,19 @@
+use crate::models::auth::{ResetPasswordRequest, MIN_PASSWORD_LENGTH};
+
+fn reset(token: &str, new_password: &str) -> ResetPasswordRequest {
+    ResetPasswordRequest { token: token.to_string(), new_password: new_password.to_string() }
+}
+
+#[test]
+fn new_passwords_must_be_long_enough() {
+    assert!(reset("Zm9vYmFy", &"x".repeat(MIN_PASSWORD_LENGTH)).validate().is_ok());
+    let err = reset("Zm9vYmFy", "short").validate().unwrap_err();
+    assert!(err.contains(&MIN_PASSWORD_LENGTH.to_string()));
+    // Characters, not bytes
+    assert!(reset("Zm9vYmFy", "ñandúes").validate().is_err());
+}
+
+#[test]
+fn a_reset_needs_its_token() {
+    assert_eq!(reset("  ", "correct horse battery").validate().unwrap_err(), "Reset token is required");
+}
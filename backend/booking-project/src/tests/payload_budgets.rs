@@ -0,0 +1,127 @@
+// Payload budgets for the hot endpoints, measured through the same compression middleware the
+// server uses. The fixtures mirror the largest real responses: a 52-seat map, the full bus list
+// and a long booking history.
+use actix_web::{http::header, middleware::Compress, test, web, App, HttpResponse};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::models::booking::{Booking, BookingDetailResponse, Passenger};
+use crate::models::bus::{Bus, BusResponse, Route, Seat, SeatAvailabilityResponse};
+
+fn sample_bus(n: usize) -> Bus {
+    Bus {
+        id: Some(ObjectId::new()),
+        bus_number: format!("Mash East Africa - KDA {:03}B", n),
+        bus_type: "VIP Oxygen".to_string(),
+        total_seats: 52,
+        policy_id: None,
+        layout: None,
+        route: Route {
+            from: "Nairobi".to_string(),
+            to: "Mombasa".to_string(),
+            departure_time: "10:00 PM".to_string(),
+            arrival_time: "06:00 AM".to_string(),
+            price: 2200.0,
+        },
+    }
+}
+
+fn seat_map() -> serde_json::Value {
+    let seats = (1..=52)
+        .map(|n| Seat { seat_number: n.to_string(), is_available: n % 3 != 0 })
+        .collect();
+    serde_json::to_value(SeatAvailabilityResponse { travel_date: "2026-12-20".to_string(), seats, headcount: None, layout: None }).unwrap()
+}
+
+fn bus_list() -> serde_json::Value {
+    let buses: Vec<BusResponse> = (0..50).map(|n| BusResponse::from(sample_bus(n))).collect();
+    serde_json::to_value(buses).unwrap()
+}
+
+fn booking_history() -> serde_json::Value {
+    let bus = sample_bus(0);
+    let bookings: Vec<serde_json::Value> = (0..100)
+        .map(|n| {
+            let booking = Booking {
+                id: Some(ObjectId::new()),
+                user_id: ObjectId::new(),
+                bus_id: bus.id.unwrap(),
+                seat_number: (n % 52 + 1).to_string(),
+                travel_date: "2026-12-20".to_string(),
+                booking_date: DateTime::now(),
+                status: "Confirmed".to_string(),
+                passenger: Some(Passenger {
+                    name: "Wanjiku Kamau".to_string(),
+                    age: "34".to_string(),
+                    gender: "female".to_string(),
+                    phone: None,
+                    id_number: None,
+                    next_of_kin: None,
+                }),
+                history: Vec::new(),
+                terms_version: None,
+                policy_version: None,
+                risk: None,
+                country: None,
+                reference: None,
+            };
+            serde_json::to_value(BookingDetailResponse::from((booking, Some(bus.clone())))).unwrap()
+        })
+        .collect();
+    serde_json::Value::Array(bookings)
+}
+
+// Returns (content-encoding, body length) for the payload served through `Compress`
+async fn served_size(payload: serde_json::Value, accept_encoding: &str) -> (Option<String>, usize) {
+    let app = test::init_service(
+        App::new()
+            .wrap(Compress::default())
+            .route("/", web::get().to(move || {
+                let payload = payload.clone();
+                async move { HttpResponse::Ok().json(payload) }
+            })),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header((header::ACCEPT_ENCODING, accept_encoding))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    let encoding = res
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let body = test::read_body(res).await;
+    (encoding, body.len())
+}
+
+async fn assert_budget(name: &str, payload: serde_json::Value, raw_budget: usize, compressed_budget: usize) {
+    let (encoding, raw) = served_size(payload.clone(), "identity").await;
+    assert_eq!(encoding, None, "{}: identity requests must not be compressed", name);
+    assert!(raw <= raw_budget, "{}: {} bytes uncompressed exceeds the {} byte budget", name, raw, raw_budget);
+
+    for accept in ["gzip", "br"] {
+        let (encoding, compressed) = served_size(payload.clone(), accept).await;
+        assert_eq!(encoding.as_deref(), Some(accept), "{}: expected {} encoding", name, accept);
+        assert!(
+            compressed <= compressed_budget,
+            "{}: {} bytes with {} exceeds the {} byte budget", name, compressed, accept, compressed_budget
+        );
+    }
+}
+
+#[actix_web::test]
+async fn seat_map_fits_budget() {
+    assert_budget("seat map", seat_map(), 2_500, 400).await;
+}
+
+#[actix_web::test]
+async fn bus_list_fits_budget() {
+    assert_budget("bus list", bus_list(), 12_500, 1_000).await;
+}
+
+#[actix_web::test]
+async fn booking_history_fits_budget() {
+    assert_budget("booking history", booking_history(), 50_000, 4_000).await;
+}
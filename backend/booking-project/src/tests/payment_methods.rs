@@ -0,0 +1,54 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::error::AppError;
+use crate::models::payment_method::{PaymentMethod, PaymentMethodResponse, SavePaymentMethodRequest};
+use crate::payments::stripe::StripeVault;
+use crate::payments::{PaymentMethodVault, ProviderToken};
+
+fn token(value: &str) -> ProviderToken {
+    ProviderToken::try_from(value.to_string()).unwrap()
+}
+
+#[test]
+fn provider_tokens_round_trip_as_plain_strings() {
+    let req: SavePaymentMethodRequest = serde_json::from_str(r#"{"provider":"stripe","token":" pm_1NqXkT2eZvKYlo2C "}"#).unwrap();
+    assert_eq!(req.token.as_str(), "pm_1NqXkT2eZvKYlo2C");
+    assert_eq!(serde_json::to_string(&req.token).unwrap(), r#""pm_1NqXkT2eZvKYlo2C""#);
+    assert_eq!(format!("{:?}", req.token), "ProviderToken(…lo2C)");
+}
+
+#[test]
+fn card_numbers_are_refused_where_a_token_belongs() {
+    for raw in ["4242 4242 4242 4242", "5555-5555-5555-4444", "378282246310005"] {
+        let parsed = serde_json::from_value::<SavePaymentMethodRequest>(serde_json::json!({ "provider": "stripe", "token": raw }));
+        assert!(parsed.is_err(), "{} was accepted", raw);
+    }
+    assert!(ProviderToken::try_from("  ".to_string()).is_err());
+    assert!(ProviderToken::try_from("x".repeat(256)).is_err());
+}
+
+#[test]
+fn saved_methods_never_echo_their_token() {
+    let method = PaymentMethod {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        provider: "stripe".to_string(),
+        token: token("pm_1NqXkT2eZvKYlo2C"),
+        brand: "visa".to_string(),
+        last4: Some("4242".to_string()),
+        exp_month: Some(12),
+        exp_year: Some(2030),
+        created_at: DateTime::from_millis(1_773_446_400_000),
+    };
+    let body = serde_json::to_string(&PaymentMethodResponse::from(method)).unwrap();
+    assert!(!body.contains("pm_1NqXkT2eZvKYlo2C"));
+    assert!(body.contains("\"last4\":\"4242\""));
+}
+
+#[actix_web::test]
+async fn stripe_refuses_another_providers_token() {
+    // Rejected before any request goes out, so no credentials or network are needed
+    let vault = StripeVault::new("sk_test_unused".to_string());
+    let paypal = token("8kk8451t");
+    assert!(matches!(vault.describe(&paypal).await, Err(AppError::Validation(_))));
+}
@@ -0,0 +1,96 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::models::booking::{Booking, BookingEvent};
+use crate::models::payment::{booking_fare_due, pays_for, StkPushRequest};
+use crate::models::webhook::{external_id, MpesaStkCallback};
+use crate::payments::mpesa::stk_password;
+
+const PAID_CALLBACK: &str = r#"{"Body":{"stkCallback":{"MerchantRequestID":"29115-34620561-1","CheckoutRequestID":"ws_CO_191220191020363925","ResultCode":0,"ResultDesc":"The service request is processed successfully.","CallbackMetadata":{"Item":[{"Name":"Amount","Value":1450.00},{"Name":"MpesaReceiptNumber","Value":"NLJ7RT61SV"},{"Name":"Balance"},{"Name":"TransactionDate","Value":20261219102115},{"Name":"PhoneNumber","Value":254712345678}]}}}}"#;
+const CANCELLED_CALLBACK: &str = r#"{"Body":{"stkCallback":{"MerchantRequestID":"29115-34620561-2","CheckoutRequestID":"ws_CO_191220191020363926","ResultCode":1032,"ResultDesc":"Request cancelled by user"}}}"#;
+
+fn push_request(bookings: usize, phone: &str) -> StkPushRequest {
+    StkPushRequest {
+        booking_ids: (0..bookings).map(|_| ObjectId::new().to_hex()).collect(),
+        phone: phone.to_string(),
+    }
+}
+
+#[test]
+fn stk_password_encodes_shortcode_passkey_and_timestamp() {
+    // From Daraja's sandbox documentation
+    assert_eq!(
+        stk_password("174379", "bfb279f9aa9bdbcf158e97dd71a467cd2e0c893059b10f78e6b72ada1ed2c919", "20160216165627"),
+        "MTc0Mzc5YmZiMjc5ZjlhYTliZGJjZjE1OGU5N2RkNzFhNDY3Y2QyZTBjODkzMDU5YjEwZjc4ZTZiNzJhZGExZWQyYzkxOTIwMTYwMjE2MTY1NjI3",
+    );
+}
+
+#[test]
+fn paid_callbacks_carry_the_amount_and_receipt() {
+    let callback = MpesaStkCallback::parse(PAID_CALLBACK.as_bytes()).unwrap();
+    assert!(callback.succeeded());
+    assert_eq!(callback.checkout_request_id, "ws_CO_191220191020363925");
+    assert_eq!(callback.amount(), Some(1450.0));
+    assert_eq!(callback.receipt_number().as_deref(), Some("NLJ7RT61SV"));
+    assert_eq!(external_id("mpesa_stk", PAID_CALLBACK.as_bytes()).as_deref(), Some("ws_CO_191220191020363925"));
+}
+
+#[test]
+fn cancelled_callbacks_have_no_metadata() {
+    let callback = MpesaStkCallback::parse(CANCELLED_CALLBACK.as_bytes()).unwrap();
+    assert!(!callback.succeeded());
+    assert_eq!(callback.result_desc, "Request cancelled by user");
+    assert_eq!(callback.amount(), None);
+    assert_eq!(callback.receipt_number(), None);
+    assert!(MpesaStkCallback::parse(br#"{"TransID":"RKTQDM7W6S"}"#).is_err());
+}
+
+#[test]
+fn push_requests_need_a_kenyan_mobile_number() {
+    assert_eq!(push_request(1, "0712 345 678").validated_phone().unwrap(), "254712345678");
+    assert_eq!(push_request(2, "+254 110 345678").validated_phone().unwrap(), "254110345678");
+    assert!(push_request(1, "0202345678").validated_phone().is_err());
+    assert!(push_request(1, "12345").validated_phone().is_err());
+    assert!(push_request(0, "0712345678").validated_phone().is_err());
+    assert!(push_request(100, "0712345678").validated_phone().is_err());
+}
+
+#[test]
+fn payments_are_held_to_the_fare_the_booking_was_made_at() {
+    // Booked on a 1200 promotion; the route has since gone back to 1450
+    let due = booking_fare_due(Some(1200.0), Some(1450.0)).unwrap();
+    assert_eq!(due, 1200.0);
+    assert!(pays_for(1200.0, due));
+    assert!(pays_for(1199.999, due));
+    assert!(!pays_for(1200.0, 1450.0));
+    assert!(!pays_for(1150.0, due));
+
+    assert_eq!(booking_fare_due(None, Some(1450.0)), Some(1450.0));
+    assert_eq!(booking_fare_due(None, None), None);
+}
+
+#[test]
+fn payment_window_reopens_when_a_held_booking_is_approved() {
+    let booked = DateTime::from_millis(1_765_000_000_000);
+    let mut booking = Booking {
+        id: Some(ObjectId::new()),
+        user_id: ObjectId::new(),
+        bus_id: ObjectId::new(),
+        seat_number: "12".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        booking_date: booked,
+        status: "PendingPayment".to_string(),
+        passenger: None,
+        history: vec![BookingEvent::new("created", "user:1", None)],
+        terms_version: None,
+        policy_version: None,
+        risk: None,
+        country: None,
+        reference: None,
+    };
+    assert_eq!(booking.awaiting_payment_since(), booked);
+
+    let mut approved = BookingEvent::new("review_approved", "admin", None);
+    approved.at = DateTime::from_millis(1_765_000_600_000);
+    booking.history.push(approved);
+    assert_eq!(booking.awaiting_payment_since(), DateTime::from_millis(1_765_000_600_000));
+}
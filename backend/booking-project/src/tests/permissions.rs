@@ -0,0 +1,59 @@
+use crate::models::permissions::{
+    admin_permission, authorize, permissions_for, ALL_PERMISSIONS, BOOKINGS_READ, BOOKINGS_REFUND, BOOKINGS_WRITE,
+    FINANCE_READ, FINANCE_WRITE, FRAUD_MANAGE, MANIFESTS_OPEN, PERMISSIONS_MANAGE, PLATFORM_MANAGE, STAFF_INVITE,
+    SUPPORT_MANAGE, TRIPS_OPERATE,
+};
+use crate::models::Claims;
+
+#[test]
+fn each_role_gets_its_row_of_the_matrix() {
+    assert_eq!(permissions_for("admin"), ALL_PERMISSIONS.to_vec());
+    assert_eq!(permissions_for("conductor"), vec![MANIFESTS_OPEN]);
+    assert_eq!(permissions_for("agent"), vec![BOOKINGS_READ, BOOKINGS_WRITE, SUPPORT_MANAGE]);
+
+    let finance = permissions_for("finance");
+    assert!(finance.contains(&BOOKINGS_REFUND) && finance.contains(&FINANCE_WRITE));
+    assert!(!finance.contains(&BOOKINGS_WRITE));
+
+    // Operator staff run their fleet; only their admin sees the money or hires
+    let operator = permissions_for("operator");
+    assert!(operator.contains(&TRIPS_OPERATE));
+    assert!(!operator.contains(&FINANCE_READ) && !operator.contains(&STAFF_INVITE));
+    let operator_admin = permissions_for("operator_admin");
+    assert!(operator_admin.contains(&FINANCE_READ) && operator_admin.contains(&STAFF_INVITE));
+    assert!(!operator_admin.contains(&FINANCE_WRITE) && !operator_admin.contains(&PERMISSIONS_MANAGE));
+}
+
+#[test]
+fn unknown_roles_get_nothing() {
+    assert!(permissions_for("customer").is_empty());
+    assert!(permissions_for("Admin").is_empty());
+    assert!(permissions_for("").is_empty());
+}
+
+#[test]
+fn tokens_without_scopes_fall_back_to_their_role() {
+    let mut claims = Claims::new("user-1".to_string(), "finance".to_string(), 0);
+    assert!(authorize(&claims, FINANCE_WRITE));
+
+    // Embedded scopes win over the role, so a narrowed token stays narrow
+    claims.scopes = vec![FINANCE_READ.to_string()];
+    assert!(authorize(&claims, FINANCE_READ));
+    assert!(!authorize(&claims, FINANCE_WRITE));
+
+    claims.scopes.clear();
+    assert!(authorize(&claims, BOOKINGS_REFUND));
+    claims.role = "customer".to_string();
+    assert!(!authorize(&claims, BOOKINGS_READ));
+}
+
+#[test]
+fn admin_paths_map_to_the_narrowest_permission() {
+    assert_eq!(admin_permission("GET", "/bookings"), BOOKINGS_READ);
+    assert_eq!(admin_permission("POST", "/bookings/abc/reseat"), BOOKINGS_WRITE);
+    assert_eq!(admin_permission("GET", "/trips/abc/2026-03-14/manifest"), MANIFESTS_OPEN);
+    assert_eq!(admin_permission("POST", "/trips/abc/2026-03-14/status"), TRIPS_OPERATE);
+    assert_eq!(admin_permission("GET", "/analytics/revenue"), FINANCE_READ);
+    assert_eq!(admin_permission("DELETE", "/blocklist/abc"), FRAUD_MANAGE);
+    assert_eq!(admin_permission("GET", "/dead-letters"), PLATFORM_MANAGE);
+}
@@ -0,0 +1,39 @@
+use chrono::TimeZone;
+use mongodb::bson;
+
+use crate::models::price_history::{price_day, PriceHistoryResponse, RoutePrice};
+
+fn price(date: &str, price: f64) -> RoutePrice {
+    RoutePrice {
+        route: "nairobi-to-kisumu".to_string(),
+        date: date.to_string(),
+        from: "Nairobi".to_string(),
+        to: "Kisumu".to_string(),
+        price,
+        lowest: price - 100.0,
+        highest: price,
+        currency: "KES".to_string(),
+        updated_at: bson::DateTime::now(),
+    }
+}
+
+#[test]
+fn prices_count_towards_the_local_day() {
+    // 22:30 UTC is already the next day in Nairobi
+    let late = chrono::Utc.with_ymd_and_hms(2026, 12, 9, 22, 30, 0).unwrap();
+    assert_eq!(price_day(late), "2026-12-10");
+    let early = chrono::Utc.with_ymd_and_hms(2026, 12, 9, 20, 0, 0).unwrap();
+    assert_eq!(price_day(early), "2026-12-09");
+}
+
+#[test]
+fn history_names_the_route_and_keeps_day_order() {
+    assert!(PriceHistoryResponse::new(90, vec![]).is_none());
+
+    let history = PriceHistoryResponse::new(90, vec![price("2026-12-01", 1450.0), price("2026-12-20", 1900.0)]).unwrap();
+    assert_eq!(history.route, "nairobi-to-kisumu");
+    assert_eq!(history.prices.len(), 2);
+    assert_eq!(history.prices[0].date, "2026-12-01");
+    assert_eq!(history.prices[1].price, 1900.0);
+    assert_eq!(history.prices[1].lowest, 1800.0);
+}
@@ -0,0 +1,30 @@
+use mongodb::bson::{doc, Bson};
+
+use crate::db::profiling::{filter_shape, plan_summary};
+
+#[test]
+fn shapes_hide_values_but_keep_operators() {
+    let filter = doc! {
+        "bus_id": { "$in": ["a", "b"] },
+        "travel_date": { "$gte": "2026-12-01" },
+        "$or": [{ "status": "Confirmed" }, { "status": "PendingReview" }],
+    };
+    assert_eq!(
+        filter_shape(&Bson::Document(filter)).to_string(),
+        r#"{ "bus_id": { "$in": "?" }, "travel_date": { "$gte": "?" }, "$or": [{ "status": "?" }, { "status": "?" }] }"#
+    );
+}
+
+#[test]
+fn summarizes_winning_plans() {
+    let find = doc! { "queryPlanner": { "winningPlan": {
+        "stage": "FETCH",
+        "inputStage": { "stage": "IXSCAN", "indexName": "bus_id_1_travel_date_1" },
+    } } };
+    assert_eq!(plan_summary(&find).as_deref(), Some("FETCH > IXSCAN(bus_id_1_travel_date_1)"));
+
+    // Aggregations nest the planner under their first stage
+    let aggregate = doc! { "stages": [{ "$cursor": { "queryPlanner": { "winningPlan": { "stage": "COLLSCAN" } } } }] };
+    assert_eq!(plan_summary(&aggregate).as_deref(), Some("COLLSCAN"));
+    assert!(plan_summary(&doc! { "ok": 1 }).is_none());
+}
@@ -0,0 +1,30 @@
+use crate::models::rate_limit::{is_rate_limit_key, window_start, LimitOverrideRequest};
+
+fn override_request(key: &str, limit: i64, hours: i64) -> LimitOverrideRequest {
+    LimitOverrideRequest {
+        key: key.to_string(),
+        limit,
+        hours,
+        reason: "Partner fare import".to_string(),
+    }
+}
+
+#[test]
+fn requests_share_a_window_until_it_rolls_over() {
+    assert_eq!(window_start(1_700_000_019, 60), 1_699_999_980);
+    assert_eq!(window_start(1_700_000_041, 60), 1_700_000_040);
+    assert_eq!(window_start(1_700_000_039, 0), 1_700_000_039);
+}
+
+#[test]
+fn overrides_are_temporary_and_name_a_caller() {
+    assert!(is_rate_limit_key("user:65f1c0ffee0000000000abcd"));
+    assert!(is_rate_limit_key("ip:203.0.113.7"));
+    assert!(!is_rate_limit_key("anon:abc"));
+    assert!(!is_rate_limit_key("ip:"));
+
+    assert!(override_request("ip:203.0.113.7", 2000, 48).validate().is_ok());
+    assert!(override_request("ip:203.0.113.7", 0, 48).validate().is_err());
+    assert!(override_request("ip:203.0.113.7", 2000, 24 * 365).validate().is_err());
+    assert!(override_request("partner-acme", 2000, 48).validate().is_err());
+}
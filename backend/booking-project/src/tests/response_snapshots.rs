@@ -0,0 +1,206 @@
+// Wire formats the frontend is built against. A failing snapshot means a client-visible change:
+// review it with `cargo insta review` and only accept it together with the frontend change.
+use std::collections::HashMap;
+
+use actix_web::{test as http_test, web, App, HttpResponse};
+use insta::assert_json_snapshot;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::db::MongoDB;
+use crate::handlers::{account, bookings};
+use crate::middleware::auth::{jwt_secret, AdminAuth, Auth};
+use crate::models::audit::{AuditEntry, AuditEntryResponse};
+use crate::models::auth::{AnonymousSessionResponse, AuthResponse};
+use crate::models::booking::{Booking, BookingDetailResponse, BookingEvent, Passenger, ShareLinkResponse};
+use crate::tests::fixtures;
+use crate::models::bus::{Bus, BusResponse, Seat, SeatAvailabilityResponse};
+use crate::models::consent::{ConsentResponse, UserConsents};
+use crate::models::hold::HoldResponse;
+use crate::models::rate_limit::{RateLimitCounter, RateLimitUsageResponse};
+use crate::models::regulatory::{RegulatoryExport, RegulatoryExportResponse};
+use crate::models::{Claims, UserResponse};
+
+const AT: i64 = 1_765_000_000_000;
+
+fn oid(hex: &str) -> ObjectId {
+    ObjectId::parse_str(hex).unwrap()
+}
+
+fn bus() -> Bus {
+    Bus { id: Some(oid("65f1c0ffee00000000000b05")), ..fixtures::bus() }
+}
+
+fn booking() -> Booking {
+    Booking {
+        id: Some(oid("65f1c0ffee000000000b00c1")),
+        user_id: oid("65f1c0ffee0000000000a11c"),
+        bus_id: oid("65f1c0ffee00000000000b05"),
+        seat_number: "12".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        booking_date: DateTime::from_millis(AT),
+        status: "Confirmed".to_string(),
+        passenger: Some(Passenger {
+            name: "Achieng Otieno".to_string(),
+            age: "29".to_string(),
+            gender: "female".to_string(),
+            phone: Some("254712345678".to_string()),
+            id_number: None,
+            next_of_kin: None,
+        }),
+        history: vec![BookingEvent {
+            event: "created".to_string(),
+            actor: "user:65f1c0ffee0000000000a11c".to_string(),
+            note: None,
+            at: DateTime::from_millis(AT),
+        }],
+        terms_version: Some("2026-01".to_string()),
+        policy_version: None,
+        risk: None,
+        country: Some("KE".to_string()),
+        reference: None,
+    }
+}
+
+// The driver connects lazily, so handlers that answer before touching the database can run without one
+async fn offline_db() -> web::Data<MongoDB> {
+    web::Data::new(MongoDB::new("mongodb://127.0.0.1:27017", "snapshots").await.unwrap())
+}
+
+async fn error_body(app_response: actix_web::dev::ServiceResponse) -> serde_json::Value {
+    let status = app_response.status().as_u16();
+    let body: serde_json::Value = http_test::read_body_json(app_response).await;
+    serde_json::json!({ "status": status, "body": body })
+}
+
+#[test]
+fn auth_responses() {
+    assert_json_snapshot!("auth_response", AuthResponse {
+        token: "<jwt>".to_string(),
+        refresh_token: "<refresh-jwt>".to_string(),
+        user: UserResponse {
+            id: "65f1c0ffee0000000000a11c".to_string(),
+            username: "achieng".to_string(),
+            email: "achieng@example.com".to_string(),
+            role: "user".to_string(),
+        },
+    });
+    assert_json_snapshot!("anonymous_session_response", AnonymousSessionResponse {
+        token: "<jwt>".to_string(),
+        anon_id: "anon-6f3b".to_string(),
+        expires_at: "2026-12-06T08:00:00+00:00".to_string(),
+    });
+}
+
+#[test]
+fn bus_and_seat_responses() {
+    let mut listed = BusResponse::from(bus());
+    listed.seats_left = Some(17);
+    listed.experiments = HashMap::from([("price_display".to_string(), "per_seat".to_string())]);
+    assert_json_snapshot!("bus_response", listed);
+
+    assert_json_snapshot!("seat_availability_response", SeatAvailabilityResponse {
+        travel_date: "2026-12-20".to_string(),
+        seats: vec![
+            Seat { seat_number: "1".to_string(), is_available: false },
+            Seat { seat_number: "2".to_string(), is_available: true },
+        ],
+        headcount: None,
+        layout: None,
+    });
+}
+
+#[test]
+fn booking_responses() {
+    // create_booking returns the stored document as is
+    assert_json_snapshot!("created_booking", booking());
+    assert_json_snapshot!("booking_detail_response", BookingDetailResponse::from((booking(), Some(bus()))));
+    assert_json_snapshot!("hold_response", HoldResponse {
+        id: "65f1c0ffee0000000000401d".to_string(),
+        bus_id: "65f1c0ffee00000000000b05".to_string(),
+        travel_date: "2026-12-20".to_string(),
+        seat_number: "12".to_string(),
+        expires_at: "2026-12-06T08:10:00+00:00".to_string(),
+    });
+    assert_json_snapshot!("share_link_response", ShareLinkResponse {
+        token: "<jwt>".to_string(),
+        url: "/api/bookings/shared/<jwt>".to_string(),
+        expires_at: "2026-12-21T08:00:00+00:00".to_string(),
+    });
+}
+
+#[test]
+fn admin_responses() {
+    assert_json_snapshot!("audit_entry_response", AuditEntryResponse::from(AuditEntry {
+        id: Some(oid("65f1c0ffee00000000000a0d")),
+        action: "account.anonymized".to_string(),
+        actor: "system:retention".to_string(),
+        subject: "user:65f1c0ffee0000000000a11c".to_string(),
+        details: Some("2 booking(s) kept without passenger details".to_string()),
+        at: DateTime::from_millis(AT),
+    }));
+    assert_json_snapshot!("regulatory_export_response", RegulatoryExportResponse::from(RegulatoryExport {
+        id: Some(oid("65f1c0ffee00000000000e4b")),
+        jurisdiction: "KE".to_string(),
+        period_start: "2026-11-01".to_string(),
+        period_end: "2026-11-30".to_string(),
+        trips: 120,
+        passengers: 4310,
+        incidents: 2,
+        generated_by: "schedule".to_string(),
+        generated_at: DateTime::from_millis(AT),
+        content: String::new(),
+    }));
+    assert_json_snapshot!("rate_limit_usage_response", RateLimitUsageResponse::new(RateLimitCounter {
+        id: None,
+        key: "ip:203.0.113.7".to_string(),
+        window_start: 1_765_000_020,
+        count: 87,
+        expires_at: DateTime::from_millis(AT),
+    }, 120));
+    let consents = UserConsents { id: None, user_id: oid("65f1c0ffee0000000000a11c"), purposes: HashMap::new() };
+    assert_json_snapshot!("consent_response", ConsentResponse::all(&consents));
+}
+
+#[actix_web::test]
+async fn error_responses() {
+    let app = http_test::init_service(
+        App::new()
+            .app_data(offline_db().await)
+            .route("/api/bookings", web::post().to(bookings::create_booking))
+            .route("/api/bookings/{id}", web::delete().to(bookings::cancel_booking))
+            .service(web::resource("/api/account/consents").wrap(Auth).route(web::get().to(account::get_consents)))
+            .service(
+                web::scope("/api/admin")
+                    .wrap(AdminAuth)
+                    .route("/audit-log", web::get().to(HttpResponse::Ok)),
+            ),
+    ).await;
+
+    let request = http_test::TestRequest::post()
+        .uri("/api/bookings")
+        .set_json(serde_json::json!({ "bus_id": "b", "seat_number": "1", "travel_date": "2026-12-20", "passenger": null }))
+        .to_request();
+    assert_json_snapshot!("booking_unauthorized", error_body(http_test::call_service(&app, request).await).await);
+
+    let request = http_test::TestRequest::delete().uri("/api/bookings/65f1c0ffee000000000b00c1").to_request();
+    assert_json_snapshot!("cancel_unauthorized", error_body(http_test::call_service(&app, request).await).await);
+
+    let request = http_test::TestRequest::get()
+        .uri("/api/account/consents")
+        .insert_header(("Authorization", "Bearer not-a-jwt"))
+        .to_request();
+    assert_json_snapshot!("auth_middleware_rejected", error_body(http_test::call_service(&app, request).await).await);
+
+    let request = http_test::TestRequest::get().uri("/api/admin/audit-log").to_request();
+    assert_json_snapshot!("admin_unauthorized", error_body(http_test::call_service(&app, request).await).await);
+
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    let customer = Claims::new(ObjectId::new().to_hex(), "customer".to_string(), exp);
+    let token = encode(&Header::default(), &customer, &EncodingKey::from_secret(jwt_secret().as_ref())).unwrap();
+    let request = http_test::TestRequest::get()
+        .uri("/api/admin/audit-log")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    assert_json_snapshot!("admin_forbidden", error_body(http_test::call_service(&app, request).await).await);
+}
@@ -0,0 +1,50 @@
+use mongodb::bson::oid::ObjectId;
+
+use crate::models::schedule::{bus_runs_on, ScheduleRequest};
+
+fn weekdays_in_december(weekdays: Vec<u32>) -> ScheduleRequest {
+    ScheduleRequest {
+        weekdays,
+        valid_from: Some("2026-12-01".to_string()),
+        valid_until: Some("2026-12-31".to_string()),
+        extra_dates: Vec::new(),
+        skip_dates: Vec::new(),
+    }
+}
+
+#[test]
+fn schedules_pick_the_days_a_bus_runs() {
+    // Monday to Friday, except Christmas Day, plus one Sunday special
+    let mut request = weekdays_in_december(vec![5, 1, 2, 3, 4, 1]);
+    request.skip_dates = vec!["2026-12-25".to_string()];
+    request.extra_dates = vec!["2026-12-20".to_string()];
+    assert!(request.validate().is_ok());
+    let schedule = request.into_schedule(ObjectId::new());
+    assert_eq!(schedule.weekdays, vec![1, 2, 3, 4, 5]);
+
+    assert!(schedule.runs_on("2026-12-21"));
+    assert!(!schedule.runs_on("2026-12-26"));
+    assert!(!schedule.runs_on("2026-12-25"));
+    assert!(schedule.runs_on("2026-12-20"));
+    assert!(!schedule.runs_on("2027-01-04"));
+    assert!(!schedule.runs_on("20 December"));
+
+    // No schedule means the bus runs every day, as before schedules existed
+    assert!(bus_runs_on(None, "2026-12-26"));
+    assert!(!bus_runs_on(Some(&schedule), "2026-12-26"));
+}
+
+#[test]
+fn schedule_requests_are_checked() {
+    assert!(weekdays_in_december(Vec::new()).validate().is_ok());
+    assert!(weekdays_in_december(vec![0]).validate().is_err());
+    assert!(weekdays_in_december(vec![8]).validate().is_err());
+
+    let mut backwards = weekdays_in_december(vec![1]);
+    backwards.valid_until = Some("2026-11-30".to_string());
+    assert!(backwards.validate().is_err());
+
+    let mut bad_date = weekdays_in_december(vec![1]);
+    bad_date.skip_dates = vec!["25/12/2026".to_string()];
+    assert!(bad_date.validate().is_err());
+}
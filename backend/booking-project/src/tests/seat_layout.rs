@@ -0,0 +1,116 @@
+use serde_json::json;
+
+use crate::models::bus::{Bus, BusRequest, Seat, SeatAvailabilityResponse};
+use crate::tests::fixtures::{self, route};
+use crate::models::seat_layout::{LayoutSeat, SeatLayout};
+
+fn seat(label: &str, row: u32, column: u32, class: &str) -> LayoutSeat {
+    LayoutSeat { label: label.to_string(), row, column, class: class.to_string() }
+}
+
+// A 2+1 VIP cabin: two seats left of the aisle, one right, and the door where 3C would be
+fn vip_layout() -> SeatLayout {
+    SeatLayout {
+        rows: 3,
+        columns: 3,
+        aisles: vec![2],
+        seats: vec![
+            seat("3A", 3, 1, "standard"),
+            seat("3B", 3, 2, "standard"),
+            seat("1A", 1, 1, "vip"),
+            seat("1B", 1, 2, "vip"),
+            seat("1C", 1, 3, "vip"),
+            seat("2A", 2, 1, "standard"),
+            seat("2B", 2, 2, "standard"),
+            seat("2C", 2, 3, "standard"),
+        ],
+    }
+}
+
+fn bus(layout: Option<SeatLayout>) -> Bus {
+    Bus {
+        bus_number: "Mash Poa - KDC 220X".to_string(),
+        bus_type: "VIP".to_string(),
+        total_seats: layout.as_ref().map_or(4, |l| l.seats.len() as i32),
+        layout,
+        route: route("Nairobi", "Mombasa", "09:00 PM", "06:30 AM", 2500.0),
+        ..fixtures::bus()
+    }
+}
+
+#[test]
+fn laid_out_buses_book_seats_by_label() {
+    let bus = bus(Some(vip_layout()));
+    assert_eq!(bus.seat_labels(), ["1A", "1B", "1C", "2A", "2B", "2C", "3A", "3B"]);
+    assert!(bus.has_seat("2C"));
+    assert!(!bus.has_seat("3C"));
+    assert!(!bus.has_seat("1"));
+
+    let numbered = self::bus(None);
+    assert_eq!(numbered.seat_labels(), ["1", "2", "3", "4"]);
+    assert!(numbered.has_seat("4"));
+    assert!(!numbered.has_seat("1A"));
+}
+
+#[test]
+fn layouts_need_distinct_seats_inside_the_grid() {
+    assert!(vip_layout().validate().is_ok());
+
+    let mut twice = vip_layout();
+    twice.seats.push(seat("1A", 3, 3, "standard"));
+    assert!(twice.validate().is_err());
+
+    let mut stacked = vip_layout();
+    stacked.seats.push(seat("3C", 3, 2, "standard"));
+    assert!(stacked.validate().is_err());
+
+    let mut outside = vip_layout();
+    outside.seats.push(seat("4A", 4, 1, "standard"));
+    assert!(outside.validate().is_err());
+
+    let mut first_class = vip_layout();
+    first_class.seats[0].class = "first".to_string();
+    assert!(first_class.validate().is_err());
+
+    let mut wall = vip_layout();
+    wall.aisles = vec![3];
+    assert!(wall.validate().is_err());
+
+    let mut open = vip_layout();
+    open.seats[0].label = "open".to_string();
+    assert!(open.validate().is_err());
+}
+
+#[test]
+fn a_bus_counts_the_seats_in_its_layout() {
+    let request = |total_seats: i32| BusRequest {
+        bus_number: "Mash Poa - KDC 220X".to_string(),
+        bus_type: "VIP".to_string(),
+        total_seats,
+        route: bus(None).route,
+        layout: Some(vip_layout()),
+    };
+    assert!(request(8).validate().is_ok());
+    assert!(request(44).validate().is_err());
+}
+
+#[test]
+fn seat_maps_carry_the_layout_and_default_class() {
+    let layout: SeatLayout = serde_json::from_value(json!({
+        "rows": 1,
+        "columns": 2,
+        "seats": [{ "label": "1A", "row": 1, "column": 1 }, { "label": "1B", "row": 1, "column": 2, "class": "vip" }],
+    })).unwrap();
+    assert_eq!(layout.seats[0].class, "standard");
+    assert!(layout.aisles.is_empty());
+
+    let response = SeatAvailabilityResponse {
+        travel_date: "2026-12-20".to_string(),
+        seats: vec![Seat { seat_number: "1A".to_string(), is_available: true }],
+        headcount: None,
+        layout: Some(layout),
+    };
+    assert_eq!(serde_json::to_value(response).unwrap()["layout"]["seats"][1], json!({
+        "label": "1B", "row": 1, "column": 2, "class": "vip",
+    }));
+}
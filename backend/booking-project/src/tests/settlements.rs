@@ -0,0 +1,89 @@
+use mongodb::bson::{self, oid::ObjectId};
+
+use crate::models::commission::CommissionRule;
+use crate::models::settlement::{BookingSplit, Settlement};
+
+fn split(fare: f64, commission: f64) -> BookingSplit {
+    BookingSplit {
+        id: Some(ObjectId::new()),
+        booking_id: ObjectId::new(),
+        bus_id: ObjectId::new(),
+        operator: "Easy Coach".to_string(),
+        travel_date: "2026-03-14".to_string(),
+        fare,
+        commission,
+        operator_share: fare - commission,
+        commission_rule_id: None,
+        reversal: false,
+        settlement_id: None,
+        recorded_at: bson::DateTime::from_millis(1_773_446_400_000),
+    }
+}
+
+fn reversal_of(split: &BookingSplit) -> BookingSplit {
+    BookingSplit {
+        fare: -split.fare,
+        commission: -split.commission,
+        operator_share: -split.operator_share,
+        reversal: true,
+        ..split.clone()
+    }
+}
+
+fn settlement() -> Settlement {
+    Settlement {
+        id: None,
+        operator: "Easy Coach".to_string(),
+        period_start: "2026-03-09".to_string(),
+        period_end: "2026-03-15".to_string(),
+        booking_count: 0,
+        gross: 0.0,
+        commission: 0.0,
+        net_payout: 0.0,
+        status: "Pending".to_string(),
+        payout_reference: None,
+        created_at: bson::DateTime::from_millis(1_773_619_200_000),
+        paid_at: None,
+    }
+}
+
+#[test]
+fn settlements_total_their_splits() {
+    let mut s = settlement();
+    s.set_totals(&[split(1450.0, 145.0), split(1800.0, 180.0)]);
+    assert_eq!(s.booking_count, 2);
+    assert_eq!(s.gross, 3250.0);
+    assert_eq!(s.commission, 325.0);
+    assert_eq!(s.net_payout, 2925.0);
+}
+
+#[test]
+fn reversals_net_out_an_earlier_booking() {
+    // The first booking was paid out last period and cancelled since
+    let cancelled = split(1450.0, 145.0);
+    let mut s = settlement();
+    s.set_totals(&[reversal_of(&cancelled), split(1800.0, 180.0)]);
+    assert_eq!(s.booking_count, 0);
+    assert_eq!(s.gross, 350.0);
+    assert_eq!(s.commission, 35.0);
+    assert_eq!(s.net_payout, 315.0);
+}
+
+#[test]
+fn totals_are_rounded_to_cents() {
+    let mut s = settlement();
+    s.set_totals(&[split(0.1, 0.01), split(0.2, 0.02)]);
+    assert_eq!(s.gross, 0.3);
+    assert_eq!(s.commission, 0.03);
+    assert_eq!(s.net_payout, 0.27);
+}
+
+#[test]
+fn commission_never_exceeds_the_fare() {
+    let percent = CommissionRule::builtin("Easy Coach");
+    assert_eq!(percent.commission_for(1450.0), 145.0);
+
+    let flat = CommissionRule { kind: "flat".to_string(), value: 200.0, ..percent };
+    assert_eq!(flat.commission_for(1450.0), 200.0);
+    assert_eq!(flat.commission_for(150.0), 150.0);
+}
@@ -0,0 +1,33 @@
+use crate::db::email_change::{sign_change_link, CONFIRM_SCOPE};
+use crate::db::sharing::{share_link_expiry, sign_share_token, verify_share_token};
+use crate::error::AppError;
+use crate::tests::fixtures;
+
+#[test]
+fn share_links_last_until_the_end_of_the_travel_day() {
+    let day_run = fixtures::route("Nairobi", "Kisumu", "08:15 AM", "04:30 PM", 1450.0);
+    assert_eq!(share_link_expiry(&day_run, "2026-03-14").unwrap().to_rfc3339(), "2026-03-15T00:00:00+03:00");
+
+    // Overnight trips stay shareable until they arrive
+    let overnight = fixtures::route("Nairobi", "Mombasa", "09:00 PM", "06:00 AM", 1800.0);
+    assert_eq!(share_link_expiry(&overnight, "2026-03-14").unwrap().to_rfc3339(), "2026-03-15T06:00:00+03:00");
+
+    assert!(share_link_expiry(&day_run, "14/03/2026").is_err());
+}
+
+#[test]
+fn share_tokens_open_only_their_booking_until_they_expire() {
+    let in_a_day = (chrono::Utc::now() + chrono::Duration::days(1)).timestamp();
+    let token = sign_share_token("booking-1", in_a_day).unwrap();
+    assert_eq!(verify_share_token(&token).unwrap(), "booking-1");
+
+    let an_hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).timestamp();
+    let expired = sign_share_token("booking-1", an_hour_ago).unwrap();
+    assert!(matches!(verify_share_token(&expired), Err(AppError::Unauthorized(_))));
+}
+
+#[test]
+fn other_signed_links_are_not_share_links() {
+    let confirm = sign_change_link("booking-1", CONFIRM_SCOPE, chrono::Duration::hours(1)).unwrap();
+    assert!(matches!(verify_share_token(&confirm), Err(AppError::Unauthorized(_))));
+}
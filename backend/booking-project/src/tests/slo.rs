@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::config::{SloConfig, SloObjective};
+use crate::metrics::slo::SloTracker;
+
+fn tracker() -> SloTracker {
+    SloTracker::new(SloConfig {
+        objectives: vec![SloObjective::parse("seats GET /api/buses/{id}/seats p99<300ms").unwrap()],
+        burn_rate_alert: 14.4,
+        budget_window_minutes: 24 * 60,
+        check_interval_secs: 60,
+    })
+}
+
+#[test]
+fn parses_objectives() {
+    let objective = SloObjective::parse("search get /api/buses p95<500ms").unwrap();
+    assert_eq!((objective.method.as_str(), objective.threshold_ms), ("GET", 500));
+    assert!((objective.target - 0.95).abs() < 1e-9);
+    assert!(SloObjective::parse("search GET /api/buses 500ms").is_none());
+    assert!(SloObjective::parse("search GET api/buses p95<500ms").is_none());
+}
+
+#[test]
+fn burn_rate_is_slow_ratio_over_budget() {
+    let tracker = tracker();
+    for i in 0..100 {
+        let elapsed = Duration::from_millis(if i < 5 { 900 } else { 50 });
+        tracker.record_at("GET", "/api/buses/{id}/seats", elapsed, 1000);
+    }
+    tracker.record_at("POST", "/api/buses/{id}/seats", Duration::from_secs(5), 1000);
+
+    let reading = &tracker.readings_at(1000)[0];
+    // 5% slow against a 1% budget
+    assert!((reading.short_burn.unwrap() - 5.0).abs() < 1e-9);
+    assert!((reading.budget_remaining.unwrap() + 4.0).abs() < 1e-9);
+
+    // The short window forgets the spike before the long one does
+    let later = &tracker.readings_at(1010)[0];
+    assert!(later.short_burn.is_none());
+    assert!(later.long_burn.is_some());
+}
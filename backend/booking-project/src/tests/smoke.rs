@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::smoke::{bus_with_seats, document_id, first_available_seat, report, Outcome, StepResult};
+
+#[test]
+fn journey_reads_ids_and_seats_from_api_responses() {
+    assert_eq!(document_id(&json!({ "_id": { "$oid": "65f1c0ffee0000000000abcd" } })).as_deref(), Some("65f1c0ffee0000000000abcd"));
+    assert_eq!(document_id(&json!({ "id": "pm_1" })).as_deref(), Some("pm_1"));
+    assert_eq!(document_id(&json!({ "status": "Confirmed" })), None);
+
+    let buses = json!([{ "id": "full", "seats_left": 0 }, { "id": "open", "seats_left": 3 }]);
+    assert_eq!(bus_with_seats(&buses).as_deref(), Some("open"));
+
+    let seat_map = json!({ "seats": [
+        { "seat_number": "1", "is_available": false },
+        { "seat_number": "2", "is_available": true },
+    ] });
+    assert_eq!(first_available_seat(&seat_map).as_deref(), Some("2"));
+}
+
+#[test]
+fn any_failed_step_fails_the_report() {
+    let step = |name, outcome| StepResult { name, outcome, elapsed: Duration::from_millis(40), detail: String::new() };
+    let passing = [step("register", Outcome::Passed), step("pay", Outcome::Skipped)];
+    assert!(report(&passing).ends_with("Smoke test passed in 80ms\n"));
+
+    let failing = [step("register", Outcome::Passed), step("book", Outcome::Failed)];
+    let out = report(&failing);
+    assert!(out.contains("book       FAILED"));
+    assert!(out.ends_with("1 step(s) failed\n"));
+}
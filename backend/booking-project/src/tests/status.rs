@@ -0,0 +1,30 @@
+use crate::models::status::{build_version, DependencyCheck, HealthResponse, ReadinessResponse};
+use crate::openapi::Audience;
+
+fn dependency(name: &'static str, status: &'static str) -> DependencyCheck {
+    DependencyCheck { name, status, latency_ms: 3 }
+}
+
+#[test]
+fn liveness_reports_the_build_and_uptime() {
+    let health = serde_json::to_value(HealthResponse::now()).unwrap();
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["version"], build_version());
+    assert!(health["uptime_secs"].is_u64());
+}
+
+#[test]
+fn readiness_needs_every_dependency_up() {
+    assert!(ReadinessResponse::new(vec![dependency("mongodb", "up")]).is_ready());
+    let down = ReadinessResponse::new(vec![dependency("mongodb", "down")]);
+    assert!(!down.is_ready());
+    let body = serde_json::to_value(&down).unwrap();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["dependencies"][0]["name"], "mongodb");
+}
+
+#[test]
+fn probes_are_left_out_of_the_audience_specs() {
+    assert_eq!(Audience::of("/health"), None);
+    assert_eq!(Audience::of("/ready"), None);
+}
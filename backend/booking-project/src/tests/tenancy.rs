@@ -0,0 +1,222 @@
+// An operator admin must never see another operator's bookings: the guard pins the tenant from the
+// token, and every scoped bookings filter is AND-ed with the operator's own buses.
+use actix_web::test::{call_and_read_body, call_service, init_service, TestRequest};
+use actix_web::{http::StatusCode, web, App, HttpResponse};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+
+use crate::middleware::auth::{jwt_secret, AdminAuth};
+use crate::middleware::tenancy::TenancyGuard;
+use crate::models::bus::{Bus, BusRequest};
+use crate::models::tenancy::{is_tenant_aware, Tenant};
+use crate::models::Claims;
+use crate::tests::fixtures;
+
+fn bus(bus_number: &str) -> Bus {
+    Bus { bus_number: bus_number.to_string(), ..fixtures::bus() }
+}
+
+fn claims(role: &str, operator: Option<&str>) -> Claims {
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    Claims::new(ObjectId::new().to_hex(), role.to_string(), exp).with_operator(operator.map(str::to_string))
+}
+
+fn token(claims: &Claims) -> String {
+    let secret = jwt_secret();
+    encode(&Header::default(), claims, &EncodingKey::from_secret(secret.as_ref())).unwrap()
+}
+
+// Just enough of MongoDB's matching for the filters the tenancy layer builds: `$and`, `$in` and equality
+fn matches(filter: &Document, record: &Document) -> bool {
+    filter.iter().all(|(key, condition)| match (key.as_str(), condition) {
+        ("$and", Bson::Array(clauses)) => clauses.iter().all(|c| matches(c.as_document().unwrap(), record)),
+        (field, Bson::Document(op)) if op.contains_key("$in") => {
+            let allowed = op.get_array("$in").unwrap();
+            record.get(field).is_some_and(|v| allowed.contains(v))
+        }
+        (field, expected) => record.get(field) == Some(expected),
+    })
+}
+
+#[test]
+fn operator_roles_are_pinned_to_their_operator() {
+    assert_eq!(Tenant::from_claims(&claims("operator_admin", Some("Easy Coach"))), Ok(Tenant::Operator("Easy Coach".to_string())));
+    assert_eq!(Tenant::from_claims(&claims("conductor", Some("Easy Coach"))), Ok(Tenant::Operator("Easy Coach".to_string())));
+    assert_eq!(Tenant::from_claims(&claims("operator", Some("Easy Coach"))), Ok(Tenant::Operator("Easy Coach".to_string())));
+    assert!(Tenant::from_claims(&claims("operator", None)).is_err());
+    assert!(Tenant::from_claims(&claims("operator_admin", None)).is_err());
+    assert!(Tenant::from_claims(&claims("operator_admin", Some("  "))).is_err());
+    assert_eq!(Tenant::from_claims(&claims("finance", Some("Easy Coach"))), Ok(Tenant::Operator("Easy Coach".to_string())));
+    assert_eq!(Tenant::from_claims(&claims("finance", None)), Ok(Tenant::Platform));
+    assert_eq!(Tenant::from_claims(&claims("admin", Some("Easy Coach"))), Ok(Tenant::Platform));
+    assert_eq!(Tenant::from_claims(&claims("admin", None)), Ok(Tenant::Platform));
+}
+
+#[test]
+fn scoped_booking_filters_never_match_another_operators_bookings() {
+    let (own, other) = (bus("Easy Coach - KCH 123A"), bus("Guardian Angel - KDB 456C"));
+    let tenant = Tenant::Operator("Easy Coach".to_string());
+    let own_ids = vec![own.id.unwrap()];
+
+    let own_booking = doc! { "bus_id": own.id.unwrap(), "status": "Confirmed" };
+    let other_booking = doc! { "bus_id": other.id.unwrap(), "status": "Confirmed" };
+
+    let unfiltered = tenant.scope_bookings(doc! {}, &own_ids);
+    assert!(matches(&unfiltered, &own_booking));
+    assert!(!matches(&unfiltered, &other_booking));
+
+    // Asking for the other operator's bus explicitly still comes back empty
+    let probing = tenant.scope_bookings(doc! { "bus_id": other.id.unwrap() }, &own_ids);
+    assert!(!matches(&probing, &other_booking));
+    assert!(!matches(&probing, &own_booking));
+
+    // An operator with no buses sees nothing at all
+    let empty = tenant.scope_bookings(doc! { "status": "Confirmed" }, &[]);
+    assert!(!matches(&empty, &own_booking));
+    assert!(!matches(&empty, &other_booking));
+
+    let platform = Tenant::Platform.scope_bookings(doc! { "status": "Confirmed" }, &[]);
+    assert!(matches(&platform, &other_booking));
+}
+
+#[test]
+fn ownership_and_report_filters_follow_the_tenant() {
+    let tenant = Tenant::Operator("Easy Coach".to_string());
+    assert!(tenant.owns_bus(&bus("Easy Coach - KCH 123A")));
+    assert!(tenant.owns_bus(&bus("easy coach - KCH 999Z")));
+    assert!(!tenant.owns_bus(&bus("Guardian Angel - KDB 456C")));
+    assert_eq!(tenant.operator_filter(Some("Guardian Angel")), Some("Easy Coach"));
+    assert_eq!(tenant.operator_filter(None), Some("Easy Coach"));
+    assert_eq!(Tenant::Platform.operator_filter(Some("Guardian Angel")), Some("Guardian Angel"));
+}
+
+#[test]
+fn only_scoped_admin_paths_are_tenant_aware() {
+    for (method, path) in [
+        ("GET", "/bookings"),
+        ("GET", "/bookings/review"),
+        ("POST", "/bookings/abc/reseat"),
+        ("PUT", "/trips/abc/2026-12-20/status"),
+        ("PUT", "/buses/abc/policy"),
+        ("POST", "/buses"),
+        ("DELETE", "/buses/abc"),
+        ("GET", "/settlements"),
+        ("GET", "/operators/Easy Coach/statement"),
+        ("GET", "/analytics/revenue"),
+        ("DELETE", "/invitations/abc"),
+    ] {
+        assert!(is_tenant_aware(method, path), "{} {}", method, path);
+    }
+    for (method, path) in [
+        ("POST", "/settlements"),
+        ("GET", "/ledger"),
+        ("GET", "/incidents"),
+        ("GET", "/blocklist"),
+        ("GET", "/analytics/punctuality"),
+        ("GET", "/payment-methods/audit"),
+        ("GET", "/users/abc/permissions"),
+    ] {
+        assert!(!is_tenant_aware(method, path), "{} {}", method, path);
+    }
+}
+
+async fn echo_tenant(tenant: web::ReqData<Tenant>) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{:?}", tenant.into_inner()))
+}
+
+#[actix_web::test]
+async fn guard_injects_the_tenant_and_refuses_unscoped_routes() {
+    let app = init_service(
+        App::new().service(
+            web::scope("/api/admin")
+                .wrap(TenancyGuard)
+                .wrap(AdminAuth)
+                .route("/bookings", web::get().to(echo_tenant))
+                .route("/ledger", web::get().to(echo_tenant)),
+        ),
+    )
+    .await;
+
+    let get = |path: &str, claims: &Claims| {
+        TestRequest::get()
+            .uri(path)
+            .insert_header(("Authorization", format!("Bearer {}", token(claims))))
+            .to_request()
+    };
+
+    let operator = claims("operator_admin", Some("Easy Coach"));
+    let body = call_and_read_body(&app, get("/api/admin/bookings", &operator)).await;
+    assert_eq!(body, r#"Operator("Easy Coach")"#);
+
+    let refused = call_service(&app, get("/api/admin/ledger", &operator)).await;
+    assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+
+    let unassigned = call_service(&app, get("/api/admin/bookings", &claims("operator_admin", None))).await;
+    assert_eq!(unassigned.status(), StatusCode::FORBIDDEN);
+
+    let body = call_and_read_body(&app, get("/api/admin/ledger", &claims("admin", None))).await;
+    assert_eq!(body, "Platform");
+}
+
+#[test]
+fn new_buses_need_a_usable_route() {
+    let request = |bus_number: &str, total_seats: i32| BusRequest {
+        bus_number: bus_number.to_string(),
+        bus_type: "Standard".to_string(),
+        total_seats,
+        route: bus(bus_number).route,
+        layout: None,
+    };
+    assert!(request("Easy Coach - KCH 123A", 44).validate().is_ok());
+    assert!(request("KCH 123A", 44).validate().is_err());
+    assert!(request("Easy Coach - KCH 123A", 0).validate().is_err());
+
+    let mut backwards = request("Easy Coach - KCH 123A", 44);
+    backwards.route.to = "nairobi".to_string();
+    assert!(backwards.validate().is_err());
+    let mut bad_time = request("Easy Coach - KCH 123A", 44);
+    bad_time.route.departure_time = "25:00".to_string();
+    assert!(bad_time.validate().is_err());
+}
+
+#[actix_web::test]
+async fn operators_run_their_fleet_but_not_its_finances() {
+    let app = init_service(
+        App::new().service(
+            web::scope("/api/admin")
+                .wrap(TenancyGuard)
+                .wrap(AdminAuth)
+                .route("/buses", web::post().to(echo_tenant))
+                .route("/trips/{bus_id}/{date}/status", web::put().to(echo_tenant))
+                .route("/settlements", web::get().to(echo_tenant))
+                .route("/invitations", web::post().to(echo_tenant)),
+        ),
+    )
+    .await;
+
+    let request = |method: &str, path: &str, claims: &Claims| {
+        let request = match method {
+            "POST" => TestRequest::post(),
+            "PUT" => TestRequest::put(),
+            _ => TestRequest::get(),
+        };
+        request
+            .uri(path)
+            .insert_header(("Authorization", format!("Bearer {}", token(claims))))
+            .to_request()
+    };
+
+    let operator = claims("operator", Some("Easy Coach"));
+    let body = call_and_read_body(&app, request("POST", "/api/admin/buses", &operator)).await;
+    assert_eq!(body, r#"Operator("Easy Coach")"#);
+    let status = call_service(&app, request("PUT", "/api/admin/trips/abc/2026-12-20/status", &operator)).await;
+    assert_eq!(status.status(), StatusCode::OK);
+
+    for (method, path) in [("GET", "/api/admin/settlements"), ("POST", "/api/admin/invitations")] {
+        let refused = call_service(&app, request(method, path, &operator)).await;
+        assert_eq!(refused.status(), StatusCode::FORBIDDEN, "{} {}", method, path);
+    }
+
+    let customer = call_service(&app, request("POST", "/api/admin/buses", &claims("user", None))).await;
+    assert_eq!(customer.status(), StatusCode::FORBIDDEN);
+}
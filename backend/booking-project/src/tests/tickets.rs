@@ -0,0 +1,69 @@
+use crate::models::receipt::BookingReceipt;
+use crate::models::ticket::{qr_modules, sign_ticket, verify_ticket, ETicket, TicketClaims};
+
+const KEY: &str = "ticket-test-key";
+
+fn claims(seat: &str) -> TicketClaims {
+    TicketClaims {
+        booking_id: "65f1c0ffee0000000000b001".to_string(),
+        bus_id: "65f1c0ffee0000000000b0b5".to_string(),
+        travel_date: "2026-11-20".to_string(),
+        seat_number: seat.to_string(),
+    }
+}
+
+fn receipt() -> BookingReceipt {
+    BookingReceipt {
+        reference: "BB-7K2F9Q".to_string(),
+        passenger: "Wanjiru Kamau".to_string(),
+        seat_number: "14".to_string(),
+        bus_number: "Mash Poa - KDC 220X".to_string(),
+        bus_type: "Executive".to_string(),
+        from: "Nairobi".to_string(),
+        to: "Mombasa".to_string(),
+        travel_date: "2026-11-20".to_string(),
+        departure: "08:30".to_string(),
+        fare: 1500.0,
+        status: "Confirmed".to_string(),
+        booked_on: "2026-11-01".to_string(),
+        language: "en".to_string(),
+    }
+}
+
+#[test]
+fn signed_tickets_verify_back_to_their_claims() {
+    let payload = sign_ticket(&claims("14"), KEY);
+    assert!(payload.starts_with("BBT1.65f1c0ffee0000000000b001."));
+    assert_eq!(verify_ticket(&payload, KEY), Ok(claims("14")));
+    // Seat labels keep any dots they have
+    assert_eq!(verify_ticket(&sign_ticket(&claims("U.2"), KEY), KEY), Ok(claims("U.2")));
+}
+
+#[test]
+fn edited_or_foreign_tickets_are_refused() {
+    let payload = sign_ticket(&claims("14"), KEY);
+    assert!(verify_ticket(&payload.replacen(".14.", ".15.", 1), KEY).is_err());
+    assert!(verify_ticket(&payload, "another-key").is_err());
+    assert!(verify_ticket("BBT1.65f1c0ffee0000000000b001", KEY).is_err());
+    assert!(verify_ticket("", KEY).is_err());
+}
+
+#[test]
+fn ticket_qr_codes_are_square() {
+    let modules = qr_modules(&sign_ticket(&claims("14"), KEY)).unwrap();
+    assert!(modules.len() >= 21);
+    assert!(modules.iter().all(|row| row.len() == modules.len()));
+    // The finder pattern in the top-left corner starts with a dark run of seven
+    assert!(modules[0][..7].iter().all(|dark| *dark));
+}
+
+#[test]
+fn e_tickets_render_as_a_single_page_pdf() {
+    let ticket = ETicket { receipt: receipt(), qr_payload: sign_ticket(&claims("14"), KEY) };
+    let pdf = String::from_utf8_lossy(&ticket.to_pdf().unwrap()).to_string();
+    assert!(pdf.starts_with("%PDF-1.4"));
+    assert!(pdf.contains("/Count 1"));
+    assert!(pdf.contains("(E-TICKET - BB-7K2F9Q) Tj"));
+    assert!(pdf.contains(" re\n"));
+    assert!(pdf.ends_with("%%EOF\n"));
+}
@@ -0,0 +1,96 @@
+use chrono::Duration;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::oid::ObjectId;
+
+use crate::config::TokenConfig;
+use crate::middleware::auth::decode_claims;
+use crate::models::user::CLAIMS_VERSION;
+use crate::models::TokenType;
+use crate::tokens::{refresh_token_hash, TokenService};
+
+const SECRET: &str = "token-test-secret";
+
+fn service(issuer: Option<&str>, embed_scopes: bool) -> TokenService {
+    TokenService::new(TokenConfig {
+        secret: SECRET.to_string(),
+        issuer: issuer.map(str::to_string),
+        access_ttl_hours: 1,
+        refresh_ttl_days: 1,
+        anonymous_ttl_days: 1,
+        embed_scopes,
+    })
+}
+
+fn sign(claims: &impl serde::Serialize) -> String {
+    encode(&Header::default(), claims, &EncodingKey::from_secret(SECRET.as_ref())).unwrap()
+}
+
+#[test]
+fn tokens_from_before_versioning_still_sign_people_in() {
+    let exp = (chrono::Utc::now() + Duration::hours(1)).timestamp();
+    let legacy = sign(&serde_json::json!({ "sub": "65f1c0ffee0000000000a11c", "role": "user", "exp": exp }));
+    let claims = decode_claims(&legacy, SECRET).unwrap();
+    assert_eq!(claims.ver, 1);
+    assert_eq!(claims.typ, TokenType::Access);
+    assert!(claims.scopes.is_empty());
+    assert_eq!(claims.operator, None);
+}
+
+#[test]
+fn access_tokens_carry_the_configured_claims() {
+    let user_id = ObjectId::new().to_hex();
+    let token = service(Some("bus-book"), true).issue_access(&user_id, "operator_admin", Some("Easy Coach".to_string())).unwrap();
+    let claims = decode_claims(&token, SECRET).unwrap();
+    assert_eq!(claims.ver, CLAIMS_VERSION);
+    assert_eq!(claims.sub, user_id);
+    assert_eq!(claims.typ, TokenType::Access);
+    assert_eq!(claims.operator.as_deref(), Some("Easy Coach"));
+    assert_eq!(claims.iss.as_deref(), Some("bus-book"));
+    assert!(!claims.scopes.is_empty());
+
+    let token = service(None, false).issue_access(&user_id, "operator_admin", None).unwrap();
+    let claims = decode_claims(&token, SECRET).unwrap();
+    assert!(claims.scopes.is_empty());
+    assert_eq!(claims.iss, None);
+}
+
+#[test]
+fn impersonation_tokens_name_the_admin_behind_them() {
+    let mut impersonation = service(None, true)
+        .claims("65f1c0ffee0000000000a11c", "user", None, TokenType::Impersonation, Duration::minutes(15));
+    impersonation.act = Some("user:65f1c0ffee00000000000adm".to_string());
+    let token = encode(&Header::default(), &impersonation, &EncodingKey::from_secret(SECRET.as_ref())).unwrap();
+    let claims = decode_claims(&token, SECRET).unwrap();
+    assert_eq!(claims.typ, TokenType::Impersonation);
+    assert_eq!(claims.act.as_deref(), Some("user:65f1c0ffee00000000000adm"));
+}
+
+#[test]
+fn refresh_and_future_tokens_do_not_authorize_requests() {
+    let tokens = service(None, true);
+    let mut refresh = tokens.claims("65f1c0ffee0000000000a11c", "user", None, TokenType::Refresh, Duration::hours(1));
+    assert!(decode_claims(&sign(&refresh), SECRET).is_err());
+
+    refresh.typ = TokenType::Access;
+    refresh.ver = CLAIMS_VERSION + 1;
+    assert!(decode_claims(&sign(&refresh), SECRET).is_err());
+}
+
+#[test]
+fn refresh_tokens_only_work_at_the_refresh_endpoint() {
+    let tokens = service(None, true);
+    let (refresh, claims) = tokens.issue_refresh("65f1c0ffee0000000000a11c").unwrap();
+    assert_eq!(claims.typ, TokenType::Refresh);
+    assert!(claims.scopes.is_empty());
+    assert!(decode_claims(&refresh, SECRET).is_err());
+    assert_eq!(tokens.decode_refresh(&refresh).unwrap().jti, claims.jti);
+
+    let access = tokens.issue_access("65f1c0ffee0000000000a11c", "user", None).unwrap();
+    assert!(tokens.decode_refresh(&access).is_err());
+
+    // Stored by hash; every token issued hashes differently
+    let (other, _) = tokens.issue_refresh("65f1c0ffee0000000000a11c").unwrap();
+    assert_eq!(refresh_token_hash(&refresh), refresh_token_hash(&refresh));
+    assert_ne!(refresh_token_hash(&refresh), refresh_token_hash(&other));
+    assert!(!refresh_token_hash(&refresh).contains(&refresh));
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::trip_history::{rating_prompt, RateTripRequest, RATING_WINDOW_DAYS};
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn rating_prompt_stays_open_for_the_window() {
+    let ended_at = at("2026-12-20T16:30:00+03:00");
+    assert_eq!(rating_prompt(false, ended_at, ended_at + Duration::hours(1)), "rate");
+    assert_eq!(rating_prompt(false, ended_at, ended_at + Duration::days(RATING_WINDOW_DAYS)), "rate");
+    assert_eq!(rating_prompt(false, ended_at, ended_at + Duration::days(RATING_WINDOW_DAYS + 1)), "expired");
+    // Once rated it never asks again, however late
+    assert_eq!(rating_prompt(true, ended_at, ended_at + Duration::days(60)), "rated");
+}
+
+#[test]
+fn ratings_are_one_to_five_with_a_short_comment() {
+    let rating = |score: i32, comment: Option<&str>| RateTripRequest { score, comment: comment.map(str::to_string) };
+    assert!(rating(5, Some("Left on time, clean bus")).validate().is_ok());
+    assert!(rating(1, None).validate().is_ok());
+    assert!(rating(0, None).validate().is_err());
+    assert!(rating(6, None).validate().is_err());
+    assert!(rating(4, Some(&"a".repeat(501))).validate().is_err());
+}
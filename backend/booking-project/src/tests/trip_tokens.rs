@@ -0,0 +1,50 @@
+use jsonwebtoken::{encode, EncodingKey, Header};
+
+use crate::db::manifest::verify_trip_token;
+use crate::error::AppError;
+use crate::middleware::auth::jwt_secret;
+use crate::models::manifest::{TripAccessClaims, TRIP_ACCESS_SCOPE};
+use crate::tokens::TokenService;
+
+const BUS_ID: &str = "65f1c0ffee0000000000b05e";
+
+fn trip_token(scope: &str, expires_in: chrono::Duration) -> String {
+    let now = chrono::Utc::now();
+    let claims = TripAccessClaims {
+        sub: "conductor-1".to_string(),
+        scope: scope.to_string(),
+        bus_id: BUS_ID.to_string(),
+        travel_date: "2026-03-14".to_string(),
+        exp: (now + expires_in).timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref())).unwrap()
+}
+
+#[test]
+fn trip_tokens_only_open_their_own_trip() {
+    let token = trip_token(TRIP_ACCESS_SCOPE, chrono::Duration::hours(6));
+    assert_eq!(verify_trip_token(&token, BUS_ID, "2026-03-14", 0).unwrap().sub, "conductor-1");
+
+    let other_bus = verify_trip_token(&token, "65f1c0ffee0000000000b05f", "2026-03-14", 0);
+    assert!(matches!(other_bus, Err(AppError::Forbidden(_))));
+    let other_day = verify_trip_token(&token, BUS_ID, "2026-03-15", 0);
+    assert!(matches!(other_day, Err(AppError::Forbidden(_))));
+}
+
+#[test]
+fn only_trip_scoped_tokens_are_accepted() {
+    let wrong_scope = trip_token("trip:passenger", chrono::Duration::hours(6));
+    assert!(verify_trip_token(&wrong_scope, BUS_ID, "2026-03-14", 0).is_err());
+
+    // A conductor's login token doesn't stand in for the trip token
+    let login = TokenService::from_env().issue_access("conductor-1", "conductor", None).unwrap();
+    assert!(verify_trip_token(&login, BUS_ID, "2026-03-14", 0).is_err());
+}
+
+#[test]
+fn grace_keeps_a_lapsed_token_working_a_little_longer() {
+    let lapsed = trip_token(TRIP_ACCESS_SCOPE, chrono::Duration::minutes(-10));
+    assert!(matches!(verify_trip_token(&lapsed, BUS_ID, "2026-03-14", 0), Err(AppError::Unauthorized(_))));
+    assert!(verify_trip_token(&lapsed, BUS_ID, "2026-03-14", 15 * 60).is_ok());
+}
@@ -0,0 +1,59 @@
+use ring::hmac;
+
+use crate::models::webhook::{external_id, token_matches, verify_stripe_signature, MpesaConfirmation, StripeEvent};
+
+const SECRET: &str = "whsec_test";
+const MPESA_BODY: &str = r#"{"TransactionType":"Pay Bill","TransID":"RKTQDM7W6S","TransTime":"20261120063845","TransAmount":"1450.00","BusinessShortCode":"600638","BillRefNumber":" 65f1c0ffee000000000b00c1 ","MSISDN":"254712345678","FirstName":"Achieng"}"#;
+const STRIPE_BODY: &str = r#"{"id":"evt_1","type":"payment_intent.succeeded","data":{"object":{"id":"pi_1","amount_received":145000,"metadata":{"booking_id":"65f1c0ffee000000000b00c1"}}}}"#;
+
+fn sign(timestamp: i64, body: &str, secret: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, format!("{}.{}", timestamp, body).as_bytes());
+    tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn stripe_signatures_are_checked_against_the_exact_body() {
+    let header = format!("t=1765000000,v1={}", sign(1_765_000_000, STRIPE_BODY, SECRET));
+    assert_eq!(verify_stripe_signature(&header, STRIPE_BODY.as_bytes(), SECRET), Ok(1_765_000_000));
+
+    let tampered = STRIPE_BODY.replace("145000", "1");
+    assert!(verify_stripe_signature(&header, tampered.as_bytes(), SECRET).is_err());
+    assert!(verify_stripe_signature(&header, STRIPE_BODY.as_bytes(), "whsec_other").is_err());
+    assert!(verify_stripe_signature("t=1765000000", STRIPE_BODY.as_bytes(), SECRET).is_err());
+    assert!(verify_stripe_signature("v1=zz", STRIPE_BODY.as_bytes(), SECRET).is_err());
+}
+
+#[test]
+fn any_signature_in_the_header_may_match_during_secret_rotation() {
+    let header = format!(
+        "t=1765000000,v1={},v1={},v0=ignored",
+        sign(1_765_000_000, STRIPE_BODY, "whsec_old"),
+        sign(1_765_000_000, STRIPE_BODY, SECRET),
+    );
+    assert!(verify_stripe_signature(&header, STRIPE_BODY.as_bytes(), SECRET).is_ok());
+}
+
+#[test]
+fn payment_callbacks_name_the_booking_and_amount() {
+    let confirmation = MpesaConfirmation::parse(MPESA_BODY.as_bytes()).unwrap();
+    assert_eq!(confirmation.bill_ref_number.trim(), "65f1c0ffee000000000b00c1");
+    assert_eq!(confirmation.amount(), Some(1450.0));
+    assert!(MpesaConfirmation::parse(br#"{"TransID":"","TransAmount":"1","BillRefNumber":"x"}"#).is_err());
+
+    let event = StripeEvent::parse(STRIPE_BODY.as_bytes()).unwrap();
+    assert_eq!(event.booking_id(), Some("65f1c0ffee000000000b00c1"));
+    assert_eq!(event.amount_received(), Some(1450.0));
+
+    assert_eq!(external_id("mpesa", MPESA_BODY.as_bytes()).as_deref(), Some("RKTQDM7W6S"));
+    assert_eq!(external_id("stripe", STRIPE_BODY.as_bytes()).as_deref(), Some("evt_1"));
+    assert_eq!(external_id("mpesa", b"not json"), None);
+}
+
+#[test]
+fn callback_tokens_must_match_exactly() {
+    assert!(token_matches("s3cret-token", "s3cret-token"));
+    assert!(!token_matches("s3cret-toke", "s3cret-token"));
+    assert!(!token_matches("s3cret-tokem", "s3cret-token"));
+    assert!(!token_matches("", "s3cret-token"));
+}
@@ -0,0 +1,95 @@
+//! Issuance of the tokens that sign people in.
+//!
+//! Every auth flow (registration, password and Google login, refresh, anonymous sessions) gets
+//! its tokens here, so claims are shaped the same way whichever door a user came in through.
+//! Access tokens are verified by [`crate::middleware::auth::decode_claims`], which also accepts
+//! the unversioned tokens issued before [`CLAIMS_VERSION`] existed; refresh tokens only by
+//! [`TokenService::decode_refresh`].
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use mongodb::bson::oid::ObjectId;
+use ring::digest;
+use serde::Serialize;
+
+use crate::config::TokenConfig;
+use crate::models::auth::AnonymousClaims;
+use crate::models::user::CLAIMS_VERSION;
+use crate::models::{Claims, TokenType};
+
+pub const ANONYMOUS_SCOPE: &str = "anonymous";
+
+pub type TokenResult<T> = Result<T, jsonwebtoken::errors::Error>;
+
+pub struct TokenService {
+    config: TokenConfig,
+}
+
+impl TokenService {
+    pub fn new(config: TokenConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(TokenConfig::from_env())
+    }
+
+    fn sign<T: Serialize>(&self, claims: &T) -> TokenResult<String> {
+        encode(&Header::default(), claims, &EncodingKey::from_secret(self.config.secret.as_ref()))
+    }
+
+    /// Claims for `user_id` with the configured enrichment applied, valid for `ttl`.
+    pub fn claims(&self, user_id: &str, role: &str, operator: Option<String>, typ: TokenType, ttl: Duration) -> Claims {
+        let expires_at = Utc::now() + ttl;
+        let mut claims = Claims::new(user_id.to_string(), role.to_string(), expires_at.timestamp() as usize)
+            .with_operator(operator);
+        claims.typ = typ;
+        claims.iss = self.config.issuer.clone();
+        if !self.config.embed_scopes {
+            claims.scopes.clear();
+        }
+        claims
+    }
+
+    /// A session token for a signed-in user.
+    pub fn issue_access(&self, user_id: &str, role: &str, operator: Option<String>) -> TokenResult<String> {
+        let ttl = Duration::hours(self.config.access_ttl_hours);
+        self.sign(&self.claims(user_id, role, operator, TokenType::Access, ttl))
+    }
+
+    /// A refresh token for `user_id` with its claims. Role and operator are read from the account
+    /// again when it is used, so the token carries neither.
+    pub fn issue_refresh(&self, user_id: &str) -> TokenResult<(String, Claims)> {
+        let ttl = Duration::days(self.config.refresh_ttl_days);
+        let mut claims = self.claims(user_id, "", None, TokenType::Refresh, ttl);
+        claims.scopes.clear();
+        claims.jti = Some(ObjectId::new().to_hex());
+        Ok((self.sign(&claims)?, claims))
+    }
+
+    /// Claims of a refresh token. Access tokens are refused here, as refresh tokens are everywhere else.
+    pub fn decode_refresh(&self, token: &str) -> TokenResult<Claims> {
+        let claims = decode::<Claims>(token, &DecodingKey::from_secret(self.config.secret.as_ref()), &Validation::new(Algorithm::HS256))?.claims;
+        if claims.typ != TokenType::Refresh || claims.ver > CLAIMS_VERSION || claims.jti.is_none() {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+        Ok(claims)
+    }
+
+    /// A token for a visitor who hasn't signed in, with its expiry.
+    pub fn issue_anonymous(&self, anon_id: &str) -> TokenResult<(String, DateTime<Utc>)> {
+        let expires_at = Utc::now() + Duration::days(self.config.anonymous_ttl_days);
+        let token = self.sign(&AnonymousClaims {
+            sub: anon_id.to_string(),
+            scope: ANONYMOUS_SCOPE.to_string(),
+            exp: expires_at.timestamp() as usize,
+        })?;
+        Ok((token, expires_at))
+    }
+}
+
+/// What `refresh_tokens` stores in place of the token, so a database leak can't be replayed.
+pub fn refresh_token_hash(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, token.as_bytes()))
+}